@@ -573,6 +573,7 @@ def external_crates_repository(name, static_openssl, cargo_lockfile, lockfile):
                     "ecdsa",
                     "pem",
                     "pkcs8",
+                    "schnorr",
                 ],
                 default_features = False,
             ),
@@ -1225,6 +1226,9 @@ def external_crates_repository(name, static_openssl, cargo_lockfile, lockfile):
             "turmoil": crate.spec(
                 version = "^0.5",
             ),
+            "unicode-segmentation": crate.spec(
+                version = "^1.10.1",
+            ),
             "url": crate.spec(
                 version = "^2.1.1",
                 features = [
@@ -8,6 +8,7 @@ use std::collections::BTreeMap;
 /// NOTE: ⌈ 128 / 7 ⌉ = 19
 const INT128_BUF_SIZE: usize = 19;
 pub type Map = BTreeMap<String, Value>;
+pub type ICRC3Map = BTreeMap<String, ICRC3Value>;
 pub type Hash = [u8; 32];
 
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -86,6 +87,85 @@ impl Value {
     }
 }
 
+/// A value as defined by the ICRC-3 standard.
+///
+/// Unlike [Value], there is a single representation for natural numbers and
+/// integers are always hashed using signed LEB-128 encoding.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ICRC3Value {
+    Blob(ByteBuf),
+    Text(String),
+    Nat(Nat),
+    Int(Int),
+    Array(Vec<ICRC3Value>),
+    Map(ICRC3Map),
+}
+
+impl ICRC3Value {
+    /// Computes the representation-independent hash of a value.
+    pub fn hash(&self) -> Hash {
+        match self {
+            ICRC3Value::Nat(nat) => {
+                let mut buf = vec![];
+                nat.encode(&mut buf).expect("bug: cannot encode a Nat");
+                Sha256::digest(&buf).into()
+            }
+            ICRC3Value::Int(int) => {
+                let mut buf = vec![];
+                int.encode(&mut buf).expect("bug: cannot encode an Int");
+                Sha256::digest(&buf).into()
+            }
+            ICRC3Value::Blob(bytes) => Sha256::digest(bytes).into(),
+            ICRC3Value::Text(text) => Sha256::digest(text.as_bytes()).into(),
+            ICRC3Value::Array(values) => {
+                let mut hasher = Sha256::new();
+                for v in values.iter() {
+                    hasher.update(v.hash());
+                }
+                hasher.finalize().into()
+            }
+            ICRC3Value::Map(map) => {
+                let mut hpairs = Vec::with_capacity(map.len());
+                for (k, v) in map.iter() {
+                    let key_hash: Hash = Sha256::digest(k.as_bytes()).into();
+                    hpairs.push((key_hash, v.hash()));
+                }
+
+                hpairs.sort_unstable();
+
+                let mut hasher = Sha256::new();
+                for (khash, vhash) in hpairs.iter() {
+                    hasher.update(&khash[..]);
+                    hasher.update(&vhash[..]);
+                }
+                hasher.finalize().into()
+            }
+        }
+    }
+}
+
+/// Converts a value into the ICRC-3 representation.
+///
+/// Blocks store CBOR unsigned integers, which [Value] represents as
+/// non-negative `Int`s but hashes like `Nat`s. Such integers, as well as
+/// `Nat64`s, become `Nat`s, so the conversion preserves the hash of the value.
+impl From<Value> for ICRC3Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Blob(bytes) => Self::Blob(bytes),
+            Value::Text(text) => Self::Text(text),
+            Value::Nat(nat) => Self::Nat(nat),
+            Value::Nat64(n) => Self::Nat(Nat::from(n)),
+            Value::Int(int) => match int.0.to_biguint() {
+                Some(n) => Self::Nat(Nat(n)),
+                None => Self::Int(int),
+            },
+            Value::Array(values) => Self::Array(values.into_iter().map(Self::from).collect()),
+            Value::Map(map) => Self::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect()),
+        }
+    }
+}
+
 /// Encodes a 128-bit integer using unsigned LEB-128 encoding.
 /// Returns the index of the last valid byte in the buffer.
 fn leb128(buf: &mut [u8; INT128_BUF_SIZE], v: u128) -> usize {
@@ -151,6 +231,37 @@ fn check_interface_spec_example() {
     );
 }
 
+#[test]
+fn check_icrc3_value_conversion_preserves_hash() {
+    let value = Value::Map({
+        let mut m = BTreeMap::new();
+        m.insert("nat".to_string(), Value::Nat(Nat::from(u128::MAX)));
+        m.insert("nat64".to_string(), Value::Nat64(u64::MAX));
+        m.insert("small_int".to_string(), Value::Int(Int::from(64)));
+        m.insert("large_int".to_string(), Value::Int(Int::from(u64::MAX)));
+        m.insert("negative_int".to_string(), Value::Int(Int::from(-123456)));
+        m.insert(
+            "array".to_string(),
+            Value::Array(vec![Value::text("a"), Value::blob(b"\xca\xfe".to_vec())]),
+        );
+        m
+    });
+    assert_eq!(ICRC3Value::from(value.clone()).hash(), value.hash());
+}
+
+#[test]
+fn check_icrc3_int_hash_uses_signed_leb128() {
+    // 64 is encoded as [0x40] in LEB-128 but as [0xc0, 0x00] in signed LEB-128.
+    assert_ne!(
+        ICRC3Value::Int(Int::from(64)).hash(),
+        ICRC3Value::Nat(Nat::from(64_u64)).hash()
+    );
+    assert_eq!(
+        ICRC3Value::Int(Int::from(-123456)).hash(),
+        Value::Int(Int::from(-123456)).hash()
+    );
+}
+
 #[test]
 fn test_leb128() {
     let mut buf = [0; INT128_BUF_SIZE];
@@ -1,7 +1,7 @@
 use crate::icrc1::transfer::BlockIndex;
 
 use super::{
-    blocks::{BlockRange, GetBlocksRequest},
+    blocks::{BlockRange, GetBlocksRequest, GetBlocksResult},
     transactions::{GetTransactionsRequest, TransactionRange},
 };
 use candid::{CandidType, Deserialize, Nat, Principal};
@@ -91,3 +91,22 @@ pub struct ArchiveInfo {
 }
 pub type QueryBlockArchiveFn = QueryArchiveFn<GetBlocksRequest, BlockRange>;
 pub type QueryTxArchiveFn = QueryArchiveFn<GetTransactionsRequest, TransactionRange>;
+pub type ICRC3QueryArchiveFn = QueryArchiveFn<Vec<GetBlocksRequest>, GetBlocksResult>;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetArchivesArgs {
+    /// If set, only the archives following `from` are returned. Clients set
+    /// it to the last archive they received to page through the archives.
+    pub from: Option<Principal>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ICRC3ArchiveInfo {
+    pub canister_id: Principal,
+    /// The index of the first block in the archive.
+    pub start: Nat,
+    /// The index of the last block in the archive.
+    pub end: Nat,
+}
+
+pub type GetArchivesResult = Vec<ICRC3ArchiveInfo>;
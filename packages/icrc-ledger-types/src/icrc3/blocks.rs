@@ -1,10 +1,14 @@
 use crate::icrc3::archive::ArchivedRange;
-use crate::icrc3::archive::QueryBlockArchiveFn;
-use crate::{icrc::generic_value::Value, icrc1::transfer::BlockIndex};
+use crate::icrc3::archive::{ICRC3QueryArchiveFn, QueryBlockArchiveFn};
+use crate::{
+    icrc::generic_value::{ICRC3Value, Value},
+    icrc1::transfer::BlockIndex,
+};
 use candid::{CandidType, Deserialize, Nat};
 use serde_bytes::ByteBuf;
 
 pub type GenericBlock = Value;
+pub type ICRC3GenericBlock = ICRC3Value;
 
 #[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
 pub struct GetBlocksResponse {
@@ -52,3 +56,43 @@ pub struct DataCertificate {
     pub certificate: Option<serde_bytes::ByteBuf>,
     pub hash_tree: serde_bytes::ByteBuf,
 }
+
+/// A block together with its index in the block log.
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: ICRC3GenericBlock,
+}
+
+/// Ranges of blocks that must be fetched from an archive using the
+/// callback.
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct ArchivedBlocks {
+    pub args: Vec<GetBlocksRequest>,
+    pub callback: ICRC3QueryArchiveFn,
+}
+
+/// The result of an `icrc3_get_blocks` call.
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct GetBlocksResult {
+    /// The total number of blocks in the log.
+    pub log_length: Nat,
+    pub blocks: Vec<BlockWithId>,
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+/// The certificate for the tip of the block log, see `icrc3_get_tip_certificate`.
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct ICRC3DataCertificate {
+    /// The certificate as returned by the IC.
+    pub certificate: serde_bytes::ByteBuf,
+    /// The CBOR-encoded hash tree containing the `last_block_index` and
+    /// `last_block_hash` labels.
+    pub hash_tree: serde_bytes::ByteBuf,
+}
+
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct SupportedBlockType {
+    pub block_type: String,
+    pub url: String,
+}
@@ -18,6 +18,91 @@ pub struct RawCheckpoint {
     pub checkpoint_name: String,
 }
 
+/// The subnets an instance consists of. An instance created without a topology
+/// consists of a single system subnet.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Topology {
+    pub nns: bool,
+    pub system: bool,
+    pub application: usize,
+    pub bitcoin: bool,
+    pub fiduciary: bool,
+}
+
+impl Topology {
+    pub fn num_subnets(&self) -> usize {
+        [self.nns, self.system, self.bitcoin, self.fiduciary]
+            .into_iter()
+            .filter(|flag| *flag)
+            .count()
+            + self.application
+    }
+}
+
+/// Management canister features of an instance beyond those every instance has. Instances
+/// created with features but without a topology consist of a single system subnet.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct InstanceFeatures {
+    /// Names of secp256k1 keys for `ecdsa_public_key` and `sign_with_ecdsa`, e.g. `key_1`, in
+    /// addition to `master_ecdsa_public_key`. All keys are backed by the same test secret key.
+    pub ecdsa_keys: Vec<String>,
+    /// Installs stubs of the Bitcoin testnet and mainnet canisters, to which the management
+    /// canister's Bitcoin API is routed. The stubs report no UTXOs and a zero balance, and can
+    /// be reinstalled by the anonymous principal, e.g. with the Bitcoin canister mock.
+    pub bitcoin: bool,
+    /// Fees charged on the application subnets of the instance instead of the default ones.
+    /// System subnets charge no fees.
+    pub cycles_fees: CyclesFees,
+    /// Seeds the randomness of all subnets (and thus the result of `raw_rand`) and the test
+    /// secret key backing the tECDSA keys, so that instances created with the same seed behave
+    /// identically. Without a seed, every instance uses the same fixed values.
+    pub seed: Option<[u8; 32]>,
+}
+
+/// Overrides of the cycle fees of a subnet, in cycles. Unset fees keep their default value.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct CyclesFees {
+    /// Fee for every update message executed.
+    pub update_message_execution_fee: Option<u128>,
+    /// Fee for every 10 instructions executed by update messages.
+    pub ten_update_instructions_execution_fee: Option<u128>,
+    /// Fee for every ingress message received.
+    pub ingress_message_reception_fee: Option<u128>,
+    /// Fee for every byte received in an ingress message.
+    pub ingress_byte_reception_fee: Option<u128>,
+    /// Fee for every inter-canister call.
+    pub xnet_call_fee: Option<u128>,
+    /// Fee for every byte sent in an inter-canister call.
+    pub xnet_byte_transmission_fee: Option<u128>,
+    /// Fee for storing a GiB of data per second.
+    pub gib_storage_per_second_fee: Option<u128>,
+    /// Fee for each percent of compute allocation per second.
+    pub compute_percent_allocated_per_second_fee: Option<u128>,
+    /// How often canisters are charged for their memory and compute allocations.
+    pub allocation_charge_interval_nanos: Option<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct RawCreateInstance {
+    pub checkpoint_name: Option<String>,
+    pub topology: Option<Topology>,
+    #[serde(default)]
+    pub features: InstanceFeatures,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawSnapshot {
+    pub snapshot_dir: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawExportState {
+    pub export_dir: String,
+}
+
 // ================================================================================================================= //
 // HTTP JSON Response types
 
@@ -25,6 +110,45 @@ pub struct RawCheckpoint {
 pub enum CreateInstanceResponse {
     Created { instance_id: InstanceId },
     Error { message: String },
+    QuotaExceeded(QuotaExceeded),
+}
+
+/// A quota of the server which a request would exceed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quota {
+    /// The server already manages the maximum number of instances.
+    MaxInstances { max_instances: usize },
+    /// The instance uses more memory than a single instance may use.
+    MaxMemory {
+        memory_bytes: u64,
+        max_memory_bytes: u64,
+    },
+}
+
+/// The error returned with status code 429 if a request exceeds a quota.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaExceeded {
+    pub message: String,
+    pub quota: Quota,
+}
+
+impl From<Quota> for QuotaExceeded {
+    fn from(quota: Quota) -> Self {
+        let message = match &quota {
+            Quota::MaxInstances { max_instances } => format!(
+                "The server already manages the maximum number of {} instances.",
+                max_instances
+            ),
+            Quota::MaxMemory {
+                memory_bytes,
+                max_memory_bytes,
+            } => format!(
+                "The instance uses {} bytes of memory, more than the quota of {} bytes.",
+                memory_bytes, max_memory_bytes
+            ),
+        };
+        Self { message, quota }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, Copy)]
@@ -43,6 +167,53 @@ pub struct RawCanisterCall {
     pub payload: Vec<u8>,
 }
 
+/// An ingress message executed on an instance, together with the time of the instance when the
+/// message was submitted. Returned by `/instances/:id/ingress_history` and replayed by
+/// `/instances/:id/replay`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawIngressRecord {
+    pub time_nanos_since_epoch: u64,
+    pub call: RawCanisterCall,
+}
+
+/// An operation of a batch submitted to `/instances/:id/batch`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum RawBatchOperation {
+    /// Installs a module on a canister as its controller. The mode is one of `install`,
+    /// `reinstall` and `upgrade`.
+    Install {
+        #[serde(with = "base64")]
+        canister_id: Vec<u8>,
+        mode: String,
+        #[serde(with = "base64")]
+        wasm_module: Vec<u8>,
+        #[serde(with = "base64")]
+        arg: Vec<u8>,
+    },
+    /// Executes an update call as an ingress message.
+    Call(RawCanisterCall),
+    Query(RawCanisterCall),
+    AdvanceTime {
+        nanos: u64,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum CanisterLogKind {
+    /// Printed by the canister via `ic0.debug_print`.
+    DebugPrint,
+    /// The message of a trap in an ingress message sent to the canister.
+    Trap,
+}
+
+/// A line of a canister's log, as returned by `/instances/:id/canisters/:canister_id/logs`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct RawCanisterLogRecord {
+    pub time_nanos_since_epoch: u64,
+    pub kind: CanisterLogKind,
+    pub message: String,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum RawCanisterResult {
     Ok(RawWasmResult),
@@ -71,6 +242,36 @@ pub struct RawStableMemory {
     pub blob: Vec<u8>,
 }
 
+/// Activity on an instance, streamed by `/instances/:id/events` as server-sent events
+/// whose data is the JSON encoding of this type.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum InstanceEvent {
+    IngressSubmitted {
+        message_id: String,
+        #[serde(with = "base64")]
+        sender: Vec<u8>,
+        #[serde(with = "base64")]
+        canister_id: Vec<u8>,
+        method: String,
+    },
+    CanisterInstalled {
+        #[serde(with = "base64")]
+        canister_id: Vec<u8>,
+        mode: String,
+    },
+    CanisterTrapped {
+        #[serde(with = "base64")]
+        canister_id: Vec<u8>,
+        message: String,
+    },
+    /// The cycles a canister burnt in a single execution round.
+    CyclesConsumed {
+        #[serde(with = "base64")]
+        canister_id: Vec<u8>,
+        cycles: u128,
+    },
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ApiError {
     message: String,
@@ -89,6 +290,7 @@ pub enum ApiResponse<T> {
     Busy { state_label: String, op_id: String },
     Started { state_label: String, op_id: String },
     Error { message: String },
+    QuotaExceeded(QuotaExceeded),
 }
 
 impl<T: DeserializeOwned> From<Response> for ApiResponse<T> {
@@ -125,6 +327,15 @@ impl<T: DeserializeOwned> From<Response> for ApiResponse<T> {
                     },
                 }
             }
+            reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                let result = resp.json::<QuotaExceeded>();
+                match result {
+                    Ok(quota_exceeded) => ApiResponse::QuotaExceeded(quota_exceeded),
+                    Err(e) => ApiResponse::Error {
+                        message: format!("Could not parse response: {}", e),
+                    },
+                }
+            }
             _ => {
                 let result = resp.json::<ApiError>();
                 match result {
@@ -145,6 +356,13 @@ pub struct RawAddCycles {
     pub amount: u128,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawSetCycles {
+    #[serde(with = "base64")]
+    pub canister_id: Vec<u8>,
+    pub amount: u128,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct RawCycles {
     pub cycles: u128,
@@ -165,6 +383,33 @@ impl From<Principal> for RawCanisterId {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawSubnetId {
+    // raw bytes of the principal
+    #[serde(with = "base64")]
+    pub subnet_id: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawPrincipalId {
+    // raw bytes of the principal
+    #[serde(with = "base64")]
+    pub principal_id: Vec<u8>,
+}
+
+/// Creates a canister with the given id, e.g. that of a mainnet canister. Without a subnet,
+/// the canister is created on the subnet the id is routed to, or on the subnet that hosts
+/// canisters created without a specified id if the id is not routed to any subnet.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawCreateCanisterWithId {
+    #[serde(with = "base64")]
+    pub canister_id: Vec<u8>,
+    pub controllers: Vec<RawPrincipalId>,
+    pub cycles: u128,
+    #[serde(default)]
+    pub subnet_id: Option<RawSubnetId>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RawVerifyCanisterSigArg {
     #[serde(with = "base64")]
@@ -177,6 +422,49 @@ pub struct RawVerifyCanisterSigArg {
     pub root_pubkey: Vec<u8>,
 }
 
+/// A delegation in the chain of an IC request signature, see the
+/// [IC specification](https://internetcomputer.org/docs/current/references/ic-interface-spec#authentication).
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawSignedDelegation {
+    #[serde(with = "base64")]
+    pub pubkey: Vec<u8>,
+    pub expiration_nanos: u64,
+    #[serde(default)]
+    pub targets: Option<Vec<RawCanisterId>>,
+    /// The signature of the delegation by the previous key in the chain.
+    #[serde(with = "base64")]
+    pub signature: Vec<u8>,
+}
+
+/// The signature of an IC request id by `sender_pubkey` or, with delegations, by the key of
+/// the last delegation.
+#[derive(Serialize, Deserialize)]
+pub struct RawVerifyRequestSigArg {
+    #[serde(with = "base64")]
+    pub request_id: Vec<u8>,
+    #[serde(with = "base64")]
+    pub sig: Vec<u8>,
+    #[serde(with = "base64")]
+    pub sender_pubkey: Vec<u8>,
+    #[serde(default)]
+    pub delegations: Option<Vec<RawSignedDelegation>>,
+    /// Only needed if a canister signature is part of the chain.
+    #[serde(default, with = "base64")]
+    pub root_pubkey: Vec<u8>,
+    /// The time against which the expiration of the delegations is checked. Defaults to the
+    /// current time of the server.
+    #[serde(default)]
+    pub current_time_nanos: Option<u64>,
+}
+
+/// The body of `/verify_signature`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RawVerifySignatureArg {
+    CanisterSig(RawVerifyCanisterSigArg),
+    RequestSig(RawVerifyRequestSigArg),
+}
+
 // By default, serde serializes Vec<u8> to a list of numbers, which is inefficient.
 // This enables serializing Vec<u8> to a compact base64 representation.
 #[allow(deprecated)]
@@ -1,9 +1,11 @@
 use crate::common::{
     blob::{BlobCompression, BlobId},
     rest::{
-        ApiResponse, CreateInstanceResponse, InstanceId, RawAddCycles, RawCanisterCall,
-        RawCanisterId, RawCanisterResult, RawCycles, RawSetStableMemory, RawStableMemory, RawTime,
-        RawWasmResult,
+        ApiResponse, CreateInstanceResponse, InstanceFeatures, InstanceId, RawAddCycles,
+        RawBatchOperation, RawCanisterCall, RawCanisterId, RawCanisterLogRecord, RawCanisterResult,
+        RawCreateCanisterWithId, RawCreateInstance, RawCycles, RawExportState, RawIngressRecord,
+        RawPrincipalId, RawSetCycles, RawSetStableMemory, RawSnapshot, RawStableMemory,
+        RawSubnetId, RawTime, RawWasmResult, Topology,
     },
 };
 use candid::{
@@ -11,7 +13,7 @@ use candid::{
     utils::{ArgumentDecoder, ArgumentEncoder},
     Principal,
 };
-use common::rest::RawVerifyCanisterSigArg;
+use common::rest::{RawVerifyCanisterSigArg, RawVerifyRequestSigArg};
 use ic_cdk::api::management_canister::{
     main::{CanisterInstallMode, CreateCanisterArgument, InstallCodeArgument},
     provisional::{CanisterId, CanisterIdRecord, CanisterSettings},
@@ -28,6 +30,8 @@ pub mod common;
 const PROCESSING_TIME_HEADER: &str = "processing-timeout-ms";
 const PROCESSING_TIME_VALUE_MS: u64 = 300_000;
 const LOCALHOST: &str = "127.0.0.1";
+/// If set, requests carry this token and servers started by the client require it.
+const AUTH_TOKEN_ENV_NAME: &str = "POCKET_IC_AUTH_TOKEN";
 
 pub struct PocketIc {
     pub instance_id: InstanceId,
@@ -37,11 +41,63 @@ pub struct PocketIc {
 
 impl PocketIc {
     pub fn new() -> Self {
+        Self::create_instance("instances", None::<()>)
+    }
+
+    /// Creates a new instance made of the subnets of the given topology. Canisters are
+    /// created on the first application subnet, or on the first subnet if there is none.
+    pub fn new_with_topology(topology: Topology) -> Self {
+        Self::new_with_features(topology, InstanceFeatures::default())
+    }
+
+    /// Creates a new instance made of the subnets of the given topology, with additional
+    /// management canister features such as tECDSA keys or the Bitcoin API.
+    pub fn new_with_features(topology: Topology, features: InstanceFeatures) -> Self {
+        Self::create_instance(
+            "instances",
+            Some(RawCreateInstance {
+                checkpoint_name: None,
+                topology: Some(topology),
+                features,
+            }),
+        )
+    }
+
+    /// Creates a new instance made of the subnets of the given topology, which must include
+    /// an NNS subnet, with a minimal NNS (registry, governance, ledger, cycles minting canister
+    /// and root) installed at the well-known NNS canister ids. The server must have the NNS
+    /// canister Wasms, e.g., from the `POCKET_IC_NNS_WASM_DIR` environment variable of the
+    /// process starting it.
+    pub fn new_with_nns(topology: Topology) -> Self {
+        Self::create_instance(
+            "instances?bootstrap=nns",
+            Some(RawCreateInstance {
+                checkpoint_name: None,
+                topology: Some(topology),
+                features: InstanceFeatures::default(),
+            }),
+        )
+    }
+
+    /// Creates a new instance from a snapshot previously written by [PocketIc::snapshot].
+    pub fn from_snapshot(snapshot_dir: PathBuf) -> Self {
+        Self::create_instance(
+            "instances/from_snapshot",
+            Some(RawSnapshot {
+                snapshot_dir: snapshot_dir.to_str().unwrap().to_string(),
+            }),
+        )
+    }
+
+    fn create_instance<B: Serialize>(endpoint: &str, body: Option<B>) -> Self {
         let server_url = crate::start_or_reuse_server();
-        let reqwest_client = reqwest::blocking::Client::new();
+        let reqwest_client = reqwest_client();
         use CreateInstanceResponse::*;
-        let instance_id = match reqwest_client
-            .post(server_url.join("instances").unwrap())
+        let mut request = reqwest_client.post(server_url.join(endpoint).unwrap());
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let instance_id = match request
             .send()
             .expect("Failed to get result")
             .json::<CreateInstanceResponse>()
@@ -49,6 +105,7 @@ impl PocketIc {
         {
             Created { instance_id } => instance_id,
             Error { message } => panic!("{}", message),
+            QuotaExceeded(quota_exceeded) => panic!("{}", quota_exceeded.message),
         };
 
         Self {
@@ -108,7 +165,7 @@ impl PocketIc {
 
     pub fn list_instances() -> Vec<String> {
         let url = crate::start_or_reuse_server().join("instances").unwrap();
-        let instances: Vec<String> = reqwest::blocking::Client::new()
+        let instances: Vec<String> = reqwest_client()
             .get(url)
             .send()
             .expect("Failed to get result")
@@ -125,7 +182,7 @@ impl PocketIc {
         root_pubkey: Vec<u8>,
     ) -> Result<(), String> {
         let url = self.server_url.join("verify_signature").unwrap();
-        reqwest::blocking::Client::new()
+        reqwest_client()
             .post(url)
             .json(&RawVerifyCanisterSigArg {
                 msg,
@@ -139,6 +196,19 @@ impl PocketIc {
             .expect("Failed to get json")
     }
 
+    /// Verifies the signature of an IC request id, following the delegations of the argument,
+    /// if any, the way the IC authenticates requests.
+    pub fn verify_request_signature(&self, arg: RawVerifyRequestSigArg) -> Result<(), String> {
+        let url = self.server_url.join("verify_signature").unwrap();
+        reqwest_client()
+            .post(url)
+            .json(&arg)
+            .send()
+            .expect("Failed to get result")
+            .json()
+            .expect("Failed to get json")
+    }
+
     pub fn tick(&self) {
         let endpoint = "update/tick";
         self.post::<(), _>(endpoint, "");
@@ -200,6 +270,19 @@ impl PocketIc {
         result.cycles
     }
 
+    /// Sets the cycle balance of a canister, e.g. to test its behavior below the freezing
+    /// threshold or once it ran out of cycles.
+    pub fn set_cycles(&self, canister_id: Principal, amount: u128) {
+        let endpoint = "update/set_cycles";
+        let _: RawCycles = self.post(
+            endpoint,
+            RawSetCycles {
+                canister_id: canister_id.as_slice().to_vec(),
+                amount,
+            },
+        );
+    }
+
     pub fn update_call(
         &self,
         canister_id: Principal,
@@ -252,6 +335,54 @@ impl PocketIc {
         canister_id
     }
 
+    /// Creates a canister with the given id, controllers and cycles balance, e.g. to reproduce
+    /// the canister ids of mainnet. Without a subnet, the canister is created on the subnet the
+    /// id is routed to, or on the subnet that hosts canisters created without a specified id.
+    /// With a subnet, the id is rerouted to that subnet if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the canister already exists or the subnet is not part of this instance.
+    pub fn create_canister_with_id(
+        &self,
+        canister_id: CanisterId,
+        controllers: Vec<Principal>,
+        cycles: u128,
+        subnet_id: Option<Principal>,
+    ) -> CanisterId {
+        let endpoint = "update/create_canister_with_id";
+        let result: RawCanisterId = self.post(
+            endpoint,
+            RawCreateCanisterWithId {
+                canister_id: canister_id.as_slice().to_vec(),
+                controllers: controllers
+                    .into_iter()
+                    .map(|controller| RawPrincipalId {
+                        principal_id: controller.as_slice().to_vec(),
+                    })
+                    .collect(),
+                cycles,
+                subnet_id: subnet_id.map(|subnet_id| RawSubnetId {
+                    subnet_id: subnet_id.as_slice().to_vec(),
+                }),
+            },
+        );
+        Principal::from_slice(&result.canister_id)
+    }
+
+    /// Returns the subnet that hosts the given canister, or would host it if it was created
+    /// with [Self::create_canister_with_id] without a subnet.
+    pub fn get_subnet(&self, canister_id: CanisterId) -> Principal {
+        let endpoint = "read/get_subnet";
+        let result: RawSubnetId = self.post(
+            endpoint,
+            RawCanisterId {
+                canister_id: canister_id.as_slice().to_vec(),
+            },
+        );
+        Principal::from_slice(&result.subnet_id)
+    }
+
     pub fn install_canister(
         &self,
         canister_id: CanisterId,
@@ -374,6 +505,90 @@ impl PocketIc {
         self.post::<(), &str>(endpoint, "");
     }
 
+    /// Writes a snapshot of this instance, including its registry and time, to the given
+    /// directory. The directory must be empty or not exist yet.
+    pub fn snapshot(&self, snapshot_dir: PathBuf) {
+        let endpoint = "snapshot";
+        self.post::<(), _>(
+            endpoint,
+            RawSnapshot {
+                snapshot_dir: snapshot_dir.to_str().unwrap().to_string(),
+            },
+        );
+    }
+
+    /// Writes the state and registry of each subnet of this instance to a subdirectory of
+    /// `export_dir` named after the subnet id, in the layout a replica uses, so that it can be
+    /// inspected with `ic-replay` or `state-tool`. The directory must be empty or not exist yet.
+    pub fn export_state(&self, export_dir: PathBuf) {
+        let endpoint = "export_state";
+        self.post::<(), _>(
+            endpoint,
+            RawExportState {
+                export_dir: export_dir.to_str().unwrap().to_string(),
+            },
+        );
+    }
+
+    /// Returns the ingress messages executed on this instance, in order.
+    pub fn ingress_history(&self) -> Vec<RawIngressRecord> {
+        self.get("ingress_history")
+    }
+
+    /// Re-executes the given ingress messages, e.g. the [PocketIc::ingress_history] of
+    /// another instance, on this instance and returns their results.
+    pub fn replay(&self, history: Vec<RawIngressRecord>) -> Vec<Result<WasmResult, UserError>> {
+        let results: Vec<RawCanisterResult> = self.post("replay", history);
+        results
+            .into_iter()
+            .map(|result| match result {
+                RawCanisterResult::Ok(RawWasmResult::Reply(data)) => Ok(WasmResult::Reply(data)),
+                RawCanisterResult::Ok(RawWasmResult::Reject(text)) => Ok(WasmResult::Reject(text)),
+                RawCanisterResult::Err(user_error) => Err(user_error),
+            })
+            .collect()
+    }
+
+    /// Executes the given operations in order in a single request, without other operations
+    /// on this instance in between. Returns one result per operation, or `None` for the
+    /// operations without a result, such as advancing the time.
+    pub fn batch(&self, ops: Vec<RawBatchOperation>) -> Vec<Option<Result<WasmResult, UserError>>> {
+        let results: Vec<Option<RawCanisterResult>> = self.post("batch", ops);
+        results
+            .into_iter()
+            .map(|result| {
+                result.map(|result| match result {
+                    RawCanisterResult::Ok(RawWasmResult::Reply(data)) => {
+                        Ok(WasmResult::Reply(data))
+                    }
+                    RawCanisterResult::Ok(RawWasmResult::Reject(text)) => {
+                        Ok(WasmResult::Reject(text))
+                    }
+                    RawCanisterResult::Err(user_error) => Err(user_error),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns what the given canister printed via `ic0.debug_print` and the traps of ingress
+    /// messages sent to it, oldest first. With `since`, only records at or after that time are
+    /// returned, so that the log can be polled.
+    pub fn canister_logs(
+        &self,
+        canister_id: CanisterId,
+        since: Option<SystemTime>,
+    ) -> Vec<RawCanisterLogRecord> {
+        let mut endpoint = format!("canisters/{}/logs", canister_id);
+        if let Some(since) = since {
+            let since_nanos = since
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_nanos();
+            endpoint.push_str(&format!("?since_nanos={}", since_nanos));
+        }
+        self.get(&endpoint)
+    }
+
     fn instance_url(&self) -> Url {
         let instance_id = self.instance_id;
         self.server_url
@@ -395,6 +610,7 @@ impl PocketIc {
         match result {
             ApiResponse::Success(t) => t,
             ApiResponse::Error { message } => panic!("{}", message),
+            ApiResponse::QuotaExceeded(quota_exceeded) => panic!("{}", quota_exceeded.message),
             ApiResponse::Busy { state_label, op_id } => {
                 panic!("Busy: state_label: {}, op_id: {}", state_label, op_id)
             }
@@ -415,6 +631,7 @@ impl PocketIc {
         match result.into() {
             ApiResponse::Success(t) => t,
             ApiResponse::Error { message } => panic!("{}", message),
+            ApiResponse::QuotaExceeded(quota_exceeded) => panic!("{}", quota_exceeded.message),
             ApiResponse::Busy { state_label, op_id } => {
                 panic!("Busy: state_label: {}, op_id: {}", state_label, op_id)
             }
@@ -715,6 +932,22 @@ pub enum WasmResult {
     Reject(String),
 }
 
+/// A client that authenticates with the token in the `POCKET_IC_AUTH_TOKEN` environment variable,
+/// if set.
+fn reqwest_client() -> reqwest::blocking::Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Ok(token) = std::env::var(AUTH_TOKEN_ENV_NAME) {
+        let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+            .expect("Invalid PocketIC auth token");
+        value.set_sensitive(true);
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+    }
+    reqwest::blocking::Client::builder()
+        .default_headers(headers)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
 /// Attempt to start a new PocketIC server if it's not already running.
 pub fn start_or_reuse_server() -> Url {
     // Use the parent process ID to find the PocketIC server port for this `cargo test` run.
@@ -1,16 +1,31 @@
 use candid::{CandidType, Deserialize, Principal};
-use ic_agent::Agent;
+use ic_agent::agent::{RejectCode, RejectResponse};
+use ic_agent::{Agent, AgentError};
+use ic_btc_interface::Utxo;
 use ic_canisters_http_types::{HttpRequest, HttpResponse};
-use ic_ckbtc_minter::queries::RetrieveBtcStatusRequest;
-use ic_ckbtc_minter::state::eventlog::{Event, GetEventsArg};
-use ic_ckbtc_minter::state::RetrieveBtcStatus;
+use ic_ckbtc_minter::queries::{
+    EstimateFeeArg, EstimateRetrieveBtcFeeArg, ManagedUtxo, ManagedUtxos, OwedKytFee, Pagination,
+    RetrieveBtcStatusRequest, SubmittedTransactions, WithdrawalFee, MAX_ITEMS_PER_PAGE,
+};
+use ic_ckbtc_minter::state::eventlog::{GetEventsArg, GetEventsResult};
+use ic_ckbtc_minter::state::{RetrieveBtcFeeRate, RetrieveBtcStatus, TaintedUtxoReleaseStatus};
 use ic_ckbtc_minter::updates::{
+    deposit_notifications::SubscribeDepositNotificationsError,
     get_btc_address::GetBtcAddressArgs,
-    retrieve_btc::{RetrieveBtcArgs, RetrieveBtcError, RetrieveBtcOk},
+    retrieve_btc::{
+        RetrieveBtcArgs, RetrieveBtcError, RetrieveBtcOk, RetrieveBtcWithApprovalArgs,
+        RetrieveBtcWithApprovalError,
+    },
+    tainted_utxo_release::{
+        RequestTaintedUtxoReleaseArgs, RequestTaintedUtxoReleaseError,
+        ReviewTaintedUtxoReleaseArgs, ReviewTaintedUtxoReleaseError,
+    },
     update_balance::{UpdateBalanceArgs, UpdateBalanceError, UtxoStatus},
 };
+use ic_ckbtc_minter::{DistributeOwedKytFeesError, KytFeeDistribution, MinterInfo};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use std::collections::BTreeMap;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub enum CkBtcMinterAgentError {
@@ -30,50 +45,200 @@ impl From<candid::Error> for CkBtcMinterAgentError {
     }
 }
 
+/// Controls how the agent retries calls that fail with transient errors.
+///
+/// The agent retries only queries and updates that are safe to repeat, such
+/// as `update_balance`. It never retries calls that move funds, such as
+/// `retrieve_btc`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub initial_delay: Duration,
+    /// The factor by which the delay grows after each retry.
+    pub backoff_factor: u32,
+}
+
+impl RetryPolicy {
+    /// A policy that makes every call exactly once.
+    pub fn no_retries() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay: Duration::ZERO,
+            backoff_factor: 1,
+        }
+    }
+
+    /// Returns the delay before the retry following the given attempt (starting at 1).
+    fn delay_after(&self, attempt: u32) -> Duration {
+        self.initial_delay.saturating_mul(
+            self.backoff_factor
+                .saturating_pow(attempt.saturating_sub(1)),
+        )
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_secs(1),
+            backoff_factor: 2,
+        }
+    }
+}
+
+/// Returns true if repeating the call that failed with the given error might succeed.
+fn is_transient(err: &AgentError) -> bool {
+    matches!(
+        err,
+        AgentError::TransportError(_)
+            | AgentError::ReplicaError(RejectResponse {
+                reject_code: RejectCode::SysTransient,
+                ..
+            })
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CallKind {
+    Query,
+    /// An update call that has the same effect when made several times.
+    IdempotentUpdate,
+    Update,
+}
+
 /// Agent to make calls to the ckBTC minter.
 #[derive(Clone)]
 pub struct CkBtcMinterAgent {
     pub agent: Agent,
     pub minter_canister_id: Principal,
+    pub retry_policy: RetryPolicy,
+}
+
+/// Builds a [CkBtcMinterAgent].
+#[derive(Clone)]
+pub struct CkBtcMinterAgentBuilder {
+    agent: Agent,
+    minter_canister_id: Principal,
+    retry_policy: RetryPolicy,
+}
+
+impl CkBtcMinterAgentBuilder {
+    /// Sets the policy for retrying calls that fail with transient errors.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Disables retries of failed calls.
+    pub fn without_retries(self) -> Self {
+        self.with_retry_policy(RetryPolicy::no_retries())
+    }
+
+    pub fn build(self) -> CkBtcMinterAgent {
+        CkBtcMinterAgent {
+            agent: self.agent,
+            minter_canister_id: self.minter_canister_id,
+            retry_policy: self.retry_policy,
+        }
+    }
 }
 
 impl CkBtcMinterAgent {
+    /// Creates an agent with the default configuration.
+    pub fn new(agent: Agent, minter_canister_id: Principal) -> Self {
+        Self::builder(agent, minter_canister_id).build()
+    }
+
+    pub fn builder(agent: Agent, minter_canister_id: Principal) -> CkBtcMinterAgentBuilder {
+        CkBtcMinterAgentBuilder {
+            agent,
+            minter_canister_id,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    async fn call<Input, Output>(
+        &self,
+        kind: CallKind,
+        method_name: &str,
+        arg: Input,
+    ) -> Result<Output, CkBtcMinterAgentError>
+    where
+        Input: CandidType,
+        Output: CandidType + for<'a> Deserialize<'a>,
+    {
+        let arg = candid::encode_one(arg)?;
+        let max_attempts = match kind {
+            CallKind::Query | CallKind::IdempotentUpdate => self.retry_policy.max_attempts.max(1),
+            CallKind::Update => 1,
+        };
+        let mut attempt = 1;
+        loop {
+            let result = match kind {
+                CallKind::Query => {
+                    self.agent
+                        .query(&self.minter_canister_id, method_name)
+                        .with_arg(arg.clone())
+                        .call()
+                        .await
+                }
+                CallKind::IdempotentUpdate | CallKind::Update => {
+                    self.agent
+                        .update(&self.minter_canister_id, method_name)
+                        .with_arg(arg.clone())
+                        .call_and_wait()
+                        .await
+                }
+            };
+            match result {
+                Ok(reply) => return Ok(candid::decode_one(&reply)?),
+                Err(err) if attempt < max_attempts && is_transient(&err) => {
+                    tokio::time::sleep(self.retry_policy.delay_after(attempt)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
     async fn update<Input, Output>(
         &self,
-        method_name: impl Into<String>,
+        method_name: &str,
+        arg: Input,
+    ) -> Result<Output, CkBtcMinterAgentError>
+    where
+        Input: CandidType,
+        Output: CandidType + for<'a> Deserialize<'a>,
+    {
+        self.call(CallKind::Update, method_name, arg).await
+    }
+
+    async fn idempotent_update<Input, Output>(
+        &self,
+        method_name: &str,
         arg: Input,
     ) -> Result<Output, CkBtcMinterAgentError>
     where
         Input: CandidType,
         Output: CandidType + for<'a> Deserialize<'a>,
     {
-        Ok(candid::decode_one(
-            &self
-                .agent
-                .update(&self.minter_canister_id, method_name)
-                .with_arg(candid::encode_one(arg)?)
-                .call_and_wait()
-                .await?,
-        )?)
+        self.call(CallKind::IdempotentUpdate, method_name, arg)
+            .await
     }
 
     async fn query<Input, Output>(
         &self,
-        method_name: impl Into<String>,
+        method_name: &str,
         arg: Input,
     ) -> Result<Output, CkBtcMinterAgentError>
     where
         Input: CandidType,
         Output: CandidType + for<'a> Deserialize<'a>,
     {
-        Ok(candid::decode_one(
-            &self
-                .agent
-                .query(&self.minter_canister_id, method_name)
-                .with_arg(candid::encode_one(arg)?)
-                .call()
-                .await?,
-        )?)
+        self.call(CallKind::Query, method_name, arg).await
     }
 
     pub async fn get_btc_address(
@@ -81,12 +246,12 @@ impl CkBtcMinterAgent {
         owner: Option<Principal>,
         subaccount: Option<Subaccount>,
     ) -> Result<String, CkBtcMinterAgentError> {
-        self.update("get_btc_address", GetBtcAddressArgs { owner, subaccount })
+        self.idempotent_update("get_btc_address", GetBtcAddressArgs { owner, subaccount })
             .await
     }
 
     pub async fn get_withdrawal_account(&self) -> Result<Account, CkBtcMinterAgentError> {
-        self.update("get_withdrawal_account", ()).await
+        self.idempotent_update("get_withdrawal_account", ()).await
     }
 
     pub async fn retrieve_btc(
@@ -96,11 +261,126 @@ impl CkBtcMinterAgent {
         self.update("retrieve_btc", args).await
     }
 
+    pub async fn retrieve_btc_with_approval(
+        &self,
+        args: RetrieveBtcWithApprovalArgs,
+    ) -> Result<Result<RetrieveBtcOk, RetrieveBtcWithApprovalError>, CkBtcMinterAgentError> {
+        self.update("retrieve_btc_with_approval", args).await
+    }
+
     pub async fn update_balance(
         &self,
         args: UpdateBalanceArgs,
     ) -> Result<Result<Vec<UtxoStatus>, UpdateBalanceError>, CkBtcMinterAgentError> {
-        self.update("update_balance", args).await
+        self.idempotent_update("update_balance", args).await
+    }
+
+    pub async fn subscribe_deposit_notifications(
+        &self,
+        canister_id: Principal,
+    ) -> Result<Result<(), SubscribeDepositNotificationsError>, CkBtcMinterAgentError> {
+        self.idempotent_update("subscribe_deposit_notifications", canister_id)
+            .await
+    }
+
+    pub async fn unsubscribe_deposit_notifications(&self) -> Result<(), CkBtcMinterAgentError> {
+        self.idempotent_update("unsubscribe_deposit_notifications", ())
+            .await
+    }
+
+    pub async fn request_tainted_utxo_release(
+        &self,
+        args: RequestTaintedUtxoReleaseArgs,
+    ) -> Result<Result<(), RequestTaintedUtxoReleaseError>, CkBtcMinterAgentError> {
+        self.update("request_tainted_utxo_release", args).await
+    }
+
+    pub async fn review_tainted_utxo_release(
+        &self,
+        args: ReviewTaintedUtxoReleaseArgs,
+    ) -> Result<Result<(), ReviewTaintedUtxoReleaseError>, CkBtcMinterAgentError> {
+        self.update("review_tainted_utxo_release", args).await
+    }
+
+    pub async fn tainted_utxo_release_status(
+        &self,
+        utxo: Utxo,
+    ) -> Result<Option<TaintedUtxoReleaseStatus>, CkBtcMinterAgentError> {
+        self.query("tainted_utxo_release_status", utxo).await
+    }
+
+    pub async fn estimate_withdrawal_fee(
+        &self,
+        amount: Option<u64>,
+    ) -> Result<WithdrawalFee, CkBtcMinterAgentError> {
+        self.query("estimate_withdrawal_fee", EstimateFeeArg { amount })
+            .await
+    }
+
+    pub async fn estimate_retrieve_btc_fee(
+        &self,
+        amount: u64,
+        fee_rate: Option<RetrieveBtcFeeRate>,
+    ) -> Result<WithdrawalFee, CkBtcMinterAgentError> {
+        self.query(
+            "estimate_retrieve_btc_fee",
+            EstimateRetrieveBtcFeeArg { amount, fee_rate },
+        )
+        .await
+    }
+
+    pub async fn get_deposit_fee(&self) -> Result<u64, CkBtcMinterAgentError> {
+        self.query("get_deposit_fee", ()).await
+    }
+
+    pub async fn get_minter_info(&self) -> Result<MinterInfo, CkBtcMinterAgentError> {
+        self.query("get_minter_info", ()).await
+    }
+
+    pub async fn list_managed_utxos(
+        &self,
+        start: u64,
+        length: u64,
+    ) -> Result<ManagedUtxos, CkBtcMinterAgentError> {
+        self.query("list_managed_utxos", Pagination { start, length })
+            .await
+    }
+
+    /// Returns all UTXOs that the minter manages, fetching as many pages as needed.
+    pub async fn get_known_utxos(&self) -> Result<Vec<ManagedUtxo>, CkBtcMinterAgentError> {
+        let mut utxos = vec![];
+        loop {
+            let page = self
+                .list_managed_utxos(utxos.len() as u64, MAX_ITEMS_PER_PAGE)
+                .await?;
+            if page.utxos.is_empty() {
+                return Ok(utxos);
+            }
+            utxos.extend(page.utxos);
+            if utxos.len() as u64 >= page.total {
+                return Ok(utxos);
+            }
+        }
+    }
+
+    pub async fn list_submitted_transactions(
+        &self,
+        start: u64,
+        length: u64,
+    ) -> Result<SubmittedTransactions, CkBtcMinterAgentError> {
+        self.query("list_submitted_transactions", Pagination { start, length })
+            .await
+    }
+
+    pub async fn get_owed_kyt_fees(&self) -> Result<Vec<OwedKytFee>, CkBtcMinterAgentError> {
+        self.query("get_owed_kyt_fees", ()).await
+    }
+
+    pub async fn distribute_owed_kyt_fees(
+        &self,
+    ) -> Result<Result<Vec<KytFeeDistribution>, DistributeOwedKytFeesError>, CkBtcMinterAgentError>
+    {
+        self.update("distribute_owed_kyt_fees", ()).await
     }
 
     pub async fn retrieve_btc_status(
@@ -122,7 +402,7 @@ impl CkBtcMinterAgent {
         &self,
         start: u64,
         length: u64,
-    ) -> Result<Vec<Event>, CkBtcMinterAgentError> {
+    ) -> Result<GetEventsResult, CkBtcMinterAgentError> {
         self.query("get_events", GetEventsArg { start, length })
             .await
     }
@@ -1,5 +1,6 @@
 use crate::Event;
 use crate::KytMode;
+use crate::QuorumPolicy;
 use askama::Template;
 use candid::Principal;
 
@@ -10,5 +11,7 @@ pub struct DashboardTemplate {
     pub maintainers: Vec<Principal>,
     pub events: Vec<Event>,
     pub mode: KytMode,
+    pub quorum_policy: QuorumPolicy,
+    pub failover_order: Vec<Principal>,
     pub last_api_key_update_date: String,
 }
@@ -175,6 +175,16 @@ impl Error {
     pub fn is_access_denied_error(&self) -> bool {
         self.status == 403
     }
+
+    /// Decodes the error of an unsuccessful response. Responses that are not
+    /// KYT errors (e.g., the HTML page of a failing proxy) become generic errors.
+    pub fn from_response(response: &HttpResponse) -> Self {
+        serde_json::from_slice(&response.body).unwrap_or_else(|_| Self {
+            status: response.status.0.to_u16().unwrap_or(u16::MAX),
+            error: None,
+            message: format!("unexpected response with status {}", response.status),
+        })
+    }
 }
 
 pub async fn http_call<I: Serialize, O: DeserializeOwned>(
@@ -239,14 +249,7 @@ pub async fn http_call<I: Serialize, O: DeserializeOwned>(
         });
         Ok(result)
     } else {
-        let e: Error = serde_json::from_slice(&response.body).unwrap_or_else(|e| {
-            panic!(
-                "failed to decode error {}: {}",
-                String::from_utf8_lossy(&response.body),
-                e
-            )
-        });
-        Err(e)
+        Err(Error::from_response(&response))
     })
 }
 
@@ -23,6 +23,31 @@ impl fmt::Display for KytMode {
     }
 }
 
+/// How the canister combines the answers of several KYT providers.
+#[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum QuorumPolicy {
+    /// Ask the providers one at a time, in the failover order, and use the
+    /// answer of the first provider that responds.
+    FirstAvailable,
+    /// Ask all providers and report alerts if any of them does. Providers that
+    /// fail to respond are ignored, as long as at least one responds.
+    AnyReject,
+    /// Ask all providers and accept the request only if every one of them
+    /// responds without alerts. A single unavailable provider makes the
+    /// check fail.
+    AllAccept,
+}
+
+impl fmt::Display for QuorumPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuorumPolicy::FirstAvailable => write!(f, "FirstAvailable"),
+            QuorumPolicy::AnyReject => write!(f, "AnyReject"),
+            QuorumPolicy::AllAccept => write!(f, "AllAccept"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, CandidType, Deserialize)]
 pub struct InitArg {
     /// The principal of the minter canister.
@@ -43,6 +68,14 @@ pub struct UpgradeArg {
     pub minter_id: Option<Principal>,
     pub maintainers: Option<Vec<Principal>>,
     pub mode: Option<KytMode>,
+    /// Overrides the mode for specific providers.
+    /// Replaces all previous overrides if set.
+    pub provider_modes: Option<Vec<(Principal, KytMode)>>,
+    /// The order in which the canister asks providers.
+    /// Providers that are not in this list come after the listed ones.
+    /// If the list is empty, the canister rotates the providers.
+    pub failover_order: Option<Vec<Principal>>,
+    pub quorum_policy: Option<QuorumPolicy>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, CandidType, Deserialize)]
@@ -1,12 +1,13 @@
 use candid::candid_method;
 use candid::Principal;
 use ic_canisters_http_types as http;
+use ic_cdk::api::call::RejectionCode;
 use ic_cdk::api::management_canister::http_request::{HttpMethod, HttpResponse, TransformArgs};
 use ic_cdk_macros::{init, post_upgrade, query, update};
 use ic_ckbtc_kyt::SetApiKeyArg;
 use ic_ckbtc_kyt::{
     Alert, AlertLevel, DepositRequest, Error, ExposureType, FetchAlertsResponse, KytMode,
-    LifecycleArg, WithdrawalAttempt,
+    LifecycleArg, QuorumPolicy, WithdrawalAttempt,
 };
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory as VM};
 use ic_stable_structures::storable::Storable;
@@ -82,6 +83,10 @@ fn default_kyt_mode() -> KytMode {
     KytMode::Normal
 }
 
+fn default_quorum_policy() -> QuorumPolicy {
+    QuorumPolicy::FirstAvailable
+}
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Config {
     api_keys: BTreeMap<Principal, String>,
@@ -92,6 +97,23 @@ struct Config {
     /// The IC timestamp of the last API key update.
     #[serde(skip_serializing_if = "Option::is_none")]
     last_api_key_update: Option<u64>,
+    /// Overrides of the mode for specific providers.
+    #[serde(default)]
+    provider_modes: BTreeMap<Principal, KytMode>,
+    /// The order in which we ask providers; we rotate them if it is empty.
+    #[serde(default)]
+    failover_order: Vec<Principal>,
+    #[serde(default = "default_quorum_policy")]
+    quorum_policy: QuorumPolicy,
+}
+
+impl Config {
+    fn provider_mode(&self, provider: &Principal) -> KytMode {
+        self.provider_modes
+            .get(provider)
+            .unwrap_or(&self.mode)
+            .clone()
+    }
 }
 
 impl Default for Config {
@@ -102,6 +124,9 @@ impl Default for Config {
             maintainers: vec![],
             mode: default_kyt_mode(),
             last_api_key_update: None,
+            provider_modes: Default::default(),
+            failover_order: vec![],
+            quorum_policy: default_quorum_policy(),
         }
     }
 }
@@ -128,6 +153,7 @@ impl Event {
             EventKind::ApiKeyUpdate { .. } => "legacy_api_key_update",
             EventKind::ApiKeySet { .. } => "api_key_set",
             EventKind::ApiKeyExpired { .. } => "api_key_expired",
+            EventKind::ProviderUnavailable { .. } => "provider_unavailable",
         }
     }
 
@@ -139,6 +165,7 @@ impl Event {
             EventKind::ApiKeyUpdate { .. } => None,
             EventKind::ApiKeySet { .. } => None,
             EventKind::ApiKeyExpired { .. } => None,
+            EventKind::ProviderUnavailable { .. } => None,
         }
     }
 
@@ -149,6 +176,7 @@ impl Event {
             EventKind::ApiKeyUpdate => None,
             EventKind::ApiKeySet { caller, .. } => caller.as_ref(),
             EventKind::ApiKeyExpired { .. } => None,
+            EventKind::ProviderUnavailable { .. } => None,
         }
     }
 
@@ -160,6 +188,7 @@ impl Event {
             EventKind::ApiKeyUpdate => true,
             EventKind::ApiKeySet { .. } => true,
             EventKind::ApiKeyExpired { .. } => true,
+            EventKind::ProviderUnavailable { .. } => false,
         }
     }
 }
@@ -183,6 +212,10 @@ pub enum EventKind {
 
         #[serde(rename = "alerts")]
         alerts: Vec<Alert>,
+
+        #[serde(rename = "provider")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provider: Option<Principal>,
     },
     #[serde(rename = "address_check")]
     AddressCheck {
@@ -204,6 +237,10 @@ pub enum EventKind {
 
         #[serde(rename = "alerts")]
         alerts: Vec<Alert>,
+
+        #[serde(rename = "provider")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provider: Option<Principal>,
     },
     #[serde(rename = "api_key_update")]
     ApiKeyUpdate,
@@ -219,11 +256,44 @@ pub enum EventKind {
     },
     #[serde(rename = "api_key_expired")]
     ApiKeyExpired { provider: Principal },
+    #[serde(rename = "provider_unavailable")]
+    ProviderUnavailable {
+        #[serde(rename = "provider")]
+        provider: Principal,
+
+        #[serde(rename = "error")]
+        error: String,
+    },
 }
 
 enum KytCheckError {
     RpcError(json_rpc::Error),
     TimedOut(String),
+    /// The HTTPS outcall did not go through.
+    CallFailed(String),
+}
+
+impl KytCheckError {
+    fn call_failed(context: &str, (code, msg): (RejectionCode, String)) -> Self {
+        Self::CallFailed(format!("{}: {:?} {}", context, code, msg))
+    }
+}
+
+impl fmt::Display for KytCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KytCheckError::RpcError(err) => write!(f, "{}", err),
+            KytCheckError::TimedOut(msg) => write!(f, "{}", msg),
+            KytCheckError::CallFailed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// A request that the canister checks with the KYT providers.
+#[derive(Clone)]
+enum CheckRequest {
+    Utxo(DepositRequest),
+    Withdrawal(WithdrawalAttempt),
 }
 
 impl From<json_rpc::Error> for KytCheckError {
@@ -257,18 +327,13 @@ thread_local! {
     static UTXO_CHECKS_COUNT: Cell<u64> = Cell::default();
     static ADDRESS_CHECKS_COUNT: Cell<u64> = Cell::default();
 
+    /// The number of checks per provider and outcome.
+    static PROVIDER_CHECKS_COUNT: RefCell<BTreeMap<(Principal, &'static str), u64>> = RefCell::default();
+
     /// The provider we used for the last KYT call.
     static LAST_USED_PROVIDER: Cell<Option<Principal>> = Cell::default();
 }
 
-fn pick_api_key() -> Result<(Principal, String), Error> {
-    CONFIG_CELL.with(|cfg_cell| {
-        let cfg_value = cfg_cell.borrow();
-        let cfg = cfg_value.get();
-        pick_api_key_from(&cfg.api_keys)
-    })
-}
-
 fn pick_api_key_from(api_keys: &BTreeMap<Principal, String>) -> Result<(Principal, String), Error> {
     fn first_key_value(map: &BTreeMap<Principal, String>) -> Option<(Principal, String)> {
         map.first_key_value().map(|(p, k)| (*p, k.clone()))
@@ -298,8 +363,35 @@ fn pick_api_key_from(api_keys: &BTreeMap<Principal, String>) -> Result<(Principa
     })
 }
 
-fn kyt_mode() -> KytMode {
-    CONFIG_CELL.with(|cell| cell.borrow().get().mode.clone())
+/// Returns the providers with an API key, in the order in which we should ask them.
+fn providers_in_order(cfg: &Config) -> Result<Vec<(Principal, String)>, Error> {
+    let api_keys = &cfg.api_keys;
+    if cfg.failover_order.is_empty() {
+        let (first, _) = pick_api_key_from(api_keys)?;
+        return Ok(api_keys
+            .range(first..)
+            .chain(api_keys.range(..first))
+            .map(|(p, k)| (*p, k.clone()))
+            .collect());
+    }
+    if api_keys.is_empty() {
+        return Err(Error::TemporarilyUnavailable(
+            "No valid API keys".to_string(),
+        ));
+    }
+    let listed = cfg
+        .failover_order
+        .iter()
+        .filter_map(|p| api_keys.get(p).map(|k| (*p, k.clone())));
+    let unlisted = api_keys
+        .iter()
+        .filter(|(p, _)| !cfg.failover_order.contains(p))
+        .map(|(p, k)| (*p, k.clone()));
+    Ok(listed.chain(unlisted).collect())
+}
+
+fn count_provider_check(provider: Principal, outcome: &'static str) {
+    PROVIDER_CHECKS_COUNT.with(|c| *c.borrow_mut().entry((provider, outcome)).or_default() += 1);
 }
 
 fn modify_config(f: impl FnOnce(Config) -> Config) {
@@ -369,6 +461,7 @@ fn init(arg: LifecycleArg) {
                 maintainers: arg.maintainers,
                 mode: arg.mode,
                 last_api_key_update: Some(ic_cdk::api::time()),
+                ..Config::default()
             }))
             .expect("failed to initialize the config");
     })
@@ -392,6 +485,15 @@ fn post_upgrade(arg: LifecycleArg) {
         if let Some(mode) = arg.mode {
             config.mode = mode;
         }
+        if let Some(provider_modes) = arg.provider_modes {
+            config.provider_modes = provider_modes.into_iter().collect();
+        }
+        if let Some(failover_order) = arg.failover_order {
+            config.failover_order = failover_order;
+        }
+        if let Some(quorum_policy) = arg.quorum_policy {
+            config.quorum_policy = quorum_policy;
+        }
 
         cell.borrow_mut()
             .set(config)
@@ -453,51 +555,9 @@ async fn get_utxo_alerts(
 #[update(guard = "caller_is_minter")]
 #[candid_method(update)]
 async fn fetch_utxo_alerts(request: DepositRequest) -> Result<FetchAlertsResponse, Error> {
-    loop {
-        let (provider, api_key) = pick_api_key()?;
-        let (external_id, alerts) = match kyt_mode() {
-            KytMode::Normal => match get_utxo_alerts(api_key, request.clone()).await {
-                Ok(result) => result,
-                Err(KytCheckError::TimedOut(msg)) => {
-                    return Err(Error::TemporarilyUnavailable(msg))
-                }
-                Err(KytCheckError::RpcError(err)) => {
-                    if err.is_access_denied_error() {
-                        expire_key(provider);
-                        // Try again with a different provider.
-                        continue;
-                    } else {
-                        return Err(Error::TemporarilyUnavailable(err.to_string()));
-                    }
-                }
-            },
-            KytMode::AcceptAll => (ic_cdk::api::time().to_string(), vec![]),
-            KytMode::RejectAll => (
-                ic_cdk::api::time().to_string(),
-                vec![Alert {
-                    level: AlertLevel::Severe,
-                    category: None,
-                    service: None,
-                    exposure_type: ExposureType::Direct,
-                }],
-            ),
-        };
-
-        UTXO_CHECKS_COUNT.with(|c| c.set(c.get() + 1));
-
-        record_event(EventKind::UtxoCheck {
-            txid: request.txid,
-            vout: request.vout,
-            caller: Some(request.caller),
-            alerts: alerts.clone(),
-            external_id: external_id.clone(),
-        });
-        return Ok(FetchAlertsResponse {
-            external_id,
-            alerts,
-            provider,
-        });
-    }
+    let response = check_with_providers(CheckRequest::Utxo(request)).await?;
+    UTXO_CHECKS_COUNT.with(|c| c.set(c.get() + 1));
+    Ok(response)
 }
 
 async fn get_withdrawal_alerts(
@@ -528,52 +588,121 @@ async fn get_withdrawal_alerts(
 async fn fetch_withdrawal_alerts(
     withdrawal: WithdrawalAttempt,
 ) -> Result<FetchAlertsResponse, Error> {
-    loop {
-        let (provider, api_key) = pick_api_key()?;
-
-        let (external_id, alerts) = match kyt_mode() {
-            KytMode::Normal => match get_withdrawal_alerts(api_key, withdrawal.clone()).await {
-                Ok(result) => result,
-                Err(KytCheckError::TimedOut(msg)) => {
-                    return Err(Error::TemporarilyUnavailable(msg))
-                }
-                Err(KytCheckError::RpcError(e)) => {
-                    if e.is_access_denied_error() {
-                        expire_key(provider);
-                        // Try again with a different provider.
-                        continue;
-                    } else {
-                        return Err(Error::TemporarilyUnavailable(e.to_string()));
-                    }
-                }
-            },
-            KytMode::AcceptAll => (ic_cdk::api::time().to_string(), vec![]),
-            KytMode::RejectAll => (
-                ic_cdk::api::time().to_string(),
-                vec![Alert {
-                    level: AlertLevel::Severe,
-                    service: None,
-                    category: None,
-                    exposure_type: ExposureType::Direct,
-                }],
-            ),
-        };
+    let response = check_with_providers(CheckRequest::Withdrawal(withdrawal)).await?;
+    ADDRESS_CHECKS_COUNT.with(|c| c.set(c.get() + 1));
+    Ok(response)
+}
 
-        ADDRESS_CHECKS_COUNT.with(|c| c.set(c.get() + 1));
+/// Checks the request with a single provider.
+async fn check_with_provider(
+    mode: KytMode,
+    api_key: String,
+    request: CheckRequest,
+) -> Result<(json_rpc::ExternalId, Vec<Alert>), KytCheckError> {
+    match mode {
+        KytMode::Normal => match request {
+            CheckRequest::Utxo(request) => get_utxo_alerts(api_key, request).await,
+            CheckRequest::Withdrawal(withdrawal) => {
+                get_withdrawal_alerts(api_key, withdrawal).await
+            }
+        },
+        KytMode::AcceptAll => Ok((ic_cdk::api::time().to_string(), vec![])),
+        KytMode::RejectAll => Ok((
+            ic_cdk::api::time().to_string(),
+            vec![Alert {
+                level: AlertLevel::Severe,
+                category: None,
+                service: None,
+                exposure_type: ExposureType::Direct,
+            }],
+        )),
+    }
+}
 
-        record_event(EventKind::AddressCheck {
+fn record_check(
+    request: &CheckRequest,
+    provider: Principal,
+    external_id: String,
+    alerts: Vec<Alert>,
+) {
+    let kind = match request.clone() {
+        CheckRequest::Utxo(request) => EventKind::UtxoCheck {
+            txid: request.txid,
+            vout: request.vout,
+            caller: Some(request.caller),
+            external_id,
+            alerts,
+            provider: Some(provider),
+        },
+        CheckRequest::Withdrawal(withdrawal) => EventKind::AddressCheck {
             caller: Some(withdrawal.caller),
             withdrawal_id: withdrawal.id,
             address: withdrawal.address,
             amount: withdrawal.amount,
-            alerts: alerts.clone(),
-            external_id: external_id.clone(),
-        });
-        return Ok(FetchAlertsResponse {
             external_id,
             alerts,
-            provider,
-        });
+            provider: Some(provider),
+        },
+    };
+    record_event(kind);
+}
+
+/// Checks the request with the providers, in the failover order, and
+/// combines their answers according to the quorum policy.
+async fn check_with_providers(request: CheckRequest) -> Result<FetchAlertsResponse, Error> {
+    let (providers, policy) = CONFIG_CELL.with(|cell| {
+        let cfg = cell.borrow();
+        let cfg = cfg.get();
+        let providers = providers_in_order(cfg)?
+            .into_iter()
+            .map(|(provider, api_key)| (provider, api_key, cfg.provider_mode(&provider)))
+            .collect::<Vec<_>>();
+        Ok::<_, Error>((providers, cfg.quorum_policy.clone()))
+    })?;
+
+    let mut answers: Vec<FetchAlertsResponse> = vec![];
+    let mut last_error = None;
+    for (provider, api_key, mode) in providers {
+        match check_with_provider(mode, api_key, request.clone()).await {
+            Ok((external_id, alerts)) => {
+                count_provider_check(provider, if alerts.is_empty() { "clean" } else { "alerts" });
+                record_check(&request, provider, external_id.clone(), alerts.clone());
+                answers.push(FetchAlertsResponse {
+                    external_id,
+                    alerts,
+                    provider,
+                });
+                if policy == QuorumPolicy::FirstAvailable {
+                    break;
+                }
+            }
+            Err(err) => {
+                count_provider_check(provider, "error");
+                match &err {
+                    KytCheckError::RpcError(e) if e.is_access_denied_error() => {
+                        expire_key(provider)
+                    }
+                    _ => record_event(EventKind::ProviderUnavailable {
+                        provider,
+                        error: err.to_string(),
+                    }),
+                }
+                if policy == QuorumPolicy::AllAccept {
+                    return Err(Error::TemporarilyUnavailable(err.to_string()));
+                }
+                // Try again with a different provider.
+                last_error = Some(err.to_string());
+            }
+        }
+    }
+
+    // Report the alerts of any provider that found some.
+    match answers.iter().position(|answer| !answer.alerts.is_empty()) {
+        Some(i) => Ok(answers.swap_remove(i)),
+        None if !answers.is_empty() => Ok(answers.swap_remove(0)),
+        None => Err(Error::TemporarilyUnavailable(
+            last_error.unwrap_or_else(|| "No valid API keys".to_string()),
+        )),
     }
 }
 
@@ -594,7 +723,7 @@ fn cleanup_response(mut args: TransformArgs) -> HttpResponse {
         // The error response might contain non-deterministic fields that make it impossible to reach consensus,
         // such as timestamps:
         // {"timestamp":"2023-03-01T20:35:49.416+00:00","status":403,"error":"Forbidden","message":"AccessDenied","path":"/api/kyt/v2/users/cktestbtc/transfers"}
-        let error: json_rpc::Error = serde_json::from_slice(&args.response.body).unwrap();
+        let error = json_rpc::Error::from_response(&args.response);
         args.response.body = serde_json::to_string(&error).unwrap().into_bytes();
     }
     args.response
@@ -663,6 +792,26 @@ fn http_request(req: http::HttpRequest) -> http::HttpResponse {
             )
             .unwrap();
 
+        PROVIDER_CHECKS_COUNT.with(|c| {
+            let mut counter = writer
+                .counter_vec(
+                    "ckbtc_kyt_provider_checks_total",
+                    "The number of checks per KYT provider and outcome since the last canister upgrade.",
+                )
+                .unwrap();
+            for ((provider, outcome), count) in c.borrow().iter() {
+                counter = counter
+                    .value(
+                        &[
+                            ("provider", provider.to_string().as_str()),
+                            ("outcome", outcome),
+                        ],
+                        *count as f64,
+                    )
+                    .unwrap();
+            }
+        });
+
         http::HttpResponseBuilder::ok()
             .header("Content-Type", "text/plain; version=0.0.4")
             .with_body_and_content_length(writer.into_inner())
@@ -685,6 +834,8 @@ fn http_request(req: http::HttpRequest) -> http::HttpResponse {
                 config.last_api_key_update.unwrap_or_default(),
             ),
             mode: config.mode,
+            quorum_policy: config.quorum_policy,
+            failover_order: config.failover_order,
         }
         .render()
         .unwrap();
@@ -700,7 +851,7 @@ fn http_request(req: http::HttpRequest) -> http::HttpResponse {
 async fn http_register_tx(
     api_key: String,
     req: DepositRequest,
-) -> Result<json_rpc::RegisterTransferResponse, json_rpc::Error> {
+) -> Result<json_rpc::RegisterTransferResponse, KytCheckError> {
     let response: json_rpc::RegisterTransferResponse = json_rpc::http_call(
         HttpMethod::POST,
         api_key,
@@ -713,14 +864,14 @@ async fn http_register_tx(
         },
     )
     .await
-    .expect("failed to register transfer")?;
+    .map_err(|err| KytCheckError::call_failed("failed to register transfer", err))??;
     Ok(response)
 }
 
 async fn http_is_transfer_ready(
     api_key: String,
     external_id: json_rpc::ExternalId,
-) -> Result<bool, json_rpc::Error> {
+) -> Result<bool, KytCheckError> {
     let response: json_rpc::TransferSummaryResponse = json_rpc::http_call(
         HttpMethod::GET,
         api_key,
@@ -728,7 +879,7 @@ async fn http_is_transfer_ready(
         json_rpc::GetSummaryRequest { external_id },
     )
     .await
-    .expect("failed to get a transfer summary")?;
+    .map_err(|err| KytCheckError::call_failed("failed to get a transfer summary", err))??;
 
     Ok(response.updated_at.is_some())
 }
@@ -736,7 +887,7 @@ async fn http_is_transfer_ready(
 async fn http_get_utxo_alerts(
     api_key: String,
     external_id: json_rpc::ExternalId,
-) -> Result<Vec<Alert>, json_rpc::Error> {
+) -> Result<Vec<Alert>, KytCheckError> {
     let response: json_rpc::GetAlertsResponse = json_rpc::http_call(
         HttpMethod::GET,
         api_key,
@@ -744,7 +895,7 @@ async fn http_get_utxo_alerts(
         json_rpc::GetAlertsRequest { external_id },
     )
     .await
-    .expect("failed to fetch alerts")?;
+    .map_err(|err| KytCheckError::call_failed("failed to fetch alerts", err))??;
     Ok(response
         .alerts
         .into_iter()
@@ -755,7 +906,7 @@ async fn http_get_utxo_alerts(
 async fn http_register_withdrawal(
     api_key: String,
     withdrawal: WithdrawalAttempt,
-) -> Result<json_rpc::RegisterWithdrawalResponse, json_rpc::Error> {
+) -> Result<json_rpc::RegisterWithdrawalResponse, KytCheckError> {
     let response: json_rpc::RegisterWithdrawalResponse = json_rpc::http_call(
         HttpMethod::POST,
         api_key,
@@ -770,14 +921,14 @@ async fn http_register_withdrawal(
         },
     )
     .await
-    .expect("failed to register a withdrawal")?;
+    .map_err(|err| KytCheckError::call_failed("failed to register a withdrawal", err))??;
     Ok(response)
 }
 
 async fn http_is_withdrawal_ready(
     api_key: String,
     external_id: json_rpc::ExternalId,
-) -> Result<bool, json_rpc::Error> {
+) -> Result<bool, KytCheckError> {
     let response: json_rpc::WithdrawalSummaryResponse = json_rpc::http_call(
         HttpMethod::GET,
         api_key,
@@ -785,7 +936,7 @@ async fn http_is_withdrawal_ready(
         json_rpc::GetSummaryRequest { external_id },
     )
     .await
-    .expect("failed to get a transfer summary")?;
+    .map_err(|err| KytCheckError::call_failed("failed to get a transfer summary", err))??;
 
     Ok(response.updated_at.is_some())
 }
@@ -793,7 +944,7 @@ async fn http_is_withdrawal_ready(
 async fn http_get_withdrawal_alerts(
     api_key: String,
     external_id: json_rpc::ExternalId,
-) -> Result<Vec<Alert>, json_rpc::Error> {
+) -> Result<Vec<Alert>, KytCheckError> {
     let response: json_rpc::GetAlertsResponse = json_rpc::http_call(
         HttpMethod::GET,
         api_key,
@@ -801,7 +952,7 @@ async fn http_get_withdrawal_alerts(
         json_rpc::GetAlertsRequest { external_id },
     )
     .await
-    .expect("failed to fetch alerts")?;
+    .map_err(|err| KytCheckError::call_failed("failed to fetch alerts", err))??;
     Ok(response
         .alerts
         .into_iter()
@@ -859,6 +1010,47 @@ fn test_key_rotation() {
     assert!(result.is_err(), "expected an error, got: {:?}", result);
 }
 
+#[test]
+fn test_providers_in_order() {
+    let p1 = Principal::management_canister();
+    let p2 = Principal::anonymous();
+    let p3 = Principal::from_slice(&[1]);
+    let mut config = Config::default();
+    for (p, k) in [(p1, "A"), (p2, "B"), (p3, "C")] {
+        config.api_keys.insert(p, k.to_string());
+    }
+    let providers = |config: &Config| {
+        providers_in_order(config)
+            .unwrap()
+            .into_iter()
+            .map(|(_, k)| k)
+            .collect::<Vec<_>>()
+    };
+
+    // Without a failover order, every call starts with the next provider.
+    let first = providers(&config);
+    assert_eq!(first.len(), 3);
+    let second = providers(&config);
+    assert_ne!(first[0], second[0]);
+
+    // Listed providers come first, the others in key order.
+    config.failover_order = vec![p2, Principal::from_slice(&[2])];
+    let mut unlisted: Vec<_> = config
+        .api_keys
+        .iter()
+        .filter(|(p, _)| **p != p2)
+        .map(|(_, k)| k.clone())
+        .collect();
+    unlisted.insert(0, "B".to_string());
+    assert_eq!(providers(&config), unlisted);
+
+    config.provider_modes.insert(p3, KytMode::AcceptAll);
+    assert_eq!(config.provider_mode(&p3), KytMode::AcceptAll);
+    assert_eq!(config.provider_mode(&p1), KytMode::Normal);
+
+    assert!(providers_in_order(&Config::default()).is_err());
+}
+
 #[test]
 fn check_candid_interface_compatibility() {
     use candid::utils::{service_equal, CandidSource};
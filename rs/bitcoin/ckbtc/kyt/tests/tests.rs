@@ -2,7 +2,7 @@ use assert_matches::assert_matches;
 use candid::{Decode, Encode, Principal};
 use ic_ckbtc_kyt::{
     Alert, AlertLevel, DepositRequest, Error as KytError, ExposureType, FetchAlertsResponse,
-    InitArg, KytMode, LifecycleArg, SetApiKeyArg,
+    InitArg, KytMode, LifecycleArg, QuorumPolicy, SetApiKeyArg, UpgradeArg,
 };
 use ic_state_machine_tests::{
     CanisterHttpRequestContext, CanisterHttpResponsePayload, Cycles, IngressState, IngressStatus,
@@ -199,3 +199,122 @@ fn test_key_recovery() {
         WasmResult::Reject(msg) => panic!("unexpected reject: {}", msg),
     }
 }
+
+#[test]
+fn test_provider_failover() {
+    let env = StateMachine::new();
+    let p1 = Principal::management_canister();
+    let p2 = Principal::anonymous();
+    let minter_id = Principal::anonymous();
+
+    let kyt = env
+        .install_canister_with_cycles(
+            kyt_wasm(),
+            Encode!(&LifecycleArg::InitArg(InitArg {
+                minter_id,
+                maintainers: vec![p1, p2],
+                mode: KytMode::Normal,
+            }))
+            .unwrap(),
+            None,
+            Cycles::from(100_000_000_000_000u64),
+        )
+        .expect("failed to install the KYT canister");
+
+    for (provider, api_key) in [(p1, "Key1"), (p2, "Key2")] {
+        env.execute_ingress_as(
+            provider.into(),
+            kyt,
+            "set_api_key",
+            Encode!(&SetApiKeyArg {
+                api_key: api_key.to_string()
+            })
+            .unwrap(),
+        )
+        .unwrap();
+    }
+
+    env.upgrade_canister(
+        kyt,
+        kyt_wasm(),
+        Encode!(&LifecycleArg::UpgradeArg(UpgradeArg {
+            minter_id: None,
+            maintainers: None,
+            mode: None,
+            provider_modes: None,
+            failover_order: Some(vec![p2, p1]),
+            quorum_policy: Some(QuorumPolicy::FirstAvailable),
+        }))
+        .unwrap(),
+    )
+    .expect("failed to upgrade the KYT canister");
+
+    let call_id = env.send_ingress(
+        minter_id.into(),
+        kyt,
+        "fetch_utxo_alerts",
+        Encode!(&DepositRequest {
+            caller: minter_id,
+            txid: [0; 32],
+            vout: 0
+        })
+        .unwrap(),
+    );
+
+    env.tick();
+
+    handle_http_call("transfer with the first provider down", &env, |req| {
+        assert_has_header(req, "Token", "Key2");
+        CanisterHttpResponsePayload {
+            status: 503,
+            headers: vec![],
+            body: b"<html>Service Unavailable</html>".to_vec(),
+        }
+    });
+
+    tick_until_next_request(&env);
+
+    handle_http_call("failover to the next provider", &env, |req| {
+        assert_has_header(req, "Token", "Key1");
+        assert!(
+            req.url.ends_with("/transfers"),
+            "expected a transfer registration, got: {:?}",
+            req
+        );
+        CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: br#"{"externalId": "12356-abcde", "updatedAt": "2023-03-02T15:23:27+00:00", "transferReference":"0000000000000000000000000000000000000000000000000000000000000000:0"}"#.to_vec(),
+        }
+    });
+
+    tick_until_next_request(&env);
+
+    handle_http_call("fetch alerts", &env, |req| {
+        assert_has_header(req, "Token", "Key1");
+        CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: br#"{"alerts": []}"#.to_vec(),
+        }
+    });
+
+    let result = env
+        .await_ingress(call_id, /*max_ticks=*/ MAX_TICKS)
+        .expect("the fetch request didn't finish");
+
+    match &result {
+        WasmResult::Reply(bytes) => {
+            let response = Decode!(bytes, Result<FetchAlertsResponse, KytError>).unwrap();
+            assert_eq!(
+                response,
+                Ok(FetchAlertsResponse {
+                    external_id: "12356-abcde".to_string(),
+                    provider: p1,
+                    alerts: vec![],
+                })
+            );
+        }
+        WasmResult::Reject(msg) => panic!("unexpected reject: {}", msg),
+    }
+}
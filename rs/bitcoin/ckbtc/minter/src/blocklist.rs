@@ -1,3 +1,6 @@
+use crate::address::BitcoinAddress;
+use ic_btc_interface::Network;
+
 /// The list of addresses to which we do not allow retrievals.
 /// NOTE: Keep it sorted!
 pub const BTC_ADDRESS_BLOCKLIST: &[&str] = &[
@@ -342,3 +345,13 @@ pub const BTC_ADDRESS_BLOCKLIST: &[&str] = &[
     "bc1qwa6zu6qhl6wqnlxp642vcf89nptsassle25ulf",
     "bc1qx3e2axj3wsfn0ndtvlwmkghmmgm4583nqg8ngk",
 ];
+
+/// Returns true if the address is on the blocklist.
+///
+/// The check compares the canonical mainnet encoding of the address, so that
+/// it does not depend on the spelling of the address (e.g., upper-case bech32).
+pub fn is_blocked(address: &BitcoinAddress) -> bool {
+    BTC_ADDRESS_BLOCKLIST
+        .binary_search(&address.display(Network::Mainnet).as_str())
+        .is_ok()
+}
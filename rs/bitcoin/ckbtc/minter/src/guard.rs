@@ -147,6 +147,9 @@ mod tests {
             mode: crate::state::Mode::GeneralAvailability,
             kyt_principal: Some(CanisterId::from(0)),
             kyt_fee: None,
+            resubmission_delay_blocks: None,
+            max_fee_bump_percent: None,
+            tainted_utxo_reviewer: None,
         }
     }
 
@@ -6,7 +6,7 @@ use crate::state::ReimbursementReason;
 use crate::tasks::schedule_after;
 use candid::{CandidType, Deserialize};
 use ic_btc_interface::{MillisatoshiPerByte, Network, OutPoint, Satoshi, Txid, Utxo};
-use ic_canister_log::log;
+use ic_canister_log::{log, Severity};
 use ic_ic00_types::DerivationPath;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{Memo, TransferError};
@@ -57,27 +57,30 @@ pub const MIN_RELAY_FEE_PER_VBYTE: MillisatoshiPerByte = 1_000;
 /// The minimum time the minter should wait before replacing a stuck transaction.
 pub const MIN_RESUBMISSION_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
 
+/// The default dustRelayFee is 3 sat/vB,
+/// which translates to a dust threshold of 546 satoshi for P2PKH outputs.
+/// The threshold for other types is lower,
+/// so we simply use 546 satoshi as the minimum amount per output.
+const MIN_OUTPUT_AMOUNT: u64 = 546;
+
 /// The maximum memo size of a transaction on the ckBTC ledger.
 /// The ckBTC minter requires at least 69 bytes, we choose 80
 /// to have some room for future modifications.
 pub const CKBTC_LEDGER_MEMO_SIZE: u16 = 80;
 
-#[derive(Clone, serde::Serialize, Deserialize, Debug)]
-pub enum Priority {
-    P0,
-    P1,
-}
-
+/// An entry of the `/logs` endpoint response.
 #[derive(Clone, serde::Serialize, Deserialize, Debug)]
 pub struct LogEntry {
     pub timestamp: u64,
-    pub priority: Priority,
+    pub severity: Severity,
+    pub module: String,
     pub file: String,
     pub line: u32,
     pub message: String,
     pub counter: u64,
 }
 
+/// The `/logs` endpoint response.
 #[derive(Clone, Default, serde::Serialize, Deserialize, Debug)]
 pub struct Log {
     pub entries: Vec<LogEntry>,
@@ -90,6 +93,21 @@ pub struct MinterInfo {
     pub kyt_fee: u64,
 }
 
+/// The outcome of paying the fees owed to a KYT provider.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct KytFeeDistribution {
+    pub kyt_provider: candid::Principal,
+    pub amount: u64,
+    /// The index of the mint transaction on the ledger, or why minting failed.
+    pub result: Result<u64, String>,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum DistributeOwedKytFeesError {
+    /// Another distribution of KYT fees is in progress.
+    AlreadyProcessing,
+}
+
 #[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ECDSAPublicKey {
     pub public_key: Vec<u8>,
@@ -108,6 +126,8 @@ struct SignTxRequest {
     requests: Vec<state::RetrieveBtcRequest>,
     /// The list of UTXOs we use as transaction inputs.
     utxos: Vec<Utxo>,
+    /// The fee rate of the unsigned transaction.
+    fee_per_vbyte: MillisatoshiPerByte,
 }
 
 /// Undoes changes we make to the ckBTC state when we construct a pending transaction.
@@ -215,6 +235,43 @@ pub async fn estimate_fee_per_vbyte() -> Option<MillisatoshiPerByte> {
     }
 }
 
+/// Returns the percentile of recent fee rates that we use to get a transaction
+/// confirmed within the given number of blocks.
+pub fn fee_percentile_for_confirmation_target(blocks: u32) -> usize {
+    match blocks {
+        0..=1 => 90,
+        2..=3 => 75,
+        4..=6 => 50,
+        7..=24 => 25,
+        _ => 10,
+    }
+}
+
+/// Returns the fee rate, in millisatoshi per vbyte, of a transaction that
+/// fulfills requests asking for the given `fee_rate`.
+///
+/// * `median_fee_per_vbyte` - The current 50th percentile of BTC fees, used if
+///   the requests did not ask for a specific fee rate.
+/// * `fee_percentiles` - The most recent fee percentiles, see
+///   [`state::CkBtcMinterState::last_fee_per_vbyte`].
+pub fn resolve_fee_per_vbyte(
+    fee_rate: Option<&state::RetrieveBtcFeeRate>,
+    median_fee_per_vbyte: MillisatoshiPerByte,
+    fee_percentiles: &[MillisatoshiPerByte],
+) -> MillisatoshiPerByte {
+    match fee_rate {
+        None => median_fee_per_vbyte,
+        Some(state::RetrieveBtcFeeRate::SatoshiPerVbyte(sat_per_vbyte)) => {
+            sat_per_vbyte.saturating_mul(1_000)
+        }
+        Some(state::RetrieveBtcFeeRate::TargetConfirmationBlocks(blocks)) => fee_percentiles
+            .get(fee_percentile_for_confirmation_target(*blocks))
+            .copied()
+            .unwrap_or(median_fee_per_vbyte)
+            .max(MIN_RELAY_FEE_PER_VBYTE),
+    }
+}
+
 /// Constructs and sends out signed bitcoin transactions for pending retrieve
 /// requests.
 async fn submit_pending_requests() {
@@ -244,6 +301,13 @@ async fn submit_pending_requests() {
             return None;
         }
 
+        // All the requests of a batch ask for the same fee rate.
+        let fee_per_vbyte = resolve_fee_per_vbyte(
+            batch[0].fee_rate.as_ref(),
+            fee_millisatoshi_per_vbyte,
+            &s.last_fee_per_vbyte,
+        );
+
         let outputs: Vec<_> = batch
             .iter()
             .map(|req| (req.address.clone(), req.amount))
@@ -253,7 +317,7 @@ async fn submit_pending_requests() {
             &mut s.available_utxos,
             outputs,
             main_address,
-            fee_millisatoshi_per_vbyte,
+            fee_per_vbyte,
         ) {
             Ok((unsigned_tx, change_output, utxos)) => {
                 for req in batch.iter() {
@@ -269,6 +333,7 @@ async fn submit_pending_requests() {
                     unsigned_tx,
                     requests: batch,
                     utxos,
+                    fee_per_vbyte,
                 })
             }
             Err(BuildTxError::AmountTooLow) => {
@@ -378,7 +443,7 @@ async fn submit_pending_requests() {
                                     used_utxos,
                                     change_output: Some(req.change_output),
                                     submitted_at: ic_cdk::api::time(),
-                                    fee_per_vbyte: Some(fee_millisatoshi_per_vbyte),
+                                    fee_per_vbyte: Some(req.fee_per_vbyte),
                                 },
                             );
                         });
@@ -403,6 +468,27 @@ async fn submit_pending_requests() {
     }
 }
 
+/// Returns the fee rate of a transaction replacing a stuck transaction with the
+/// fee rate `prev_fee_per_vbyte`, or None if the replacement would exceed
+/// `max_fee_per_vbyte`.
+pub fn replacement_fee_per_vbyte(
+    requested_fee_per_vbyte: MillisatoshiPerByte,
+    prev_fee_per_vbyte: Option<MillisatoshiPerByte>,
+    max_fee_per_vbyte: Option<MillisatoshiPerByte>,
+) -> Option<MillisatoshiPerByte> {
+    // Ensure that the fee is at least min relay fee higher than the previous
+    // transaction fee to comply with BIP-125 (https://en.bitcoin.it/wiki/BIP_0125).
+    let min_fee_per_vbyte = prev_fee_per_vbyte
+        .map(|prev_fee| prev_fee + MIN_RELAY_FEE_PER_VBYTE)
+        .unwrap_or_default();
+    let fee_per_vbyte = requested_fee_per_vbyte.max(min_fee_per_vbyte);
+    match max_fee_per_vbyte {
+        Some(max_fee) if min_fee_per_vbyte > max_fee => None,
+        Some(max_fee) => Some(fee_per_vbyte.min(max_fee)),
+        None => Some(fee_per_vbyte),
+    }
+}
+
 fn finalization_time_estimate(min_confirmations: u32, network: Network) -> Duration {
     Duration::from_nanos(
         min_confirmations as u64
@@ -459,6 +545,108 @@ async fn reimburse_failed_kyt() {
     }
 }
 
+/// Sends the value of the quarantined UTXOs with approved release requests to
+/// the addresses that their owners specified.
+async fn release_tainted_utxos() {
+    let approved_releases: Vec<_> = state::read_state(|s| {
+        s.tainted_utxo_releases
+            .iter()
+            .filter(|(_, release)| release.status == state::TaintedUtxoReleaseStatus::Approved)
+            .map(|(utxo, release)| (utxo.clone(), release.clone()))
+            .collect()
+    });
+
+    if approved_releases.is_empty() {
+        return;
+    }
+
+    let fee_per_vbyte = match estimate_fee_per_vbyte().await {
+        Some(fee) => fee,
+        None => return,
+    };
+
+    let ecdsa_public_key = updates::get_btc_address::init_ecdsa_public_key().await;
+    let (key_name, network) = state::read_state(|s| (s.ecdsa_key_name.clone(), s.btc_network));
+    let main_address = address::account_to_bitcoin_address(
+        &ecdsa_public_key,
+        &Account {
+            owner: ic_cdk::id(),
+            subaccount: None,
+        },
+    );
+
+    for (utxo, release) in approved_releases {
+        // The release transaction has a change output, so the minter can
+        // finalize or replace it like the retrieve_btc transactions.
+        let (unsigned_tx, change_output, _) = match build_unsigned_transaction(
+            &mut BTreeSet::from([utxo.clone()]),
+            vec![(release.address.clone(), utxo.value)],
+            main_address.clone(),
+            fee_per_vbyte,
+        ) {
+            Ok(tx) => tx,
+            Err(err) => {
+                log!(
+                    P0,
+                    "[release_tainted_utxos]: failed to build a transaction releasing UTXO {}: {:?}",
+                    tx::DisplayOutpoint(&utxo.outpoint),
+                    err,
+                );
+                continue;
+            }
+        };
+        let txid = unsigned_tx.txid();
+        let outpoint_account = BTreeMap::from([(utxo.outpoint.clone(), release.account)]);
+
+        let signed_tx = match sign_transaction(
+            key_name.clone(),
+            &ecdsa_public_key,
+            &outpoint_account,
+            unsigned_tx,
+        )
+        .await
+        {
+            Ok(tx) => tx,
+            Err(err) => {
+                log!(
+                    P0,
+                    "[release_tainted_utxos]: failed to sign a BTC transaction: {}",
+                    err
+                );
+                continue;
+            }
+        };
+
+        match management::send_transaction(&signed_tx, network).await {
+            Ok(()) => {
+                log!(
+                    P0,
+                    "[release_tainted_utxos]: released UTXO {} to {} in transaction {}",
+                    tx::DisplayOutpoint(&utxo.outpoint),
+                    release.address.display(network),
+                    &txid,
+                );
+                let release_tx = state::SubmittedBtcTransaction {
+                    requests: vec![],
+                    txid,
+                    used_utxos: vec![utxo.clone()],
+                    submitted_at: ic_cdk::api::time(),
+                    change_output: Some(change_output),
+                    fee_per_vbyte: Some(fee_per_vbyte),
+                };
+                state::mutate_state(|s| state::audit::released_tainted_utxo(s, utxo, release_tx));
+            }
+            Err(err) => {
+                log!(
+                    P0,
+                    "[release_tainted_utxos]: failed to send a bitcoin transaction: {}",
+                    err
+                );
+            }
+        }
+    }
+}
+
 async fn finalize_requests() {
     if state::read_state(|s| s.submitted_transactions.is_empty()) {
         return;
@@ -532,10 +720,14 @@ async fn finalize_requests() {
         }
     });
 
-    // Do not replace transactions if less than MIN_RESUBMISSION_DELAY passed since their
+    // Do not replace transactions if less than the resubmission delay passed since their
     // submission. This strategy works around short-term fee spikes.
+    let resubmission_delay = state::read_state(|s| match s.resubmission_delay_blocks {
+        Some(blocks) => finalization_time_estimate(blocks, s.btc_network),
+        None => MIN_RESUBMISSION_DELAY,
+    });
     maybe_finalized_transactions
-        .retain(|_txid, tx| tx.submitted_at + MIN_RESUBMISSION_DELAY.as_nanos() as u64 <= now);
+        .retain(|_txid, tx| tx.submitted_at + resubmission_delay.as_nanos() as u64 <= now);
 
     if maybe_finalized_transactions.is_empty() {
         // There are no transactions eligible for replacement.
@@ -601,25 +793,63 @@ async fn finalize_requests() {
         None => return,
     };
 
-    let key_name = state::read_state(|s| s.ecdsa_key_name.clone());
+    let (key_name, fee_percentiles, max_fee_bump_percent) = state::read_state(|s| {
+        (
+            s.ecdsa_key_name.clone(),
+            s.last_fee_per_vbyte.clone(),
+            s.max_fee_bump_percent,
+        )
+    });
 
     for (old_txid, submitted_tx) in maybe_finalized_transactions {
         let mut utxos: BTreeSet<_> = submitted_tx.used_utxos.iter().cloned().collect();
 
-        let tx_fee_per_vbyte = match submitted_tx.fee_per_vbyte {
-            Some(prev_fee) => {
-                // Ensure that the fee is at least min relay fee higher than the previous
-                // transaction fee to comply with BIP-125 (https://en.bitcoin.it/wiki/BIP_0125).
-                fee_per_vbyte.max(prev_fee + MIN_RELAY_FEE_PER_VBYTE)
+        let requested_fee_per_vbyte = resolve_fee_per_vbyte(
+            submitted_tx
+                .requests
+                .first()
+                .and_then(|req| req.fee_rate.as_ref()),
+            fee_per_vbyte,
+            &fee_percentiles,
+        );
+
+        let max_fee_per_vbyte = max_fee_bump_percent.and_then(|percent| {
+            state::read_state(|s| s.original_fee_per_vbyte(&submitted_tx))
+                .map(|original_fee| original_fee.saturating_mul(100 + percent) / 100)
+        });
+
+        let tx_fee_per_vbyte = match replacement_fee_per_vbyte(
+            requested_fee_per_vbyte,
+            submitted_tx.fee_per_vbyte,
+            max_fee_per_vbyte,
+        ) {
+            Some(fee) => fee,
+            None => {
+                log!(
+                    P0,
+                    "[finalize_requests]: not replacing stuck transaction {}: the fee cap of {:?} millisatoshi/vbyte is reached",
+                    &submitted_tx.txid,
+                    max_fee_per_vbyte,
+                );
+                continue;
             }
-            None => fee_per_vbyte,
         };
 
-        let outputs = submitted_tx
-            .requests
-            .iter()
-            .map(|req| (req.address.clone(), req.amount))
-            .collect();
+        // Transactions releasing tainted UTXOs do not have retrieve_btc requests,
+        // the release request determines their output instead.
+        let release = state::read_state(|s| {
+            s.find_tainted_utxo_release(&submitted_tx)
+                .map(|(utxo, release)| (utxo.clone(), release.clone()))
+        });
+
+        let outputs = match &release {
+            Some((utxo, release)) => vec![(release.address.clone(), utxo.value)],
+            None => submitted_tx
+                .requests
+                .iter()
+                .map(|req| (req.address.clone(), req.amount))
+                .collect(),
+        };
 
         let (unsigned_tx, change_output, used_utxos) = match build_unsigned_transaction(
             &mut utxos,
@@ -641,7 +871,11 @@ async fn finalize_requests() {
             }
         };
 
-        let outpoint_account = state::read_state(|s| filter_output_accounts(s, &unsigned_tx));
+        // The minter does not track the accounts of quarantined UTXOs.
+        let outpoint_account = match release {
+            Some((utxo, release)) => BTreeMap::from([(utxo.outpoint, release.account)]),
+            None => state::read_state(|s| filter_output_accounts(s, &unsigned_tx)),
+        };
 
         assert!(
             utxos.is_empty(),
@@ -988,11 +1222,6 @@ pub fn build_unsigned_transaction(
     }
 
     let fee_shares = distribute(fee + minter_fee, outputs.len() as u64);
-    // The default dustRelayFee is 3 sat/vB,
-    // which translates to a dust threshold of 546 satoshi for P2PKH outputs.
-    // The threshold for other types is lower,
-    // so we simply use 546 satoshi as the minimum amount per output.
-    const MIN_OUTPUT_AMOUNT: u64 = 546;
 
     for (output, fee_share) in unsigned_tx.outputs.iter_mut().zip(fee_shares.iter()) {
         if output.address != main_address {
@@ -1039,7 +1268,8 @@ fn distribute(amount: u64, n: u64) -> Vec<u64> {
     shares
 }
 
-pub async fn distribute_kyt_fees() {
+/// Mints the fees owed to each KYT provider, and returns the outcome for each provider.
+pub async fn distribute_kyt_fees() -> Vec<KytFeeDistribution> {
     use ic_icrc1_client_cdk::CdkRuntime;
     use ic_icrc1_client_cdk::ICRC1Client;
     use icrc_ledger_types::icrc1::transfer::TransferArg;
@@ -1075,10 +1305,13 @@ pub async fn distribute_kyt_fees() {
     }
 
     let fees_to_distribute = state::read_state(|s| s.owed_kyt_amount.clone());
+    let mut distributions = Vec::with_capacity(fees_to_distribute.len());
     for (provider, amount) in fees_to_distribute {
         let memo = crate::memo::MintMemo::Kyt;
-        match mint(amount, provider, crate::memo::encode(&memo).into()).await {
+        let result = mint(amount, provider, crate::memo::encode(&memo).into()).await;
+        match &result {
             Ok(block_index) => {
+                let block_index = *block_index;
                 state::mutate_state(|s| {
                     if let Err(state::Overdraft(overdraft)) =
                         state::audit::distributed_kyt_fee(s, provider, amount, block_index)
@@ -1113,7 +1346,13 @@ pub async fn distribute_kyt_fees() {
                 );
             }
         }
+        distributions.push(KytFeeDistribution {
+            kyt_provider: provider,
+            amount,
+            result: result.map_err(|error| format!("{:?}", error)),
+        });
     }
+    distributions
 }
 
 pub fn timer() {
@@ -1141,6 +1380,7 @@ pub fn timer() {
                 submit_pending_requests().await;
                 finalize_requests().await;
                 reimburse_failed_kyt().await;
+                release_tainted_utxos().await;
             });
         }
         TaskType::RefreshFeePercentiles => {
@@ -1238,3 +1478,32 @@ pub fn estimate_fee(
         bitcoin_fee,
     }
 }
+
+/// Returns the smallest amount that a withdrawal of `amount` satoshi asking
+/// for `fee_rate` needs to cover the estimated fees of its transaction and
+/// still leave an output above the dust threshold.
+pub fn min_withdrawal_amount_for_fee_rate(
+    state: &state::CkBtcMinterState,
+    amount: u64,
+    fee_rate: &state::RetrieveBtcFeeRate,
+) -> u64 {
+    let median_fee_per_vbyte = state
+        .last_fee_per_vbyte
+        .get(50)
+        .copied()
+        .unwrap_or(MIN_RELAY_FEE_PER_VBYTE);
+    let fee_per_vbyte = resolve_fee_per_vbyte(
+        Some(fee_rate),
+        median_fee_per_vbyte,
+        &state.last_fee_per_vbyte,
+    );
+    let fee = estimate_fee(
+        &state.available_utxos,
+        Some(amount),
+        fee_per_vbyte,
+        state.kyt_fee,
+    );
+    fee.minter_fee
+        .saturating_add(fee.bitcoin_fee)
+        .saturating_add(MIN_OUTPUT_AMOUNT)
+}
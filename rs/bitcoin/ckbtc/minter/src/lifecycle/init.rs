@@ -1,7 +1,7 @@
 use crate::lifecycle::upgrade::UpgradeArgs;
 pub use crate::state::Mode;
 use crate::state::{replace_state, CkBtcMinterState};
-use candid::{CandidType, Deserialize};
+use candid::{CandidType, Deserialize, Principal};
 use ic_base_types::CanisterId;
 use ic_btc_interface::Network;
 use serde::Serialize;
@@ -84,6 +84,21 @@ pub struct InitArgs {
     /// NOTE: this field is optional for backward compatibility.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kyt_principal: Option<CanisterId>,
+
+    /// The number of blocks after which the minter replaces a withdrawal
+    /// transaction that is still unconfirmed with one paying a higher fee.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resubmission_delay_blocks: Option<u32>,
+
+    /// Caps the fee rate of replacement transactions, in percent above the fee
+    /// rate of the original transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_bump_percent: Option<u64>,
+
+    /// The principal that approves or rejects requests to release tainted
+    /// UTXOs back to their owners.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tainted_utxo_reviewer: Option<Principal>,
 }
 
 pub fn init(args: InitArgs) {
@@ -2,11 +2,29 @@ use crate::logs::P0;
 use crate::state::eventlog::{replay, Event};
 use crate::state::{replace_state, Mode};
 use crate::storage::{count_events, events, record_event};
-use candid::{CandidType, Deserialize};
+use candid::{CandidType, Deserialize, Principal};
 use ic_base_types::CanisterId;
 use ic_canister_log::log;
 use serde::Serialize;
 
+/// An update of an optional setting: either a new value, or a reset to the
+/// default behavior of the minter.
+#[derive(CandidType, Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SettingUpdate<T> {
+    Set(T),
+    Reset,
+}
+
+impl<T> SettingUpdate<T> {
+    /// Returns the value of the setting after the update.
+    pub fn into_setting(self) -> Option<T> {
+        match self {
+            SettingUpdate::Set(value) => Some(value),
+            SettingUpdate::Reset => None,
+        }
+    }
+}
+
 #[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
 pub struct UpgradeArgs {
     /// Minimum amount of bitcoin that can be retrieved.
@@ -32,6 +50,22 @@ pub struct UpgradeArgs {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kyt_principal: Option<CanisterId>,
+
+    /// The number of blocks after which the minter replaces a withdrawal
+    /// transaction that is still unconfirmed with one paying a higher fee.
+    /// `Reset` restores the default delay of one day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resubmission_delay_blocks: Option<SettingUpdate<u32>>,
+
+    /// Caps the fee rate of replacement transactions, in percent above the fee
+    /// rate of the original transaction. `Reset` removes the cap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_bump_percent: Option<SettingUpdate<u64>>,
+
+    /// The principal that approves or rejects requests to release tainted
+    /// UTXOs back to their owners.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tainted_utxo_reviewer: Option<Principal>,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArgs>) {
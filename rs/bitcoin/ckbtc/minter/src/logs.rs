@@ -4,4 +4,4 @@ use ic_canister_log::declare_log_buffer;
 declare_log_buffer!(name = P0, capacity = 1000);
 
 // Low-priority info messages.
-declare_log_buffer!(name = P1, capacity = 1000);
+declare_log_buffer!(name = P1, capacity = 1000, severity = Debug);
@@ -1,29 +1,37 @@
 use candid::candid_method;
 use candid::Principal;
-use ic_canister_log::export as export_logs;
+use ic_btc_interface::Utxo;
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk_macros::{init, post_upgrade, query, update};
 use ic_ckbtc_minter::dashboard::build_dashboard;
 use ic_ckbtc_minter::lifecycle::upgrade::UpgradeArgs;
 use ic_ckbtc_minter::lifecycle::{self, init::MinterArg};
 use ic_ckbtc_minter::metrics::encode_metrics;
-use ic_ckbtc_minter::queries::{EstimateFeeArg, RetrieveBtcStatusRequest, WithdrawalFee};
-use ic_ckbtc_minter::state::{read_state, RetrieveBtcStatus};
+use ic_ckbtc_minter::queries::{
+    EstimateFeeArg, EstimateRetrieveBtcFeeArg, ManagedUtxos, OwedKytFee, Pagination,
+    RetrieveBtcStatusRequest, SubmittedTransactions, WithdrawalFee,
+};
+use ic_ckbtc_minter::state::{read_state, RetrieveBtcStatus, TaintedUtxoReleaseStatus};
 use ic_ckbtc_minter::tasks::{schedule_now, TaskType};
 use ic_ckbtc_minter::updates::retrieve_btc::{
     RetrieveBtcArgs, RetrieveBtcError, RetrieveBtcOk, RetrieveBtcWithApprovalArgs,
     RetrieveBtcWithApprovalError,
 };
+use ic_ckbtc_minter::updates::tainted_utxo_release::{
+    RequestTaintedUtxoReleaseArgs, RequestTaintedUtxoReleaseError, ReviewTaintedUtxoReleaseArgs,
+    ReviewTaintedUtxoReleaseError,
+};
 use ic_ckbtc_minter::updates::{
     self,
+    deposit_notifications::SubscribeDepositNotificationsError,
     get_btc_address::GetBtcAddressArgs,
     update_balance::{UpdateBalanceArgs, UpdateBalanceError, UtxoStatus},
 };
-use ic_ckbtc_minter::MinterInfo;
 use ic_ckbtc_minter::{
-    state::eventlog::{Event, GetEventsArg},
-    storage, {Log, LogEntry, Priority},
+    state::eventlog::{Event, GetEventsArg, GetEventsResult},
+    storage,
 };
+use ic_ckbtc_minter::{DistributeOwedKytFeesError, KytFeeDistribution, MinterInfo};
 use icrc_ledger_types::icrc1::account::Account;
 
 #[candid_method(init)]
@@ -173,6 +181,53 @@ async fn update_balance(args: UpdateBalanceArgs) -> Result<Vec<UtxoStatus>, Upda
     check_postcondition(updates::update_balance::update_balance(args).await)
 }
 
+#[candid_method(update)]
+#[update]
+fn subscribe_deposit_notifications(
+    canister_id: Principal,
+) -> Result<(), SubscribeDepositNotificationsError> {
+    check_anonymous_caller();
+    check_postcondition(
+        updates::deposit_notifications::subscribe_deposit_notifications(canister_id),
+    )
+}
+
+#[candid_method(update)]
+#[update]
+fn unsubscribe_deposit_notifications() {
+    check_anonymous_caller();
+    updates::deposit_notifications::unsubscribe_deposit_notifications()
+}
+
+#[candid_method(update)]
+#[update]
+async fn request_tainted_utxo_release(
+    args: RequestTaintedUtxoReleaseArgs,
+) -> Result<(), RequestTaintedUtxoReleaseError> {
+    check_anonymous_caller();
+    check_postcondition(updates::tainted_utxo_release::request_tainted_utxo_release(args).await)
+}
+
+#[candid_method(update)]
+#[update]
+fn review_tainted_utxo_release(
+    args: ReviewTaintedUtxoReleaseArgs,
+) -> Result<(), ReviewTaintedUtxoReleaseError> {
+    check_postcondition(updates::tainted_utxo_release::review_tainted_utxo_release(
+        args,
+    ))
+}
+
+#[candid_method(query)]
+#[query]
+fn tainted_utxo_release_status(utxo: Utxo) -> Option<TaintedUtxoReleaseStatus> {
+    read_state(|s| {
+        s.tainted_utxo_releases
+            .get(&utxo)
+            .map(|release| release.status.clone())
+    })
+}
+
 #[candid_method(update)]
 #[update]
 async fn get_canister_status() -> ic_cdk::api::management_canister::main::CanisterStatusResponse {
@@ -186,6 +241,45 @@ async fn get_canister_status() -> ic_cdk::api::management_canister::main::Canist
     .0
 }
 
+#[candid_method(update)]
+#[update]
+async fn distribute_owed_kyt_fees() -> Result<Vec<KytFeeDistribution>, DistributeOwedKytFeesError> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        panic!("only controllers can distribute owed KYT fees")
+    }
+    let _guard = ic_ckbtc_minter::guard::DistributeKytFeeGuard::new()
+        .ok_or(DistributeOwedKytFeesError::AlreadyProcessing)?;
+    Ok(check_postcondition(
+        ic_ckbtc_minter::distribute_kyt_fees().await,
+    ))
+}
+
+#[candid_method(query)]
+#[query]
+fn get_owed_kyt_fees() -> Vec<OwedKytFee> {
+    read_state(|s| {
+        s.owed_kyt_amount
+            .iter()
+            .map(|(kyt_provider, amount)| OwedKytFee {
+                kyt_provider: *kyt_provider,
+                amount: *amount,
+            })
+            .collect()
+    })
+}
+
+#[candid_method(query)]
+#[query]
+fn list_managed_utxos(pagination: Pagination) -> ManagedUtxos {
+    read_state(|s| ic_ckbtc_minter::queries::list_managed_utxos(s, &pagination))
+}
+
+#[candid_method(query)]
+#[query]
+fn list_submitted_transactions(pagination: Pagination) -> SubmittedTransactions {
+    read_state(|s| ic_ckbtc_minter::queries::list_submitted_transactions(s, &pagination))
+}
+
 #[candid_method(query)]
 #[query]
 fn estimate_withdrawal_fee(arg: EstimateFeeArg) -> WithdrawalFee {
@@ -199,6 +293,24 @@ fn estimate_withdrawal_fee(arg: EstimateFeeArg) -> WithdrawalFee {
     })
 }
 
+#[candid_method(query)]
+#[query]
+fn estimate_retrieve_btc_fee(arg: EstimateRetrieveBtcFeeArg) -> WithdrawalFee {
+    read_state(|s| {
+        let fee_per_vbyte = ic_ckbtc_minter::resolve_fee_per_vbyte(
+            arg.fee_rate.as_ref(),
+            s.last_fee_per_vbyte[50],
+            &s.last_fee_per_vbyte,
+        );
+        ic_ckbtc_minter::estimate_fee(
+            &s.available_utxos,
+            Some(arg.amount),
+            fee_per_vbyte,
+            s.kyt_fee,
+        )
+    })
+}
+
 #[candid_method(query)]
 #[query]
 fn get_minter_info() -> MinterInfo {
@@ -242,49 +354,18 @@ fn http_request(req: HttpRequest) -> HttpResponse {
             .with_body_and_content_length(dashboard)
             .build()
     } else if req.path() == "/logs" {
-        use serde_json;
-        use std::str::FromStr;
-
-        let max_skip_timestamp = match req.raw_query_param("time") {
-            Some(arg) => match u64::from_str(arg) {
-                Ok(value) => value,
-                Err(_) => {
-                    return HttpResponseBuilder::bad_request()
-                        .with_body_and_content_length("failed to parse the 'time' parameter")
-                        .build()
-                }
-            },
-            None => 0,
-        };
+        use ic_canister_log::export_logs_http;
+        use ic_ckbtc_minter::logs::{P0, P1};
 
-        let mut entries: Log = Default::default();
-        for entry in export_logs(&ic_ckbtc_minter::logs::P0) {
-            entries.entries.push(LogEntry {
-                timestamp: entry.timestamp,
-                counter: entry.counter,
-                priority: Priority::P0,
-                file: entry.file.to_string(),
-                line: entry.line,
-                message: entry.message,
-            });
-        }
-        for entry in export_logs(&ic_ckbtc_minter::logs::P1) {
-            entries.entries.push(LogEntry {
-                timestamp: entry.timestamp,
-                counter: entry.counter,
-                priority: Priority::P1,
-                file: entry.file.to_string(),
-                line: entry.line,
-                message: entry.message,
-            });
+        match export_logs_http(&req.url, &[&P0, &P1]) {
+            Ok(body) => HttpResponseBuilder::ok()
+                .header("Content-Type", "application/json; charset=utf-8")
+                .with_body_and_content_length(body)
+                .build(),
+            Err(message) => HttpResponseBuilder::bad_request()
+                .with_body_and_content_length(message)
+                .build(),
         }
-        entries
-            .entries
-            .retain(|entry| entry.timestamp >= max_skip_timestamp);
-        HttpResponseBuilder::ok()
-            .header("Content-Type", "application/json; charset=utf-8")
-            .with_body_and_content_length(serde_json::to_string(&entries).unwrap_or_default())
-            .build()
     } else {
         HttpResponseBuilder::not_found().build()
     }
@@ -292,13 +373,16 @@ fn http_request(req: HttpRequest) -> HttpResponse {
 
 #[candid_method(query)]
 #[query]
-fn get_events(args: GetEventsArg) -> Vec<Event> {
+fn get_events(args: GetEventsArg) -> GetEventsResult {
     const MAX_EVENTS_PER_QUERY: usize = 2000;
 
-    storage::events()
-        .skip(args.start as usize)
-        .take(MAX_EVENTS_PER_QUERY.min(args.length as usize))
-        .collect()
+    GetEventsResult {
+        events: storage::entries()
+            .skip(args.start as usize)
+            .take(MAX_EVENTS_PER_QUERY.min(args.length as usize))
+            .collect(),
+        total_event_count: storage::count_events(),
+    }
 }
 
 #[cfg(feature = "self_check")]
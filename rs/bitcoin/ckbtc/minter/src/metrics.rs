@@ -4,6 +4,7 @@ use std::cell::Cell;
 thread_local! {
     pub static GET_UTXOS_CLIENT_CALLS: Cell<u64> = Cell::default();
     pub static GET_UTXOS_MINTER_CALLS: Cell<u64> = Cell::default();
+    pub static BLOCKED_RETRIEVE_BTC_ATTEMPTS: Cell<u64> = Cell::default();
 }
 
 pub fn encode_metrics(
@@ -142,6 +143,12 @@ pub fn encode_metrics(
             GET_UTXOS_MINTER_CALLS.with(|cell| cell.get()) as f64,
         )?;
 
+    metrics.encode_counter(
+        "ckbtc_minter_blocked_retrieve_btc_attempts",
+        BLOCKED_RETRIEVE_BTC_ATTEMPTS.with(|cell| cell.get()) as f64,
+        "Total number of retrieve_btc requests to blocked addresses since the last upgrade.",
+    )?;
+
     metrics.encode_gauge(
         "ckbtc_minter_btc_balance",
         state::read_state(|s| {
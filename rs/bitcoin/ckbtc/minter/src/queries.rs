@@ -1,6 +1,12 @@
-use candid::CandidType;
+use crate::state::{ChangeOutput, CkBtcMinterState, RetrieveBtcFeeRate, SubmittedBtcTransaction};
+use candid::{CandidType, Principal};
+use ic_btc_interface::{Txid, Utxo};
+use icrc_ledger_types::icrc1::account::Account;
 use serde::Deserialize;
 
+/// The maximum number of items that a paginated query returns.
+pub const MAX_ITEMS_PER_PAGE: u64 = 1_000;
+
 #[derive(CandidType, Deserialize)]
 pub struct RetrieveBtcStatusRequest {
     pub block_index: u64,
@@ -11,8 +17,123 @@ pub struct EstimateFeeArg {
     pub amount: Option<u64>,
 }
 
+#[derive(CandidType, Deserialize)]
+pub struct EstimateRetrieveBtcFeeArg {
+    pub amount: u64,
+    pub fee_rate: Option<RetrieveBtcFeeRate>,
+}
+
 #[derive(CandidType, Deserialize, Debug)]
 pub struct WithdrawalFee {
     pub minter_fee: u64,
     pub bitcoin_fee: u64,
 }
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OwedKytFee {
+    pub kyt_provider: Principal,
+    pub amount: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Pagination {
+    pub start: u64,
+    pub length: u64,
+}
+
+impl Pagination {
+    fn page<T>(&self, items: impl Iterator<Item = T>) -> Vec<T> {
+        items
+            .skip(self.start as usize)
+            .take(self.length.min(MAX_ITEMS_PER_PAGE) as usize)
+            .collect()
+    }
+}
+
+/// A UTXO that the minter holds on behalf of a ckBTC account.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManagedUtxo {
+    pub utxo: Utxo,
+    /// The account whose deposit address received the UTXO.
+    pub account: Account,
+    /// False if a submitted transaction spends the UTXO.
+    pub available: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManagedUtxos {
+    /// The total number of UTXOs, over all pages.
+    pub total: u64,
+    pub utxos: Vec<ManagedUtxo>,
+}
+
+/// A Bitcoin transaction that the minter sent and that is not finalized yet.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubmittedTransaction {
+    pub txid: Txid,
+    /// The burn block indices of the retrieve_btc requests that the transaction fulfills.
+    pub request_block_indices: Vec<u64>,
+    pub used_utxos: Vec<Utxo>,
+    pub change_output: Option<ChangeOutput>,
+    /// The IC time at which the minter submitted the transaction.
+    pub submitted_at: u64,
+    /// Fee per vbyte in millisatoshi.
+    pub fee_per_vbyte: Option<u64>,
+    /// The transaction that replaced this one because it got stuck, if any.
+    pub replaced_by: Option<Txid>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubmittedTransactions {
+    /// The total number of transactions, over all pages.
+    pub total: u64,
+    pub transactions: Vec<SubmittedTransaction>,
+}
+
+/// Returns a page of the UTXOs that the minter manages, ordered by account.
+pub fn list_managed_utxos(state: &CkBtcMinterState, pagination: &Pagination) -> ManagedUtxos {
+    let utxos = state
+        .utxos_state_addresses
+        .iter()
+        .flat_map(|(account, utxos)| {
+            utxos.iter().map(|utxo| ManagedUtxo {
+                utxo: utxo.clone(),
+                account: *account,
+                available: state.available_utxos.contains(utxo),
+            })
+        });
+    ManagedUtxos {
+        total: state
+            .utxos_state_addresses
+            .values()
+            .map(|utxos| utxos.len() as u64)
+            .sum(),
+        utxos: pagination.page(utxos),
+    }
+}
+
+/// Returns a page of the transactions waiting for finalization, followed by
+/// the stuck transactions that the minter replaced.
+pub fn list_submitted_transactions(
+    state: &CkBtcMinterState,
+    pagination: &Pagination,
+) -> SubmittedTransactions {
+    let to_submitted_transaction = |tx: &SubmittedBtcTransaction| SubmittedTransaction {
+        txid: tx.txid,
+        request_block_indices: tx.requests.iter().map(|req| req.block_index).collect(),
+        used_utxos: tx.used_utxos.clone(),
+        change_output: tx.change_output.clone(),
+        submitted_at: tx.submitted_at,
+        fee_per_vbyte: tx.fee_per_vbyte,
+        replaced_by: state.replacement_txid.get(&tx.txid).copied(),
+    };
+    let transactions = state
+        .submitted_transactions
+        .iter()
+        .chain(state.stuck_transactions.iter())
+        .map(to_submitted_transaction);
+    SubmittedTransactions {
+        total: (state.submitted_transactions.len() + state.stuck_transactions.len()) as u64,
+        transactions: pagination.page(transactions),
+    }
+}
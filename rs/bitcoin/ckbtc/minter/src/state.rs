@@ -71,6 +71,21 @@ pub struct RetrieveBtcRequest {
     #[serde(rename = "kyt_provider")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kyt_provider: Option<Principal>,
+    /// The fee rate that the user asked for.
+    /// If not set, the minter uses the median fee rate.
+    #[serde(rename = "fee_rate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_rate: Option<RetrieveBtcFeeRate>,
+}
+
+/// How to choose the fee rate of the transaction that fulfills a retrieve_btc request.
+#[derive(candid::CandidType, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetrieveBtcFeeRate {
+    /// Aim for a confirmation within the given number of blocks.
+    /// The minter picks a percentile of recent fee rates accordingly.
+    TargetConfirmationBlocks(u32),
+    /// An explicit fee rate, in satoshi per vbyte.
+    SatoshiPerVbyte(u64),
 }
 
 /// A transaction output storing the minter's change.
@@ -301,6 +316,17 @@ pub struct CkBtcMinterState {
     /// The principal of the KYT canister.
     pub kyt_principal: Option<CanisterId>,
 
+    /// The number of blocks after which we replace an unconfirmed transaction.
+    /// We use [crate::MIN_RESUBMISSION_DELAY] if not set.
+    pub resubmission_delay_blocks: Option<u32>,
+
+    /// The maximum fee rate increase of replacement transactions, in percent
+    /// of the fee rate of the original transaction. Unbounded if not set.
+    pub max_fee_bump_percent: Option<u64>,
+
+    /// The principal that reviews requests to release tainted UTXOs.
+    pub tainted_utxo_reviewer: Option<Principal>,
+
     /// The set of UTXOs unused in pending transactions.
     pub available_utxos: BTreeSet<Utxo>,
 
@@ -351,6 +377,42 @@ pub struct CkBtcMinterState {
     /// Map from burn block index to amount to reimburse because of
     /// KYT fees.
     pub reimbursement_map: BTreeMap<u64, ReimburseDepositTask>,
+
+    /// Maps account owners to the canister that the minter notifies when it
+    /// mints ckBTC for their deposits.
+    pub deposit_notification_subscriptions: BTreeMap<Principal, Principal>,
+
+    /// Requests to return quarantined UTXOs to their owners.
+    pub tainted_utxo_releases: BTreeMap<Utxo, TaintedUtxoRelease>,
+
+    /// The (caller, address) pairs of retrieve_btc attempts that the minter
+    /// rejected because the address is blocked. Only the first attempt of
+    /// each pair is recorded in the event log.
+    pub blocked_retrieve_btc_attempts: BTreeSet<(Principal, String)>,
+}
+
+/// The status of a request to return a tainted UTXO to its owner.
+#[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, serde::Deserialize, Serialize)]
+pub enum TaintedUtxoReleaseStatus {
+    /// The request waits for a decision of the reviewer.
+    PendingReview,
+    /// The reviewer approved the request, the minter will send the UTXO
+    /// value to the requested address.
+    Approved,
+    /// The reviewer rejected the request.
+    Rejected,
+    /// The minter sent a transaction returning the UTXO value. If the minter
+    /// replaces the transaction, the txid refers to the latest replacement.
+    Released { txid: Txid },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, Serialize)]
+pub struct TaintedUtxoRelease {
+    /// The account whose deposit address received the UTXO.
+    pub account: Account,
+    /// The address to send the UTXO value to.
+    pub address: BitcoinAddress,
+    pub status: TaintedUtxoReleaseStatus,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, Serialize)]
@@ -382,8 +444,14 @@ impl CkBtcMinterState {
             mode,
             kyt_fee,
             kyt_principal,
+            resubmission_delay_blocks,
+            max_fee_bump_percent,
+            tainted_utxo_reviewer,
         }: InitArgs,
     ) {
+        self.resubmission_delay_blocks = resubmission_delay_blocks;
+        self.max_fee_bump_percent = max_fee_bump_percent;
+        self.tainted_utxo_reviewer = tainted_utxo_reviewer;
         self.btc_network = btc_network.into();
         self.ecdsa_key_name = ecdsa_key_name;
         self.retrieve_btc_min_amount = retrieve_btc_min_amount;
@@ -408,8 +476,20 @@ impl CkBtcMinterState {
             mode,
             kyt_principal,
             kyt_fee,
+            resubmission_delay_blocks,
+            max_fee_bump_percent,
+            tainted_utxo_reviewer,
         }: UpgradeArgs,
     ) {
+        if let Some(tainted_utxo_reviewer) = tainted_utxo_reviewer {
+            self.tainted_utxo_reviewer = Some(tainted_utxo_reviewer);
+        }
+        if let Some(resubmission_delay_blocks) = resubmission_delay_blocks {
+            self.resubmission_delay_blocks = resubmission_delay_blocks.into_setting();
+        }
+        if let Some(max_fee_bump_percent) = max_fee_bump_percent {
+            self.max_fee_bump_percent = max_fee_bump_percent.into_setting();
+        }
         if let Some(retrieve_btc_min_amount) = retrieve_btc_min_amount {
             self.retrieve_btc_min_amount = retrieve_btc_min_amount;
         }
@@ -611,12 +691,22 @@ impl CkBtcMinterState {
     }
 
     /// Forms a batch of retrieve_btc requests that the minter can fulfill.
+    ///
+    /// All requests in a batch ask for the same fee rate, because they end up in the same
+    /// transaction.
     pub fn build_batch(&mut self, max_size: usize) -> Vec<RetrieveBtcRequest> {
         let available_utxos_value = self.available_utxos.iter().map(|u| u.value).sum::<u64>();
-        let mut batch = vec![];
+        let mut batch: Vec<RetrieveBtcRequest> = vec![];
         let mut tx_amount = 0;
         for req in std::mem::take(&mut self.pending_retrieve_btc_requests) {
-            if available_utxos_value < req.amount + tx_amount || batch.len() >= max_size {
+            let other_fee_rate = batch
+                .first()
+                .map(|first| first.fee_rate != req.fee_rate)
+                .unwrap_or(false);
+            if available_utxos_value < req.amount + tx_amount
+                || batch.len() >= max_size
+                || other_fee_rate
+            {
                 // Put this request back to the queue until we have enough liquid UTXOs.
                 self.pending_retrieve_btc_requests.push(req);
             } else {
@@ -692,6 +782,13 @@ impl CkBtcMinterState {
 
         for utxo in finalized_tx.used_utxos.iter() {
             self.forget_utxo(utxo);
+            // The transaction returned a tainted UTXO to its owner.
+            if self.quarantined_utxos.remove(utxo) {
+                self.set_tainted_utxo_release_status(
+                    utxo,
+                    TaintedUtxoReleaseStatus::Released { txid: *txid },
+                );
+            }
         }
         self.finalized_requests_count += finalized_tx.requests.len() as u64;
         for request in finalized_tx.requests {
@@ -774,6 +871,12 @@ impl CkBtcMinterState {
             .position(|tx| &tx.txid == old_txid)
             .expect("BUG: attempted to replace an unknown transaction");
 
+        for utxo in tx.used_utxos.iter() {
+            if let Some(release) = self.tainted_utxo_releases.get_mut(utxo) {
+                release.status = TaintedUtxoReleaseStatus::Released { txid: new_txid };
+            }
+        }
+
         std::mem::swap(&mut self.submitted_transactions[pos], &mut tx);
         // tx points to the old transaction now.
         debug_assert_eq!(&tx.txid, old_txid);
@@ -793,6 +896,23 @@ impl CkBtcMinterState {
         Some(last)
     }
 
+    /// Returns the fee rate of the first transaction in the chain of replacements
+    /// that ends with the given submitted transaction.
+    pub fn original_fee_per_vbyte(&self, tx: &SubmittedBtcTransaction) -> Option<u64> {
+        let mut fee_per_vbyte = tx.fee_per_vbyte;
+        let mut txid = &tx.txid;
+        while let Some(older_txid) = self.rev_replacement_txid.get(txid) {
+            fee_per_vbyte = self
+                .stuck_transactions
+                .iter()
+                .find(|stuck_tx| &stuck_tx.txid == older_txid)
+                .and_then(|stuck_tx| stuck_tx.fee_per_vbyte)
+                .or(fee_per_vbyte);
+            txid = older_txid;
+        }
+        fee_per_vbyte
+    }
+
     /// Removes a pending retrieve_btc request with the specified block index.
     fn remove_pending_request(&mut self, block_index: u64) -> Option<RetrieveBtcRequest> {
         match self
@@ -936,6 +1056,68 @@ impl CkBtcMinterState {
         }
     }
 
+    /// Records a request to return the given quarantined UTXO to the specified address.
+    fn request_tainted_utxo_release(
+        &mut self,
+        utxo: Utxo,
+        account: Account,
+        address: BitcoinAddress,
+    ) {
+        debug_assert!(self.quarantined_utxos.contains(&utxo));
+        self.tainted_utxo_releases.insert(
+            utxo,
+            TaintedUtxoRelease {
+                account,
+                address,
+                status: TaintedUtxoReleaseStatus::PendingReview,
+            },
+        );
+    }
+
+    /// Updates the status of the release request for the given UTXO.
+    /// Returns false if there is no such request.
+    fn set_tainted_utxo_release_status(
+        &mut self,
+        utxo: &Utxo,
+        status: TaintedUtxoReleaseStatus,
+    ) -> bool {
+        match self.tainted_utxo_releases.get_mut(utxo) {
+            Some(release) => {
+                release.status = status;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records the transaction that returns the value of a quarantined UTXO
+    /// to its owner. The UTXO stays in the quarantine until the transaction
+    /// is confirmed.
+    ///
+    /// Returns false if there is no release request for the UTXO.
+    fn release_tainted_utxo(&mut self, utxo: &Utxo, tx: SubmittedBtcTransaction) -> bool {
+        debug_assert!(tx.requests.is_empty());
+        if !self.set_tainted_utxo_release_status(
+            utxo,
+            TaintedUtxoReleaseStatus::Released { txid: tx.txid },
+        ) {
+            return false;
+        }
+        self.push_submitted_transaction(tx);
+        true
+    }
+
+    /// Returns the quarantined UTXO and its release request if the given
+    /// transaction returns a tainted UTXO to its owner.
+    pub fn find_tainted_utxo_release(
+        &self,
+        tx: &SubmittedBtcTransaction,
+    ) -> Option<(&Utxo, &TaintedUtxoRelease)> {
+        tx.used_utxos
+            .iter()
+            .find_map(|utxo| self.tainted_utxo_releases.get_key_value(utxo))
+    }
+
     /// Decreases the owed amount for the given provider by the amount.
     /// Returns an error if the distributed amount exceeds the amount owed to the provider.
     ///
@@ -1054,6 +1236,31 @@ impl CkBtcMinterState {
             other.kyt_principal,
             "kyt_principal does not match"
         );
+        ensure_eq!(
+            self.resubmission_delay_blocks,
+            other.resubmission_delay_blocks,
+            "resubmission_delay_blocks does not match"
+        );
+        ensure_eq!(
+            self.max_fee_bump_percent,
+            other.max_fee_bump_percent,
+            "max_fee_bump_percent does not match"
+        );
+        ensure_eq!(
+            self.tainted_utxo_reviewer,
+            other.tainted_utxo_reviewer,
+            "tainted_utxo_reviewer does not match"
+        );
+        ensure_eq!(
+            self.tainted_utxo_releases,
+            other.tainted_utxo_releases,
+            "tainted_utxo_releases do not match"
+        );
+        ensure_eq!(
+            self.blocked_retrieve_btc_attempts,
+            other.blocked_retrieve_btc_attempts,
+            "blocked_retrieve_btc_attempts do not match"
+        );
 
         let my_txs = as_sorted_vec(self.submitted_transactions.iter().cloned(), |tx| tx.txid);
         let other_txs = as_sorted_vec(other.submitted_transactions.iter().cloned(), |tx| tx.txid);
@@ -1090,6 +1297,12 @@ impl CkBtcMinterState {
             "rev_replacement_txid maps do not match"
         );
 
+        ensure_eq!(
+            self.deposit_notification_subscriptions,
+            other.deposit_notification_subscriptions,
+            "deposit_notification_subscriptions do not match"
+        );
+
         Ok(())
     }
 }
@@ -1125,6 +1338,9 @@ impl From<InitArgs> for CkBtcMinterState {
             tokens_burned: 0,
             ledger_id: args.ledger_id,
             kyt_principal: args.kyt_principal,
+            resubmission_delay_blocks: args.resubmission_delay_blocks,
+            max_fee_bump_percent: args.max_fee_bump_percent,
+            tainted_utxo_reviewer: args.tainted_utxo_reviewer,
             available_utxos: Default::default(),
             outpoint_account: Default::default(),
             utxos_state_addresses: Default::default(),
@@ -1141,6 +1357,9 @@ impl From<InitArgs> for CkBtcMinterState {
             ignored_utxos: Default::default(),
             quarantined_utxos: Default::default(),
             reimbursement_map: Default::default(),
+            deposit_notification_subscriptions: Default::default(),
+            tainted_utxo_releases: Default::default(),
+            blocked_retrieve_btc_attempts: Default::default(),
         }
     }
 }
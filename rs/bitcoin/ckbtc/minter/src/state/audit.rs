@@ -2,8 +2,9 @@
 
 use super::{
     eventlog::Event, CkBtcMinterState, FinalizedBtcRetrieval, FinalizedStatus, RetrieveBtcRequest,
-    SubmittedBtcTransaction, UtxoCheckStatus,
+    SubmittedBtcTransaction, TaintedUtxoReleaseStatus, UtxoCheckStatus,
 };
+use crate::address::BitcoinAddress;
 use crate::state::ReimburseDepositTask;
 use crate::storage::record_event;
 use crate::ReimbursementReason;
@@ -138,6 +139,26 @@ pub fn retrieve_btc_kyt_failed(
     *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) += state.kyt_fee;
 }
 
+/// Records the first rejected attempt by `owner` to retrieve BTC to the
+/// blocked `address`. Later attempts for the same pair are not recorded.
+pub fn retrieve_btc_address_blocked(
+    state: &mut CkBtcMinterState,
+    owner: Principal,
+    address: String,
+    amount: u64,
+) {
+    if state
+        .blocked_retrieve_btc_attempts
+        .insert((owner, address.clone()))
+    {
+        record_event(&Event::RetrieveBtcAddressBlocked {
+            owner,
+            address,
+            amount,
+        });
+    }
+}
+
 pub fn schedule_deposit_reimbursement(
     state: &mut CkBtcMinterState,
     account: Account,
@@ -161,6 +182,77 @@ pub fn schedule_deposit_reimbursement(
     );
 }
 
+pub fn set_deposit_notification_subscription(
+    state: &mut CkBtcMinterState,
+    owner: Principal,
+    canister_id: Option<Principal>,
+) {
+    record_event(&Event::SetDepositNotificationSubscription { owner, canister_id });
+    match canister_id {
+        Some(canister_id) => {
+            state
+                .deposit_notification_subscriptions
+                .insert(owner, canister_id);
+        }
+        None => {
+            state.deposit_notification_subscriptions.remove(&owner);
+        }
+    }
+}
+
+pub fn request_tainted_utxo_release(
+    state: &mut CkBtcMinterState,
+    utxo: Utxo,
+    account: Account,
+    address: BitcoinAddress,
+) {
+    record_event(&Event::RequestedTaintedUtxoRelease {
+        utxo: utxo.clone(),
+        account,
+        address: address.clone(),
+    });
+    state.request_tainted_utxo_release(utxo, account, address);
+}
+
+pub fn review_tainted_utxo_release(
+    state: &mut CkBtcMinterState,
+    utxo: Utxo,
+    reviewer: Principal,
+    approved: bool,
+) {
+    record_event(&Event::ReviewedTaintedUtxoRelease {
+        utxo: utxo.clone(),
+        reviewer,
+        approved,
+    });
+    let status = if approved {
+        TaintedUtxoReleaseStatus::Approved
+    } else {
+        TaintedUtxoReleaseStatus::Rejected
+    };
+    assert!(state.set_tainted_utxo_release_status(&utxo, status));
+}
+
+pub fn released_tainted_utxo(
+    state: &mut CkBtcMinterState,
+    utxo: Utxo,
+    tx: SubmittedBtcTransaction,
+) {
+    record_event(&Event::ReleasedTaintedUtxo {
+        utxo: utxo.clone(),
+        txid: tx.txid,
+        change_output: tx
+            .change_output
+            .clone()
+            .expect("bug: all release transactions must have the change output"),
+        submitted_at: tx.submitted_at,
+        fee_per_vbyte: tx
+            .fee_per_vbyte
+            .expect("bug: all release transactions must have the fee"),
+    });
+    assert!(state.release_tainted_utxo(&utxo, tx));
+}
+
 pub fn reimbursed_failed_deposit(
     state: &mut CkBtcMinterState,
     burn_block_index: u64,
@@ -1,10 +1,11 @@
+use crate::address::BitcoinAddress;
 use crate::lifecycle::init::InitArgs;
 use crate::lifecycle::upgrade::UpgradeArgs;
 use crate::state::{
     ChangeOutput, CkBtcMinterState, FinalizedBtcRetrieval, FinalizedStatus, Overdraft,
     RetrieveBtcRequest, SubmittedBtcTransaction, UtxoCheckStatus,
 };
-use crate::state::{ReimburseDepositTask, ReimbursementReason};
+use crate::state::{ReimburseDepositTask, ReimbursementReason, TaintedUtxoReleaseStatus};
 use candid::Principal;
 use ic_btc_interface::{Txid, Utxo};
 use icrc_ledger_types::icrc1::account::Account;
@@ -16,6 +17,25 @@ pub struct GetEventsArg {
     pub length: u64,
 }
 
+/// An entry of the minter event log.
+#[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventLogEntry {
+    /// The IC time (in nanoseconds since the epoch) at which the minter
+    /// recorded the event. Events recorded before the minter started
+    /// timestamping its events have no timestamp.
+    #[serde(rename = "timestamp")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(rename = "payload")]
+    pub payload: Event,
+}
+
+#[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct GetEventsResult {
+    pub events: Vec<EventLogEntry>,
+    pub total_event_count: u64,
+}
+
 #[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Event {
     /// Indicates the minter initialization with the specified arguments.  Must be
@@ -177,6 +197,67 @@ pub enum Event {
         /// The mint block on the ledger.
         mint_block_index: u64,
     },
+
+    /// Indicates that the minter notifies the specified canister about the
+    /// deposits of the owner, or stops doing so if the canister is not set.
+    #[serde(rename = "set_deposit_notification_subscription")]
+    SetDepositNotificationSubscription {
+        /// The owner of the accounts receiving the deposits.
+        owner: Principal,
+        /// The canister to notify.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        canister_id: Option<Principal>,
+    },
+
+    /// Indicates that the owner of a quarantined UTXO asked the minter to send
+    /// the UTXO value to the specified address.
+    #[serde(rename = "requested_tainted_utxo_release")]
+    RequestedTaintedUtxoRelease {
+        /// The quarantined UTXO.
+        utxo: Utxo,
+        /// The account whose deposit address received the UTXO.
+        account: Account,
+        /// The destination of the released BTC.
+        address: BitcoinAddress,
+    },
+
+    /// Indicates the decision of the reviewer on a tainted UTXO release request.
+    #[serde(rename = "reviewed_tainted_utxo_release")]
+    ReviewedTaintedUtxoRelease {
+        utxo: Utxo,
+        /// The principal that made the decision.
+        reviewer: Principal,
+        approved: bool,
+    },
+
+    /// Indicates that the minter sent a transaction returning the value of a
+    /// tainted UTXO to the address requested by its owner.
+    #[serde(rename = "released_tainted_utxo")]
+    ReleasedTaintedUtxo {
+        utxo: Utxo,
+        /// The Txid of the Bitcoin transaction.
+        txid: Txid,
+        /// The output with the minter's change.
+        change_output: ChangeOutput,
+        /// The IC time at which the minter submitted the transaction.
+        submitted_at: u64,
+        /// The fee per vbyte (in millisatoshi) that we used for the transaction.
+        #[serde(rename = "fee")]
+        fee_per_vbyte: u64,
+    },
+
+    /// Indicates that the minter rejected a retrieve_btc request because the
+    /// destination address is on the blocklist. Only the first rejected
+    /// attempt of each (owner, address) pair is recorded.
+    #[serde(rename = "retrieve_btc_address_blocked")]
+    RetrieveBtcAddressBlocked {
+        /// The principal that attempted the withdrawal.
+        owner: Principal,
+        /// The blocked destination address.
+        address: String,
+        /// The requested amount, in satoshi.
+        amount: u64,
+    },
 }
 
 #[derive(Debug)]
@@ -349,6 +430,69 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
             } => {
                 state.reimbursement_map.remove(&burn_block_index);
             }
+            Event::SetDepositNotificationSubscription { owner, canister_id } => match canister_id {
+                Some(canister_id) => {
+                    state
+                        .deposit_notification_subscriptions
+                        .insert(owner, canister_id);
+                }
+                None => {
+                    state.deposit_notification_subscriptions.remove(&owner);
+                }
+            },
+            Event::RequestedTaintedUtxoRelease {
+                utxo,
+                account,
+                address,
+            } => {
+                if !state.quarantined_utxos.contains(&utxo) {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Attempted to release a UTXO that is not quarantined: {:?}",
+                        utxo
+                    )));
+                }
+                state.request_tainted_utxo_release(utxo, account, address);
+            }
+            Event::ReviewedTaintedUtxoRelease { utxo, approved, .. } => {
+                let status = if approved {
+                    TaintedUtxoReleaseStatus::Approved
+                } else {
+                    TaintedUtxoReleaseStatus::Rejected
+                };
+                if !state.set_tainted_utxo_release_status(&utxo, status) {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Attempted to review a non-existent release request for UTXO {:?}",
+                        utxo
+                    )));
+                }
+            }
+            Event::ReleasedTaintedUtxo {
+                utxo,
+                txid,
+                change_output,
+                submitted_at,
+                fee_per_vbyte,
+            } => {
+                if !state.release_tainted_utxo(
+                    &utxo,
+                    SubmittedBtcTransaction {
+                        requests: vec![],
+                        txid,
+                        used_utxos: vec![utxo.clone()],
+                        change_output: Some(change_output),
+                        submitted_at,
+                        fee_per_vbyte: Some(fee_per_vbyte),
+                    },
+                ) {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Attempted to release UTXO {:?} without a release request",
+                        utxo
+                    )));
+                }
+            }
+            Event::RetrieveBtcAddressBlocked { owner, address, .. } => {
+                state.blocked_retrieve_btc_attempts.insert((owner, address));
+            }
         }
     }
 
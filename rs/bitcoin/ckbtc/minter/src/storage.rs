@@ -1,4 +1,4 @@
-use crate::state::eventlog::Event;
+use crate::state::eventlog::{Event, EventLogEntry};
 use ic_stable_structures::{
     log::{Log as StableLog, NoSuchEntry},
     memory_manager::{MemoryId, MemoryManager, VirtualMemory},
@@ -35,58 +35,72 @@ pub struct EventIterator {
 }
 
 impl Iterator for EventIterator {
-    type Item = Event;
+    type Item = EventLogEntry;
 
-    fn next(&mut self) -> Option<Event> {
+    fn next(&mut self) -> Option<EventLogEntry> {
         EVENTS.with(|events| {
             let events = events.borrow();
 
             match events.read_entry(self.pos, &mut self.buf) {
                 Ok(()) => {
                     self.pos = self.pos.saturating_add(1);
-                    Some(decode_event(&self.buf))
+                    Some(decode_entry(&self.buf))
                 }
                 Err(NoSuchEntry) => None,
             }
         })
     }
 
-    fn nth(&mut self, n: usize) -> Option<Event> {
+    fn nth(&mut self, n: usize) -> Option<EventLogEntry> {
         self.pos = self.pos.saturating_add(n as u64);
         self.next()
     }
 }
 
-/// Encodes an event into a byte array.
-fn encode_event(event: &Event) -> Vec<u8> {
+/// Encodes an event log entry into a byte array.
+pub(crate) fn encode_entry(entry: &EventLogEntry) -> Vec<u8> {
     let mut buf = Vec::new();
-    ciborium::ser::into_writer(event, &mut buf).expect("failed to encode a minter event");
+    ciborium::ser::into_writer(entry, &mut buf).expect("failed to encode a minter event");
     buf
 }
 
+/// Decodes an event log entry. Entries recorded before the minter started
+/// timestamping its events consist of a bare event.
+///
 /// # Panics
 ///
 /// This function panics if the event decoding fails.
-fn decode_event(buf: &[u8]) -> Event {
-    ciborium::de::from_reader(buf).expect("failed to decode a minter event")
+pub(crate) fn decode_entry(buf: &[u8]) -> EventLogEntry {
+    ciborium::de::from_reader(buf).unwrap_or_else(|_| EventLogEntry {
+        timestamp: None,
+        payload: ciborium::de::from_reader(buf).expect("failed to decode a minter event"),
+    })
 }
 
-/// Returns an iterator over all minter events.
-pub fn events() -> impl Iterator<Item = Event> {
+/// Returns an iterator over all entries of the event log.
+pub fn entries() -> impl Iterator<Item = EventLogEntry> {
     EventIterator {
         buf: vec![],
         pos: 0,
     }
 }
 
+/// Returns an iterator over all minter events.
+pub fn events() -> impl Iterator<Item = Event> {
+    entries().map(|entry| entry.payload)
+}
+
 /// Returns the current number of events in the log.
 pub fn count_events() -> u64 {
     EVENTS.with(|events| events.borrow().len())
 }
 
-/// Records a new minter event.
+/// Records a new minter event, timestamped with the current IC time.
 pub fn record_event(event: &Event) {
-    let bytes = encode_event(event);
+    let bytes = encode_entry(&EventLogEntry {
+        timestamp: Some(ic_cdk::api::time()),
+        payload: event.clone(),
+    });
     EVENTS.with(|events| {
         events
             .borrow()
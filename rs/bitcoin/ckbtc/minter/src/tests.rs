@@ -1,13 +1,16 @@
 use crate::MINTER_FEE_CONSTANT;
 use crate::{
-    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign, greedy,
-    signature::EncodedSignature, tx, BuildTxError,
+    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign,
+    fee_percentile_for_confirmation_target, greedy, min_withdrawal_amount_for_fee_rate,
+    replacement_fee_per_vbyte, resolve_fee_per_vbyte, signature::EncodedSignature, tx,
+    BuildTxError, MIN_RELAY_FEE_PER_VBYTE,
 };
 use crate::{
     lifecycle::init::InitArgs,
+    lifecycle::upgrade::{SettingUpdate, UpgradeArgs},
     state::{
-        ChangeOutput, CkBtcMinterState, Mode, RetrieveBtcRequest, RetrieveBtcStatus,
-        SubmittedBtcTransaction,
+        ChangeOutput, CkBtcMinterState, Mode, RetrieveBtcFeeRate, RetrieveBtcRequest,
+        RetrieveBtcStatus, SubmittedBtcTransaction, TaintedUtxoReleaseStatus,
     },
 };
 use bitcoin::network::constants::Network as BtcNetwork;
@@ -308,6 +311,526 @@ fn blocklist_is_sorted() {
     }
 }
 
+#[test]
+fn should_block_blocked_addresses() {
+    use crate::blocklist::{is_blocked, BTC_ADDRESS_BLOCKLIST};
+    for address in BTC_ADDRESS_BLOCKLIST {
+        let parsed = BitcoinAddress::parse(address, Network::Mainnet)
+            .unwrap_or_else(|e| panic!("failed to parse blocked address {}: {}", address, e));
+        assert_eq!(&parsed.display(Network::Mainnet), address);
+        assert!(is_blocked(&parsed), "{} is not blocked", address);
+    }
+
+    // The spelling of the address does not matter.
+    let upper_case = BitcoinAddress::parse(
+        "BC1QX3E2AXJ3WSFN0NDTVLWMKGHMMGM4583NQG8NGK",
+        Network::Mainnet,
+    )
+    .unwrap();
+    assert!(is_blocked(&upper_case));
+
+    let not_blocked = BitcoinAddress::parse(
+        "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+        Network::Mainnet,
+    )
+    .unwrap();
+    assert!(!is_blocked(&not_blocked));
+}
+
+#[test]
+fn should_resolve_requested_fee_rates() {
+    let percentiles: Vec<u64> = (0..100).map(|p| 1_000 * p).collect();
+    let median = percentiles[50];
+
+    assert_eq!(resolve_fee_per_vbyte(None, median, &percentiles), median);
+    assert_eq!(
+        resolve_fee_per_vbyte(
+            Some(&RetrieveBtcFeeRate::SatoshiPerVbyte(7)),
+            median,
+            &percentiles
+        ),
+        7_000
+    );
+    for blocks in [1, 2, 6, 24, 144] {
+        assert_eq!(
+            resolve_fee_per_vbyte(
+                Some(&RetrieveBtcFeeRate::TargetConfirmationBlocks(blocks)),
+                median,
+                &percentiles
+            ),
+            percentiles[fee_percentile_for_confirmation_target(blocks)]
+        );
+    }
+    // Shorter targets never ask for lower fees.
+    assert!(fee_percentile_for_confirmation_target(1) > fee_percentile_for_confirmation_target(3));
+    assert!(
+        fee_percentile_for_confirmation_target(6) > fee_percentile_for_confirmation_target(144)
+    );
+
+    // Without fee percentiles (e.g., on regtest), targets fall back to the median.
+    assert_eq!(
+        resolve_fee_per_vbyte(
+            Some(&RetrieveBtcFeeRate::TargetConfirmationBlocks(1)),
+            median,
+            &[]
+        ),
+        median
+    );
+    // Targets never resolve to a fee below the minimum relay fee.
+    assert_eq!(
+        resolve_fee_per_vbyte(
+            Some(&RetrieveBtcFeeRate::TargetConfirmationBlocks(144)),
+            median,
+            &vec![0; 100]
+        ),
+        MIN_RELAY_FEE_PER_VBYTE
+    );
+}
+
+#[test]
+fn should_cap_replacement_fees() {
+    // Without a previous fee, the requested fee is used as is.
+    assert_eq!(replacement_fee_per_vbyte(5_000, None, None), Some(5_000));
+    // The replacement must pay at least the min relay fee more than the original.
+    assert_eq!(
+        replacement_fee_per_vbyte(5_000, Some(5_000), None),
+        Some(5_000 + MIN_RELAY_FEE_PER_VBYTE)
+    );
+    assert_eq!(
+        replacement_fee_per_vbyte(20_000, Some(5_000), None),
+        Some(20_000)
+    );
+    // The cap limits how much the fee can grow.
+    assert_eq!(
+        replacement_fee_per_vbyte(20_000, Some(5_000), Some(10_000)),
+        Some(10_000)
+    );
+    assert_eq!(
+        replacement_fee_per_vbyte(20_000, Some(5_000), Some(6_000)),
+        Some(6_000)
+    );
+    // Once the cap does not leave room for another bump, we stop resubmitting.
+    assert_eq!(
+        replacement_fee_per_vbyte(20_000, Some(5_500), Some(6_000)),
+        None
+    );
+}
+
+#[test]
+fn should_decode_event_log_entries_with_and_without_timestamp() {
+    use crate::state::eventlog::{Event, EventLogEntry};
+    use crate::storage::{decode_entry, encode_entry};
+
+    let event = Event::IgnoredUtxo {
+        utxo: dummy_utxo_from_value(1_000),
+    };
+
+    let entry = EventLogEntry {
+        timestamp: Some(1_700_000_000_000_000_000),
+        payload: event.clone(),
+    };
+    assert_eq!(decode_entry(&encode_entry(&entry)), entry);
+
+    // Events recorded before the minter started timestamping its events.
+    let mut legacy = vec![];
+    ciborium::ser::into_writer(&event, &mut legacy).unwrap();
+    assert_eq!(
+        decode_entry(&legacy),
+        EventLogEntry {
+            timestamp: None,
+            payload: event,
+        }
+    );
+}
+
+#[test]
+fn should_batch_requests_with_the_same_fee_rate() {
+    let mut state = CkBtcMinterState::from(InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 5_000u64,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
+    });
+    state.add_utxos(
+        Account {
+            owner: Principal::anonymous(),
+            subaccount: None,
+        },
+        vec![dummy_utxo_from_value(1_000_000_000)],
+    );
+
+    let fee_rates = [
+        None,
+        Some(RetrieveBtcFeeRate::SatoshiPerVbyte(20)),
+        None,
+        Some(RetrieveBtcFeeRate::SatoshiPerVbyte(20)),
+    ];
+    for (block_index, fee_rate) in fee_rates.into_iter().enumerate() {
+        state.push_back_pending_request(RetrieveBtcRequest {
+            amount: 100_000,
+            address: BitcoinAddress::P2wpkhV0([1; 20]),
+            block_index: block_index as u64,
+            received_at: block_index as u64,
+            kyt_provider: None,
+            fee_rate,
+        });
+    }
+
+    let first_batch = state.build_batch(10);
+    assert_eq!(
+        first_batch
+            .iter()
+            .map(|req| req.block_index)
+            .collect::<Vec<_>>(),
+        vec![0, 2]
+    );
+    let second_batch = state.build_batch(10);
+    assert_eq!(
+        second_batch
+            .iter()
+            .map(|req| req.block_index)
+            .collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert!(second_batch
+        .iter()
+        .all(|req| req.fee_rate == Some(RetrieveBtcFeeRate::SatoshiPerVbyte(20))));
+    state.check_invariants().expect("invariant check failed");
+}
+
+#[test]
+fn should_require_withdrawals_to_cover_the_requested_fee_rate() {
+    let mut state = CkBtcMinterState::from(InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 5_000u64,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
+    });
+    state.add_utxos(
+        Account {
+            owner: Principal::anonymous(),
+            subaccount: None,
+        },
+        vec![dummy_utxo_from_value(1_000_000_000)],
+    );
+    let amount = 100_000;
+
+    let fee = estimate_fee(&state.available_utxos, Some(amount), 10_000, state.kyt_fee);
+    let min_amount = min_withdrawal_amount_for_fee_rate(
+        &state,
+        amount,
+        &RetrieveBtcFeeRate::SatoshiPerVbyte(10),
+    );
+    assert_eq!(min_amount, fee.minter_fee + fee.bitcoin_fee + 546);
+    assert!(amount >= min_amount);
+
+    // The same amount does not cover the fees at the highest fee rate.
+    let min_amount = min_withdrawal_amount_for_fee_rate(
+        &state,
+        amount,
+        &RetrieveBtcFeeRate::SatoshiPerVbyte(10_000),
+    );
+    assert!(amount < min_amount, "{} >= {}", amount, min_amount);
+}
+
+#[test]
+fn should_replay_fee_bump_settings_upgrades() {
+    use crate::state::eventlog::{replay, Event};
+
+    let init_args = InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 5_000u64,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+        resubmission_delay_blocks: Some(6),
+        max_fee_bump_percent: Some(50),
+        tainted_utxo_reviewer: None,
+    };
+    let settings = |events: Vec<Event>| {
+        let state = replay(events.into_iter()).expect("failed to replay the events");
+        (state.resubmission_delay_blocks, state.max_fee_bump_percent)
+    };
+
+    assert_eq!(
+        settings(vec![Event::Init(init_args.clone())]),
+        (Some(6), Some(50))
+    );
+    // Upgrades without these settings keep them.
+    assert_eq!(
+        settings(vec![
+            Event::Init(init_args.clone()),
+            Event::Upgrade(UpgradeArgs::default()),
+        ]),
+        (Some(6), Some(50))
+    );
+    assert_eq!(
+        settings(vec![
+            Event::Init(init_args.clone()),
+            Event::Upgrade(UpgradeArgs {
+                resubmission_delay_blocks: Some(SettingUpdate::Set(144)),
+                max_fee_bump_percent: Some(SettingUpdate::Set(100)),
+                ..UpgradeArgs::default()
+            }),
+        ]),
+        (Some(144), Some(100))
+    );
+    assert_eq!(
+        settings(vec![
+            Event::Init(init_args.clone()),
+            Event::Upgrade(UpgradeArgs {
+                resubmission_delay_blocks: Some(SettingUpdate::Reset),
+                ..UpgradeArgs::default()
+            }),
+            Event::Upgrade(UpgradeArgs {
+                max_fee_bump_percent: Some(SettingUpdate::Reset),
+                ..UpgradeArgs::default()
+            }),
+        ]),
+        (None, None)
+    );
+}
+
+#[test]
+fn should_record_blocked_retrieve_btc_attempt_once_per_caller_and_address() {
+    use crate::state::audit::retrieve_btc_address_blocked;
+    use crate::state::eventlog::{replay, Event};
+
+    let init_args = InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 5_000u64,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
+    };
+    let mut state = replay(vec![Event::Init(init_args.clone())].into_iter()).unwrap();
+    let (alice, bob) = (
+        Principal::management_canister(),
+        Principal::from_slice(&[1; 29]),
+    );
+    let address = "bc1qblocked".to_string();
+
+    let events_before = crate::storage::count_events();
+    retrieve_btc_address_blocked(&mut state, alice, address.clone(), 10_000);
+    retrieve_btc_address_blocked(&mut state, alice, address.clone(), 20_000);
+    retrieve_btc_address_blocked(&mut state, bob, address.clone(), 10_000);
+    assert_eq!(crate::storage::count_events(), events_before + 2);
+
+    let replayed = replay(
+        [
+            Event::Init(init_args),
+            Event::RetrieveBtcAddressBlocked {
+                owner: alice,
+                address: address.clone(),
+                amount: 10_000,
+            },
+            Event::RetrieveBtcAddressBlocked {
+                owner: bob,
+                address,
+                amount: 10_000,
+            },
+        ]
+        .into_iter(),
+    )
+    .unwrap();
+    assert_eq!(
+        replayed.blocked_retrieve_btc_attempts,
+        state.blocked_retrieve_btc_attempts
+    );
+}
+
+#[test]
+fn should_replay_tainted_utxo_releases() {
+    use crate::state::eventlog::{replay, Event};
+
+    let init_args = InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 5_000u64,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: Some(Principal::management_canister()),
+    };
+    let utxo = Utxo {
+        outpoint: OutPoint {
+            txid: [1; 32].into(),
+            vout: 0,
+        },
+        value: 100_000,
+        height: 10,
+    };
+    let account = Account {
+        owner: Principal::anonymous(),
+        subaccount: None,
+    };
+    let address = BitcoinAddress::P2wpkhV0([2; 20]);
+    let (txid, replacement_txid) = (Txid::from([3; 32]), Txid::from([4; 32]));
+
+    let events = vec![
+        Event::Init(init_args),
+        Event::CheckedUtxo {
+            utxo: utxo.clone(),
+            uuid: "uuid".to_string(),
+            clean: false,
+            kyt_provider: Some(Principal::anonymous()),
+        },
+        Event::RequestedTaintedUtxoRelease {
+            utxo: utxo.clone(),
+            account,
+            address: address.clone(),
+        },
+        Event::ReviewedTaintedUtxoRelease {
+            utxo: utxo.clone(),
+            reviewer: Principal::management_canister(),
+            approved: true,
+        },
+        Event::ReleasedTaintedUtxo {
+            utxo: utxo.clone(),
+            txid,
+            change_output: ChangeOutput {
+                vout: 1,
+                value: 312,
+            },
+            submitted_at: 0,
+            fee_per_vbyte: 10_000,
+        },
+        Event::ReplacedBtcTransaction {
+            old_txid: txid,
+            new_txid: replacement_txid,
+            change_output: ChangeOutput {
+                vout: 1,
+                value: 312,
+            },
+            submitted_at: 1,
+            fee_per_vbyte: 20_000,
+        },
+        Event::ConfirmedBtcTransaction {
+            txid: replacement_txid,
+        },
+    ];
+    let replay_prefix =
+        |n: usize| replay(events.iter().take(n).cloned()).expect("failed to replay the events");
+    let release_status = |state: &CkBtcMinterState| {
+        state
+            .tainted_utxo_releases
+            .get(&utxo)
+            .map(|release| release.status.clone())
+    };
+
+    let state = replay_prefix(2);
+    assert!(state.quarantined_utxos.contains(&utxo));
+    assert_eq!(release_status(&state), None);
+
+    let state = replay_prefix(3);
+    let release = state.tainted_utxo_releases.get(&utxo).unwrap();
+    assert_eq!(release.account, account);
+    assert_eq!(release.address, address);
+    assert_eq!(release.status, TaintedUtxoReleaseStatus::PendingReview);
+
+    let state = replay_prefix(4);
+    assert_eq!(
+        release_status(&state),
+        Some(TaintedUtxoReleaseStatus::Approved)
+    );
+
+    // The minter tracks the release transaction until it is confirmed.
+    let state = replay_prefix(5);
+    assert_eq!(
+        release_status(&state),
+        Some(TaintedUtxoReleaseStatus::Released { txid })
+    );
+    assert_eq!(state.submitted_transactions.len(), 1);
+    assert_eq!(state.submitted_transactions[0].txid, txid);
+    assert_eq!(
+        state.submitted_transactions[0].used_utxos,
+        vec![utxo.clone()]
+    );
+    assert!(state.submitted_transactions[0].requests.is_empty());
+    assert!(state.quarantined_utxos.contains(&utxo));
+
+    let state = replay_prefix(6);
+    assert_eq!(
+        release_status(&state),
+        Some(TaintedUtxoReleaseStatus::Released {
+            txid: replacement_txid
+        })
+    );
+    assert_eq!(state.stuck_transactions.len(), 1);
+    assert_eq!(state.submitted_transactions[0].txid, replacement_txid);
+
+    let state = replay_prefix(7);
+    assert!(state.submitted_transactions.is_empty());
+    assert!(state.stuck_transactions.is_empty());
+    assert!(!state.quarantined_utxos.contains(&utxo));
+    assert_eq!(
+        release_status(&state),
+        Some(TaintedUtxoReleaseStatus::Released {
+            txid: replacement_txid
+        })
+    );
+    assert_eq!(state.check_invariants(), Ok(()));
+
+    // A release request requires a quarantined UTXO.
+    assert!(replay(
+        events
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, event)| event.clone())
+    )
+    .is_err());
+    // The minter cannot review or release UTXOs without a release request.
+    assert!(replay(
+        events
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 2)
+            .map(|(_, event)| event.clone())
+    )
+    .is_err());
+    assert!(replay(
+        events
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 2 && *i != 3)
+            .map(|(_, event)| event.clone())
+    )
+    .is_err());
+}
+
 fn arb_amount() -> impl Strategy<Value = Satoshi> {
     1..10_000_000_000u64
 }
@@ -405,6 +928,7 @@ fn arb_retrieve_btc_requests(
                 block_index,
                 received_at,
                 kyt_provider: provider.map(|id| Principal::from(CanisterId::from_u64(id).get())),
+                fee_rate: None,
             },
         );
     pvec(request_strategy, num).prop_map(|mut reqs| {
@@ -757,7 +1281,10 @@ proptest! {
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
             kyt_fee: None,
-            kyt_principal: None
+            kyt_principal: None,
+            resubmission_delay_blocks: None,
+            max_fee_bump_percent: None,
+            tainted_utxo_reviewer: None,
         });
         for (utxo, acc_idx) in utxos_acc_idx {
             state.add_utxos(accounts[acc_idx], vec![utxo]);
@@ -781,7 +1308,10 @@ proptest! {
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
             kyt_fee: None,
-            kyt_principal: None
+            kyt_principal: None,
+            resubmission_delay_blocks: None,
+            max_fee_bump_percent: None,
+            tainted_utxo_reviewer: None,
         });
 
         let mut available_amount = 0;
@@ -824,7 +1354,10 @@ proptest! {
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
             kyt_fee: None,
-            kyt_principal: None
+            kyt_principal: None,
+            resubmission_delay_blocks: None,
+            max_fee_bump_percent: None,
+            tainted_utxo_reviewer: None,
         });
 
         for (utxo, acc_idx) in utxos_acc_idx {
@@ -1,6 +1,8 @@
+pub mod deposit_notifications;
 pub mod get_btc_address;
 pub mod get_withdrawal_account;
 pub mod retrieve_btc;
+pub mod tainted_utxo_release;
 pub mod update_balance;
 
 pub use get_btc_address::get_btc_address;
@@ -0,0 +1,105 @@
+use crate::logs::P0;
+use crate::state::{self, mutate_state, read_state};
+use crate::tx::DisplayOutpoint;
+use candid::{CandidType, Deserialize, Principal};
+use ic_btc_interface::Utxo;
+use ic_canister_log::log;
+use icrc_ledger_types::icrc1::account::Account;
+
+/// The maximum number of principals that can subscribe to deposit notifications.
+const MAX_SUBSCRIPTIONS: usize = 10_000;
+
+/// The method of the subscribed canister that the minter calls on every deposit.
+pub const DEPOSIT_NOTIFICATION_METHOD: &str = "on_ckbtc_deposit";
+
+/// The argument of the [DEPOSIT_NOTIFICATION_METHOD] call.
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct DepositNotification {
+    /// The account that received the ckBTC.
+    pub account: Account,
+    /// The deposited UTXO.
+    pub utxo: Utxo,
+    /// The minted amount (UTXO value minus fees).
+    pub minted_amount: u64,
+    /// The MINT transaction index on the ledger.
+    pub block_index: u64,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum SubscribeDepositNotificationsError {
+    /// The minter cannot accept more subscriptions.
+    TooManySubscriptions,
+    /// Only canisters can subscribe to deposit notifications.
+    CallerNotACanister,
+}
+
+/// Returns true if `principal` is an opaque ID, the class of canister IDs.
+/// Users' principals are self-authenticating, and cannot subscribe: each
+/// subscription costs the creation of a canister, so that the limited
+/// number of subscriptions cannot be exhausted for free.
+fn is_canister(principal: &Principal) -> bool {
+    const TYPE_OPAQUE: u8 = 0x01;
+    principal.as_slice().last() == Some(&TYPE_OPAQUE)
+}
+
+/// Registers `canister_id` as the canister to notify when the minter mints
+/// ckBTC for any account of the caller, replacing the previous one, if any.
+/// The caller must be a canister.
+pub fn subscribe_deposit_notifications(
+    canister_id: Principal,
+) -> Result<(), SubscribeDepositNotificationsError> {
+    let caller = ic_cdk::caller();
+    if canister_id == ic_cdk::id() {
+        ic_cdk::trap("the minter cannot notify itself");
+    }
+    if !is_canister(&caller) {
+        return Err(SubscribeDepositNotificationsError::CallerNotACanister);
+    }
+    read_state(|s| {
+        if !s.deposit_notification_subscriptions.contains_key(&caller)
+            && s.deposit_notification_subscriptions.len() >= MAX_SUBSCRIPTIONS
+        {
+            return Err(SubscribeDepositNotificationsError::TooManySubscriptions);
+        }
+        Ok(())
+    })?;
+    mutate_state(|s| {
+        state::audit::set_deposit_notification_subscription(s, caller, Some(canister_id))
+    });
+    Ok(())
+}
+
+/// Stops notifying a canister about the deposits of the caller.
+pub fn unsubscribe_deposit_notifications() {
+    let caller = ic_cdk::caller();
+    if read_state(|s| s.deposit_notification_subscriptions.contains_key(&caller)) {
+        mutate_state(|s| state::audit::set_deposit_notification_subscription(s, caller, None));
+    }
+}
+
+/// Notifies the canister that subscribed to the deposits of the account's owner, if any.
+/// The minter does not wait for a reply, so failures of the callback cannot
+/// affect the deposit.
+pub fn notify_deposit(notification: DepositNotification) {
+    let canister_id = match read_state(|s| {
+        s.deposit_notification_subscriptions
+            .get(&notification.account.owner)
+            .cloned()
+    }) {
+        Some(canister_id) => canister_id,
+        None => return,
+    };
+    if let Err(code) = ic_cdk::api::call::notify(
+        canister_id,
+        DEPOSIT_NOTIFICATION_METHOD,
+        (notification.clone(),),
+    ) {
+        log!(
+            P0,
+            "Failed to notify {} about the deposit of UTXO {}: {:?}",
+            canister_id,
+            DisplayOutpoint(&notification.utxo.outpoint),
+            code
+        );
+    }
+}
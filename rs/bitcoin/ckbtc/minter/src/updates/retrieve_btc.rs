@@ -8,7 +8,7 @@ use crate::tasks::{schedule_now, TaskType};
 use crate::{
     address::{account_to_bitcoin_address, BitcoinAddress, ParseAddressError},
     guard::{retrieve_btc_guard, GuardError},
-    state::{self, mutate_state, read_state, RetrieveBtcRequest},
+    state::{self, mutate_state, read_state, RetrieveBtcFeeRate, RetrieveBtcRequest},
 };
 use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_base_types::PrincipalId;
@@ -25,6 +25,9 @@ use std::cmp::max;
 
 const MAX_CONCURRENT_PENDING_REQUESTS: usize = 1000;
 
+/// The highest fee rate that users can ask for, in satoshi per vbyte.
+const MAX_FEE_RATE_SAT_PER_VBYTE: u64 = 10_000;
+
 /// The arguments of the [retrieve_btc] endpoint.
 #[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
 pub struct RetrieveBtcArgs {
@@ -33,6 +36,9 @@ pub struct RetrieveBtcArgs {
 
     // address where to send bitcoins
     pub address: String,
+
+    // The fee rate of the withdrawal transaction, the median fee rate if not set.
+    pub fee_rate: Option<RetrieveBtcFeeRate>,
 }
 
 /// The arguments of the [retrieve_btc_with_approval] endpoint.
@@ -46,6 +52,9 @@ pub struct RetrieveBtcWithApprovalArgs {
 
     // The subaccount to burn ckBTC from.
     pub from_subaccount: Option<Subaccount>,
+
+    // The fee rate of the withdrawal transaction, the median fee rate if not set.
+    pub fee_rate: Option<RetrieveBtcFeeRate>,
 }
 
 #[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -58,6 +67,65 @@ pub enum ErrorCode {
     // The retrieval address didn't pass the KYT check.
     TaintedAddress = 1,
     KytCallFailed = 2,
+    // The requested fee rate is out of the accepted range.
+    InvalidFeeRate = 3,
+    // The retrieval address is on the blocklist.
+    BlockedAddress = 4,
+}
+
+/// Rejects retrievals to addresses on the blocklist. Every attempt is counted
+/// in a metric, but only the first attempt of each (caller, address) pair is
+/// recorded in the event log, so that repeated calls cannot grow it.
+fn check_address_not_blocked(
+    caller: Principal,
+    address: &str,
+    parsed_address: &BitcoinAddress,
+    amount: u64,
+) -> Result<(), String> {
+    if !crate::blocklist::is_blocked(parsed_address) {
+        return Ok(());
+    }
+    log!(
+        P1,
+        "rejected an attempt by {} to withdraw {} BTC to the blocked address {}",
+        caller,
+        crate::tx::DisplayAmount(amount),
+        address
+    );
+    crate::metrics::BLOCKED_RETRIEVE_BTC_ATTEMPTS.with(|cell| cell.set(cell.get() + 1));
+    mutate_state(|s| {
+        state::audit::retrieve_btc_address_blocked(s, caller, address.to_string(), amount)
+    });
+    Err(format!("Destination address {} is blocked", address))
+}
+
+/// Checks that the minter can honor the fee rate that the user asked for.
+pub fn validate_fee_rate(fee_rate: Option<&RetrieveBtcFeeRate>) -> Result<(), String> {
+    match fee_rate {
+        None => Ok(()),
+        Some(RetrieveBtcFeeRate::TargetConfirmationBlocks(0)) => {
+            Err("the target confirmation time must be at least one block".to_string())
+        }
+        Some(RetrieveBtcFeeRate::TargetConfirmationBlocks(_)) => Ok(()),
+        Some(RetrieveBtcFeeRate::SatoshiPerVbyte(rate))
+            if *rate == 0 || *rate > MAX_FEE_RATE_SAT_PER_VBYTE =>
+        {
+            Err(format!(
+                "the fee rate must be between 1 and {} satoshi per vbyte, got {}",
+                MAX_FEE_RATE_SAT_PER_VBYTE, rate
+            ))
+        }
+        Some(RetrieveBtcFeeRate::SatoshiPerVbyte(_)) => Ok(()),
+    }
+}
+
+/// Returns the minimum amount if `amount` does not cover the fees of a
+/// transaction at the requested `fee_rate`. Requests without a fee rate use
+/// the median fee rate, which `retrieve_btc_min_amount` already accounts for.
+fn min_amount_for_fee_rate(amount: u64, fee_rate: Option<&RetrieveBtcFeeRate>) -> Option<u64> {
+    let fee_rate = fee_rate?;
+    let min_amount = read_state(|s| crate::min_withdrawal_amount_for_fee_rate(s, amount, fee_rate));
+    (amount < min_amount).then_some(min_amount)
 }
 
 #[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -153,13 +221,6 @@ pub async fn retrieve_btc(args: RetrieveBtcArgs) -> Result<RetrieveBtcOk, Retrie
     state::read_state(|s| s.mode.is_withdrawal_available_for(&caller))
         .map_err(RetrieveBtcError::TemporarilyUnavailable)?;
 
-    if crate::blocklist::BTC_ADDRESS_BLOCKLIST
-        .binary_search(&args.address.trim())
-        .is_ok()
-    {
-        ic_cdk::trap("attempted to retrieve BTC to a blocked address");
-    }
-
     let ecdsa_public_key = init_ecdsa_public_key().await;
     let main_address = account_to_bitcoin_address(
         &ecdsa_public_key,
@@ -183,6 +244,21 @@ pub async fn retrieve_btc(args: RetrieveBtcArgs) -> Result<RetrieveBtcOk, Retrie
     }
 
     let parsed_address = BitcoinAddress::parse(&args.address, btc_network)?;
+    check_address_not_blocked(caller, &args.address, &parsed_address, args.amount).map_err(
+        |error_message| RetrieveBtcError::GenericError {
+            error_message,
+            error_code: ErrorCode::BlockedAddress as u64,
+        },
+    )?;
+    validate_fee_rate(args.fee_rate.as_ref()).map_err(|error_message| {
+        RetrieveBtcError::GenericError {
+            error_message,
+            error_code: ErrorCode::InvalidFeeRate as u64,
+        }
+    })?;
+    if let Some(min_amount) = min_amount_for_fee_rate(args.amount, args.fee_rate.as_ref()) {
+        return Err(RetrieveBtcError::AmountTooLow(min_amount));
+    }
     if read_state(|s| s.count_incomplete_retrieve_btc_requests() >= MAX_CONCURRENT_PENDING_REQUESTS)
     {
         return Err(RetrieveBtcError::TemporarilyUnavailable(
@@ -253,6 +329,7 @@ pub async fn retrieve_btc(args: RetrieveBtcArgs) -> Result<RetrieveBtcOk, Retrie
         block_index,
         received_at: ic_cdk::api::time(),
         kyt_provider: Some(kyt_provider),
+        fee_rate: args.fee_rate,
     };
 
     log!(
@@ -283,13 +360,6 @@ pub async fn retrieve_btc_with_approval(
     state::read_state(|s| s.mode.is_withdrawal_available_for(&caller))
         .map_err(RetrieveBtcWithApprovalError::TemporarilyUnavailable)?;
 
-    if crate::blocklist::BTC_ADDRESS_BLOCKLIST
-        .binary_search(&args.address.trim())
-        .is_ok()
-    {
-        ic_cdk::trap("attempted to retrieve BTC to a blocked address");
-    }
-
     let ecdsa_public_key = init_ecdsa_public_key().await;
     let main_address = account_to_bitcoin_address(
         &ecdsa_public_key,
@@ -311,6 +381,21 @@ pub async fn retrieve_btc_with_approval(
         return Err(RetrieveBtcWithApprovalError::AmountTooLow(min_amount));
     }
     let parsed_address = BitcoinAddress::parse(&args.address, btc_network)?;
+    check_address_not_blocked(caller, &args.address, &parsed_address, args.amount).map_err(
+        |error_message| RetrieveBtcWithApprovalError::GenericError {
+            error_message,
+            error_code: ErrorCode::BlockedAddress as u64,
+        },
+    )?;
+    validate_fee_rate(args.fee_rate.as_ref()).map_err(|error_message| {
+        RetrieveBtcWithApprovalError::GenericError {
+            error_message,
+            error_code: ErrorCode::InvalidFeeRate as u64,
+        }
+    })?;
+    if let Some(min_amount) = min_amount_for_fee_rate(args.amount, args.fee_rate.as_ref()) {
+        return Err(RetrieveBtcWithApprovalError::AmountTooLow(min_amount));
+    }
     if read_state(|s| s.count_incomplete_retrieve_btc_requests() >= MAX_CONCURRENT_PENDING_REQUESTS)
     {
         return Err(RetrieveBtcWithApprovalError::TemporarilyUnavailable(
@@ -375,6 +460,7 @@ pub async fn retrieve_btc_with_approval(
                 block_index,
                 received_at: ic_cdk::api::time(),
                 kyt_provider: Some(kyt_provider),
+                fee_rate: args.fee_rate,
             };
 
             mutate_state(|s| state::audit::accept_retrieve_btc_request(s, request));
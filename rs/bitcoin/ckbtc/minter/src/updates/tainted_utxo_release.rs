@@ -0,0 +1,169 @@
+use super::get_btc_address::{account_to_p2wpkh_address_from_state, init_ecdsa_public_key};
+use crate::address::{BitcoinAddress, ParseAddressError};
+use crate::logs::P0;
+use crate::management::{get_utxos, CallError, CallSource};
+use crate::state::{self, mutate_state, read_state, CkBtcMinterState, TaintedUtxoReleaseStatus};
+use crate::tasks::{schedule_now, TaskType};
+use crate::tx::DisplayOutpoint;
+use candid::{CandidType, Deserialize};
+use ic_btc_interface::Utxo;
+use ic_canister_log::log;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use serde::Serialize;
+
+/// The argument of the [request_tainted_utxo_release] endpoint.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RequestTaintedUtxoReleaseArgs {
+    /// The subaccount of the caller whose deposit address received the UTXO.
+    pub subaccount: Option<Subaccount>,
+    /// The quarantined UTXO.
+    pub utxo: Utxo,
+    /// The address to send the UTXO value to once the reviewer approves the request.
+    pub address: String,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum RequestTaintedUtxoReleaseError {
+    /// The minter experiences temporary issues, try the call again later.
+    TemporarilyUnavailable(String),
+    /// The minter failed to parse the destination address.
+    MalformedAddress(String),
+    /// The UTXO is not quarantined at the deposit address of the caller.
+    UnknownUtxo,
+    /// There is already a release request for the UTXO.
+    AlreadyRequested(TaintedUtxoReleaseStatus),
+}
+
+impl From<ParseAddressError> for RequestTaintedUtxoReleaseError {
+    fn from(e: ParseAddressError) -> Self {
+        Self::MalformedAddress(e.to_string())
+    }
+}
+
+impl From<CallError> for RequestTaintedUtxoReleaseError {
+    fn from(e: CallError) -> Self {
+        Self::TemporarilyUnavailable(e.to_string())
+    }
+}
+
+/// The argument of the [review_tainted_utxo_release] endpoint.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ReviewTaintedUtxoReleaseArgs {
+    /// The UTXO of the release request.
+    pub utxo: Utxo,
+    /// Whether the minter should send the UTXO value to the requested address.
+    pub approve: bool,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum ReviewTaintedUtxoReleaseError {
+    /// The caller is not the configured tainted UTXO reviewer.
+    Unauthorized,
+    /// There is no release request for the UTXO.
+    UnknownRequest,
+    /// The reviewer already decided on the request.
+    AlreadyReviewed(TaintedUtxoReleaseStatus),
+}
+
+fn check_release_request(
+    s: &CkBtcMinterState,
+    utxo: &Utxo,
+) -> Result<(), RequestTaintedUtxoReleaseError> {
+    if !s.quarantined_utxos.contains(utxo) {
+        return Err(RequestTaintedUtxoReleaseError::UnknownUtxo);
+    }
+    if let Some(release) = s.tainted_utxo_releases.get(utxo) {
+        return Err(RequestTaintedUtxoReleaseError::AlreadyRequested(
+            release.status.clone(),
+        ));
+    }
+    Ok(())
+}
+
+/// Asks the minter to return a UTXO that the KYT provider considered tainted
+/// to the specified address. The minter releases the UTXO only after the
+/// reviewer approves the request.
+pub async fn request_tainted_utxo_release(
+    args: RequestTaintedUtxoReleaseArgs,
+) -> Result<(), RequestTaintedUtxoReleaseError> {
+    let account = Account {
+        owner: ic_cdk::caller(),
+        subaccount: args.subaccount,
+    };
+
+    let (btc_network, min_confirmations) = read_state(|s| (s.btc_network, s.min_confirmations));
+    let address = BitcoinAddress::parse(&args.address, btc_network)?;
+
+    read_state(|s| check_release_request(s, &args.utxo))?;
+
+    // The quarantine does not remember the accounts of the UTXOs, so we check
+    // that the UTXO belongs to the deposit address of the caller.
+    init_ecdsa_public_key().await;
+    let deposit_address = read_state(|s| account_to_p2wpkh_address_from_state(s, &account));
+    let utxos = get_utxos(
+        btc_network,
+        &deposit_address,
+        min_confirmations,
+        CallSource::Client,
+    )
+    .await?
+    .utxos;
+
+    if !utxos.contains(&args.utxo) {
+        return Err(RequestTaintedUtxoReleaseError::UnknownUtxo);
+    }
+
+    mutate_state(|s| {
+        check_release_request(s, &args.utxo)?;
+        state::audit::request_tainted_utxo_release(s, args.utxo.clone(), account, address);
+        Ok(())
+    })?;
+
+    log!(
+        P0,
+        "Account {} requested the release of the tainted UTXO {} to {}",
+        account,
+        DisplayOutpoint(&args.utxo.outpoint),
+        args.address,
+    );
+
+    Ok(())
+}
+
+/// Records the decision of the reviewer on a tainted UTXO release request.
+pub fn review_tainted_utxo_release(
+    args: ReviewTaintedUtxoReleaseArgs,
+) -> Result<(), ReviewTaintedUtxoReleaseError> {
+    let caller = ic_cdk::caller();
+
+    mutate_state(|s| {
+        if s.tainted_utxo_reviewer != Some(caller) {
+            return Err(ReviewTaintedUtxoReleaseError::Unauthorized);
+        }
+        match s.tainted_utxo_releases.get(&args.utxo) {
+            None => return Err(ReviewTaintedUtxoReleaseError::UnknownRequest),
+            Some(release) if release.status != TaintedUtxoReleaseStatus::PendingReview => {
+                return Err(ReviewTaintedUtxoReleaseError::AlreadyReviewed(
+                    release.status.clone(),
+                ))
+            }
+            Some(_) => {}
+        }
+        state::audit::review_tainted_utxo_release(s, args.utxo.clone(), caller, args.approve);
+        Ok(())
+    })?;
+
+    log!(
+        P0,
+        "Reviewer {} {} the release of the tainted UTXO {}",
+        caller,
+        if args.approve { "approved" } else { "rejected" },
+        DisplayOutpoint(&args.utxo.outpoint),
+    );
+
+    if args.approve {
+        schedule_now(TaskType::ProcessLogic);
+    }
+
+    Ok(())
+}
@@ -19,6 +19,7 @@ use crate::{
     management::{fetch_utxo_alerts, get_utxos, CallError, CallSource},
     state,
     tx::{DisplayAmount, DisplayOutpoint},
+    updates::deposit_notifications::{notify_deposit, DepositNotification},
     updates::get_btc_address,
 };
 
@@ -234,6 +235,12 @@ pub async fn update_balance(
                         vec![utxo.clone()],
                     )
                 });
+                notify_deposit(DepositNotification {
+                    account: caller_account,
+                    utxo: utxo.clone(),
+                    minted_amount: amount,
+                    block_index,
+                });
                 utxo_statuses.push(UtxoStatus::Minted {
                     block_index,
                     utxo,
@@ -9,16 +9,28 @@ use ic_canisters_http_types::{HttpRequest, HttpResponse};
 use ic_ckbtc_kyt::{InitArg as KytInitArg, KytMode, LifecycleArg, SetApiKeyArg};
 use ic_ckbtc_minter::lifecycle::init::{InitArgs as CkbtcMinterInitArgs, MinterArg};
 use ic_ckbtc_minter::lifecycle::upgrade::UpgradeArgs;
-use ic_ckbtc_minter::queries::{EstimateFeeArg, RetrieveBtcStatusRequest, WithdrawalFee};
-use ic_ckbtc_minter::state::{Mode, RetrieveBtcStatus};
+use ic_ckbtc_minter::queries::{
+    EstimateFeeArg, ManagedUtxo, ManagedUtxos, OwedKytFee, Pagination, RetrieveBtcStatusRequest,
+    SubmittedTransactions, WithdrawalFee,
+};
+use ic_ckbtc_minter::state::eventlog::Event;
+use ic_ckbtc_minter::state::{Mode, RetrieveBtcStatus, TaintedUtxoReleaseStatus};
+use ic_ckbtc_minter::updates::deposit_notifications::{
+    DepositNotification, SubscribeDepositNotificationsError,
+};
 use ic_ckbtc_minter::updates::get_btc_address::GetBtcAddressArgs;
 use ic_ckbtc_minter::updates::retrieve_btc::{
     RetrieveBtcArgs, RetrieveBtcError, RetrieveBtcOk, RetrieveBtcWithApprovalArgs,
     RetrieveBtcWithApprovalError,
 };
+use ic_ckbtc_minter::updates::tainted_utxo_release::{
+    RequestTaintedUtxoReleaseArgs, RequestTaintedUtxoReleaseError, ReviewTaintedUtxoReleaseArgs,
+    ReviewTaintedUtxoReleaseError,
+};
 use ic_ckbtc_minter::updates::update_balance::{UpdateBalanceArgs, UpdateBalanceError, UtxoStatus};
 use ic_ckbtc_minter::{
-    Log, MinterInfo, CKBTC_LEDGER_MEMO_SIZE, MIN_RELAY_FEE_PER_VBYTE, MIN_RESUBMISSION_DELAY,
+    DistributeOwedKytFeesError, KytFeeDistribution, Log, MinterInfo, CKBTC_LEDGER_MEMO_SIZE,
+    MIN_RELAY_FEE_PER_VBYTE, MIN_RESUBMISSION_DELAY,
 };
 use ic_icrc1_ledger::{InitArgsBuilder as LedgerInitArgsBuilder, LedgerArgument};
 use ic_state_machine_tests::{Cycles, StateMachine, StateMachineBuilder, WasmResult};
@@ -105,6 +117,9 @@ fn install_minter(env: &StateMachine, ledger_id: CanisterId) -> CanisterId {
         mode: Mode::GeneralAvailability,
         kyt_fee: None,
         kyt_principal: Some(CanisterId::from(0)),
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     };
     let minter_arg = MinterArg::Init(args);
     env.install_canister(minter_wasm(), Encode!(&minter_arg).unwrap(), None)
@@ -170,6 +185,9 @@ fn test_wrong_upgrade_parameter() {
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(1001),
         kyt_principal: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     });
     let args = Encode!(&args).unwrap();
     if env.install_canister(minter_wasm(), args, None).is_ok() {
@@ -185,6 +203,9 @@ fn test_wrong_upgrade_parameter() {
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(1001),
         kyt_principal: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     });
     let args = Encode!(&args).unwrap();
     if env.install_canister(minter_wasm(), args, None).is_ok() {
@@ -204,6 +225,9 @@ fn test_wrong_upgrade_parameter() {
         mode: Some(Mode::ReadOnly),
         kyt_principal: None,
         kyt_fee: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     if env
@@ -232,6 +256,9 @@ fn test_upgrade_read_only() {
         mode: Some(Mode::ReadOnly),
         kyt_principal: Some(CanisterId::from(0)),
         kyt_fee: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&minter_arg).unwrap())
@@ -263,6 +290,7 @@ fn test_upgrade_read_only() {
     let retrieve_btc_args = RetrieveBtcArgs {
         amount: 10,
         address: "".into(),
+        fee_rate: None,
     };
     let res = env
         .execute_ingress_as(
@@ -302,6 +330,9 @@ fn test_upgrade_restricted() {
         mode: Some(Mode::RestrictedTo(vec![authorized_principal])),
         kyt_fee: None,
         kyt_principal: Some(CanisterId::from(0)),
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&minter_arg).unwrap())
@@ -333,6 +364,7 @@ fn test_upgrade_restricted() {
     let retrieve_btc_args = RetrieveBtcArgs {
         amount: 10,
         address: "".into(),
+        fee_rate: None,
     };
     let res = env
         .execute_ingress_as(
@@ -357,6 +389,9 @@ fn test_upgrade_restricted() {
         mode: Some(Mode::DepositsRestrictedTo(vec![authorized_principal])),
         kyt_principal: Some(CanisterId::from(0)),
         kyt_fee: None,
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     };
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&upgrade_args).unwrap())
         .expect("Failed to upgrade the minter canister");
@@ -448,6 +483,9 @@ fn test_minter() {
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(1001),
         kyt_principal: Some(CanisterId::from(0)),
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     });
     let args = Encode!(&args).unwrap();
     let minter_id = env.install_canister(minter_wasm(), args, None).unwrap();
@@ -542,6 +580,9 @@ impl CkBtcSetup {
                 mode: Mode::GeneralAvailability,
                 kyt_fee: Some(KYT_FEE),
                 kyt_principal: kyt_id.into(),
+                resubmission_delay_blocks: None,
+                max_fee_bump_percent: None,
+                tainted_utxo_reviewer: None,
             }))
             .unwrap(),
         )
@@ -700,6 +741,38 @@ impl CkBtcSetup {
         .unwrap()
     }
 
+    pub fn list_managed_utxos(&self, start: u64, length: u64) -> ManagedUtxos {
+        Decode!(
+            &assert_reply(
+                self.env
+                    .query(
+                        self.minter_id,
+                        "list_managed_utxos",
+                        Encode!(&Pagination { start, length }).unwrap()
+                    )
+                    .expect("failed to query managed UTXOs")
+            ),
+            ManagedUtxos
+        )
+        .unwrap()
+    }
+
+    pub fn list_submitted_transactions(&self, start: u64, length: u64) -> SubmittedTransactions {
+        Decode!(
+            &assert_reply(
+                self.env
+                    .query(
+                        self.minter_id,
+                        "list_submitted_transactions",
+                        Encode!(&Pagination { start, length }).unwrap()
+                    )
+                    .expect("failed to query submitted transactions")
+            ),
+            SubmittedTransactions
+        )
+        .unwrap()
+    }
+
     pub fn deposit_utxo(&self, account: impl Into<Account>, utxo: Utxo) {
         let account = account.into();
         let deposit_address = self.get_btc_address(account);
@@ -842,6 +915,7 @@ impl CkBtcSetup {
                 self.env.execute_ingress_as(self.caller, self.minter_id, "retrieve_btc", Encode!(&RetrieveBtcArgs {
                     address,
                     amount,
+                    fee_rate: None,
                 }).unwrap())
                 .expect("failed to execute retrieve_btc request")
             ),
@@ -860,7 +934,8 @@ impl CkBtcSetup {
                 self.env.execute_ingress_as(self.caller, self.minter_id, "retrieve_btc_with_approval", Encode!(&RetrieveBtcWithApprovalArgs {
                     address,
                     amount,
-                    from_subaccount
+                    from_subaccount,
+                    fee_rate: None,
                 }).unwrap())
                 .expect("failed to execute retrieve_btc request")
             ),
@@ -945,9 +1020,9 @@ impl CkBtcSetup {
         )
     }
 
-    pub fn print_minter_events(&self) {
-        use ic_ckbtc_minter::state::eventlog::{Event, GetEventsArg};
-        let events = Decode!(
+    pub fn get_events(&self) -> Vec<Event> {
+        use ic_ckbtc_minter::state::eventlog::{GetEventsArg, GetEventsResult};
+        Decode!(
             &assert_reply(
                 self.env
                     .query(
@@ -961,10 +1036,101 @@ impl CkBtcSetup {
                     )
                     .expect("failed to query minter events")
             ),
-            Vec<Event>
+            GetEventsResult
         )
-        .unwrap();
-        println!("{:#?}", events);
+        .unwrap()
+        .events
+        .into_iter()
+        .map(|entry| entry.payload)
+        .collect()
+    }
+
+    pub fn print_minter_events(&self) {
+        println!("{:#?}", self.get_events());
+    }
+
+    pub fn subscribe_deposit_notifications(
+        &self,
+        canister_id: CanisterId,
+    ) -> Result<(), SubscribeDepositNotificationsError> {
+        Decode!(
+            &assert_reply(
+                self.env
+                    .execute_ingress_as(
+                        self.caller,
+                        self.minter_id,
+                        "subscribe_deposit_notifications",
+                        Encode!(&Principal::from(canister_id)).unwrap()
+                    )
+                    .expect("failed to subscribe to deposit notifications")
+            ),
+            Result<(), SubscribeDepositNotificationsError>
+        )
+        .unwrap()
+    }
+
+    pub fn request_tainted_utxo_release(
+        &self,
+        utxo: Utxo,
+        address: String,
+    ) -> Result<(), RequestTaintedUtxoReleaseError> {
+        Decode!(
+            &assert_reply(
+                self.env
+                    .execute_ingress_as(
+                        self.caller,
+                        self.minter_id,
+                        "request_tainted_utxo_release",
+                        Encode!(&RequestTaintedUtxoReleaseArgs {
+                            subaccount: None,
+                            utxo,
+                            address,
+                        })
+                        .unwrap()
+                    )
+                    .expect("failed to request a tainted UTXO release")
+            ),
+            Result<(), RequestTaintedUtxoReleaseError>
+        )
+        .unwrap()
+    }
+
+    pub fn review_tainted_utxo_release(
+        &self,
+        reviewer: PrincipalId,
+        utxo: Utxo,
+        approve: bool,
+    ) -> Result<(), ReviewTaintedUtxoReleaseError> {
+        Decode!(
+            &assert_reply(
+                self.env
+                    .execute_ingress_as(
+                        reviewer,
+                        self.minter_id,
+                        "review_tainted_utxo_release",
+                        Encode!(&ReviewTaintedUtxoReleaseArgs { utxo, approve }).unwrap()
+                    )
+                    .expect("failed to review a tainted UTXO release")
+            ),
+            Result<(), ReviewTaintedUtxoReleaseError>
+        )
+        .unwrap()
+    }
+
+    pub fn tainted_utxo_release_status(&self, utxo: Utxo) -> Option<TaintedUtxoReleaseStatus> {
+        Decode!(
+            &assert_reply(
+                self.env
+                    .query(
+                        self.minter_id,
+                        "tainted_utxo_release_status",
+                        Encode!(&utxo).unwrap()
+                    )
+                    .expect("failed to query the tainted UTXO release status")
+            ),
+            Option<TaintedUtxoReleaseStatus>
+        )
+        .unwrap()
     }
 
     pub fn print_minter_logs(&self) {
@@ -1117,6 +1283,200 @@ fn test_transaction_finalization() {
     assert_eq!(ckbtc.await_finalization(block_index, 10), txid);
 }
 
+#[test]
+fn test_list_managed_utxos_and_submitted_transactions() {
+    let ckbtc = CkBtcSetup::new();
+    let user = Principal::from(ckbtc.caller);
+
+    let utxos: Vec<_> = [(1..=32, 100_000_000), (2..=33, 200_000_000)]
+        .into_iter()
+        .map(|(txid_range, value)| Utxo {
+            height: 0,
+            outpoint: OutPoint {
+                txid: range_to_txid(txid_range),
+                vout: 1,
+            },
+            value,
+        })
+        .collect();
+    for utxo in utxos.iter() {
+        ckbtc.deposit_utxo(user, utxo.clone());
+    }
+
+    let page = ckbtc.list_managed_utxos(0, 10);
+    assert_eq!(page.total, 2);
+    assert_eq!(page.utxos.len(), 2);
+    for utxo in utxos.iter() {
+        assert!(page.utxos.contains(&ManagedUtxo {
+            utxo: utxo.clone(),
+            account: user.into(),
+            available: true,
+        }));
+    }
+    let first_page = ckbtc.list_managed_utxos(0, 1);
+    let second_page = ckbtc.list_managed_utxos(1, 1);
+    assert_eq!(first_page.total, 2);
+    assert_eq!([first_page.utxos, second_page.utxos].concat(), page.utxos);
+    assert!(ckbtc.list_managed_utxos(2, 10).utxos.is_empty());
+    assert_eq!(ckbtc.list_submitted_transactions(0, 10).total, 0);
+
+    let withdrawal_amount = 50_000_000;
+    let withdrawal_account = ckbtc.withdrawal_account(user.into());
+    ckbtc.refresh_fee_percentiles();
+    ckbtc.transfer(user, withdrawal_account, withdrawal_amount);
+    let RetrieveBtcOk { block_index } = ckbtc
+        .retrieve_btc(WITHDRAWAL_ADDRESS.to_string(), withdrawal_amount)
+        .expect("retrieve_btc failed");
+    ckbtc.env.advance_time(MAX_TIME_IN_QUEUE);
+    let txid = ckbtc.await_btc_transaction(block_index, 10);
+
+    let transactions = ckbtc.list_submitted_transactions(0, 10);
+    assert_eq!(transactions.total, 1);
+    let tx = &transactions.transactions[0];
+    assert_eq!(tx.txid, txid);
+    assert_eq!(tx.request_block_indices, vec![block_index]);
+    assert_eq!(tx.replaced_by, None);
+    assert!(tx.change_output.is_some());
+
+    // The UTXOs that the transaction spends are still in custody until it is finalized.
+    let page = ckbtc.list_managed_utxos(0, 10);
+    assert_eq!(page.total, 2);
+    for managed in page.utxos {
+        assert_eq!(managed.available, !tx.used_utxos.contains(&managed.utxo));
+    }
+}
+
+/// Installs a canister that keeps the last [DepositNotification] it received,
+/// and returns it from its `last_notification` query.
+fn install_deposit_notification_receiver(env: &StateMachine) -> CanisterId {
+    env.install_canister_wat(
+        r#"(module
+             (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+             (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+             (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+             (import "ic0" "msg_reply" (func $msg_reply))
+             ;; The size of the last notification is at address 0, and its
+             ;; bytes start at address 16.
+             (func $on_ckbtc_deposit
+               (i32.store (i32.const 0) (call $msg_arg_data_size))
+               (call $msg_arg_data_copy (i32.const 16) (i32.const 0) (call $msg_arg_data_size))
+               (call $msg_reply_data_append (i32.const 4) (i32.const 6))
+               (call $msg_reply))
+             (func $last_notification
+               (call $msg_reply_data_append (i32.const 16) (i32.load (i32.const 0)))
+               (call $msg_reply))
+             (memory $memory 1)
+             (data (i32.const 4) "DIDL\00\00")
+             (export "memory" (memory $memory))
+             (export "canister_update on_ckbtc_deposit" (func $on_ckbtc_deposit))
+             (export "canister_query last_notification" (func $last_notification)))"#,
+        vec![],
+        None,
+    )
+}
+
+#[test]
+fn test_deposit_notification_subscription() {
+    let ckbtc = CkBtcSetup::new();
+    let user = Principal::from(ckbtc.caller);
+    let receiver_id = install_deposit_notification_receiver(&ckbtc.env);
+
+    ckbtc
+        .subscribe_deposit_notifications(receiver_id)
+        .expect("failed to subscribe to deposit notifications");
+    assert!(ckbtc
+        .get_events()
+        .contains(&Event::SetDepositNotificationSubscription {
+            owner: user,
+            canister_id: Some(receiver_id.into()),
+        }));
+
+    let deposit_value = 100_000_000;
+    let utxo = Utxo {
+        height: 0,
+        outpoint: OutPoint {
+            txid: range_to_txid(1..=32),
+            vout: 1,
+        },
+        value: deposit_value,
+    };
+    ckbtc.deposit_utxo(user, utxo.clone());
+    assert_eq!(ckbtc.balance_of(user), Nat::from(deposit_value - KYT_FEE));
+
+    ckbtc.env.tick();
+    let notification = Decode!(
+        &assert_reply(
+            ckbtc
+                .env
+                .query(receiver_id, "last_notification", vec![])
+                .expect("failed to query the last notification")
+        ),
+        DepositNotification
+    )
+    .unwrap();
+    assert_eq!(
+        notification,
+        DepositNotification {
+            account: Account {
+                owner: user,
+                subaccount: None,
+            },
+            utxo,
+            minted_amount: deposit_value - KYT_FEE,
+            block_index: 0,
+        }
+    );
+
+    // Only canisters can subscribe.
+    assert_eq!(
+        Decode!(
+            &assert_reply(
+                ckbtc
+                    .env
+                    .execute_ingress_as(
+                        PrincipalId::new_self_authenticating(&[1, 2, 3]),
+                        ckbtc.minter_id,
+                        "subscribe_deposit_notifications",
+                        Encode!(&Principal::from(receiver_id)).unwrap()
+                    )
+                    .expect("failed to subscribe to deposit notifications")
+            ),
+            Result<(), SubscribeDepositNotificationsError>
+        )
+        .unwrap(),
+        Err(SubscribeDepositNotificationsError::CallerNotACanister)
+    );
+
+    // Subscriptions are per caller, and anonymous callers cannot subscribe.
+    assert!(ckbtc
+        .env
+        .execute_ingress_as(
+            PrincipalId::new_anonymous(),
+            ckbtc.minter_id,
+            "subscribe_deposit_notifications",
+            Encode!(&Principal::from(ckbtc.ledger_id)).unwrap()
+        )
+        .is_err());
+
+    assert_reply(
+        ckbtc
+            .env
+            .execute_ingress_as(
+                ckbtc.caller,
+                ckbtc.minter_id,
+                "unsubscribe_deposit_notifications",
+                Encode!().unwrap(),
+            )
+            .expect("failed to unsubscribe from deposit notifications"),
+    );
+    assert!(ckbtc
+        .get_events()
+        .contains(&Event::SetDepositNotificationSubscription {
+            owner: user,
+            canister_id: None,
+        }));
+}
+
 #[test]
 fn test_min_retrieval_amount() {
     let ckbtc = CkBtcSetup::new();
@@ -1532,6 +1892,81 @@ fn test_ledger_memo() {
     assert_eq!(decoded_data, MintMemo::Kyt);
 }
 
+#[test]
+fn test_distribute_owed_kyt_fees() {
+    let ckbtc = CkBtcSetup::new();
+
+    let get_owed_kyt_fees = || {
+        Decode!(
+            &assert_reply(
+                ckbtc
+                    .env
+                    .query(ckbtc.minter_id, "get_owed_kyt_fees", Encode!().unwrap())
+                    .expect("failed to query owed KYT fees")
+            ),
+            Vec<OwedKytFee>
+        )
+        .unwrap()
+    };
+    assert_eq!(get_owed_kyt_fees(), vec![]);
+
+    // Each deposit costs a KYT fee, which the minter owes to the KYT provider.
+    let user = Principal::from(ckbtc.caller);
+    ckbtc.deposit_utxo(
+        user,
+        Utxo {
+            height: 0,
+            outpoint: OutPoint {
+                txid: range_to_txid(1..=32),
+                vout: 1,
+            },
+            value: 100_000_000,
+        },
+    );
+    let kyt_provider = Principal::from(ckbtc.kyt_provider);
+    assert_eq!(
+        get_owed_kyt_fees(),
+        vec![OwedKytFee {
+            kyt_provider,
+            amount: KYT_FEE,
+        }]
+    );
+
+    // Only controllers may distribute the fees.
+    assert!(ckbtc
+        .env
+        .execute_ingress_as(
+            ckbtc.caller,
+            ckbtc.minter_id,
+            "distribute_owed_kyt_fees",
+            Encode!().unwrap()
+        )
+        .is_err());
+
+    let distributions = Decode!(
+        &assert_reply(
+            ckbtc
+                .env
+                .execute_ingress(
+                    ckbtc.minter_id,
+                    "distribute_owed_kyt_fees",
+                    Encode!().unwrap()
+                )
+                .expect("failed to distribute owed KYT fees")
+        ),
+        Result<Vec<KytFeeDistribution>, DistributeOwedKytFeesError>
+    )
+    .unwrap()
+    .expect("the distribution of KYT fees was rejected");
+    assert_eq!(distributions.len(), 1);
+    assert_eq!(distributions[0].kyt_provider, kyt_provider);
+    assert_eq!(distributions[0].amount, KYT_FEE);
+    assert!(distributions[0].result.is_ok());
+
+    assert_eq!(get_owed_kyt_fees(), vec![]);
+    assert_eq!(ckbtc.balance_of(kyt_provider), Nat::from(KYT_FEE));
+}
+
 #[test]
 fn test_filter_logs() {
     let ckbtc = CkBtcSetup::new();
@@ -1830,6 +2265,9 @@ fn test_retrieve_btc_with_approval_fail() {
                 minter_id: None,
                 maintainers: None,
                 mode: Some(KytMode::RejectAll),
+                provider_modes: None,
+                failover_order: None,
+                quorum_policy: None,
             }))
             .unwrap(),
         )
@@ -1880,3 +2318,168 @@ fn test_retrieve_btc_with_approval_fail() {
         Nat::from(deposit_value - 2 * KYT_FEE - TRANSFER_FEE)
     );
 }
+
+#[test]
+fn test_tainted_utxo_release() {
+    let ckbtc = CkBtcSetup::new();
+    let reviewer = PrincipalId::new_user_test_id(3);
+
+    ckbtc
+        .env
+        .upgrade_canister(
+            ckbtc.minter_id,
+            minter_wasm(),
+            Encode!(&MinterArg::Upgrade(Some(UpgradeArgs {
+                tainted_utxo_reviewer: Some(reviewer.into()),
+                ..Default::default()
+            })))
+            .unwrap(),
+        )
+        .expect("failed to upgrade the minter");
+
+    ckbtc
+        .env
+        .upgrade_canister(
+            ckbtc.kyt_id,
+            kyt_wasm(),
+            Encode!(&LifecycleArg::UpgradeArg(ic_ckbtc_kyt::UpgradeArg {
+                minter_id: None,
+                maintainers: None,
+                mode: Some(KytMode::RejectAll),
+                provider_modes: None,
+                failover_order: None,
+                quorum_policy: None,
+            }))
+            .unwrap(),
+        )
+        .expect("failed to upgrade the KYT canister");
+
+    // Step 1: deposit a tainted UTXO
+
+    let user = Principal::from(ckbtc.caller);
+    let utxo = Utxo {
+        height: 0,
+        outpoint: OutPoint {
+            txid: range_to_txid(1..=32),
+            vout: 1,
+        },
+        value: 1_000_000,
+    };
+    ckbtc.push_utxo(ckbtc.get_btc_address(user), utxo.clone());
+
+    let utxo_status = Decode!(
+        &assert_reply(
+            ckbtc
+                .env
+                .execute_ingress_as(
+                    ckbtc.caller,
+                    ckbtc.minter_id,
+                    "update_balance",
+                    Encode!(&UpdateBalanceArgs {
+                        owner: None,
+                        subaccount: None,
+                    })
+                    .unwrap()
+                )
+                .expect("failed to update balance")
+        ),
+        Result<Vec<UtxoStatus>, UpdateBalanceError>
+    )
+    .unwrap();
+    assert_eq!(utxo_status, Ok(vec![UtxoStatus::Tainted(utxo.clone())]));
+    assert_eq!(ckbtc.balance_of(user), Nat::from(0_u64));
+
+    // Step 2: request the release
+
+    let unknown_utxo = Utxo {
+        outpoint: OutPoint {
+            txid: range_to_txid(2..=33),
+            vout: 0,
+        },
+        ..utxo.clone()
+    };
+    assert_eq!(
+        ckbtc.request_tainted_utxo_release(unknown_utxo, WITHDRAWAL_ADDRESS.to_string()),
+        Err(RequestTaintedUtxoReleaseError::UnknownUtxo)
+    );
+    assert_eq!(
+        ckbtc.request_tainted_utxo_release(utxo.clone(), WITHDRAWAL_ADDRESS.to_string()),
+        Ok(())
+    );
+    assert_eq!(
+        ckbtc.request_tainted_utxo_release(utxo.clone(), WITHDRAWAL_ADDRESS.to_string()),
+        Err(RequestTaintedUtxoReleaseError::AlreadyRequested(
+            TaintedUtxoReleaseStatus::PendingReview
+        ))
+    );
+
+    // Step 3: review the request
+
+    assert_eq!(
+        ckbtc.review_tainted_utxo_release(ckbtc.caller, utxo.clone(), true),
+        Err(ReviewTaintedUtxoReleaseError::Unauthorized)
+    );
+    assert_eq!(
+        ckbtc.tainted_utxo_release_status(utxo.clone()),
+        Some(TaintedUtxoReleaseStatus::PendingReview)
+    );
+    assert_eq!(
+        ckbtc.review_tainted_utxo_release(reviewer, utxo.clone(), true),
+        Ok(())
+    );
+    assert_eq!(
+        ckbtc.review_tainted_utxo_release(reviewer, utxo.clone(), false),
+        Err(ReviewTaintedUtxoReleaseError::AlreadyReviewed(
+            TaintedUtxoReleaseStatus::Approved
+        ))
+    );
+
+    // Step 4: wait for the release transaction
+
+    let txid = ckbtc.tick_until("the minter releases the UTXO", 10, |ckbtc| {
+        match ckbtc.tainted_utxo_release_status(utxo.clone()) {
+            Some(TaintedUtxoReleaseStatus::Released { txid }) => Some(txid),
+            _ => None,
+        }
+    });
+    let mempool = ckbtc.mempool();
+    let tx = mempool
+        .get(&txid)
+        .expect("the mempool does not contain the release transaction");
+
+    assert_eq!(
+        input_utxos(tx)
+            .into_iter()
+            .map(|outpoint| (vec_to_txid(outpoint.txid.to_vec()), outpoint.vout))
+            .collect::<Vec<_>>(),
+        vec![(utxo.outpoint.txid, utxo.outpoint.vout)]
+    );
+    // The release transaction signals replace-by-fee.
+    assert_eq!(tx.input[0].sequence, 0xfffffffd);
+    assert_eq!(2, tx.output.len());
+    assert_eq!(
+        BtcAddress::from_script(&tx.output[0].script_pubkey, BtcNetwork::Bitcoin)
+            .unwrap()
+            .to_string(),
+        WITHDRAWAL_ADDRESS
+    );
+
+    // Step 5: confirm the release transaction
+
+    ckbtc.finalize_transaction(tx);
+    ckbtc.tick_until("the minter confirms the release transaction", 10, |ckbtc| {
+        ckbtc
+            .get_events()
+            .contains(&Event::ConfirmedBtcTransaction { txid })
+            .then_some(())
+    });
+    assert_eq!(
+        ckbtc.tainted_utxo_release_status(utxo.clone()),
+        Some(TaintedUtxoReleaseStatus::Released { txid })
+    );
+    assert_eq!(
+        ckbtc.request_tainted_utxo_release(utxo, WITHDRAWAL_ADDRESS.to_string()),
+        Err(RequestTaintedUtxoReleaseError::UnknownUtxo)
+    );
+    ckbtc.minter_self_check();
+}
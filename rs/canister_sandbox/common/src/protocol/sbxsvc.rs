@@ -69,8 +69,8 @@ pub struct OpenWasmSerializedRequest {
     /// This types in just an `Arc` reference to a vector of bytes and the only
     /// reason it is `Arc` is so that we can cheaply create the
     /// `OpenWasmSerializedRequest` before sending it to the sandbox.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub serialized_module: Arc<SerializedModuleBytes>,
 }
 
@@ -268,8 +268,8 @@ pub struct CreateExecutionStateSerializedRequest {
     /// This types in just an `Arc` reference to a vector of bytes and the only
     /// reason it is `Arc` is so that we can cheaply create the
     /// `CreateExecutionStateSerializedRequest` before sending it to the sandbox.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub serialized_module: Arc<SerializedModule>,
     pub wasm_page_map: PageMapSerialization,
     pub next_wasm_memory_id: MemoryId,
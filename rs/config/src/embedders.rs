@@ -70,6 +70,10 @@ pub struct FeatureFlags {
     /// Track dirty pages with a write barrier instead of the signal handler.
     pub write_barrier: FlagStatus,
     pub wasm_native_stable_memory: FlagStatus,
+    /// If this flag is enabled, then `ic0.debug_print` additionally emits a
+    /// structured record on the logger of the execution environment, so that
+    /// test environments can collect the output per canister.
+    pub canister_debug_print_records: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -78,6 +82,7 @@ impl FeatureFlags {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             write_barrier: FlagStatus::Disabled,
             wasm_native_stable_memory: FlagStatus::Enabled,
+            canister_debug_print_records: FlagStatus::Disabled,
         }
     }
 }
@@ -371,6 +371,86 @@ fn point_mul(c: &mut Criterion) {
     }
 }
 
+fn point_ops_consttime_vs_vartime(c: &mut Criterion) {
+    let rng = &mut reproducible_rng();
+
+    for curve_type in EccCurveType::all() {
+        let mut group = c.benchmark_group(format!(
+            "crypto_point_ops_consttime_vs_vartime_{}",
+            curve_type
+        ));
+
+        let mul_2_instance = |rng: &mut _| {
+            (
+                random_point(curve_type, rng),
+                random_scalar(curve_type, rng),
+                random_point(curve_type, rng),
+                random_scalar(curve_type, rng),
+            )
+        };
+
+        group.bench_function("mul_2_points", |b| {
+            b.iter_batched_ref(
+                || mul_2_instance(rng),
+                |(p_0, s_0, p_1, s_1)| EccPoint::mul_2_points(p_0, s_0, p_1, s_1),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function("mul_2_points_vartime", |b| {
+            b.iter_batched_ref(
+                || mul_2_instance(rng),
+                |(p_0, s_0, p_1, s_1)| EccPoint::mul_2_points_vartime(p_0, s_0, p_1, s_1),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function("pedersen", |b| {
+            b.iter_batched_ref(
+                || {
+                    (
+                        random_scalar(curve_type, rng),
+                        random_scalar(curve_type, rng),
+                    )
+                },
+                |(s_0, s_1)| EccPoint::pedersen(s_0, s_1),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function("pedersen_vartime", |b| {
+            b.iter_batched_ref(
+                || {
+                    (
+                        random_scalar(curve_type, rng),
+                        random_scalar(curve_type, rng),
+                    )
+                },
+                |(s_0, s_1)| EccPoint::pedersen_vartime(s_0, s_1),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function("mul_by_g", |b| {
+            b.iter_batched_ref(
+                || random_scalar(curve_type, rng),
+                |s| EccPoint::mul_by_g(s),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function("mul_by_g_vartime", |b| {
+            b.iter_batched_ref(
+                || random_scalar(curve_type, rng),
+                |s| EccPoint::mul_by_g_vartime(s),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.finish();
+    }
+}
+
 fn point_serialize(c: &mut Criterion) {
     let rng = &mut reproducible_rng();
 
@@ -407,6 +487,6 @@ fn point_serialize(c: &mut Criterion) {
 criterion_group! {
 name = group_ops;
 config = Criterion::default().measurement_time(Duration::from_secs(30));
-targets = point_multiexp_constant_time, point_multiexp_vartime_total, point_multiexp_vartime_online, point_mul, point_double_vs_addition, point_serialize,
+targets = point_multiexp_constant_time, point_multiexp_vartime_total, point_multiexp_vartime_online, point_mul, point_ops_consttime_vs_vartime, point_double_vs_addition, point_serialize,
 }
 criterion_main!(group_ops);
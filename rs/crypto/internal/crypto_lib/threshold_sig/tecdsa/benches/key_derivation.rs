@@ -7,11 +7,10 @@ use ic_types::crypto::AlgorithmId;
 fn key_derivation(c: &mut Criterion) {
     let mut rng = reproducible_rng();
 
-    // Add P256 here when key derivation is defined for that curve:
-    for curve in [EccCurveType::K256] {
+    for curve in EccCurveType::all() {
         let algorithm_id = match curve {
             EccCurveType::K256 => AlgorithmId::EcdsaSecp256k1,
-            EccCurveType::P256 => unreachable!(),
+            EccCurveType::P256 => AlgorithmId::EcdsaP256,
         };
 
         let sk = EccScalar::random(curve, &mut rng);
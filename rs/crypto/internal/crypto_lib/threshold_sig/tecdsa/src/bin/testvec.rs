@@ -0,0 +1,34 @@
+//! Writes the fixed encodings used by `verify_fixed_serialization_continues_to_be_accepted`
+//!
+//! Usage: `cargo run --features testvec --bin tecdsa-testvec -- [OUTPUT_DIR]`
+//!
+//! Only fixtures which do not yet exist in OUTPUT_DIR (by default
+//! `tests/data`) are written; existing fixtures are left untouched.
+
+use ic_crypto_internal_threshold_sig_ecdsa::testvec::generate_test_vectors;
+use ic_crypto_internal_threshold_sig_ecdsa::Seed;
+use std::path::PathBuf;
+
+fn main() {
+    let output_dir = PathBuf::from(
+        std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| "tests/data".to_string()),
+    );
+
+    let vectors = generate_test_vectors(Seed::from_bytes(b"ic-crypto-tecdsa-testvec-seed"))
+        .expect("Failed to generate test vectors");
+
+    for vector in vectors {
+        let path = output_dir.join(&vector.file_name);
+
+        if path.exists() {
+            println!("Skipping existing {}", path.display());
+            continue;
+        }
+
+        std::fs::write(&path, vector.hex)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        println!("Wrote {}", path.display());
+    }
+}
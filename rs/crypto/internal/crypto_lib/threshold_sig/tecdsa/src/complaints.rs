@@ -62,12 +62,6 @@ pub fn generate_complaints(
         }
     }
 
-    if complaints.is_empty() {
-        return Err(ThresholdEcdsaError::InvalidArguments(
-            "generate_complaints should return at least one complaint".to_string(),
-        ));
-    }
-
     Ok(complaints)
 }
 
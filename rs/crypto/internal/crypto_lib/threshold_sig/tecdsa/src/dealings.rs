@@ -190,7 +190,7 @@ impl IDkgDealingInternal {
         }
 
         for recipient in recipients {
-            if recipient.curve_type() != curve {
+            if recipient.curve_type() != curve.mega_key_curve() {
                 return Err(ThresholdEcdsaError::InvalidRecipients);
             }
         }
@@ -348,8 +348,11 @@ impl IDkgDealingInternal {
             (Op::Random, None) => {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
-                self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                self.ciphertext.verify_is(
+                    MEGaCiphertextType::Pairs,
+                    curve_type.mega_key_curve(),
+                    curve_type,
+                )?;
                 // no ZK proof for this transcript type
                 Ok(())
             }
@@ -360,8 +363,11 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
-                self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                self.ciphertext.verify_is(
+                    MEGaCiphertextType::Single,
+                    curve_type.mega_key_curve(),
+                    curve_type,
+                )?;
 
                 proof.verify(
                     &previous_commitment.evaluate_at(dealer_index)?,
@@ -376,8 +382,11 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
-                self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                self.ciphertext.verify_is(
+                    MEGaCiphertextType::Single,
+                    curve_type.mega_key_curve(),
+                    curve_type,
+                )?;
 
                 match previous_commitment {
                     PolynomialCommitment::Pedersen(_) => {
@@ -398,8 +407,11 @@ impl IDkgDealingInternal {
             (Op::UnmaskedTimesMasked(lhs, rhs), Some(ZkProof::ProofOfProduct(proof))) => {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
-                self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                self.ciphertext.verify_is(
+                    MEGaCiphertextType::Pairs,
+                    curve_type.mega_key_curve(),
+                    curve_type,
+                )?;
                 lhs.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 rhs.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
 
@@ -426,7 +438,9 @@ impl IDkgDealingInternal {
         dealer_index: NodeIndex,
         recipient_index: NodeIndex,
     ) -> ThresholdEcdsaResult<()> {
-        if private_key.curve_type() != curve_type || public_key.curve_type() != curve_type {
+        let key_curve = curve_type.mega_key_curve();
+
+        if private_key.curve_type() != key_curve || public_key.curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
@@ -447,12 +461,11 @@ impl IDkgDealingInternal {
     }
 
     pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
-        serde_cbor::to_vec(self).map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+        serialize_artifact(self)
     }
 
     pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
-        serde_cbor::from_slice::<Self>(bytes)
-            .map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+        deserialize_artifact(bytes)
     }
 }
 
@@ -4,11 +4,11 @@ use hex_literal::hex;
 use ic_types::NodeIndex;
 use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::fmt;
 use std::sync::Arc;
-use subtle::Choice;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 mod secp256k1;
 mod secp256r1;
@@ -97,14 +97,40 @@ impl EccCurveType {
         }
     }
 
+    /// Return the curve used by the IDKG protocol for the given algorithm
+    ///
+    /// This accepts all threshold signature algorithms which are built on
+    /// top of IDKG transcripts.
     pub(crate) fn from_algorithm(alg_id: ic_types::crypto::AlgorithmId) -> Option<Self> {
         match alg_id {
             AlgorithmId::ThresholdEcdsaSecp256k1 => Some(EccCurveType::K256),
-            //AlgorithmId::ThresholdEcdsaSecp256r1 => Some(EccCurveType::P256),
+            AlgorithmId::ThresholdEcdsaSecp256r1 => Some(EccCurveType::P256),
+            AlgorithmId::ThresholdSchnorrBip340 => Some(EccCurveType::K256),
             _ => None,
         }
     }
 
+    /// Return the curve of a threshold ECDSA signature algorithm
+    pub(crate) fn from_ecdsa_algorithm(alg_id: ic_types::crypto::AlgorithmId) -> Option<Self> {
+        match alg_id {
+            AlgorithmId::ThresholdEcdsaSecp256k1 => Some(EccCurveType::K256),
+            AlgorithmId::ThresholdEcdsaSecp256r1 => Some(EccCurveType::P256),
+            _ => None,
+        }
+    }
+
+    /// Return the curve of the MEGa keys that IDKG dealings over this curve
+    /// are encrypted to
+    ///
+    /// Nodes only hold secp256k1 MEGa keys, so the shares of dealings over
+    /// any curve are encrypted to secp256k1 keys.
+    pub fn mega_key_curve(&self) -> EccCurveType {
+        match self {
+            EccCurveType::K256 => EccCurveType::K256,
+            EccCurveType::P256 => EccCurveType::K256,
+        }
+    }
+
     /// Return a vector over all available curve types
     ///
     /// This is mostly useful for tests
@@ -124,7 +150,10 @@ impl fmt::Display for EccCurveType {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Zeroize, ZeroizeOnDrop)]
+/// A scalar value
+///
+/// Equality comparisons of scalars of the same curve run in constant time
+#[derive(Clone, Eq, Zeroize, ZeroizeOnDrop)]
 pub enum EccScalar {
     K256(secp256k1::Scalar),
     P256(secp256r1::Scalar),
@@ -375,7 +404,25 @@ impl EccScalar {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+impl ConstantTimeEq for EccScalar {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        // The curve of a scalar is public
+        match (self, other) {
+            (Self::K256(x), Self::K256(y)) => x.ct_eq(y),
+            (Self::P256(x), Self::P256(y)) => x.ct_eq(y),
+            (_, _) => Choice::from(0),
+        }
+    }
+}
+
+impl PartialEq for EccScalar {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+// The encoding of a scalar may be secret, so clear it once (de)serialized
+#[derive(Deserialize, Serialize, Zeroize, ZeroizeOnDrop)]
 struct EccScalarSerializationHelper(#[serde(with = "serde_bytes")] Vec<u8>);
 
 impl Serialize for EccScalar {
@@ -393,9 +440,29 @@ impl<'de> Deserialize<'de> for EccScalar {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+/// The serialization of a secret scalar
+///
+/// The contents are cleared on drop, and equality is checked in constant time
+#[derive(Clone, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub enum EccScalarBytes {
     K256(Box<[u8; 32]>),
+    P256(Box<[u8; 32]>),
+}
+
+impl ConstantTimeEq for EccScalarBytes {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        match (self, other) {
+            (Self::K256(x), Self::K256(y)) => x[..].ct_eq(&y[..]),
+            (Self::P256(x), Self::P256(y)) => x[..].ct_eq(&y[..]),
+            (_, _) => Choice::from(0),
+        }
+    }
+}
+
+impl PartialEq for EccScalarBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
 }
 
 impl TryFrom<&EccScalarBytes> for EccScalar {
@@ -404,6 +471,7 @@ impl TryFrom<&EccScalarBytes> for EccScalar {
     fn try_from(bytes: &EccScalarBytes) -> ThresholdEcdsaSerializationResult<Self> {
         match bytes {
             EccScalarBytes::K256(raw) => EccScalar::deserialize(EccCurveType::K256, raw.as_ref()),
+            EccScalarBytes::P256(raw) => EccScalar::deserialize(EccCurveType::P256, raw.as_ref()),
         }
     }
 }
@@ -412,15 +480,21 @@ impl TryFrom<&EccScalar> for EccScalarBytes {
     type Error = ThresholdEcdsaSerializationError;
 
     fn try_from(scalar: &EccScalar) -> ThresholdEcdsaSerializationResult<Self> {
+        let bytes = Zeroizing::new(scalar.serialize());
+
+        if bytes.len() != 32 {
+            return Err(ThresholdEcdsaSerializationError(format!(
+                "Unexpected scalar length {}",
+                bytes.len()
+            )));
+        }
+
+        let mut raw = Box::new([0u8; 32]);
+        raw.copy_from_slice(&bytes);
+
         match scalar.curve_type() {
-            EccCurveType::K256 => {
-                Ok(Self::K256(scalar.serialize().try_into().map_err(|e| {
-                    ThresholdEcdsaSerializationError(format!("{:?}", e))
-                })?))
-            }
-            _ => {
-                panic!("we don't support other curves yet at the higher layers");
-            }
+            EccCurveType::K256 => Ok(Self::K256(raw)),
+            EccCurveType::P256 => Ok(Self::P256(raw)),
         }
     }
 }
@@ -654,6 +728,40 @@ impl EccPoint {
         }
     }
 
+    /// Return pt1 * scalar1 + pt2 * scalar2 in variable time
+    ///
+    /// This is a non-constant-time equivalent to [`Self::mul_2_points`],
+    /// intended for verifying proofs and signatures, where both the points
+    /// and the scalars are public. Points without a precomputed LUT are
+    /// precomputed with the default window size, except on secp256k1 where
+    /// the constant-time `lincomb` (which makes use of the GLV endomorphism)
+    /// is faster than computing two LUTs.
+    ///
+    /// Warning: this function leaks information about the scalars via
+    /// side channels. Do not use this function with secret scalars.
+    pub fn mul_2_points_vartime(
+        pt1: &EccPoint,
+        scalar1: &EccScalar,
+        pt2: &EccPoint,
+        scalar2: &EccScalar,
+    ) -> ThresholdEcdsaResult<Self> {
+        let curve_type = pt1.curve_type();
+        if pt2.curve_type() != curve_type
+            || scalar1.curve_type() != curve_type
+            || scalar2.curve_type() != curve_type
+        {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        if curve_type == EccCurveType::K256 && !pt1.is_precopmuted() && !pt2.is_precopmuted() {
+            return Self::mul_2_points(pt1, scalar1, pt2, scalar2);
+        }
+
+        let pt1 = pt1.with_lut()?;
+        let pt2 = pt2.with_lut()?;
+        Self::mul_n_points_vartime(&[(&pt1, scalar1), (&pt2, scalar2)])
+    }
+
     pub const MIN_LUT_WINDOW_SIZE: usize = NafLut::MIN_WINDOW_SIZE;
     pub const MAX_LUT_WINDOW_SIZE: usize = NafLut::MAX_WINDOW_SIZE;
     pub const DEFAULT_LUT_WINDOW_SIZE: usize = NafLut::DEFAULT_WINDOW_SIZE;
@@ -676,6 +784,16 @@ impl EccPoint {
         self.precompute.is_some()
     }
 
+    /// Returns a copy of `self` that has a LUT, computing one with the
+    /// default window size if `self` was not precomputed.
+    pub(crate) fn with_lut(&self) -> ThresholdEcdsaResult<Self> {
+        let mut pt = self.clone();
+        if !pt.is_precopmuted() {
+            pt.precompute(Self::DEFAULT_LUT_WINDOW_SIZE)?;
+        }
+        Ok(pt)
+    }
+
     /// Takes in an NAF state for a scalar and an accumulator point,
     /// which must be initialized with the identity in the first call,
     /// and performs one step for the scalar-point multiplication.
@@ -753,7 +871,6 @@ impl EccPoint {
         points: &[Self],
         index: usize,
     ) -> ThresholdEcdsaResult<Self> {
-        use subtle::ConstantTimeEq;
         if points.is_empty() {
             return Err(ThresholdEcdsaError::InvalidArguments(String::from(
                 "The input to constant-time select from slice must contain at least one element",
@@ -781,7 +898,6 @@ impl EccPoint {
         input: &Self,
         index: usize,
     ) -> ThresholdEcdsaResult<()> {
-        use subtle::ConstantTimeEq;
         for (i, point) in points.iter_mut().enumerate() {
             point.conditional_assign(input, usize::ct_eq(&i, &index))?;
         }
@@ -925,6 +1041,25 @@ impl EccPoint {
         Self::mul_2_points(&g, scalar1, &h, scalar2)
     }
 
+    /// Return g * scalar1 + h * scalar2 in variable time
+    ///
+    /// This is a non-constant-time equivalent to [`Self::pedersen`] that
+    /// uses LUTs for `g` and `h` which are computed once per curve.
+    ///
+    /// Warning: this function leaks information about the scalars via
+    /// side channels. Do not use this function with secret scalars.
+    pub fn pedersen_vartime(
+        scalar1: &EccScalar,
+        scalar2: &EccScalar,
+    ) -> ThresholdEcdsaResult<Self> {
+        let curve_type = scalar1.curve_type();
+        if scalar2.curve_type() != curve_type {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+        let (g, h) = generators_with_lut(curve_type);
+        Self::mul_n_points_vartime(&[(g, scalar1), (h, scalar2)])
+    }
+
     pub fn mul_by_g(scalar: &EccScalar) -> Self {
         match scalar {
             EccScalar::K256(s) => secp256k1::Point::generator().mul(s).into(),
@@ -932,6 +1067,24 @@ impl EccPoint {
         }
     }
 
+    /// Return g * scalar in variable time
+    ///
+    /// This is a non-constant-time equivalent to [`Self::mul_by_g`] that
+    /// uses a LUT for `g` which is computed once per curve. On secp256k1
+    /// this uses [`Self::mul_by_g`], which is faster.
+    ///
+    /// Warning: this function leaks information about the scalar via
+    /// side channels. Do not use this function with a secret scalar.
+    pub fn mul_by_g_vartime(scalar: &EccScalar) -> ThresholdEcdsaResult<Self> {
+        match scalar.curve_type() {
+            EccCurveType::K256 => Ok(Self::mul_by_g(scalar)),
+            curve_type => {
+                let (g, _h) = generators_with_lut(curve_type);
+                g.scalar_mul_vartime(scalar)
+            }
+        }
+    }
+
     /// Serialize a point in compressed form
     ///
     /// The output is in SEC1 format, and will be 1 header byte
@@ -1079,6 +1232,35 @@ impl EccPoint {
     }
 }
 
+lazy_static::lazy_static! {
+    /// The generators g and h of each curve, with LUTs of the maximal window
+    /// size since they are computed only once
+    static ref K256_GENERATORS_WITH_LUT: (EccPoint, EccPoint) =
+        compute_generators_with_lut(EccCurveType::K256);
+    static ref P256_GENERATORS_WITH_LUT: (EccPoint, EccPoint) =
+        compute_generators_with_lut(EccCurveType::P256);
+}
+
+fn compute_generators_with_lut(curve: EccCurveType) -> (EccPoint, EccPoint) {
+    let with_lut = |mut pt: EccPoint| {
+        pt.precompute(EccPoint::MAX_LUT_WINDOW_SIZE)
+            .expect("MAX_LUT_WINDOW_SIZE is a valid window size");
+        pt
+    };
+    (
+        with_lut(EccPoint::generator_g(curve)),
+        with_lut(EccPoint::generator_h(curve)),
+    )
+}
+
+/// Returns the generators g and h of `curve` with precomputed LUTs
+fn generators_with_lut(curve: EccCurveType) -> &'static (EccPoint, EccPoint) {
+    match curve {
+        EccCurveType::K256 => &K256_GENERATORS_WITH_LUT,
+        EccCurveType::P256 => &P256_GENERATORS_WITH_LUT,
+    }
+}
+
 /// Converts `secp256r1` point to `EccPoint`
 impl From<secp256r1::Point> for EccPoint {
     fn from(point: secp256r1::Point) -> Self {
@@ -6,7 +6,7 @@ use k256::elliptic_curve::{
     Field, Group,
 };
 use std::ops::Neg;
-use subtle::{Choice, ConditionallySelectable};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 #[derive(Clone, Eq, PartialEq, Zeroize, ZeroizeOnDrop)]
@@ -14,6 +14,12 @@ pub struct Scalar {
     s: k256::Scalar,
 }
 
+impl ConstantTimeEq for Scalar {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.s.ct_eq(&other.s)
+    }
+}
+
 impl Scalar {
     pub const BYTES: usize = 32;
 
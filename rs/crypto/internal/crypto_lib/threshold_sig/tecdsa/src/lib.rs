@@ -49,6 +49,12 @@
 //! correctly (with regards to the included commitment) results in a
 //! complaint being generated.
 //!
+//! File: `metrics.rs`
+//!
+//! Counts generated and verified complaints and openings. The counters
+//! can be read with [`complaint_and_opening_metrics`] and exported by
+//! higher layers, to help identify misbehaving nodes.
+//!
 //! ## Protocol: Transcripts
 //!
 //! File: `transcript.rs`
@@ -69,6 +75,19 @@
 //! * Generation and verification of signature shares
 //! * Generation and verification of combined signatures
 //!
+//! ## Protocol: BIP-340 Schnorr Signature Generation and Verification
+//!
+//! File: `schnorr.rs`
+//!
+//! Threshold Schnorr signatures over secp256k1 as specified in BIP-340.
+//! These use the same dealings and transcripts as threshold ECDSA, but
+//! only require two unmasked transcripts: the key and a random
+//! presignature. Each signature share is a Shamir share of the `s` value
+//! of the signature, so combining shares is a single interpolation.
+//!
+//! * Generation and verification of signature shares
+//! * Generation and verification of combined signatures
+//!
 //! ## Protocol: Multi-encryption gadget (MEGa)
 //!
 //! File: `mega.rs`
@@ -76,6 +95,11 @@
 //! Implements the MEGa encryption/decryption scheme, including key
 //! generation.
 //!
+//! The curve of the recipients' keys may differ from the curve of the
+//! plaintexts. Dealings over P256 (for secp256r1 ECDSA) are encrypted to
+//! the same secp256k1 MEGa keys as dealings over K256; see
+//! [`EccCurveType::mega_key_curve`].
+//!
 //! [`RandomOracle`](#utility-functions-random-oracle) is used to
 //! generate the additive masking values.
 //!
@@ -201,6 +225,13 @@
 //!
 //! Contains a function for corrupting dealings which is used when testing
 //! malicious behavior.
+//!
+//! File: `testvec.rs` (feature `testvec` only)
+//!
+//! Deterministically generates the fixed encodings in `tests/data` which
+//! are used to check that old artifacts continue to be accepted. Missing
+//! fixtures can be written with
+//! `cargo run --features testvec --bin tecdsa-testvec -- tests/data`.
 
 #![forbid(unsafe_code)]
 
@@ -251,6 +282,48 @@ pub struct ThresholdEcdsaSerializationError(pub String);
 pub type ThresholdEcdsaSerializationResult<T> =
     std::result::Result<T, ThresholdEcdsaSerializationError>;
 
+/// The version of the versioned encoding of dealings, transcripts, commitment
+/// openings and signature shares
+///
+/// The versioned encoding of an artifact is a single version byte followed by
+/// its CBOR encoding. The legacy encoding is the bare CBOR map, whose first
+/// byte is in the range 0xA0..=0xBF, so it cannot be confused with a versioned
+/// encoding.
+///
+/// Both encodings are accepted when decoding, but artifacts are still encoded
+/// in the legacy encoding: they are exchanged between nodes, which cannot
+/// decode the versioned encoding before they run a release that contains this
+/// version. Encoding artifacts with the version byte must wait for a later
+/// release, once every node accepts it.
+pub const ARTIFACT_SERIALIZATION_VERSION: u8 = 1;
+
+fn serialize_artifact<T: Serialize>(artifact: &T) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
+    serde_cbor::to_vec(artifact).map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+}
+
+fn deserialize_artifact<T: serde::de::DeserializeOwned>(
+    bytes: &[u8],
+) -> ThresholdEcdsaSerializationResult<T> {
+    let cbor = match bytes.first() {
+        Some(&ARTIFACT_SERIALIZATION_VERSION) => &bytes[1..],
+        // Unversioned encoding, which starts with a CBOR map header
+        Some(0xA0..=0xBF) => bytes,
+        Some(version) => {
+            return Err(ThresholdEcdsaSerializationError(format!(
+                "Unsupported serialization version {}",
+                version
+            )))
+        }
+        None => {
+            return Err(ThresholdEcdsaSerializationError(
+                "Empty serialization".to_string(),
+            ))
+        }
+    };
+    serde_cbor::from_slice::<T>(cbor)
+        .map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+}
+
 mod complaints;
 mod dealings;
 mod fe;
@@ -258,10 +331,14 @@ mod group;
 mod hash2curve;
 mod key_derivation;
 mod mega;
+mod metrics;
 mod poly;
 pub mod ro;
+pub mod schnorr;
 pub mod sign;
 pub mod test_utils;
+#[cfg(feature = "testvec")]
+pub mod testvec;
 mod transcript;
 pub mod zk;
 
@@ -270,11 +347,17 @@ pub use crate::dealings::*;
 pub use crate::fe::*;
 pub use crate::group::*;
 pub use crate::mega::*;
+pub use crate::metrics::{complaint_and_opening_metrics, ComplaintAndOpeningMetrics};
 pub use crate::poly::*;
 pub use crate::transcript::*;
 
 pub use crate::key_derivation::{DerivationIndex, DerivationPath};
-pub use sign::{ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaSigShareInternal};
+pub use schnorr::{ThresholdSchnorrCombinedSigInternal, ThresholdSchnorrSigShareInternal};
+pub use sign::{
+    ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaSigShareBatchInput,
+    ThresholdEcdsaSigShareBatchInternal, ThresholdEcdsaSigShareBatchVerificationInput,
+    ThresholdEcdsaSigShareInternal,
+};
 
 /// Create MEGa encryption keypair
 pub fn gen_keypair(curve_type: EccCurveType, seed: Seed) -> (MEGaPublicKey, MEGaPrivateKey) {
@@ -332,6 +415,53 @@ pub fn create_dealing(
     .map_err(|e| e.into())
 }
 
+/// Create a dealing which reshares the dealer's share of an existing transcript
+///
+/// The dealing is for a new set of receivers `new_recipients` with
+/// reconstruction threshold `new_threshold`, both of which may differ from
+/// those of `old_transcript`. The secret shared by the resulting transcript
+/// is the same as that of `old_transcript`.
+///
+/// `dealer_opening` is the dealer's opening of `old_transcript`, and
+/// `dealer_index` its index in `old_transcript`. Whether this is a reshare
+/// of a masked or an unmasked transcript follows from the commitment type of
+/// `old_transcript`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_reshare_dealing(
+    algorithm_id: AlgorithmId,
+    associated_data: &[u8],
+    dealer_index: NodeIndex,
+    old_transcript: &IDkgTranscriptInternal,
+    dealer_opening: &CommitmentOpening,
+    new_threshold: NumberOfNodes,
+    new_recipients: &[MEGaPublicKey],
+    seed: Seed,
+) -> Result<IDkgDealingInternal, IdkgCreateDealingInternalError> {
+    // Refuse to reshare a share which is not consistent with the old transcript
+    old_transcript
+        .combined_commitment
+        .commitment()
+        .check_opening(dealer_index, dealer_opening)
+        .map_err(|_| IdkgCreateDealingInternalError::InvalidSecretShare)?;
+
+    let shares = match dealer_opening {
+        CommitmentOpening::Simple(value) => SecretShares::ReshareOfUnmasked(value.clone()),
+        CommitmentOpening::Pedersen(value, mask) => {
+            SecretShares::ReshareOfMasked(value.clone(), mask.clone())
+        }
+    };
+
+    create_dealing(
+        algorithm_id,
+        associated_data,
+        dealer_index,
+        new_threshold,
+        new_recipients,
+        &shares,
+        seed,
+    )
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IDkgCreateTranscriptInternalError {
     UnsupportedAlgorithm,
@@ -378,6 +508,25 @@ pub fn create_transcript(
     .map_err(|e| e.into())
 }
 
+/// Create the transcript which reshares `old_transcript`
+///
+/// The `verified_dealings` must have been created by [`create_reshare_dealing`]
+/// for the new receivers and with threshold `new_threshold`. At least as many
+/// dealings as the reconstruction threshold of `old_transcript` are required.
+pub fn reshare_transcript(
+    algorithm_id: AlgorithmId,
+    old_transcript: &IDkgTranscriptInternal,
+    new_threshold: NumberOfNodes,
+    verified_dealings: &BTreeMap<NodeIndex, IDkgDealingInternal>,
+) -> Result<IDkgTranscriptInternal, IDkgCreateTranscriptInternalError> {
+    create_transcript(
+        algorithm_id,
+        new_threshold,
+        verified_dealings,
+        &IDkgTranscriptOperationInternal::reshare_of(old_transcript),
+    )
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IDkgVerifyTranscriptInternalError {
     IncorrectTranscript,
@@ -621,6 +770,16 @@ impl From<&ExtendedDerivationPath> for DerivationPath {
 }
 
 impl ThresholdEcdsaSigShareInternal {
+    pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
+        serialize_artifact(self)
+    }
+
+    pub fn deserialize(raw: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
+        deserialize_artifact(raw)
+    }
+}
+
+impl ThresholdEcdsaSigShareBatchInternal {
     pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
         serde_cbor::to_vec(self).map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
     }
@@ -650,7 +809,7 @@ impl From<ThresholdEcdsaError> for ThresholdEcdsaGenerateSigShareInternalError {
 
 // Returns None if the AlgorithmId does not map to threshold ECDSA
 fn signature_parameters(algorithm_id: AlgorithmId) -> Option<(EccCurveType, usize)> {
-    EccCurveType::from_algorithm(algorithm_id).map(|curve| (curve, curve.scalar_bytes()))
+    EccCurveType::from_ecdsa_algorithm(algorithm_id).map(|curve| (curve, curve.scalar_bytes()))
 }
 
 /// Create a new threshold ECDSA signature share
@@ -701,6 +860,29 @@ pub fn sign_share(
     .map_err(|e| e.into())
 }
 
+/// Create a batch of threshold ECDSA signature shares
+///
+/// This is equivalent to calling [`sign_share`] once for each input, but
+/// the key derivation is only performed once per distinct derivation path.
+///
+/// Each input must use a different presignature; the batch is rejected
+/// if any presignature transcript occurs more than once.
+pub fn sign_share_batch(
+    inputs: &[ThresholdEcdsaSigShareBatchInput],
+    key_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<ThresholdEcdsaSigShareBatchInternal, ThresholdEcdsaGenerateSigShareInternalError> {
+    let (curve_type, hash_len) = signature_parameters(algorithm_id)
+        .ok_or(ThresholdEcdsaGenerateSigShareInternalError::UnsupportedAlgorithm)?;
+
+    if inputs.iter().any(|i| i.hashed_message.len() != hash_len) {
+        return Err(ThresholdEcdsaGenerateSigShareInternalError::UnsupportedAlgorithm);
+    }
+
+    ThresholdEcdsaSigShareBatchInternal::new(inputs, key_transcript, curve_type)
+        .map_err(|e| e.into())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ThresholdEcdsaVerifySigShareInternalError {
     UnsupportedAlgorithm,
@@ -761,6 +943,29 @@ pub fn verify_signature_share(
         .map_err(|e| e.into())
 }
 
+/// Verify a batch of signature shares
+///
+/// The inputs must be consistent with, and in the same order as, those
+/// used when the batch was created
+pub fn verify_signature_share_batch(
+    sig_share_batch: &ThresholdEcdsaSigShareBatchInternal,
+    inputs: &[ThresholdEcdsaSigShareBatchVerificationInput],
+    signer_index: NodeIndex,
+    key_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<(), ThresholdEcdsaVerifySigShareInternalError> {
+    let (curve_type, hash_len) = signature_parameters(algorithm_id)
+        .ok_or(ThresholdEcdsaVerifySigShareInternalError::UnsupportedAlgorithm)?;
+
+    if inputs.iter().any(|i| i.hashed_message.len() != hash_len) {
+        return Err(ThresholdEcdsaVerifySigShareInternalError::UnsupportedAlgorithm);
+    }
+
+    sig_share_batch
+        .verify(inputs, signer_index, key_transcript, curve_type)
+        .map_err(|e| e.into())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ThresholdEcdsaCombineSigSharesInternalError {
     UnsupportedAlgorithm,
@@ -795,7 +1000,7 @@ pub fn combine_sig_shares(
     sig_shares: &BTreeMap<NodeIndex, ThresholdEcdsaSigShareInternal>,
     algorithm_id: AlgorithmId,
 ) -> Result<ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaCombineSigSharesInternalError> {
-    let curve = EccCurveType::from_algorithm(algorithm_id)
+    let curve = EccCurveType::from_ecdsa_algorithm(algorithm_id)
         .ok_or(ThresholdEcdsaCombineSigSharesInternalError::UnsupportedAlgorithm)?;
 
     sign::ThresholdEcdsaCombinedSigInternal::new(
@@ -908,8 +1113,217 @@ pub fn derive_public_key(
     )?)
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrGenerateSigShareInternalError {
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrGenerateSigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Create a new threshold BIP-340 Schnorr signature share
+///
+/// The randomness should be random and shared by all nodes, for instance
+/// by deriving a value from the random tape. It is used to rerandomize
+/// the presignature.
+///
+/// The presig_transcript is the transcript of an unmasked random value,
+/// and key_opening and presig_opening are our openings of the
+/// commitments in the key and presignature transcripts.
+///
+/// Unlike ECDSA, BIP-340 signs the message itself rather than a hash.
+#[allow(clippy::too_many_arguments)]
+pub fn sign_schnorr_share(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    key_opening: &CommitmentOpening,
+    presig_transcript: &IDkgTranscriptInternal,
+    presig_opening: &CommitmentOpening,
+    algorithm_id: AlgorithmId,
+) -> Result<ThresholdSchnorrSigShareInternal, ThresholdSchnorrGenerateSigShareInternalError> {
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdSchnorrGenerateSigShareInternalError::UnsupportedAlgorithm);
+    }
+
+    ThresholdSchnorrSigShareInternal::new(
+        derivation_path,
+        message,
+        randomness,
+        key_transcript,
+        key_opening,
+        presig_transcript,
+        presig_opening,
+    )
+    .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrVerifySigShareInternalError {
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InvalidSignatureShare,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrVerifySigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignatureShare => Self::InvalidSignatureShare,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Verify a threshold BIP-340 Schnorr signature share
+///
+/// The values provided must be consistent with when the signature share
+/// was created
+#[allow(clippy::too_many_arguments)]
+pub fn verify_schnorr_signature_share(
+    sig_share: &ThresholdSchnorrSigShareInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    signer_index: NodeIndex,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<(), ThresholdSchnorrVerifySigShareInternalError> {
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdSchnorrVerifySigShareInternalError::UnsupportedAlgorithm);
+    }
+
+    sig_share
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            signer_index,
+            key_transcript,
+            presig_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrCombineSigSharesInternalError {
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InsufficientShares,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrCombineSigSharesInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InsufficientDealings => Self::InsufficientShares,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Combine sufficient signature shares into a BIP-340 Schnorr signature
+///
+/// The signature shares must be verified prior to use, and there must
+/// be at least reconstruction_threshold many of them.
+#[allow(clippy::too_many_arguments)]
+pub fn combine_schnorr_sig_shares(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    reconstruction_threshold: NumberOfNodes,
+    sig_shares: &BTreeMap<NodeIndex, ThresholdSchnorrSigShareInternal>,
+    algorithm_id: AlgorithmId,
+) -> Result<ThresholdSchnorrCombinedSigInternal, ThresholdSchnorrCombineSigSharesInternalError> {
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdSchnorrCombineSigSharesInternalError::UnsupportedAlgorithm);
+    }
+
+    ThresholdSchnorrCombinedSigInternal::new(
+        derivation_path,
+        message,
+        randomness,
+        key_transcript,
+        presig_transcript,
+        reconstruction_threshold,
+        sig_shares,
+    )
+    .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrVerifySignatureInternalError {
+    InvalidSignature,
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrVerifySignatureInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignature => Self::InvalidSignature,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Verify a threshold BIP-340 Schnorr signature
+///
+/// In addition to checking that the signature itself is consistent with
+/// the provided message and the public key associated with
+/// `derivation_path`, this function also verifies that the signature was
+/// generated correctly with regards to the provided presignature
+/// transcript and randomness.
+pub fn verify_threshold_schnorr_signature(
+    signature: &ThresholdSchnorrCombinedSigInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    presig_transcript: &IDkgTranscriptInternal,
+    key_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<(), ThresholdSchnorrVerifySignatureInternalError> {
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdSchnorrVerifySignatureInternalError::UnsupportedAlgorithm);
+    }
+
+    signature
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            presig_transcript,
+            key_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IDkgGenerateComplaintsInternalError {
+    /// None of the dealings required a complaint from this receiver
+    NoComplaintsRequired {
+        receiver_index: NodeIndex,
+    },
     InvalidArguments(String),
     InternalError(String),
 }
@@ -919,6 +1333,9 @@ impl From<IDkgGenerateComplaintsInternalError> for IDkgLoadTranscriptError {
         type Igcie = IDkgGenerateComplaintsInternalError;
         type Ilte = IDkgLoadTranscriptError;
         match generate_complaints_internal_error {
+            e @ Igcie::NoComplaintsRequired { .. } => Ilte::InvalidArguments {
+                internal_error: format!("{:?}", e),
+            },
             Igcie::InvalidArguments(internal_error) => Ilte::InvalidArguments { internal_error },
             Igcie::InternalError(internal_error) => Ilte::InternalError { internal_error },
         }
@@ -948,7 +1365,7 @@ impl From<ThresholdEcdsaError> for IDkgGenerateComplaintsInternalError {
 /// was wrong.
 ///
 /// This function assumes there is at least one erroneous dealing that
-/// requires complaining.
+/// requires complaining; otherwise `NoComplaintsRequired` is returned.
 pub fn generate_complaints(
     verified_dealings: &BTreeMap<NodeIndex, IDkgDealingInternal>,
     associated_data: &[u8],
@@ -957,29 +1374,79 @@ pub fn generate_complaints(
     public_key: &MEGaPublicKey,
     seed: Seed,
 ) -> Result<BTreeMap<NodeIndex, IDkgComplaintInternal>, IDkgGenerateComplaintsInternalError> {
-    Ok(complaints::generate_complaints(
+    let complaints = complaints::generate_complaints(
         verified_dealings,
         associated_data,
         receiver_index,
         secret_key,
         public_key,
         seed,
-    )?)
+    )?;
+
+    if complaints.is_empty() {
+        return Err(IDkgGenerateComplaintsInternalError::NoComplaintsRequired { receiver_index });
+    }
+
+    metrics::observe_complaints_generated(complaints.len());
+
+    Ok(complaints)
+}
+
+/// The reason a complaint was found to be invalid
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ComplaintRejectionReason {
+    /// The proof of discrete log equivalence did not verify
+    InvalidProof,
+    /// The complaint is for a different curve than the dealing
+    CurveMismatch,
+    /// The decrypted opening is consistent with the dealing commitment,
+    /// so there was nothing to complain about
+    OpeningIsConsistent,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IDkgVerifyComplaintInternalError {
-    InvalidComplaint,
+    InvalidComplaint {
+        dealer_index: NodeIndex,
+        complainer_index: NodeIndex,
+        reason: ComplaintRejectionReason,
+    },
+    /// The ciphertext of the dealing does not match the type of its commitment
+    UnexpectedCommitmentType {
+        dealer_index: NodeIndex,
+        commitment_type: PolynomialCommitmentType,
+    },
     InvalidArgument(String),
     InternalError(String),
 }
 
-impl From<ThresholdEcdsaError> for IDkgVerifyComplaintInternalError {
-    fn from(e: ThresholdEcdsaError) -> Self {
+impl IDkgVerifyComplaintInternalError {
+    fn from_threshold_ecdsa_error(
+        e: ThresholdEcdsaError,
+        dealing: &IDkgDealingInternal,
+        dealer_index: NodeIndex,
+        complainer_index: NodeIndex,
+    ) -> Self {
+        let invalid_complaint = |reason| Self::InvalidComplaint {
+            dealer_index,
+            complainer_index,
+            reason,
+        };
+
         match e {
-            ThresholdEcdsaError::InvalidProof => Self::InvalidComplaint,
-            ThresholdEcdsaError::InvalidComplaint => Self::InvalidComplaint,
-            ThresholdEcdsaError::CurveMismatch => Self::InvalidComplaint,
+            ThresholdEcdsaError::InvalidProof => {
+                invalid_complaint(ComplaintRejectionReason::InvalidProof)
+            }
+            ThresholdEcdsaError::InvalidComplaint => {
+                invalid_complaint(ComplaintRejectionReason::OpeningIsConsistent)
+            }
+            ThresholdEcdsaError::CurveMismatch => {
+                invalid_complaint(ComplaintRejectionReason::CurveMismatch)
+            }
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::UnexpectedCommitmentType {
+                dealer_index,
+                commitment_type: dealing.commitment.ctype(),
+            },
             ThresholdEcdsaError::InvalidArguments(e) => Self::InvalidArgument(e),
             other => Self::InternalError(format!("{:?}", other)),
         }
@@ -991,7 +1458,10 @@ impl From<IDkgVerifyComplaintInternalError> for IDkgVerifyComplaintError {
         type Vcie = IDkgVerifyComplaintInternalError;
         type Vce = IDkgVerifyComplaintError;
         match verify_complaint_internal_error {
-            Vcie::InvalidComplaint => Vce::InvalidComplaint,
+            Vcie::InvalidComplaint { .. } => Vce::InvalidComplaint,
+            e @ Vcie::UnexpectedCommitmentType { .. } => Vce::InternalError {
+                internal_error: format!("{:?}", e),
+            },
             Vcie::InternalError(internal_error) => Vce::InternalError { internal_error },
             Vcie::InvalidArgument(internal_error) => Vce::InvalidArgument { internal_error },
         }
@@ -1007,23 +1477,57 @@ pub fn verify_complaint(
     dealer_index: NodeIndex,
     associated_data: &[u8],
 ) -> Result<(), IDkgVerifyComplaintInternalError> {
-    Ok(complaint.verify(
-        dealing,
-        dealer_index,
-        complainer_index,
-        complainer_key,
-        associated_data,
-    )?)
+    let result = complaint
+        .verify(
+            dealing,
+            dealer_index,
+            complainer_index,
+            complainer_key,
+            associated_data,
+        )
+        .map_err(|e| {
+            IDkgVerifyComplaintInternalError::from_threshold_ecdsa_error(
+                e,
+                dealing,
+                dealer_index,
+                complainer_index,
+            )
+        });
+
+    metrics::observe_complaint_verification(&result);
+
+    result
 }
 
 #[derive(Clone, Debug)]
 pub enum ThresholdOpenDealingInternalError {
+    /// The opener's own share of the dealing is not consistent with the
+    /// dealing commitment. In this case the opener should have complained
+    /// about the dealing rather than being asked to open it.
+    InconsistentShare {
+        dealer_index: NodeIndex,
+        opener_index: NodeIndex,
+        commitment_type: PolynomialCommitmentType,
+    },
     InternalError(String),
 }
 
-impl From<ThresholdEcdsaError> for ThresholdOpenDealingInternalError {
-    fn from(e: ThresholdEcdsaError) -> Self {
-        Self::InternalError(format!("{:?}", e))
+impl ThresholdOpenDealingInternalError {
+    fn from_threshold_ecdsa_error(
+        e: ThresholdEcdsaError,
+        dealing: &IDkgDealingInternal,
+        dealer_index: NodeIndex,
+        opener_index: NodeIndex,
+    ) -> Self {
+        match e {
+            ThresholdEcdsaError::InvalidCommitment
+            | ThresholdEcdsaError::InconsistentOpeningAndCommitment => Self::InconsistentShare {
+                dealer_index,
+                opener_index,
+                commitment_type: dealing.commitment.ctype(),
+            },
+            other => Self::InternalError(format!("{:?}", other)),
+        }
     }
 }
 
@@ -1046,7 +1550,7 @@ pub fn open_dealing(
     opener_secret_key: &MEGaPrivateKey,
     opener_public_key: &MEGaPublicKey,
 ) -> Result<CommitmentOpening, ThresholdOpenDealingInternalError> {
-    CommitmentOpening::open_dealing(
+    let result = CommitmentOpening::open_dealing(
         verified_dealing,
         associated_data,
         dealer_index,
@@ -1054,25 +1558,34 @@ pub fn open_dealing(
         opener_secret_key,
         opener_public_key,
     )
-    .map_err(|e| e.into())
+    .map_err(|e| {
+        ThresholdOpenDealingInternalError::from_threshold_ecdsa_error(
+            e,
+            verified_dealing,
+            dealer_index,
+            opener_index,
+        )
+    });
+
+    metrics::observe_dealing_opening(&result);
+
+    result
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ThresholdVerifyOpeningInternalError {
-    InvalidOpening,
-    MismatchingType,
+    InvalidOpening {
+        opener_index: NodeIndex,
+        commitment_type: PolynomialCommitmentType,
+    },
+    MismatchingType {
+        opener_index: NodeIndex,
+        commitment_type: PolynomialCommitmentType,
+        opening_type: PolynomialCommitmentType,
+    },
     InternalError(String),
 }
 
-impl From<ThresholdEcdsaError> for ThresholdVerifyOpeningInternalError {
-    fn from(e: ThresholdEcdsaError) -> Self {
-        match e {
-            ThresholdEcdsaError::InconsistentOpeningAndCommitment => Self::MismatchingType,
-            x => Self::InternalError(format!("{:?}", x)),
-        }
-    }
-}
-
 /// Verifies an opening of a dealing
 ///
 /// This checks that the opening received by a peer in response to a
@@ -1092,14 +1605,31 @@ pub fn verify_dealing_opening(
     opener_index: NodeIndex,
     opening: &CommitmentOpening,
 ) -> Result<(), ThresholdVerifyOpeningInternalError> {
-    let is_invalid = verified_dealing
+    let commitment_type = verified_dealing.commitment.ctype();
+
+    let result = verified_dealing
         .commitment
         .check_opening(opener_index, opening)
-        .is_err();
-    if is_invalid {
-        return Err(ThresholdVerifyOpeningInternalError::InvalidOpening);
-    }
-    Ok(())
+        .map_err(|e| match e {
+            ThresholdEcdsaError::InconsistentOpeningAndCommitment => {
+                ThresholdVerifyOpeningInternalError::MismatchingType {
+                    opener_index,
+                    commitment_type,
+                    opening_type: opening.ctype(),
+                }
+            }
+            ThresholdEcdsaError::InvalidCommitment => {
+                ThresholdVerifyOpeningInternalError::InvalidOpening {
+                    opener_index,
+                    commitment_type,
+                }
+            }
+            other => ThresholdVerifyOpeningInternalError::InternalError(format!("{:?}", other)),
+        });
+
+    metrics::observe_opening_verification(&result);
+
+    result
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -7,7 +7,8 @@ use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use strum_macros::EnumIter;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 #[cfg(test)]
 mod tests;
@@ -213,30 +214,32 @@ impl MEGaCiphertext {
 
     /// Simple type verification for MEGa ciphertexts
     ///
-    /// Verifies that the ciphertext is of the expected type (single or pairs)
-    /// and is for the expected curve.
+    /// Verifies that the ciphertext is of the expected type (single or pairs),
+    /// that the ephemeral key and proof of possession are on the curve of the
+    /// recipients' keys, and that the encrypted values are on the curve of
+    /// the plaintexts.
     pub fn verify_is(
         &self,
         ctype: MEGaCiphertextType,
-        curve: EccCurveType,
+        key_curve: EccCurveType,
+        plaintext_curve: EccCurveType,
     ) -> ThresholdEcdsaResult<()> {
-        if self.ephemeral_key().curve_type() != curve {
+        if self.ephemeral_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
-        if self.pop_public_key().curve_type() != curve {
+        if self.pop_public_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
-        if self.pop_proof().curve_type()? != curve {
+        if self.pop_proof().curve_type()? != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
         let curves_ok = match self {
-            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == curve),
-            MEGaCiphertext::Pairs(c) => c
-                .ctexts
-                .iter()
-                .all(|(x, y)| x.curve_type() == curve && y.curve_type() == curve),
+            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == plaintext_curve),
+            MEGaCiphertext::Pairs(c) => c.ctexts.iter().all(|(x, y)| {
+                x.curve_type() == plaintext_curve && y.curve_type() == plaintext_curve
+            }),
         };
 
         if !curves_ok {
@@ -312,10 +315,14 @@ impl From<MEGaCiphertextPair> for MEGaCiphertext {
     }
 }
 
+/// Check the plaintexts and recipients of a MEGa encryption
+///
+/// Returns the curve of the plaintexts and the curve of the recipients'
+/// keys, which need not be the same.
 fn check_plaintexts(
     plaintexts: &[EccScalar],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -336,19 +343,17 @@ fn check_plaintexts(
         }
     }
 
-    for recipient in recipients {
-        if recipient.curve_type() != curve_type {
-            return Err(ThresholdEcdsaError::CurveMismatch);
-        }
-    }
-
-    Ok(curve_type)
+    Ok((curve_type, check_recipients(recipients)?))
 }
 
+/// Check the plaintext pairs and recipients of a MEGa encryption
+///
+/// Returns the curve of the plaintexts and the curve of the recipients'
+/// keys, which need not be the same.
 fn check_plaintexts_pair(
     plaintexts: &[(EccScalar, EccScalar)],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -369,13 +374,22 @@ fn check_plaintexts_pair(
         }
     }
 
+    Ok((curve_type, check_recipients(recipients)?))
+}
+
+/// Check that all recipient keys are on the same curve, and return that curve
+///
+/// The recipients must be non-empty
+fn check_recipients(recipients: &[MEGaPublicKey]) -> ThresholdEcdsaResult<EccCurveType> {
+    let key_curve = recipients[0].curve_type();
+
     for recipient in recipients {
-        if recipient.curve_type() != curve_type {
+        if recipient.curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
     }
 
-    Ok(curve_type)
+    Ok(key_curve)
 }
 
 fn mega_hash_to_scalars(
@@ -386,9 +400,8 @@ fn mega_hash_to_scalars(
     public_key: &EccPoint,
     ephemeral_key: &EccPoint,
     shared_secret: &EccPoint,
+    plaintext_curve: EccCurveType,
 ) -> ThresholdEcdsaResult<Vec<EccScalar>> {
-    let curve_type = public_key.curve_type();
-
     let count = match ctype {
         MEGaCiphertextType::Single => 1,
         MEGaCiphertextType::Pairs => 2,
@@ -401,7 +414,7 @@ fn mega_hash_to_scalars(
     ro.add_point("public_key", public_key)?;
     ro.add_point("ephemeral_key", ephemeral_key)?;
     ro.add_point("shared_secret", shared_secret)?;
-    ro.output_scalars(curve_type, count)
+    ro.output_scalars(plaintext_curve, count)
 }
 
 /// Compute the Proof Of Possession (PoP) base element
@@ -493,12 +506,12 @@ impl MEGaCiphertextSingle {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts(plaintexts, recipients)?;
+        let (plaintext_curve, key_curve) = check_plaintexts(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Single;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -513,6 +526,7 @@ impl MEGaCiphertextSingle {
                 &pubkey.point,
                 &v,
                 &ubeta,
+                plaintext_curve,
             )?;
 
             let ctext = hm[0].add(ptext)?;
@@ -557,6 +571,8 @@ impl MEGaCiphertextSingle {
             ));
         }
 
+        let ctext = &self.ctexts[recipient_index as usize];
+
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Single,
             dealer_index,
@@ -565,9 +581,10 @@ impl MEGaCiphertextSingle {
             &recipient_public_key.point,
             &self.ephemeral_key,
             shared_secret,
+            ctext.curve_type(),
         )?;
 
-        self.ctexts[recipient_index as usize].sub(&hm[0])
+        ctext.sub(&hm[0])
     }
 
     pub fn decrypt(
@@ -604,12 +621,12 @@ impl MEGaCiphertextPair {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts_pair(plaintexts, recipients)?;
+        let (plaintext_curve, key_curve) = check_plaintexts_pair(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Pairs;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -624,6 +641,7 @@ impl MEGaCiphertextPair {
                 &pubkey.point,
                 &v,
                 &ubeta,
+                plaintext_curve,
             )?;
 
             let ctext0 = hm[0].add(&ptext.0)?;
@@ -669,6 +687,8 @@ impl MEGaCiphertextPair {
             ));
         }
 
+        let ctext = &self.ctexts[recipient_index as usize];
+
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Pairs,
             dealer_index,
@@ -677,10 +697,11 @@ impl MEGaCiphertextPair {
             &recipient_public_key.point,
             &self.ephemeral_key,
             shared_secret,
+            ctext.0.curve_type(),
         )?;
 
-        let ptext0 = self.ctexts[recipient_index as usize].0.sub(&hm[0])?;
-        let ptext1 = self.ctexts[recipient_index as usize].1.sub(&hm[1])?;
+        let ptext0 = ctext.0.sub(&hm[0])?;
+        let ptext1 = ctext.1.sub(&hm[1])?;
 
         Ok((ptext0, ptext1))
     }
@@ -761,9 +782,21 @@ macro_rules! generate_serializable_keyset {
                 }
             }
 
-            #[derive(Clone, Eq, PartialEq, Zeroize, ZeroizeOnDrop, Serialize, Deserialize)]
+            #[derive(Clone, Eq, Zeroize, ZeroizeOnDrop, Serialize, Deserialize)]
             pub struct [<MEGaPrivateKey $curve Bytes>](SecretArray<{ $priv_size }>);
 
+            impl ConstantTimeEq for [<MEGaPrivateKey $curve Bytes>] {
+                fn ct_eq(&self, other: &Self) -> Choice {
+                    self.0.expose_secret()[..].ct_eq(&other.0.expose_secret()[..])
+                }
+            }
+
+            impl PartialEq for [<MEGaPrivateKey $curve Bytes>] {
+                fn eq(&self, other: &Self) -> bool {
+                    self.ct_eq(other).into()
+                }
+            }
+
             impl [<MEGaPrivateKey $curve Bytes>] {
                 pub const SIZE: usize = $priv_size;
             }
@@ -780,7 +813,8 @@ macro_rules! generate_serializable_keyset {
                 fn try_from(key: &MEGaPrivateKey) -> ThresholdEcdsaSerializationResult<Self> {
                     match key.curve_type() {
                         EccCurveType::$curve => {
-                            let mut bits: [u8; Self::SIZE] = key.serialize().try_into().map_err(|e| {
+                            let serialized = Zeroizing::new(key.serialize());
+                            let mut bits: [u8; Self::SIZE] = serialized.as_slice().try_into().map_err(|e| {
                                 ThresholdEcdsaSerializationError(format!("{:?}", e))
                             })?;
 
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A snapshot of the complaint and opening counters
+///
+/// The counters are process wide and only ever increase. This crate does
+/// not depend on any metrics library; instead higher layers periodically
+/// call [`complaint_and_opening_metrics`] and export the values.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ComplaintAndOpeningMetrics {
+    /// Number of complaints generated against dealings
+    pub complaints_generated: u64,
+    /// Number of complaints which were verified to be valid
+    pub complaints_accepted: u64,
+    /// Number of complaints which were rejected as invalid
+    pub complaints_rejected: u64,
+    /// Number of dealings successfully opened in response to a complaint
+    pub dealings_opened: u64,
+    /// Number of dealings which could not be opened
+    pub dealing_openings_failed: u64,
+    /// Number of openings which were verified to be valid
+    pub openings_accepted: u64,
+    /// Number of openings which were rejected as invalid
+    pub openings_rejected: u64,
+}
+
+struct Counters {
+    complaints_generated: AtomicU64,
+    complaints_accepted: AtomicU64,
+    complaints_rejected: AtomicU64,
+    dealings_opened: AtomicU64,
+    dealing_openings_failed: AtomicU64,
+    openings_accepted: AtomicU64,
+    openings_rejected: AtomicU64,
+}
+
+static COUNTERS: Counters = Counters {
+    complaints_generated: AtomicU64::new(0),
+    complaints_accepted: AtomicU64::new(0),
+    complaints_rejected: AtomicU64::new(0),
+    dealings_opened: AtomicU64::new(0),
+    dealing_openings_failed: AtomicU64::new(0),
+    openings_accepted: AtomicU64::new(0),
+    openings_rejected: AtomicU64::new(0),
+};
+
+fn increment(counter: &AtomicU64, by: u64) {
+    counter.fetch_add(by, Ordering::Relaxed);
+}
+
+pub(crate) fn observe_complaints_generated(count: usize) {
+    increment(&COUNTERS.complaints_generated, count as u64);
+}
+
+pub(crate) fn observe_complaint_verification<T, E>(result: &Result<T, E>) {
+    match result {
+        Ok(_) => increment(&COUNTERS.complaints_accepted, 1),
+        Err(_) => increment(&COUNTERS.complaints_rejected, 1),
+    }
+}
+
+pub(crate) fn observe_dealing_opening<T, E>(result: &Result<T, E>) {
+    match result {
+        Ok(_) => increment(&COUNTERS.dealings_opened, 1),
+        Err(_) => increment(&COUNTERS.dealing_openings_failed, 1),
+    }
+}
+
+pub(crate) fn observe_opening_verification<T, E>(result: &Result<T, E>) {
+    match result {
+        Ok(_) => increment(&COUNTERS.openings_accepted, 1),
+        Err(_) => increment(&COUNTERS.openings_rejected, 1),
+    }
+}
+
+/// Return the current values of the complaint and opening counters
+pub fn complaint_and_opening_metrics() -> ComplaintAndOpeningMetrics {
+    let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+    ComplaintAndOpeningMetrics {
+        complaints_generated: get(&COUNTERS.complaints_generated),
+        complaints_accepted: get(&COUNTERS.complaints_accepted),
+        complaints_rejected: get(&COUNTERS.complaints_rejected),
+        dealings_opened: get(&COUNTERS.dealings_opened),
+        dealing_openings_failed: get(&COUNTERS.dealing_openings_failed),
+        openings_accepted: get(&COUNTERS.openings_accepted),
+        openings_rejected: get(&COUNTERS.openings_rejected),
+    }
+}
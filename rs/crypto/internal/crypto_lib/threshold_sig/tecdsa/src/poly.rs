@@ -4,6 +4,7 @@ use ic_types::crypto::canister_threshold_sig::idkg::IDkgOpening;
 use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
+use subtle::{Choice, ConstantTimeEq};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// A Polynomial whose coefficients are scalars in an elliptic curve group
@@ -281,12 +282,29 @@ impl Polynomial {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Eq, Serialize, Deserialize)]
 pub enum CommitmentOpening {
     Simple(EccScalar),
     Pedersen(EccScalar, EccScalar),
 }
 
+impl ConstantTimeEq for CommitmentOpening {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        // The type of an opening is public
+        match (self, other) {
+            (Self::Simple(x), Self::Simple(y)) => x.ct_eq(y),
+            (Self::Pedersen(x1, x2), Self::Pedersen(y1, y2)) => x1.ct_eq(y1) & x2.ct_eq(y2),
+            (_, _) => Choice::from(0),
+        }
+    }
+}
+
+impl PartialEq for CommitmentOpening {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
 impl CommitmentOpening {
     pub fn open_dealing(
         verified_dealing: &IDkgDealingInternal,
@@ -306,13 +324,20 @@ impl CommitmentOpening {
         )
     }
 
+    /// Return the type of commitment this opening is for
+    pub(crate) fn ctype(&self) -> PolynomialCommitmentType {
+        match self {
+            Self::Simple(_) => PolynomialCommitmentType::Simple,
+            Self::Pedersen(_, _) => PolynomialCommitmentType::Pedersen,
+        }
+    }
+
     pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
-        serde_cbor::to_vec(self).map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+        serialize_artifact(self)
     }
 
     pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
-        serde_cbor::from_slice::<Self>(bytes)
-            .map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+        deserialize_artifact(bytes)
     }
 }
 
@@ -365,12 +390,28 @@ impl TryFrom<&IDkgOpening> for CommitmentOpening {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[derive(Clone, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub enum CommitmentOpeningBytes {
     Simple(EccScalarBytes),
     Pedersen(EccScalarBytes, EccScalarBytes),
 }
 
+impl ConstantTimeEq for CommitmentOpeningBytes {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        match (self, other) {
+            (Self::Simple(x), Self::Simple(y)) => x.ct_eq(y),
+            (Self::Pedersen(x1, x2), Self::Pedersen(y1, y2)) => x1.ct_eq(y1) & x2.ct_eq(y2),
+            (_, _) => Choice::from(0),
+        }
+    }
+}
+
+impl PartialEq for CommitmentOpeningBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
 impl TryFrom<&CommitmentOpening> for CommitmentOpeningBytes {
     type Error = ThresholdEcdsaSerializationError;
 
@@ -521,7 +562,7 @@ impl PedersenCommitment {
 }
 
 /// The type of a commitment to a polynomial
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum PolynomialCommitmentType {
     Simple,
     Pedersen,
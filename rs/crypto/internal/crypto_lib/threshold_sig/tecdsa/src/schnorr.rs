@@ -0,0 +1,405 @@
+use crate::*;
+use ic_types::crypto::canister_threshold_sig::MasterEcdsaPublicKey;
+
+/// The length in bytes of a BIP-340 signature
+const BIP340_SIGNATURE_BYTES: usize = 64;
+
+/// Computes the BIP-340 tagged hash of the concatenation of `inputs`
+///
+/// This is SHA-256(SHA-256(tag) || SHA-256(tag) || inputs)
+fn bip340_tagged_hash(tag: &str, inputs: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = ic_crypto_sha2::Sha256::hash(tag.as_bytes());
+
+    let mut sha256 = ic_crypto_sha2::Sha256::new();
+    sha256.write(&tag_hash);
+    sha256.write(&tag_hash);
+    for input in inputs {
+        sha256.write(input);
+    }
+    sha256.finish()
+}
+
+/// Returns the BIP-340 encoding of a point, namely its affine x coordinate
+fn bip340_point_bytes(pt: &EccPoint) -> ThresholdEcdsaResult<Vec<u8>> {
+    Ok(pt.affine_x()?.as_bytes())
+}
+
+/// Returns true if the affine y coordinate of the point is even
+///
+/// BIP-340 only uses points with even y; a point with odd y is
+/// replaced by its negation, which shares the same x coordinate.
+fn has_even_y(pt: &EccPoint) -> ThresholdEcdsaResult<bool> {
+    Ok(pt.affine_y()?.sign() == 0)
+}
+
+/// Returns the point or its negation, whichever has even y
+fn force_even_y(pt: &EccPoint) -> ThresholdEcdsaResult<EccPoint> {
+    if has_even_y(pt)? {
+        Ok(pt.clone())
+    } else {
+        Ok(pt.negate())
+    }
+}
+
+/// Returns the scalar or its negation, depending on if the point has even y
+///
+/// If `pt` is the public commitment to `s` (i.e. `pt = s*G`) then the
+/// returned value is the discrete logarithm of `force_even_y(pt)`.
+fn negate_if_odd_y(s: &EccScalar, pt: &EccPoint) -> ThresholdEcdsaResult<EccScalar> {
+    if has_even_y(pt)? {
+        Ok(s.clone())
+    } else {
+        Ok(s.negate())
+    }
+}
+
+/// Computes the BIP-340 challenge e = H("BIP0340/challenge", R.x || P.x || m)
+fn bip340_challenge(
+    presig: &EccPoint,
+    public_key: &EccPoint,
+    message: &[u8],
+) -> ThresholdEcdsaResult<EccScalar> {
+    let e = bip340_tagged_hash(
+        "BIP0340/challenge",
+        &[
+            &bip340_point_bytes(presig)?,
+            &bip340_point_bytes(public_key)?,
+            message,
+        ],
+    );
+    EccScalar::from_bytes_wide(EccCurveType::K256, &e)
+}
+
+/// The values which all signers derive in the same way from the public inputs
+struct Bip340SigningContext {
+    /// The additive tweak of the derived key relative to the master key
+    key_tweak: EccScalar,
+    /// The derived public key, before forcing even y
+    public_key: EccPoint,
+    /// The additive rerandomization of the presignature
+    randomizer: EccScalar,
+    /// The rerandomized presignature, before forcing even y
+    presig: EccPoint,
+    /// The BIP-340 challenge
+    challenge: EccScalar,
+}
+
+impl Bip340SigningContext {
+    fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: &Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<Self> {
+        let curve_type = EccCurveType::K256;
+
+        let pre_sig = match &presig_transcript.combined_commitment {
+            CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => {
+                c.constant_term()
+            }
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let master_public_key = match &key_transcript.combined_commitment {
+            CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => {
+                c.constant_term()
+            }
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        if pre_sig.curve_type() != curve_type || master_public_key.curve_type() != curve_type {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        let (key_tweak, _chain_key) = derivation_path.derive_tweak(&master_public_key)?;
+        let public_key = master_public_key.add_points(&EccPoint::mul_by_g(&key_tweak))?;
+
+        let mut ro = ro::RandomOracle::new("ic-crypto-tschnorr-bip340-rerandomize-presig");
+        ro.add_bytestring("randomness", &randomness.get())?;
+        ro.add_bytestring("message", message)?;
+        ro.add_point("pre_sig", &pre_sig)?;
+        ro.add_scalar("key_tweak", &key_tweak)?;
+        let randomizer = ro.output_scalar(curve_type)?;
+
+        let presig = pre_sig.add_points(&EccPoint::mul_by_g(&randomizer))?;
+
+        if presig.is_infinity()? || public_key.is_infinity()? {
+            return Err(ThresholdEcdsaError::InvalidPoint);
+        }
+
+        let challenge = bip340_challenge(&presig, &public_key, message)?;
+
+        Ok(Self {
+            key_tweak,
+            public_key,
+            randomizer,
+            presig,
+            challenge,
+        })
+    }
+}
+
+/// A signature share of a threshold BIP-340 Schnorr signature
+///
+/// The share is a Shamir share of the `s` component of the signature.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdSchnorrSigShareInternal {
+    s: EccScalar,
+}
+
+impl ThresholdSchnorrSigShareInternal {
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        key_opening: &CommitmentOpening,
+        presig_transcript: &IDkgTranscriptInternal,
+        presig_opening: &CommitmentOpening,
+    ) -> ThresholdEcdsaResult<Self> {
+        let ctx = Bip340SigningContext::new(
+            derivation_path,
+            message,
+            &randomness,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let key_share = match key_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let presig_share = match presig_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        // Adding the same constant to every share of a polynomial adds the
+        // constant to the shared secret, since the Lagrange coefficients
+        // used for reconstruction sum to one.
+        let tweaked_key_share = negate_if_odd_y(&key_share.add(&ctx.key_tweak)?, &ctx.public_key)?;
+
+        let rerandomized_presig_share =
+            negate_if_odd_y(&presig_share.add(&ctx.randomizer)?, &ctx.presig)?;
+
+        let s = rerandomized_presig_share.add(&ctx.challenge.mul(&tweaked_key_share)?)?;
+
+        Ok(Self { s })
+    }
+
+    /// Verify a signature share
+    ///
+    /// This checks the share against the commitments of the key and
+    /// presignature transcripts evaluated at the signer's index.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        signer_index: NodeIndex,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let ctx = Bip340SigningContext::new(
+            derivation_path,
+            message,
+            &randomness,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        if self.s.curve_type() != EccCurveType::K256 {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        // The public commitments to the signer's shares of the tweaked key and
+        // of the rerandomized presignature, negated in the same way as the
+        // shares themselves
+        let key_j = key_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&ctx.key_tweak))?;
+        let key_j = if has_even_y(&ctx.public_key)? {
+            key_j
+        } else {
+            key_j.negate()
+        };
+
+        let presig_j = presig_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&ctx.randomizer))?;
+        let presig_j = if has_even_y(&ctx.presig)? {
+            presig_j
+        } else {
+            presig_j.negate()
+        };
+
+        let expected = presig_j.add_points(&key_j.scalar_mul(&ctx.challenge)?)?;
+
+        if EccPoint::mul_by_g_vartime(&self.s)? != expected {
+            return Err(ThresholdEcdsaError::InvalidSignatureShare);
+        }
+
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
+        serde_cbor::to_vec(self).map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+    }
+
+    pub fn deserialize(raw: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
+        serde_cbor::from_slice::<Self>(raw)
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+    }
+}
+
+/// A threshold BIP-340 Schnorr signature
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdSchnorrCombinedSigInternal {
+    r: EccPoint,
+    s: EccScalar,
+}
+
+impl ThresholdSchnorrCombinedSigInternal {
+    /// Serialize the signature in the 64 byte format specified by BIP-340
+    pub fn serialize(&self) -> Vec<u8> {
+        // r always has even y, so its compressed SEC1 encoding is the
+        // 0x02 header byte followed by the x coordinate
+        let r_bytes = self.r.serialize();
+        let s_bytes = self.s.serialize();
+
+        let mut sig = Vec::with_capacity(BIP340_SIGNATURE_BYTES);
+        sig.extend_from_slice(&r_bytes[1..]);
+        sig.extend_from_slice(&s_bytes);
+        sig
+    }
+
+    /// Deserialize a signature in the 64 byte format specified by BIP-340
+    pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
+        if bytes.len() != BIP340_SIGNATURE_BYTES {
+            return Err(ThresholdEcdsaSerializationError(
+                "Bad signature length".to_string(),
+            ));
+        }
+
+        // BIP-340 encodes R by its x coordinate only; R always has even y
+        let mut r_bytes = Vec::with_capacity(1 + BIP340_SIGNATURE_BYTES / 2);
+        r_bytes.push(0x02);
+        r_bytes.extend_from_slice(&bytes[..BIP340_SIGNATURE_BYTES / 2]);
+
+        let r = EccPoint::deserialize(EccCurveType::K256, &r_bytes)
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("Invalid r: {:?}", e)))?;
+
+        let s = EccScalar::deserialize(EccCurveType::K256, &bytes[BIP340_SIGNATURE_BYTES / 2..])
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("Invalid s: {:?}", e)))?;
+
+        Ok(Self { r, s })
+    }
+
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+        reconstruction_threshold: NumberOfNodes,
+        sig_shares: &BTreeMap<NodeIndex, ThresholdSchnorrSigShareInternal>,
+    ) -> ThresholdEcdsaResult<Self> {
+        let reconstruction_threshold = reconstruction_threshold.get() as usize;
+        if sig_shares.len() < reconstruction_threshold {
+            return Err(ThresholdEcdsaError::InsufficientDealings);
+        }
+
+        let ctx = Bip340SigningContext::new(
+            derivation_path,
+            message,
+            &randomness,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let mut x_values = Vec::with_capacity(reconstruction_threshold);
+        let mut samples = Vec::with_capacity(reconstruction_threshold);
+
+        for (index, sig_share) in sig_shares.iter().take(reconstruction_threshold) {
+            x_values.push(*index);
+            samples.push(sig_share.s.clone());
+        }
+
+        let coefficients = LagrangeCoefficients::at_zero(EccCurveType::K256, &x_values)?;
+        let s = coefficients.interpolate_scalar(&samples)?;
+
+        let r = force_even_y(&ctx.presig)?;
+
+        Ok(Self { r, s })
+    }
+
+    /// Verify a threshold BIP-340 signature
+    ///
+    /// This not only verifies the BIP-340 signature equation but also that
+    /// it was generated with a particular presignature transcript.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        presig_transcript: &IDkgTranscriptInternal,
+        key_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let ctx = Bip340SigningContext::new(
+            derivation_path,
+            message,
+            &randomness,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        if self.r.curve_type() != EccCurveType::K256 || self.s.curve_type() != EccCurveType::K256 {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        // The signature must commit to the rerandomized presignature
+        if self.r.affine_x()? != ctx.presig.affine_x()? || !has_even_y(&self.r)? {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        let public_key = force_even_y(&ctx.public_key)?;
+
+        // Check s*G - e*P == R
+        let rp = EccPoint::mul_2_points_vartime(
+            &EccPoint::generator_g(EccCurveType::K256),
+            &self.s,
+            &public_key,
+            &ctx.challenge.negate(),
+        )?;
+
+        if rp.is_infinity()? || rp != self.r {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the BIP-340 public key derived from `master_public_key` according
+/// to the `derivation_path`
+///
+/// The returned key is the 32 byte x-only encoding specified by BIP-340.
+pub fn derive_bip340_public_key(
+    master_public_key: &MasterEcdsaPublicKey,
+    derivation_path: &DerivationPath,
+) -> ThresholdEcdsaResult<Vec<u8>> {
+    let raw_master_pk = match master_public_key.algorithm_id {
+        AlgorithmId::ThresholdSchnorrBip340 => {
+            EccPoint::deserialize(EccCurveType::K256, &master_public_key.public_key)?
+        }
+        _ => return Err(ThresholdEcdsaError::CurveMismatch),
+    };
+
+    let (key_tweak, _chain_key) = derivation_path.derive_tweak(&raw_master_pk)?;
+    let public_key = raw_master_pk.add_points(&EccPoint::mul_by_g(&key_tweak))?;
+
+    bip340_point_bytes(&public_key)
+}
@@ -28,14 +28,21 @@ fn convert_hash_to_integer(
     EccScalar::from_bytes_wide(curve_type, hashed_message)
 }
 
+fn derive_key_tweak(
+    derivation_path: &DerivationPath,
+    key_transcript: &IDkgTranscriptInternal,
+) -> ThresholdEcdsaResult<EccScalar> {
+    let (key_tweak, _chain_key) = derivation_path.derive_tweak(&key_transcript.constant_term())?;
+    Ok(key_tweak)
+}
+
 fn derive_rho(
     curve_type: EccCurveType,
     hashed_message: &[u8],
     randomness: &Randomness,
-    derivation_path: &DerivationPath,
-    key_transcript: &IDkgTranscriptInternal,
+    key_tweak: &EccScalar,
     presig_transcript: &IDkgTranscriptInternal,
-) -> ThresholdEcdsaResult<(EccScalar, EccScalar, EccScalar, EccPoint)> {
+) -> ThresholdEcdsaResult<(EccScalar, EccScalar, EccPoint)> {
     let pre_sig = match &presig_transcript.combined_commitment {
         CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => c.constant_term(),
         _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
@@ -45,13 +52,11 @@ fn derive_rho(
         return Err(ThresholdEcdsaError::UnexpectedCommitmentType);
     }
 
-    let (key_tweak, _chain_key) = derivation_path.derive_tweak(&key_transcript.constant_term())?;
-
     let mut ro = ro::RandomOracle::new("ic-crypto-tecdsa-rerandomize-presig");
     ro.add_bytestring("randomness", &randomness.get())?;
     ro.add_bytestring("hashed_message", hashed_message)?;
     ro.add_point("pre_sig", &pre_sig)?;
-    ro.add_scalar("key_tweak", &key_tweak)?;
+    ro.add_scalar("key_tweak", key_tweak)?;
     let randomizer = ro.output_scalar(curve_type)?;
 
     // Rerandomize presignature
@@ -60,7 +65,7 @@ fn derive_rho(
 
     let rho = ecdsa_conversion_function(&randomized_pre_sig)?;
 
-    Ok((rho, key_tweak, randomizer, randomized_pre_sig))
+    Ok((rho, randomizer, randomized_pre_sig))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,12 +87,36 @@ impl ThresholdEcdsaSigShareInternal {
         key_times_lambda: &CommitmentOpening,
         curve_type: EccCurveType,
     ) -> ThresholdEcdsaResult<Self> {
-        let (rho, key_tweak, randomizer, _presig) = derive_rho(
+        let key_tweak = derive_key_tweak(derivation_path, key_transcript)?;
+
+        Self::new_with_key_tweak(
+            &key_tweak,
+            hashed_message,
+            randomness,
+            presig_transcript,
+            lambda,
+            kappa_times_lambda,
+            key_times_lambda,
+            curve_type,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_with_key_tweak(
+        key_tweak: &EccScalar,
+        hashed_message: &[u8],
+        randomness: Randomness,
+        presig_transcript: &IDkgTranscriptInternal,
+        lambda: &CommitmentOpening,
+        kappa_times_lambda: &CommitmentOpening,
+        key_times_lambda: &CommitmentOpening,
+        curve_type: EccCurveType,
+    ) -> ThresholdEcdsaResult<Self> {
+        let (rho, randomizer, _presig) = derive_rho(
             curve_type,
             hashed_message,
             &randomness,
-            derivation_path,
-            key_transcript,
+            key_tweak,
             presig_transcript,
         )?;
 
@@ -95,7 +124,7 @@ impl ThresholdEcdsaSigShareInternal {
         // a reduction if int(hashed_message) >= group_order
         let e = convert_hash_to_integer(hashed_message, curve_type)?;
 
-        let theta = e.add(&rho.mul(&key_tweak)?)?;
+        let theta = e.add(&rho.mul(key_tweak)?)?;
 
         let (lambda_value, lambda_mask) = match lambda {
             CommitmentOpening::Pedersen(lambda_value, lambda_mask) => (lambda_value, lambda_mask),
@@ -151,29 +180,57 @@ impl ThresholdEcdsaSigShareInternal {
         key_times_lambda: &IDkgTranscriptInternal,
         curve_type: EccCurveType,
     ) -> ThresholdEcdsaResult<()> {
-        // Compute rho and tweak
-        let (rho, key_tweak, randomizer, _presig) = derive_rho(
+        let key_tweak = derive_key_tweak(derivation_path, key_transcript)?;
+
+        self.verify_with_key_tweak(
+            &key_tweak,
+            hashed_message,
+            randomness,
+            signer_index,
+            presig_transcript,
+            lambda,
+            kappa_times_lambda,
+            key_times_lambda,
+            curve_type,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn verify_with_key_tweak(
+        &self,
+        key_tweak: &EccScalar,
+        hashed_message: &[u8],
+        randomness: Randomness,
+        signer_index: NodeIndex,
+        presig_transcript: &IDkgTranscriptInternal,
+        lambda: &IDkgTranscriptInternal,
+        kappa_times_lambda: &IDkgTranscriptInternal,
+        key_times_lambda: &IDkgTranscriptInternal,
+        curve_type: EccCurveType,
+    ) -> ThresholdEcdsaResult<()> {
+        // Compute rho
+        let (rho, randomizer, _presig) = derive_rho(
             curve_type,
             hashed_message,
             &randomness,
-            derivation_path,
-            key_transcript,
+            key_tweak,
             presig_transcript,
         )?;
 
         // Compute theta
         let e = convert_hash_to_integer(hashed_message, curve_type)?;
 
-        let theta = e.add(&rho.mul(&key_tweak)?)?;
+        let theta = e.add(&rho.mul(key_tweak)?)?;
 
         // Evaluate commitments at the receiver index
         let lambda_j = lambda.evaluate_at(signer_index)?;
         let kappa_times_lambda_j = kappa_times_lambda.evaluate_at(signer_index)?;
         let key_times_lambda_j = key_times_lambda.evaluate_at(signer_index)?;
 
-        let sigma_num = lambda_j
-            .scalar_mul(&theta)?
-            .add_points(&key_times_lambda_j.scalar_mul(&rho)?)?;
+        // The commitments and the scalars are public, so the vartime
+        // operations can be used
+        let sigma_num =
+            EccPoint::mul_2_points_vartime(&lambda_j, &theta, &key_times_lambda_j, &rho)?;
 
         let sigma_den = lambda_j
             .scalar_mul(&randomizer)?
@@ -181,7 +238,7 @@ impl ThresholdEcdsaSigShareInternal {
 
         match &self.sigma_numerator {
             CommitmentOpening::Pedersen(v, m) => {
-                if sigma_num != EccPoint::pedersen(v, m)? {
+                if sigma_num != EccPoint::pedersen_vartime(v, m)? {
                     return Err(ThresholdEcdsaError::InvalidCommitment);
                 }
             }
@@ -190,7 +247,7 @@ impl ThresholdEcdsaSigShareInternal {
 
         match &self.sigma_denominator {
             CommitmentOpening::Pedersen(v, m) => {
-                if sigma_den != EccPoint::pedersen(v, m)? {
+                if sigma_den != EccPoint::pedersen_vartime(v, m)? {
                     return Err(ThresholdEcdsaError::InvalidCommitment);
                 }
             }
@@ -201,6 +258,166 @@ impl ThresholdEcdsaSigShareInternal {
     }
 }
 
+/// The inputs of a single signature share of a batch
+///
+/// Each entry of a batch must use its own presignature quadruple
+/// (kappa, lambda, kappa_times_lambda, key_times_lambda). Only the key
+/// transcript is shared by all entries.
+#[derive(Debug, Clone)]
+pub struct ThresholdEcdsaSigShareBatchInput<'a> {
+    pub derivation_path: &'a DerivationPath,
+    pub hashed_message: &'a [u8],
+    pub randomness: Randomness,
+    pub presig_transcript: &'a IDkgTranscriptInternal,
+    pub lambda: &'a CommitmentOpening,
+    pub kappa_times_lambda: &'a CommitmentOpening,
+    pub key_times_lambda: &'a CommitmentOpening,
+}
+
+/// The public inputs used to verify a single signature share of a batch
+#[derive(Debug, Clone)]
+pub struct ThresholdEcdsaSigShareBatchVerificationInput<'a> {
+    pub derivation_path: &'a DerivationPath,
+    pub hashed_message: &'a [u8],
+    pub randomness: Randomness,
+    pub presig_transcript: &'a IDkgTranscriptInternal,
+    pub lambda: &'a IDkgTranscriptInternal,
+    pub kappa_times_lambda: &'a IDkgTranscriptInternal,
+    pub key_times_lambda: &'a IDkgTranscriptInternal,
+}
+
+/// Returns an error if two entries of a batch share a presignature
+///
+/// Reusing a presignature for two different messages reveals the key.
+fn check_distinct_presignatures<'a>(
+    presig_transcripts: impl Iterator<Item = &'a IDkgTranscriptInternal>,
+) -> ThresholdEcdsaResult<()> {
+    let mut seen: Vec<&IDkgTranscriptInternal> = Vec::new();
+
+    for presig_transcript in presig_transcripts {
+        if seen.contains(&presig_transcript) {
+            return Err(ThresholdEcdsaError::InvalidArguments(
+                "Presignature used more than once in a batch".to_string(),
+            ));
+        }
+        seen.push(presig_transcript);
+    }
+
+    Ok(())
+}
+
+/// Caches the key tweak of each distinct derivation path of a batch
+struct KeyTweakCache<'a> {
+    key_transcript: &'a IDkgTranscriptInternal,
+    tweaks: Vec<(&'a DerivationPath, EccScalar)>,
+}
+
+impl<'a> KeyTweakCache<'a> {
+    fn new(key_transcript: &'a IDkgTranscriptInternal) -> Self {
+        Self {
+            key_transcript,
+            tweaks: Vec::new(),
+        }
+    }
+
+    fn get(&mut self, derivation_path: &'a DerivationPath) -> ThresholdEcdsaResult<EccScalar> {
+        if let Some((_, tweak)) = self.tweaks.iter().find(|(p, _)| *p == derivation_path) {
+            return Ok(tweak.clone());
+        }
+
+        let tweak = derive_key_tweak(derivation_path, self.key_transcript)?;
+        self.tweaks.push((derivation_path, tweak.clone()));
+        Ok(tweak)
+    }
+}
+
+/// A batch of signature shares created by a single node
+///
+/// The shares are in the same order as the inputs they were created from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdEcdsaSigShareBatchInternal {
+    shares: Vec<ThresholdEcdsaSigShareInternal>,
+}
+
+impl ThresholdEcdsaSigShareBatchInternal {
+    pub(crate) fn new(
+        inputs: &[ThresholdEcdsaSigShareBatchInput],
+        key_transcript: &IDkgTranscriptInternal,
+        curve_type: EccCurveType,
+    ) -> ThresholdEcdsaResult<Self> {
+        check_distinct_presignatures(inputs.iter().map(|i| i.presig_transcript))?;
+
+        let mut key_tweaks = KeyTweakCache::new(key_transcript);
+        let mut shares = Vec::with_capacity(inputs.len());
+
+        for input in inputs {
+            let key_tweak = key_tweaks.get(input.derivation_path)?;
+
+            shares.push(ThresholdEcdsaSigShareInternal::new_with_key_tweak(
+                &key_tweak,
+                input.hashed_message,
+                input.randomness,
+                input.presig_transcript,
+                input.lambda,
+                input.kappa_times_lambda,
+                input.key_times_lambda,
+                curve_type,
+            )?);
+        }
+
+        Ok(Self { shares })
+    }
+
+    /// Verify a batch of signature shares
+    ///
+    /// The inputs must be given in the same order as when the batch was
+    /// created. Fails if the batch does not contain exactly one share
+    /// per input, or if any of the shares is invalid.
+    pub fn verify(
+        &self,
+        inputs: &[ThresholdEcdsaSigShareBatchVerificationInput],
+        signer_index: NodeIndex,
+        key_transcript: &IDkgTranscriptInternal,
+        curve_type: EccCurveType,
+    ) -> ThresholdEcdsaResult<()> {
+        if self.shares.len() != inputs.len() {
+            return Err(ThresholdEcdsaError::InvalidSignatureShare);
+        }
+
+        check_distinct_presignatures(inputs.iter().map(|i| i.presig_transcript))?;
+
+        let mut key_tweaks = KeyTweakCache::new(key_transcript);
+
+        for (share, input) in self.shares.iter().zip(inputs) {
+            let key_tweak = key_tweaks.get(input.derivation_path)?;
+
+            share.verify_with_key_tweak(
+                &key_tweak,
+                input.hashed_message,
+                input.randomness,
+                signer_index,
+                input.presig_transcript,
+                input.lambda,
+                input.kappa_times_lambda,
+                input.key_times_lambda,
+                curve_type,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Return the signature shares of this batch
+    pub fn shares(&self) -> &[ThresholdEcdsaSigShareInternal] {
+        &self.shares
+    }
+
+    /// Return the signature shares of this batch, consuming it
+    pub fn into_shares(self) -> Vec<ThresholdEcdsaSigShareInternal> {
+        self.shares
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ThresholdEcdsaCombinedSigInternal {
     r: EccScalar,
@@ -223,7 +440,7 @@ impl ThresholdEcdsaCombinedSigInternal {
         algorithm_id: AlgorithmId,
         bytes: &[u8],
     ) -> ThresholdEcdsaSerializationResult<Self> {
-        let curve_type = EccCurveType::from_algorithm(algorithm_id).ok_or_else(|| {
+        let curve_type = EccCurveType::from_ecdsa_algorithm(algorithm_id).ok_or_else(|| {
             ThresholdEcdsaSerializationError(format!(
                 "Invalid algorithm {:?} for threshold ECDSA",
                 algorithm_id
@@ -265,12 +482,13 @@ impl ThresholdEcdsaCombinedSigInternal {
             return Err(ThresholdEcdsaError::InsufficientDealings);
         }
 
-        let (rho, _key_tweak, _randomizer, _presig) = derive_rho(
+        let key_tweak = derive_key_tweak(derivation_path, key_transcript)?;
+
+        let (rho, _randomizer, _presig) = derive_rho(
             curve_type,
             hashed_message,
             &randomness,
-            derivation_path,
-            key_transcript,
+            &key_tweak,
             presig_transcript,
         )?;
 
@@ -348,12 +566,13 @@ impl ThresholdEcdsaCombinedSigInternal {
 
         let msg = convert_hash_to_integer(hashed_message, curve_type)?;
 
-        let (rho, key_tweak, _, pre_sig) = derive_rho(
+        let key_tweak = derive_key_tweak(derivation_path, key_transcript)?;
+
+        let (rho, _, pre_sig) = derive_rho(
             curve_type,
             hashed_message,
             &randomness,
-            derivation_path,
-            key_transcript,
+            &key_tweak,
             presig_transcript,
         )?;
 
@@ -379,7 +598,12 @@ impl ThresholdEcdsaCombinedSigInternal {
         let u1 = msg.mul(&s_inv)?;
         let u2 = self.r.mul(&s_inv)?;
 
-        let rp = EccPoint::mul_2_points(&EccPoint::generator_g(curve_type), &u1, &public_key, &u2)?;
+        let rp = EccPoint::mul_2_points_vartime(
+            &EccPoint::generator_g(curve_type),
+            &u1,
+            &public_key,
+            &u2,
+        )?;
 
         if rp.is_infinity()? {
             return Err(ThresholdEcdsaError::InvalidSignature);
@@ -421,6 +645,9 @@ pub fn derive_public_key(
         AlgorithmId::EcdsaSecp256k1 => {
             EccPoint::deserialize(EccCurveType::K256, &master_public_key.public_key)?
         }
+        AlgorithmId::EcdsaP256 => {
+            EccPoint::deserialize(EccCurveType::P256, &master_public_key.public_key)?
+        }
         _ => return Err(ThresholdEcdsaError::CurveMismatch),
     };
     // Compute tweak
@@ -0,0 +1,325 @@
+//! Generation of the fixed encodings in `tests/data`
+//!
+//! The test `verify_fixed_serialization_continues_to_be_accepted` checks
+//! that encodings produced by earlier versions of this crate can still
+//! be deserialized. This module runs the protocol deterministically and
+//! returns the hex encodings of each artifact checked by that test, for
+//! all supported curves.
+//!
+//! Existing fixtures must never be replaced by newly generated ones, as
+//! that would defeat the purpose of the test. Only add the fixtures
+//! which are missing, for instance for a newly supported curve.
+
+use crate::*;
+use rand::{Rng, RngCore};
+
+const RECEIVERS: usize = 4;
+const THRESHOLD: usize = 2;
+
+/// The hex encoding of a single protocol artifact
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TestVector {
+    /// The name of the file in `tests/data`, for example `dealing_random_p256.hex`
+    pub file_name: String,
+    /// The hex encoded serialization of the artifact
+    pub hex: String,
+}
+
+fn internal_error<E: std::fmt::Debug>(e: E) -> ThresholdEcdsaError {
+    ThresholdEcdsaError::InvalidArguments(format!("{:?}", e))
+}
+
+struct TranscriptRound {
+    dealings: BTreeMap<NodeIndex, IDkgDealingInternal>,
+    transcript: IDkgTranscriptInternal,
+    openings: Vec<CommitmentOpening>,
+}
+
+struct Setup {
+    alg: AlgorithmId,
+    ad: Vec<u8>,
+    pk: Vec<MEGaPublicKey>,
+    sk: Vec<MEGaPrivateKey>,
+    seed: Seed,
+}
+
+impl Setup {
+    fn new(curve: EccCurveType, seed: Seed) -> ThresholdEcdsaResult<Self> {
+        let alg = match curve {
+            EccCurveType::K256 => AlgorithmId::ThresholdEcdsaSecp256k1,
+            EccCurveType::P256 => AlgorithmId::ThresholdEcdsaSecp256r1,
+        };
+
+        let rng = &mut seed.derive("setup").into_rng();
+        let mut ad = vec![0u8; 32];
+        rng.fill_bytes(&mut ad);
+
+        let mut pk = Vec::with_capacity(RECEIVERS);
+        let mut sk = Vec::with_capacity(RECEIVERS);
+
+        for _ in 0..RECEIVERS {
+            let (public_key, private_key) =
+                gen_keypair(curve.mega_key_curve(), Seed::from_rng(rng));
+            pk.push(public_key);
+            sk.push(private_key);
+        }
+
+        Ok(Self {
+            alg,
+            ad,
+            pk,
+            sk,
+            seed,
+        })
+    }
+
+    fn threshold(&self) -> NumberOfNodes {
+        NumberOfNodes::from(THRESHOLD as u32)
+    }
+
+    fn run(
+        &self,
+        name: &str,
+        shares: &[SecretShares],
+        mode: &IDkgTranscriptOperationInternal,
+    ) -> ThresholdEcdsaResult<TranscriptRound> {
+        let mut dealings = BTreeMap::new();
+
+        for (dealer_index, share) in shares.iter().enumerate() {
+            let dealer_index = dealer_index as NodeIndex;
+
+            let dealing = create_dealing(
+                self.alg,
+                &self.ad,
+                dealer_index,
+                self.threshold(),
+                &self.pk,
+                share,
+                self.seed
+                    .derive(&format!("{}-dealing-{}", name, dealer_index)),
+            )
+            .map_err(internal_error)?;
+
+            dealings.insert(dealer_index, dealing);
+        }
+
+        let transcript = create_transcript(self.alg, self.threshold(), &dealings, mode)
+            .map_err(internal_error)?;
+
+        let mut openings = Vec::with_capacity(RECEIVERS);
+
+        for receiver in 0..RECEIVERS {
+            let opening = compute_secret_shares(
+                &dealings,
+                &transcript,
+                &self.ad,
+                receiver as NodeIndex,
+                &self.sk[receiver],
+                &self.pk[receiver],
+            )
+            .map_err(internal_error)?;
+
+            openings.push(opening);
+        }
+
+        Ok(TranscriptRound {
+            dealings,
+            transcript,
+            openings,
+        })
+    }
+
+    fn random(&self, name: &str) -> ThresholdEcdsaResult<TranscriptRound> {
+        self.run(
+            name,
+            &vec![SecretShares::Random; RECEIVERS],
+            &IDkgTranscriptOperationInternal::Random,
+        )
+    }
+
+    fn reshare(
+        &self,
+        name: &str,
+        round: &TranscriptRound,
+    ) -> ThresholdEcdsaResult<TranscriptRound> {
+        let shares = round
+            .openings
+            .iter()
+            .map(|opening| match opening {
+                CommitmentOpening::Simple(v) => SecretShares::ReshareOfUnmasked(v.clone()),
+                CommitmentOpening::Pedersen(v, m) => {
+                    SecretShares::ReshareOfMasked(v.clone(), m.clone())
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.run(
+            name,
+            &shares,
+            &IDkgTranscriptOperationInternal::reshare_of(&round.transcript),
+        )
+    }
+
+    fn multiply(
+        &self,
+        name: &str,
+        lhs: &TranscriptRound,
+        rhs: &TranscriptRound,
+    ) -> ThresholdEcdsaResult<TranscriptRound> {
+        let mut shares = Vec::with_capacity(RECEIVERS);
+
+        for (l, r) in lhs.openings.iter().zip(rhs.openings.iter()) {
+            match (l, r) {
+                (CommitmentOpening::Simple(lv), CommitmentOpening::Pedersen(rv, rm)) => shares
+                    .push(SecretShares::UnmaskedTimesMasked(
+                        lv.clone(),
+                        (rv.clone(), rm.clone()),
+                    )),
+                _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+            }
+        }
+
+        let mode = IDkgTranscriptOperationInternal::UnmaskedTimesMasked(
+            lhs.transcript.combined_commitment.commitment().clone(),
+            rhs.transcript.combined_commitment.commitment().clone(),
+        );
+
+        self.run(name, &shares, &mode)
+    }
+
+    fn complaint(&self, round: &TranscriptRound) -> ThresholdEcdsaResult<IDkgComplaintInternal> {
+        let complainer = 0;
+
+        let mut dealings = round.dealings.clone();
+        let dealing = dealings
+            .get_mut(&0)
+            .ok_or(ThresholdEcdsaError::InsufficientDealings)?;
+        *dealing = test_utils::corrupt_dealing(
+            dealing,
+            &[complainer],
+            self.seed.derive("corrupt-dealing"),
+        )?;
+
+        let complaints = generate_complaints(
+            &dealings,
+            &self.ad,
+            complainer,
+            &self.sk[complainer as usize],
+            &self.pk[complainer as usize],
+            self.seed.derive("complaint"),
+        )
+        .map_err(internal_error)?;
+
+        complaints
+            .into_values()
+            .next()
+            .ok_or(ThresholdEcdsaError::InvalidComplaint)
+    }
+}
+
+fn curve_suffix(curve: EccCurveType) -> &'static str {
+    // The K256 fixtures predate P256 support and have no suffix
+    match curve {
+        EccCurveType::K256 => "",
+        EccCurveType::P256 => "_p256",
+    }
+}
+
+/// Generate the test vectors for a single curve
+pub fn generate_test_vectors_for_curve(
+    curve: EccCurveType,
+    seed: Seed,
+) -> ThresholdEcdsaResult<Vec<TestVector>> {
+    let setup = Setup::new(curve, seed.derive(&curve.to_string()))?;
+
+    let mut vectors = Vec::new();
+
+    let mut push = |name: &str, bytes: Vec<u8>| {
+        vectors.push(TestVector {
+            file_name: format!("{}{}.hex", name, curve_suffix(curve)),
+            hex: hex::encode(bytes),
+        });
+    };
+
+    let random = setup.random("random")?;
+    let reshare_of_masked = setup.reshare("reshare_of_masked", &random)?;
+    let reshare_of_unmasked = setup.reshare("reshare_of_unmasked", &reshare_of_masked)?;
+    let lambda = setup.random("lambda")?;
+    let multiply = setup.multiply("multiply", &reshare_of_unmasked, &lambda)?;
+
+    let kappa = setup.random("kappa")?;
+    let kappa = setup.reshare("kappa_unmasked", &kappa)?;
+    let kappa_times_lambda = setup.multiply("kappa_times_lambda", &kappa, &lambda)?;
+
+    for (name, round) in [
+        ("random", &random),
+        ("reshare_of_masked", &reshare_of_masked),
+        ("reshare_of_unmasked", &reshare_of_unmasked),
+        ("multiply", &multiply),
+    ] {
+        let dealing = round
+            .dealings
+            .values()
+            .next()
+            .ok_or(ThresholdEcdsaError::InsufficientDealings)?;
+
+        push(
+            &format!("dealing_{}", name),
+            dealing.serialize().map_err(internal_error)?,
+        );
+        push(
+            &format!("transcript_{}", name),
+            round.transcript.serialize().map_err(internal_error)?,
+        );
+    }
+
+    push(
+        "opening_simple",
+        reshare_of_unmasked.openings[0]
+            .serialize()
+            .map_err(internal_error)?,
+    );
+    push(
+        "opening_pedersen",
+        random.openings[0].serialize().map_err(internal_error)?,
+    );
+
+    push(
+        "complaint",
+        setup
+            .complaint(&random)?
+            .serialize()
+            .map_err(internal_error)?,
+    );
+
+    let hashed_message = ic_crypto_sha2::Sha256::hash(b"ic-crypto-tecdsa-testvec-message");
+    let randomness = Randomness::from(seed.derive("randomness").into_rng().gen::<[u8; 32]>());
+
+    let sig_share = sign_share(
+        &DerivationPath::new_bip32(&[1, 2, 3]),
+        &hashed_message,
+        randomness,
+        &reshare_of_unmasked.transcript,
+        &kappa.transcript,
+        &lambda.openings[0],
+        &kappa_times_lambda.openings[0],
+        &multiply.openings[0],
+        setup.alg,
+    )
+    .map_err(internal_error)?;
+
+    push("sig_share", sig_share.serialize().map_err(internal_error)?);
+
+    Ok(vectors)
+}
+
+/// Generate the test vectors for all supported curves
+pub fn generate_test_vectors(seed: Seed) -> ThresholdEcdsaResult<Vec<TestVector>> {
+    let mut vectors = Vec::new();
+
+    for curve in EccCurveType::all() {
+        vectors.extend(generate_test_vectors_for_curve(curve, seed.clone())?);
+    }
+
+    Ok(vectors)
+}
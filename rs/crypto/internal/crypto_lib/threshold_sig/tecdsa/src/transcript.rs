@@ -14,12 +14,11 @@ pub struct IDkgTranscriptInternal {
 
 impl IDkgTranscriptInternal {
     pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
-        serde_cbor::to_vec(self).map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+        serialize_artifact(self)
     }
 
     pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
-        serde_cbor::from_slice::<Self>(bytes)
-            .map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+        deserialize_artifact(bytes)
     }
 
     pub fn constant_term(&self) -> EccPoint {
@@ -98,6 +97,21 @@ pub enum IDkgTranscriptOperationInternal {
     UnmaskedTimesMasked(PolynomialCommitment, PolynomialCommitment),
 }
 
+impl IDkgTranscriptOperationInternal {
+    /// Return the operation which reshares `transcript`
+    ///
+    /// A masked (Pedersen) transcript is reshared as `ReshareOfMasked`,
+    /// an unmasked (simple) transcript as `ReshareOfUnmasked`.
+    pub fn reshare_of(transcript: &IDkgTranscriptInternal) -> Self {
+        let commitment = transcript.combined_commitment.commitment().clone();
+
+        match commitment {
+            PolynomialCommitment::Pedersen(_) => Self::ReshareOfMasked(commitment),
+            PolynomialCommitment::Simple(_) => Self::ReshareOfUnmasked(commitment),
+        }
+    }
+}
+
 impl TryFrom<&IDkgTranscriptOperation> for IDkgTranscriptOperationInternal {
     type Error = ThresholdEcdsaSerializationError;
 
@@ -406,7 +420,7 @@ impl CommitmentOpening {
         secret_key: &MEGaPrivateKey,
         public_key: &MEGaPublicKey,
     ) -> Result<Self, IDkgComputeSecretSharesInternalError> {
-        let curve = secret_key.curve_type();
+        let curve = transcript_commitment.commitment().curve_type();
         let mut openings = Vec::with_capacity(verified_dealings.len());
 
         for (dealer_index, dealing) in verified_dealings {
@@ -484,7 +498,7 @@ impl CommitmentOpening {
         secret_key: &MEGaPrivateKey,
         public_key: &MEGaPublicKey,
     ) -> Result<Self, IDkgComputeSecretSharesInternalError> {
-        let curve = secret_key.curve_type();
+        let curve = transcript_commitment.commitment().curve_type();
         let mut openings = Vec::with_capacity(verified_dealings.len());
 
         for (dealer_index, dealing) in verified_dealings {
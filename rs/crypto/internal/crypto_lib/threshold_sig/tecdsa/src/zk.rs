@@ -67,9 +67,7 @@ impl ProofOfEqualOpeningsInstance {
 
     fn recover_commitment(&self, proof: &ProofOfEqualOpenings) -> ThresholdEcdsaResult<EccPoint> {
         let amb = self.a.sub_points(&self.b)?;
-        let c_amb = amb.scalar_mul(&proof.challenge)?;
-        let h_c = self.h.scalar_mul(&proof.response)?;
-        h_c.sub_points(&c_amb)
+        EccPoint::mul_2_points_vartime(&self.h, &proof.response, &amb, &proof.challenge.negate())
     }
 
     fn hash_to_challenge(
@@ -214,12 +212,19 @@ impl ProofOfProductInstance {
         &self,
         proof: &ProofOfProduct,
     ) -> ThresholdEcdsaResult<(EccPoint, EccPoint)> {
-        let r1_com = EccPoint::mul_by_g(&proof.response1)
-            .sub_points(&self.lhs_com.scalar_mul(&proof.challenge)?)?;
+        let nchallenge = proof.challenge.negate();
+
+        let r1_com =
+            EccPoint::mul_2_points_vartime(&self.g, &proof.response1, &self.lhs_com, &nchallenge)?;
 
-        let r2_com =
-            EccPoint::mul_2_points(&self.rhs_com, &proof.response1, &self.h, &proof.response2)?
-                .sub_points(&self.product_com.scalar_mul(&proof.challenge)?)?;
+        let rhs_com = self.rhs_com.with_lut()?;
+        let h = self.h.with_lut()?;
+        let product_com = self.product_com.with_lut()?;
+        let r2_com = EccPoint::mul_n_points_vartime(&[
+            (&rhs_com, &proof.response1),
+            (&h, &proof.response2),
+            (&product_com, &nchallenge),
+        ])?;
 
         Ok((r1_com, r2_com))
     }
@@ -359,8 +364,8 @@ impl ProofOfDLogEquivalenceInstance {
     ) -> ThresholdEcdsaResult<(EccPoint, EccPoint)> {
         let nchallenge = proof.challenge.negate();
 
-        let g_r = EccPoint::mul_2_points(&self.g, &proof.response, &self.g_x, &nchallenge)?;
-        let h_r = EccPoint::mul_2_points(&self.h, &proof.response, &self.h_x, &nchallenge)?;
+        let g_r = EccPoint::mul_2_points_vartime(&self.g, &proof.response, &self.g_x, &nchallenge)?;
+        let h_r = EccPoint::mul_2_points_vartime(&self.h, &proof.response, &self.h_x, &nchallenge)?;
 
         Ok((g_r, h_r))
     }
@@ -161,7 +161,24 @@ fn should_complaint_system_work() -> ThresholdEcdsaResult<()> {
 
         assert_eq!(
             verify_dealing_opening(dealing, opener_index, &corrupted_opening).unwrap_err(),
-            ThresholdVerifyOpeningInternalError::InvalidOpening
+            ThresholdVerifyOpeningInternalError::InvalidOpening {
+                opener_index,
+                commitment_type: PolynomialCommitmentType::Pedersen,
+            }
+        );
+
+        let mistyped_opening = match &opening {
+            CommitmentOpening::Pedersen(value, _mask) => CommitmentOpening::Simple(value.clone()),
+            CommitmentOpening::Simple(_) => panic!("Random dealings have Pedersen commitments"),
+        };
+
+        assert_eq!(
+            verify_dealing_opening(dealing, opener_index, &mistyped_opening).unwrap_err(),
+            ThresholdVerifyOpeningInternalError::MismatchingType {
+                opener_index,
+                commitment_type: PolynomialCommitmentType::Pedersen,
+                opening_type: PolynomialCommitmentType::Simple,
+            }
         );
     }
 
@@ -247,3 +264,142 @@ fn should_complaint_verification_reject_spurious_complaints() -> ThresholdEcdsaR
 
     Ok(())
 }
+
+#[test]
+fn should_complaint_errors_identify_the_dealer_and_complainer() -> ThresholdEcdsaResult<()> {
+    let curve = EccCurveType::K256;
+    let associated_data = b"assoc_data_test";
+
+    let rng = &mut reproducible_rng();
+
+    let sk0 = MEGaPrivateKey::generate(curve, rng);
+    let pk0 = sk0.public_key();
+
+    let sk1 = MEGaPrivateKey::generate(curve, rng);
+    let pk1 = sk1.public_key();
+
+    let dealer_index = 3;
+    let complainer_index = 0;
+    let threshold = 1;
+
+    let dealing = IDkgDealingInternal::new(
+        &SecretShares::Random,
+        curve,
+        Seed::from_rng(rng),
+        threshold,
+        &[pk0.clone(), pk1.clone()],
+        dealer_index,
+        associated_data,
+    )?;
+
+    let metrics_before = complaint_and_opening_metrics();
+
+    // No complaint is possible against a correct dealing
+    let dealings = BTreeMap::from([(dealer_index, dealing.clone())]);
+
+    assert_eq!(
+        generate_complaints(
+            &dealings,
+            associated_data,
+            complainer_index,
+            &sk0,
+            &pk0,
+            Seed::from_rng(rng),
+        )
+        .unwrap_err(),
+        IDkgGenerateComplaintsInternalError::NoComplaintsRequired {
+            receiver_index: complainer_index
+        }
+    );
+
+    let corrupted_dealing =
+        test_utils::corrupt_dealing(&dealing, &[complainer_index], Seed::from_rng(rng))?;
+    let dealings = BTreeMap::from([(dealer_index, corrupted_dealing.clone())]);
+
+    let complaints = generate_complaints(
+        &dealings,
+        associated_data,
+        complainer_index,
+        &sk0,
+        &pk0,
+        Seed::from_rng(rng),
+    )
+    .expect("failed to generate complaints");
+
+    let complaint = complaints.get(&dealer_index).expect("missing complaint");
+
+    assert_eq!(
+        verify_complaint(
+            complaint,
+            complainer_index,
+            &pk0,
+            &corrupted_dealing,
+            dealer_index,
+            associated_data,
+        ),
+        Ok(())
+    );
+
+    // The complaint does not verify for a different complainer
+    assert_eq!(
+        verify_complaint(
+            complaint,
+            complainer_index,
+            &pk1,
+            &corrupted_dealing,
+            dealer_index,
+            associated_data,
+        )
+        .unwrap_err(),
+        IDkgVerifyComplaintInternalError::InvalidComplaint {
+            dealer_index,
+            complainer_index,
+            reason: ComplaintRejectionReason::InvalidProof,
+        }
+    );
+
+    // An honest opener can open the dealing and the opening is accepted
+    let opener_index = 1;
+    let opening = open_dealing(
+        &corrupted_dealing,
+        associated_data,
+        dealer_index,
+        opener_index,
+        &sk1,
+        &pk1,
+    )
+    .expect("failed to open dealing");
+
+    assert_eq!(
+        verify_dealing_opening(&corrupted_dealing, opener_index, &opening),
+        Ok(())
+    );
+
+    // The complainer's share cannot be opened
+    assert!(matches!(
+        open_dealing(
+            &corrupted_dealing,
+            associated_data,
+            dealer_index,
+            complainer_index,
+            &sk0,
+            &pk0,
+        ),
+        Err(ThresholdOpenDealingInternalError::InconsistentShare {
+            dealer_index: 3,
+            opener_index: 0,
+            commitment_type: PolynomialCommitmentType::Pedersen,
+        })
+    ));
+
+    // Other tests may run concurrently, so only check lower bounds
+    let metrics_after = complaint_and_opening_metrics();
+    assert!(metrics_after.complaints_generated > metrics_before.complaints_generated);
+    assert!(metrics_after.complaints_accepted > metrics_before.complaints_accepted);
+    assert!(metrics_after.complaints_rejected > metrics_before.complaints_rejected);
+    assert!(metrics_after.dealings_opened > metrics_before.dealings_opened);
+    assert!(metrics_after.dealing_openings_failed > metrics_before.dealing_openings_failed);
+    assert!(metrics_after.openings_accepted > metrics_before.openings_accepted);
+
+    Ok(())
+}
@@ -493,7 +493,12 @@ mod privately_verify_dealing {
         let rng = &mut reproducible_rng();
         let setup = Setup::new_with_k256_keys_and_dealing(rng);
         for algorithm_id in AlgorithmId::iter() {
-            if algorithm_id != AlgorithmId::ThresholdEcdsaSecp256k1 {
+            if !matches!(
+                algorithm_id,
+                AlgorithmId::ThresholdEcdsaSecp256k1
+                    | AlgorithmId::ThresholdEcdsaSecp256r1
+                    | AlgorithmId::ThresholdSchnorrBip340
+            ) {
                 assert_eq!(
                     privately_verify_dealing(
                         algorithm_id,
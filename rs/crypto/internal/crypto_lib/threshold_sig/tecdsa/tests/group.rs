@@ -276,6 +276,78 @@ fn test_mul_2_is_correct() -> ThresholdEcdsaResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_mul_2_vartime_is_consistent_with_mul_2() -> ThresholdEcdsaResult<()> {
+    let rng = &mut reproducible_rng();
+
+    for curve_type in EccCurveType::all() {
+        let g = EccPoint::generator_g(curve_type);
+
+        for _iteration in 0..100 {
+            let p_0 = g.scalar_mul(&EccScalar::random(curve_type, rng))?;
+            let mut p_1 = g.scalar_mul(&EccScalar::random(curve_type, rng))?;
+            // a mix of points with and without a LUT
+            p_1.precompute(EccPoint::MIN_LUT_WINDOW_SIZE)?;
+
+            let s_0 = EccScalar::random(curve_type, rng);
+            let s_1 = EccScalar::random(curve_type, rng);
+
+            assert_eq!(
+                EccPoint::mul_2_points_vartime(&p_0, &s_0, &p_1, &s_1)?,
+                EccPoint::mul_2_points(&p_0, &s_0, &p_1, &s_1)?
+            );
+        }
+
+        let zero = EccScalar::zero(curve_type);
+        assert_eq!(
+            EccPoint::mul_2_points_vartime(&g, &zero, &g, &zero)?,
+            EccPoint::identity(curve_type)
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_pedersen_and_mul_by_g_vartime_are_consistent() -> ThresholdEcdsaResult<()> {
+    let rng = &mut reproducible_rng();
+
+    for curve_type in EccCurveType::all() {
+        for _iteration in 0..100 {
+            let s_0 = EccScalar::random(curve_type, rng);
+            let s_1 = EccScalar::random(curve_type, rng);
+
+            assert_eq!(
+                EccPoint::pedersen_vartime(&s_0, &s_1)?,
+                EccPoint::pedersen(&s_0, &s_1)?
+            );
+            assert_eq!(EccPoint::mul_by_g_vartime(&s_0)?, EccPoint::mul_by_g(&s_0));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_vartime_ops_reject_mismatched_curves() -> ThresholdEcdsaResult<()> {
+    let rng = &mut reproducible_rng();
+
+    let k256 = EccScalar::random(EccCurveType::K256, rng);
+    let p256 = EccScalar::random(EccCurveType::P256, rng);
+    let g = EccPoint::generator_g(EccCurveType::K256);
+
+    assert_eq!(
+        EccPoint::mul_2_points_vartime(&g, &k256, &g, &p256),
+        Err(ThresholdEcdsaError::CurveMismatch)
+    );
+    assert_eq!(
+        EccPoint::pedersen_vartime(&k256, &p256),
+        Err(ThresholdEcdsaError::CurveMismatch)
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_mul_n_ct_pippenger_is_correct() -> ThresholdEcdsaResult<()> {
     let rng = &mut reproducible_rng();
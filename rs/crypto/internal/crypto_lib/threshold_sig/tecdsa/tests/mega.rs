@@ -115,6 +115,84 @@ fn mega_single_smoke_test() -> Result<(), ThresholdEcdsaError> {
     Ok(())
 }
 
+#[test]
+fn mega_can_encrypt_p256_plaintexts_to_k256_keys() -> Result<(), ThresholdEcdsaError> {
+    let key_curve = EccCurveType::K256;
+    let plaintext_curve = EccCurveType::P256;
+
+    let rng = &mut reproducible_rng();
+
+    let a_sk = MEGaPrivateKey::generate(key_curve, rng);
+    let b_sk = MEGaPrivateKey::generate(key_curve, rng);
+
+    let a_pk = a_sk.public_key();
+    let b_pk = b_sk.public_key();
+
+    let associated_data = b"assoc_data_test";
+    let dealer_index = 0;
+
+    let ptext_for_a = EccScalar::random(plaintext_curve, rng);
+    let ptext_for_b = EccScalar::random(plaintext_curve, rng);
+
+    let ctext = MEGaCiphertextSingle::encrypt(
+        Seed::from_rng(rng),
+        &[ptext_for_a.clone(), ptext_for_b.clone()],
+        &[a_pk.clone(), b_pk.clone()],
+        dealer_index,
+        associated_data,
+    )?;
+
+    MEGaCiphertext::from(ctext.clone()).verify_is(
+        MEGaCiphertextType::Single,
+        key_curve,
+        plaintext_curve,
+    )?;
+
+    assert_eq!(
+        ctext.decrypt(associated_data, dealer_index, 0, &a_sk, &a_pk)?,
+        ptext_for_a
+    );
+    assert_eq!(
+        ctext.decrypt(associated_data, dealer_index, 1, &b_sk, &b_pk)?,
+        ptext_for_b
+    );
+
+    let ptext_for_a = (
+        EccScalar::random(plaintext_curve, rng),
+        EccScalar::random(plaintext_curve, rng),
+    );
+    let ptext_for_b = (
+        EccScalar::random(plaintext_curve, rng),
+        EccScalar::random(plaintext_curve, rng),
+    );
+
+    let ctext = MEGaCiphertextPair::encrypt(
+        Seed::from_rng(rng),
+        &[ptext_for_a.clone(), ptext_for_b.clone()],
+        &[a_pk.clone(), b_pk.clone()],
+        dealer_index,
+        associated_data,
+    )?;
+
+    let mega = MEGaCiphertext::from(ctext.clone());
+    mega.verify_is(MEGaCiphertextType::Pairs, key_curve, plaintext_curve)?;
+    assert_eq!(
+        mega.verify_is(MEGaCiphertextType::Pairs, key_curve, key_curve),
+        Err(ThresholdEcdsaError::CurveMismatch)
+    );
+
+    assert_eq!(
+        ctext.decrypt(associated_data, dealer_index, 0, &a_sk, &a_pk)?,
+        ptext_for_a
+    );
+    assert_eq!(
+        ctext.decrypt(associated_data, dealer_index, 1, &b_sk, &b_pk)?,
+        ptext_for_b
+    );
+
+    Ok(())
+}
+
 #[test]
 fn mega_pair_smoke_test() -> Result<(), ThresholdEcdsaError> {
     let curve = EccCurveType::K256;
@@ -18,177 +18,288 @@ fn insufficient_dealings(r: Result<ProtocolRound, ThresholdEcdsaError>) {
 
 #[test]
 fn should_reshare_transcripts_correctly() -> Result<(), ThresholdEcdsaError> {
-    let random_seed = Seed::from_rng(&mut reproducible_rng());
-    let setup = ProtocolSetup::new(EccCurveType::K256, 4, 2, random_seed)?;
-
-    let no_corruption = 0; // number of corrupted dealings == 0
-    let corrupted_dealings = 1;
+    for curve in EccCurveType::all() {
+        let random_seed = Seed::from_rng(&mut reproducible_rng());
+        let setup = ProtocolSetup::new(curve, 4, 2, random_seed)?;
+
+        let no_corruption = 0; // number of corrupted dealings == 0
+        let corrupted_dealings = 1;
+
+        // First create a transcript of random dealings
+        let random = ProtocolRound::random(&setup, 4, corrupted_dealings)?;
+
+        // Now reshare the random value twice
+
+        // 1 dealing is not sufficient
+        insufficient_dealings(ProtocolRound::reshare_of_masked(
+            &setup,
+            &random,
+            1,
+            no_corruption,
+        ));
+
+        // 2, 3, or 4 works:
+        let reshared2 = ProtocolRound::reshare_of_masked(&setup, &random, 2, corrupted_dealings)?;
+        let reshared3 = ProtocolRound::reshare_of_masked(&setup, &random, 3, corrupted_dealings)?;
+        let reshared4 = ProtocolRound::reshare_of_masked(&setup, &random, 4, corrupted_dealings)?;
+
+        // The same value is committed in the resharings despite different dealing cnt
+        assert_eq!(reshared2.constant_term(), reshared3.constant_term());
+        assert_eq!(reshared2.constant_term(), reshared4.constant_term());
+
+        // Now reshare the now-unmasked value
+        insufficient_dealings(ProtocolRound::reshare_of_unmasked(
+            &setup,
+            &reshared2,
+            1,
+            no_corruption,
+        ));
+        let unmasked =
+            ProtocolRound::reshare_of_unmasked(&setup, &reshared2, 2, corrupted_dealings)?;
+        assert_eq!(reshared2.constant_term(), unmasked.constant_term());
+
+        // Now multiply the masked and umasked values
+        // We need 3 dealings to multiply
+        insufficient_dealings(ProtocolRound::multiply(
+            &setup,
+            &random,
+            &unmasked,
+            1,
+            no_corruption,
+        ));
+        insufficient_dealings(ProtocolRound::multiply(
+            &setup,
+            &random,
+            &unmasked,
+            2,
+            no_corruption,
+        ));
+        let _product = ProtocolRound::multiply(&setup, &random, &unmasked, 3, corrupted_dealings)?;
+    }
 
-    // First create a transcript of random dealings
-    let random = ProtocolRound::random(&setup, 4, corrupted_dealings)?;
+    Ok(())
+}
 
-    // Now reshare the random value twice
+#[test]
+fn should_multiply_transcripts_correctly() -> Result<(), ThresholdEcdsaError> {
+    for curve in EccCurveType::all() {
+        let random_seed = Seed::from_rng(&mut reproducible_rng());
+        let setup = ProtocolSetup::new(curve, 4, 2, random_seed)?;
+
+        let dealers = 4;
+        let corrupted_dealings = 1;
+
+        // First create two random transcripts
+        let random_a = ProtocolRound::random(&setup, dealers, corrupted_dealings)?;
+        let random_b = ProtocolRound::random(&setup, dealers, corrupted_dealings)?;
+
+        // Now reshare them both
+        let random_c =
+            ProtocolRound::reshare_of_masked(&setup, &random_a, dealers, corrupted_dealings)?;
+        let random_d =
+            ProtocolRound::reshare_of_masked(&setup, &random_b, dealers, corrupted_dealings)?;
+
+        // Now multiply A*D and B*C (which will be the same numbers)
+        let product_ad =
+            ProtocolRound::multiply(&setup, &random_a, &random_d, dealers, corrupted_dealings)?;
+        let product_bc =
+            ProtocolRound::multiply(&setup, &random_b, &random_c, dealers, corrupted_dealings)?;
+
+        // Now reshare AD and BC
+        let reshare_ad =
+            ProtocolRound::reshare_of_masked(&setup, &product_ad, dealers, corrupted_dealings)?;
+        let reshare_bc =
+            ProtocolRound::reshare_of_masked(&setup, &product_bc, dealers, corrupted_dealings)?;
+
+        // The committed values of AD and BC should be the same:
+        assert_eq!(reshare_ad.constant_term(), reshare_bc.constant_term());
+    }
 
-    // 1 dealing is not sufficient
-    insufficient_dealings(ProtocolRound::reshare_of_masked(
-        &setup,
-        &random,
-        1,
-        no_corruption,
-    ));
+    Ok(())
+}
 
-    // 2, 3, or 4 works:
-    let reshared2 = ProtocolRound::reshare_of_masked(&setup, &random, 2, corrupted_dealings)?;
-    let reshared3 = ProtocolRound::reshare_of_masked(&setup, &random, 3, corrupted_dealings)?;
-    let reshared4 = ProtocolRound::reshare_of_masked(&setup, &random, 4, corrupted_dealings)?;
-
-    // The same value is committed in the resharings despite different dealing cnt
-    assert_eq!(reshared2.constant_term(), reshared3.constant_term());
-    assert_eq!(reshared2.constant_term(), reshared4.constant_term());
-
-    // Now reshare the now-unmasked value
-    insufficient_dealings(ProtocolRound::reshare_of_unmasked(
-        &setup,
-        &reshared2,
-        1,
-        no_corruption,
-    ));
-    let unmasked = ProtocolRound::reshare_of_unmasked(&setup, &reshared2, 2, corrupted_dealings)?;
-    assert_eq!(reshared2.constant_term(), unmasked.constant_term());
-
-    // Now multiply the masked and umasked values
-    // We need 3 dealings to multiply
-    insufficient_dealings(ProtocolRound::multiply(
-        &setup,
-        &random,
-        &unmasked,
-        1,
-        no_corruption,
-    ));
-    insufficient_dealings(ProtocolRound::multiply(
-        &setup,
-        &random,
-        &unmasked,
-        2,
-        no_corruption,
-    ));
-    let _product = ProtocolRound::multiply(&setup, &random, &unmasked, 3, corrupted_dealings)?;
+#[test]
+fn should_reshare_transcripts_with_dynamic_threshold() -> Result<(), ThresholdEcdsaError> {
+    for curve in EccCurveType::all() {
+        let random_seed = Seed::from_rng(&mut reproducible_rng());
+        let mut setup = ProtocolSetup::new(curve, 5, 2, random_seed)?;
+
+        let no_corruption = 0; // number of corrupted dealings == 0
+        let corrupted_dealings = 1;
+
+        let random_a = ProtocolRound::random(&setup, 5, corrupted_dealings)?;
+
+        insufficient_dealings(ProtocolRound::reshare_of_masked(
+            &setup,
+            &random_a,
+            1,
+            no_corruption,
+        ));
+        let reshared_b =
+            ProtocolRound::reshare_of_masked(&setup, &random_a, 2, corrupted_dealings)?;
+
+        setup.modify_threshold(1);
+        setup.remove_nodes(2);
+        insufficient_dealings(ProtocolRound::reshare_of_unmasked(
+            &setup,
+            &reshared_b,
+            1,
+            no_corruption,
+        ));
+
+        let reshared_c =
+            ProtocolRound::reshare_of_unmasked(&setup, &reshared_b, 2, corrupted_dealings)?;
+        let reshared_d =
+            ProtocolRound::reshare_of_unmasked(&setup, &reshared_b, 3, corrupted_dealings)?;
+
+        // b, c, and d all have the same value
+        assert_eq!(reshared_b.constant_term(), reshared_c.constant_term());
+        assert_eq!(reshared_b.constant_term(), reshared_d.constant_term());
+    }
 
     Ok(())
 }
 
 #[test]
-fn should_multiply_transcripts_correctly() -> Result<(), ThresholdEcdsaError> {
-    let random_seed = Seed::from_rng(&mut reproducible_rng());
-    let setup = ProtocolSetup::new(EccCurveType::K256, 4, 2, random_seed)?;
+fn should_reshare_transcripts_to_a_different_committee() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
 
-    let dealers = 4;
-    let corrupted_dealings = 1;
+    for curve in EccCurveType::all() {
+        let old_setup = ProtocolSetup::new(curve, 5, 2, Seed::from_rng(rng))?;
 
-    // First create two random transcripts
-    let random_a = ProtocolRound::random(&setup, dealers, corrupted_dealings)?;
-    let random_b = ProtocolRound::random(&setup, dealers, corrupted_dealings)?;
+        let random = ProtocolRound::random(&old_setup, 5, 1)?;
+        let key = ProtocolRound::reshare_of_masked(&old_setup, &random, 3, 1)?;
 
-    // Now reshare them both
-    let random_c =
-        ProtocolRound::reshare_of_masked(&setup, &random_a, dealers, corrupted_dealings)?;
-    let random_d =
-        ProtocolRound::reshare_of_masked(&setup, &random_b, dealers, corrupted_dealings)?;
+        for (receivers, threshold) in [(7, 3), (3, 1), (5, 2)] {
+            let new_setup = ProtocolSetup::new(curve, receivers, threshold, Seed::from_rng(rng))?;
 
-    // Now multiply A*D and B*C (which will be the same numbers)
-    let product_ad =
-        ProtocolRound::multiply(&setup, &random_a, &random_d, dealers, corrupted_dealings)?;
-    let product_bc =
-        ProtocolRound::multiply(&setup, &random_b, &random_c, dealers, corrupted_dealings)?;
+            // At least as many dealings as the old threshold are required
+            insufficient_dealings(ProtocolRound::reshare_transcript(&new_setup, &key, 1));
 
-    // Now reshare AD and BC
-    let reshare_ad =
-        ProtocolRound::reshare_of_masked(&setup, &product_ad, dealers, corrupted_dealings)?;
-    let reshare_bc =
-        ProtocolRound::reshare_of_masked(&setup, &product_bc, dealers, corrupted_dealings)?;
+            let reshared_key = ProtocolRound::reshare_transcript(&new_setup, &key, 2)?;
+            assert_eq!(reshared_key.constant_term(), key.constant_term());
+            assert_eq!(reshared_key.openings.len(), receivers);
 
-    // The committed values of AD and BC should be the same:
-    assert_eq!(reshare_ad.constant_term(), reshare_bc.constant_term());
+            // A masked transcript can be reshared to the new committee as well
+            let reshared_random = ProtocolRound::reshare_transcript(&new_setup, &random, 3)?;
+            let old_committee_view = ProtocolRound::reshare_of_masked(&old_setup, &random, 3, 0)?;
+
+            let reshared_back =
+                ProtocolRound::reshare_transcript(&old_setup, &reshared_random, threshold)?;
+            assert_eq!(
+                reshared_back.constant_term(),
+                old_committee_view.constant_term()
+            );
+        }
+    }
 
     Ok(())
 }
 
 #[test]
-fn should_reshare_transcripts_with_dynamic_threshold() -> Result<(), ThresholdEcdsaError> {
-    let random_seed = Seed::from_rng(&mut reproducible_rng());
-    let mut setup = ProtocolSetup::new(EccCurveType::K256, 5, 2, random_seed)?;
-
-    let no_corruption = 0; // number of corrupted dealings == 0
-    let corrupted_dealings = 1;
-
-    let random_a = ProtocolRound::random(&setup, 5, corrupted_dealings)?;
-
-    insufficient_dealings(ProtocolRound::reshare_of_masked(
-        &setup,
-        &random_a,
-        1,
-        no_corruption,
-    ));
-    let reshared_b = ProtocolRound::reshare_of_masked(&setup, &random_a, 2, corrupted_dealings)?;
-
-    setup.modify_threshold(1);
-    setup.remove_nodes(2);
-    insufficient_dealings(ProtocolRound::reshare_of_unmasked(
-        &setup,
-        &reshared_b,
-        1,
-        no_corruption,
-    ));
-
-    let reshared_c =
-        ProtocolRound::reshare_of_unmasked(&setup, &reshared_b, 2, corrupted_dealings)?;
-    let reshared_d =
-        ProtocolRound::reshare_of_unmasked(&setup, &reshared_b, 3, corrupted_dealings)?;
+fn should_refuse_to_reshare_inconsistent_openings() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
 
-    // b, c, and d all have the same value
-    assert_eq!(reshared_b.constant_term(), reshared_c.constant_term());
-    assert_eq!(reshared_b.constant_term(), reshared_d.constant_term());
+    for curve in EccCurveType::all() {
+        let setup = ProtocolSetup::new(curve, 4, 2, Seed::from_rng(rng))?;
+        let new_setup = ProtocolSetup::new(curve, 6, 3, Seed::from_rng(rng))?;
+
+        let random = ProtocolRound::random(&setup, 4, 0)?;
+        let key = ProtocolRound::reshare_of_masked(&setup, &random, 4, 0)?;
+
+        let alg = setup.alg();
+        let new_public_keys = new_setup.public_keys();
+
+        // The opening of dealer 1 does not match the commitment for dealer 0
+        assert_eq!(
+            create_reshare_dealing(
+                alg,
+                b"ad",
+                0,
+                &key.transcript,
+                &key.openings[1],
+                NumberOfNodes::from(3),
+                new_public_keys,
+                Seed::from_rng(rng),
+            ),
+            Err(IdkgCreateDealingInternalError::InvalidSecretShare)
+        );
+
+        // The opening must be of the same type as the transcript
+        assert_eq!(
+            create_reshare_dealing(
+                alg,
+                b"ad",
+                0,
+                &random.transcript,
+                &key.openings[0],
+                NumberOfNodes::from(3),
+                new_public_keys,
+                Seed::from_rng(rng),
+            ),
+            Err(IdkgCreateDealingInternalError::InvalidSecretShare)
+        );
+
+        // The new threshold cannot exceed the number of new receivers
+        assert_eq!(
+            create_reshare_dealing(
+                alg,
+                b"ad",
+                0,
+                &key.transcript,
+                &key.openings[0],
+                NumberOfNodes::from(7),
+                new_public_keys,
+                Seed::from_rng(rng),
+            ),
+            Err(IdkgCreateDealingInternalError::InvalidThreshold(7, 6))
+        );
+    }
 
     Ok(())
 }
 
 #[test]
 fn should_multiply_transcripts_with_dynamic_threshold() -> Result<(), ThresholdEcdsaError> {
-    let random_seed = Seed::from_rng(&mut reproducible_rng());
-    let mut setup = ProtocolSetup::new(EccCurveType::K256, 5, 2, random_seed)?;
-
-    let corrupted_dealings = 1;
-
-    let random_a = ProtocolRound::random(&setup, 5, corrupted_dealings)?;
-    let random_b = ProtocolRound::random(&setup, 5, corrupted_dealings)?;
-
-    let reshared_c = ProtocolRound::reshare_of_masked(&setup, &random_a, 3, corrupted_dealings)?;
-
-    setup.modify_threshold(1);
-    setup.remove_nodes(2);
-    insufficient_dealings(ProtocolRound::multiply(
-        &setup,
-        &random_b,
-        &reshared_c,
-        1,
-        0,
-    ));
-    insufficient_dealings(ProtocolRound::multiply(
-        &setup,
-        &random_b,
-        &reshared_c,
-        2,
-        0,
-    ));
-
-    let _product = ProtocolRound::multiply(&setup, &random_b, &reshared_c, 3, corrupted_dealings)?;
+    for curve in EccCurveType::all() {
+        let random_seed = Seed::from_rng(&mut reproducible_rng());
+        let mut setup = ProtocolSetup::new(curve, 5, 2, random_seed)?;
+
+        let corrupted_dealings = 1;
+
+        let random_a = ProtocolRound::random(&setup, 5, corrupted_dealings)?;
+        let random_b = ProtocolRound::random(&setup, 5, corrupted_dealings)?;
+
+        let reshared_c =
+            ProtocolRound::reshare_of_masked(&setup, &random_a, 3, corrupted_dealings)?;
+
+        setup.modify_threshold(1);
+        setup.remove_nodes(2);
+        insufficient_dealings(ProtocolRound::multiply(
+            &setup,
+            &random_b,
+            &reshared_c,
+            1,
+            0,
+        ));
+        insufficient_dealings(ProtocolRound::multiply(
+            &setup,
+            &random_b,
+            &reshared_c,
+            2,
+            0,
+        ));
+
+        let _product =
+            ProtocolRound::multiply(&setup, &random_b, &reshared_c, 3, corrupted_dealings)?;
+    }
 
     Ok(())
 }
-fn random_subset(
-    shares: &BTreeMap<NodeIndex, ThresholdEcdsaSigShareInternal>,
+fn random_subset<T: Clone>(
+    shares: &BTreeMap<NodeIndex, T>,
     include: usize,
-) -> BTreeMap<NodeIndex, ThresholdEcdsaSigShareInternal> {
+) -> BTreeMap<NodeIndex, T> {
     assert!(include <= shares.len());
 
     let rng = &mut reproducible_rng();
@@ -220,6 +331,149 @@ fn should_basic_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
         Ok(())
     }
 
+    let rng = &mut reproducible_rng();
+
+    for curve in EccCurveType::all() {
+        let nodes = 10;
+        let threshold = nodes / 3;
+        let number_of_dealings_corrupted = threshold;
+
+        let random_seed = Seed::from_rng(rng);
+
+        let setup = SignatureProtocolSetup::new(
+            curve,
+            nodes,
+            threshold,
+            number_of_dealings_corrupted,
+            random_seed,
+        )?;
+
+        let alg = setup.alg();
+
+        let signed_message = rng.gen::<[u8; 32]>().to_vec();
+        let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+
+        let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+        let proto = SignatureProtocolExecution::new(
+            setup.clone(),
+            signed_message.clone(),
+            random_beacon,
+            derivation_path.clone(),
+        );
+
+        let shares = proto.generate_shares()?;
+
+        for i in 0..=nodes {
+            let shares = random_subset(&shares, i);
+
+            if shares.len() < threshold {
+                assert!(proto.generate_signature(&shares).is_err());
+            } else {
+                let sig = proto.generate_signature(&shares).unwrap();
+                test_sig_serialization(alg, &sig)?;
+                assert!(proto.verify_signature(&sig).is_ok());
+            }
+        }
+
+        // Test that another run of the protocol generates signatures
+        // which are not verifiable in the earlier one (due to different rho)
+        let random_beacon2 = Randomness::from(rng.gen::<[u8; 32]>());
+        let proto2 =
+            SignatureProtocolExecution::new(setup, signed_message, random_beacon2, derivation_path);
+
+        let shares = proto2.generate_shares()?;
+        let sig = proto2.generate_signature(&shares).unwrap();
+        test_sig_serialization(alg, &sig)?;
+
+        assert!(proto.verify_signature(&sig).is_err());
+        assert!(proto2.verify_signature(&sig).is_ok());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_signatures_are_rejected() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
+
+    for curve in EccCurveType::all() {
+        let nodes = 13;
+        let threshold = (nodes + 2) / 3;
+        let number_of_dealings_corrupted = 0;
+
+        let random_seed = Seed::from_rng(rng);
+
+        let setup = SignatureProtocolSetup::new(
+            curve,
+            nodes,
+            threshold,
+            number_of_dealings_corrupted,
+            random_seed,
+        )?;
+
+        let alg = setup.alg();
+
+        let signed_message = rng.gen::<[u8; 32]>().to_vec();
+        let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+
+        let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+        let proto =
+            SignatureProtocolExecution::new(setup, signed_message, random_beacon, derivation_path);
+
+        let shares = proto.generate_shares()?;
+
+        let sig = proto.generate_signature(&shares).unwrap();
+
+        assert_eq!(proto.verify_signature(&sig), Ok(()));
+
+        let sig = sig.serialize();
+
+        assert_eq!(sig.len() % 2, 0);
+
+        let half_sig = sig.len() / 2;
+
+        let sig_with_r_eq_zero = {
+            let mut sig_with_r_eq_zero = sig.clone();
+            sig_with_r_eq_zero[..half_sig].fill(0);
+            ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig_with_r_eq_zero).unwrap()
+        };
+
+        assert!(proto.verify_signature(&sig_with_r_eq_zero).is_err());
+
+        let sig_with_s_eq_zero = {
+            let mut sig_with_s_eq_zero = sig.clone();
+            sig_with_s_eq_zero[half_sig..].fill(0);
+            ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig_with_s_eq_zero).unwrap()
+        };
+
+        assert!(proto.verify_signature(&sig_with_s_eq_zero).is_err());
+
+        let sig_with_high_s = {
+            let s = EccScalar::deserialize(curve, &sig[half_sig..])
+                .unwrap()
+                .negate();
+
+            let mut sig_with_high_s = sig;
+            sig_with_high_s[half_sig..].copy_from_slice(&s.serialize());
+            ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig_with_high_s).unwrap()
+        };
+
+        assert!(proto.verify_signature(&sig_with_high_s).is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_basic_schnorr_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
+    fn test_sig_serialization(sig: &ThresholdSchnorrCombinedSigInternal) {
+        let bytes = sig.serialize();
+        assert_eq!(bytes.len(), 64);
+        let sig2 = ThresholdSchnorrCombinedSigInternal::deserialize(&bytes)
+            .expect("Deserialization failed");
+        assert_eq!(*sig, sig2);
+    }
+
     let nodes = 10;
     let threshold = nodes / 3;
     let number_of_dealings_corrupted = threshold;
@@ -227,21 +481,22 @@ fn should_basic_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
     let rng = &mut reproducible_rng();
     let random_seed = Seed::from_rng(rng);
 
-    let setup = SignatureProtocolSetup::new(
-        EccCurveType::K256,
+    let setup = SchnorrSignatureProtocolSetup::new(
         nodes,
         threshold,
         number_of_dealings_corrupted,
         random_seed,
     )?;
 
-    let alg = setup.alg();
-
-    let signed_message = rng.gen::<[u8; 32]>().to_vec();
+    // BIP-340 signs messages of any length
+    let message_len = rng.gen_range(0..100);
+    let signed_message = (0..message_len)
+        .map(|_| rng.gen::<u8>())
+        .collect::<Vec<_>>();
     let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
 
     let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
-    let proto = SignatureProtocolExecution::new(
+    let proto = SchnorrSignatureProtocolExecution::new(
         setup.clone(),
         signed_message.clone(),
         random_beacon,
@@ -254,23 +509,30 @@ fn should_basic_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
         let shares = random_subset(&shares, i);
 
         if shares.len() < threshold {
-            assert!(proto.generate_signature(&shares).is_err());
+            assert_eq!(
+                proto.generate_signature(&shares),
+                Err(ThresholdSchnorrCombineSigSharesInternalError::InsufficientShares)
+            );
         } else {
             let sig = proto.generate_signature(&shares).unwrap();
-            test_sig_serialization(alg, &sig)?;
+            test_sig_serialization(&sig);
             assert!(proto.verify_signature(&sig).is_ok());
         }
     }
 
     // Test that another run of the protocol generates signatures
-    // which are not verifiable in the earlier one (due to different rho)
+    // which are not verifiable in the earlier one (due to different presig)
     let random_beacon2 = Randomness::from(rng.gen::<[u8; 32]>());
-    let proto2 =
-        SignatureProtocolExecution::new(setup, signed_message, random_beacon2, derivation_path);
+    let proto2 = SchnorrSignatureProtocolExecution::new(
+        setup,
+        signed_message,
+        random_beacon2,
+        derivation_path,
+    );
 
     let shares = proto2.generate_shares()?;
     let sig = proto2.generate_signature(&shares).unwrap();
-    test_sig_serialization(alg, &sig)?;
+    test_sig_serialization(&sig);
 
     assert!(proto.verify_signature(&sig).is_err());
     assert!(proto2.verify_signature(&sig).is_ok());
@@ -279,70 +541,235 @@ fn should_basic_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
 }
 
 #[test]
-fn invalid_signatures_are_rejected() -> Result<(), ThresholdEcdsaError> {
-    let nodes = 13;
+fn invalid_schnorr_signatures_and_shares_are_rejected() -> Result<(), ThresholdEcdsaError> {
+    let nodes = 7;
     let threshold = (nodes + 2) / 3;
     let number_of_dealings_corrupted = 0;
 
     let rng = &mut reproducible_rng();
     let random_seed = Seed::from_rng(rng);
 
-    let setup = SignatureProtocolSetup::new(
-        EccCurveType::K256,
+    let setup = SchnorrSignatureProtocolSetup::new(
         nodes,
         threshold,
         number_of_dealings_corrupted,
         random_seed,
     )?;
 
-    let alg = setup.alg();
-
     let signed_message = rng.gen::<[u8; 32]>().to_vec();
     let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
 
     let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
-    let proto =
-        SignatureProtocolExecution::new(setup, signed_message, random_beacon, derivation_path);
+    let proto = SchnorrSignatureProtocolExecution::new(
+        setup,
+        signed_message,
+        random_beacon,
+        derivation_path,
+    );
 
     let shares = proto.generate_shares()?;
 
+    // A share is only valid for the index of the signer who created it
+    assert_eq!(
+        proto.verify_share(1, &shares[&0]),
+        Err(ThresholdSchnorrVerifySigShareInternalError::InvalidSignatureShare)
+    );
+
     let sig = proto.generate_signature(&shares).unwrap();
 
     assert_eq!(proto.verify_signature(&sig), Ok(()));
 
     let sig = sig.serialize();
-
-    assert_eq!(sig.len() % 2, 0);
-
     let half_sig = sig.len() / 2;
 
-    let sig_with_r_eq_zero = {
-        let mut sig_with_r_eq_zero = sig.clone();
-        sig_with_r_eq_zero[..half_sig].fill(0);
-        ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig_with_r_eq_zero).unwrap()
+    let sig_with_modified_s = {
+        let s = EccScalar::deserialize(EccCurveType::K256, &sig[half_sig..])
+            .unwrap()
+            .add(&EccScalar::one(EccCurveType::K256))?;
+
+        let mut sig_with_modified_s = sig.clone();
+        sig_with_modified_s[half_sig..].copy_from_slice(&s.serialize());
+        ThresholdSchnorrCombinedSigInternal::deserialize(&sig_with_modified_s).unwrap()
     };
 
-    assert!(proto.verify_signature(&sig_with_r_eq_zero).is_err());
+    assert_eq!(
+        proto.verify_signature(&sig_with_modified_s),
+        Err(ThresholdSchnorrVerifySignatureInternalError::InvalidSignature)
+    );
 
     let sig_with_s_eq_zero = {
         let mut sig_with_s_eq_zero = sig.clone();
         sig_with_s_eq_zero[half_sig..].fill(0);
-        ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig_with_s_eq_zero).unwrap()
+        ThresholdSchnorrCombinedSigInternal::deserialize(&sig_with_s_eq_zero).unwrap()
     };
 
-    assert!(proto.verify_signature(&sig_with_s_eq_zero).is_err());
+    assert_eq!(
+        proto.verify_signature(&sig_with_s_eq_zero),
+        Err(ThresholdSchnorrVerifySignatureInternalError::InvalidSignature)
+    );
 
-    let sig_with_high_s = {
-        let s = EccScalar::deserialize(EccCurveType::K256, &sig[half_sig..])
-            .unwrap()
-            .negate();
+    // r must be the x coordinate of a point on the curve
+    let mut sig_with_invalid_r = sig;
+    sig_with_invalid_r[..half_sig].fill(0xFF);
+    assert!(ThresholdSchnorrCombinedSigInternal::deserialize(&sig_with_invalid_r).is_err());
 
-        let mut sig_with_high_s = sig;
-        sig_with_high_s[half_sig..].copy_from_slice(&s.serialize());
-        ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig_with_high_s).unwrap()
-    };
+    Ok(())
+}
+
+#[test]
+fn should_batched_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
+
+    for curve in EccCurveType::all() {
+        let nodes = 10;
+        let threshold = nodes / 3;
+        let number_of_dealings_corrupted = threshold;
+
+        let setup = SignatureProtocolSetup::new(
+            curve,
+            nodes,
+            threshold,
+            number_of_dealings_corrupted,
+            Seed::from_rng(rng),
+        )?;
+
+        let alg = setup.alg();
+
+        // Two of the entries share a derivation path
+        let derivation_paths = [
+            DerivationPath::new_bip32(&[1, 2, 3]),
+            DerivationPath::new_bip32(&[4, 5]),
+            DerivationPath::new_bip32(&[1, 2, 3]),
+        ];
+
+        let mut executions = Vec::with_capacity(derivation_paths.len());
+        for (i, derivation_path) in derivation_paths.iter().enumerate() {
+            let setup = if i == 0 {
+                setup.clone()
+            } else {
+                setup.with_new_presignature(nodes, number_of_dealings_corrupted)?
+            };
+
+            executions.push(SignatureProtocolExecution::new(
+                setup,
+                rng.gen::<[u8; 32]>().to_vec(),
+                Randomness::from(rng.gen::<[u8; 32]>()),
+                derivation_path.clone(),
+            ));
+        }
+
+        let proto = SignatureProtocolBatchExecution::new(executions);
+
+        let batches = proto.generate_shares_batch()?;
+
+        for batch in batches.values() {
+            let bytes = batch.serialize().expect("Serialization failed");
+            let batch2 = ThresholdEcdsaSigShareBatchInternal::deserialize(&bytes)
+                .expect("Deserialization failed");
+            assert_eq!(batch2.serialize().expect("Serialization failed"), bytes);
+        }
+
+        // The batched shares combine to signatures which are also accepted
+        // by the individual executions
+        for (execution, shares) in proto
+            .executions()
+            .iter()
+            .zip(proto.shares_by_execution(&batches))
+        {
+            let shares = random_subset(&shares, threshold);
+            let sig = execution.generate_signature(&shares).unwrap();
+            assert!(execution.verify_signature(&sig).is_ok());
+
+            let sig2 = ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig.serialize())
+                .expect("Deserialization failed");
+            assert_eq!(sig, sig2);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn batched_signing_rejects_reused_presignatures() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
+
+    let nodes = 4;
+    let threshold = 2;
+
+    let setup =
+        SignatureProtocolSetup::new(EccCurveType::K256, nodes, threshold, 0, Seed::from_rng(rng))?;
+
+    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+
+    let executions = (0..2)
+        .map(|_| {
+            SignatureProtocolExecution::new(
+                setup.clone(),
+                rng.gen::<[u8; 32]>().to_vec(),
+                Randomness::from(rng.gen::<[u8; 32]>()),
+                derivation_path.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let proto = SignatureProtocolBatchExecution::new(executions);
+
+    assert!(matches!(
+        proto.generate_share_batch(0),
+        Err(ThresholdEcdsaGenerateSigShareInternalError::InternalError(
+            _
+        ))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_signature_share_batches_are_rejected() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
+
+    let nodes = 4;
+    let threshold = 2;
+
+    let setup =
+        SignatureProtocolSetup::new(EccCurveType::K256, nodes, threshold, 0, Seed::from_rng(rng))?;
+
+    let executions = (0..3)
+        .map(|i| {
+            let setup = if i == 0 {
+                setup.clone()
+            } else {
+                setup.with_new_presignature(nodes, 0).unwrap()
+            };
+            SignatureProtocolExecution::new(
+                setup,
+                rng.gen::<[u8; 32]>().to_vec(),
+                Randomness::from(rng.gen::<[u8; 32]>()),
+                DerivationPath::new_bip32(&[i]),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let proto = SignatureProtocolBatchExecution::new(executions);
+    let inputs = proto.verification_inputs();
+
+    let batch = proto.generate_share_batch(0).unwrap();
+
+    assert!(proto.verify_share_batch(&batch, &inputs, 0).is_ok());
+
+    // Wrong signer
+    assert!(proto.verify_share_batch(&batch, &inputs, 1).is_err());
+
+    // Missing inputs
+    assert_eq!(
+        proto.verify_share_batch(&batch, &inputs[1..], 0),
+        Err(ThresholdEcdsaVerifySigShareInternalError::InvalidSignatureShare)
+    );
 
-    assert!(proto.verify_signature(&sig_with_high_s).is_err());
+    // Inputs in the wrong order
+    let mut reordered = inputs.clone();
+    reordered.swap(0, 2);
+    assert!(proto.verify_share_batch(&batch, &reordered, 0).is_err());
 
     Ok(())
 }
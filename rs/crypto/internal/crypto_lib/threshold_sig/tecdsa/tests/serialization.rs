@@ -194,6 +194,270 @@ fn verify_protocol_output_remains_unchanged_over_time() -> Result<(), ThresholdE
     Ok(())
 }
 
+#[test]
+fn verify_p256_protocol_output_remains_unchanged_over_time() -> Result<(), ThresholdEcdsaError> {
+    let nodes = 5;
+    let threshold = 2;
+
+    let seed = Seed::from_bytes(b"ic-crypto-tecdsa-p256-fixed-seed");
+
+    let setup = SignatureProtocolSetup::new(
+        EccCurveType::P256,
+        nodes,
+        threshold,
+        0,
+        seed.derive("setup"),
+    )?;
+
+    check_dealings(
+        "p256 key",
+        &setup.key,
+        "04b1c641fa48e71b",
+        "bca0a33ad303efac",
+        &[
+            (0, "56b54a6e44b8f083"),
+            (1, "663923d0b6b018fa"),
+            (2, "87e207d3fd134246"),
+            (3, "e2db0bdd06abcae1"),
+            (4, "eef43c88f8c20aa8"),
+        ],
+    )?;
+
+    check_dealings(
+        "p256 key*lambda",
+        &setup.key_times_lambda,
+        "10f22cbbf82cfb01",
+        "4226f4cdf2e717d0",
+        &[
+            (0, "97578d6536850077"),
+            (1, "a337105eae6dd836"),
+            (2, "ce84b2544f509c29"),
+            (3, "860f811240f20d0e"),
+            (4, "6781d994fcf2edb6"),
+        ],
+    )?;
+
+    check_dealings(
+        "p256 lambda",
+        &setup.lambda,
+        "de0458f89301f702",
+        "44751761c9ffccaa",
+        &[
+            (0, "9493525c0dec3a0e"),
+            (1, "9017dbd7715ccf89"),
+            (2, "cf3217889a5aeaa5"),
+            (3, "a9b61235134baa4e"),
+            (4, "1f1c15787b04899d"),
+        ],
+    )?;
+
+    check_dealings(
+        "p256 kappa",
+        &setup.kappa,
+        "2ab208601e371515",
+        "aab5187b3cf0f005",
+        &[
+            (0, "ff8dcdd3061f8a70"),
+            (1, "c5294892cfe0d525"),
+            (2, "6e784b3a9a0ff955"),
+            (3, "1e136701ec91479d"),
+            (4, "b7e9fc22f4138454"),
+        ],
+    )?;
+
+    check_dealings(
+        "p256 kappa*lambda",
+        &setup.kappa_times_lambda,
+        "4e5b429c16af79d5",
+        "d4c6b1dd630e68da",
+        &[
+            (0, "a0627716dd37698b"),
+            (1, "ebc1c1b1a736a6a1"),
+            (2, "eac4e8d1461b9d3d"),
+            (3, "537b06c096940b6c"),
+            (4, "78bfa2249dd83ab4"),
+        ],
+    )?;
+
+    let signed_message = seed.derive("message").into_rng().gen::<[u8; 32]>().to_vec();
+    let random_beacon =
+        ic_types::Randomness::from(seed.derive("beacon").into_rng().gen::<[u8; 32]>());
+
+    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+    let proto =
+        SignatureProtocolExecution::new(setup, signed_message, random_beacon, derivation_path);
+
+    let shares = proto.generate_shares()?;
+
+    check_shares(
+        &shares,
+        &[
+            (0, "4b4abda86100afee"),
+            (1, "4cd076abe0f3a059"),
+            (2, "da33ad27fea1179b"),
+            (3, "f1800f6ed9326803"),
+            (4, "79177a5f50cac37f"),
+        ],
+    )?;
+
+    let sig = proto.generate_signature(&shares).unwrap();
+
+    verify_data(
+        "p256 signature".to_string(),
+        "b2d1f404cf2a2ba2",
+        &sig.serialize(),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn verify_schnorr_protocol_output_remains_unchanged_over_time() -> Result<(), ThresholdEcdsaError> {
+    let nodes = 5;
+    let threshold = 2;
+
+    let seed = Seed::from_bytes(b"ic-crypto-tschnorr-fixed-seed");
+
+    let setup = SchnorrSignatureProtocolSetup::new(nodes, threshold, 0, seed.derive("setup"))?;
+
+    check_dealings(
+        "schnorr key",
+        &setup.key,
+        "16d79681f8ac6481",
+        "61bcdbdec8e2886b",
+        &[
+            (0, "93bceee37a9887d8"),
+            (1, "118c30da5baabfe1"),
+            (2, "940b048f76714c8d"),
+            (3, "c720f0f2d4af3a2b"),
+            (4, "a5fb3568484bbbe8"),
+        ],
+    )?;
+
+    check_dealings(
+        "schnorr presig",
+        &setup.presig,
+        "0f220b1b68e5c2d4",
+        "85435f66cad369e6",
+        &[
+            (0, "799e221cdf433b1d"),
+            (1, "bae7203145776932"),
+            (2, "2a4ece74541e6b62"),
+            (3, "f2c4a692cd293358"),
+            (4, "95da71a257cd2402"),
+        ],
+    )?;
+
+    let signed_message = seed.derive("message").into_rng().gen::<[u8; 32]>().to_vec();
+    let random_beacon =
+        ic_types::Randomness::from(seed.derive("beacon").into_rng().gen::<[u8; 32]>());
+
+    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+    let proto = SchnorrSignatureProtocolExecution::new(
+        setup,
+        signed_message,
+        random_beacon,
+        derivation_path,
+    );
+
+    let shares = proto.generate_shares()?;
+
+    assert_eq!(shares.len(), nodes);
+
+    for (index, hash) in [
+        (0, "0bffb71ccdec7cad"),
+        (1, "c8d8e22d8c5192af"),
+        (2, "58ed95493ac36402"),
+        (3, "fe17ecfb9afa3093"),
+        (4, "813ea696b8b47630"),
+    ] {
+        verify_data(
+            format!("schnorr share {}", index),
+            hash,
+            &shares[&index].serialize().expect("Serialization failed"),
+        );
+    }
+
+    let sig = proto.generate_signature(&shares).unwrap();
+
+    verify_data(
+        "schnorr signature".to_string(),
+        "413f536d4a03e2b3",
+        &sig.serialize(),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn verify_reshare_output_remains_unchanged_over_time() -> Result<(), ThresholdEcdsaError> {
+    let seed = Seed::from_bytes(b"ic-crypto-tecdsa-reshare-fixed-seed");
+
+    let old_setup = ProtocolSetup::new(EccCurveType::K256, 4, 2, seed.derive("old-setup"))?;
+    let new_setup = ProtocolSetup::new(EccCurveType::K256, 6, 3, seed.derive("new-setup"))?;
+
+    let random = ProtocolRound::random(&old_setup, 4, 0)?;
+    let key = ProtocolRound::reshare_of_masked(&old_setup, &random, 4, 0)?;
+
+    let reshared = ProtocolRound::reshare_transcript(&new_setup, &key, 2)?;
+
+    assert_eq!(reshared.constant_term(), key.constant_term());
+
+    check_dealings(
+        "reshared key",
+        &reshared,
+        "98dad8280acb6cb4",
+        "30bc44308c399c59",
+        &[(0, "cb0329f165786d3b"), (1, "c360127f58594c75")],
+    )?;
+
+    let reshared_random = ProtocolRound::reshare_transcript(&new_setup, &random, 3)?;
+
+    check_dealings(
+        "reshared random",
+        &reshared_random,
+        "b8db7db2013aa9d2",
+        "100e58422b24da6f",
+        &[
+            (0, "e0353c72a2d4e02c"),
+            (1, "2222ecc9f3bb26e2"),
+            (2, "0bdd50a2537f9c2c"),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Checks that the fixed legacy encoding `hex` can be decoded, also when
+/// prefixed by the serialization version, and that encoding the decoded
+/// artifact again produces the same legacy encoding.
+fn check_fixed_encoding<T>(
+    name: &str,
+    hex: &str,
+    deserialize: fn(&[u8]) -> ThresholdEcdsaSerializationResult<T>,
+    serialize: fn(&T) -> ThresholdEcdsaSerializationResult<Vec<u8>>,
+) {
+    let encoding = hex::decode(hex).expect("Invalid hex");
+    let artifact = deserialize(&encoding)
+        .unwrap_or_else(|_| panic!("Was unable to deserialize a fixed {} encoding", name));
+    let reencoding = serialize(&artifact).expect("Serialization failed");
+    assert_eq!(hex::encode(reencoding), hex::encode(&encoding), "{}", name);
+
+    let versioned = [&[ARTIFACT_SERIALIZATION_VERSION], &encoding[..]].concat();
+    assert!(
+        deserialize(&versioned).is_ok(),
+        "Was unable to deserialize a versioned {} encoding",
+        name
+    );
+}
+
+/// The fixtures in tests/data were produced by earlier versions of this
+/// crate. Missing fixtures (for instance for a newly supported curve) can
+/// be added with
+///
+/// $ cargo run --features testvec --bin tecdsa-testvec -- tests/data
+///
+/// which never overwrites existing fixtures.
 #[test]
 fn verify_fixed_serialization_continues_to_be_accepted() -> Result<(), ThresholdEcdsaError> {
     let dealing_bits = [
@@ -201,12 +465,19 @@ fn verify_fixed_serialization_continues_to_be_accepted() -> Result<(), Threshold
         include_str!("data/dealing_reshare_of_masked.hex"),
         include_str!("data/dealing_reshare_of_unmasked.hex"),
         include_str!("data/dealing_multiply.hex"),
+        include_str!("data/dealing_random_p256.hex"),
+        include_str!("data/dealing_reshare_of_masked_p256.hex"),
+        include_str!("data/dealing_reshare_of_unmasked_p256.hex"),
+        include_str!("data/dealing_multiply_p256.hex"),
     ];
 
     for dealing_encoding in dealing_bits {
-        let dealing_encoding = hex::decode(dealing_encoding).expect("Invalid hex");
-        let _dealing = IDkgDealingInternal::deserialize(&dealing_encoding)
-            .expect("Was unable to deserialize a fixed dealing encoding");
+        check_fixed_encoding(
+            "dealing",
+            dealing_encoding,
+            IDkgDealingInternal::deserialize,
+            IDkgDealingInternal::serialize,
+        );
     }
 
     let transcript_bits = [
@@ -214,26 +485,41 @@ fn verify_fixed_serialization_continues_to_be_accepted() -> Result<(), Threshold
         include_str!("data/transcript_reshare_of_masked.hex"),
         include_str!("data/transcript_reshare_of_unmasked.hex"),
         include_str!("data/transcript_multiply.hex"),
+        include_str!("data/transcript_random_p256.hex"),
+        include_str!("data/transcript_reshare_of_masked_p256.hex"),
+        include_str!("data/transcript_reshare_of_unmasked_p256.hex"),
+        include_str!("data/transcript_multiply_p256.hex"),
     ];
 
     for transcript_encoding in transcript_bits {
-        let transcript_encoding = hex::decode(transcript_encoding).expect("Invalid hex");
-        let _transcript = IDkgTranscriptInternal::deserialize(&transcript_encoding)
-            .expect("Was unable to deserialize a fixed transcript encoding");
+        check_fixed_encoding(
+            "transcript",
+            transcript_encoding,
+            IDkgTranscriptInternal::deserialize,
+            IDkgTranscriptInternal::serialize,
+        );
     }
 
     let opening_bits = [
         include_str!("data/opening_simple.hex"),
         include_str!("data/opening_pedersen.hex"),
+        include_str!("data/opening_simple_p256.hex"),
+        include_str!("data/opening_pedersen_p256.hex"),
     ];
 
     for opening_encoding in opening_bits {
-        let opening_encoding = hex::decode(opening_encoding).expect("Invalid hex");
-        let _opening = CommitmentOpening::deserialize(&opening_encoding)
-            .expect("Was unable to deserialize a fixed opening encoding");
+        check_fixed_encoding(
+            "opening",
+            opening_encoding,
+            CommitmentOpening::deserialize,
+            CommitmentOpening::serialize,
+        );
     }
 
-    let complaint_bits = [include_str!("data/complaint.hex")];
+    let complaint_bits = [
+        include_str!("data/complaint.hex"),
+        include_str!("data/complaint_p256.hex"),
+    ];
 
     for complaint_encoding in complaint_bits {
         let complaint_encoding = hex::decode(complaint_encoding).expect("Invalid hex");
@@ -241,17 +527,52 @@ fn verify_fixed_serialization_continues_to_be_accepted() -> Result<(), Threshold
             .expect("Was unable to deserialize a fixed complaint encoding");
     }
 
-    let sig_share_bits = [include_str!("data/sig_share.hex")];
+    let sig_share_bits = [
+        include_str!("data/sig_share.hex"),
+        include_str!("data/sig_share_p256.hex"),
+    ];
 
     for sig_share_encoding in sig_share_bits {
-        let sig_share_encoding = hex::decode(sig_share_encoding).expect("Invalid hex");
-        let _sig_share = ThresholdEcdsaSigShareInternal::deserialize(&sig_share_encoding)
-            .expect("Was unable to deserialize a fixed sig_share encoding");
+        check_fixed_encoding(
+            "sig_share",
+            sig_share_encoding,
+            ThresholdEcdsaSigShareInternal::deserialize,
+            ThresholdEcdsaSigShareInternal::serialize,
+        );
     }
 
     Ok(())
 }
 
+#[test]
+fn unsupported_serialization_versions_are_rejected() {
+    let transcript_encoding =
+        hex::decode(include_str!("data/transcript_random.hex")).expect("Invalid hex");
+    let transcript = IDkgTranscriptInternal::deserialize(&transcript_encoding)
+        .expect("Was unable to deserialize a fixed transcript encoding");
+    let versioned = [
+        &[ARTIFACT_SERIALIZATION_VERSION],
+        &transcript.serialize().expect("Serialization failed")[..],
+    ]
+    .concat();
+    assert!(IDkgTranscriptInternal::deserialize(&versioned).is_ok());
+
+    for version in [0, ARTIFACT_SERIALIZATION_VERSION + 1, 0x9F, 0xC0, 0xFF] {
+        let mut encoding = versioned.clone();
+        encoding[0] = version;
+        assert!(
+            IDkgTranscriptInternal::deserialize(&encoding).is_err(),
+            "version {} was accepted",
+            version
+        );
+    }
+
+    assert!(IDkgTranscriptInternal::deserialize(&[]).is_err());
+    assert!(IDkgDealingInternal::deserialize(&[]).is_err());
+    assert!(CommitmentOpening::deserialize(&[]).is_err());
+    assert!(ThresholdEcdsaSigShareInternal::deserialize(&[]).is_err());
+}
+
 #[test]
 fn mega_k256_keyset_serialization_is_stable() -> Result<(), ThresholdEcdsaError> {
     let seed = Seed::from_bytes(b"ic-crypto-k256-keyset-serialization-stability-test");
@@ -316,3 +637,95 @@ fn commitment_opening_k256_serialization_is_stable() -> Result<(), ThresholdEcds
 
     Ok(())
 }
+
+#[test]
+fn commitment_opening_p256_serialization_is_stable() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut Seed::from_bytes(b"ic-crypto-commitment-opening-serialization-stability-test")
+        .into_rng();
+
+    let s1 = EccScalar::random(EccCurveType::P256, rng);
+    let s2 = EccScalar::random(EccCurveType::P256, rng);
+
+    assert_eq!(
+        hex::encode(s1.serialize()),
+        "533db71736dbb11c23fd9a6cd703d37afd5173b943dc932d387dc17c89aaad84"
+    );
+    assert_eq!(
+        hex::encode(s2.serialize()),
+        "431fb614454b7c1f2ec2bd76832daf4ec6cadaa38bfbfb801a6d209b275af28d"
+    );
+
+    let s1_bytes = EccScalarBytes::try_from(&s1).expect("Deserialization failed");
+    let s2_bytes = EccScalarBytes::try_from(&s2).expect("Deserialization failed");
+
+    let simple = CommitmentOpeningBytes::Simple(s1_bytes.clone());
+
+    assert_eq!(hex::encode(serde_cbor::to_vec(&simple).unwrap()), "a16653696d706c65a1645032353698201853183d18b717183618db18b1181c182318fd189a186c18d70318d3187a18fd1851187318b9184318dc1893182d1838187d18c1187c188918aa18ad1884");
+
+    let pedersen = CommitmentOpeningBytes::Pedersen(s1_bytes, s2_bytes);
+
+    assert_eq!(hex::encode(serde_cbor::to_vec(&pedersen).unwrap()), "a168506564657273656e82a1645032353698201853183d18b717183618db18b1181c182318fd189a186c18d70318d3187a18fd1851187318b9184318dc1893182d1838187d18c1187c188918aa18ad1884a1645032353698201843181f18b6141845184b187c181f182e18c218bd18761883182d18af184e18c618ca18da18a3188b18fb18fb1880181a186d1820189b1827185a18f2188d");
+
+    Ok(())
+}
+
+#[test]
+fn secret_serialization_types_compare_by_value() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut Seed::from_bytes(b"ic-crypto-secret-bytes-comparison-test").into_rng();
+
+    for curve in EccCurveType::all() {
+        let s1 = EccScalar::random(curve, rng);
+        let s2 = EccScalar::random(curve, rng);
+
+        let s1_bytes = EccScalarBytes::try_from(&s1).expect("Serialization failed");
+        let s2_bytes = EccScalarBytes::try_from(&s2).expect("Serialization failed");
+
+        assert!(s1_bytes == s1_bytes.clone());
+        assert!(s1_bytes != s2_bytes);
+        assert_eq!(
+            EccScalar::try_from(&s1_bytes).expect("Deserialization failed"),
+            s1
+        );
+
+        let simple = CommitmentOpeningBytes::Simple(s1_bytes.clone());
+        let pedersen = CommitmentOpeningBytes::Pedersen(s1_bytes.clone(), s2_bytes.clone());
+
+        assert!(simple == simple.clone());
+        assert!(pedersen == pedersen.clone());
+        assert!(simple != pedersen);
+        assert!(pedersen != CommitmentOpeningBytes::Pedersen(s1_bytes.clone(), s1_bytes.clone()));
+
+        let opening = CommitmentOpening::try_from(&pedersen).expect("Deserialization failed");
+        assert_eq!(opening, CommitmentOpening::Pedersen(s1, s2.clone()));
+        assert_eq!(
+            CommitmentOpening::deserialize(&opening.serialize().expect("Serialization failed"))
+                .expect("Deserialization failed"),
+            opening
+        );
+        assert!(opening != CommitmentOpening::Simple(s2));
+    }
+
+    // Scalars of different curves never compare equal, even with the same encoding
+    let k256 = EccScalar::from_u64(EccCurveType::K256, 42);
+    let p256 = EccScalar::from_u64(EccCurveType::P256, 42);
+    assert!(k256 != p256);
+    assert!(
+        EccScalarBytes::try_from(&k256).expect("Serialization failed")
+            != EccScalarBytes::try_from(&p256).expect("Serialization failed")
+    );
+
+    let sk1 = MEGaPrivateKey::generate(EccCurveType::K256, rng);
+    let sk2 = MEGaPrivateKey::generate(EccCurveType::K256, rng);
+
+    let sk1_bytes = MEGaPrivateKeyK256Bytes::try_from(&sk1).expect("Serialization failed");
+    let sk2_bytes = MEGaPrivateKeyK256Bytes::try_from(&sk2).expect("Serialization failed");
+
+    assert!(sk1_bytes == sk1_bytes.clone());
+    assert!(sk1_bytes != sk2_bytes);
+    assert_eq!(
+        MEGaPrivateKey::try_from(&sk1_bytes).expect("Deserialization failed"),
+        sk1
+    );
+
+    Ok(())
+}
@@ -28,13 +28,19 @@ impl ProtocolSetup {
     ) -> Result<Self, ThresholdEcdsaError> {
         let alg = match curve {
             EccCurveType::K256 => AlgorithmId::ThresholdEcdsaSecp256k1,
-            _ => {
-                return Err(ThresholdEcdsaError::InvalidArguments(
-                    "Unsupported curve".to_string(),
-                ))
-            }
+            EccCurveType::P256 => AlgorithmId::ThresholdEcdsaSecp256r1,
         };
 
+        Self::new_for_algorithm(alg, curve, receivers, threshold, seed)
+    }
+
+    pub fn new_for_algorithm(
+        alg: AlgorithmId,
+        curve: EccCurveType,
+        receivers: usize,
+        threshold: usize,
+        seed: Seed,
+    ) -> Result<Self, ThresholdEcdsaError> {
         let rng = &mut seed.into_rng();
         let ad = rng.gen::<[u8; 32]>().to_vec();
 
@@ -42,7 +48,7 @@ impl ProtocolSetup {
         let mut pk = Vec::with_capacity(receivers);
 
         for _i in 0..receivers {
-            let k = MEGaPrivateKey::generate(curve, rng);
+            let k = MEGaPrivateKey::generate(curve.mega_key_curve(), rng);
             pk.push(k.public_key());
             sk.push(k);
         }
@@ -90,6 +96,14 @@ impl ProtocolSetup {
         self.threshold = NumberOfNodes::from(threshold as u32);
     }
 
+    pub fn alg(&self) -> AlgorithmId {
+        self.alg
+    }
+
+    pub fn public_keys(&self) -> &[MEGaPublicKey] {
+        &self.pk
+    }
+
     pub fn receiver_info(&self) -> Vec<(MEGaPrivateKey, MEGaPublicKey, NodeIndex)> {
         let mut info = Vec::with_capacity(self.receivers);
         for i in 0..self.receivers {
@@ -247,6 +261,68 @@ impl ProtocolRound {
         Ok(Self::new(setup, dealings, transcript, mode))
     }
 
+    /// Reshares the transcript of `old` to the receivers of `new_setup`
+    ///
+    /// The first `number_of_dealers` receivers of `old` act as dealers. The
+    /// receivers and threshold of `new_setup` may differ from those `old`
+    /// was created with.
+    pub fn reshare_transcript(
+        new_setup: &ProtocolSetup,
+        old: &ProtocolRound,
+        number_of_dealers: usize,
+    ) -> ThresholdEcdsaResult<Self> {
+        assert!(number_of_dealers <= old.openings.len());
+
+        let mode = IDkgTranscriptOperationInternal::reshare_of(&old.transcript);
+        let rng = &mut new_setup.next_dealing_seed().into_rng();
+
+        let mut dealings = BTreeMap::new();
+
+        for (dealer_index, opening) in old.openings.iter().enumerate().take(number_of_dealers) {
+            let dealer_index = dealer_index as NodeIndex;
+
+            let dealing = create_reshare_dealing(
+                new_setup.alg,
+                &new_setup.ad,
+                dealer_index,
+                &old.transcript,
+                opening,
+                new_setup.threshold,
+                &new_setup.pk,
+                Seed::from_rng(rng),
+            )
+            .expect("failed to create reshare dealing");
+
+            Self::test_public_dealing_verification(new_setup, &dealing, &mode, dealer_index);
+
+            dealings.insert(dealer_index, dealing);
+        }
+
+        let transcript = match reshare_transcript(
+            new_setup.alg,
+            &old.transcript,
+            new_setup.threshold,
+            &dealings,
+        ) {
+            Ok(t) => t,
+            Err(IDkgCreateTranscriptInternalError::InsufficientDealings) => {
+                return Err(ThresholdEcdsaError::InsufficientDealings)
+            }
+            Err(e) => panic!("Unexpected error from reshare_transcript {:?}", e),
+        };
+
+        assert!(verify_transcript(
+            &transcript,
+            new_setup.alg,
+            new_setup.threshold,
+            &dealings,
+            &mode
+        )
+        .is_ok());
+
+        Ok(Self::new(new_setup, dealings, transcript, mode))
+    }
+
     /// Runs a `ProtocolRound` for a `UnmaskedTimesMasked` transcript with
     /// `number_of_dealers` many distinct dealers.
     ///
@@ -736,10 +812,63 @@ impl SignatureProtocolSetup {
         })
     }
 
+    /// Returns a setup with the same key but a fresh presignature quadruple
+    pub fn with_new_presignature(
+        &self,
+        number_of_dealers: usize,
+        number_of_dealings_corrupted: usize,
+    ) -> ThresholdEcdsaResult<Self> {
+        let mut setup = self.setup.clone();
+        setup.seed = self.setup.next_dealing_seed();
+        setup.protocol_round = std::cell::Cell::new(0);
+
+        let kappa = ProtocolRound::random(&setup, number_of_dealers, number_of_dealings_corrupted)?;
+        let lambda =
+            ProtocolRound::random(&setup, number_of_dealers, number_of_dealings_corrupted)?;
+
+        let kappa = ProtocolRound::reshare_of_masked(
+            &setup,
+            &kappa,
+            number_of_dealers,
+            number_of_dealings_corrupted,
+        )?;
+
+        let key_times_lambda = ProtocolRound::multiply(
+            &setup,
+            &lambda,
+            &self.key,
+            number_of_dealers,
+            number_of_dealings_corrupted,
+        )?;
+        let kappa_times_lambda = ProtocolRound::multiply(
+            &setup,
+            &lambda,
+            &kappa,
+            number_of_dealers,
+            number_of_dealings_corrupted,
+        )?;
+
+        Ok(Self {
+            setup,
+            key: self.key.clone(),
+            kappa,
+            lambda,
+            key_times_lambda,
+            kappa_times_lambda,
+        })
+    }
+
     pub fn public_key(&self, path: &DerivationPath) -> Result<EcdsaPublicKey, ThresholdEcdsaError> {
+        let public_key = self.key.transcript.constant_term();
+
+        let algorithm_id = match public_key.curve_type() {
+            EccCurveType::K256 => AlgorithmId::EcdsaSecp256k1,
+            EccCurveType::P256 => AlgorithmId::EcdsaP256,
+        };
+
         let master_public_key = MasterEcdsaPublicKey {
-            algorithm_id: AlgorithmId::EcdsaSecp256k1,
-            public_key: self.key.transcript.constant_term().serialize(),
+            algorithm_id,
+            public_key: public_key.serialize(),
         };
         ic_crypto_internal_threshold_sig_ecdsa::sign::derive_public_key(&master_public_key, path)
     }
@@ -850,15 +979,343 @@ impl SignatureProtocolExecution {
         // If verification succeeded, check with RustCrypto's ECDSA also
         let pk = self.setup.public_key(&self.derivation_path)?;
 
-        use k256::ecdsa::signature::Verifier;
+        match pk.algorithm_id {
+            AlgorithmId::EcdsaSecp256k1 => {
+                use k256::ecdsa::signature::Verifier;
+
+                let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
+                    .expect("Failed to parse public key");
+
+                let sig = k256::ecdsa::Signature::try_from(sig.serialize().as_ref())
+                    .expect("Failed to parse signature");
+
+                assert!(vk.verify(&self.signed_message, &sig).is_ok());
+            }
+            AlgorithmId::EcdsaP256 => {
+                use p256::ecdsa::signature::Verifier;
+
+                let vk = p256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
+                    .expect("Failed to parse public key");
+
+                let sig = p256::ecdsa::Signature::try_from(sig.serialize().as_ref())
+                    .expect("Failed to parse signature");
+
+                assert!(vk.verify(&self.signed_message, &sig).is_ok());
+            }
+            alg => panic!("Unexpected algorithm {:?}", alg),
+        }
+
+        Ok(())
+    }
+}
+
+/// Several signature protocol executions which share the same key
+#[derive(Clone, Debug)]
+pub struct SignatureProtocolBatchExecution {
+    executions: Vec<SignatureProtocolExecution>,
+}
+
+impl SignatureProtocolBatchExecution {
+    pub fn new(executions: Vec<SignatureProtocolExecution>) -> Self {
+        assert!(!executions.is_empty());
+        for execution in &executions {
+            assert_eq!(
+                execution.setup.key.transcript,
+                executions[0].setup.key.transcript
+            );
+        }
+
+        Self { executions }
+    }
+
+    pub fn executions(&self) -> &[SignatureProtocolExecution] {
+        &self.executions
+    }
+
+    fn key_transcript(&self) -> &IDkgTranscriptInternal {
+        &self.executions[0].setup.key.transcript
+    }
+
+    fn alg(&self) -> AlgorithmId {
+        self.executions[0].setup.setup.alg
+    }
+
+    fn sign_inputs(&self, node_index: usize) -> Vec<ThresholdEcdsaSigShareBatchInput> {
+        self.executions
+            .iter()
+            .map(|e| ThresholdEcdsaSigShareBatchInput {
+                derivation_path: &e.derivation_path,
+                hashed_message: &e.hashed_message,
+                randomness: e.random_beacon,
+                presig_transcript: &e.setup.kappa.transcript,
+                lambda: &e.setup.lambda.openings[node_index],
+                kappa_times_lambda: &e.setup.kappa_times_lambda.openings[node_index],
+                key_times_lambda: &e.setup.key_times_lambda.openings[node_index],
+            })
+            .collect()
+    }
+
+    pub fn verification_inputs(&self) -> Vec<ThresholdEcdsaSigShareBatchVerificationInput> {
+        self.executions
+            .iter()
+            .map(|e| ThresholdEcdsaSigShareBatchVerificationInput {
+                derivation_path: &e.derivation_path,
+                hashed_message: &e.hashed_message,
+                randomness: e.random_beacon,
+                presig_transcript: &e.setup.kappa.transcript,
+                lambda: &e.setup.lambda.transcript,
+                kappa_times_lambda: &e.setup.kappa_times_lambda.transcript,
+                key_times_lambda: &e.setup.key_times_lambda.transcript,
+            })
+            .collect()
+    }
+
+    pub fn generate_shares_batch(
+        &self,
+    ) -> ThresholdEcdsaResult<BTreeMap<u32, ThresholdEcdsaSigShareBatchInternal>> {
+        let mut batches = BTreeMap::new();
+
+        let verification_inputs = self.verification_inputs();
+
+        for node_index in 0..self.executions[0].setup.setup.receivers {
+            let batch = self
+                .generate_share_batch(node_index)
+                .expect("Failed to create sig share batch");
+
+            self.verify_share_batch(&batch, &verification_inputs, node_index as NodeIndex)
+                .expect("Signature share batch verification failed");
+
+            batches.insert(node_index as NodeIndex, batch);
+        }
+
+        Ok(batches)
+    }
+
+    pub fn generate_share_batch(
+        &self,
+        node_index: usize,
+    ) -> Result<ThresholdEcdsaSigShareBatchInternal, ThresholdEcdsaGenerateSigShareInternalError>
+    {
+        sign_share_batch(
+            &self.sign_inputs(node_index),
+            self.key_transcript(),
+            self.alg(),
+        )
+    }
+
+    pub fn verify_share_batch(
+        &self,
+        batch: &ThresholdEcdsaSigShareBatchInternal,
+        inputs: &[ThresholdEcdsaSigShareBatchVerificationInput],
+        signer_index: NodeIndex,
+    ) -> Result<(), ThresholdEcdsaVerifySigShareInternalError> {
+        verify_signature_share_batch(
+            batch,
+            inputs,
+            signer_index,
+            self.key_transcript(),
+            self.alg(),
+        )
+    }
+
+    /// Split the batches of each node into the shares of each execution
+    pub fn shares_by_execution(
+        &self,
+        batches: &BTreeMap<NodeIndex, ThresholdEcdsaSigShareBatchInternal>,
+    ) -> Vec<BTreeMap<NodeIndex, ThresholdEcdsaSigShareInternal>> {
+        let mut shares = vec![BTreeMap::new(); self.executions.len()];
+
+        for (node_index, batch) in batches {
+            assert_eq!(batch.shares().len(), self.executions.len());
+            for (i, share) in batch.shares().iter().enumerate() {
+                shares[i].insert(*node_index, share.clone());
+            }
+        }
+
+        shares
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SchnorrSignatureProtocolSetup {
+    setup: ProtocolSetup,
+    pub key: ProtocolRound,
+    pub presig: ProtocolRound,
+}
+
+impl SchnorrSignatureProtocolSetup {
+    pub fn new(
+        number_of_dealers: usize,
+        threshold: usize,
+        number_of_dealings_corrupted: usize,
+        seed: Seed,
+    ) -> ThresholdEcdsaResult<Self> {
+        let setup = ProtocolSetup::new_for_algorithm(
+            AlgorithmId::ThresholdSchnorrBip340,
+            EccCurveType::K256,
+            number_of_dealers,
+            threshold,
+            seed,
+        )?;
+
+        let key = ProtocolRound::random(&setup, number_of_dealers, number_of_dealings_corrupted)?;
+        let presig =
+            ProtocolRound::random(&setup, number_of_dealers, number_of_dealings_corrupted)?;
+
+        let key = ProtocolRound::reshare_of_masked(
+            &setup,
+            &key,
+            number_of_dealers,
+            number_of_dealings_corrupted,
+        )?;
+        let presig = ProtocolRound::reshare_of_masked(
+            &setup,
+            &presig,
+            number_of_dealers,
+            number_of_dealings_corrupted,
+        )?;
+
+        Ok(Self { setup, key, presig })
+    }
+
+    pub fn public_key(&self, path: &DerivationPath) -> Result<Vec<u8>, ThresholdEcdsaError> {
+        let master_public_key = MasterEcdsaPublicKey {
+            algorithm_id: AlgorithmId::ThresholdSchnorrBip340,
+            public_key: self.key.transcript.constant_term().serialize(),
+        };
+        ic_crypto_internal_threshold_sig_ecdsa::schnorr::derive_bip340_public_key(
+            &master_public_key,
+            path,
+        )
+    }
+
+    pub fn alg(&self) -> AlgorithmId {
+        self.setup.alg
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SchnorrSignatureProtocolExecution {
+    setup: SchnorrSignatureProtocolSetup,
+    signed_message: Vec<u8>,
+    random_beacon: Randomness,
+    derivation_path: DerivationPath,
+}
+
+impl SchnorrSignatureProtocolExecution {
+    pub fn new(
+        setup: SchnorrSignatureProtocolSetup,
+        signed_message: Vec<u8>,
+        random_beacon: Randomness,
+        derivation_path: DerivationPath,
+    ) -> Self {
+        Self {
+            setup,
+            signed_message,
+            random_beacon,
+            derivation_path,
+        }
+    }
+
+    pub fn generate_shares(
+        &self,
+    ) -> ThresholdEcdsaResult<BTreeMap<NodeIndex, ThresholdSchnorrSigShareInternal>> {
+        let mut shares = BTreeMap::new();
+
+        for node_index in 0..self.setup.setup.receivers {
+            let share = sign_schnorr_share(
+                &self.derivation_path,
+                &self.signed_message,
+                self.random_beacon,
+                &self.setup.key.transcript,
+                &self.setup.key.openings[node_index],
+                &self.setup.presig.transcript,
+                &self.setup.presig.openings[node_index],
+                self.setup.setup.alg,
+            )
+            .expect("Failed to create sig share");
+
+            verify_schnorr_signature_share(
+                &share,
+                &self.derivation_path,
+                &self.signed_message,
+                self.random_beacon,
+                node_index as NodeIndex,
+                &self.setup.key.transcript,
+                &self.setup.presig.transcript,
+                self.setup.setup.alg,
+            )
+            .expect("Signature share verification failed");
+
+            shares.insert(node_index as NodeIndex, share);
+        }
+
+        Ok(shares)
+    }
+
+    pub fn verify_share(
+        &self,
+        signer_index: NodeIndex,
+        share: &ThresholdSchnorrSigShareInternal,
+    ) -> Result<(), ThresholdSchnorrVerifySigShareInternalError> {
+        verify_schnorr_signature_share(
+            share,
+            &self.derivation_path,
+            &self.signed_message,
+            self.random_beacon,
+            signer_index,
+            &self.setup.key.transcript,
+            &self.setup.presig.transcript,
+            self.setup.setup.alg,
+        )
+    }
+
+    pub fn generate_signature(
+        &self,
+        shares: &BTreeMap<NodeIndex, ThresholdSchnorrSigShareInternal>,
+    ) -> Result<ThresholdSchnorrCombinedSigInternal, ThresholdSchnorrCombineSigSharesInternalError>
+    {
+        combine_schnorr_sig_shares(
+            &self.derivation_path,
+            &self.signed_message,
+            self.random_beacon,
+            &self.setup.key.transcript,
+            &self.setup.presig.transcript,
+            self.setup.setup.threshold,
+            shares,
+            self.setup.setup.alg,
+        )
+    }
+
+    pub fn verify_signature(
+        &self,
+        sig: &ThresholdSchnorrCombinedSigInternal,
+    ) -> Result<(), ThresholdSchnorrVerifySignatureInternalError> {
+        verify_threshold_schnorr_signature(
+            sig,
+            &self.derivation_path,
+            &self.signed_message,
+            self.random_beacon,
+            &self.setup.presig.transcript,
+            &self.setup.key.transcript,
+            self.setup.setup.alg,
+        )?;
+
+        // If verification succeeded, check with RustCrypto's BIP-340 also,
+        // which only supports messages of exactly 32 bytes
+        if self.signed_message.len() != 32 {
+            return Ok(());
+        }
+
+        let pk = self.setup.public_key(&self.derivation_path)?;
 
-        let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
-            .expect("Failed to parse public key");
+        let vk = k256::schnorr::VerifyingKey::from_bytes(&pk).expect("Failed to parse public key");
 
-        let sig = k256::ecdsa::Signature::try_from(sig.serialize().as_ref())
+        let sig = k256::schnorr::Signature::try_from(sig.serialize().as_ref())
             .expect("Failed to parse signature");
 
-        assert!(vk.verify(&self.signed_message, &sig).is_ok());
+        use k256::schnorr::signature::hazmat::PrehashVerifier;
+        assert!(vk.verify_prehash(&self.signed_message, &sig).is_ok());
 
         Ok(())
     }
@@ -103,6 +103,29 @@ impl std::fmt::Debug for CspSecretKey {
                     "CspSecretKey::IDkgCommitmentOpening::Pedersen::K256 - REDACTED"
                 )
             }
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Simple(EccScalarBytes::P256(
+                _,
+            ))) => {
+                write!(
+                    f,
+                    "CspSecretKey::IDkgCommitmentOpening::Simple::P256 - REDACTED"
+                )
+            }
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(
+                EccScalarBytes::P256(_),
+                EccScalarBytes::P256(_),
+            )) => {
+                write!(
+                    f,
+                    "CspSecretKey::IDkgCommitmentOpening::Pedersen::P256 - REDACTED"
+                )
+            }
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(_, _)) => {
+                write!(
+                    f,
+                    "CspSecretKey::IDkgCommitmentOpening::Pedersen - REDACTED"
+                )
+            }
         }
     }
 }
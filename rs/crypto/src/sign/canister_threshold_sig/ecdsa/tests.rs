@@ -54,7 +54,12 @@ mod get_tecdsa_master_public_key {
     #[test]
     fn should_return_error_if_algorithm_id_is_invalid() {
         AlgorithmId::iter()
-            .filter(|algorithm_id| *algorithm_id != AlgorithmId::ThresholdEcdsaSecp256k1)
+            .filter(|algorithm_id| {
+                !matches!(
+                    algorithm_id,
+                    AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdEcdsaSecp256r1
+                )
+            })
             .for_each(|wrong_algorithm_id| {
                 let transcript = dummy_transcript(
                     IDkgTranscriptType::Unmasked(IDkgUnmaskedTranscriptOrigin::ReshareUnmasked(
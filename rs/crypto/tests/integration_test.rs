@@ -889,6 +889,10 @@ fn algorithm_id_should_match_algorithm_id_proto() {
         AlgorithmId::ThresholdEcdsaSecp256r1 as i32,
         AlgorithmIdProto::ThresholdEcdsaSecp256r1 as i32
     );
+    assert_eq!(
+        AlgorithmId::ThresholdSchnorrBip340 as i32,
+        AlgorithmIdProto::ThresholdSchnorrBip340 as i32
+    );
 }
 
 #[test]
@@ -56,8 +56,8 @@ pub struct SerializedModule {
     /// The serialized `wasmtime::Module`. This field is wrapped in an `Arc` so
     /// that it can be cheaply moved out of the `SerializedModule` in the cases
     /// when the other fields aren't needed.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub bytes: Arc<SerializedModuleBytes>,
     /// List of functions exported by the canister.
     pub exported_functions: BTreeSet<WasmMethod>,
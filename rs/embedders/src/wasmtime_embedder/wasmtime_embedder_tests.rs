@@ -73,6 +73,9 @@ fn test_wasmtime_system_api() {
         EmbeddersConfig::default()
             .feature_flags
             .wasm_native_stable_memory,
+        EmbeddersConfig::default()
+            .feature_flags
+            .canister_debug_print_records,
         EmbeddersConfig::default().max_sum_exported_function_name_lengths,
         Memory::new_for_testing(),
         Arc::new(DefaultOutOfInstructionsHandler {}),
@@ -11,6 +11,7 @@ contract CkEthDeposit {
     address payable private cketh_minter_main_address;
 
     event ReceivedEth(address indexed from, uint256 value, bytes32 indexed principal);
+    event ReceivedEthToSubaccount(address indexed from, uint256 value, bytes32 indexed principal, bytes32 subaccount);
     event SentEth(address indexed to, uint256 value);
 
     /**
@@ -35,4 +36,13 @@ contract CkEthDeposit {
         emit ReceivedEth(msg.sender, msg.value, _principal);
         cketh_minter_main_address.transfer(msg.value);
     }
+
+    /**
+     * @dev Emits the `ReceivedEthToSubaccount` event if the transfer succeeds.
+     * The deposited ETH is credited to the ICRC-1 subaccount `_subaccount` of `_principal`.
+     */
+    function depositToSubaccount(bytes32 _principal, bytes32 _subaccount) public payable {
+        emit ReceivedEthToSubaccount(msg.sender, msg.value, _principal, _subaccount);
+        cketh_minter_main_address.transfer(msg.value);
+    }
 } 
\ No newline at end of file
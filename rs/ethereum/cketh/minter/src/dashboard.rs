@@ -43,6 +43,7 @@ pub struct DashboardTemplate {
     pub minted_events: Vec<MintedEvent>,
     pub events_to_mint: Vec<ReceivedEthEvent>,
     pub rejected_deposits: BTreeMap<EventSource, String>,
+    pub reorged_deposits: Vec<(MintedEvent, String)>,
     pub withdrawal_requests: Vec<EthWithdrawalRequest>,
     pub pending_transactions: Vec<DashboardPendingTransaction>,
     pub finalized_transactions: Vec<DashboardFinalizedTransaction>,
@@ -55,6 +56,19 @@ impl DashboardTemplate {
         let mut events_to_mint: Vec<_> = state.events_to_mint.values().cloned().collect();
         events_to_mint.sort_unstable_by_key(|event| Reverse(event.block_number));
 
+        let mut reorged_deposits: Vec<_> = state
+            .reorged_deposits
+            .iter()
+            .map(|(source, reason)| {
+                let minted_event = state
+                    .minted_events
+                    .get(source)
+                    .expect("BUG: reorged deposit must have been minted");
+                (minted_event.clone(), reason.clone())
+            })
+            .collect();
+        reorged_deposits.sort_unstable_by_key(|(event, _reason)| Reverse(event.mint_block_index));
+
         let mut withdrawal_requests: Vec<_> = state
             .eth_transactions
             .withdrawal_requests_iter()
@@ -119,6 +133,7 @@ impl DashboardTemplate {
             minted_events,
             events_to_mint,
             rejected_deposits: state.invalid_events.clone(),
+            reorged_deposits,
             withdrawal_requests,
             pending_transactions,
             finalized_transactions,
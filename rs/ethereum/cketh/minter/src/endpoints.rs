@@ -1,6 +1,12 @@
-use crate::transactions::EthWithdrawalRequest;
+use crate::eth_rpc::FeeHistory;
+use crate::eth_rpc_client::ProvidersHealth;
+use crate::transactions::{CancelWithdrawalRequestError, EthWithdrawalRequest};
 use crate::tx::{SignedEip1559TransactionRequest, TransactionPrice};
-use candid::{CandidType, Deserialize, Nat};
+use crate::tx::{
+    DEFAULT_FEE_HISTORY_BLOCK_COUNT, DEFAULT_FEE_HISTORY_REWARD_PERCENTILE,
+    MAX_FEE_HISTORY_BLOCK_COUNT,
+};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
 use minicbor::{Decode, Encode};
 use serde::Serialize;
@@ -12,6 +18,7 @@ pub struct Eip1559TransactionPrice {
     pub max_fee_per_gas: Nat,
     pub max_priority_fee_per_gas: Nat,
     pub max_transaction_fee: Nat,
+    pub fee_history: Option<Eip1559FeeHistory>,
 }
 
 impl From<TransactionPrice> for Eip1559TransactionPrice {
@@ -21,6 +28,112 @@ impl From<TransactionPrice> for Eip1559TransactionPrice {
             max_fee_per_gas: value.max_fee_per_gas.into(),
             max_priority_fee_per_gas: value.max_priority_fee_per_gas.into(),
             max_transaction_fee: value.max_transaction_fee().into(),
+            fee_history: None,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct Eip1559TransactionPriceArg {
+    pub reward_percentile: Option<u8>,
+    pub block_count: Option<u8>,
+}
+
+impl Eip1559TransactionPriceArg {
+    pub fn block_count(&self) -> u8 {
+        self.block_count.unwrap_or(DEFAULT_FEE_HISTORY_BLOCK_COUNT)
+    }
+
+    pub fn reward_percentile(&self) -> u8 {
+        self.reward_percentile
+            .unwrap_or(DEFAULT_FEE_HISTORY_REWARD_PERCENTILE)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let block_count = self.block_count();
+        if block_count == 0 || block_count > MAX_FEE_HISTORY_BLOCK_COUNT {
+            return Err(format!(
+                "block_count must be between 1 and {MAX_FEE_HISTORY_BLOCK_COUNT}, got {block_count}"
+            ));
+        }
+        let reward_percentile = self.reward_percentile();
+        if reward_percentile > 100 {
+            return Err(format!(
+                "reward_percentile must be between 0 and 100, got {reward_percentile}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The fee history from which a transaction price was estimated.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Eip1559FeeHistory {
+    pub oldest_block: Nat,
+    pub base_fee_per_gas: Vec<Nat>,
+    pub reward_percentile: u8,
+    pub reward: Vec<Nat>,
+}
+
+impl Eip1559FeeHistory {
+    pub fn new(fee_history: FeeHistory, reward_percentile: u8) -> Self {
+        Self {
+            oldest_block: fee_history.oldest_block.into(),
+            base_fee_per_gas: fee_history
+                .base_fee_per_gas
+                .into_iter()
+                .map(Nat::from)
+                .collect(),
+            reward_percentile,
+            reward: fee_history
+                .reward
+                .into_iter()
+                .flatten()
+                .map(Nat::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RpcProviderHealth {
+    pub url: String,
+    pub latency_ms: u64,
+    pub block_number: Option<Nat>,
+    /// Difference between the block number returned by the provider and the quorum block number.
+    pub block_divergence: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CheckRpcProvidersResult {
+    pub quorum_block_number: Option<Nat>,
+    pub providers: Vec<RpcProviderHealth>,
+}
+
+impl From<ProvidersHealth> for CheckRpcProvidersResult {
+    fn from(health: ProvidersHealth) -> Self {
+        let quorum = health.quorum_block_number;
+        Self {
+            quorum_block_number: quorum.map(Nat::from),
+            providers: health
+                .providers
+                .into_iter()
+                .map(|provider| {
+                    let block_divergence = provider.block_divergence(quorum);
+                    let (block_number, error) = match provider.block_number {
+                        Ok(block_number) => (Some(block_number), None),
+                        Err(error) => (None, Some(error)),
+                    };
+                    RpcProviderHealth {
+                        url: provider.url,
+                        latency_ms: provider.latency.as_millis() as u64,
+                        block_number: block_number.map(Nat::from),
+                        block_divergence,
+                        error,
+                    }
+                })
+                .collect(),
         }
     }
 }
@@ -94,6 +207,38 @@ impl Display for RetrieveEthStatus {
     }
 }
 
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ListWithdrawalsArg {
+    pub principal: Principal,
+    pub start: u64,
+    pub length: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawalDetails {
+    pub withdrawal_id: u64,
+    pub recipient_address: String,
+    pub withdrawal_amount: Nat,
+    pub status: RetrieveEthStatus,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ListWithdrawalsResult {
+    pub withdrawals: Vec<WithdrawalDetails>,
+    pub total_withdrawal_count: u64,
+}
+
+impl WithdrawalDetails {
+    pub fn new(request: &EthWithdrawalRequest, status: RetrieveEthStatus) -> Self {
+        Self {
+            withdrawal_id: request.ledger_burn_index.get(),
+            recipient_address: request.destination.to_string(),
+            withdrawal_amount: request.withdrawal_amount.into(),
+            status,
+        }
+    }
+}
+
 #[derive(CandidType, Deserialize)]
 pub struct WithdrawalArg {
     pub amount: Nat,
@@ -106,6 +251,33 @@ pub enum WithdrawalError {
     InsufficientFunds { balance: Nat },
     InsufficientAllowance { allowance: Nat },
     TemporarilyUnavailable(String),
+    RecipientBlocked { address: String },
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CancelWithdrawalResult {
+    /// The ckETH ledger index of the transaction reimbursing the withdrawal amount.
+    pub reimbursement_block_index: Nat,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub enum CancelWithdrawalError {
+    NotFound,
+    NotOwner,
+    TransactionAlreadyCreated,
+    TemporarilyUnavailable(String),
+}
+
+impl From<CancelWithdrawalRequestError> for CancelWithdrawalError {
+    fn from(error: CancelWithdrawalRequestError) -> Self {
+        match error {
+            CancelWithdrawalRequestError::NotFound => Self::NotFound,
+            CancelWithdrawalRequestError::NotOwner => Self::NotOwner,
+            CancelWithdrawalRequestError::TransactionAlreadyCreated => {
+                Self::TransactionAlreadyCreated
+            }
+        }
+    }
 }
 
 impl From<TransferFromError> for WithdrawalError {
@@ -184,6 +356,7 @@ pub mod events {
             from_address: String,
             value: Nat,
             principal: Principal,
+            subaccount: Option<[u8; 32]>,
         },
         InvalidDeposit {
             event_source: EventSource,
@@ -200,6 +373,7 @@ pub mod events {
             withdrawal_amount: Nat,
             destination: String,
             ledger_burn_index: Nat,
+            from: Option<Principal>,
         },
         SignedTx {
             withdrawal_id: Nat,
@@ -213,5 +387,49 @@ pub mod events {
             withdrawal_id: Nat,
             transaction_hash: String,
         },
+        FeeRefunded {
+            withdrawal_id: Nat,
+            beneficiary: Principal,
+            amount: Nat,
+            refund_block_index: Nat,
+        },
+        ReorgedDeposit {
+            event_source: EventSource,
+            reason: String,
+        },
+        BlockedWithdrawalAttempt {
+            caller: Principal,
+            destination: String,
+            amount: Nat,
+        },
+        FailedMint {
+            event_source: EventSource,
+            reason: String,
+        },
+        SkippedBlockRange {
+            caller: Principal,
+            from: Nat,
+            to: Nat,
+        },
+        RescanRequested {
+            caller: Principal,
+            from: Nat,
+            to: Nat,
+        },
+        WithdrawalCancelled {
+            withdrawal_id: Nat,
+            reimbursement_block_index: Nat,
+        },
+        RequeuedFailedMint {
+            caller: Principal,
+            event_source: EventSource,
+        },
+        WithdrawalCancellationRequested {
+            withdrawal_id: Nat,
+            created_at_time: u64,
+        },
+        WithdrawalCancellationRejected {
+            withdrawal_id: Nat,
+        },
     }
 }
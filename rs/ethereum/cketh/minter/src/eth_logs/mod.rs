@@ -3,6 +3,7 @@ mod tests;
 
 use crate::address::Address;
 use crate::eth_rpc::{FixedSizeData, Hash, LogEntry};
+use crate::eth_rpc_client::responses::{TransactionReceiptWithLogs, TransactionStatus};
 use crate::eth_rpc_client::EthRpcClient;
 use crate::logs::{DEBUG, INFO};
 use crate::numeric::{BlockNumber, LogIndex, Wei};
@@ -10,6 +11,7 @@ use crate::state::read_state;
 use candid::Principal;
 use hex_literal::hex;
 use ic_canister_log::log;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -17,6 +19,8 @@ use thiserror::Error;
 
 pub(crate) const RECEIVED_ETH_EVENT_TOPIC: [u8; 32] =
     hex!("257e057bb61920d8d0ed2cb7b720ac7f9c513cd1110bc9fa543079154f45f435");
+pub(crate) const RECEIVED_ETH_TO_SUBACCOUNT_EVENT_TOPIC: [u8; 32] =
+    hex!("84c64df3ee15dc696580c0d8df45788b840751d6828434944ad88589c15b6171");
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Encode, Decode)]
 pub struct ReceivedEthEvent {
@@ -32,6 +36,10 @@ pub struct ReceivedEthEvent {
     pub value: Wei,
     #[cbor(n(5), with = "crate::cbor::principal")]
     pub principal: Principal,
+    /// The ICRC-1 subaccount of `principal` to credit.
+    /// `None` for the default account.
+    #[cbor(n(6), with = "minicbor::bytes", has_nil)]
+    pub subaccount: Option<Subaccount>,
 }
 
 impl fmt::Debug for ReceivedEthEvent {
@@ -43,6 +51,10 @@ impl fmt::Debug for ReceivedEthEvent {
             .field("from_address", &self.from_address)
             .field("value", &self.value)
             .field("principal", &format_args!("{}", self.principal))
+            .field(
+                "subaccount",
+                &self.subaccount.as_ref().map(|s| hex::encode(s.as_slice())),
+            )
             .finish()
     }
 }
@@ -72,6 +84,14 @@ impl ReceivedEthEvent {
             log_index: self.log_index,
         }
     }
+
+    /// The ICRC-1 account credited with the deposited ETH.
+    pub fn account(&self) -> Account {
+        Account {
+            owner: self.principal,
+            subaccount: self.subaccount,
+        }
+    }
 }
 
 pub async fn last_received_eth_events(
@@ -79,7 +99,7 @@ pub async fn last_received_eth_events(
     from: BlockNumber,
     to: BlockNumber,
 ) -> (Vec<ReceivedEthEvent>, Vec<ReceivedEthEventError>) {
-    use crate::eth_rpc::GetLogsParam;
+    use crate::eth_rpc::{GetLogsParam, Topic};
 
     if from > to {
         ic_cdk::trap(&format!(
@@ -93,7 +113,10 @@ pub async fn last_received_eth_events(
             from_block: from.into(),
             to_block: to.into(),
             address: vec![contract_address],
-            topics: vec![FixedSizeData(RECEIVED_ETH_EVENT_TOPIC)],
+            topics: vec![Topic::from(vec![
+                FixedSizeData(RECEIVED_ETH_EVENT_TOPIC),
+                FixedSizeData(RECEIVED_ETH_TO_SUBACCOUNT_EVENT_TOPIC),
+            ])],
         })
         .await
         .expect("HTTP call failed");
@@ -191,18 +214,35 @@ impl TryFrom<LogEntry> for ReceivedEthEvent {
                 },
             }
         })?;
-        let value_bytes: [u8; 32] =
-            entry
-                .data
-                .0
-                .try_into()
-                .map_err(|data| ReceivedEthEventError::InvalidEventSource {
+        let (value_bytes, subaccount) = if entry.topics[0].0
+            == RECEIVED_ETH_TO_SUBACCOUNT_EVENT_TOPIC
+        {
+            let data: [u8; 64] = entry.data.0.try_into().map_err(|data| {
+                ReceivedEthEventError::InvalidEventSource {
+                    source: event_source,
+                    error: EventSourceError::InvalidEvent(format!(
+                        "Invalid data length; expected 32-byte value and 32-byte subaccount, got {}",
+                        hex::encode(data)
+                    )),
+                }
+            })?;
+            let (value_bytes, subaccount) = data.split_at(32);
+            (
+                <[u8; 32]>::try_from(value_bytes).expect("BUG: value is 32 bytes"),
+                parse_subaccount(subaccount.try_into().expect("BUG: subaccount is 32 bytes")),
+            )
+        } else {
+            let value_bytes: [u8; 32] = entry.data.0.try_into().map_err(|data| {
+                ReceivedEthEventError::InvalidEventSource {
                     source: event_source,
                     error: EventSourceError::InvalidEvent(format!(
                         "Invalid data length; expected 32-byte value, got {}",
                         hex::encode(data)
                     )),
-                })?;
+                }
+            })?;
+            (value_bytes, None)
+        };
         let value = Wei::from_be_bytes(value_bytes);
 
         Ok(ReceivedEthEvent {
@@ -212,10 +252,65 @@ impl TryFrom<LogEntry> for ReceivedEthEvent {
             from_address,
             value,
             principal,
+            subaccount,
         })
     }
 }
 
+/// Why the receipt of a deposit transaction does not back a log entry returned by `eth_getLogs`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DepositReceiptMismatch {
+    #[error("receipt mismatch: transaction {transaction_hash} has status {status}")]
+    TransactionNotSuccessful {
+        transaction_hash: Hash,
+        status: TransactionStatus,
+    },
+    #[error(
+        "receipt mismatch: log entry {event_source} is not included in the transaction receipt"
+    )]
+    LogNotIncluded { event_source: EventSource },
+}
+
+/// Checks that the deposit `event`, scraped from the logs of `contract_address`,
+/// is backed by the `receipt` of its transaction: the transaction must have
+/// succeeded and its receipt must contain the exact same log entry.
+///
+/// Some providers were observed returning logs of reverted transactions,
+/// which must not result in minting.
+pub fn check_deposit_receipt(
+    event: &ReceivedEthEvent,
+    contract_address: Address,
+    receipt: &TransactionReceiptWithLogs,
+) -> Result<(), DepositReceiptMismatch> {
+    if receipt.status != TransactionStatus::Success {
+        return Err(DepositReceiptMismatch::TransactionNotSuccessful {
+            transaction_hash: event.transaction_hash,
+            status: receipt.status,
+        });
+    }
+    let included = receipt.logs.iter().any(|entry| {
+        entry.address == contract_address
+            && !entry.removed
+            && ReceivedEthEvent::try_from(entry.clone()).as_ref() == Ok(event)
+    });
+    if !included {
+        return Err(DepositReceiptMismatch::LogNotIncluded {
+            event_source: event.source(),
+        });
+    }
+    Ok(())
+}
+
+/// The all-zero subaccount designates the default account,
+/// which is represented by `None`.
+fn parse_subaccount(subaccount: Subaccount) -> Option<Subaccount> {
+    if subaccount == [0; 32] {
+        None
+    } else {
+        Some(subaccount)
+    }
+}
+
 /// Decode a candid::Principal from a slice of at most 32 bytes
 /// encoded as follows
 /// - the first byte is the number of bytes in the principal
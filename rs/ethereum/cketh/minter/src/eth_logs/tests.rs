@@ -117,3 +117,142 @@ mod parse_principal_from_slice {
         principal_bytes
     }
 }
+
+mod check_deposit_receipt {
+    use crate::address::Address;
+    use crate::eth_logs::{check_deposit_receipt, DepositReceiptMismatch, ReceivedEthEvent};
+    use crate::eth_rpc_client::responses::{TransactionReceiptWithLogs, TransactionStatus};
+    use assert_matches::assert_matches;
+    use std::str::FromStr;
+
+    const CONTRACT_ADDRESS: &str = "0xb44b5e756a894775fc32eddf3314bb1b1944dc34";
+    const RECEIPT: &str = r#"{
+        "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+        "blockNumber": "0x3ca487",
+        "contractAddress": null,
+        "cumulativeGasUsed": "0x4a5a0b",
+        "effectiveGasPrice": "0x2e0e5d7b9",
+        "from": "0xdd2851cdd40ae6536831558dd46db62fac7a844d",
+        "gasUsed": "0x6f65",
+        "logs": [
+            {
+                "address": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+                "topics": [
+                    "0x257e057bb61920d8d0ed2cb7b720ac7f9c513cd1110bc9fa543079154f45f435",
+                    "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                    "0x09efcdab00000000000100000000000000000000000000000000000000000000"
+                ],
+                "data": "0x000000000000000000000000000000000000000000000000002386f26fc10000",
+                "blockNumber": "0x3ca487",
+                "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+                "transactionIndex": "0x22",
+                "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+                "logIndex": "0x27",
+                "removed": false
+            }
+        ],
+        "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+        "status": "0x1",
+        "to": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+        "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+        "transactionIndex": "0x22",
+        "type": "0x2"
+    }"#;
+
+    #[test]
+    fn should_accept_deposit_included_in_successful_transaction() {
+        let receipt = receipt();
+        let event = deposit_event(&receipt);
+
+        assert_eq!(
+            check_deposit_receipt(&event, contract_address(), &receipt),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn should_reject_deposit_of_reverted_transaction() {
+        let mut receipt = receipt();
+        let event = deposit_event(&receipt);
+        receipt.status = TransactionStatus::Failure;
+
+        assert_eq!(
+            check_deposit_receipt(&event, contract_address(), &receipt),
+            Err(DepositReceiptMismatch::TransactionNotSuccessful {
+                transaction_hash: event.transaction_hash,
+                status: TransactionStatus::Failure,
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_deposit_not_included_in_receipt() {
+        let receipt = receipt();
+        let event = deposit_event(&receipt);
+
+        let mut receipt_without_logs = receipt.clone();
+        receipt_without_logs.logs.clear();
+        let mut receipt_with_removed_log = receipt.clone();
+        receipt_with_removed_log.logs[0].removed = true;
+        let mut receipt_with_other_log_index = receipt.clone();
+        receipt_with_other_log_index.logs[0].log_index = Some(0_u8.into());
+
+        for receipt in [
+            receipt_without_logs,
+            receipt_with_removed_log,
+            receipt_with_other_log_index,
+        ] {
+            assert_eq!(
+                check_deposit_receipt(&event, contract_address(), &receipt),
+                Err(DepositReceiptMismatch::LogNotIncluded {
+                    event_source: event.source(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_deposit_with_different_amount_or_emitter() {
+        let receipt = receipt();
+        let event = deposit_event(&receipt);
+
+        let mut inflated_event = event.clone();
+        inflated_event.value = inflated_event.value.checked_mul(10_u8).unwrap();
+        assert_matches!(
+            check_deposit_receipt(&inflated_event, contract_address(), &receipt),
+            Err(DepositReceiptMismatch::LogNotIncluded { .. })
+        );
+
+        let other_contract =
+            Address::from_str("0x7e41257f7b5c3dd3313ef02b1f4c864fe95bec2b").unwrap();
+        assert_matches!(
+            check_deposit_receipt(&event, other_contract, &receipt),
+            Err(DepositReceiptMismatch::LogNotIncluded { .. })
+        );
+    }
+
+    #[test]
+    fn should_use_distinct_reason_for_receipt_mismatch() {
+        let receipt = receipt();
+        let event = deposit_event(&receipt);
+
+        let reason = DepositReceiptMismatch::LogNotIncluded {
+            event_source: event.source(),
+        }
+        .to_string();
+
+        assert!(reason.starts_with("receipt mismatch: "), "{reason}");
+    }
+
+    fn contract_address() -> Address {
+        Address::from_str(CONTRACT_ADDRESS).unwrap()
+    }
+
+    fn receipt() -> TransactionReceiptWithLogs {
+        serde_json::from_str(RECEIPT).unwrap()
+    }
+
+    fn deposit_event(receipt: &TransactionReceiptWithLogs) -> ReceivedEthEvent {
+        ReceivedEthEvent::try_from(receipt.logs[0].clone()).unwrap()
+    }
+}
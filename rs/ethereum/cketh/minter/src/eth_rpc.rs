@@ -3,7 +3,7 @@
 
 use crate::address::Address;
 use crate::endpoints::CandidBlockTag;
-use crate::eth_rpc_client::responses::TransactionReceipt;
+use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionReceiptWithLogs};
 use crate::eth_rpc_error::{sanitize_send_raw_transaction_result, Parser};
 use crate::logs::{DEBUG, TRACE_HTTP};
 use crate::numeric::{BlockNumber, LogIndex, TransactionCount, Wei, WeiPerGas};
@@ -244,7 +244,29 @@ pub struct GetLogsParam {
     /// Topics are order-dependent.
     /// Each topic can also be an array of DATA with "or" options.
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub topics: Vec<FixedSizeData>,
+    pub topics: Vec<Topic>,
+}
+
+/// A topic filter of the [`eth_getLogs`](https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_getlogs) call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Topic {
+    /// Matches logs with exactly this topic.
+    Single(FixedSizeData),
+    /// Matches logs with any of these topics.
+    Multiple(Vec<FixedSizeData>),
+}
+
+impl From<FixedSizeData> for Topic {
+    fn from(data: FixedSizeData) -> Self {
+        Topic::Single(data)
+    }
+}
+
+impl From<Vec<FixedSizeData>> for Topic {
+    fn from(data: Vec<FixedSizeData>) -> Self {
+        Topic::Multiple(data)
+    }
 }
 
 /// An entry of the [`eth_getLogs`](https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_getlogs) call reply.
@@ -433,6 +455,7 @@ pub enum ResponseTransform {
     Block,
     LogEntries,
     TransactionReceipt,
+    TransactionReceiptWithLogs,
     FeeHistory,
     SendRawTransaction,
 }
@@ -456,6 +479,9 @@ impl ResponseTransform {
             Self::Block => redact_response::<Block>(body_bytes),
             Self::LogEntries => redact_response::<Vec<LogEntry>>(body_bytes),
             Self::TransactionReceipt => redact_response::<TransactionReceipt>(body_bytes),
+            Self::TransactionReceiptWithLogs => {
+                redact_response::<TransactionReceiptWithLogs>(body_bytes)
+            }
             Self::FeeHistory => redact_response::<FeeHistory>(body_bytes),
             Self::SendRawTransaction => {
                 sanitize_send_raw_transaction_result(body_bytes, Parser::new())
@@ -6,16 +6,19 @@ use crate::eth_rpc::{
 };
 use crate::eth_rpc_client::providers::{RpcNodeProvider, MAINNET_PROVIDERS, SEPOLIA_PROVIDERS};
 use crate::eth_rpc_client::requests::GetTransactionCountParams;
-use crate::eth_rpc_client::responses::TransactionReceipt;
+use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionReceiptWithLogs};
 use crate::lifecycle::EthereumNetwork;
 use crate::logs::{DEBUG, INFO};
-use crate::numeric::TransactionCount;
+use crate::numeric::{BlockNumber, TransactionCount};
 use crate::state::State;
+use candid::CandidType;
 use ic_canister_log::log;
+use minicbor::{Decode, Encode};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::time::Duration;
 
 mod providers;
 pub mod requests;
@@ -24,25 +27,239 @@ pub mod responses;
 #[cfg(test)]
 mod tests;
 
+/// How the responses of the providers queried in parallel are reduced to a
+/// single result.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum ConsensusStrategy {
+    /// All providers must return the same result.
+    #[n(0)]
+    Equality,
+    /// At least `min` providers must return the same result.
+    /// The other providers may return a different result or an error.
+    #[n(1)]
+    Threshold {
+        #[n(0)]
+        min: u8,
+    },
+    /// The median of the results (the lower one for an even number of results).
+    /// All providers must return a result.
+    /// Only supported for methods whose results can be ordered.
+    #[n(2)]
+    Median,
+    /// The smallest of the results.
+    /// All providers must return a result.
+    /// Only supported for methods whose results can be ordered.
+    #[n(3)]
+    Min,
+}
+
+impl ConsensusStrategy {
+    fn requires_ordered_results(&self) -> bool {
+        matches!(self, ConsensusStrategy::Median | ConsensusStrategy::Min)
+    }
+}
+
+/// The consensus strategy to use for each JSON-RPC method queried in parallel.
+/// Unset methods use their default strategy.
+#[derive(
+    CandidType, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Decode,
+)]
+pub struct RpcConsensusPolicy {
+    /// Defaults to `Equality`.
+    #[n(0)]
+    pub eth_get_logs: Option<ConsensusStrategy>,
+    /// Defaults to `Equality`. Blocks are ordered by number.
+    #[n(1)]
+    pub eth_get_block_by_number: Option<ConsensusStrategy>,
+    /// Defaults to `Equality`.
+    #[n(2)]
+    pub eth_get_transaction_receipt: Option<ConsensusStrategy>,
+    /// Transaction count at the latest block. Defaults to `Min`.
+    #[n(3)]
+    pub eth_get_latest_transaction_count: Option<ConsensusStrategy>,
+    /// Transaction count at the finalized block. Defaults to `Equality`.
+    #[n(4)]
+    pub eth_get_finalized_transaction_count: Option<ConsensusStrategy>,
+}
+
+impl RpcConsensusPolicy {
+    pub fn eth_get_logs(&self) -> ConsensusStrategy {
+        self.eth_get_logs.unwrap_or(ConsensusStrategy::Equality)
+    }
+
+    pub fn eth_get_block_by_number(&self) -> ConsensusStrategy {
+        self.eth_get_block_by_number
+            .unwrap_or(ConsensusStrategy::Equality)
+    }
+
+    pub fn eth_get_transaction_receipt(&self) -> ConsensusStrategy {
+        self.eth_get_transaction_receipt
+            .unwrap_or(ConsensusStrategy::Equality)
+    }
+
+    pub fn eth_get_latest_transaction_count(&self) -> ConsensusStrategy {
+        self.eth_get_latest_transaction_count
+            .unwrap_or(ConsensusStrategy::Min)
+    }
+
+    pub fn eth_get_finalized_transaction_count(&self) -> ConsensusStrategy {
+        self.eth_get_finalized_transaction_count
+            .unwrap_or(ConsensusStrategy::Equality)
+    }
+
+    /// Checks that every strategy can be applied to its method, and that
+    /// thresholds can be met by the providers of the given network.
+    pub fn validate(&self, chain: EthereumNetwork) -> Result<(), String> {
+        let num_providers = providers(chain).len();
+        let strategies = [
+            ("eth_get_logs", self.eth_get_logs(), false),
+            (
+                "eth_get_block_by_number",
+                self.eth_get_block_by_number(),
+                true,
+            ),
+            (
+                "eth_get_transaction_receipt",
+                self.eth_get_transaction_receipt(),
+                false,
+            ),
+            (
+                "eth_get_latest_transaction_count",
+                self.eth_get_latest_transaction_count(),
+                true,
+            ),
+            (
+                "eth_get_finalized_transaction_count",
+                self.eth_get_finalized_transaction_count(),
+                true,
+            ),
+        ];
+        for (method, strategy, has_ordered_results) in strategies {
+            if strategy.requires_ordered_results() && !has_ordered_results {
+                return Err(format!(
+                    "{method}: {strategy:?} is not supported since results cannot be ordered"
+                ));
+            }
+            if let ConsensusStrategy::Threshold { min } = strategy {
+                if min == 0 || usize::from(min) > num_providers {
+                    return Err(format!(
+                        "{method}: threshold {min} must be between 1 and the number of providers ({num_providers})"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn providers(chain: EthereumNetwork) -> &'static [RpcNodeProvider] {
+    match chain {
+        EthereumNetwork::Mainnet => &MAINNET_PROVIDERS,
+        EthereumNetwork::Sepolia => &SEPOLIA_PROVIDERS,
+    }
+}
+
+/// Health of a single JSON-RPC provider, as observed by [`EthRpcClient::check_providers`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProviderHealth {
+    pub url: String,
+    /// Time elapsed between sending the request and receiving the response.
+    pub latency: Duration,
+    /// Number of the latest block returned by the provider, or the error it returned.
+    pub block_number: Result<BlockNumber, String>,
+}
+
+/// Health of all JSON-RPC providers of a network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProvidersHealth {
+    /// Latest block number agreed upon by the providers, according to the consensus policy
+    /// of `eth_getBlockByNumber`. `None` if the providers did not reach consensus.
+    pub quorum_block_number: Option<BlockNumber>,
+    pub providers: Vec<ProviderHealth>,
+}
+
+impl ProviderHealth {
+    /// Difference between the block number returned by the provider and the given quorum
+    /// block number, if both are known.
+    pub fn block_divergence(&self, quorum_block_number: Option<BlockNumber>) -> Option<i64> {
+        let block_number = self.block_number.as_ref().ok()?;
+        let quorum_block_number = quorum_block_number?;
+        Some((block_number.as_f64() - quorum_block_number.as_f64()) as i64)
+    }
+}
+
+impl ProvidersHealth {
+    /// Encodes the latency, block number and block divergence of each provider,
+    /// labeled by the provider URL.
+    pub fn encode_metrics<W: std::io::Write>(
+        &self,
+        w: &mut ic_metrics_encoder::MetricsEncoder<W>,
+    ) -> std::io::Result<()> {
+        let mut latency = w.gauge_vec(
+            "cketh_minter_rpc_provider_latency_seconds",
+            "Latency of the JSON-RPC providers during the last health check.",
+        )?;
+        for provider in &self.providers {
+            latency = latency.value(
+                &[("provider", provider.url.as_str())],
+                provider.latency.as_secs_f64(),
+            )?;
+        }
+
+        let mut block_number = w.gauge_vec(
+            "cketh_minter_rpc_provider_block_number",
+            "Latest block number returned by the JSON-RPC providers during the last health check.",
+        )?;
+        for provider in &self.providers {
+            if let Ok(number) = &provider.block_number {
+                block_number =
+                    block_number.value(&[("provider", provider.url.as_str())], number.as_f64())?;
+            }
+        }
+
+        if self.quorum_block_number.is_some() {
+            let mut divergence = w.gauge_vec(
+                "cketh_minter_rpc_provider_block_divergence",
+                "Difference between the block number returned by the JSON-RPC providers and the quorum block number.",
+            )?;
+            for provider in &self.providers {
+                if let Some(block_divergence) = provider.block_divergence(self.quorum_block_number)
+                {
+                    divergence = divergence.value(
+                        &[("provider", provider.url.as_str())],
+                        block_divergence as f64,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EthRpcClient {
     chain: EthereumNetwork,
+    consensus_policy: RpcConsensusPolicy,
 }
 
 impl EthRpcClient {
-    const fn new(chain: EthereumNetwork) -> Self {
-        Self { chain }
+    const fn new(chain: EthereumNetwork, consensus_policy: RpcConsensusPolicy) -> Self {
+        Self {
+            chain,
+            consensus_policy,
+        }
     }
 
     pub const fn from_state(state: &State) -> Self {
-        Self::new(state.ethereum_network())
+        Self::new(state.ethereum_network(), state.rpc_consensus_policy)
+    }
+
+    pub const fn consensus_policy(&self) -> &RpcConsensusPolicy {
+        &self.consensus_policy
     }
 
     fn providers(&self) -> &[RpcNodeProvider] {
-        match self.chain {
-            EthereumNetwork::Mainnet => &MAINNET_PROVIDERS,
-            EthereumNetwork::Sepolia => &SEPOLIA_PROVIDERS,
-        }
+        providers(self.chain)
     }
 
     /// Query all providers in sequence until one returns an ok result
@@ -132,7 +349,7 @@ impl EthRpcClient {
         let results: MultiCallResults<Vec<LogEntry>> = self
             .parallel_call("eth_getLogs", vec![params], ResponseSizeEstimate::new(100))
             .await;
-        results.reduce_with_equality()
+        results.reduce(self.consensus_policy.eth_get_logs())
     }
 
     pub async fn eth_get_block_by_number(
@@ -151,7 +368,70 @@ impl EthRpcClient {
                 ResponseSizeEstimate::new(6 * 1024),
             )
             .await;
-        results.reduce_with_equality()
+        results.reduce_by_key(self.consensus_policy.eth_get_block_by_number(), |block| {
+            block.number
+        })
+    }
+
+    /// Query all providers in parallel for the latest block and measure how long each of them
+    /// takes to respond.
+    pub async fn check_providers(&self) -> ProvidersHealth {
+        use crate::eth_rpc::{BlockTag, GetBlockByNumberParams};
+
+        let providers = self.providers();
+        let params = GetBlockByNumberParams {
+            block: BlockSpec::Tag(BlockTag::Latest),
+            include_full_transactions: false,
+        };
+        let results: Vec<(HttpOutcallResult<JsonRpcResult<Block>>, Duration)> = {
+            let mut fut = Vec::with_capacity(providers.len());
+            for provider in providers {
+                let params = params.clone();
+                fut.push(async move {
+                    let start = ic_cdk::api::time();
+                    let result = eth_rpc::call(
+                        provider.url().to_string(),
+                        "eth_getBlockByNumber",
+                        params,
+                        ResponseSizeEstimate::new(6 * 1024),
+                    )
+                    .await;
+                    let latency = Duration::from_nanos(ic_cdk::api::time().saturating_sub(start));
+                    (result, latency)
+                });
+            }
+            futures::future::join_all(fut).await
+        };
+        let health = providers
+            .iter()
+            .zip(results.iter())
+            .map(|(provider, (result, latency))| ProviderHealth {
+                url: provider.url().to_string(),
+                latency: *latency,
+                block_number: match result {
+                    Ok(JsonRpcResult::Result(block)) => Ok(block.number),
+                    Ok(JsonRpcResult::Error { code, message }) => {
+                        Err(format!("JSON-RPC error {code}: {message}"))
+                    }
+                    Err(e) => Err(format!("{e:?}")),
+                },
+            })
+            .collect();
+        let quorum_block_number = MultiCallResults::from_non_empty_iter(
+            providers
+                .iter()
+                .cloned()
+                .zip(results.into_iter().map(|(result, _latency)| result)),
+        )
+        .reduce_by_key(self.consensus_policy.eth_get_block_by_number(), |block| {
+            block.number
+        })
+        .ok()
+        .map(|block| block.number);
+        ProvidersHealth {
+            quorum_block_number,
+            providers: health,
+        }
     }
 
     pub async fn eth_get_transaction_receipt(
@@ -165,7 +445,26 @@ impl EthRpcClient {
                 ResponseSizeEstimate::new(700),
             )
             .await;
-        results.reduce_with_equality()
+        results.reduce(self.consensus_policy.eth_get_transaction_receipt())
+    }
+
+    /// Same as [`Self::eth_get_transaction_receipt`], but keeps the logs of the receipt.
+    pub async fn eth_get_transaction_receipt_with_logs(
+        &self,
+        tx_hash: Hash,
+    ) -> Result<
+        Option<TransactionReceiptWithLogs>,
+        MultiCallError<Option<TransactionReceiptWithLogs>>,
+    > {
+        // A deposit transaction emits a single log entry of roughly 1KiB.
+        let results: MultiCallResults<Option<TransactionReceiptWithLogs>> = self
+            .parallel_call(
+                "eth_getTransactionReceipt",
+                vec![tx_hash],
+                ResponseSizeEstimate::new(2_000),
+            )
+            .await;
+        results.reduce(self.consensus_policy.eth_get_transaction_receipt())
     }
 
     pub async fn eth_fee_history(
@@ -312,4 +611,87 @@ impl<T: Debug + PartialEq> MultiCallResults<T> {
             .expect("BUG: MultiCallResults is guaranteed to be non-empty");
         Ok(min)
     }
+
+    pub fn reduce_with_median_by_key<F: FnMut(&T) -> K, K: Ord>(
+        self,
+        extractor: F,
+    ) -> Result<T, MultiCallError<T>> {
+        let mut values: Vec<T> = self.all_ok()?.into_values().collect();
+        values.sort_by_key(extractor);
+        let median_index = (values.len() - 1) / 2;
+        Ok(values.swap_remove(median_index))
+    }
+
+    /// Returns the result returned by at least `min` providers.
+    /// Errors and results returned by fewer providers are ignored.
+    pub fn reduce_with_threshold(mut self, min: u8) -> Result<T, MultiCallError<T>> {
+        let ok_results: Vec<(&RpcNodeProvider, &T)> = self
+            .results
+            .iter()
+            .filter_map(|(provider, result)| match result {
+                Ok(JsonRpcResult::Result(value)) => Some((provider, value)),
+                _ => None,
+            })
+            .collect();
+        if ok_results.is_empty() {
+            return Err(self
+                .all_ok()
+                .expect_err("BUG: all_ok must fail when there are no ok results"));
+        }
+        let agreed_provider = ok_results
+            .iter()
+            .find(|(_provider, value)| {
+                ok_results
+                    .iter()
+                    .filter(|(_other_provider, other_value)| other_value == value)
+                    .count()
+                    >= usize::from(min)
+            })
+            .map(|(provider, _value)| **provider);
+
+        match agreed_provider {
+            Some(provider) => match self.results.remove(&provider) {
+                Some(Ok(JsonRpcResult::Result(value))) => Ok(value),
+                _ => panic!("BUG: expected an ok result from {provider:?}"),
+            },
+            None => {
+                let error = MultiCallError::InconsistentResults(self);
+                log!(
+                    INFO,
+                    "[reduce_with_threshold]: fewer than {min} providers agree {error:?}"
+                );
+                Err(error)
+            }
+        }
+    }
+
+    /// Reduces the results with the given strategy.
+    ///
+    /// Panics if the strategy requires results that can be ordered, see
+    /// [`Self::reduce_by_key`].
+    pub fn reduce(self, strategy: ConsensusStrategy) -> Result<T, MultiCallError<T>> {
+        match strategy {
+            ConsensusStrategy::Equality => self.reduce_with_equality(),
+            ConsensusStrategy::Threshold { min } => self.reduce_with_threshold(min),
+            ConsensusStrategy::Median | ConsensusStrategy::Min => {
+                panic!("BUG: {strategy:?} requires results that can be ordered")
+            }
+        }
+    }
+
+    /// Reduces the results with the given strategy, ordering results by the
+    /// key returned by `extractor` when needed.
+    pub fn reduce_by_key<F: FnMut(&T) -> K, K: Ord>(
+        self,
+        strategy: ConsensusStrategy,
+        extractor: F,
+    ) -> Result<T, MultiCallError<T>> {
+        match strategy {
+            ConsensusStrategy::Median => self.reduce_with_median_by_key(extractor),
+            ConsensusStrategy::Min => self.reduce_with_min_by_key(extractor),
+            ConsensusStrategy::Equality | ConsensusStrategy::Threshold { .. } => {
+                self.reduce(strategy)
+            }
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::eth_rpc::{Hash, HttpResponsePayload, ResponseTransform};
+use crate::eth_rpc::{Hash, HttpResponsePayload, LogEntry, ResponseTransform};
 use crate::numeric::{BlockNumber, GasAmount, WeiPerGas};
 use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
@@ -38,6 +38,32 @@ impl HttpResponsePayload for TransactionReceipt {
     }
 }
 
+/// The parts of a transaction receipt needed to cross-check a deposit,
+/// including the logs emitted by the transaction.
+///
+/// Unlike [`TransactionReceipt`], it is never stored in the minter state.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceiptWithLogs {
+    /// The number of the block containing the transaction.
+    pub block_number: BlockNumber,
+
+    /// Status of the transaction.
+    pub status: TransactionStatus,
+
+    /// The hash of the transaction
+    pub transaction_hash: Hash,
+
+    /// The log entries emitted by the transaction.
+    pub logs: Vec<LogEntry>,
+}
+
+impl HttpResponsePayload for TransactionReceiptWithLogs {
+    fn response_transform() -> Option<ResponseTransform> {
+        Some(ResponseTransform::TransactionReceiptWithLogs)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Encode, Decode)]
 #[serde(try_from = "ethnum::u256", into = "ethnum::u256")]
 pub enum TransactionStatus {
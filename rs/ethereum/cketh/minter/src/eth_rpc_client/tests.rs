@@ -5,7 +5,7 @@ mod eth_rpc_client {
 
     #[test]
     fn should_retrieve_sepolia_providers_in_stable_order() {
-        let client = EthRpcClient::new(EthereumNetwork::Sepolia);
+        let client = EthRpcClient::new(EthereumNetwork::Sepolia, Default::default());
 
         let providers = client.providers();
 
@@ -21,7 +21,7 @@ mod eth_rpc_client {
 
     #[test]
     fn should_retrieve_mainnet_providers_in_stable_order() {
-        let client = EthRpcClient::new(EthereumNetwork::Mainnet);
+        let client = EthRpcClient::new(EthereumNetwork::Mainnet, Default::default());
 
         let providers = client.providers();
 
@@ -35,11 +35,84 @@ mod eth_rpc_client {
     }
 }
 
+mod rpc_consensus_policy {
+    use crate::eth_rpc_client::{ConsensusStrategy, RpcConsensusPolicy};
+    use crate::lifecycle::EthereumNetwork;
+
+    #[test]
+    fn should_use_default_strategies() {
+        let policy = RpcConsensusPolicy::default();
+
+        assert_eq!(policy.eth_get_logs(), ConsensusStrategy::Equality);
+        assert_eq!(
+            policy.eth_get_block_by_number(),
+            ConsensusStrategy::Equality
+        );
+        assert_eq!(
+            policy.eth_get_transaction_receipt(),
+            ConsensusStrategy::Equality
+        );
+        assert_eq!(
+            policy.eth_get_latest_transaction_count(),
+            ConsensusStrategy::Min
+        );
+        assert_eq!(
+            policy.eth_get_finalized_transaction_count(),
+            ConsensusStrategy::Equality
+        );
+        assert_eq!(policy.validate(EthereumNetwork::Mainnet), Ok(()));
+        assert_eq!(policy.validate(EthereumNetwork::Sepolia), Ok(()));
+    }
+
+    #[test]
+    fn should_validate_threshold_against_number_of_providers() {
+        let policy = RpcConsensusPolicy {
+            eth_get_logs: Some(ConsensusStrategy::Threshold { min: 3 }),
+            ..Default::default()
+        };
+
+        assert_eq!(policy.validate(EthereumNetwork::Sepolia), Ok(()));
+        assert!(policy.validate(EthereumNetwork::Mainnet).is_err());
+
+        let policy = RpcConsensusPolicy {
+            eth_get_logs: Some(ConsensusStrategy::Threshold { min: 0 }),
+            ..Default::default()
+        };
+        assert!(policy.validate(EthereumNetwork::Sepolia).is_err());
+    }
+
+    #[test]
+    fn should_reject_ordered_strategies_for_unordered_results() {
+        for strategy in [ConsensusStrategy::Median, ConsensusStrategy::Min] {
+            let policy = RpcConsensusPolicy {
+                eth_get_logs: Some(strategy),
+                ..Default::default()
+            };
+            assert!(policy.validate(EthereumNetwork::Sepolia).is_err());
+
+            let policy = RpcConsensusPolicy {
+                eth_get_transaction_receipt: Some(strategy),
+                ..Default::default()
+            };
+            assert!(policy.validate(EthereumNetwork::Sepolia).is_err());
+
+            let policy = RpcConsensusPolicy {
+                eth_get_block_by_number: Some(strategy),
+                eth_get_latest_transaction_count: Some(strategy),
+                eth_get_finalized_transaction_count: Some(strategy),
+                ..Default::default()
+            };
+            assert_eq!(policy.validate(EthereumNetwork::Sepolia), Ok(()));
+        }
+    }
+}
+
 mod multi_call_results {
-    use crate::eth_rpc_client::providers::{EthereumProvider, RpcNodeProvider};
+    use crate::eth_rpc_client::providers::{EthereumProvider, RpcNodeProvider, SepoliaProvider};
 
     const ANKR: RpcNodeProvider = RpcNodeProvider::Ethereum(EthereumProvider::Ankr);
     const CLOUDFLARE: RpcNodeProvider = RpcNodeProvider::Ethereum(EthereumProvider::Cloudflare);
+    const PUBLIC_NODE: RpcNodeProvider = RpcNodeProvider::Sepolia(SepoliaProvider::PublicNode);
 
     mod reduce_with_equality {
         use crate::eth_rpc::{HttpOutcallError, JsonRpcResult};
@@ -224,6 +297,135 @@ mod multi_call_results {
             );
         }
     }
+
+    mod reduce_with_median_by_key {
+        use crate::eth_rpc::{HttpOutcallError, JsonRpcResult};
+        use crate::eth_rpc_client::tests::multi_call_results::{ANKR, CLOUDFLARE, PUBLIC_NODE};
+        use crate::eth_rpc_client::{MultiCallError, MultiCallResults};
+        use ic_cdk::api::call::RejectionCode;
+
+        #[test]
+        fn should_get_median() {
+            let results: MultiCallResults<u64> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result(12))),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result(10))),
+                (PUBLIC_NODE, Ok(JsonRpcResult::Result(100))),
+            ]);
+
+            assert_eq!(results.reduce_with_median_by_key(|count| *count), Ok(12));
+        }
+
+        #[test]
+        fn should_get_lower_median_for_even_number_of_results() {
+            let results: MultiCallResults<u64> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result(12))),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result(10))),
+            ]);
+
+            assert_eq!(results.reduce_with_median_by_key(|count| *count), Ok(10));
+        }
+
+        #[test]
+        fn should_fail_when_some_results_are_errors() {
+            let error = HttpOutcallError::IcError {
+                code: RejectionCode::SysTransient,
+                message: "transient".to_string(),
+            };
+            let results: MultiCallResults<u64> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result(12))),
+                (CLOUDFLARE, Err(error.clone())),
+                (PUBLIC_NODE, Ok(JsonRpcResult::Result(100))),
+            ]);
+
+            assert_eq!(
+                results.reduce_with_median_by_key(|count| *count),
+                Err(MultiCallError::ConsistentHttpOutcallError(error))
+            );
+        }
+    }
+
+    mod reduce_with_threshold {
+        use crate::eth_rpc::{HttpOutcallError, JsonRpcResult};
+        use crate::eth_rpc_client::tests::multi_call_results::{ANKR, CLOUDFLARE, PUBLIC_NODE};
+        use crate::eth_rpc_client::{MultiCallError, MultiCallResults};
+        use ic_cdk::api::call::RejectionCode;
+
+        #[test]
+        fn should_ignore_error_when_threshold_reached() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (
+                    CLOUDFLARE,
+                    Err(HttpOutcallError::IcError {
+                        code: RejectionCode::SysTransient,
+                        message: "transient".to_string(),
+                    }),
+                ),
+                (PUBLIC_NODE, Ok(JsonRpcResult::Result("0x01".to_string()))),
+            ]);
+
+            assert_eq!(results.reduce_with_threshold(2), Ok("0x01".to_string()));
+        }
+
+        #[test]
+        fn should_ignore_disagreeing_provider_when_threshold_reached() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result("0x02".to_string()))),
+                (PUBLIC_NODE, Ok(JsonRpcResult::Result("0x02".to_string()))),
+            ]);
+
+            assert_eq!(results.reduce_with_threshold(2), Ok("0x02".to_string()));
+        }
+
+        #[test]
+        fn should_be_inconsistent_when_threshold_not_reached() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result("0x02".to_string()))),
+                (
+                    PUBLIC_NODE,
+                    Ok(JsonRpcResult::Error {
+                        code: -32000,
+                        message: "nonce too low".to_string(),
+                    }),
+                ),
+            ]);
+
+            assert_eq!(
+                results.clone().reduce_with_threshold(2),
+                Err(MultiCallError::InconsistentResults(results))
+            );
+        }
+
+        #[test]
+        fn should_be_consistent_error_when_no_ok_results() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (
+                    ANKR,
+                    Ok(JsonRpcResult::Error {
+                        code: -32000,
+                        message: "nonce too low".to_string(),
+                    }),
+                ),
+                (
+                    CLOUDFLARE,
+                    Ok(JsonRpcResult::Error {
+                        code: -32000,
+                        message: "nonce too low".to_string(),
+                    }),
+                ),
+            ]);
+
+            assert_eq!(
+                results.reduce_with_threshold(1),
+                Err(MultiCallError::ConsistentJsonRpcError {
+                    code: -32000,
+                    message: "nonce too low".to_string(),
+                })
+            );
+        }
+    }
 }
 
 mod eth_get_transaction_receipt {
@@ -339,3 +541,119 @@ mod eth_get_transaction_count {
         assert_eq!(count, TransactionCount::from(0x3d8_u32));
     }
 }
+
+mod providers_health {
+    use crate::endpoints::{CheckRpcProvidersResult, RpcProviderHealth};
+    use crate::eth_rpc_client::{ProviderHealth, ProvidersHealth};
+    use crate::numeric::BlockNumber;
+    use candid::Nat;
+    use std::time::Duration;
+
+    #[test]
+    fn should_compute_block_divergence_from_quorum() {
+        let health = providers_health();
+        let quorum = health.quorum_block_number;
+
+        assert_eq!(health.providers[0].block_divergence(quorum), Some(0));
+        assert_eq!(health.providers[1].block_divergence(quorum), Some(-2));
+        assert_eq!(health.providers[2].block_divergence(quorum), Some(3));
+        assert_eq!(health.providers[3].block_divergence(quorum), None);
+    }
+
+    #[test]
+    fn should_not_compute_block_divergence_without_quorum() {
+        let health = providers_health();
+
+        for provider in &health.providers {
+            assert_eq!(provider.block_divergence(None), None);
+        }
+    }
+
+    #[test]
+    fn should_convert_to_candid_result() {
+        let result = CheckRpcProvidersResult::from(providers_health());
+
+        assert_eq!(result.quorum_block_number, Some(Nat::from(1_000_u64)));
+        assert_eq!(
+            result.providers[1],
+            RpcProviderHealth {
+                url: "https://b".to_string(),
+                latency_ms: 200,
+                block_number: Some(Nat::from(998_u64)),
+                block_divergence: Some(-2),
+                error: None,
+            }
+        );
+        assert_eq!(
+            result.providers[3],
+            RpcProviderHealth {
+                url: "https://d".to_string(),
+                latency_ms: 400,
+                block_number: None,
+                block_divergence: None,
+                error: Some("JSON-RPC error -32000: unavailable".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn should_encode_metrics() {
+        let mut encoder = ic_metrics_encoder::MetricsEncoder::new(vec![], 0);
+
+        providers_health().encode_metrics(&mut encoder).unwrap();
+
+        let metrics = String::from_utf8(encoder.into_inner()).unwrap();
+        for expected in [
+            r#"cketh_minter_rpc_provider_latency_seconds{provider="https://b"} 0.2 "#,
+            r#"cketh_minter_rpc_provider_block_number{provider="https://c"} 1003 "#,
+            r#"cketh_minter_rpc_provider_block_divergence{provider="https://a"} 0 "#,
+            r#"cketh_minter_rpc_provider_block_divergence{provider="https://b"} -2 "#,
+            r#"cketh_minter_rpc_provider_block_divergence{provider="https://c"} 3 "#,
+        ] {
+            assert!(
+                metrics.contains(expected),
+                "missing {expected} in {metrics}"
+            );
+        }
+        assert!(
+            !metrics.contains(r#"cketh_minter_rpc_provider_block_number{provider="https://d"}"#)
+        );
+        assert!(!metrics
+            .contains(r#"cketh_minter_rpc_provider_block_divergence{provider="https://d"}"#));
+    }
+
+    #[test]
+    fn should_not_encode_block_divergence_without_quorum() {
+        let mut encoder = ic_metrics_encoder::MetricsEncoder::new(vec![], 0);
+        let health = ProvidersHealth {
+            quorum_block_number: None,
+            ..providers_health()
+        };
+
+        health.encode_metrics(&mut encoder).unwrap();
+
+        let metrics = String::from_utf8(encoder.into_inner()).unwrap();
+        assert!(metrics.contains("cketh_minter_rpc_provider_block_number"));
+        assert!(!metrics.contains("cketh_minter_rpc_provider_block_divergence"));
+    }
+
+    fn providers_health() -> ProvidersHealth {
+        let provider =
+            |url: &str, latency_ms: u64, block_number: Result<u64, &str>| ProviderHealth {
+                url: url.to_string(),
+                latency: Duration::from_millis(latency_ms),
+                block_number: block_number
+                    .map(BlockNumber::from)
+                    .map_err(|e| e.to_string()),
+            };
+        ProvidersHealth {
+            quorum_block_number: Some(BlockNumber::from(1_000_u64)),
+            providers: vec![
+                provider("https://a", 100, Ok(1_000)),
+                provider("https://b", 200, Ok(998)),
+                provider("https://c", 300, Ok(1_003)),
+                provider("https://d", 400, Err("JSON-RPC error -32000: unavailable")),
+            ],
+        }
+    }
+}
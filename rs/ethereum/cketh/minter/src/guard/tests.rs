@@ -110,6 +110,9 @@ fn init_state() {
                 ethereum_block_height: Default::default(),
                 minimum_withdrawal_amount: wei_from_milli_ether(10).into(),
                 next_transaction_nonce: Default::default(),
+                rpc_consensus_policy: None,
+                minimum_deposit_amount: None,
+                disperse_contract_address: None,
             })
             .expect("init args should be valid"),
         );
@@ -14,6 +14,7 @@ pub mod lifecycle;
 pub mod logs;
 pub mod management;
 pub mod map;
+pub mod memo;
 pub mod numeric;
 mod serde_data;
 pub mod state;
@@ -30,3 +31,5 @@ pub const MAIN_DERIVATION_PATH: Vec<ByteBuf> = vec![];
 pub const SCRAPPING_ETH_LOGS_INTERVAL: Duration = Duration::from_secs(3 * 60);
 pub const PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL: Duration = Duration::from_secs(15);
 pub const MINT_RETRY_DELAY: Duration = Duration::from_secs(3 * 60);
+pub const MAX_MINT_RETRY_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
+pub const MAX_MINT_ATTEMPTS: u32 = 10;
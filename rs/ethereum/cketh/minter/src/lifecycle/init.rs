@@ -1,6 +1,7 @@
 use crate::address::Address;
 use crate::endpoints::CandidBlockTag;
 use crate::eth_rpc::BlockTag;
+use crate::eth_rpc_client::RpcConsensusPolicy;
 use crate::lifecycle::EthereumNetwork;
 use crate::numeric::{BlockNumber, TransactionNonce, Wei};
 use crate::state::{InvalidStateError, State};
@@ -28,6 +29,12 @@ pub struct InitArg {
     pub minimum_withdrawal_amount: Nat,
     #[cbor(n(7), with = "crate::cbor::nat")]
     pub next_transaction_nonce: Nat,
+    #[n(8)]
+    pub rpc_consensus_policy: Option<RpcConsensusPolicy>,
+    #[cbor(n(9), with = "crate::cbor::nat::option")]
+    pub minimum_deposit_amount: Option<Nat>,
+    #[n(10)]
+    pub disperse_contract_address: Option<String>,
 }
 
 impl TryFrom<InitArg> for State {
@@ -41,6 +48,9 @@ impl TryFrom<InitArg> for State {
             ethereum_block_height,
             minimum_withdrawal_amount,
             next_transaction_nonce,
+            rpc_consensus_policy,
+            minimum_deposit_amount,
+            disperse_contract_address,
         }: InitArg,
     ) -> Result<Self, Self::Error> {
         use std::str::FromStr;
@@ -50,12 +60,23 @@ impl TryFrom<InitArg> for State {
         let minimum_withdrawal_amount = Wei::try_from(minimum_withdrawal_amount).map_err(|e| {
             InvalidStateError::InvalidMinimumWithdrawalAmount(format!("ERROR: {}", e))
         })?;
+        let minimum_deposit_amount = minimum_deposit_amount
+            .map(Wei::try_from)
+            .transpose()
+            .map_err(|e| InvalidStateError::InvalidMinimumDepositAmount(format!("ERROR: {}", e)))?
+            .unwrap_or(Wei::ZERO);
         let ethereum_contract_address = ethereum_contract_address
             .map(|a| Address::from_str(&a))
             .transpose()
             .map_err(|e| {
                 InvalidStateError::InvalidEthereumContractAddress(format!("ERROR: {}", e))
             })?;
+        let disperse_contract_address = disperse_contract_address
+            .map(|a| Address::from_str(&a))
+            .transpose()
+            .map_err(|e| {
+                InvalidStateError::InvalidDisperseContractAddress(format!("ERROR: {}", e))
+            })?;
         let state = Self {
             ethereum_network,
             ecdsa_key_name,
@@ -64,19 +85,31 @@ impl TryFrom<InitArg> for State {
             eth_transactions: EthTransactions::new(initial_nonce),
             ledger_id,
             minimum_withdrawal_amount,
+            minimum_deposit_amount,
             ethereum_block_height: BlockTag::from(ethereum_block_height),
+            ethereum_block_confirmations: None,
             // Note that the default block to start from for logs scrapping
             // depends on the chain we are using:
             // Ethereum and Sepolia have for example different block heights at a given time.
             // https://sepolia.etherscan.io/block/3938798
             last_scraped_block_number: BlockNumber::new(3_956_206),
+            retired_contracts: Default::default(),
             last_observed_block_number: None,
             events_to_mint: Default::default(),
             minted_events: Default::default(),
             ecdsa_public_key: None,
             invalid_events: Default::default(),
+            unconfirmed_deposits: Default::default(),
+            reorged_deposits: Default::default(),
             active_tasks: Default::default(),
             http_request_counter: 0,
+            events_to_mint_failed: Default::default(),
+            mint_retries: Default::default(),
+            rpc_providers_health: None,
+            last_transaction_price_estimate: None,
+            rpc_consensus_policy: rpc_consensus_policy.unwrap_or_default(),
+            disperse_contract_address,
+            fee_estimation_policy: Default::default(),
         };
         state.validate_config()?;
         Ok(state)
@@ -88,6 +88,9 @@ mod init {
             ethereum_block_height: Default::default(),
             minimum_withdrawal_amount: Wei::TWO.into(),
             next_transaction_nonce: TransactionNonce::ZERO.into(),
+            rpc_consensus_policy: None,
+            minimum_deposit_amount: None,
+            disperse_contract_address: None,
         }
     }
 }
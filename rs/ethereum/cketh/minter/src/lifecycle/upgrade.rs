@@ -1,8 +1,10 @@
 use crate::endpoints::CandidBlockTag;
+use crate::eth_rpc_client::RpcConsensusPolicy;
 use crate::logs::INFO;
 use crate::state::audit::{process_event, EventType};
 use crate::state::mutate_state;
 use crate::state::STATE;
+use crate::tx::FeeEstimationPolicy;
 use candid::{CandidType, Deserialize, Nat};
 use ic_canister_log::log;
 use minicbor::{Decode, Encode};
@@ -19,6 +21,20 @@ pub struct UpgradeArg {
     pub ethereum_contract_address: Option<String>,
     #[n(3)]
     pub ethereum_block_height: Option<CandidBlockTag>,
+    #[n(4)]
+    pub rpc_consensus_policy: Option<RpcConsensusPolicy>,
+    #[cbor(n(5), with = "crate::cbor::nat::option")]
+    pub minimum_deposit_amount: Option<Nat>,
+    #[n(6)]
+    pub disperse_contract_address: Option<String>,
+    #[n(7)]
+    pub ethereum_block_confirmations: Option<u64>,
+    /// When `ethereum_contract_address` changes, number of blocks after the last scraped block
+    /// during which the logs of the previous helper smart contract are still scraped.
+    #[n(8)]
+    pub ethereum_contract_address_overlap_blocks: Option<u64>,
+    #[n(9)]
+    pub fee_estimation_policy: Option<FeeEstimationPolicy>,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArg>) {
@@ -27,6 +43,7 @@ pub fn post_upgrade(upgrade_args: Option<UpgradeArg>) {
     STATE.with(|cell| {
         *cell.borrow_mut() = Some(crate::storage::decode_state());
     });
+    mutate_state(|s| s.eth_transactions.rebuild_withdrawal_indices());
     if let Some(args) = upgrade_args {
         mutate_state(|s| process_event(s, EventType::Upgrade(args)))
     }
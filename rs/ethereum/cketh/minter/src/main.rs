@@ -8,18 +8,22 @@ use ic_cketh_minter::endpoints::events::{
     Event as CandidEvent, EventSource as CandidEventSource, GetEventsArg, GetEventsResult,
 };
 use ic_cketh_minter::endpoints::{
-    Eip1559TransactionPrice, RetrieveEthRequest, RetrieveEthStatus, WithdrawalArg, WithdrawalError,
+    CancelWithdrawalError, CancelWithdrawalResult, CheckRpcProvidersResult, Eip1559FeeHistory,
+    Eip1559TransactionPrice, Eip1559TransactionPriceArg, ListWithdrawalsArg, ListWithdrawalsResult,
+    RetrieveEthRequest, RetrieveEthStatus, WithdrawalArg, WithdrawalDetails, WithdrawalError,
 };
 use ic_cketh_minter::eth_logs::{
-    report_transaction_error, EventSource, ReceivedEthEvent, ReceivedEthEventError,
+    check_deposit_receipt, report_transaction_error, EventSource, ReceivedEthEvent,
+    ReceivedEthEventError,
 };
-use ic_cketh_minter::eth_rpc::FeeHistory;
+use ic_cketh_minter::eth_rpc::{FeeHistory, Hash};
 use ic_cketh_minter::eth_rpc::{JsonRpcResult, SendRawTransactionResult};
-use ic_cketh_minter::eth_rpc_client::responses::TransactionReceipt;
+use ic_cketh_minter::eth_rpc_client::responses::{TransactionReceipt, TransactionReceiptWithLogs};
 use ic_cketh_minter::eth_rpc_client::{EthRpcClient, MultiCallError};
 use ic_cketh_minter::guard::{retrieve_eth_guard, TimerGuard};
 use ic_cketh_minter::lifecycle::MinterArg;
 use ic_cketh_minter::logs::{DEBUG, INFO};
+use ic_cketh_minter::memo::{BurnMemo, MintMemo};
 use ic_cketh_minter::numeric::{
     BlockNumber, LedgerBurnIndex, LedgerMintIndex, TransactionCount, Wei,
 };
@@ -28,15 +32,17 @@ use ic_cketh_minter::state::{
     lazy_call_ecdsa_public_key, mutate_state, read_state, State, TaskType, STATE,
 };
 use ic_cketh_minter::transactions::{
-    create_transaction, CreateTransactionError, EthWithdrawalRequest,
+    create_batch_transaction, create_transaction, CreateTransactionError, EthWithdrawalRequest,
+    MAX_WITHDRAWALS_PER_BATCH_TRANSACTION,
 };
 use ic_cketh_minter::tx::{estimate_transaction_price, TransactionPrice};
 use ic_cketh_minter::{
-    eth_logs, eth_rpc, MINT_RETRY_DELAY, PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL,
+    eth_logs, eth_rpc, MAX_MINT_ATTEMPTS, PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL,
     SCRAPPING_ETH_LOGS_INTERVAL,
 };
 use ic_cketh_minter::{state, storage};
 use ic_icrc1_client_cdk::{CdkRuntime, ICRC1Client};
+use icrc_ledger_client_retry::{LedgerClientError, RetryConfig, RetryingICRC1Client};
 use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
 use std::cmp::{min, Ordering};
 use std::collections::{BTreeMap, BTreeSet};
@@ -98,26 +104,106 @@ async fn scrap_eth_logs() {
             return;
         }
     };
-    let mut last_scraped_block_number = read_state(|s| s.last_scraped_block_number);
+    let (mut last_scraped_block_number, retired_contracts) =
+        read_state(|s| (s.last_scraped_block_number, s.retired_contracts.clone()));
     let last_queried_block_number = update_last_observed_block_number().await;
     while last_scraped_block_number < last_queried_block_number {
-        last_scraped_block_number = scrap_eth_logs_between(
+        match scrap_eth_logs_between(
             contract_address,
             last_scraped_block_number,
             last_queried_block_number,
         )
-        .await;
+        .await
+        {
+            Some(block_number) => last_scraped_block_number = block_number,
+            None => break,
+        }
+    }
+    // Deposits sent to a previous helper smart contract during a migration are still
+    // processed until the end of its overlap window.
+    for (retired_contract_address, retired_contract) in retired_contracts {
+        let to = min(
+            retired_contract.last_block_to_scrape,
+            last_queried_block_number,
+        );
+        let mut last_scraped_block_number = retired_contract.last_scraped_block_number;
+        while last_scraped_block_number < to {
+            match scrap_eth_logs_between(retired_contract_address, last_scraped_block_number, to)
+                .await
+            {
+                Some(block_number) => last_scraped_block_number = block_number,
+                None => break,
+            }
+        }
+    }
+    revalidate_minted_deposits(last_queried_block_number).await;
+}
+
+/// Checks that the transactions of minted deposits are still part of the chain
+/// once they are buried under enough blocks, and freezes the deposits that were reorged.
+async fn revalidate_minted_deposits(last_observed_block_number: BlockNumber) {
+    let deposits = read_state(|s| s.deposits_to_revalidate(last_observed_block_number));
+    if deposits.is_empty() {
+        return;
+    }
+    let rpc_client = read_state(EthRpcClient::from_state);
+    let results = join_all(deposits.iter().map(|minted_event| {
+        rpc_client.eth_get_transaction_receipt(minted_event.deposit_event.transaction_hash)
+    }))
+    .await;
+    for (minted_event, result) in zip(deposits, results) {
+        let deposit = &minted_event.deposit_event;
+        let reorg_reason = match result {
+            Ok(Some(receipt)) if receipt.block_number == deposit.block_number => None,
+            Ok(Some(receipt)) => Some(format!(
+                "transaction {} moved from block {} to block {}",
+                deposit.transaction_hash, deposit.block_number, receipt.block_number
+            )),
+            Ok(None) => Some(format!(
+                "transaction {} not found",
+                deposit.transaction_hash
+            )),
+            Err(e) => {
+                log!(
+                    INFO,
+                    "Failed to get transaction receipt for deposit {deposit:?}: {e:?}. Will retry later",
+                );
+                continue;
+            }
+        };
+        match reorg_reason {
+            None => mutate_state(|s| s.record_confirmed_deposit(&minted_event.source())),
+            Some(reason) => {
+                log!(
+                    INFO,
+                    "Detected reorged deposit {deposit:?}: {reason}. Freezing {} wei minted to {}",
+                    deposit.value,
+                    deposit.account()
+                );
+                mutate_state(|s| {
+                    process_event(
+                        s,
+                        EventType::ReorgedDeposit {
+                            event_source: minted_event.source(),
+                            reason,
+                        },
+                    )
+                });
+            }
+        }
     }
 }
 
 /// Scraps Ethereum logs between `from` and `min(from + 1024, to)` since certain RPC providers
 /// require that the number of blocks queried is no greater than 1024.
-/// Returns the last block number that was scraped (which is `min(from + 1024, to)`).
+/// Returns the last block number that was scraped (which is `min(from + 1024, to)`),
+/// or `None` if the receipts of the new deposits could not be fetched, in which case
+/// the same blocks will be scraped again later.
 async fn scrap_eth_logs_between(
     contract_address: Address,
     from: BlockNumber,
     to: BlockNumber,
-) -> BlockNumber {
+) -> Option<BlockNumber> {
     const MAX_BLOCK_SPREAD: u16 = 1024;
     match from.cmp(&to) {
         Ordering::Less => {
@@ -127,7 +213,8 @@ async fn scrap_eth_logs_between(
             let last_scraped_block_number = min(max_to, to);
             log!(
                 DEBUG,
-                "Scrapping ETH logs from block {:?} to block {:?}...",
+                "Scrapping ETH logs of contract {} from block {:?} to block {:?}...",
+                contract_address,
                 from,
                 last_scraped_block_number
             );
@@ -138,6 +225,17 @@ async fn scrap_eth_logs_between(
                 last_scraped_block_number,
             )
             .await;
+            let transaction_events: Vec<_> = transaction_events
+                .into_iter()
+                .filter(|event| {
+                    let processed = read_state(|s| s.has_processed_deposit(&event.source()));
+                    if processed {
+                        log!(DEBUG, "Skipping already processed event {event:?}");
+                    }
+                    !processed
+                })
+                .collect();
+            let receipts = fetch_deposit_receipts(&transaction_events).await?;
             let has_new_events = !transaction_events.is_empty();
             for event in transaction_events {
                 log!(
@@ -165,6 +263,38 @@ async fn scrap_eth_logs_between(
                             },
                         )
                     });
+                } else if let Err(reason) = read_state(|s| s.validate_deposit_amount(&event)) {
+                    log!(
+                        INFO,
+                        "Received event below the minimum deposit amount: {reason}"
+                    );
+                    mutate_state(|s| {
+                        process_event(
+                            s,
+                            EventType::InvalidDeposit {
+                                event_source: event.source(),
+                                reason,
+                            },
+                        )
+                    });
+                } else if let Err(mismatch) = check_deposit_receipt(
+                    &event,
+                    contract_address,
+                    &receipts[&event.transaction_hash],
+                ) {
+                    log!(
+                        INFO,
+                        "Received event that is not backed by its transaction receipt: {mismatch}",
+                    );
+                    mutate_state(|s| {
+                        process_event(
+                            s,
+                            EventType::InvalidDeposit {
+                                event_source: event.source(),
+                                reason: mismatch.to_string(),
+                            },
+                        )
+                    });
                 } else {
                     mutate_state(|s| process_event(s, EventType::AcceptedDeposit(event)));
                 }
@@ -186,15 +316,17 @@ async fn scrap_eth_logs_between(
                 }
                 report_transaction_error(error);
             }
-            mutate_state(|s| s.last_scraped_block_number = last_scraped_block_number);
-            last_scraped_block_number
+            mutate_state(|s| {
+                s.update_last_scraped_block_number(contract_address, last_scraped_block_number)
+            });
+            Some(last_scraped_block_number)
         }
         Ordering::Equal => {
             log!(
                 DEBUG,
                 "[scrap_eth_logs] Skipping scrapping ETH logs: no new blocks",
             );
-            to
+            Some(to)
         }
         Ordering::Greater => {
             ic_cdk::trap(&format!(
@@ -205,14 +337,53 @@ async fn scrap_eth_logs_between(
     }
 }
 
+/// Fetches the receipts of the transactions of the given deposits, using the quorum of
+/// the configured providers. Returns `None` if any of them could not be fetched.
+async fn fetch_deposit_receipts(
+    events: &[ReceivedEthEvent],
+) -> Option<BTreeMap<Hash, TransactionReceiptWithLogs>> {
+    let transaction_hashes: BTreeSet<Hash> =
+        events.iter().map(|event| event.transaction_hash).collect();
+    let rpc_client = read_state(EthRpcClient::from_state);
+    let results = join_all(
+        transaction_hashes
+            .iter()
+            .map(|tx_hash| rpc_client.eth_get_transaction_receipt_with_logs(*tx_hash)),
+    )
+    .await;
+    let mut receipts = BTreeMap::new();
+    for (tx_hash, result) in zip(transaction_hashes, results) {
+        match result {
+            Ok(Some(receipt)) => {
+                receipts.insert(tx_hash, receipt);
+            }
+            Ok(None) => {
+                log!(
+                    INFO,
+                    "Receipt of deposit transaction {tx_hash} not found. Will retry later",
+                );
+                return None;
+            }
+            Err(e) => {
+                log!(
+                    INFO,
+                    "Failed to get receipt of deposit transaction {tx_hash}: {e:?}. Will retry later",
+                );
+                return None;
+            }
+        }
+    }
+    Some(receipts)
+}
+
 async fn update_last_observed_block_number() -> BlockNumber {
     use eth_rpc::{Block, BlockSpec};
 
-    let finalized_block: Block = read_state(EthRpcClient::from_state)
-        .eth_get_block_by_number(BlockSpec::Tag(read_state(State::ethereum_block_height)))
+    let observed_block: Block = read_state(EthRpcClient::from_state)
+        .eth_get_block_by_number(BlockSpec::Tag(read_state(State::observed_block_tag)))
         .await
         .expect("HTTP call failed");
-    let block_number = finalized_block.number;
+    let block_number = read_state(|s| s.confirmed_block_number(observed_block.number));
     mutate_state(|s| s.last_observed_block_number = Some(block_number));
     block_number
 }
@@ -225,38 +396,41 @@ async fn mint_cketh() {
         Err(_) => return,
     };
 
-    let (ledger_canister_id, events) = read_state(|s| (s.ledger_id, s.events_to_mint.clone()));
-    let client = ICRC1Client {
-        runtime: CdkRuntime,
-        ledger_canister_id,
-    };
-
-    let mut error_count = 0;
+    let now = ic_cdk::api::time();
+    let (ledger_canister_id, events) = read_state(|s| (s.ledger_id, s.events_to_mint_due(now)));
+    let client = RetryingICRC1Client::new(CdkRuntime, ledger_canister_id, RetryConfig::default());
 
     for (event_source, event) in events {
         let block_index = match client
             .transfer(TransferArg {
                 from_subaccount: None,
-                to: event.principal.into(),
+                to: event.account(),
                 fee: None,
                 created_at_time: None,
-                memo: None,
+                memo: Some(
+                    MintMemo::Convert {
+                        from_address: event.from_address,
+                        tx_hash: event.transaction_hash,
+                        log_index: event.log_index,
+                    }
+                    .into(),
+                ),
                 amount: Nat::from(event.value),
             })
             .await
         {
-            Ok(Ok(block_index)) => block_index,
-            Ok(Err(err)) => {
+            Ok(block_index) => block_index,
+            Err(LedgerClientError::Ledger(err)) => {
                 log!(INFO, "Failed to mint ckETH: {event:?} {err}");
-                error_count += 1;
+                record_failed_mint_attempt(event_source, now, err.to_string());
                 continue;
             }
-            Err(err) => {
+            Err(LedgerClientError::Call(err)) => {
                 log!(
                     INFO,
-                    "Failed to send a message to the ledger ({ledger_canister_id}): {err:?}"
+                    "Failed to send a message to the ledger ({ledger_canister_id}): {err}"
                 );
-                error_count += 1;
+                record_failed_mint_attempt(event_source, now, err.to_string());
                 continue;
             }
         };
@@ -273,16 +447,33 @@ async fn mint_cketh() {
             INFO,
             "Minted {} ckWei to {} in block {block_index}",
             event.value,
-            event.principal
+            event.account()
         );
     }
 
-    if error_count > 0 {
+    if let Some(next_attempt_at) = read_state(State::next_mint_retry_at) {
+        let delay = Duration::from_nanos(next_attempt_at.saturating_sub(ic_cdk::api::time()));
+        log!(INFO, "Rescheduling the minting in {delay:?}");
+        ic_cdk_timers::set_timer(delay, || ic_cdk::spawn(mint_cketh()));
+    }
+}
+
+fn record_failed_mint_attempt(event_source: EventSource, now: u64, reason: String) {
+    let attempts = mutate_state(|s| s.record_failed_mint_attempt(event_source, now));
+    if attempts >= MAX_MINT_ATTEMPTS {
         log!(
             INFO,
-            "Failed to mint {error_count} events, rescheduling the minting"
+            "Giving up minting ckETH for {event_source:?} after {attempts} attempts: {reason}"
         );
-        ic_cdk_timers::set_timer(MINT_RETRY_DELAY, || ic_cdk::spawn(mint_cketh()));
+        mutate_state(|s| {
+            process_event(
+                s,
+                EventType::FailedMint {
+                    event_source,
+                    reason,
+                },
+            )
+        });
     }
 }
 
@@ -302,7 +493,11 @@ async fn process_retrieve_eth_requests() {
         return;
     }
 
-    let transaction_price = estimate_transaction_price(&eth_fee_history().await);
+    let policy = read_state(|s| s.fee_estimation_policy.clone());
+    let estimate = estimate_transaction_price(
+        &eth_fee_history(policy.block_count(), policy.reward_percentiles()).await,
+    );
+    let transaction_price = mutate_state(|s| s.record_transaction_price_estimate(estimate));
     let max_transaction_fee = transaction_price.max_transaction_fee();
     log!(
         INFO,
@@ -314,6 +509,7 @@ async fn process_retrieve_eth_requests() {
     sign_transactions_batch().await;
     send_transactions_batch().await;
     finalize_transactions_batch().await;
+    refund_fees_batch().await;
 }
 
 async fn resubmit_transactions_batch(transaction_price: &TransactionPrice) {
@@ -323,8 +519,12 @@ async fn resubmit_transactions_batch(transaction_price: &TransactionPrice) {
     match latest_transaction_count().await {
         Ok(latest_tx_count) => {
             let transactions_to_resubmit = read_state(|s| {
-                s.eth_transactions
-                    .create_resubmit_transactions(latest_tx_count, transaction_price.clone())
+                s.eth_transactions.create_resubmit_transactions(
+                    latest_tx_count,
+                    transaction_price.clone(),
+                    s.fee_estimation_policy
+                        .max_resubmission_fee_increase_percent(),
+                )
             });
             for tx in transactions_to_resubmit {
                 match tx {
@@ -347,6 +547,16 @@ async fn resubmit_transactions_batch(transaction_price: &TransactionPrice) {
 }
 
 fn create_transactions_batch(transaction_price: TransactionPrice) {
+    if let Some(disperse_contract_address) = read_state(|s| s.disperse_contract_address) {
+        let requests = read_state(|s| {
+            s.eth_transactions
+                .withdrawal_requests_batch(MAX_WITHDRAWALS_PER_BATCH_TRANSACTION)
+        });
+        if requests.len() > 1 {
+            create_batch_transaction_for(requests, transaction_price, disperse_contract_address);
+            return;
+        }
+    }
     for request in read_state(|s| s.eth_transactions.withdrawal_requests_batch(5)) {
         log!(DEBUG, "[create_transactions_batch]: processing {request:?}",);
         let ethereum_network = read_state(State::ethereum_network);
@@ -377,6 +587,58 @@ fn create_transactions_batch(transaction_price: TransactionPrice) {
     }
 }
 
+/// Creates a single transaction serving all the given withdrawal requests.
+/// Requests whose withdrawal amount does not cover their share of the transaction fee
+/// are moved back to the end of the queue and left out of the batch.
+fn create_batch_transaction_for(
+    mut requests: Vec<EthWithdrawalRequest>,
+    transaction_price: TransactionPrice,
+    disperse_contract_address: Address,
+) {
+    let ethereum_network = read_state(State::ethereum_network);
+    while !requests.is_empty() {
+        let nonce = read_state(|s| s.eth_transactions.next_transaction_nonce());
+        match create_batch_transaction(
+            &requests,
+            nonce,
+            transaction_price.clone(),
+            ethereum_network,
+            disperse_contract_address,
+        ) {
+            Ok(tx) => {
+                log!(
+                    DEBUG,
+                    "[create_transactions_batch]: created batch transaction {tx:?} for {} withdrawal requests",
+                    requests.len()
+                );
+                mutate_state(|s| {
+                    s.eth_transactions
+                        .record_created_batch_transaction(requests, tx)
+                });
+                return;
+            }
+            Err(CreateTransactionError::InsufficientAmount {
+                ledger_burn_index,
+                withdrawal_amount,
+                max_transaction_fee,
+            }) => {
+                log!(
+                    INFO,
+                    "[create_transactions_batch]: Withdrawal request with burn index {ledger_burn_index} has insufficient
+                amount {withdrawal_amount:?} to cover its share of transaction fees: {max_transaction_fee:?}.
+                Request moved back to end of queue."
+                );
+                let position = requests
+                    .iter()
+                    .position(|r| r.ledger_burn_index == ledger_burn_index)
+                    .expect("BUG: insufficient amount for unknown withdrawal request");
+                let request = requests.remove(position);
+                mutate_state(|s| s.eth_transactions.reschedule_withdrawal_request(request));
+            }
+        }
+    }
+}
+
 async fn sign_transactions_batch() {
     let transactions_batch: Vec<_> = read_state(|s| {
         s.eth_transactions
@@ -525,6 +787,97 @@ async fn finalize_transactions_batch() {
     }
 }
 
+async fn refund_fees_batch() {
+    use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+    use num_traits::ToPrimitive;
+
+    let (ledger_canister_id, fee_refunds) = read_state(|s| {
+        (
+            s.ledger_id,
+            s.eth_transactions
+                .fee_refunds_iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+        )
+    });
+    if fee_refunds.is_empty() {
+        return;
+    }
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id,
+    };
+
+    for refund in fee_refunds {
+        // The memo and creation time identify the refund, so that the ledger rejects
+        // a retried refund whose previous attempt was minted but not recorded.
+        let created_at_time = mutate_state(|s| {
+            s.eth_transactions
+                .fee_refund_created_at_time(refund.ledger_burn_index, ic_cdk::api::time())
+        });
+        let block_index = match client
+            .transfer(TransferArg {
+                from_subaccount: None,
+                to: refund.beneficiary.into(),
+                fee: None,
+                created_at_time: Some(created_at_time),
+                memo: Some(
+                    MintMemo::ReimburseWithdrawal {
+                        withdrawal_id: refund.ledger_burn_index,
+                    }
+                    .into(),
+                ),
+                amount: Nat::from(refund.amount),
+            })
+            .await
+        {
+            Ok(Ok(block_index)) => block_index,
+            Ok(Err(TransferError::Duplicate { duplicate_of })) => match duplicate_of.0.to_u64() {
+                Some(block_index) => block_index,
+                None => {
+                    log!(
+                        INFO,
+                        "Fee refund {refund:?} already minted in block {duplicate_of}, which does not fit in u64"
+                    );
+                    continue;
+                }
+            },
+            Ok(Err(err)) => {
+                log!(
+                    INFO,
+                    "Failed to refund transaction fee {refund:?}: {err}. Will retry later."
+                );
+                continue;
+            }
+            Err(err) => {
+                log!(
+                    INFO,
+                    "Failed to send a message to the ledger ({ledger_canister_id}): {err:?}. Will retry later."
+                );
+                continue;
+            }
+        };
+        mutate_state(|s| {
+            process_event(
+                s,
+                EventType::FeeRefunded {
+                    withdrawal_id: refund.ledger_burn_index,
+                    beneficiary: refund.beneficiary,
+                    amount: refund.amount,
+                    refund_block_index: LedgerMintIndex::new(block_index),
+                },
+            )
+        });
+        log!(
+            INFO,
+            "Refunded {} ckWei of transaction fee to {} for withdrawal ID {} in block {block_index}",
+            refund.amount,
+            refund.beneficiary,
+            refund.ledger_burn_index
+        );
+    }
+}
+
 #[pre_upgrade]
 fn pre_upgrade() {
     read_state(|s| {
@@ -554,9 +907,107 @@ async fn smart_contract_address() -> String {
 /// See https://www.blocknative.com/blog/eip-1559-fees
 #[update]
 #[candid_method(update)]
-async fn eip_1559_transaction_price() -> Eip1559TransactionPrice {
-    let transaction_price = estimate_transaction_price(&eth_fee_history().await);
-    Eip1559TransactionPrice::from(transaction_price)
+async fn eip_1559_transaction_price(
+    arg: Option<Eip1559TransactionPriceArg>,
+) -> Eip1559TransactionPrice {
+    let arg = arg.unwrap_or_default();
+    if let Err(e) = arg.validate() {
+        ic_cdk::trap(&e);
+    }
+    let reward_percentile = arg.reward_percentile();
+    let fee_history = eth_fee_history(arg.block_count(), vec![reward_percentile]).await;
+    let transaction_price = estimate_transaction_price(&fee_history);
+    Eip1559TransactionPrice {
+        fee_history: Some(Eip1559FeeHistory::new(fee_history, reward_percentile)),
+        ..Eip1559TransactionPrice::from(transaction_price)
+    }
+}
+
+/// Queries each JSON-RPC provider for the latest block and reports its latency and
+/// how far its block number diverges from the quorum.
+/// Restricted to controllers, since each call makes an HTTP outcall to every provider.
+#[update]
+#[candid_method(update)]
+async fn check_rpc_providers() -> CheckRpcProvidersResult {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        ic_cdk::trap("only controllers can check the JSON-RPC providers");
+    }
+    let health = read_state(EthRpcClient::from_state).check_providers().await;
+    mutate_state(|s| s.rpc_providers_health = Some(health.clone()));
+    CheckRpcProvidersResult::from(health)
+}
+
+/// Moves the last block whose logs were scraped, to skip a corrupted range of blocks
+/// or to scan a range of blocks again after a helper smart contract migration.
+#[update]
+#[candid_method(update)]
+fn set_last_scraped_block_number(block_number: Nat) {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        ic_cdk::trap("only controllers can set the last scraped block number");
+    }
+    let block_number = BlockNumber::try_from(block_number)
+        .unwrap_or_else(|e| ic_cdk::trap(&format!("invalid block number: {e}")));
+    let _guard = TimerGuard::new(TaskType::ScrapEthLogs)
+        .unwrap_or_else(|_| ic_cdk::trap("scraping logs is in progress, try again later"));
+    let (last_scraped_block_number, last_observed_block_number) =
+        read_state(|s| (s.last_scraped_block_number, s.last_observed_block_number));
+    let event = match block_number.cmp(&last_scraped_block_number) {
+        Ordering::Equal => return,
+        Ordering::Greater => {
+            if last_observed_block_number.map_or(true, |observed| block_number > observed) {
+                ic_cdk::trap(&format!(
+                    "cannot skip blocks beyond the last observed block {last_observed_block_number:?}"
+                ));
+            }
+            EventType::SkippedBlockRange {
+                caller,
+                from: last_scraped_block_number
+                    .checked_increment()
+                    .expect("BUG: last scraped block number cannot be the maximum"),
+                to: block_number,
+            }
+        }
+        Ordering::Less => EventType::RescanRequested {
+            caller,
+            from: block_number
+                .checked_increment()
+                .expect("BUG: block number is smaller than the last scraped block number"),
+            to: last_scraped_block_number,
+        },
+    };
+    log!(INFO, "[set_last_scraped_block_number]: {event:?}");
+    mutate_state(|s| process_event(s, event));
+}
+
+/// Requeues the deposits that the minter gave up minting after too many failed attempts.
+#[update]
+#[candid_method(update)]
+fn requeue_failed_mints() -> u64 {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        ic_cdk::trap("only controllers can requeue failed mints");
+    }
+    let failed_mints: Vec<_> = read_state(|s| s.events_to_mint_failed.keys().copied().collect());
+    for event_source in &failed_mints {
+        log!(
+            INFO,
+            "[requeue_failed_mints]: {caller} requeued {event_source:?}"
+        );
+        mutate_state(|s| {
+            process_event(
+                s,
+                EventType::RequeuedFailedMint {
+                    caller,
+                    event_source: *event_source,
+                },
+            )
+        });
+    }
+    if !failed_mints.is_empty() {
+        ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(mint_cketh()));
+    }
+    failed_mints.len() as u64
 }
 
 #[update]
@@ -576,11 +1027,34 @@ async fn withdraw_eth(
         .and_then(|a| validate_address_as_destination(a).map_err(|e| e.to_string()))
         .unwrap_or_else(|e| ic_cdk::trap(&format!("invalid recipient address: {:?}", e)));
 
+    let amount = Wei::try_from(amount).expect("failed to convert Nat to u256");
+
     if ic_cketh_minter::blocklist::is_blocked(destination) {
-        ic_cdk::trap("attempted to withdraw ETH to a blocked address");
+        log!(
+            INFO,
+            "[withdraw]: {caller} attempted to withdraw {amount} wei to the blocked address {destination}"
+        );
+        mutate_state(|s| {
+            process_event(
+                s,
+                EventType::BlockedWithdrawalAttempt {
+                    caller,
+                    destination,
+                    amount,
+                },
+            )
+        });
+        return Err(WithdrawalError::RecipientBlocked {
+            address: destination.to_string(),
+        });
     }
 
-    let amount = Wei::try_from(amount).expect("failed to convert Nat to u256");
+    let frozen_amount = read_state(|s| s.frozen_amount_of(&caller));
+    if frozen_amount > Wei::ZERO {
+        return Err(WithdrawalError::TemporarilyUnavailable(format!(
+            "withdrawals are frozen: {frozen_amount} wei were minted to the caller for reorged deposits"
+        )));
+    }
 
     let minimum_withdrawal_amount = read_state(|s| s.minimum_withdrawal_amount);
     if amount < minimum_withdrawal_amount {
@@ -590,10 +1064,7 @@ async fn withdraw_eth(
     }
 
     let ledger_canister_id = read_state(|s| s.ledger_id);
-    let client = ICRC1Client {
-        runtime: CdkRuntime,
-        ledger_canister_id,
-    };
+    let client = RetryingICRC1Client::new(CdkRuntime, ledger_canister_id, RetryConfig::default());
 
     log!(INFO, "[withdraw]: burning {:?}", amount);
     match client
@@ -603,17 +1074,23 @@ async fn withdraw_eth(
             to: ic_cdk::id().into(),
             amount: Nat::from(amount),
             fee: None,
-            memo: None,
+            memo: Some(
+                BurnMemo::Convert {
+                    to_address: destination,
+                }
+                .into(),
+            ),
             created_at_time: None,
         })
         .await
     {
-        Ok(Ok(block_index)) => {
+        Ok(block_index) => {
             let ledger_burn_index = LedgerBurnIndex::new(block_index);
             let withdrawal_request = EthWithdrawalRequest {
                 withdrawal_amount: amount,
                 destination,
                 ledger_burn_index,
+                from: Some(caller),
             };
 
             log!(
@@ -628,22 +1105,150 @@ async fn withdraw_eth(
             });
             Ok(RetrieveEthRequest::from(withdrawal_request))
         }
-        Ok(Err(error)) => {
+        Err(LedgerClientError::Ledger(error)) => {
             log!(
                 DEBUG,
                 "[withdraw]: failed to transfer_from with error: {error:?}"
             );
             Err(WithdrawalError::from(error))
         }
-        Err((error_code, message)) => {
+        Err(LedgerClientError::Call(error)) => {
+            log!(DEBUG, "[withdraw]: {error}");
+            Err(WithdrawalError::TemporarilyUnavailable(error.to_string()))
+        }
+    }
+}
+
+#[update]
+#[candid_method(update)]
+async fn cancel_withdrawal(
+    ledger_burn_index: u64,
+) -> Result<CancelWithdrawalResult, CancelWithdrawalError> {
+    use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+    use num_traits::ToPrimitive;
+
+    let caller = validate_caller_not_anonymous();
+    let _guard = retrieve_eth_guard(caller).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!(
+            "Failed retrieving guard for principal {}: {:?}",
+            caller, e
+        ))
+    });
+
+    let ledger_burn_index = LedgerBurnIndex::new(ledger_burn_index);
+    // The cancellation is recorded before reimbursing, so that the request is not processed
+    // in the meantime. A cancellation that is already in progress is retried as is.
+    let (cancellation, is_retry) = mutate_state(|s| {
+        s.eth_transactions
+            .validate_withdrawal_cancellation(ledger_burn_index, caller)?;
+        let is_retry = s
+            .eth_transactions
+            .withdrawal_cancellation(&ledger_burn_index)
+            .is_some();
+        if !is_retry {
+            process_event(
+                s,
+                EventType::WithdrawalCancellationRequested {
+                    withdrawal_id: ledger_burn_index,
+                    created_at_time: ic_cdk::api::time(),
+                },
+            );
+        }
+        let cancellation = s
+            .eth_transactions
+            .withdrawal_cancellation(&ledger_burn_index)
+            .cloned()
+            .expect("BUG: withdrawal cancellation should be recorded");
+        Ok::<_, CancelWithdrawalError>((cancellation, is_retry))
+    })?;
+    let request = cancellation.request;
+    log!(
+        INFO,
+        "[cancel_withdrawal]: cancelled withdrawal request {request:?}"
+    );
+
+    let ledger_canister_id = read_state(|s| s.ledger_id);
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id,
+    };
+    // The memo and creation time identify the reimbursement, so that the ledger rejects
+    // a retried reimbursement whose previous attempt was minted but not recorded.
+    let reimbursement = match client
+        .transfer(TransferArg {
+            from_subaccount: None,
+            to: caller.into(),
+            fee: None,
+            created_at_time: Some(cancellation.created_at_time),
+            memo: Some(
+                MintMemo::ReimburseWithdrawal {
+                    withdrawal_id: request.ledger_burn_index,
+                }
+                .into(),
+            ),
+            amount: Nat::from(request.withdrawal_amount),
+        })
+        .await
+    {
+        Ok(Ok(block_index)) => Ok(block_index),
+        Ok(Err(TransferError::Duplicate { duplicate_of })) => {
+            duplicate_of.0.to_u64().ok_or_else(|| {
+                format!("reimbursement already minted in block {duplicate_of}, which does not fit in u64")
+            })
+        }
+        // The ledger rejected the only attempt, so nothing was minted
+        // and the request can safely be put back into the queue.
+        Ok(Err(err)) if !is_retry => {
             log!(
-                DEBUG,
-                "[withdraw]: failed to call ledger with error_code: {error_code} and message: {message}",
+                INFO,
+                "[cancel_withdrawal]: failed to reimburse ckETH: {err}, restoring withdrawal request {request:?}"
+            );
+            mutate_state(|s| {
+                process_event(
+                    s,
+                    EventType::WithdrawalCancellationRejected {
+                        withdrawal_id: request.ledger_burn_index,
+                    },
+                )
+            });
+            return Err(CancelWithdrawalError::TemporarilyUnavailable(format!(
+                "failed to reimburse ckETH: {err}"
+            )));
+        }
+        Ok(Err(err)) => Err(format!("failed to reimburse ckETH: {err}")),
+        Err((error_code, message)) => Err(format!(
+            "failed to call ledger with error_code: {error_code} and message: {message}"
+        )),
+    };
+    match reimbursement {
+        Ok(block_index) => {
+            log!(
+                INFO,
+                "[cancel_withdrawal]: reimbursed {} ckWei to {} in block {block_index}",
+                request.withdrawal_amount,
+                caller
             );
-            Err(WithdrawalError::TemporarilyUnavailable(
-                "failed to call ledger with error_code: {error_code} and message: {message}"
-                    .to_string(),
-            ))
+            mutate_state(|s| {
+                process_event(
+                    s,
+                    EventType::WithdrawalCancelled {
+                        withdrawal_id: request.ledger_burn_index,
+                        reimbursement_block_index: LedgerMintIndex::new(block_index),
+                    },
+                )
+            });
+            Ok(CancelWithdrawalResult {
+                reimbursement_block_index: Nat::from(block_index),
+            })
+        }
+        // A previous attempt may have been minted, so the request is not restored.
+        // Cancelling again retries the reimbursement, which the ledger deduplicates.
+        Err(error) => {
+            log!(
+                INFO,
+                "[cancel_withdrawal]: {error}, cancelling again retries reimbursing withdrawal request {request:?}"
+            );
+            Err(CancelWithdrawalError::TemporarilyUnavailable(error))
         }
     }
 }
@@ -656,13 +1261,13 @@ fn validate_caller_not_anonymous() -> candid::Principal {
     principal
 }
 
-async fn eth_fee_history() -> FeeHistory {
+async fn eth_fee_history(block_count: u8, reward_percentiles: Vec<u8>) -> FeeHistory {
     use eth_rpc::{BlockSpec, BlockTag, FeeHistoryParams, Quantity};
     read_state(EthRpcClient::from_state)
         .eth_fee_history(FeeHistoryParams {
-            block_count: Quantity::from(5_u8),
+            block_count: Quantity::from(block_count),
             highest_block: BlockSpec::Tag(BlockTag::Latest),
-            reward_percentiles: vec![20],
+            reward_percentiles,
         })
         .await
         .expect("HTTP call failed")
@@ -672,26 +1277,31 @@ async fn eth_fee_history() -> FeeHistory {
 async fn latest_transaction_count() -> Result<TransactionCount, MultiCallError<TransactionCount>> {
     use eth_rpc::{BlockSpec, BlockTag};
     use ic_cketh_minter::eth_rpc_client::requests::GetTransactionCountParams;
-    read_state(EthRpcClient::from_state)
+    let client = read_state(EthRpcClient::from_state);
+    let strategy = client.consensus_policy().eth_get_latest_transaction_count();
+    client
         .eth_get_transaction_count(GetTransactionCountParams {
             address: state::minter_address().await,
             block: BlockSpec::Tag(BlockTag::Latest),
         })
         .await
-        .reduce_with_min_by_key(|transaction_count| *transaction_count)
+        .reduce_by_key(strategy, |transaction_count| *transaction_count)
 }
 
 async fn finalized_transaction_count() -> Result<TransactionCount, MultiCallError<TransactionCount>>
 {
-    use eth_rpc::{BlockSpec, BlockTag};
     use ic_cketh_minter::eth_rpc_client::requests::GetTransactionCountParams;
-    read_state(EthRpcClient::from_state)
+    let client = read_state(EthRpcClient::from_state);
+    let strategy = client
+        .consensus_policy()
+        .eth_get_finalized_transaction_count();
+    client
         .eth_get_transaction_count(GetTransactionCountParams {
             address: state::minter_address().await,
-            block: BlockSpec::Tag(BlockTag::Finalized),
+            block: read_state(State::confirmed_block),
         })
         .await
-        .reduce_with_equality()
+        .reduce_by_key(strategy, |transaction_count| *transaction_count)
 }
 
 #[update]
@@ -701,6 +1311,23 @@ async fn retrieve_eth_status(block_index: u64) -> RetrieveEthStatus {
     read_state(|s| s.eth_transactions.transaction_status(&ledger_burn_index))
 }
 
+#[query]
+#[candid_method(query)]
+fn list_withdrawals(arg: ListWithdrawalsArg) -> ListWithdrawalsResult {
+    const MAX_WITHDRAWALS_PER_RESPONSE: u64 = 100;
+
+    read_state(|s| ListWithdrawalsResult {
+        withdrawals: s
+            .eth_transactions
+            .withdrawals_of(&arg.principal)
+            .skip(arg.start as usize)
+            .take(arg.length.min(MAX_WITHDRAWALS_PER_RESPONSE) as usize)
+            .map(|(request, status)| WithdrawalDetails::new(request, status))
+            .collect(),
+        total_withdrawal_count: s.eth_transactions.withdrawal_count_of(&arg.principal) as u64,
+    })
+}
+
 #[candid_method(query)]
 #[query]
 fn is_address_blocked(address_string: String) -> bool {
@@ -766,6 +1393,7 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     from_address,
                     value,
                     principal,
+                    subaccount,
                 }) => EP::AcceptedDeposit {
                     transaction_hash: transaction_hash.to_string(),
                     block_number: block_number.into(),
@@ -773,6 +1401,7 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     from_address: from_address.to_string(),
                     value: value.into(),
                     principal,
+                    subaccount,
                 },
                 EventType::InvalidDeposit {
                     event_source,
@@ -795,10 +1424,12 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     withdrawal_amount,
                     destination,
                     ledger_burn_index,
+                    from,
                 }) => EP::AcceptedEthWithdrawalRequest {
                     withdrawal_amount: withdrawal_amount.into(),
                     destination: destination.to_string(),
                     ledger_burn_index: ledger_burn_index.get().into(),
+                    from,
                 },
                 EventType::SignedTx { withdrawal_id, tx } => EP::SignedTx {
                     withdrawal_id: withdrawal_id.get().into(),
@@ -818,6 +1449,76 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     withdrawal_id: withdrawal_id.get().into(),
                     transaction_hash: txhash.to_string(),
                 },
+                EventType::FeeRefunded {
+                    withdrawal_id,
+                    beneficiary,
+                    amount,
+                    refund_block_index,
+                } => EP::FeeRefunded {
+                    withdrawal_id: withdrawal_id.get().into(),
+                    beneficiary,
+                    amount: amount.into(),
+                    refund_block_index: refund_block_index.get().into(),
+                },
+                EventType::ReorgedDeposit {
+                    event_source,
+                    reason,
+                } => EP::ReorgedDeposit {
+                    event_source: map_event_source(event_source),
+                    reason,
+                },
+                EventType::BlockedWithdrawalAttempt {
+                    caller,
+                    destination,
+                    amount,
+                } => EP::BlockedWithdrawalAttempt {
+                    caller,
+                    destination: destination.to_string(),
+                    amount: amount.into(),
+                },
+                EventType::FailedMint {
+                    event_source,
+                    reason,
+                } => EP::FailedMint {
+                    event_source: map_event_source(event_source),
+                    reason,
+                },
+                EventType::SkippedBlockRange { caller, from, to } => EP::SkippedBlockRange {
+                    caller,
+                    from: from.into(),
+                    to: to.into(),
+                },
+                EventType::RescanRequested { caller, from, to } => EP::RescanRequested {
+                    caller,
+                    from: from.into(),
+                    to: to.into(),
+                },
+                EventType::WithdrawalCancelled {
+                    withdrawal_id,
+                    reimbursement_block_index,
+                } => EP::WithdrawalCancelled {
+                    withdrawal_id: withdrawal_id.get().into(),
+                    reimbursement_block_index: reimbursement_block_index.get().into(),
+                },
+                EventType::RequeuedFailedMint {
+                    caller,
+                    event_source,
+                } => EP::RequeuedFailedMint {
+                    caller,
+                    event_source: map_event_source(event_source),
+                },
+                EventType::WithdrawalCancellationRequested {
+                    withdrawal_id,
+                    created_at_time,
+                } => EP::WithdrawalCancellationRequested {
+                    withdrawal_id: withdrawal_id.get().into(),
+                    created_at_time,
+                },
+                EventType::WithdrawalCancellationRejected { withdrawal_id } => {
+                    EP::WithdrawalCancellationRejected {
+                        withdrawal_id: withdrawal_id.get().into(),
+                    }
+                }
             },
         }
     }
@@ -875,6 +1576,22 @@ fn http_request(req: HttpRequest) -> HttpResponse {
                 .value(&[("status", "accepted")], s.minted_events.len() as f64)?
                 .value(&[("status", "rejected")], s.invalid_events.len() as f64)?;
 
+                w.encode_gauge(
+                    "cketh_minter_pending_mint_retries",
+                    s.mint_retries.len() as f64,
+                    "The number of deposits whose minting failed and is scheduled for retry.",
+                )?;
+
+                w.encode_gauge(
+                    "cketh_minter_failed_mints",
+                    s.events_to_mint_failed.len() as f64,
+                    "The number of deposits the ckETH minter gave up minting after too many attempts.",
+                )?;
+
+                if let Some(health) = &s.rpc_providers_health {
+                    health.encode_metrics(w)?;
+                }
+
                 Ok(())
             })
         }
@@ -897,70 +1614,18 @@ fn http_request(req: HttpRequest) -> HttpResponse {
             .with_body_and_content_length(dashboard.render().unwrap())
             .build()
     } else if req.path() == "/logs" {
-        use ic_cketh_minter::logs::{Log, Priority, Sort};
-        use std::str::FromStr;
-
-        let max_skip_timestamp = match req.raw_query_param("time") {
-            Some(arg) => match u64::from_str(arg) {
-                Ok(value) => value,
-                Err(_) => {
-                    return HttpResponseBuilder::bad_request()
-                        .with_body_and_content_length("failed to parse the 'time' parameter")
-                        .build()
-                }
-            },
-            None => 0,
-        };
-
-        let mut log: Log = Default::default();
+        use ic_canister_log::export_logs_http;
+        use ic_cketh_minter::logs::{DEBUG_BUF, INFO_BUF, TRACE_HTTP_BUF};
 
-        match req.raw_query_param("priority") {
-            Some(priority_str) => match Priority::from_str(priority_str) {
-                Ok(priority) => match priority {
-                    Priority::Info => log.push_logs(Priority::Info),
-                    Priority::TraceHttp => log.push_logs(Priority::TraceHttp),
-                    Priority::Debug => log.push_logs(Priority::Debug),
-                },
-                Err(_) => log.push_all(),
-            },
-            None => log.push_all(),
-        }
-
-        log.entries
-            .retain(|entry| entry.timestamp >= max_skip_timestamp);
-
-        fn ordering_from_query_params(sort: Option<&str>, max_skip_timestamp: u64) -> Sort {
-            match sort {
-                Some(ord_str) => match Sort::from_str(ord_str) {
-                    Ok(order) => order,
-                    Err(_) => {
-                        if max_skip_timestamp == 0 {
-                            Sort::Ascending
-                        } else {
-                            Sort::Descending
-                        }
-                    }
-                },
-                None => {
-                    if max_skip_timestamp == 0 {
-                        Sort::Ascending
-                    } else {
-                        Sort::Descending
-                    }
-                }
-            }
+        match export_logs_http(&req.url, &[&INFO_BUF, &DEBUG_BUF, &TRACE_HTTP_BUF]) {
+            Ok(body) => HttpResponseBuilder::ok()
+                .header("Content-Type", "application/json; charset=utf-8")
+                .with_body_and_content_length(body)
+                .build(),
+            Err(message) => HttpResponseBuilder::bad_request()
+                .with_body_and_content_length(message)
+                .build(),
         }
-
-        log.sort_logs(ordering_from_query_params(
-            req.raw_query_param("sort"),
-            max_skip_timestamp,
-        ));
-
-        const MAX_BODY_SIZE: usize = 3_000_000;
-        HttpResponseBuilder::ok()
-            .header("Content-Type", "application/json; charset=utf-8")
-            .with_body_and_content_length(log.serialize_logs(MAX_BODY_SIZE))
-            .build()
     } else {
         HttpResponseBuilder::not_found().build()
     }
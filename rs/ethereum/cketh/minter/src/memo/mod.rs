@@ -0,0 +1,93 @@
+//! The memos that the minter attaches to the ckETH ledger transactions it
+//! issues, so that ledger and index consumers can link ledger blocks to the
+//! corresponding Ethereum events.
+//!
+//! Memos are CBOR-encoded and, as long as log indices fit in 64 bits, fit in
+//! the 80 bytes that the ckETH ledger accepts.
+
+use crate::address::Address;
+use crate::eth_rpc::Hash;
+use crate::numeric::{LedgerBurnIndex, LogIndex};
+use icrc_ledger_types::icrc1::transfer::Memo;
+use minicbor::{Decode, Encode};
+
+#[cfg(test)]
+mod tests;
+
+/// The memo of a ckETH mint.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub enum MintMemo {
+    /// The minter minted ckETH for an ETH deposit to the helper contract.
+    #[n(0)]
+    Convert {
+        /// The sender of the ETH.
+        #[n(0)]
+        from_address: Address,
+        /// The hash of the deposit transaction.
+        #[n(1)]
+        tx_hash: Hash,
+        /// The index of the deposit event in the logs of the block.
+        #[n(2)]
+        log_index: LogIndex,
+    },
+    /// The minter reimbursed ckETH for a withdrawal that was cancelled or
+    /// whose transaction consumed less than the maximum fee.
+    #[n(1)]
+    ReimburseWithdrawal {
+        /// The withdrawal ID, i.e., the index of the ledger burn.
+        #[cbor(n(0), with = "crate::cbor::id")]
+        withdrawal_id: LedgerBurnIndex,
+    },
+}
+
+/// The memo of a ckETH burn. The withdrawal ID is the index of the burn
+/// itself.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub enum BurnMemo {
+    /// The minter burned ckETH to withdraw ETH to the given address.
+    #[n(0)]
+    Convert {
+        /// The destination of the withdrawal.
+        #[n(0)]
+        to_address: Address,
+    },
+}
+
+impl From<MintMemo> for Memo {
+    fn from(memo: MintMemo) -> Self {
+        encode(&memo)
+    }
+}
+
+impl From<BurnMemo> for Memo {
+    fn from(memo: BurnMemo) -> Self {
+        encode(&memo)
+    }
+}
+
+impl TryFrom<&Memo> for MintMemo {
+    type Error = String;
+
+    fn try_from(memo: &Memo) -> Result<Self, Self::Error> {
+        decode(memo)
+    }
+}
+
+impl TryFrom<&Memo> for BurnMemo {
+    type Error = String;
+
+    fn try_from(memo: &Memo) -> Result<Self, Self::Error> {
+        decode(memo)
+    }
+}
+
+fn encode<T: Encode<()>>(memo: &T) -> Memo {
+    let mut buf = vec![];
+    minicbor::encode(memo, &mut buf).expect("memo encoding should always succeed");
+    Memo::from(buf)
+}
+
+fn decode<'a, T: Decode<'a, ()>>(memo: &'a Memo) -> Result<T, String> {
+    minicbor::decode(memo.0.as_ref())
+        .map_err(|e| format!("failed to decode memo {}: {e}", hex::encode(&memo.0)))
+}
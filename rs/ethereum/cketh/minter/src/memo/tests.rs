@@ -0,0 +1,51 @@
+use crate::address::Address;
+use crate::eth_rpc::Hash;
+use crate::memo::{BurnMemo, MintMemo};
+use crate::numeric::{LedgerBurnIndex, LogIndex};
+use icrc_ledger_types::icrc1::transfer::Memo;
+use proptest::array::{uniform20, uniform32};
+use proptest::prelude::*;
+
+const MAX_MEMO_LENGTH: usize = 80;
+
+fn arb_address() -> impl Strategy<Value = Address> {
+    uniform20(any::<u8>()).prop_map(Address::new)
+}
+
+fn arb_mint_memo() -> impl Strategy<Value = MintMemo> {
+    prop_oneof![
+        (arb_address(), uniform32(any::<u8>()), any::<u64>()).prop_map(
+            |(from_address, tx_hash, log_index)| MintMemo::Convert {
+                from_address,
+                tx_hash: Hash(tx_hash),
+                log_index: LogIndex::from(log_index),
+            }
+        ),
+        any::<u64>().prop_map(|withdrawal_id| MintMemo::ReimburseWithdrawal {
+            withdrawal_id: LedgerBurnIndex::new(withdrawal_id),
+        }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn should_roundtrip_mint_memo(memo in arb_mint_memo()) {
+        let encoded = Memo::from(memo.clone());
+        prop_assert!(encoded.0.len() <= MAX_MEMO_LENGTH, "memo too long: {:?}", encoded);
+        prop_assert_eq!(MintMemo::try_from(&encoded), Ok(memo));
+    }
+
+    #[test]
+    fn should_roundtrip_burn_memo(to_address in arb_address()) {
+        let memo = BurnMemo::Convert { to_address };
+        let encoded = Memo::from(memo.clone());
+        prop_assert!(encoded.0.len() <= MAX_MEMO_LENGTH, "memo too long: {:?}", encoded);
+        prop_assert_eq!(BurnMemo::try_from(&encoded), Ok(memo));
+    }
+}
+
+#[test]
+fn should_fail_to_decode_foreign_memo() {
+    assert!(MintMemo::try_from(&Memo::from(42_u64)).is_err());
+    assert!(BurnMemo::try_from(&Memo::from(vec![])).is_err());
+}
@@ -1,11 +1,14 @@
 use crate::address::Address;
 use crate::eth_logs::{EventSource, ReceivedEthEvent};
-use crate::eth_rpc::BlockTag;
+use crate::eth_rpc::{BlockSpec, BlockTag};
+use crate::eth_rpc_client::{ProvidersHealth, RpcConsensusPolicy};
 use crate::lifecycle::upgrade::UpgradeArg;
 use crate::lifecycle::EthereumNetwork;
 use crate::logs::DEBUG;
 use crate::numeric::{BlockNumber, LedgerMintIndex, TransactionNonce, Wei};
 use crate::transactions::EthTransactions;
+use crate::tx::{FeeEstimationPolicy, TransactionPrice};
+use crate::{MAX_MINT_RETRY_DELAY, MINT_RETRY_DELAY};
 use candid::Principal;
 use ic_canister_log::log;
 use ic_cdk::api::management_canister::ecdsa::EcdsaPublicKeyResponse;
@@ -21,6 +24,10 @@ pub mod event;
 #[cfg(test)]
 mod tests;
 
+/// Number of blocks beyond the configured block height after which a minted deposit
+/// is re-validated against the Ethereum chain to detect reorganizations.
+pub const DEPOSIT_REVALIDATION_CONFIRMATIONS: u64 = 64;
+
 thread_local! {
     pub static STATE: RefCell<Option<State>> = RefCell::default();
 }
@@ -37,6 +44,30 @@ impl MintedEvent {
     }
 }
 
+/// Deposit for which minting failed [`crate::MAX_MINT_ATTEMPTS`] times in a row.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct FailedMint {
+    pub deposit_event: ReceivedEthEvent,
+    pub reason: String,
+}
+
+/// Helper smart contract that was replaced by a new one, but whose logs are still scraped
+/// for an overlap window, so that deposits sent to it during the migration are not lost.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct RetiredContract {
+    pub last_scraped_block_number: BlockNumber,
+    /// Last block (inclusive) whose logs are scraped for this contract.
+    pub last_block_to_scrape: BlockNumber,
+}
+
+/// Failed attempts to mint a deposit.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct MintRetry {
+    pub attempts: u32,
+    /// IC time in nanoseconds before which minting must not be retried.
+    pub next_attempt_at: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct State {
     pub ethereum_network: EthereumNetwork,
@@ -45,13 +76,43 @@ pub struct State {
     pub ethereum_contract_address: Option<Address>,
     pub ecdsa_public_key: Option<EcdsaPublicKeyResponse>,
     pub minimum_withdrawal_amount: Wei,
+    /// Deposits below this amount are rejected instead of being minted.
+    /// Deposits below this amount are not minted. Defaults to zero.
+    #[serde(default = "zero_wei")]
+    pub minimum_deposit_amount: Wei,
     pub ethereum_block_height: BlockTag,
+    /// Number of confirmations on top of the latest block after which a block is considered final.
+    /// Takes precedence over `ethereum_block_height` when set.
+    pub ethereum_block_confirmations: Option<u64>,
     pub last_scraped_block_number: BlockNumber,
+    /// Previous helper smart contracts whose logs are still scraped until the end of
+    /// their overlap window.
+    #[serde(default)]
+    pub retired_contracts: BTreeMap<Address, RetiredContract>,
     pub last_observed_block_number: Option<BlockNumber>,
     pub events_to_mint: BTreeMap<EventSource, ReceivedEthEvent>,
+    /// Deposits that could not be minted after too many attempts.
+    /// They are no longer retried automatically.
+    #[serde(default)]
+    pub events_to_mint_failed: BTreeMap<EventSource, FailedMint>,
     pub minted_events: BTreeMap<EventSource, MintedEvent>,
     pub invalid_events: BTreeMap<EventSource, String>,
+    /// Minted deposits whose transaction was not yet re-validated after
+    /// [`DEPOSIT_REVALIDATION_CONFIRMATIONS`] additional blocks.
+    #[serde(default)]
+    pub unconfirmed_deposits: BTreeSet<EventSource>,
+    /// Minted deposits whose transaction disappeared from the chain due to a reorganization.
+    /// Their beneficiaries cannot withdraw, see [`State::frozen_amount_of`].
+    #[serde(default)]
+    pub reorged_deposits: BTreeMap<EventSource, String>,
     pub eth_transactions: EthTransactions,
+    #[serde(default)]
+    pub rpc_consensus_policy: RpcConsensusPolicy,
+    /// Contract used to serve several withdrawal requests with a single transaction.
+    /// Withdrawal requests are not batched if not set.
+    pub disperse_contract_address: Option<Address>,
+    #[serde(default)]
+    pub fee_estimation_policy: FeeEstimationPolicy,
 
     /// Per-principal lock for pending_retrieve_eth_requests
     #[serde(skip)]
@@ -65,6 +126,18 @@ pub struct State {
     /// Used to correlate request and response in logs.
     #[serde(skip)]
     pub http_request_counter: u64,
+
+    /// Failed mint attempts since the last upgrade, used to back off exponentially.
+    #[serde(skip)]
+    pub mint_retries: BTreeMap<EventSource, MintRetry>,
+
+    /// Result of the last health check of the JSON-RPC providers.
+    #[serde(skip)]
+    pub rpc_providers_health: Option<ProvidersHealth>,
+
+    /// Last (smoothed) estimate of the transaction price since the last upgrade.
+    #[serde(skip)]
+    pub last_transaction_price_estimate: Option<TransactionPrice>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -74,6 +147,14 @@ pub enum InvalidStateError {
     InvalidLedgerId(String),
     InvalidEthereumContractAddress(String),
     InvalidMinimumWithdrawalAmount(String),
+    InvalidMinimumDepositAmount(String),
+    InvalidRpcConsensusPolicy(String),
+    InvalidDisperseContractAddress(String),
+    InvalidFeeEstimationPolicy(String),
+}
+
+fn zero_wei() -> Wei {
+    Wei::ZERO
 }
 
 impl State {
@@ -97,14 +178,73 @@ impl State {
                 "ethereum_contract_address cannot be the zero address".to_string(),
             ));
         }
+        if self
+            .disperse_contract_address
+            .iter()
+            .any(|address| address == &Address::ZERO)
+        {
+            return Err(InvalidStateError::InvalidDisperseContractAddress(
+                "disperse_contract_address cannot be the zero address".to_string(),
+            ));
+        }
         if self.minimum_withdrawal_amount == Wei::ZERO {
             return Err(InvalidStateError::InvalidMinimumWithdrawalAmount(
                 "minimum_withdrawal_amount must be positive".to_string(),
             ));
         }
+        self.rpc_consensus_policy
+            .validate(self.ethereum_network)
+            .map_err(InvalidStateError::InvalidRpcConsensusPolicy)?;
+        self.fee_estimation_policy
+            .validate()
+            .map_err(InvalidStateError::InvalidFeeEstimationPolicy)?;
         Ok(())
     }
 
+    /// Smooths a new estimate of the transaction price with the previous one according to the
+    /// fee estimation policy, and records the result as the latest estimate.
+    pub fn record_transaction_price_estimate(
+        &mut self,
+        estimate: TransactionPrice,
+    ) -> TransactionPrice {
+        let smoothed = match &self.last_transaction_price_estimate {
+            Some(previous) => estimate.smooth(
+                previous,
+                self.fee_estimation_policy.smoothing_factor_percent(),
+            ),
+            None => estimate,
+        };
+        self.last_transaction_price_estimate = Some(smoothed.clone());
+        smoothed
+    }
+
+    /// Records that the logs of the helper smart contract at `contract_address` were scraped
+    /// up to `block_number` (inclusive). Retired contracts are no longer tracked once their
+    /// overlap window was fully scraped.
+    pub fn update_last_scraped_block_number(
+        &mut self,
+        contract_address: Address,
+        block_number: BlockNumber,
+    ) {
+        if self.ethereum_contract_address == Some(contract_address) {
+            self.last_scraped_block_number = block_number;
+            return;
+        }
+        if let btree_map::Entry::Occupied(mut entry) =
+            self.retired_contracts.entry(contract_address)
+        {
+            if block_number >= entry.get().last_block_to_scrape {
+                log!(
+                    DEBUG,
+                    "Finished scraping the logs of retired contract {contract_address}"
+                );
+                entry.remove();
+            } else {
+                entry.get_mut().last_scraped_block_number = block_number;
+            }
+        }
+    }
+
     pub fn minter_address(&self) -> Option<Address> {
         let pubkey = PublicKey::deserialize_sec1(&self.ecdsa_public_key.as_ref()?.public_key)
             .unwrap_or_else(|e| {
@@ -121,6 +261,7 @@ impl State {
         );
         assert!(!self.minted_events.contains_key(&event_source));
         assert!(!self.invalid_events.contains_key(&event_source));
+        assert!(!self.events_to_mint_failed.contains_key(&event_source));
 
         self.events_to_mint.insert(event_source, event);
     }
@@ -165,6 +306,158 @@ impl State {
             None,
             "attempted to mint ckETH twice for the same event {source:?}"
         );
+        self.unconfirmed_deposits.insert(source);
+        self.mint_retries.remove(&source);
+    }
+
+    /// Whether the deposit with the given source was already accepted, rejected or minted.
+    /// Checks that the deposited amount is at least the minimum deposit amount.
+    pub fn validate_deposit_amount(&self, event: &ReceivedEthEvent) -> Result<(), String> {
+        if event.value < self.minimum_deposit_amount {
+            return Err(format!(
+                "deposit of {} wei is below the minimum deposit amount of {} wei",
+                event.value, self.minimum_deposit_amount
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn has_processed_deposit(&self, source: &EventSource) -> bool {
+        self.events_to_mint.contains_key(source)
+            || self.minted_events.contains_key(source)
+            || self.invalid_events.contains_key(source)
+            || self.events_to_mint_failed.contains_key(source)
+    }
+
+    /// Events to mint whose retry delay, if any, elapsed at the given time.
+    pub fn events_to_mint_due(&self, now: u64) -> Vec<(EventSource, ReceivedEthEvent)> {
+        self.events_to_mint
+            .iter()
+            .filter(|(source, _event)| {
+                self.mint_retries
+                    .get(source)
+                    .map_or(true, |retry| retry.next_attempt_at <= now)
+            })
+            .map(|(source, event)| (*source, event.clone()))
+            .collect()
+    }
+
+    /// Records a failed attempt to mint the given event and schedules the next attempt
+    /// with an exponential backoff. Returns the number of failed attempts so far.
+    pub fn record_failed_mint_attempt(&mut self, source: EventSource, now: u64) -> u32 {
+        assert!(
+            self.events_to_mint.contains_key(&source),
+            "attempted to retry minting an unknown event {source:?}"
+        );
+        let retry = self.mint_retries.entry(source).or_insert(MintRetry {
+            attempts: 0,
+            next_attempt_at: now,
+        });
+        retry.attempts = retry.attempts.saturating_add(1);
+        let delay = MINT_RETRY_DELAY
+            .checked_mul(2_u32.saturating_pow(retry.attempts - 1))
+            .map_or(MAX_MINT_RETRY_DELAY, |delay| {
+                delay.min(MAX_MINT_RETRY_DELAY)
+            });
+        retry.next_attempt_at = now.saturating_add(delay.as_nanos() as u64);
+        retry.attempts
+    }
+
+    /// IC time in nanoseconds of the earliest scheduled mint retry.
+    pub fn next_mint_retry_at(&self) -> Option<u64> {
+        self.mint_retries
+            .values()
+            .map(|retry| retry.next_attempt_at)
+            .min()
+    }
+
+    fn record_failed_mint(&mut self, source: EventSource, reason: String) {
+        let deposit_event = match self.events_to_mint.remove(&source) {
+            Some(event) => event,
+            None => panic!("attempted to mark an unknown event {source:?} as failed to mint"),
+        };
+        self.mint_retries.remove(&source);
+        assert_eq!(
+            self.events_to_mint_failed.insert(
+                source,
+                FailedMint {
+                    deposit_event,
+                    reason
+                }
+            ),
+            None,
+            "attempted to mark the event {source:?} as failed to mint twice"
+        );
+    }
+
+    fn record_requeued_failed_mint(&mut self, source: EventSource) {
+        let failed_mint = match self.events_to_mint_failed.remove(&source) {
+            Some(failed_mint) => failed_mint,
+            None => panic!("attempted to requeue an unknown failed mint {source:?}"),
+        };
+        self.record_event_to_mint(failed_mint.deposit_event);
+    }
+
+    /// Returns the minted deposits that should be re-validated given the last observed block number.
+    pub fn deposits_to_revalidate(
+        &self,
+        last_observed_block_number: BlockNumber,
+    ) -> Vec<MintedEvent> {
+        self.unconfirmed_deposits
+            .iter()
+            .map(|source| {
+                self.minted_events
+                    .get(source)
+                    .expect("BUG: unconfirmed deposit must have been minted")
+            })
+            .filter(|minted_event| {
+                minted_event
+                    .deposit_event
+                    .block_number
+                    .checked_add(BlockNumber::from(DEPOSIT_REVALIDATION_CONFIRMATIONS))
+                    .map_or(false, |block_number| {
+                        block_number <= last_observed_block_number
+                    })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn record_confirmed_deposit(&mut self, source: &EventSource) {
+        assert!(
+            self.unconfirmed_deposits.remove(source),
+            "attempted to confirm an unknown deposit {source:?}"
+        );
+    }
+
+    fn record_reorged_deposit(&mut self, source: EventSource, reason: String) {
+        assert!(
+            self.unconfirmed_deposits.remove(&source),
+            "attempted to mark an unknown deposit {source:?} as reorged"
+        );
+        assert_eq!(
+            self.reorged_deposits.insert(source, reason),
+            None,
+            "attempted to mark the deposit {source:?} as reorged twice"
+        );
+    }
+
+    /// Total amount of reorged deposits minted to the given principal.
+    /// While it is non-zero, the principal cannot withdraw ETH.
+    ///
+    /// Note that the minter does not hold the corresponding ckETH: they remain transferable
+    /// on the ledger, and another principal receiving them can still withdraw them.
+    /// Recovering such amounts requires a manual intervention.
+    pub fn frozen_amount_of(&self, principal: &Principal) -> Wei {
+        self.reorged_deposits
+            .keys()
+            .filter_map(|source| self.minted_events.get(source))
+            .filter(|minted_event| &minted_event.deposit_event.principal == principal)
+            .fold(Wei::ZERO, |total, minted_event| {
+                total
+                    .checked_add(minted_event.deposit_event.value)
+                    .expect("BUG: overflow when summing frozen amounts")
+            })
     }
 
     pub fn next_request_id(&mut self) -> u64 {
@@ -183,6 +476,37 @@ impl State {
         self.ethereum_block_height
     }
 
+    /// Tag of the block to query to determine the last observed block number.
+    pub const fn observed_block_tag(&self) -> BlockTag {
+        match self.ethereum_block_confirmations {
+            Some(_) => BlockTag::Latest,
+            None => self.ethereum_block_height,
+        }
+    }
+
+    /// Number of the last block considered final, given the number of the block
+    /// returned for [`State::observed_block_tag`].
+    pub fn confirmed_block_number(&self, observed_block_number: BlockNumber) -> BlockNumber {
+        match self.ethereum_block_confirmations {
+            Some(confirmations) => observed_block_number
+                .checked_sub(BlockNumber::from(confirmations))
+                .unwrap_or(BlockNumber::ZERO),
+            None => observed_block_number,
+        }
+    }
+
+    /// Block at which the transaction count of the minter is considered final.
+    pub fn confirmed_block(&self) -> BlockSpec {
+        match (
+            self.ethereum_block_confirmations,
+            self.last_observed_block_number,
+        ) {
+            (Some(_), Some(block_number)) => BlockSpec::Number(block_number),
+            (Some(_), None) => BlockSpec::Tag(BlockTag::Finalized),
+            (None, _) => BlockSpec::Tag(self.ethereum_block_height),
+        }
+    }
+
     fn upgrade(&mut self, upgrade_args: UpgradeArg) -> Result<(), InvalidStateError> {
         use std::str::FromStr;
 
@@ -191,6 +515,12 @@ impl State {
             minimum_withdrawal_amount,
             ethereum_contract_address,
             ethereum_block_height,
+            rpc_consensus_policy,
+            minimum_deposit_amount,
+            disperse_contract_address,
+            ethereum_block_confirmations,
+            ethereum_contract_address_overlap_blocks,
+            fee_estimation_policy,
         } = upgrade_args;
         if let Some(nonce) = next_transaction_nonce {
             let nonce = TransactionNonce::try_from(nonce)
@@ -207,11 +537,52 @@ impl State {
             let ethereum_contract_address = Address::from_str(&address).map_err(|e| {
                 InvalidStateError::InvalidEthereumContractAddress(format!("ERROR: {}", e))
             })?;
+            let previous_contract_address = self
+                .ethereum_contract_address
+                .filter(|previous| previous != &ethereum_contract_address);
+            if let (Some(previous), Some(overlap_blocks)) = (
+                previous_contract_address,
+                ethereum_contract_address_overlap_blocks.filter(|&blocks| blocks > 0),
+            ) {
+                let last_block_to_scrape = self
+                    .last_scraped_block_number
+                    .checked_add(BlockNumber::from(overlap_blocks))
+                    .unwrap_or(BlockNumber::MAX);
+                self.retired_contracts.insert(
+                    previous,
+                    RetiredContract {
+                        last_scraped_block_number: self.last_scraped_block_number,
+                        last_block_to_scrape,
+                    },
+                );
+            }
+            self.retired_contracts.remove(&ethereum_contract_address);
             self.ethereum_contract_address = Some(ethereum_contract_address);
         }
         if let Some(block_height) = ethereum_block_height {
             self.ethereum_block_height = block_height.into();
         }
+        if let Some(confirmations) = ethereum_block_confirmations {
+            self.ethereum_block_confirmations = Some(confirmations).filter(|&c| c > 0);
+        }
+        if let Some(policy) = rpc_consensus_policy {
+            self.rpc_consensus_policy = policy;
+        }
+        if let Some(amount) = minimum_deposit_amount {
+            let minimum_deposit_amount = Wei::try_from(amount).map_err(|e| {
+                InvalidStateError::InvalidMinimumDepositAmount(format!("ERROR: {}", e))
+            })?;
+            self.minimum_deposit_amount = minimum_deposit_amount;
+        }
+        if let Some(address) = disperse_contract_address {
+            let disperse_contract_address = Address::from_str(&address).map_err(|e| {
+                InvalidStateError::InvalidDisperseContractAddress(format!("ERROR: {}", e))
+            })?;
+            self.disperse_contract_address = Some(disperse_contract_address);
+        }
+        if let Some(policy) = fee_estimation_policy {
+            self.fee_estimation_policy = policy;
+        }
         self.validate_config()
     }
 }
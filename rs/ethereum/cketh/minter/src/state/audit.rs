@@ -1,5 +1,6 @@
 pub use super::event::{Event, EventType};
 use super::State;
+use crate::numeric::BlockNumber;
 use crate::storage::record_event;
 
 /// Updates the state to reflect the given state transition.
@@ -31,6 +32,53 @@ fn apply_state_transition(state: &mut State, payload: &EventType) {
         EventType::SyncedToBlock { block_number } => {
             state.last_scraped_block_number = *block_number;
         }
+        EventType::FeeRefunded { withdrawal_id, .. } => {
+            state.eth_transactions.record_fee_refund(*withdrawal_id);
+        }
+        EventType::ReorgedDeposit {
+            event_source,
+            reason,
+        } => {
+            state.record_reorged_deposit(*event_source, reason.clone());
+        }
+        EventType::BlockedWithdrawalAttempt { .. } => {
+            // Blocked withdrawal attempts are only recorded for compliance review.
+        }
+        EventType::FailedMint {
+            event_source,
+            reason,
+        } => {
+            state.record_failed_mint(*event_source, reason.clone());
+        }
+        EventType::SkippedBlockRange { to, .. } => {
+            state.last_scraped_block_number = *to;
+        }
+        EventType::RescanRequested { from, .. } => {
+            state.last_scraped_block_number = from
+                .checked_sub(BlockNumber::ONE)
+                .expect("BUG: cannot rescan from block 0");
+        }
+        EventType::RequeuedFailedMint { event_source, .. } => {
+            state.record_requeued_failed_mint(*event_source);
+        }
+        EventType::WithdrawalCancelled { withdrawal_id, .. } => {
+            state
+                .eth_transactions
+                .record_cancelled_withdrawal_request(*withdrawal_id);
+        }
+        EventType::WithdrawalCancellationRequested {
+            withdrawal_id,
+            created_at_time,
+        } => {
+            state
+                .eth_transactions
+                .record_withdrawal_cancellation(*withdrawal_id, *created_at_time);
+        }
+        EventType::WithdrawalCancellationRejected { withdrawal_id } => {
+            state
+                .eth_transactions
+                .record_rejected_withdrawal_cancellation(*withdrawal_id);
+        }
         e => {
             unimplemented!("Handling {e:?} is not yet implemlemented");
         }
@@ -1,9 +1,11 @@
+use crate::address::Address;
 use crate::eth_logs::{EventSource, ReceivedEthEvent};
 use crate::eth_rpc::Hash;
 use crate::lifecycle::{init::InitArg, upgrade::UpgradeArg};
-use crate::numeric::{BlockNumber, LedgerBurnIndex, LedgerMintIndex};
+use crate::numeric::{BlockNumber, LedgerBurnIndex, LedgerMintIndex, Wei};
 use crate::transactions::EthWithdrawalRequest;
 use crate::tx::SignedEip1559TransactionRequest;
+use candid::Principal;
 use minicbor::{Decode, Encode};
 
 /// The event describing the ckETH minter state transition.
@@ -77,6 +79,124 @@ pub enum EventType {
         #[n(1)]
         txhash: Hash,
     },
+    /// The minter refunded the unused part of the transaction fee on the ckETH ledger.
+    #[n(11)]
+    FeeRefunded {
+        /// The withdrawal identifier.
+        #[cbor(n(0), with = "crate::cbor::id")]
+        withdrawal_id: LedgerBurnIndex,
+        /// The issuer of the withdrawal request receiving the refund.
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        beneficiary: Principal,
+        /// The refunded amount.
+        #[n(2)]
+        amount: Wei,
+        /// The transaction index on the ckETH ledger.
+        #[cbor(n(3), with = "crate::cbor::id")]
+        refund_block_index: LedgerMintIndex,
+    },
+    /// The minter detected that the transaction of a minted deposit
+    /// is no longer part of the Ethereum chain.
+    #[n(12)]
+    ReorgedDeposit {
+        /// The unique identifier of the deposit on the Ethereum network.
+        #[n(0)]
+        event_source: EventSource,
+        /// The reason why the minter considers the deposit reorged.
+        #[n(1)]
+        reason: String,
+    },
+    /// The minter rejected a withdrawal request to a blocked address.
+    #[n(13)]
+    BlockedWithdrawalAttempt {
+        /// The principal that attempted the withdrawal.
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        caller: Principal,
+        /// The blocked destination address.
+        #[n(1)]
+        destination: Address,
+        /// The requested withdrawal amount.
+        #[n(2)]
+        amount: Wei,
+    },
+    /// The minter gave up minting ckETH for a deposit after too many failed attempts.
+    #[n(14)]
+    FailedMint {
+        /// The unique identifier of the deposit on the Ethereum network.
+        #[n(0)]
+        event_source: EventSource,
+        /// The error returned by the last attempt.
+        #[n(1)]
+        reason: String,
+    },
+    /// A controller moved the last scraped block forward, so that the helper smart contract
+    /// logs in the given range of blocks are never processed.
+    #[n(15)]
+    SkippedBlockRange {
+        /// The controller that requested the change.
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        caller: Principal,
+        /// The first skipped block number (inclusive).
+        #[n(1)]
+        from: BlockNumber,
+        /// The last skipped block number (inclusive).
+        #[n(2)]
+        to: BlockNumber,
+    },
+    /// A controller moved the last scraped block backward, so that the helper smart contract
+    /// logs in the given range of blocks are processed again.
+    #[n(16)]
+    RescanRequested {
+        /// The controller that requested the change.
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        caller: Principal,
+        /// The first block number to scrape again (inclusive).
+        #[n(1)]
+        from: BlockNumber,
+        /// The last block number to scrape again (inclusive).
+        #[n(2)]
+        to: BlockNumber,
+    },
+    /// The minter reimbursed the withdrawal amount of a cancelled withdrawal request.
+    #[n(17)]
+    WithdrawalCancelled {
+        /// The withdrawal identifier.
+        #[cbor(n(0), with = "crate::cbor::id")]
+        withdrawal_id: LedgerBurnIndex,
+        /// The transaction index on the ckETH ledger reimbursing the withdrawal amount.
+        #[cbor(n(1), with = "crate::cbor::id")]
+        reimbursement_block_index: LedgerMintIndex,
+    },
+    /// A controller requeued a deposit that the minter gave up minting,
+    /// so that minting it is attempted again.
+    #[n(18)]
+    RequeuedFailedMint {
+        /// The controller that requested the change.
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        caller: Principal,
+        /// The unique identifier of the deposit on the Ethereum network.
+        #[n(1)]
+        event_source: EventSource,
+    },
+    /// The issuer of a pending withdrawal request cancelled it,
+    /// and the minter started reimbursing the withdrawal amount.
+    #[n(19)]
+    WithdrawalCancellationRequested {
+        /// The withdrawal identifier.
+        #[cbor(n(0), with = "crate::cbor::id")]
+        withdrawal_id: LedgerBurnIndex,
+        /// The creation time of the ckETH ledger transaction reimbursing the withdrawal amount.
+        #[n(1)]
+        created_at_time: u64,
+    },
+    /// The ckETH ledger rejected the reimbursement of a cancelled withdrawal request,
+    /// so the request was put back into the queue.
+    #[n(20)]
+    WithdrawalCancellationRejected {
+        /// The withdrawal identifier.
+        #[cbor(n(0), with = "crate::cbor::id")]
+        withdrawal_id: LedgerBurnIndex,
+    },
 }
 
 #[derive(Encode, Decode, Debug, PartialEq, Eq)]
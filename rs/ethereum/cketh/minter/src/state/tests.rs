@@ -52,6 +52,9 @@ fn a_state() -> State {
         ethereum_block_height: Default::default(),
         minimum_withdrawal_amount: wei_from_milli_ether(10).into(),
         next_transaction_nonce: Default::default(),
+        rpc_consensus_policy: None,
+        minimum_deposit_amount: None,
+        disperse_contract_address: None,
     })
     .expect("init args should be valid")
 }
@@ -60,7 +63,8 @@ mod mint_transaction {
     use crate::eth_logs::{EventSourceError, ReceivedEthEvent};
     use crate::lifecycle::init::InitArg;
     use crate::numeric::{wei_from_milli_ether, BlockNumber, LedgerMintIndex, LogIndex, Wei};
-    use crate::state::{MintedEvent, State};
+    use crate::state::{FailedMint, MintedEvent, State, DEPOSIT_REVALIDATION_CONFIRMATIONS};
+    use crate::{MAX_MINT_RETRY_DELAY, MINT_RETRY_DELAY};
 
     #[test]
     fn should_record_mint_task_from_event() {
@@ -161,11 +165,199 @@ mod mint_transaction {
           log_index: 29, \
           from_address: 0xdd2851Cdd40aE6536831558DD46db62fAc7A844d, \
           value: 10_000_000_000_000_000, \
-          principal: k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae \
+          principal: k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae, \
+          subaccount: None \
         }";
         assert_eq!(format!("{:?}", received_eth_event()), expected);
     }
 
+    #[test]
+    fn should_revalidate_minted_deposit_after_enough_confirmations() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(event.clone());
+        state.record_successful_mint(event.source(), LedgerMintIndex::new(1));
+
+        let confirmed_block = event
+            .block_number
+            .checked_add(BlockNumber::from(DEPOSIT_REVALIDATION_CONFIRMATIONS))
+            .unwrap();
+        assert_eq!(
+            state.deposits_to_revalidate(confirmed_block.checked_sub(BlockNumber::ONE).unwrap()),
+            vec![]
+        );
+        let minted_event = MintedEvent {
+            deposit_event: event.clone(),
+            mint_block_index: LedgerMintIndex::new(1),
+        };
+        assert_eq!(
+            state.deposits_to_revalidate(confirmed_block),
+            vec![minted_event]
+        );
+
+        state.record_confirmed_deposit(&event.source());
+
+        assert_eq!(state.deposits_to_revalidate(confirmed_block), vec![]);
+        assert_eq!(state.frozen_amount_of(&event.principal), Wei::ZERO);
+    }
+
+    #[test]
+    fn should_freeze_reorged_deposit() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(event.clone());
+        state.record_successful_mint(event.source(), LedgerMintIndex::new(1));
+
+        state.record_reorged_deposit(event.source(), "transaction not found".to_string());
+
+        assert_eq!(state.deposits_to_revalidate(BlockNumber::MAX), vec![]);
+        assert_eq!(
+            state.reorged_deposits.get(&event.source()),
+            Some(&"transaction not found".to_string())
+        );
+        assert_eq!(state.frozen_amount_of(&event.principal), event.value);
+        assert_eq!(
+            state.frozen_amount_of(&candid::Principal::management_canister()),
+            Wei::ZERO
+        );
+    }
+
+    #[test]
+    fn should_back_off_exponentially_when_mint_fails() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(event.clone());
+        let now = 1_000;
+        let delay = MINT_RETRY_DELAY.as_nanos() as u64;
+
+        assert_eq!(state.record_failed_mint_attempt(event.source(), now), 1);
+        assert_eq!(state.next_mint_retry_at(), Some(now + delay));
+        assert_eq!(state.events_to_mint_due(now + delay - 1), vec![]);
+        assert_eq!(
+            state.events_to_mint_due(now + delay),
+            vec![(event.source(), event.clone())]
+        );
+
+        assert_eq!(state.record_failed_mint_attempt(event.source(), now), 2);
+        assert_eq!(state.next_mint_retry_at(), Some(now + 2 * delay));
+
+        assert_eq!(state.record_failed_mint_attempt(event.source(), now), 3);
+        assert_eq!(state.next_mint_retry_at(), Some(now + 4 * delay));
+
+        for _ in 0..30 {
+            state.record_failed_mint_attempt(event.source(), now);
+        }
+        assert_eq!(
+            state.next_mint_retry_at(),
+            Some(now + MAX_MINT_RETRY_DELAY.as_nanos() as u64)
+        );
+
+        state.record_successful_mint(event.source(), LedgerMintIndex::new(1));
+        assert_eq!(state.next_mint_retry_at(), None);
+    }
+
+    #[test]
+    fn should_know_processed_deposits() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        assert!(!state.has_processed_deposit(&event.source()));
+
+        state.record_event_to_mint(event.clone());
+        assert!(state.has_processed_deposit(&event.source()));
+
+        state.record_successful_mint(event.source(), LedgerMintIndex::new(1));
+        assert!(state.has_processed_deposit(&event.source()));
+    }
+
+    #[test]
+    fn should_move_failed_mint_to_dead_letter_list() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(event.clone());
+        state.record_failed_mint_attempt(event.source(), 0);
+
+        state.record_failed_mint(event.source(), "ledger is stopped".to_string());
+
+        assert_eq!(state.events_to_mint_due(u64::MAX), vec![]);
+        assert_eq!(state.next_mint_retry_at(), None);
+        assert_eq!(
+            state.events_to_mint_failed.get(&event.source()),
+            Some(&FailedMint {
+                deposit_event: event,
+                reason: "ledger is stopped".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn should_requeue_failed_mint() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(event.clone());
+        state.record_failed_mint_attempt(event.source(), 0);
+        state.record_failed_mint(event.source(), "ledger is stopped".to_string());
+
+        state.record_requeued_failed_mint(event.source());
+
+        assert_eq!(state.events_to_mint_failed.get(&event.source()), None);
+        assert_eq!(
+            state.events_to_mint_due(0),
+            vec![(event.source(), event.clone())]
+        );
+        assert!(state.has_processed_deposit(&event.source()));
+
+        state.record_successful_mint(event.source(), LedgerMintIndex::new(1));
+        assert!(state.minted_events.contains_key(&event.source()));
+    }
+
+    #[test]
+    #[should_panic = "unknown failed mint"]
+    fn should_not_requeue_unknown_failed_mint() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(event.clone());
+
+        state.record_requeued_failed_mint(event.source());
+    }
+
+    #[test]
+    #[should_panic = "unknown deposit"]
+    fn should_not_mark_unminted_deposit_as_reorged() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(event.clone());
+
+        state.record_reorged_deposit(event.source(), "transaction not found".to_string());
+    }
+
+    #[test]
+    fn should_skip_deposit_below_minimum_amount() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.minimum_deposit_amount = event.value.checked_add(Wei::ONE).unwrap();
+
+        let reason = state
+            .validate_deposit_amount(&event)
+            .expect_err("deposit below the minimum amount should be rejected");
+        assert_eq!(
+            reason,
+            "deposit of 10_000_000_000_000_000 wei is below the minimum deposit amount of 10_000_000_000_000_001 wei"
+        );
+
+        assert!(state.record_invalid_deposit(event.source(), reason));
+        assert!(state.has_processed_deposit(&event.source()));
+        assert!(!state.events_to_mint.contains_key(&event.source()));
+    }
+
+    #[test]
+    fn should_accept_deposit_of_minimum_amount() {
+        let mut state = dummy_state();
+        let event = received_eth_event();
+        state.minimum_deposit_amount = event.value;
+
+        assert_eq!(state.validate_deposit_amount(&event), Ok(()));
+    }
+
     fn dummy_state() -> State {
         use candid::Principal;
         State::try_from(InitArg {
@@ -177,6 +369,9 @@ mod mint_transaction {
             ethereum_block_height: Default::default(),
             minimum_withdrawal_amount: wei_from_milli_ether(10).into(),
             next_transaction_nonce: Default::default(),
+            rpc_consensus_policy: None,
+            minimum_deposit_amount: None,
+            disperse_contract_address: None,
         })
         .expect("init args should be valid")
     }
@@ -195,6 +390,7 @@ mod mint_transaction {
             principal: "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae"
                 .parse()
                 .unwrap(),
+            subaccount: None,
         }
     }
 }
@@ -202,9 +398,11 @@ mod mint_transaction {
 mod upgrade {
     use crate::address::Address;
     use crate::eth_rpc::BlockTag;
+    use crate::eth_rpc_client::{ConsensusStrategy, RpcConsensusPolicy};
     use crate::lifecycle::upgrade::UpgradeArg;
-    use crate::numeric::{wei_from_milli_ether, TransactionNonce, Wei};
+    use crate::numeric::{wei_from_milli_ether, BlockNumber, TransactionNonce, Wei};
     use crate::state::{InvalidStateError, State};
+    use crate::tx::FeeEstimationPolicy;
     use assert_matches::assert_matches;
     use candid::Nat;
     use num_bigint::BigUint;
@@ -253,6 +451,33 @@ mod upgrade {
         );
     }
 
+    #[test]
+    fn should_fail_when_rpc_consensus_policy_invalid() {
+        let mut state = initial_state();
+        assert_matches!(
+            state.upgrade(UpgradeArg {
+                rpc_consensus_policy: Some(RpcConsensusPolicy {
+                    eth_get_block_by_number: Some(ConsensusStrategy::Threshold { min: 4 }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            Err(InvalidStateError::InvalidRpcConsensusPolicy(_))
+        );
+
+        let mut state = initial_state();
+        assert_matches!(
+            state.upgrade(UpgradeArg {
+                rpc_consensus_policy: Some(RpcConsensusPolicy {
+                    eth_get_transaction_receipt: Some(ConsensusStrategy::Median),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            Err(InvalidStateError::InvalidRpcConsensusPolicy(_))
+        );
+    }
+
     #[test]
     fn should_succeed() {
         use crate::endpoints::CandidBlockTag;
@@ -264,6 +489,20 @@ mod upgrade {
                 "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34".to_string(),
             ),
             ethereum_block_height: Some(CandidBlockTag::Safe),
+            rpc_consensus_policy: Some(RpcConsensusPolicy {
+                eth_get_logs: Some(ConsensusStrategy::Threshold { min: 2 }),
+                ..Default::default()
+            }),
+            minimum_deposit_amount: Some(Nat::from(1_000)),
+            disperse_contract_address: Some(
+                "0xD152f549545093347A162Dce210e7293f1452150".to_string(),
+            ),
+            ethereum_block_confirmations: Some(12),
+            ethereum_contract_address_overlap_blocks: None,
+            fee_estimation_policy: Some(FeeEstimationPolicy {
+                reward_percentiles: Some(vec![25, 50]),
+                ..Default::default()
+            }),
         };
 
         state.upgrade(upgrade_arg).expect("valid upgrade args");
@@ -278,6 +517,228 @@ mod upgrade {
             Some(Address::from_str("0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34").unwrap())
         );
         assert_eq!(state.ethereum_block_height, BlockTag::Safe);
+        assert_eq!(
+            state.rpc_consensus_policy.eth_get_logs(),
+            ConsensusStrategy::Threshold { min: 2 }
+        );
+        assert_eq!(state.minimum_deposit_amount, Wei::from(1_000_u64));
+        assert_eq!(
+            state.disperse_contract_address,
+            Some(Address::from_str("0xD152f549545093347A162Dce210e7293f1452150").unwrap())
+        );
+        assert_eq!(state.ethereum_block_confirmations, Some(12));
+        assert_eq!(
+            state.fee_estimation_policy.reward_percentiles(),
+            vec![25, 50]
+        );
+        assert_eq!(state.fee_estimation_policy.block_count(), 10);
+    }
+
+    #[test]
+    fn should_fail_when_fee_estimation_policy_invalid() {
+        for policy in [
+            FeeEstimationPolicy {
+                block_count: Some(0),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                block_count: Some(21),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                reward_percentiles: Some(vec![]),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                reward_percentiles: Some(vec![10, 20, 30, 40, 50, 60]),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                reward_percentiles: Some(vec![30, 20]),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                reward_percentiles: Some(vec![50, 101]),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                smoothing_factor_percent: Some(0),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                smoothing_factor_percent: Some(101),
+                ..Default::default()
+            },
+            FeeEstimationPolicy {
+                max_resubmission_fee_increase_percent: Some(9),
+                ..Default::default()
+            },
+        ] {
+            let mut state = initial_state();
+            assert_matches!(
+                state.upgrade(UpgradeArg {
+                    fee_estimation_policy: Some(policy),
+                    ..Default::default()
+                }),
+                Err(InvalidStateError::InvalidFeeEstimationPolicy(_))
+            );
+        }
+    }
+
+    #[test]
+    fn should_smooth_transaction_price_estimates() {
+        use crate::numeric::{GasAmount, WeiPerGas};
+        use crate::tx::TransactionPrice;
+
+        let price = |max_fee_per_gas: u64, max_priority_fee_per_gas: u64| TransactionPrice {
+            gas_limit: GasAmount::new(21_000),
+            max_fee_per_gas: WeiPerGas::from(max_fee_per_gas),
+            max_priority_fee_per_gas: WeiPerGas::from(max_priority_fee_per_gas),
+        };
+        let mut state = initial_state();
+
+        assert_eq!(
+            state.record_transaction_price_estimate(price(100, 10)),
+            price(100, 10)
+        );
+        assert_eq!(
+            state.record_transaction_price_estimate(price(200, 30)),
+            price(150, 20)
+        );
+        assert_eq!(state.last_transaction_price_estimate, Some(price(150, 20)));
+
+        state
+            .upgrade(UpgradeArg {
+                fee_estimation_policy: Some(FeeEstimationPolicy {
+                    smoothing_factor_percent: Some(100),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            state.record_transaction_price_estimate(price(50, 5)),
+            price(50, 5)
+        );
+    }
+
+    #[test]
+    fn should_switch_between_block_tag_and_confirmations() {
+        use crate::endpoints::CandidBlockTag;
+        use crate::eth_rpc::BlockSpec;
+        let mut state = initial_state();
+        state
+            .upgrade(UpgradeArg {
+                ethereum_block_height: Some(CandidBlockTag::Safe),
+                ..Default::default()
+            })
+            .expect("valid upgrade args");
+        assert_eq!(state.observed_block_tag(), BlockTag::Safe);
+        assert_eq!(
+            state.confirmed_block_number(BlockNumber::from(100_u64)),
+            BlockNumber::from(100_u64)
+        );
+        assert_eq!(state.confirmed_block(), BlockSpec::Tag(BlockTag::Safe));
+
+        state
+            .upgrade(UpgradeArg {
+                ethereum_block_confirmations: Some(12),
+                ..Default::default()
+            })
+            .expect("valid upgrade args");
+        assert_eq!(state.observed_block_tag(), BlockTag::Latest);
+        assert_eq!(
+            state.confirmed_block_number(BlockNumber::from(100_u64)),
+            BlockNumber::from(88_u64)
+        );
+        assert_eq!(
+            state.confirmed_block_number(BlockNumber::from(5_u64)),
+            BlockNumber::ZERO
+        );
+        assert_eq!(state.confirmed_block(), BlockSpec::Tag(BlockTag::Finalized));
+        state.last_observed_block_number = Some(BlockNumber::from(88_u64));
+        assert_eq!(
+            state.confirmed_block(),
+            BlockSpec::Number(BlockNumber::from(88_u64))
+        );
+
+        state
+            .upgrade(UpgradeArg {
+                ethereum_block_confirmations: Some(0),
+                ..Default::default()
+            })
+            .expect("valid upgrade args");
+        assert_eq!(state.ethereum_block_confirmations, None);
+        assert_eq!(state.observed_block_tag(), BlockTag::Safe);
+        assert_eq!(state.confirmed_block(), BlockSpec::Tag(BlockTag::Safe));
+    }
+
+    #[test]
+    fn should_keep_scraping_previous_contract_during_overlap() {
+        use crate::state::RetiredContract;
+        let old_contract = Address::from_str("0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34").unwrap();
+        let new_contract = Address::from_str("0x907b6EFc1a398fD88A8161b3cA02eEc8Eaf72ca1").unwrap();
+        let mut state = initial_state();
+        state
+            .upgrade(UpgradeArg {
+                ethereum_contract_address: Some(old_contract.to_string()),
+                ethereum_contract_address_overlap_blocks: Some(100),
+                ..Default::default()
+            })
+            .expect("valid upgrade args");
+        assert_eq!(state.retired_contracts, Default::default());
+        state.last_scraped_block_number = BlockNumber::from(1_000_u64);
+
+        state
+            .upgrade(UpgradeArg {
+                ethereum_contract_address: Some(new_contract.to_string()),
+                ethereum_contract_address_overlap_blocks: Some(100),
+                ..Default::default()
+            })
+            .expect("valid upgrade args");
+        assert_eq!(state.ethereum_contract_address, Some(new_contract));
+        assert_eq!(
+            state.retired_contracts.get(&old_contract),
+            Some(&RetiredContract {
+                last_scraped_block_number: BlockNumber::from(1_000_u64),
+                last_block_to_scrape: BlockNumber::from(1_100_u64),
+            })
+        );
+
+        state.update_last_scraped_block_number(new_contract, BlockNumber::from(1_200_u64));
+        state.update_last_scraped_block_number(old_contract, BlockNumber::from(1_050_u64));
+        assert_eq!(
+            state.last_scraped_block_number,
+            BlockNumber::from(1_200_u64)
+        );
+        assert_eq!(
+            state.retired_contracts[&old_contract].last_scraped_block_number,
+            BlockNumber::from(1_050_u64)
+        );
+
+        state.update_last_scraped_block_number(old_contract, BlockNumber::from(1_100_u64));
+        assert_eq!(state.retired_contracts, Default::default());
+        assert_eq!(
+            state.last_scraped_block_number,
+            BlockNumber::from(1_200_u64)
+        );
+    }
+
+    #[test]
+    fn should_not_retire_previous_contract_without_overlap() {
+        let mut state = initial_state();
+        for address in [
+            "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34",
+            "0x907b6EFc1a398fD88A8161b3cA02eEc8Eaf72ca1",
+        ] {
+            state
+                .upgrade(UpgradeArg {
+                    ethereum_contract_address: Some(address.to_string()),
+                    ..Default::default()
+                })
+                .expect("valid upgrade args");
+        }
+        assert_eq!(state.retired_contracts, Default::default());
     }
 
     fn initial_state() -> State {
@@ -292,6 +753,9 @@ mod upgrade {
             ethereum_block_height: Default::default(),
             minimum_withdrawal_amount: wei_from_milli_ether(10).into(),
             next_transaction_nonce: Default::default(),
+            rpc_consensus_policy: None,
+            minimum_deposit_amount: None,
+            disperse_contract_address: None,
         })
         .expect("valid init args")
     }
@@ -357,6 +821,7 @@ prop_compose! {
         ethereum_block_height in arb_block_tag(),
         minimum_withdrawal_amount in arb_nat(),
         next_transaction_nonce in arb_nat(),
+        minimum_deposit_amount in proptest::option::of(arb_nat()),
         ledger_id in arb_principal(),
         ecdsa_key_name in "[a-z_]*",
     ) -> InitArg {
@@ -368,6 +833,9 @@ prop_compose! {
             ethereum_block_height,
             minimum_withdrawal_amount,
             next_transaction_nonce,
+            rpc_consensus_policy: None,
+            minimum_deposit_amount,
+            disperse_contract_address: None,
         }
     }
 }
@@ -378,12 +846,21 @@ prop_compose! {
         ethereum_block_height in proptest::option::of(arb_block_tag()),
         minimum_withdrawal_amount in proptest::option::of(arb_nat()),
         next_transaction_nonce in proptest::option::of(arb_nat()),
+        minimum_deposit_amount in proptest::option::of(arb_nat()),
+        ethereum_block_confirmations in proptest::option::of(any::<u64>()),
+        ethereum_contract_address_overlap_blocks in proptest::option::of(any::<u64>()),
     ) -> UpgradeArg {
         UpgradeArg {
             ethereum_contract_address: contract_address.map(|addr| addr.to_string()),
             ethereum_block_height,
             minimum_withdrawal_amount,
             next_transaction_nonce,
+            rpc_consensus_policy: None,
+            minimum_deposit_amount,
+            disperse_contract_address: None,
+            ethereum_block_confirmations,
+            ethereum_contract_address_overlap_blocks,
+            fee_estimation_policy: None,
         }
     }
 }
@@ -396,6 +873,7 @@ prop_compose! {
         from_address in arb_address(),
         value in arb_checked_amount_of(),
         principal in arb_principal(),
+        subaccount in proptest::option::of(uniform32(any::<u8>())),
     ) -> ReceivedEthEvent {
         ReceivedEthEvent {
             transaction_hash,
@@ -404,6 +882,7 @@ prop_compose! {
             from_address,
             value,
             principal,
+            subaccount,
         }
     }
 }
@@ -478,6 +957,72 @@ fn arb_event_type() -> impl Strategy<Value = EventType> {
                 txhash,
             }
         }),
+        (
+            any::<u64>(),
+            arb_principal(),
+            arb_checked_amount_of(),
+            any::<u64>()
+        )
+            .prop_map(|(withdrawal_id, beneficiary, amount, refund_block_index)| {
+                EventType::FeeRefunded {
+                    withdrawal_id: withdrawal_id.into(),
+                    beneficiary,
+                    amount,
+                    refund_block_index: refund_block_index.into(),
+                }
+            }),
+        arb_event_source().prop_map(|event_source| EventType::ReorgedDeposit {
+            event_source,
+            reason: "transaction not found".to_string()
+        }),
+        (arb_principal(), arb_address(), arb_checked_amount_of()).prop_map(
+            |(caller, destination, amount)| EventType::BlockedWithdrawalAttempt {
+                caller,
+                destination,
+                amount,
+            }
+        ),
+        arb_event_source().prop_map(|event_source| EventType::FailedMint {
+            event_source,
+            reason: "ledger is stopped".to_string()
+        }),
+        (
+            arb_principal(),
+            arb_checked_amount_of(),
+            arb_checked_amount_of()
+        )
+            .prop_map(|(caller, from, to)| EventType::SkippedBlockRange {
+                caller,
+                from,
+                to
+            }),
+        (
+            arb_principal(),
+            arb_checked_amount_of(),
+            arb_checked_amount_of()
+        )
+            .prop_map(|(caller, from, to)| EventType::RescanRequested { caller, from, to }),
+        (arb_principal(), arb_event_source()).prop_map(|(caller, event_source)| {
+            EventType::RequeuedFailedMint {
+                caller,
+                event_source,
+            }
+        }),
+        (any::<u64>(), any::<u64>()).prop_map(|(withdrawal_id, reimbursement_block_index)| {
+            EventType::WithdrawalCancelled {
+                withdrawal_id: withdrawal_id.into(),
+                reimbursement_block_index: reimbursement_block_index.into(),
+            }
+        }),
+        (any::<u64>(), any::<u64>()).prop_map(|(withdrawal_id, created_at_time)| {
+            EventType::WithdrawalCancellationRequested {
+                withdrawal_id: withdrawal_id.into(),
+                created_at_time,
+            }
+        }),
+        any::<u64>().prop_map(|withdrawal_id| EventType::WithdrawalCancellationRejected {
+            withdrawal_id: withdrawal_id.into()
+        }),
     ]
 }
 
@@ -50,12 +50,13 @@ fn deserialize_json_reply() {
 
 mod eth_get_logs {
     use crate::address::Address;
-    use crate::eth_logs::ReceivedEthEvent;
+    use crate::eth_logs::{EventSourceError, ReceivedEthEvent, ReceivedEthEventError};
     use crate::eth_rpc::{FixedSizeData, LogEntry};
     use crate::numeric::{BlockNumber, LogIndex, Wei};
     use assert_matches::assert_matches;
     use candid::Principal;
     use ic_crypto_sha3::Keccak256;
+    use icrc_ledger_types::icrc1::account::Account;
     use std::str::FromStr;
 
     #[test]
@@ -138,11 +139,107 @@ mod eth_get_logs {
                 .unwrap(),
             value: Wei::from(10_000_000_000_000_000_u128),
             principal: Principal::from_str("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+            subaccount: None,
         };
 
         assert_eq!(parsed_event, expected_event);
     }
 
+    #[test]
+    fn should_have_correct_subaccount_topic() {
+        use crate::eth_logs::RECEIVED_ETH_TO_SUBACCOUNT_EVENT_TOPIC;
+
+        //must match event signature in minter.sol
+        let event_signature = "ReceivedEthToSubaccount(address,uint256,bytes32,bytes32)";
+        let topic = Keccak256::hash(event_signature);
+        assert_eq!(topic, RECEIVED_ETH_TO_SUBACCOUNT_EVENT_TOPIC)
+    }
+
+    #[test]
+    fn should_parse_received_eth_to_subaccount_event() {
+        let event = r#"{
+            "address": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+            "topics": [
+                "0x84c64df3ee15dc696580c0d8df45788b840751d6828434944ad88589c15b6171",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x09efcdab00000000000100000000000000000000000000000000000000000000"
+            ],
+            "data": "0x000000000000000000000000000000000000000000000000002386f26fc10000ff00000000000000000000000000000000000000000000000000000000000001",
+            "blockNumber": "0x3ca487",
+            "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+            "transactionIndex": "0x22",
+            "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+            "logIndex": "0x27",
+            "removed": false
+        }"#;
+        let parsed_event =
+            ReceivedEthEvent::try_from(serde_json::from_str::<LogEntry>(event).unwrap()).unwrap();
+        let mut subaccount = [0_u8; 32];
+        subaccount[0] = 0xff;
+        subaccount[31] = 0x01;
+
+        assert_eq!(parsed_event.value, Wei::from(10_000_000_000_000_000_u128));
+        assert_eq!(
+            parsed_event.account(),
+            Account {
+                owner: Principal::from_str("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+                subaccount: Some(subaccount),
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_zero_subaccount_as_default_account() {
+        let event = r#"{
+            "address": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+            "topics": [
+                "0x84c64df3ee15dc696580c0d8df45788b840751d6828434944ad88589c15b6171",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x09efcdab00000000000100000000000000000000000000000000000000000000"
+            ],
+            "data": "0x000000000000000000000000000000000000000000000000002386f26fc100000000000000000000000000000000000000000000000000000000000000000000",
+            "blockNumber": "0x3ca487",
+            "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+            "transactionIndex": "0x22",
+            "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+            "logIndex": "0x27",
+            "removed": false
+        }"#;
+        let parsed_event =
+            ReceivedEthEvent::try_from(serde_json::from_str::<LogEntry>(event).unwrap()).unwrap();
+
+        assert_eq!(parsed_event.subaccount, None);
+    }
+
+    #[test]
+    fn should_reject_subaccount_event_without_subaccount() {
+        let event = r#"{
+            "address": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+            "topics": [
+                "0x84c64df3ee15dc696580c0d8df45788b840751d6828434944ad88589c15b6171",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x09efcdab00000000000100000000000000000000000000000000000000000000"
+            ],
+            "data": "0x000000000000000000000000000000000000000000000000002386f26fc10000",
+            "blockNumber": "0x3ca487",
+            "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+            "transactionIndex": "0x22",
+            "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+            "logIndex": "0x27",
+            "removed": false
+        }"#;
+        let parsed_event =
+            ReceivedEthEvent::try_from(serde_json::from_str::<LogEntry>(event).unwrap());
+
+        assert_matches!(
+            parsed_event,
+            Err(ReceivedEthEventError::InvalidEventSource {
+                error: EventSourceError::InvalidEvent(_),
+                ..
+            })
+        );
+    }
+
     #[test]
     fn should_deserialize_address_from_32_bytes_hex_string() {
         let address_hex = FixedSizeData::from_str(
@@ -828,3 +925,101 @@ mod eth_fee_history {
         )
     }
 }
+
+mod eip_1559_transaction_price {
+    use crate::endpoints::{Eip1559FeeHistory, Eip1559TransactionPriceArg};
+    use crate::eth_rpc::FeeHistory;
+    use crate::numeric::{BlockNumber, WeiPerGas};
+    use crate::tx::{DEFAULT_FEE_HISTORY_BLOCK_COUNT, DEFAULT_FEE_HISTORY_REWARD_PERCENTILE};
+    use candid::Nat;
+
+    #[test]
+    fn should_use_defaults_when_arguments_unset() {
+        let arg = Eip1559TransactionPriceArg::default();
+
+        assert_eq!(arg.validate(), Ok(()));
+        assert_eq!(arg.block_count(), DEFAULT_FEE_HISTORY_BLOCK_COUNT);
+        assert_eq!(
+            arg.reward_percentile(),
+            DEFAULT_FEE_HISTORY_REWARD_PERCENTILE
+        );
+    }
+
+    #[test]
+    fn should_accept_valid_arguments() {
+        for (block_count, reward_percentile) in [(1, 0), (5, 50), (20, 100)] {
+            let arg = Eip1559TransactionPriceArg {
+                reward_percentile: Some(reward_percentile),
+                block_count: Some(block_count),
+            };
+
+            assert_eq!(arg.validate(), Ok(()));
+            assert_eq!(arg.block_count(), block_count);
+            assert_eq!(arg.reward_percentile(), reward_percentile);
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_block_count() {
+        for block_count in [0, 21, u8::MAX] {
+            let arg = Eip1559TransactionPriceArg {
+                reward_percentile: None,
+                block_count: Some(block_count),
+            };
+
+            assert_eq!(
+                arg.validate(),
+                Err(format!(
+                    "block_count must be between 1 and 20, got {block_count}"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_reward_percentile() {
+        for reward_percentile in [101, u8::MAX] {
+            let arg = Eip1559TransactionPriceArg {
+                reward_percentile: Some(reward_percentile),
+                block_count: None,
+            };
+
+            assert_eq!(
+                arg.validate(),
+                Err(format!(
+                    "reward_percentile must be between 0 and 100, got {reward_percentile}"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn should_return_sampled_fee_history() {
+        let fee_history = FeeHistory {
+            oldest_block: BlockNumber::new(0x10f73fc),
+            base_fee_per_gas: vec![
+                WeiPerGas::new(0x729d3f3b3),
+                WeiPerGas::new(0x766e503ea),
+                WeiPerGas::new(0x75b51b620),
+            ],
+            reward: vec![
+                vec![WeiPerGas::new(0x5f5e100)],
+                vec![WeiPerGas::new(0x55d4a80)],
+            ],
+        };
+
+        assert_eq!(
+            Eip1559FeeHistory::new(fee_history, 20),
+            Eip1559FeeHistory {
+                oldest_block: Nat::from(0x10f73fc_u64),
+                base_fee_per_gas: vec![
+                    Nat::from(0x729d3f3b3_u64),
+                    Nat::from(0x766e503ea_u64),
+                    Nat::from(0x75b51b620_u64),
+                ],
+                reward_percentile: 20,
+                reward: vec![Nat::from(0x5f5e100_u64), Nat::from(0x55d4a80_u64)],
+            }
+        );
+    }
+}
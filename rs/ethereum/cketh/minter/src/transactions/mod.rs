@@ -7,17 +7,33 @@ use crate::eth_rpc::Hash;
 use crate::eth_rpc_client::responses::TransactionReceipt;
 use crate::lifecycle::EthereumNetwork;
 use crate::map::MultiKeyMap;
-use crate::numeric::{LedgerBurnIndex, TransactionCount, TransactionNonce, Wei};
+use crate::numeric::{GasAmount, LedgerBurnIndex, TransactionCount, TransactionNonce, Wei};
 use crate::tx::{
     Eip1559TransactionRequest, FinalizedEip1559Transaction, SignedEip1559TransactionRequest,
     TransactionPrice,
 };
+use candid::Principal;
 use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{btree_map, BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::iter::zip;
+
+/// Maximum number of withdrawal requests served by a single batch transaction.
+pub const MAX_WITHDRAWALS_PER_BATCH_TRANSACTION: usize = 20;
+
+/// Gas limit of a call to `disperseEther` not counting the transfers.
+const DISPERSE_BASE_GAS_LIMIT: GasAmount = GasAmount::new(50_000);
+
+/// Additional gas limit for each transfer of a call to `disperseEther`,
+/// covering the creation of the recipient account if needed.
+const DISPERSE_GAS_LIMIT_PER_TRANSFER: GasAmount = GasAmount::new(40_000);
+
+/// Function selector of `disperseEther(address[],uint256[])`.
+const DISPERSE_ETHER_SELECTOR: [u8; 4] = [0xe6, 0x3d, 0x38, 0xed];
 
 /// Ethereum withdrawal request issued by the user.
-#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Encode, Decode)]
+#[derive(Clone, Serialize, Deserialize, Eq, PartialEq, Encode, Decode)]
 pub struct EthWithdrawalRequest {
     #[n(0)]
     pub withdrawal_amount: Wei,
@@ -25,6 +41,26 @@ pub struct EthWithdrawalRequest {
     pub destination: Address,
     #[cbor(n(2), with = "crate::cbor::id")]
     pub ledger_burn_index: LedgerBurnIndex,
+    /// The principal whose ckETH were burned to issue this request.
+    /// Unknown for requests issued before the minter started tracking it.
+    #[serde(default)]
+    #[cbor(n(3), with = "crate::cbor::principal::option", has_nil)]
+    pub from: Option<Principal>,
+}
+
+impl fmt::Debug for EthWithdrawalRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("EthWithdrawalRequest");
+        debug
+            .field("withdrawal_amount", &self.withdrawal_amount)
+            .field("destination", &self.destination)
+            .field("ledger_burn_index", &self.ledger_burn_index);
+        match &self.from {
+            Some(from) => debug.field("from", &format_args!("Some({from})")),
+            None => debug.field("from", &format_args!("None")),
+        };
+        debug.finish()
+    }
 }
 
 /// State machine holding Ethereum transactions issued by the minter.
@@ -48,6 +84,39 @@ pub struct EthTransactions {
     sent_tx: MultiKeyMap<TransactionNonce, LedgerBurnIndex, Vec<SignedEip1559TransactionRequest>>,
     finalized_tx: MultiKeyMap<TransactionNonce, LedgerBurnIndex, FinalizedEip1559Transaction>,
     next_nonce: TransactionNonce,
+    /// All withdrawal requests that were not cancelled, whatever their status.
+    #[serde(default)]
+    withdrawal_requests_by_index: BTreeMap<LedgerBurnIndex, EthWithdrawalRequest>,
+    /// Burn indices of the withdrawal requests issued by each principal.
+    #[serde(default)]
+    withdrawals_by_principal: BTreeMap<Principal, BTreeSet<LedgerBurnIndex>>,
+    /// Transaction fees to refund for finalized transactions that cost less than charged.
+    #[serde(default)]
+    fee_refunds: BTreeMap<LedgerBurnIndex, FeeRefund>,
+    /// Withdrawal requests served by a single batch transaction,
+    /// indexed by the burn index of the first request of the batch
+    /// which identifies the transaction.
+    #[serde(default)]
+    batched_withdrawals: BTreeMap<LedgerBurnIndex, Vec<EthWithdrawalRequest>>,
+    /// Burn index identifying the batch transaction of each batched withdrawal request.
+    #[serde(default)]
+    batch_transaction_index: BTreeMap<LedgerBurnIndex, LedgerBurnIndex>,
+    /// Withdrawal requests cancelled by their issuer whose reimbursement was not minted yet.
+    #[serde(default)]
+    withdrawal_cancellations: BTreeMap<LedgerBurnIndex, WithdrawalCancellation>,
+}
+
+/// Part of the transaction fee charged to the issuer of a withdrawal request
+/// that was not consumed by the finalized transaction.
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct FeeRefund {
+    pub ledger_burn_index: LedgerBurnIndex,
+    pub beneficiary: Principal,
+    pub amount: Wei,
+    /// Time of the ledger transaction minting the refund, fixed on the first attempt
+    /// so that the ledger deduplicates retried refunds.
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -70,6 +139,29 @@ pub enum ResubmitTransaction {
     ToSend(SignedEip1559TransactionRequest),
 }
 
+/// A withdrawal request that its issuer cancelled and whose withdrawal amount
+/// is being reimbursed.
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct WithdrawalCancellation {
+    pub request: EthWithdrawalRequest,
+    /// Position of the request in the queue when it was cancelled,
+    /// so that it can be restored if the ledger rejects the reimbursement.
+    pub position: usize,
+    /// Time of the ledger transaction minting the reimbursement, fixed when the request
+    /// is cancelled so that the ledger deduplicates retried reimbursements.
+    pub created_at_time: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CancelWithdrawalRequestError {
+    /// There is no withdrawal request with that ledger burn index.
+    NotFound,
+    /// The withdrawal request was issued by another principal.
+    NotOwner,
+    /// A transaction was already created for the withdrawal request.
+    TransactionAlreadyCreated,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ResubmitTransactionError {
     InsufficientTransactionAmount {
@@ -89,6 +181,12 @@ impl EthTransactions {
             sent_tx: MultiKeyMap::default(),
             finalized_tx: MultiKeyMap::default(),
             next_nonce,
+            withdrawal_requests_by_index: BTreeMap::new(),
+            withdrawals_by_principal: BTreeMap::new(),
+            fee_refunds: BTreeMap::new(),
+            batched_withdrawals: BTreeMap::new(),
+            batch_transaction_index: BTreeMap::new(),
+            withdrawal_cancellations: BTreeMap::new(),
         }
     }
 
@@ -113,9 +211,57 @@ impl EthTransactions {
         {
             panic!("BUG: duplicate ledger burn index {burn_index}");
         }
+        self.index_withdrawal_request(&request);
         self.withdrawal_requests.push_back(request);
     }
 
+    /// Rebuild the withdrawal request indices from the pending and batched withdrawal requests.
+    /// Needed after upgrading from a minter version that did not maintain these indices.
+    /// Requests that were already consumed into a non-batch transaction are no longer known
+    /// and cannot be indexed.
+    pub fn rebuild_withdrawal_indices(&mut self) {
+        let requests: Vec<_> = self
+            .withdrawal_requests
+            .iter()
+            .chain(self.batched_withdrawals.values().flatten())
+            .filter(|r| {
+                !self
+                    .withdrawal_requests_by_index
+                    .contains_key(&r.ledger_burn_index)
+            })
+            .cloned()
+            .collect();
+        for request in requests {
+            self.index_withdrawal_request(&request);
+        }
+    }
+
+    fn index_withdrawal_request(&mut self, request: &EthWithdrawalRequest) {
+        if let Some(from) = request.from {
+            self.withdrawals_by_principal
+                .entry(from)
+                .or_default()
+                .insert(request.ledger_burn_index);
+        }
+        self.withdrawal_requests_by_index
+            .insert(request.ledger_burn_index, request.clone());
+    }
+
+    fn unindex_withdrawal_request(&mut self, request: &EthWithdrawalRequest) {
+        self.withdrawal_requests_by_index
+            .remove(&request.ledger_burn_index);
+        if let Some(from) = request.from {
+            if let btree_map::Entry::Occupied(mut withdrawals) =
+                self.withdrawals_by_principal.entry(from)
+            {
+                withdrawals.get_mut().remove(&request.ledger_burn_index);
+                if withdrawals.get().is_empty() {
+                    withdrawals.remove();
+                }
+            }
+        }
+    }
+
     /// Move an existing withdrawal request to the back of the queue.
     pub fn reschedule_withdrawal_request(&mut self, request: EthWithdrawalRequest) {
         assert_eq!(
@@ -131,6 +277,118 @@ impl EthTransactions {
         self.record_withdrawal_request(request);
     }
 
+    /// Check that `caller` can cancel the withdrawal request with the given burn index,
+    /// i.e. that they issued it and that no transaction was created for it yet.
+    /// A request whose cancellation is already in progress can be cancelled again
+    /// by its issuer, to retry the reimbursement.
+    pub fn validate_withdrawal_cancellation(
+        &self,
+        ledger_burn_index: LedgerBurnIndex,
+        caller: Principal,
+    ) -> Result<(), CancelWithdrawalRequestError> {
+        let request = match self
+            .withdrawal_requests
+            .iter()
+            .find(|r| r.ledger_burn_index == ledger_burn_index)
+            .or_else(|| {
+                self.withdrawal_cancellations
+                    .get(&ledger_burn_index)
+                    .map(|cancellation| &cancellation.request)
+            }) {
+            Some(request) => request,
+            None => {
+                return match self.transaction_status(&ledger_burn_index) {
+                    RetrieveEthStatus::NotFound => Err(CancelWithdrawalRequestError::NotFound),
+                    _ => Err(CancelWithdrawalRequestError::TransactionAlreadyCreated),
+                };
+            }
+        };
+        if request.from != Some(caller) {
+            return Err(CancelWithdrawalRequestError::NotOwner);
+        }
+        Ok(())
+    }
+
+    /// Record that the issuer of the withdrawal request with the given burn index cancelled it.
+    /// The request is removed from the queue until its reimbursement is either minted
+    /// (see [`Self::record_cancelled_withdrawal_request`]) or rejected by the ledger
+    /// (see [`Self::record_rejected_withdrawal_cancellation`]).
+    pub fn record_withdrawal_cancellation(
+        &mut self,
+        ledger_burn_index: LedgerBurnIndex,
+        created_at_time: u64,
+    ) {
+        let (position, request) = self
+            .withdrawal_requests
+            .iter()
+            .enumerate()
+            .find(|(_, r)| r.ledger_burn_index == ledger_burn_index)
+            .map(|(position, request)| (position, request.clone()))
+            .unwrap_or_else(|| {
+                panic!("BUG: no withdrawal request with ledger burn index {ledger_burn_index}")
+            });
+        self.remove_withdrawal_request(&request);
+        self.unindex_withdrawal_request(&request);
+        self.withdrawal_cancellations.insert(
+            ledger_burn_index,
+            WithdrawalCancellation {
+                request,
+                position,
+                created_at_time,
+            },
+        );
+    }
+
+    pub fn withdrawal_cancellation(
+        &self,
+        ledger_burn_index: &LedgerBurnIndex,
+    ) -> Option<&WithdrawalCancellation> {
+        self.withdrawal_cancellations.get(ledger_burn_index)
+    }
+
+    /// Record that the ledger rejected the reimbursement of the cancelled withdrawal request
+    /// with the given burn index, which puts the request back at its original position in the queue.
+    pub fn record_rejected_withdrawal_cancellation(&mut self, ledger_burn_index: LedgerBurnIndex) {
+        let WithdrawalCancellation {
+            request, position, ..
+        } = self
+            .withdrawal_cancellations
+            .remove(&ledger_burn_index)
+            .unwrap_or_else(|| {
+                panic!("BUG: no withdrawal cancellation for ledger burn index {ledger_burn_index}")
+            });
+        self.restore_withdrawal_request(request, position);
+    }
+
+    fn restore_withdrawal_request(&mut self, request: EthWithdrawalRequest, position: usize) {
+        assert!(
+            !self
+                .withdrawal_requests
+                .iter()
+                .any(|r| r.ledger_burn_index == request.ledger_burn_index),
+            "BUG: withdrawal request with ledger burn index {} is already queued",
+            request.ledger_burn_index
+        );
+        self.index_withdrawal_request(&request);
+        let position = position.min(self.withdrawal_requests.len());
+        self.withdrawal_requests.insert(position, request);
+    }
+
+    /// Record that the withdrawal request with the given burn index was cancelled
+    /// and its amount reimbursed. Removes the request from the queue if it is still there.
+    pub fn record_cancelled_withdrawal_request(&mut self, ledger_burn_index: LedgerBurnIndex) {
+        self.withdrawal_cancellations.remove(&ledger_burn_index);
+        if let Some(request) = self
+            .withdrawal_requests
+            .iter()
+            .find(|r| r.ledger_burn_index == ledger_burn_index)
+            .cloned()
+        {
+            self.remove_withdrawal_request(&request);
+            self.unindex_withdrawal_request(&request);
+        }
+    }
+
     pub fn record_created_transaction(
         &mut self,
         withdrawal_request: EthWithdrawalRequest,
@@ -162,6 +420,50 @@ impl EthTransactions {
         );
     }
 
+    /// Records a transaction serving all the given withdrawal requests at once.
+    /// The transaction is identified by the burn index of the first request.
+    pub fn record_created_batch_transaction(
+        &mut self,
+        withdrawal_requests: Vec<EthWithdrawalRequest>,
+        transaction: Eip1559TransactionRequest,
+    ) {
+        let first_request = withdrawal_requests
+            .first()
+            .expect("BUG: empty batch of withdrawal requests");
+        let batch_index = first_request.ledger_burn_index;
+        let mut total_withdrawal_amount = Wei::ZERO;
+        for request in &withdrawal_requests {
+            assert!(
+                self.withdrawal_requests.contains(request),
+                "BUG: withdrawal request not found"
+            );
+            total_withdrawal_amount = total_withdrawal_amount
+                .checked_add(request.withdrawal_amount)
+                .expect("BUG: overflow when summing withdrawal amounts");
+        }
+        assert!(
+            total_withdrawal_amount > transaction.amount,
+            "BUG: transaction amount should be the withdrawal amounts deducted from transaction fees"
+        );
+        let nonce = self.next_nonce;
+        assert_eq!(transaction.nonce, nonce, "BUG: transaction nonce mismatch");
+        self.next_nonce = self
+            .next_nonce
+            .checked_increment()
+            .expect("Transaction nonce overflow");
+        for request in &withdrawal_requests {
+            self.remove_withdrawal_request(request);
+            self.batch_transaction_index
+                .insert(request.ledger_burn_index, batch_index);
+        }
+        self.batched_withdrawals
+            .insert(batch_index, withdrawal_requests);
+        assert_eq!(
+            self.created_tx.try_insert(nonce, batch_index, transaction),
+            Ok(())
+        );
+    }
+
     pub fn record_signed_transaction(
         &mut self,
         signed_transaction: SignedEip1559TransactionRequest,
@@ -215,6 +517,7 @@ impl EthTransactions {
     /// with nonces greater than the latest mined transaction nonce:
     /// * the resubmitted transaction will need to be re-signed if its transaction fee was increased
     /// * the resubmitted transaction can be resent as is if its transaction fee was not increased
+    /// * the fees of a re-signed transaction are increased by at most `max_fee_increase_percent`
     /// We stop on the first error since if a transaction with nonce n could not be resubmitted
     /// (e.g., the transaction amount does not cover the new fees),
     /// then the next transactions with nonces n+1, n+2, ... are blocked anyway
@@ -223,6 +526,7 @@ impl EthTransactions {
         &self,
         latest_transaction_count: TransactionCount,
         current_transaction_price: TransactionPrice,
+        max_fee_increase_percent: u16,
     ) -> Vec<Result<ResubmitTransaction, ResubmitTransactionError>> {
         // If transaction count at block height H is c > 0, then transactions with nonces
         // 0, 1, ..., c - 1 were mined. If transaction count is 0, then no transactions were mined.
@@ -240,8 +544,34 @@ impl EthTransactions {
             let last_tx_max_fee = last_tx_price.max_transaction_fee();
             if last_tx_price.is_fee_increased(&current_transaction_price) {
                 let new_tx_price = last_tx_price
-                    .increase_by_10_percent()
-                    .max(current_transaction_price.clone());
+                    .resubmission_price(&current_transaction_price, max_fee_increase_percent);
+                if let Some(requests) = self.batched_withdrawals.get(burn_index) {
+                    match batch_transaction_amounts(requests, &new_tx_price) {
+                        Ok(amounts) => {
+                            let new_tx = Eip1559TransactionRequest {
+                                max_priority_fee_per_gas: new_tx_price.max_priority_fee_per_gas,
+                                max_fee_per_gas: new_tx_price.max_fee_per_gas,
+                                gas_limit: new_tx_price.gas_limit,
+                                amount: sum(&amounts),
+                                data: encode_disperse_ether(requests, &amounts),
+                                ..last_tx
+                            };
+                            transactions_to_resubmit.push(Ok(ResubmitTransaction::ToSign(new_tx)));
+                            continue;
+                        }
+                        Err(_) => {
+                            transactions_to_resubmit.push(Err(
+                                ResubmitTransactionError::InsufficientTransactionAmount {
+                                    ledger_burn_index: *burn_index,
+                                    transaction_nonce: *nonce,
+                                    transaction_amount: last_tx.amount,
+                                    max_transaction_fee: new_tx_price.max_transaction_fee(),
+                                },
+                            ));
+                            return transactions_to_resubmit;
+                        }
+                    }
+                }
                 let new_amount = match last_tx.amount.checked_sub(
                     new_tx_price
                         .max_transaction_fee()
@@ -369,6 +699,14 @@ impl EthTransactions {
             &mut self.signed_tx,
             &nonce,
         );
+        for refund in self.fee_refunds_for(ledger_burn_index, &finalized_tx) {
+            let refund_index = refund.ledger_burn_index;
+            assert_eq!(
+                self.fee_refunds.insert(refund_index, refund),
+                None,
+                "BUG: duplicate fee refund for ledger burn index {refund_index}"
+            );
+        }
         assert_eq!(
             self.finalized_tx
                 .try_insert(nonce, ledger_burn_index, finalized_tx),
@@ -376,6 +714,101 @@ impl EthTransactions {
         );
     }
 
+    /// The transaction fee charged to the issuer of the withdrawal request is the difference
+    /// between the withdrawal amount and the transaction amount.
+    /// Returns the part of that fee that was not consumed by the finalized transaction, if any.
+    /// Requests whose issuer is unknown are not refunded.
+    fn fee_refund(
+        &self,
+        ledger_burn_index: LedgerBurnIndex,
+        finalized_tx: &FinalizedEip1559Transaction,
+    ) -> Option<FeeRefund> {
+        let request = self.withdrawal_requests_by_index.get(&ledger_burn_index)?;
+        let charged_fee = request
+            .withdrawal_amount
+            .checked_sub(*finalized_tx.transaction_amount())
+            .expect("BUG: transaction amount cannot exceed the withdrawal amount");
+        let amount = charged_fee.checked_sub(finalized_tx.effective_transaction_fee())?;
+        if amount == Wei::ZERO {
+            return None;
+        }
+        Some(FeeRefund {
+            ledger_burn_index,
+            beneficiary: request.from?,
+            amount,
+            created_at_time: None,
+        })
+    }
+
+    /// Returns the fee refunds of all the withdrawal requests served by the finalized transaction.
+    /// The effective transaction fee of a batch transaction is split evenly between its requests.
+    fn fee_refunds_for(
+        &self,
+        ledger_burn_index: LedgerBurnIndex,
+        finalized_tx: &FinalizedEip1559Transaction,
+    ) -> Vec<FeeRefund> {
+        let requests = match self.batched_withdrawals.get(&ledger_burn_index) {
+            Some(requests) => requests,
+            None => {
+                return self
+                    .fee_refund(ledger_burn_index, finalized_tx)
+                    .into_iter()
+                    .collect()
+            }
+        };
+        let amounts = batch_transaction_amounts(requests, &finalized_tx.transaction_price())
+            .expect("BUG: finalized batch transaction must cover its transaction fee");
+        let effective_fee_share = finalized_tx
+            .effective_transaction_fee()
+            .checked_div_ceil(requests.len() as u64)
+            .expect("BUG: batch of withdrawal requests cannot be empty");
+        zip(requests, amounts)
+            .filter_map(|(request, amount)| {
+                let charged_fee = request
+                    .withdrawal_amount
+                    .checked_sub(amount)
+                    .expect("BUG: transaction amount cannot exceed the withdrawal amount");
+                let refund = charged_fee.checked_sub(effective_fee_share)?;
+                let beneficiary = request.from?;
+                (refund > Wei::ZERO).then_some(FeeRefund {
+                    ledger_burn_index: request.ledger_burn_index,
+                    beneficiary,
+                    amount: refund,
+                    created_at_time: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Remove the fee refund for the given burn index after it was minted on the ledger.
+    pub fn record_fee_refund(&mut self, ledger_burn_index: LedgerBurnIndex) -> FeeRefund {
+        self.fee_refunds
+            .remove(&ledger_burn_index)
+            .unwrap_or_else(|| {
+                panic!("BUG: no fee refund for ledger burn index {ledger_burn_index}")
+            })
+    }
+
+    /// Returns the time of the ledger transaction minting the fee refund for the given burn index,
+    /// setting it to `now` if the refund was never attempted before.
+    pub fn fee_refund_created_at_time(
+        &mut self,
+        ledger_burn_index: LedgerBurnIndex,
+        now: u64,
+    ) -> u64 {
+        let refund = self
+            .fee_refunds
+            .get_mut(&ledger_burn_index)
+            .unwrap_or_else(|| {
+                panic!("BUG: no fee refund for ledger burn index {ledger_burn_index}")
+            });
+        *refund.created_at_time.get_or_insert(now)
+    }
+
+    pub fn fee_refunds_iter(&self) -> impl Iterator<Item = &FeeRefund> {
+        self.fee_refunds.values()
+    }
+
     pub fn transaction_status(&self, burn_index: &LedgerBurnIndex) -> RetrieveEthStatus {
         if self
             .withdrawal_requests
@@ -385,6 +818,11 @@ impl EthTransactions {
             return RetrieveEthStatus::Pending;
         }
 
+        let burn_index = self
+            .batch_transaction_index
+            .get(burn_index)
+            .unwrap_or(burn_index);
+
         if self.created_tx.contains_alt(burn_index) {
             return RetrieveEthStatus::TxCreated;
         }
@@ -406,6 +844,33 @@ impl EthTransactions {
         RetrieveEthStatus::NotFound
     }
 
+    /// Returns the withdrawal requests issued by the given principal together with their status,
+    /// most recent first.
+    pub fn withdrawals_of(
+        &self,
+        principal: &Principal,
+    ) -> impl Iterator<Item = (&EthWithdrawalRequest, RetrieveEthStatus)> + '_ {
+        self.withdrawals_by_principal
+            .get(principal)
+            .into_iter()
+            .flat_map(|burn_indices| burn_indices.iter().rev())
+            .map(|burn_index| {
+                let request = self
+                    .withdrawal_requests_by_index
+                    .get(burn_index)
+                    .expect("BUG: missing withdrawal request for indexed burn index");
+                (request, self.transaction_status(burn_index))
+            })
+    }
+
+    /// Returns the number of withdrawal requests issued by the given principal.
+    pub fn withdrawal_count_of(&self, principal: &Principal) -> usize {
+        self.withdrawals_by_principal
+            .get(principal)
+            .map(BTreeSet::len)
+            .unwrap_or_default()
+    }
+
     pub fn withdrawal_requests_batch(&self, batch_size: usize) -> Vec<EthWithdrawalRequest> {
         // TODO FI-948: maybe look ahead at the size of created_tx and adapt the batch size accordingly
         // to ensure that at each state we do not process more that batch size
@@ -476,6 +941,7 @@ impl EthTransactions {
             && self.created_tx.is_empty()
             && self.signed_tx.is_empty()
             && self.sent_tx.is_empty()
+            && self.fee_refunds.is_empty()
     }
 
     fn remove_withdrawal_request(&mut self, request: &EthWithdrawalRequest) {
@@ -557,11 +1023,111 @@ pub fn create_transaction(
     })
 }
 
+/// Creates a single EIP-1559 transaction serving all the given withdrawal requests
+/// by calling `disperseEther` on the given disperse contract.
+/// The transaction fee is split evenly between the requests
+/// and deducted from their withdrawal amounts.
+///
+/// # Errors
+/// * `CreateTransactionError::InsufficientAmount` if the withdrawal amount of a request
+///    does not cover its share of the transaction fee.
+pub fn create_batch_transaction(
+    withdrawal_requests: &[EthWithdrawalRequest],
+    nonce: TransactionNonce,
+    transaction_price: TransactionPrice,
+    ethereum_network: EthereumNetwork,
+    disperse_contract_address: Address,
+) -> Result<Eip1559TransactionRequest, CreateTransactionError> {
+    assert!(
+        !withdrawal_requests.is_empty(),
+        "BUG: empty batch of withdrawal requests"
+    );
+    let transaction_price = TransactionPrice {
+        gas_limit: batch_transaction_gas_limit(withdrawal_requests.len()),
+        ..transaction_price
+    };
+    let amounts = batch_transaction_amounts(withdrawal_requests, &transaction_price)?;
+    Ok(Eip1559TransactionRequest {
+        chain_id: ethereum_network.chain_id(),
+        nonce,
+        max_priority_fee_per_gas: transaction_price.max_priority_fee_per_gas,
+        max_fee_per_gas: transaction_price.max_fee_per_gas,
+        gas_limit: transaction_price.gas_limit,
+        destination: disperse_contract_address,
+        amount: sum(&amounts),
+        data: encode_disperse_ether(withdrawal_requests, &amounts),
+        access_list: Default::default(),
+    })
+}
+
+fn batch_transaction_gas_limit(num_transfers: usize) -> GasAmount {
+    DISPERSE_GAS_LIMIT_PER_TRANSFER
+        .checked_mul(num_transfers as u64)
+        .and_then(|gas_limit| gas_limit.checked_add(DISPERSE_BASE_GAS_LIMIT))
+        .expect("BUG: overflow when computing batch transaction gas limit")
+}
+
+/// Amounts transferred to the recipient of each withdrawal request,
+/// where each request pays an equal share of the maximum transaction fee.
+fn batch_transaction_amounts(
+    withdrawal_requests: &[EthWithdrawalRequest],
+    transaction_price: &TransactionPrice,
+) -> Result<Vec<Wei>, CreateTransactionError> {
+    let max_transaction_fee = transaction_price.max_transaction_fee();
+    let max_transaction_fee_share = max_transaction_fee
+        .checked_div_ceil(withdrawal_requests.len() as u64)
+        .expect("BUG: batch of withdrawal requests cannot be empty");
+    withdrawal_requests
+        .iter()
+        .map(|request| {
+            request
+                .withdrawal_amount
+                .checked_sub(max_transaction_fee_share)
+                .filter(|amount| amount > &Wei::ZERO)
+                .ok_or(CreateTransactionError::InsufficientAmount {
+                    ledger_burn_index: request.ledger_burn_index,
+                    withdrawal_amount: request.withdrawal_amount,
+                    max_transaction_fee: max_transaction_fee_share,
+                })
+        })
+        .collect()
+}
+
+fn sum(amounts: &[Wei]) -> Wei {
+    amounts.iter().fold(Wei::ZERO, |total, amount| {
+        total
+            .checked_add(*amount)
+            .expect("BUG: overflow when summing transaction amounts")
+    })
+}
+
+/// ABI-encodes a call to `disperseEther(address[] recipients, uint256[] values)`.
+fn encode_disperse_ether(withdrawal_requests: &[EthWithdrawalRequest], amounts: &[Wei]) -> Vec<u8> {
+    assert_eq!(withdrawal_requests.len(), amounts.len());
+    let word = |value: u64| Wei::from(value).to_be_bytes();
+    let len = withdrawal_requests.len() as u64;
+    let mut data = DISPERSE_ETHER_SELECTOR.to_vec();
+    // Offsets of the two dynamic arrays from the start of the arguments.
+    data.extend_from_slice(&word(2 * 32));
+    data.extend_from_slice(&word(2 * 32 + (len + 1) * 32));
+    data.extend_from_slice(&word(len));
+    for request in withdrawal_requests {
+        data.extend_from_slice(&[0_u8; 12]);
+        data.extend_from_slice(request.destination.as_ref());
+    }
+    data.extend_from_slice(&word(len));
+    for amount in amounts {
+        data.extend_from_slice(&amount.to_be_bytes());
+    }
+    data
+}
+
 /// Returns true if the two transactions are equal ignoring the transaction fee and amount.
 /// The following fields are ignored:
 /// * `max_fee_per_gas`
 /// * `max_priority_fee_per_gas`
 /// * `amount` (because the cost of the transaction is paid by the beneficiary and so influencing the fee does influence the transaction amount)
+/// * `data` (because the amounts of a batch transaction are encoded in the call to the disperse contract)
 fn equal_ignoring_fee_and_amount(
     lhs: &Eip1559TransactionRequest,
     rhs: &Eip1559TransactionRequest,
@@ -570,6 +1136,7 @@ fn equal_ignoring_fee_and_amount(
     rhs_with_lhs_fee_and_amount.max_fee_per_gas = lhs.max_fee_per_gas;
     rhs_with_lhs_fee_and_amount.max_priority_fee_per_gas = lhs.max_priority_fee_per_gas;
     rhs_with_lhs_fee_and_amount.amount = lhs.amount;
+    rhs_with_lhs_fee_and_amount.data = lhs.data.clone();
 
     lhs == &rhs_with_lhs_fee_and_amount
 }
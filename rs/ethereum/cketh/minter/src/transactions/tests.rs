@@ -79,6 +79,273 @@ mod eth_transactions {
         }
     }
 
+    mod cancel_withdrawal_request {
+        use super::*;
+        use crate::transactions::tests::{create_and_record_transaction, owner, transaction_price};
+        use crate::transactions::{
+            CancelWithdrawalRequestError, EthWithdrawalRequest, WithdrawalCancellation,
+        };
+        use candid::Principal;
+
+        #[test]
+        fn should_cancel_pending_withdrawal_request() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let first_request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            let second_request = withdrawal_request_with_index(LedgerBurnIndex::new(16));
+            transactions.record_withdrawal_request(first_request.clone());
+            transactions.record_withdrawal_request(second_request.clone());
+
+            assert_eq!(
+                transactions.validate_withdrawal_cancellation(
+                    first_request.ledger_burn_index,
+                    owner(&first_request)
+                ),
+                Ok(())
+            );
+            transactions.record_withdrawal_cancellation(first_request.ledger_burn_index, 1_000);
+
+            assert_eq!(
+                transactions.withdrawal_cancellation(&first_request.ledger_burn_index),
+                Some(&WithdrawalCancellation {
+                    request: first_request.clone(),
+                    position: 0,
+                    created_at_time: 1_000,
+                })
+            );
+            assert_eq!(
+                transactions.withdrawal_requests_batch(5),
+                vec![second_request]
+            );
+            // The issuer may cancel again to retry the reimbursement.
+            assert_eq!(
+                transactions.validate_withdrawal_cancellation(
+                    first_request.ledger_burn_index,
+                    owner(&first_request)
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                transactions.validate_withdrawal_cancellation(
+                    first_request.ledger_burn_index,
+                    Principal::management_canister()
+                ),
+                Err(CancelWithdrawalRequestError::NotOwner)
+            );
+        }
+
+        #[test]
+        fn should_not_cancel_unknown_withdrawal_request() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            transactions.record_withdrawal_request(request.clone());
+
+            assert_eq!(
+                transactions
+                    .validate_withdrawal_cancellation(LedgerBurnIndex::new(16), owner(&request)),
+                Err(CancelWithdrawalRequestError::NotFound)
+            );
+        }
+
+        #[test]
+        fn should_not_cancel_withdrawal_request_of_other_principal() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            transactions.record_withdrawal_request(request.clone());
+
+            assert_eq!(
+                transactions.validate_withdrawal_cancellation(
+                    request.ledger_burn_index,
+                    Principal::management_canister()
+                ),
+                Err(CancelWithdrawalRequestError::NotOwner)
+            );
+            assert_eq!(transactions.withdrawal_requests_batch(5), vec![request]);
+        }
+
+        #[test]
+        fn should_not_cancel_withdrawal_request_with_created_transaction() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            transactions.record_withdrawal_request(request.clone());
+            create_and_record_transaction(&mut transactions, request.clone(), transaction_price());
+
+            assert_eq!(
+                transactions
+                    .validate_withdrawal_cancellation(request.ledger_burn_index, owner(&request)),
+                Err(CancelWithdrawalRequestError::TransactionAlreadyCreated)
+            );
+        }
+
+        #[test]
+        fn should_not_cancel_withdrawal_request_with_unknown_issuer() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            let caller = owner(&request);
+            let request = EthWithdrawalRequest {
+                from: None,
+                ..request
+            };
+            transactions.record_withdrawal_request(request.clone());
+
+            assert_eq!(
+                transactions.validate_withdrawal_cancellation(request.ledger_burn_index, caller),
+                Err(CancelWithdrawalRequestError::NotOwner)
+            );
+            assert_eq!(transactions.withdrawal_requests_batch(5), vec![request]);
+        }
+
+        #[test]
+        fn should_restore_cancelled_withdrawal_request_at_original_position() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let requests: Vec<_> = (15..18)
+                .map(|index| withdrawal_request_with_index(LedgerBurnIndex::new(index)))
+                .collect();
+            for request in &requests {
+                transactions.record_withdrawal_request(request.clone());
+            }
+
+            transactions.record_withdrawal_cancellation(requests[1].ledger_burn_index, 1_000);
+            assert_eq!(
+                transactions
+                    .withdrawal_cancellation(&requests[1].ledger_burn_index)
+                    .map(|cancellation| cancellation.position),
+                Some(1)
+            );
+            assert_eq!(transactions.withdrawal_count_of(&owner(&requests[1])), 2);
+
+            transactions.record_rejected_withdrawal_cancellation(requests[1].ledger_burn_index);
+
+            assert_eq!(transactions.withdrawal_requests_batch(5), requests);
+            assert_eq!(transactions.withdrawal_count_of(&owner(&requests[1])), 3);
+            assert_eq!(
+                transactions.withdrawal_cancellation(&requests[1].ledger_burn_index),
+                None
+            );
+        }
+
+        #[test]
+        fn should_remove_withdrawal_request_when_recording_cancellation() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let first_request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            let second_request = withdrawal_request_with_index(LedgerBurnIndex::new(16));
+            transactions.record_withdrawal_request(first_request.clone());
+            transactions.record_withdrawal_request(second_request.clone());
+
+            transactions.record_withdrawal_cancellation(first_request.ledger_burn_index, 1_000);
+            transactions.record_cancelled_withdrawal_request(first_request.ledger_burn_index);
+            // Recording the same cancellation again is a no-op.
+            transactions.record_cancelled_withdrawal_request(first_request.ledger_burn_index);
+
+            assert_eq!(
+                transactions.withdrawal_requests_batch(5),
+                vec![second_request]
+            );
+            assert_eq!(
+                transactions.withdrawal_cancellation(&first_request.ledger_burn_index),
+                None
+            );
+            assert_eq!(
+                transactions.transaction_status(&first_request.ledger_burn_index),
+                crate::endpoints::RetrieveEthStatus::NotFound
+            );
+            assert_eq!(transactions.withdrawal_count_of(&owner(&first_request)), 1);
+        }
+    }
+
+    mod withdrawals_of {
+        use super::*;
+        use crate::endpoints::RetrieveEthStatus;
+        use crate::transactions::tests::{create_and_record_transaction, owner, transaction_price};
+        use crate::transactions::EthWithdrawalRequest;
+        use candid::Principal;
+
+        #[test]
+        fn should_be_empty_for_unknown_principal() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            transactions
+                .record_withdrawal_request(withdrawal_request_with_index(LedgerBurnIndex::new(15)));
+
+            let principal = Principal::management_canister();
+            assert_eq!(transactions.withdrawals_of(&principal).count(), 0);
+            assert_eq!(transactions.withdrawal_count_of(&principal), 0);
+        }
+
+        #[test]
+        fn should_list_withdrawals_most_recent_first_with_status() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let first_request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            let second_request = withdrawal_request_with_index(LedgerBurnIndex::new(16));
+            let other_request = EthWithdrawalRequest {
+                from: Some(Principal::management_canister()),
+                ..withdrawal_request_with_index(LedgerBurnIndex::new(17))
+            };
+            transactions.record_withdrawal_request(first_request.clone());
+            transactions.record_withdrawal_request(second_request.clone());
+            transactions.record_withdrawal_request(other_request);
+            create_and_record_transaction(
+                &mut transactions,
+                first_request.clone(),
+                transaction_price(),
+            );
+
+            let withdrawals: Vec<_> = transactions
+                .withdrawals_of(&owner(&first_request))
+                .map(|(request, status)| (request.clone(), status))
+                .collect();
+
+            assert_eq!(
+                withdrawals,
+                vec![
+                    (second_request, RetrieveEthStatus::Pending),
+                    (first_request.clone(), RetrieveEthStatus::TxCreated),
+                ]
+            );
+            assert_eq!(transactions.withdrawal_count_of(&owner(&first_request)), 2);
+        }
+
+        #[test]
+        fn should_not_list_cancelled_withdrawal() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            transactions.record_withdrawal_request(request.clone());
+
+            transactions.record_withdrawal_cancellation(request.ledger_burn_index, 1_000);
+
+            assert_eq!(transactions.withdrawals_of(&owner(&request)).count(), 0);
+            assert_eq!(transactions.withdrawal_count_of(&owner(&request)), 0);
+        }
+
+        #[test]
+        fn should_rebuild_indices_of_state_without_them() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let first_request = withdrawal_request_with_index(LedgerBurnIndex::new(15));
+            let second_request = withdrawal_request_with_index(LedgerBurnIndex::new(16));
+            transactions.record_withdrawal_request(first_request.clone());
+            transactions.record_withdrawal_request(second_request.clone());
+
+            let mut encoded = ciborium::Value::serialized(&transactions).unwrap();
+            encoded.as_map_mut().unwrap().retain(|(key, _)| {
+                !matches!(
+                    key.as_text(),
+                    Some("withdrawal_requests_by_index" | "withdrawals_by_principal")
+                )
+            });
+            let mut decoded: EthTransactions = encoded.deserialized().unwrap();
+            assert_eq!(decoded.withdrawal_count_of(&owner(&first_request)), 0);
+
+            decoded.rebuild_withdrawal_indices();
+
+            assert_eq!(decoded, transactions);
+            assert_eq!(
+                decoded
+                    .withdrawals_of(&owner(&first_request))
+                    .map(|(request, _status)| request.clone())
+                    .collect::<Vec<_>>(),
+                vec![second_request, first_request]
+            );
+        }
+    }
+
     mod withdrawal_requests_batch {
         use super::*;
         use proptest::proptest;
@@ -534,6 +801,7 @@ mod eth_transactions {
         use crate::transactions::tests::{
             create_and_record_signed_transaction, create_and_record_transaction,
             create_and_record_withdrawal_request, transaction_price, withdrawal_request_with_index,
+            MAX_FEE_INCREASE_PERCENT,
         };
         use crate::transactions::{EthTransactions, ResubmitTransaction, ResubmitTransactionError};
         use crate::tx::{Eip1559TransactionRequest, TransactionPrice};
@@ -541,8 +809,11 @@ mod eth_transactions {
         #[test]
         fn should_be_empty_when_no_sent_transactions() {
             let transactions = EthTransactions::new(TransactionNonce::ZERO);
-            let resubmitted_txs = transactions
-                .create_resubmit_transactions(TransactionCount::ZERO, transaction_price());
+            let resubmitted_txs = transactions.create_resubmit_transactions(
+                TransactionCount::ZERO,
+                transaction_price(),
+                MAX_FEE_INCREASE_PERCENT,
+            );
 
             assert_eq!(resubmitted_txs, vec![]);
         }
@@ -570,6 +841,7 @@ mod eth_transactions {
                 let resubmitted_txs = transactions.create_resubmit_transactions(
                     TransactionCount::from(num_tx + 1),
                     higher_new_price.clone(),
+                    MAX_FEE_INCREASE_PERCENT,
                 );
 
                 assert_eq!(resubmitted_txs, vec![]);
@@ -596,8 +868,11 @@ mod eth_transactions {
                 sent_transactions.push(signed_tx);
             }
 
-            let resubmitted_txs = transactions
-                .create_resubmit_transactions(TransactionCount::from(10_u8), initial_price.clone());
+            let resubmitted_txs = transactions.create_resubmit_transactions(
+                TransactionCount::from(10_u8),
+                initial_price.clone(),
+                MAX_FEE_INCREASE_PERCENT,
+            );
 
             //transactions with nonces 0..10 (exclusive) were mined
             assert_eq!(
@@ -681,6 +956,7 @@ mod eth_transactions {
                 let resubmitted_txs = transactions.create_resubmit_transactions(
                     TransactionCount::ZERO,
                     test.price_at_tx_resubmission.clone(),
+                    MAX_FEE_INCREASE_PERCENT,
                 );
 
                 let expected_resubmitted_tx = Eip1559TransactionRequest {
@@ -725,8 +1001,11 @@ mod eth_transactions {
                 ..initial_price
             };
 
-            let resubmitted_txs = transactions
-                .create_resubmit_transactions(TransactionCount::from(30_u8), higher_price.clone());
+            let resubmitted_txs = transactions.create_resubmit_transactions(
+                TransactionCount::from(30_u8),
+                higher_price.clone(),
+                MAX_FEE_INCREASE_PERCENT,
+            );
             assert_eq!(resubmitted_txs.len(), 70);
             for (i, resubmitted_tx) in resubmitted_txs
                 .into_iter()
@@ -772,7 +1051,7 @@ mod eth_transactions {
         use crate::transactions::tests::{
             create_and_record_signed_transaction, create_and_record_transaction,
             create_and_record_withdrawal_request, expect_panic_with_message, sign_transaction,
-            transaction_price,
+            transaction_price, MAX_FEE_INCREASE_PERCENT,
         };
         use crate::transactions::{EthTransactions, ResubmitTransaction};
         use crate::tx::{Eip1559TransactionRequest, TransactionPrice};
@@ -905,8 +1184,11 @@ mod eth_transactions {
                 create_and_record_signed_transaction(&mut transactions, created_tx.clone());
             transactions.record_sent_transaction(signed_tx);
 
-            let resubmitted_txs_1 = transactions
-                .create_resubmit_transactions(TransactionCount::ZERO, resubmit_price_1.clone());
+            let resubmitted_txs_1 = transactions.create_resubmit_transactions(
+                TransactionCount::ZERO,
+                resubmit_price_1.clone(),
+                MAX_FEE_INCREASE_PERCENT,
+            );
             let resubmitted_tx1 = Eip1559TransactionRequest {
                 max_fee_per_gas: WeiPerGas::from(13_u8),
                 max_priority_fee_per_gas: WeiPerGas::from(24_u8),
@@ -935,8 +1217,11 @@ mod eth_transactions {
                 max_fee_per_gas: initial_price.max_fee_per_gas.checked_mul(2_u8).unwrap(),
                 ..resubmit_price_1
             };
-            let resubmitted_txs_2 =
-                transactions.create_resubmit_transactions(TransactionCount::ZERO, resubmit_price_2);
+            let resubmitted_txs_2 = transactions.create_resubmit_transactions(
+                TransactionCount::ZERO,
+                resubmit_price_2,
+                MAX_FEE_INCREASE_PERCENT,
+            );
             let resubmitted_tx2 = Eip1559TransactionRequest {
                 max_fee_per_gas: WeiPerGas::from(22_u8),
                 max_priority_fee_per_gas: WeiPerGas::from(24_u8),
@@ -1086,14 +1371,18 @@ mod eth_transactions {
     }
 
     mod record_finalized_transaction {
+        use crate::eth_rpc_client::responses::TransactionReceipt;
         use crate::map::MultiKeyMap;
+        use crate::numeric::{GasAmount, Wei, WeiPerGas};
         use crate::numeric::{LedgerBurnIndex, TransactionNonce};
         use crate::transactions::tests::{
             create_and_record_signed_transaction, create_and_record_transaction,
             create_and_record_withdrawal_request, dummy_signature, expect_panic_with_message,
-            transaction_price, transaction_receipt, withdrawal_request_with_index,
+            owner, transaction_price, transaction_receipt, withdrawal_request_with_index,
+        };
+        use crate::transactions::{
+            EthTransactions, EthWithdrawalRequest, FeeRefund, ResubmitTransaction,
         };
-        use crate::transactions::{EthTransactions, ResubmitTransaction};
         use crate::tx::SignedEip1559TransactionRequest;
 
         #[test]
@@ -1219,6 +1508,136 @@ mod eth_transactions {
             assert_eq!(transactions.signed_transactions_iter().next(), None);
             assert_eq!(transactions.sent_transactions_iter().next(), None);
         }
+
+        #[test]
+        fn should_not_refund_fee_when_fully_consumed() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let ledger_burn_index = LedgerBurnIndex::new(15);
+            let withdrawal_request =
+                create_and_record_withdrawal_request(&mut transactions, ledger_burn_index);
+            let created_tx = create_and_record_transaction(
+                &mut transactions,
+                withdrawal_request,
+                transaction_price(),
+            );
+            let signed_tx = create_and_record_signed_transaction(&mut transactions, created_tx);
+            transactions.record_sent_transaction(signed_tx.clone());
+
+            transactions
+                .record_finalized_transaction(ledger_burn_index, transaction_receipt(&signed_tx));
+
+            assert_eq!(transactions.fee_refunds_iter().next(), None);
+            assert!(transactions.nothing_to_process());
+        }
+
+        #[test]
+        fn should_record_fee_refund_when_effective_fee_lower_than_charged() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let ledger_burn_index = LedgerBurnIndex::new(15);
+            let withdrawal_request =
+                create_and_record_withdrawal_request(&mut transactions, ledger_burn_index);
+            let created_tx = create_and_record_transaction(
+                &mut transactions,
+                withdrawal_request.clone(),
+                transaction_price(),
+            );
+            let signed_tx = create_and_record_signed_transaction(&mut transactions, created_tx);
+            transactions.record_sent_transaction(signed_tx.clone());
+            let receipt = TransactionReceipt {
+                effective_gas_price: WeiPerGas::new(0x3b9aca00),
+                gas_used: GasAmount::new(21_000),
+                ..transaction_receipt(&signed_tx)
+            };
+            let charged_fee = withdrawal_request
+                .withdrawal_amount
+                .checked_sub(signed_tx.transaction().amount)
+                .unwrap();
+            let effective_fee = Wei::new(0x3b9aca00 * 21_000);
+
+            transactions.record_finalized_transaction(ledger_burn_index, receipt);
+
+            let expected_refund = FeeRefund {
+                ledger_burn_index,
+                beneficiary: owner(&withdrawal_request),
+                amount: charged_fee.checked_sub(effective_fee).unwrap(),
+                created_at_time: None,
+            };
+            assert_eq!(
+                transactions.fee_refunds_iter().collect::<Vec<_>>(),
+                vec![&expected_refund]
+            );
+            assert!(!transactions.nothing_to_process());
+
+            assert_eq!(
+                transactions.record_fee_refund(ledger_burn_index),
+                expected_refund
+            );
+            assert_eq!(transactions.fee_refunds_iter().next(), None);
+            assert!(transactions.nothing_to_process());
+        }
+
+        #[test]
+        fn should_keep_fee_refund_created_at_time_of_first_attempt() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let ledger_burn_index = LedgerBurnIndex::new(15);
+            let withdrawal_request =
+                create_and_record_withdrawal_request(&mut transactions, ledger_burn_index);
+            let created_tx = create_and_record_transaction(
+                &mut transactions,
+                withdrawal_request,
+                transaction_price(),
+            );
+            let signed_tx = create_and_record_signed_transaction(&mut transactions, created_tx);
+            transactions.record_sent_transaction(signed_tx.clone());
+            let receipt = TransactionReceipt {
+                effective_gas_price: WeiPerGas::new(0x3b9aca00),
+                gas_used: GasAmount::new(21_000),
+                ..transaction_receipt(&signed_tx)
+            };
+            transactions.record_finalized_transaction(ledger_burn_index, receipt);
+
+            assert_eq!(
+                transactions.fee_refund_created_at_time(ledger_burn_index, 1_000),
+                1_000
+            );
+            assert_eq!(
+                transactions.fee_refund_created_at_time(ledger_burn_index, 2_000),
+                1_000
+            );
+            assert_eq!(
+                transactions
+                    .record_fee_refund(ledger_burn_index)
+                    .created_at_time,
+                Some(1_000)
+            );
+        }
+
+        #[test]
+        fn should_not_record_fee_refund_when_issuer_unknown() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let ledger_burn_index = LedgerBurnIndex::new(15);
+            let withdrawal_request = EthWithdrawalRequest {
+                from: None,
+                ..withdrawal_request_with_index(ledger_burn_index)
+            };
+            transactions.record_withdrawal_request(withdrawal_request.clone());
+            let created_tx = create_and_record_transaction(
+                &mut transactions,
+                withdrawal_request,
+                transaction_price(),
+            );
+            let signed_tx = create_and_record_signed_transaction(&mut transactions, created_tx);
+            transactions.record_sent_transaction(signed_tx.clone());
+            let receipt = TransactionReceipt {
+                effective_gas_price: WeiPerGas::new(0x3b9aca00),
+                gas_used: GasAmount::new(21_000),
+                ..transaction_receipt(&signed_tx)
+            };
+
+            transactions.record_finalized_transaction(ledger_burn_index, receipt);
+
+            assert_eq!(transactions.fee_refunds_iter().next(), None);
+        }
     }
 
     mod transaction_status {
@@ -1289,7 +1708,7 @@ mod eth_withdrawal_request {
     #[test]
     fn should_have_readable_debug_representation() {
         let request = withdrawal_request_with_index(LedgerBurnIndex::new(131));
-        let expected_debug = "EthWithdrawalRequest { withdrawal_amount: 1_100_000_000_000_000, destination: 0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34, ledger_burn_index: 131 }";
+        let expected_debug = "EthWithdrawalRequest { withdrawal_amount: 1_100_000_000_000_000, destination: 0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34, ledger_burn_index: 131, from: Some(k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae) }";
         assert_eq!(format!("{:?}", request), expected_debug);
     }
 }
@@ -1371,6 +1790,155 @@ mod create_transaction {
     }
 }
 
+mod create_batch_transaction {
+    use crate::address::Address;
+    use crate::endpoints::{EthTransaction, RetrieveEthStatus};
+    use crate::eth_rpc_client::responses::TransactionReceipt;
+    use crate::lifecycle::EthereumNetwork;
+    use crate::numeric::{GasAmount, LedgerBurnIndex, TransactionNonce, Wei, WeiPerGas};
+    use crate::transactions::tests::{
+        owner, sign_transaction, transaction_price, transaction_receipt,
+        withdrawal_request_with_index,
+    };
+    use crate::transactions::{
+        create_batch_transaction, CreateTransactionError, EthTransactions, EthWithdrawalRequest,
+        FeeRefund,
+    };
+    use std::str::FromStr;
+
+    const DISPERSE_CONTRACT_ADDRESS: &str = "0xD152f549545093347A162Dce210e7293f1452150";
+
+    #[test]
+    fn should_create_batch_transaction() {
+        let requests = two_withdrawal_requests();
+
+        let tx = create_batch_transaction(
+            &requests,
+            TransactionNonce::TWO,
+            transaction_price(),
+            EthereumNetwork::Sepolia,
+            disperse_contract_address(),
+        )
+        .expect("failed to create batch transaction");
+
+        let expected_amount = Wei::new(1_002_499_996_750_000);
+        assert_eq!(tx.nonce, TransactionNonce::TWO);
+        assert_eq!(tx.destination, disperse_contract_address());
+        assert_eq!(tx.gas_limit, GasAmount::new(130_000));
+        assert_eq!(
+            tx.amount,
+            expected_amount.checked_add(expected_amount).unwrap()
+        );
+        assert_eq!(
+            hex::encode(tx.data),
+            "e63d38ed\
+            0000000000000000000000000000000000000000000000000000000000000040\
+            00000000000000000000000000000000000000000000000000000000000000a0\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            000000000000000000000000b44b5e756a894775fc32eddf3314bb1b1944dc34\
+            000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            00000000000000000000000000000000000000000000000000038fc4b83190b0\
+            00000000000000000000000000000000000000000000000000038fc4b83190b0"
+        );
+    }
+
+    #[test]
+    fn should_fail_when_amount_does_not_cover_fee_share() {
+        let mut requests = two_withdrawal_requests();
+        requests[1].withdrawal_amount = Wei::new(97_500_003_250_000);
+
+        assert_eq!(
+            create_batch_transaction(
+                &requests,
+                TransactionNonce::TWO,
+                transaction_price(),
+                EthereumNetwork::Sepolia,
+                disperse_contract_address(),
+            ),
+            Err(CreateTransactionError::InsufficientAmount {
+                ledger_burn_index: LedgerBurnIndex::new(16),
+                withdrawal_amount: Wei::new(97_500_003_250_000),
+                max_transaction_fee: Wei::new(97_500_003_250_000),
+            })
+        );
+    }
+
+    #[test]
+    fn should_track_batched_withdrawals_until_finalized() {
+        let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+        let requests = two_withdrawal_requests();
+        for request in &requests {
+            transactions.record_withdrawal_request(request.clone());
+        }
+        let tx = create_batch_transaction(
+            &requests,
+            TransactionNonce::ZERO,
+            transaction_price(),
+            EthereumNetwork::Sepolia,
+            disperse_contract_address(),
+        )
+        .unwrap();
+
+        transactions.record_created_batch_transaction(requests.clone(), tx.clone());
+
+        assert_eq!(transactions.withdrawal_requests_iter().next(), None);
+        for request in &requests {
+            assert_eq!(
+                transactions.transaction_status(&request.ledger_burn_index),
+                RetrieveEthStatus::TxCreated
+            );
+        }
+
+        let signed_tx = sign_transaction(tx);
+        transactions.record_signed_transaction(signed_tx.clone());
+        transactions.record_sent_transaction(signed_tx.clone());
+        let receipt = TransactionReceipt {
+            effective_gas_price: WeiPerGas::new(1_000_000_000),
+            gas_used: GasAmount::new(100_000),
+            ..transaction_receipt(&signed_tx)
+        };
+        transactions.record_finalized_transaction(LedgerBurnIndex::new(15), receipt);
+
+        for request in &requests {
+            assert_eq!(
+                transactions.transaction_status(&request.ledger_burn_index),
+                RetrieveEthStatus::TxConfirmed(EthTransaction {
+                    transaction_hash: signed_tx.hash().to_string()
+                })
+            );
+        }
+        let expected_refunds: Vec<_> = requests
+            .iter()
+            .map(|request| FeeRefund {
+                ledger_burn_index: request.ledger_burn_index,
+                beneficiary: owner(request),
+                amount: Wei::new(47_500_003_250_000),
+                created_at_time: None,
+            })
+            .collect();
+        assert_eq!(
+            transactions.fee_refunds_iter().cloned().collect::<Vec<_>>(),
+            expected_refunds
+        );
+    }
+
+    fn two_withdrawal_requests() -> Vec<EthWithdrawalRequest> {
+        vec![
+            withdrawal_request_with_index(LedgerBurnIndex::new(15)),
+            EthWithdrawalRequest {
+                destination: Address::from_str("0xdd2851cdd40ae6536831558dd46db62fac7a844d")
+                    .unwrap(),
+                ..withdrawal_request_with_index(LedgerBurnIndex::new(16))
+            },
+        ]
+    }
+
+    fn disperse_contract_address() -> Address {
+        Address::from_str(DISPERSE_CONTRACT_ADDRESS).unwrap()
+    }
+}
+
 mod withdrawal_flow {
     use super::arbitrary::{
         arb_checked_amount_of, arb_non_overflowing_transaction_price, arb_withdrawal_request,
@@ -1382,7 +1950,7 @@ mod withdrawal_flow {
     use crate::transactions::tests::{
         create_and_record_signed_transaction, create_and_record_transaction,
         create_and_record_withdrawal_request, sign_transaction, transaction_price,
-        transaction_receipt,
+        transaction_receipt, MAX_FEE_INCREASE_PERCENT,
     };
     use crate::transactions::{create_transaction, EthTransactions, EthereumNetwork};
     use candid::Principal;
@@ -1400,7 +1968,7 @@ mod withdrawal_flow {
         });
 
         proptest!(|(transaction_price in arb_non_overflowing_transaction_price(), transaction_count in arb_checked_amount_of())| {
-            let resubmit_txs = wrapped_txs.borrow().create_resubmit_transactions(transaction_count, transaction_price.clone());
+            let resubmit_txs = wrapped_txs.borrow().create_resubmit_transactions(transaction_count, transaction_price.clone(), MAX_FEE_INCREASE_PERCENT);
             for resubmit_tx in resubmit_txs.into_iter().flatten() {
                 wrapped_txs.borrow_mut().record_resubmit_transaction(resubmit_tx);
             }
@@ -1448,6 +2016,9 @@ mod withdrawal_flow {
                     ethereum_block_height: Default::default(),
                     minimum_withdrawal_amount: wei_from_milli_ether(10).into(),
                     next_transaction_nonce: Default::default(),
+                    rpc_consensus_policy: None,
+                    minimum_deposit_amount: None,
+                    disperse_contract_address: None,
                 })
                 .expect("init args should be valid"),
             );
@@ -1512,6 +2083,7 @@ pub mod arbitrary {
         AccessList, AccessListItem, Eip1559Signature, Eip1559TransactionRequest,
         SignedEip1559TransactionRequest, StorageKey, TransactionPrice,
     };
+    use candid::Principal;
     use phantom_newtype::Id;
     use proptest::strategy::Strategy;
 
@@ -1538,14 +2110,27 @@ pub mod arbitrary {
         uniform20(any::<u8>()).prop_map(|bytes| Address::new(bytes))
     }
 
+    fn arb_principal() -> impl Strategy<Value = Principal> {
+        use proptest::arbitrary::any;
+        use proptest::collection::vec;
+        vec(any::<u8>(), 0..=29).prop_map(|bytes| Principal::from_slice(&bytes))
+    }
+
     pub fn arb_withdrawal_request() -> impl Strategy<Value = EthWithdrawalRequest> {
-        (arb_checked_amount_of(), arb_address(), arb_u64_id()).prop_map(
-            |(withdrawal_amount, destination, ledger_burn_index)| EthWithdrawalRequest {
-                withdrawal_amount,
-                destination,
-                ledger_burn_index,
-            },
+        (
+            arb_checked_amount_of(),
+            arb_address(),
+            arb_u64_id(),
+            proptest::option::of(arb_principal()),
         )
+            .prop_map(
+                |(withdrawal_amount, destination, ledger_burn_index, from)| EthWithdrawalRequest {
+                    withdrawal_amount,
+                    destination,
+                    ledger_burn_index,
+                    from,
+                },
+            )
     }
 
     pub fn arb_non_overflowing_transaction_price() -> impl Strategy<Value = TransactionPrice> {
@@ -1662,9 +2247,21 @@ fn withdrawal_request_with_index(ledger_burn_index: LedgerBurnIndex) -> EthWithd
         ledger_burn_index,
         destination: Address::from_str("0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34").unwrap(),
         withdrawal_amount: Wei::new(1_100_000_000_000_000),
+        from: Some(
+            candid::Principal::from_str(
+                "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae",
+            )
+            .unwrap(),
+        ),
     }
 }
 
+fn owner(request: &EthWithdrawalRequest) -> candid::Principal {
+    request
+        .from
+        .expect("BUG: withdrawal request without issuer")
+}
+
 fn signed_transaction_with_nonce(nonce: TransactionNonce) -> SignedEip1559TransactionRequest {
     SignedEip1559TransactionRequest::from((
         eip_1559_transaction_request_with_nonce(nonce),
@@ -1691,6 +2288,9 @@ fn eip_1559_transaction_request_with_nonce(nonce: TransactionNonce) -> Eip1559Tr
     }
 }
 
+/// Default maximum fee increase of resubmitted transactions.
+const MAX_FEE_INCREASE_PERCENT: u16 = 100;
+
 fn transaction_price() -> TransactionPrice {
     TransactionPrice {
         max_fee_per_gas: WeiPerGas::new(0x59682f32),
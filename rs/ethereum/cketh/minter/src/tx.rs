@@ -6,6 +6,7 @@ use crate::eth_rpc::{FeeHistory, Hash};
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::numeric::{BlockNumber, GasAmount, TransactionNonce, Wei, WeiPerGas};
 use crate::state::{lazy_call_ecdsa_public_key, read_state};
+use candid::CandidType;
 use ethnum::u256;
 use ic_crypto_ecdsa_secp256k1::RecoveryId;
 use ic_ic00_types::DerivationPath;
@@ -15,6 +16,96 @@ use serde::{Deserialize, Serialize};
 
 const EIP1559_TX_ID: u8 = 2;
 
+/// Number of blocks sampled by default to estimate the transaction price.
+pub const DEFAULT_FEE_HISTORY_BLOCK_COUNT: u8 = 5;
+/// Maximum number of blocks that can be sampled to estimate the transaction price.
+pub const MAX_FEE_HISTORY_BLOCK_COUNT: u8 = 20;
+/// Percentile of the effective priority fees of each sampled block used by default
+/// to estimate the transaction price.
+pub const DEFAULT_FEE_HISTORY_REWARD_PERCENTILE: u8 = 20;
+/// Maximum number of reward percentiles that can be sampled in each block.
+pub const MAX_FEE_HISTORY_REWARD_PERCENTILES: usize = 5;
+/// Minimum fee increase (in percent) for a transaction to replace a pending transaction
+/// with the same nonce.
+pub const MIN_RESUBMISSION_FEE_INCREASE_PERCENT: u16 = 10;
+
+/// How the minter estimates the price of the transactions it sends.
+/// Unset fields use their default value.
+#[derive(
+    CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Encode, Decode,
+)]
+pub struct FeeEstimationPolicy {
+    /// Number of the most recent blocks whose fee history is sampled. Defaults to 10.
+    #[n(0)]
+    pub block_count: Option<u8>,
+    /// Percentiles of the effective priority fees sampled in each block. The estimated priority
+    /// fee is the median of all samples. Defaults to `[10, 20, 30]`.
+    #[n(1)]
+    pub reward_percentiles: Option<Vec<u8>>,
+    /// Weight (in percent) of a new estimate when it is exponentially smoothed with the
+    /// previous one. 100 disables smoothing. Defaults to 50.
+    #[n(2)]
+    pub smoothing_factor_percent: Option<u8>,
+    /// Maximum fee increase (in percent) of a transaction when it is resubmitted.
+    /// Must be at least 10. Defaults to 100.
+    #[n(3)]
+    pub max_resubmission_fee_increase_percent: Option<u16>,
+}
+
+impl FeeEstimationPolicy {
+    pub fn block_count(&self) -> u8 {
+        self.block_count.unwrap_or(10)
+    }
+
+    pub fn reward_percentiles(&self) -> Vec<u8> {
+        self.reward_percentiles
+            .clone()
+            .unwrap_or_else(|| vec![10, 20, 30])
+    }
+
+    pub fn smoothing_factor_percent(&self) -> u8 {
+        self.smoothing_factor_percent.unwrap_or(50)
+    }
+
+    pub fn max_resubmission_fee_increase_percent(&self) -> u16 {
+        self.max_resubmission_fee_increase_percent.unwrap_or(100)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let block_count = self.block_count();
+        if block_count == 0 || block_count > MAX_FEE_HISTORY_BLOCK_COUNT {
+            return Err(format!(
+                "block_count must be between 1 and {MAX_FEE_HISTORY_BLOCK_COUNT}, got {block_count}"
+            ));
+        }
+        let percentiles = self.reward_percentiles();
+        if percentiles.is_empty() || percentiles.len() > MAX_FEE_HISTORY_REWARD_PERCENTILES {
+            return Err(format!(
+                "between 1 and {MAX_FEE_HISTORY_REWARD_PERCENTILES} reward_percentiles are required, got {}",
+                percentiles.len()
+            ));
+        }
+        if !percentiles.windows(2).all(|w| w[0] < w[1]) || percentiles.iter().any(|&p| p > 100) {
+            return Err(format!(
+                "reward_percentiles must be increasing and between 0 and 100, got {percentiles:?}"
+            ));
+        }
+        let smoothing_factor = self.smoothing_factor_percent();
+        if smoothing_factor == 0 || smoothing_factor > 100 {
+            return Err(format!(
+                "smoothing_factor_percent must be between 1 and 100, got {smoothing_factor}"
+            ));
+        }
+        let max_increase = self.max_resubmission_fee_increase_percent();
+        if max_increase < MIN_RESUBMISSION_FEE_INCREASE_PERCENT {
+            return Err(format!(
+                "max_resubmission_fee_increase_percent must be at least {MIN_RESUBMISSION_FEE_INCREASE_PERCENT}, got {max_increase}"
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, Eq, Hash, PartialEq, Encode, Decode)]
 #[cbor(transparent)]
 pub struct AccessList(#[n(0)] pub Vec<AccessListItem>);
@@ -143,6 +234,10 @@ impl FinalizedEip1559Transaction {
         &self.transaction.transaction().amount
     }
 
+    pub fn transaction_price(&self) -> TransactionPrice {
+        self.transaction.transaction().transaction_price()
+    }
+
     pub fn transaction_hash(&self) -> &Hash {
         &self.receipt.transaction_hash
     }
@@ -360,6 +455,49 @@ impl TransactionPrice {
         }
     }
 
+    /// Returns the price of a transaction replacing a pending transaction with price `self`:
+    /// the fees are increased by at least 10%, or up to the `current` estimate if higher,
+    /// but by at most `max_increase_percent`.
+    pub fn resubmission_price(&self, current: &Self, max_increase_percent: u16) -> Self {
+        let max_increase_percent = max_increase_percent.max(MIN_RESUBMISSION_FEE_INCREASE_PERCENT);
+        let cap = |amount: WeiPerGas| {
+            amount
+                .checked_mul(max_increase_percent)
+                .and_then(|increase| increase.checked_div_ceil(100_u8))
+                .and_then(|increase| amount.checked_add(increase))
+                .unwrap_or(WeiPerGas::MAX)
+        };
+        let new_price = self.clone().increase_by_10_percent().max(current.clone());
+        Self {
+            gas_limit: new_price.gas_limit,
+            max_fee_per_gas: new_price.max_fee_per_gas.min(cap(self.max_fee_per_gas)),
+            max_priority_fee_per_gas: new_price
+                .max_priority_fee_per_gas
+                .min(cap(self.max_priority_fee_per_gas)),
+        }
+    }
+
+    /// Exponentially smooths this estimate with the `previous` one: each fee is
+    /// `factor * self + (1 - factor) * previous`, where `factor` is given in percent.
+    pub fn smooth(self, previous: &Self, factor_percent: u8) -> Self {
+        let factor_percent = factor_percent.min(100);
+        let smooth = |new: WeiPerGas, previous: WeiPerGas| {
+            new.checked_mul(factor_percent)
+                .zip(previous.checked_mul(100 - factor_percent))
+                .and_then(|(new, previous)| new.checked_add(previous))
+                .and_then(|sum| sum.checked_div_ceil(100_u8))
+                .unwrap_or(new)
+        };
+        Self {
+            gas_limit: self.gas_limit,
+            max_fee_per_gas: smooth(self.max_fee_per_gas, previous.max_fee_per_gas),
+            max_priority_fee_per_gas: smooth(
+                self.max_priority_fee_per_gas,
+                previous.max_priority_fee_per_gas,
+            ),
+        }
+    }
+
     /// Returns true if the new transaction fee is higher than the current one
     pub fn is_fee_increased(&self, new: &Self) -> bool {
         self.max_fee_per_gas < new.max_fee_per_gas
@@ -390,7 +528,7 @@ pub fn estimate_transaction_price(fee_history: &FeeHistory) -> TransactionPrice
     let max_priority_fee_per_gas = {
         let mut rewards: Vec<&WeiPerGas> = fee_history.reward.iter().flatten().collect();
         let historic_max_priority_fee_per_gas =
-            **median(&mut rewards).expect("should be non-empty with rewards of the last 5 blocks");
+            **median(&mut rewards).expect("should be non-empty with rewards of the sampled blocks");
         historic_max_priority_fee_per_gas.max(MIN_MAX_PRIORITY_FEE_PER_GAS)
     };
     let max_fee_per_gas = base_fee_of_next_finalized_block
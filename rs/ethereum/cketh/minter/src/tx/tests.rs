@@ -40,6 +40,106 @@ mod transaction_price {
             }
         }
     }
+
+    mod resubmission_price {
+        use crate::numeric::{GasAmount, WeiPerGas};
+        use crate::tx::TransactionPrice;
+
+        fn price(max_fee_per_gas: u64, max_priority_fee_per_gas: u64) -> TransactionPrice {
+            TransactionPrice {
+                gas_limit: GasAmount::new(21_000),
+                max_fee_per_gas: WeiPerGas::from(max_fee_per_gas),
+                max_priority_fee_per_gas: WeiPerGas::from(max_priority_fee_per_gas),
+            }
+        }
+
+        #[test]
+        fn should_increase_by_at_least_10_percent() {
+            assert_eq!(
+                price(100, 10).resubmission_price(&price(101, 10), 100),
+                price(110, 11)
+            );
+            // the cap cannot be lower than the minimum increase
+            assert_eq!(
+                price(100, 10).resubmission_price(&price(101, 10), 0),
+                price(110, 11)
+            );
+        }
+
+        #[test]
+        fn should_use_current_price_when_higher() {
+            assert_eq!(
+                price(100, 10).resubmission_price(&price(150, 15), 100),
+                price(150, 15)
+            );
+        }
+
+        #[test]
+        fn should_cap_fee_increase() {
+            assert_eq!(
+                price(100, 10).resubmission_price(&price(500, 12), 100),
+                price(200, 12)
+            );
+            assert_eq!(
+                price(100, 10).resubmission_price(&price(500, 50), 50),
+                price(150, 15)
+            );
+        }
+
+        #[test]
+        fn should_saturate() {
+            let max = TransactionPrice {
+                gas_limit: GasAmount::new(21_000),
+                max_fee_per_gas: WeiPerGas::MAX,
+                max_priority_fee_per_gas: WeiPerGas::MAX,
+            };
+            assert_eq!(max.resubmission_price(&price(1, 1), 100), max);
+        }
+    }
+
+    mod smooth {
+        use crate::numeric::{GasAmount, WeiPerGas};
+        use crate::tx::tests::arb_checked_amount_of;
+        use crate::tx::TransactionPrice;
+        use proptest::{prelude::any, prop_assert, prop_assert_eq, proptest};
+
+        proptest! {
+            #[test]
+            fn should_be_between_previous_and_new_estimate(
+                gas_limit in arb_checked_amount_of(),
+                new_fee in any::<u128>(),
+                previous_fee in any::<u128>(),
+                factor_percent in 1..=100_u8,
+            ) {
+                let price = |fee: u128| TransactionPrice {
+                    gas_limit,
+                    max_fee_per_gas: WeiPerGas::from(fee),
+                    max_priority_fee_per_gas: WeiPerGas::from(fee / 2),
+                };
+                let smoothed = price(new_fee).smooth(&price(previous_fee), factor_percent);
+
+                let (low, high) = (new_fee.min(previous_fee), new_fee.max(previous_fee));
+                prop_assert_eq!(smoothed.gas_limit, gas_limit);
+                prop_assert!(WeiPerGas::from(low) <= smoothed.max_fee_per_gas);
+                prop_assert!(smoothed.max_fee_per_gas <= WeiPerGas::from(high));
+                if factor_percent == 100 {
+                    prop_assert_eq!(smoothed, price(new_fee));
+                }
+            }
+        }
+
+        #[test]
+        fn should_weigh_new_estimate() {
+            let price = |fee: u64| TransactionPrice {
+                gas_limit: GasAmount::new(21_000),
+                max_fee_per_gas: WeiPerGas::from(fee),
+                max_priority_fee_per_gas: WeiPerGas::from(fee),
+            };
+            assert_eq!(price(200).smooth(&price(100), 50), price(150));
+            assert_eq!(price(200).smooth(&price(100), 25), price(125));
+            assert_eq!(price(100).smooth(&price(200), 25), price(175));
+        }
+    }
 }
 
 fn arb_checked_amount_of<Unit>() -> impl Strategy<Value = CheckedAmountOf<Unit>> {
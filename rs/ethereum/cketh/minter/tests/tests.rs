@@ -13,6 +13,7 @@ use ic_cketh_minter::endpoints::{
 };
 use ic_cketh_minter::lifecycle::{init::InitArg as MinterInitArgs, EthereumNetwork, MinterArg};
 use ic_cketh_minter::logs::Log;
+use ic_cketh_minter::memo::{BurnMemo, MintMemo};
 use ic_cketh_minter::{PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, SCRAPPING_ETH_LOGS_INTERVAL};
 use ic_icrc1_ledger::{InitArgsBuilder as LedgerInitArgsBuilder, LedgerArgument};
 use ic_state_machine_tests::{
@@ -22,6 +23,9 @@ use ic_state_machine_tests::{
 use ic_test_utilities_load_wasm::load_wasm;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc3::transactions::{
+    GetTransactionsRequest, GetTransactionsResponse, Transaction,
+};
 use num_traits::cast::ToPrimitive;
 use serde_json::{json, Value};
 use std::path::PathBuf;
@@ -31,6 +35,8 @@ const CKETH_TRANSFER_FEE: u64 = 10;
 const MAX_TICKS: usize = 10;
 const DEFAULT_DEPOSIT_BLOCK_NUMBER: u64 = 0x9;
 const DEFAULT_DEPOSIT_LOG_INDEX: u64 = 0x24;
+const TRANSACTION_STATUS_SUCCESS: &str = "0x1";
+const TRANSACTION_STATUS_FAILURE: &str = "0x0";
 
 #[test]
 fn should_deposit_and_withdraw() {
@@ -65,26 +71,23 @@ fn should_deposit_and_withdraw() {
         .unwrap();
     let tx_hash = "0xcfa48c44dc89d18a898a42b4a5b02b6847a3c2019507d5571a481751c7a2f353".to_string();
 
+    let deposit = EthLogEntry {
+        encoded_principal: encoded_principal.clone(),
+        amount,
+        from_address,
+        transaction_hash: tx_hash.clone(),
+    };
     cketh.handle_rpc_call(
         "https://rpc.ankr.com/eth",
         "eth_getLogs",
-        eth_get_logs(Some(EthLogEntry {
-            encoded_principal: encoded_principal.clone(),
-            amount,
-            from_address,
-            transaction_hash: tx_hash.clone(),
-        })),
+        eth_get_logs(Some(deposit.clone())),
     );
     cketh.handle_rpc_call(
         "https://cloudflare-eth.com",
         "eth_getLogs",
-        eth_get_logs(Some(EthLogEntry {
-            encoded_principal,
-            amount,
-            from_address,
-            transaction_hash: tx_hash.clone(),
-        })),
+        eth_get_logs(Some(deposit.clone())),
     );
+    cketh.handle_deposit_receipt(&deposit, TRANSACTION_STATUS_SUCCESS);
 
     for _ in 0..10 {
         cketh.env.advance_time(Duration::from_secs(1));
@@ -107,6 +110,7 @@ fn should_deposit_and_withdraw() {
             from_address: from_address.to_string(),
             value: Nat::from(amount),
             principal: caller,
+            subaccount: None,
         },
     );
     assert_contains_unique_event(
@@ -119,6 +123,20 @@ fn should_deposit_and_withdraw() {
             mint_block_index: Nat::from(0),
         },
     );
+    let mint_memo = cketh
+        .get_ledger_transaction(0)
+        .mint
+        .expect("expected a mint")
+        .memo
+        .expect("expected a mint memo");
+    assert_eq!(
+        MintMemo::try_from(&mint_memo),
+        Ok(MintMemo::Convert {
+            from_address,
+            tx_hash: tx_hash.parse().unwrap(),
+            log_index: DEFAULT_DEPOSIT_LOG_INDEX.into(),
+        })
+    );
 
     // Withdraw
 
@@ -142,6 +160,20 @@ fn should_deposit_and_withdraw() {
     .0
     .to_u64()
     .unwrap();
+    let burn_memo = cketh
+        .get_ledger_transaction(block_index)
+        .burn
+        .expect("expected a burn")
+        .memo
+        .expect("expected a burn memo");
+    assert_eq!(
+        BurnMemo::try_from(&burn_memo),
+        Ok(BurnMemo::Convert {
+            to_address: "0x221E931fbFcb9bd54DdD26cE6f5e29E98AdD01C0"
+                .parse()
+                .unwrap(),
+        })
+    );
 
     cketh.wait_and_validate_withdrawal(
         "0x2cf1763e8ee3990103a31a5709b17b83f167738abb400844e67f608a98b0bdb5".to_string(),
@@ -183,26 +215,23 @@ fn should_block_blocked_addresses() {
         .unwrap();
     let tx_hash = "0xcfa48c44dc89d18a898a42b4a5b02b6847a3c2019507d5571a481751c7a2f352".to_string();
 
+    let deposit = EthLogEntry {
+        encoded_principal: encoded_principal.clone(),
+        amount,
+        from_address: from_address_blocked,
+        transaction_hash: tx_hash.clone(),
+    };
     cketh.handle_rpc_call(
         "https://rpc.ankr.com/eth",
         "eth_getLogs",
-        eth_get_logs(Some(EthLogEntry {
-            encoded_principal: encoded_principal.clone(),
-            amount,
-            from_address: from_address_blocked,
-            transaction_hash: tx_hash.clone(),
-        })),
+        eth_get_logs(Some(deposit.clone())),
     );
     cketh.handle_rpc_call(
         "https://cloudflare-eth.com",
         "eth_getLogs",
-        eth_get_logs(Some(EthLogEntry {
-            encoded_principal: encoded_principal.clone(),
-            amount,
-            from_address: from_address_blocked,
-            transaction_hash: tx_hash.to_string(),
-        })),
+        eth_get_logs(Some(deposit.clone())),
     );
+    cketh.handle_deposit_receipt(&deposit, TRANSACTION_STATUS_SUCCESS);
 
     for _ in 0..10 {
         cketh.env.advance_time(Duration::from_secs(1));
@@ -234,26 +263,23 @@ fn should_block_blocked_addresses() {
     let tx_hash = "0xcfa48c44dc89d18a898a42b4a5b02b6847a3c2019507d5571a481751c7a2f353".to_string();
 
     tick_until_next_http_request(&cketh.env, "eth_getLogs");
+    let deposit = EthLogEntry {
+        encoded_principal: encoded_principal.clone(),
+        amount,
+        from_address,
+        transaction_hash: tx_hash.clone(),
+    };
     cketh.handle_rpc_call(
         "https://rpc.ankr.com/eth",
         "eth_getLogs",
-        eth_get_logs(Some(EthLogEntry {
-            encoded_principal: encoded_principal.clone(),
-            amount,
-            from_address,
-            transaction_hash: tx_hash.clone(),
-        })),
+        eth_get_logs(Some(deposit.clone())),
     );
     cketh.handle_rpc_call(
         "https://cloudflare-eth.com",
         "eth_getLogs",
-        eth_get_logs(Some(EthLogEntry {
-            encoded_principal,
-            amount,
-            from_address,
-            transaction_hash: tx_hash.clone(),
-        })),
+        eth_get_logs(Some(deposit.clone())),
     );
+    cketh.handle_deposit_receipt(&deposit, TRANSACTION_STATUS_SUCCESS);
 
     for _ in 0..10 {
         cketh.env.advance_time(Duration::from_secs(1));
@@ -272,13 +298,91 @@ fn should_block_blocked_addresses() {
     let message_id = cketh.call_minter_withdraw(
         caller,
         Nat::from(EXPECTED_BALANCE),
-        "01e2919679362dFBC9ee1644Ba9C6da6D6245BB1".to_string(),
+        "0x01e2919679362dFBC9ee1644Ba9C6da6D6245BB1".to_string(),
     );
 
-    cketh.env.tick();
-
     // Withdrawing to a blocked address should fail.
-    assert!(cketh.env.await_ingress(message_id, MAX_TICKS).is_err());
+    let result = Decode!(&assert_reply(
+        cketh
+            .env
+            .await_ingress(message_id, MAX_TICKS)
+            .expect("failed to resolve message with id: {message_id}"),
+    ), Result<RetrieveEthRequest, WithdrawalError>)
+    .unwrap();
+    match result {
+        Err(WithdrawalError::RecipientBlocked { address }) => {
+            assert_eq!(address, "0x01e2919679362dFBC9ee1644Ba9C6da6D6245BB1")
+        }
+        other => panic!("expected RecipientBlocked error, got {other:?}"),
+    }
+    assert_contains_unique_event(
+        &cketh.get_all_events(),
+        EventPayload::BlockedWithdrawalAttempt {
+            caller,
+            destination: "0x01e2919679362dFBC9ee1644Ba9C6da6D6245BB1".to_string(),
+            amount: Nat::from(EXPECTED_BALANCE),
+        },
+    );
+}
+
+#[test]
+fn should_not_mint_deposit_of_reverted_transaction() {
+    let mut cketh = CkEthSetup::new();
+    let caller: Principal = cketh.caller.into();
+
+    cketh.env.advance_time(SCRAPPING_ETH_LOGS_INTERVAL);
+    tick_until_next_http_request(&cketh.env, "eth_getBlockByNumber");
+    cketh.handle_rpc_call(
+        "https://rpc.ankr.com/eth",
+        "eth_getBlockByNumber",
+        eth_get_block_by_number(),
+    );
+    cketh.handle_rpc_call(
+        "https://cloudflare-eth.com",
+        "eth_getBlockByNumber",
+        eth_get_block_by_number(),
+    );
+    cketh.env.advance_time(SCRAPPING_ETH_LOGS_INTERVAL);
+    tick_until_next_http_request(&cketh.env, "eth_getLogs");
+
+    let tx_hash = "0xcfa48c44dc89d18a898a42b4a5b02b6847a3c2019507d5571a481751c7a2f353".to_string();
+    let deposit = EthLogEntry {
+        encoded_principal: encode_principal(caller),
+        amount: 100_000_000_000_000_000, // 0.1 ETH
+        from_address: "0x55654e7405fcb336386ea8f36954a211b2cda764"
+            .parse()
+            .unwrap(),
+        transaction_hash: tx_hash.clone(),
+    };
+    // Both providers return the log entry even though the transaction reverted.
+    cketh.handle_rpc_call(
+        "https://rpc.ankr.com/eth",
+        "eth_getLogs",
+        eth_get_logs(Some(deposit.clone())),
+    );
+    cketh.handle_rpc_call(
+        "https://cloudflare-eth.com",
+        "eth_getLogs",
+        eth_get_logs(Some(deposit.clone())),
+    );
+    cketh.handle_deposit_receipt(&deposit, TRANSACTION_STATUS_FAILURE);
+
+    for _ in 0..10 {
+        cketh.env.advance_time(Duration::from_secs(1));
+        cketh.env.tick();
+    }
+    assert_eq!(cketh.balance_of(caller), Nat::from(0));
+
+    assert_contains_unique_event(
+        &cketh.get_all_events(),
+        EventPayload::InvalidDeposit {
+            event_source: EventSource {
+                transaction_hash: tx_hash.clone(),
+                log_index: Nat::from(DEFAULT_DEPOSIT_LOG_INDEX),
+            },
+            reason: format!("receipt mismatch: transaction {tx_hash} has status Failure"),
+        },
+    );
 }
 
 fn assert_contains_unique_event(events: &[Event], payload: EventPayload) {
@@ -337,6 +441,9 @@ fn install_minter(env: &StateMachine, ledger_id: CanisterId, minter_id: Canister
         ethereum_block_height: Default::default(),
         ethereum_contract_address: Some("0x907b6EFc1a398fD88A8161b3cA02eEc8Eaf72ca1".to_string()),
         minimum_withdrawal_amount: 1.into(),
+        rpc_consensus_policy: None,
+        minimum_deposit_amount: None,
+        disperse_contract_address: None,
     };
     let minter_arg = MinterArg::InitArg(args);
     env.install_existing_canister(minter_id, minter_wasm(), Encode!(&minter_arg).unwrap())
@@ -351,6 +458,7 @@ fn assert_has_header(req: &CanisterHttpRequestContext, name: &str, value: &str)
         .any(|h| h.name == name && h.value == value));
 }
 
+#[derive(Clone)]
 struct EthLogEntry {
     encoded_principal: String,
     amount: u64,
@@ -359,25 +467,7 @@ struct EthLogEntry {
 }
 
 fn eth_get_logs(log_entry: Option<EthLogEntry>) -> Vec<u8> {
-    let mut result: Value = Value::Null;
-    if let Some(log_entry) = log_entry {
-        let amount_hex = format!("0x{:0>64x}", log_entry.amount);
-        result = json!({
-            "address": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
-            "blockHash": "0x79cfe76d69337dae199e32c2b6b3d7c2668bfe71a05f303f95385e70031b9ef8",
-            "blockNumber": format!("0x{:x}", DEFAULT_DEPOSIT_BLOCK_NUMBER),
-            "data": amount_hex,
-            "logIndex": format!("0x{:x}", DEFAULT_DEPOSIT_LOG_INDEX),
-            "removed": false,
-            "topics": [
-                "0x257e057bb61920d8d0ed2cb7b720ac7f9c513cd1110bc9fa543079154f45f435",
-                format!("0x000000000000000000000000{}", hex::encode(log_entry.from_address.as_ref())),
-                log_entry.encoded_principal
-            ],
-            "transactionHash": log_entry.transaction_hash,
-            "transactionIndex": "0x33"
-        });
-    }
+    let result = log_entry.as_ref().map(eth_log_entry).unwrap_or(Value::Null);
 
     serde_json::to_vec(&json!({
         "jsonrpc": "2.0",
@@ -387,6 +477,52 @@ fn eth_get_logs(log_entry: Option<EthLogEntry>) -> Vec<u8> {
     .expect("Failed to serialize JSON")
 }
 
+fn eth_log_entry(log_entry: &EthLogEntry) -> Value {
+    let amount_hex = format!("0x{:0>64x}", log_entry.amount);
+    json!({
+        "address": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+        "blockHash": "0x79cfe76d69337dae199e32c2b6b3d7c2668bfe71a05f303f95385e70031b9ef8",
+        "blockNumber": format!("0x{:x}", DEFAULT_DEPOSIT_BLOCK_NUMBER),
+        "data": amount_hex,
+        "logIndex": format!("0x{:x}", DEFAULT_DEPOSIT_LOG_INDEX),
+        "removed": false,
+        "topics": [
+            "0x257e057bb61920d8d0ed2cb7b720ac7f9c513cd1110bc9fa543079154f45f435",
+            format!("0x000000000000000000000000{}", hex::encode(log_entry.from_address.as_ref())),
+            log_entry.encoded_principal
+        ],
+        "transactionHash": log_entry.transaction_hash,
+        "transactionIndex": "0x33"
+    })
+}
+
+fn eth_get_deposit_receipt(log_entry: &EthLogEntry, status: &str) -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "result": {
+            "blockHash": "0x79cfe76d69337dae199e32c2b6b3d7c2668bfe71a05f303f95385e70031b9ef8",
+            "blockNumber": format!("0x{:x}", DEFAULT_DEPOSIT_BLOCK_NUMBER),
+            "contractAddress": null,
+            "cumulativeGasUsed": "0x8b2e10",
+            "effectiveGasPrice": "0xfefbee3e",
+            "from": log_entry.from_address.to_string(),
+            "gasUsed": "0x6f65",
+            "logs": if status == TRANSACTION_STATUS_SUCCESS {
+                vec![eth_log_entry(log_entry)]
+            } else {
+                vec![]
+            },
+            "status": status,
+            "to": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+            "transactionHash": log_entry.transaction_hash,
+            "transactionIndex": "0x33",
+            "type": "0x2"
+        }
+    }))
+    .expect("Failed to serialize JSON")
+}
+
 fn eth_get_fee_history() -> Vec<u8> {
     serde_json::to_vec(&json!({
         "jsonrpc": "2.0",
@@ -568,6 +704,17 @@ impl CkEthSetup {
         panic!("no http request found that match parameters: provider: {provider} and method: {method}");
     }
 
+    pub fn handle_deposit_receipt(&mut self, deposit: &EthLogEntry, status: &str) {
+        tick_until_next_http_request(&self.env, "eth_getTransactionReceipt");
+        for provider in ["https://rpc.ankr.com/eth", "https://cloudflare-eth.com"] {
+            self.handle_rpc_call(
+                provider,
+                "eth_getTransactionReceipt",
+                eth_get_deposit_receipt(deposit, status),
+            );
+        }
+    }
+
     pub fn minter_address(&self) -> String {
         Decode!(
             &assert_reply(
@@ -618,6 +765,31 @@ impl CkEthSetup {
         .unwrap()
     }
 
+    pub fn get_ledger_transaction(&self, block_index: u64) -> Transaction {
+        let response = Decode!(
+            &assert_reply(
+                self.env
+                    .query(
+                        self.ledger_id,
+                        "get_transactions",
+                        Encode!(&GetTransactionsRequest {
+                            start: Nat::from(block_index),
+                            length: Nat::from(1),
+                        })
+                        .unwrap()
+                    )
+                    .expect("failed to query get_transactions on the ledger")
+            ),
+            GetTransactionsResponse
+        )
+        .unwrap();
+        response
+            .transactions
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| panic!("no transaction at index {block_index}"))
+    }
+
     pub fn approve_minter(
         &self,
         from: Principal,
@@ -663,10 +835,10 @@ impl CkEthSetup {
         )
     }
 
-    pub fn _get_logs(&self, priority: &str) -> Log {
+    pub fn _get_logs(&self, severity: &str) -> Log {
         let request = HttpRequest {
             method: "".to_string(),
-            url: format!("/logs?priority={priority}"),
+            url: format!("/logs?severity={severity}"),
             headers: vec![],
             body: serde_bytes::ByteBuf::new(),
         };
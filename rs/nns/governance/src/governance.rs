@@ -9203,6 +9203,9 @@ fn is_information_about_swap_from_different_sources_consistent(
                 }),
 
             dapps: _,
+            lost_control_dapp_canister_ids: _,
+            extensions: _,
+            controller_drift: _,
         } => {
             // Extract fields from swap_init.
             let sns_swap_pb::Init {
@@ -9231,6 +9234,7 @@ fn is_information_about_swap_from_different_sources_consistent(
                 neurons_fund_participants: _,
                 should_auto_finalize: _,
                 neurons_fund_participation_constraints: _,
+                participation_eligibility_canister_id: _,
             } = swap_init;
 
             (
@@ -9263,6 +9267,9 @@ impl SwapBackgroundInformation {
             dapps: dapp_canister_summaries,
             archives: ledger_archive_canister_summaries,
             index: ledger_index_canister_summary,
+            lost_control_dapp_canister_ids: _,
+            extensions: _,
+            controller_drift: _,
         } = get_sns_canisters_summary_response;
 
         // Convert field values to analogous PB types.
@@ -1370,6 +1370,7 @@ mod convert_from_create_service_nervous_system_to_sns_init_payload_tests {
                 swap_due_timestamp_seconds: None,
                 nns_proposal_id: None,
                 neurons_fund_participation_constraints: None,
+                participation_eligibility_canister_id: None,
             },
         );
 
@@ -1686,6 +1687,7 @@ mod convert_from_executed_create_service_nervous_system_proposal_to_sns_init_pay
                 }),
                 // TODO[NNS1-2558]: Test this field.
                 neurons_fund_participation_constraints: None,
+                participation_eligibility_canister_id: None,
 
                 // We'll examine these later
                 initial_token_distribution: None,
@@ -389,6 +389,10 @@ impl TryFrom<CreateServiceNervousSystem> for SnsInitPayload {
             swap_start_timestamp_seconds: None,
             swap_due_timestamp_seconds: None,
             neurons_fund_participation_constraints: None,
+
+            // CreateServiceNervousSystem proposals do not (yet) support a
+            // participation eligibility canister.
+            participation_eligibility_canister_id: None,
         };
 
         result.validate_pre_execution()?;
@@ -397,6 +397,7 @@ impl Environment for FakeDriver {
                         neurons_fund_participants: None,    // TODO[NNS1-2339]
                         should_auto_finalize: Some(true),
                         neurons_fund_participation_constraints: None,
+                        participation_eligibility_canister_id: None,
                     }),
                     ..Default::default() // Not realistic, but sufficient for tests.
                 }),
@@ -445,6 +446,9 @@ impl Environment for FakeDriver {
                     canister_id: Some(*SNS_LEDGER_INDEX_CANISTER_ID),
                     status: None,
                 }),
+                lost_control_dapp_canister_ids: None,
+                extensions: None,
+                controller_drift: None,
             })
             .unwrap());
         }
@@ -11303,6 +11303,7 @@ lazy_static! {
         neurons_fund_participants: None, // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
         neurons_fund_participation_constraints: None,
+        participation_eligibility_canister_id: None,
     };
 }
 
@@ -11475,6 +11476,9 @@ lazy_static! {
                 canister_id: Some(*SNS_LEDGER_INDEX_CANISTER_ID),
                 status: None,
             }),
+            lost_control_dapp_canister_ids: None,
+            extensions: None,
+            controller_drift: None,
         })
         .unwrap()),
     );
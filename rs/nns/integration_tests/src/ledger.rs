@@ -127,6 +127,8 @@ fn test_rosetta1_92() {
                 num_blocks_to_archive: blocks_per_archive_call,
                 cycles_for_archive_creation: Some(0),
                 max_transactions_per_response: None,
+                more_controller_ids: None,
+                archive_created_callback: None,
             })
             .send_whitelist(ALL_NNS_CANISTER_IDS.iter().map(|&x| *x).collect())
             .build()
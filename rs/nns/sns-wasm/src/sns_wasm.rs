@@ -1915,6 +1915,9 @@ impl From<GetSnsCanistersSummaryResponse> for SnsCanisterIds {
             dapps: _,
             archives: _,
             index,
+            lost_control_dapp_canister_ids: _,
+            extensions: _,
+            controller_drift: _,
         } = value;
 
         Self {
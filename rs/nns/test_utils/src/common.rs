@@ -73,6 +73,8 @@ impl NnsInitPayloadsBuilder {
                     controller_id: ROOT_CANISTER_ID.into(),
                     cycles_for_archive_creation: Some(0),
                     max_transactions_per_response: None,
+                    more_controller_ids: None,
+                    archive_created_callback: None,
                 })
                 .max_message_size_bytes(128 * 1024)
                 // 24 hour transaction window
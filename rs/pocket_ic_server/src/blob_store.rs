@@ -0,0 +1,357 @@
+//! Blob store implementations of the PocketIC server.
+//!
+//! The [DiskBlobStore] keeps blobs in a directory, so that they survive server restarts and do
+//! not have to be held in memory. Once the store grows beyond its size cap, the least recently
+//! used blobs are evicted.
+
+use crate::BlobStore;
+use ::pocket_ic::common::blob::{BinaryBlob, BlobCompression, BlobId};
+use axum::async_trait;
+use ic_crypto_sha2::Sha256;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+const GZIP_EXTENSION: &str = "gz";
+const RAW_EXTENSION: &str = "raw";
+const TMP_EXTENSION: &str = "tmp";
+
+fn blob_id(data: &[u8]) -> BlobId {
+    let mut hasher = Sha256::new();
+    hasher.write(data);
+    BlobId(hasher.finish())
+}
+
+pub struct InMemoryBlobStore {
+    map: RwLock<HashMap<BlobId, BinaryBlob>>,
+}
+
+impl InMemoryBlobStore {
+    pub fn new() -> Self {
+        InMemoryBlobStore {
+            map: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InMemoryBlobStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl BlobStore for InMemoryBlobStore {
+    async fn store(&self, blob: BinaryBlob) -> BlobId {
+        let key = blob_id(&blob.data);
+        let mut m = self.map.write().await;
+        m.insert(key.clone(), blob);
+        key
+    }
+
+    async fn fetch(&self, blob_id: BlobId) -> Option<BinaryBlob> {
+        let m = self.map.read().await;
+        m.get(&blob_id).cloned()
+    }
+}
+
+struct BlobEntry {
+    size: u64,
+    compression: BlobCompression,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct BlobIndex {
+    entries: HashMap<BlobId, BlobEntry>,
+    total_size: u64,
+    // Logical clock ordering the uses of blobs, for LRU eviction.
+    clock: u64,
+}
+
+impl BlobIndex {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Removes the least recently used blobs in `dir`, except `keep`, until the total size
+    /// fits `max_size_bytes`.
+    fn evict(&mut self, dir: &Path, max_size_bytes: u64, keep: Option<&BlobId>) {
+        while self.total_size > max_size_bytes {
+            let Some(lru) = self
+                .entries
+                .iter()
+                .filter(|(blob_id, _)| Some(*blob_id) != keep)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(blob_id, _)| blob_id.clone())
+            else {
+                return;
+            };
+            let entry = self.entries.remove(&lru).unwrap();
+            self.total_size -= entry.size;
+            if let Err(e) = std::fs::remove_file(blob_path(dir, &lru, entry.compression)) {
+                warn!(
+                    "Failed to remove evicted blob {}: {}",
+                    hex::encode(lru.0),
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// A blob store that keeps every blob in a file named after its id and compression. The index
+/// is rebuilt from the directory on startup, with the modification time of the files as the
+/// initial recency.
+pub struct DiskBlobStore {
+    dir: PathBuf,
+    max_size_bytes: u64,
+    index: Mutex<BlobIndex>,
+    // Distinguishes the temporary files of concurrent writes of the same blob.
+    tmp_counter: AtomicU64,
+}
+
+impl DiskBlobStore {
+    pub fn new(dir: PathBuf, max_size_bytes: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let mut blobs = vec![];
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            if let Some((blob_id, compression)) = parse_blob_file_name(&path) {
+                blobs.push((
+                    metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    blob_id,
+                    compression,
+                    metadata.len(),
+                ));
+            } else if is_tmp_file_name(&path) {
+                // Leftovers of interrupted writes.
+                std::fs::remove_file(path)?;
+            }
+        }
+        blobs.sort_by_key(|(modified, ..)| *modified);
+
+        let mut index = BlobIndex::default();
+        for (_, blob_id, compression, size) in blobs {
+            let last_used = index.tick();
+            index.total_size += size;
+            index.entries.insert(
+                blob_id,
+                BlobEntry {
+                    size,
+                    compression,
+                    last_used,
+                },
+            );
+        }
+        info!(
+            "Loaded {} blobs ({} bytes) from {}",
+            index.entries.len(),
+            index.total_size,
+            dir.display()
+        );
+        index.evict(&dir, max_size_bytes, None);
+        Ok(Self {
+            dir,
+            max_size_bytes,
+            index: Mutex::new(index),
+            tmp_counter: AtomicU64::new(0),
+        })
+    }
+}
+
+fn blob_path(dir: &Path, blob_id: &BlobId, compression: BlobCompression) -> PathBuf {
+    let extension = match compression {
+        BlobCompression::Gzip => GZIP_EXTENSION,
+        BlobCompression::NoCompression => RAW_EXTENSION,
+    };
+    dir.join(format!("{}.{}", hex::encode(blob_id.0), extension))
+}
+
+fn parse_blob_file_name(path: &Path) -> Option<(BlobId, BlobCompression)> {
+    let compression = match path.extension()?.to_str()? {
+        GZIP_EXTENSION => BlobCompression::Gzip,
+        RAW_EXTENSION => BlobCompression::NoCompression,
+        _ => return None,
+    };
+    let id: [u8; 32] = hex::decode(path.file_stem()?.to_str()?)
+        .ok()?
+        .try_into()
+        .ok()?;
+    Some((BlobId(id), compression))
+}
+
+/// Whether `path` is named like the temporary files written by [DiskBlobStore::store], i.e.,
+/// `<blob id>.<counter>.tmp`.
+fn is_tmp_file_name(path: &Path) -> bool {
+    if path.extension().and_then(|extension| extension.to_str()) != Some(TMP_EXTENSION) {
+        return false;
+    }
+    let Some((blob_id, counter)) = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.split_once('.'))
+    else {
+        return false;
+    };
+    blob_id.len() == 64
+        && blob_id.bytes().all(|b| b.is_ascii_hexdigit())
+        && counter.parse::<u64>().is_ok()
+}
+
+#[async_trait]
+impl BlobStore for DiskBlobStore {
+    async fn store(&self, blob: BinaryBlob) -> BlobId {
+        let key = blob_id(&blob.data);
+        {
+            let mut index = self.index.lock().await;
+            let last_used = index.tick();
+            if let Some(entry) = index.entries.get_mut(&key) {
+                entry.last_used = last_used;
+                return key;
+            }
+        }
+
+        // Write to a temporary file first so that a crash never leaves a truncated blob behind.
+        // The index is not locked meanwhile, so that large blobs do not block other requests.
+        let path = blob_path(&self.dir, &key, blob.compression);
+        let tmp_path = self.dir.join(format!(
+            "{}.{}.{}",
+            hex::encode(key.0),
+            self.tmp_counter.fetch_add(1, Ordering::Relaxed),
+            TMP_EXTENSION
+        ));
+        tokio::fs::write(&tmp_path, &blob.data)
+            .await
+            .expect("Failed to write blob");
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .expect("Failed to write blob");
+
+        let mut index = self.index.lock().await;
+        let last_used = index.tick();
+        if let Some(entry) = index.entries.get_mut(&key) {
+            // A concurrent store of the same blob finished first.
+            entry.last_used = last_used;
+            return key;
+        }
+        let size = blob.data.len() as u64;
+        index.total_size += size;
+        index.entries.insert(
+            key.clone(),
+            BlobEntry {
+                size,
+                compression: blob.compression,
+                last_used,
+            },
+        );
+        index.evict(&self.dir, self.max_size_bytes, Some(&key));
+        key
+    }
+
+    async fn fetch(&self, blob_id: BlobId) -> Option<BinaryBlob> {
+        let compression = {
+            let mut index = self.index.lock().await;
+            let last_used = index.tick();
+            let entry = index.entries.get_mut(&blob_id)?;
+            entry.last_used = last_used;
+            entry.compression
+        };
+        // A blob evicted in the meantime is reported as missing.
+        let data = tokio::fs::read(blob_path(&self.dir, &blob_id, compression))
+            .await
+            .ok()?;
+        Some(BinaryBlob { data, compression })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn blob(data: &[u8]) -> BinaryBlob {
+        BinaryBlob {
+            data: data.to_vec(),
+            compression: BlobCompression::NoCompression,
+        }
+    }
+
+    #[tokio::test]
+    async fn should_keep_blobs_across_restarts() {
+        let dir = TempDir::new().unwrap();
+        let store = DiskBlobStore::new(dir.path().to_path_buf(), 1024).unwrap();
+        let gzipped = BinaryBlob {
+            data: vec![1, 2, 3],
+            compression: BlobCompression::Gzip,
+        };
+        let raw_id = store.store(blob(b"decafbad")).await;
+        let gzipped_id = store.store(gzipped).await;
+        drop(store);
+
+        let store = DiskBlobStore::new(dir.path().to_path_buf(), 1024).unwrap();
+        let raw = store.fetch(raw_id).await.unwrap();
+        assert_eq!(raw.data, b"decafbad");
+        assert_eq!(raw.compression, BlobCompression::NoCompression);
+        let gzipped = store.fetch(gzipped_id).await.unwrap();
+        assert_eq!(gzipped.data, vec![1, 2, 3]);
+        assert_eq!(gzipped.compression, BlobCompression::Gzip);
+    }
+
+    #[tokio::test]
+    async fn should_only_remove_own_leftovers_on_startup() {
+        let dir = TempDir::new().unwrap();
+        let leftover = dir.path().join(format!("{}.3.tmp", hex::encode([7; 32])));
+        let foreign = [
+            dir.path().join("notes.tmp"),
+            dir.path().join("README"),
+            dir.path().join(format!("{}.txt", hex::encode([7; 32]))),
+        ];
+        std::fs::write(&leftover, b"trunc").unwrap();
+        for path in &foreign {
+            std::fs::write(path, b"keep").unwrap();
+        }
+
+        let store = DiskBlobStore::new(dir.path().to_path_buf(), 1024).unwrap();
+        assert!(!leftover.exists());
+        for path in &foreign {
+            assert!(path.exists());
+        }
+        assert_eq!(store.index.lock().await.total_size, 0);
+    }
+
+    #[tokio::test]
+    async fn should_evict_least_recently_used_blobs() {
+        let dir = TempDir::new().unwrap();
+        let store = DiskBlobStore::new(dir.path().to_path_buf(), 8).unwrap();
+        let a = store.store(blob(b"aaaa")).await;
+        let b = store.store(blob(b"bbbb")).await;
+        // Using `a` makes `b` the least recently used blob.
+        assert!(store.fetch(a.clone()).await.is_some());
+        let c = store.store(blob(b"cccc")).await;
+
+        assert!(store.fetch(a).await.is_some());
+        assert!(store.fetch(b).await.is_none());
+        assert!(store.fetch(c).await.is_some());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_keep_a_blob_larger_than_the_cap_until_the_next_store() {
+        let dir = TempDir::new().unwrap();
+        let store = DiskBlobStore::new(dir.path().to_path_buf(), 2).unwrap();
+        let a = store.store(blob(b"aaaa")).await;
+        assert!(store.fetch(a.clone()).await.is_some());
+        store.store(blob(b"b")).await;
+        assert!(store.fetch(a).await.is_none());
+    }
+}
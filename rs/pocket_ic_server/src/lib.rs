@@ -17,8 +17,8 @@
 //! layer of the IC.
 //!
 //! A PocketIC is a deterministic state machine that emulates an instance of the Internet Computer.
-//! Currently, a PocketIC instance consists of at most one (system) subnet. In the future,
-//! multi-subnet support will be added.
+//! By default, a PocketIC instance consists of a single (system) subnet. Instances created with a
+//! topology consist of several subnets which exchange XNet messages after every round.
 //!
 //! The states of a PocketIC instance form a directed graph, where nodes are states and edges are
 //! computations. A computation is an operation on a given state (the source of the edge) resulting
@@ -34,6 +34,9 @@
 //! The start state is a dedicated state that always exists independent of which computations have
 //! been carried out. A state which has no outcoming computations is called a leaf.
 
+pub mod blob_store;
+pub mod nns;
+pub mod openapi;
 pub mod pocket_ic;
 pub mod state_api;
 
@@ -110,7 +113,7 @@ pub mod mocket_ic {
     use ::pocket_ic::WasmResult;
 
     use super::*;
-    use crate::state_api::state::{HasStateLabel, StateLabel};
+    use crate::state_api::state::{HasMemoryUsage, HasStateLabel, StateLabel};
 
     #[derive(Clone)]
     pub struct QueryOp {
@@ -235,18 +238,27 @@ pub mod mocket_ic {
             StateLabel(hasher.finish())
         }
     }
+
+    impl HasMemoryUsage for MocketIc {
+        fn memory_usage(&self) -> u64 {
+            self.state as u64
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::mocket_ic::*;
     use super::*;
-    use crate::pocket_ic::{CanisterCall, ExecuteIngressMessage, PocketIc};
+    use crate::pocket_ic::{
+        CanisterCall, ExecuteIngressMessage, InstallCanisterAsController, PocketIc,
+    };
     use crate::state_api::state::*;
     use ::pocket_ic::WasmResult;
     use candid::{decode_args, encode_args};
     use ic_cdk::api::management_canister::main::CreateCanisterArgument;
     use ic_cdk::api::management_canister::provisional::CanisterIdRecord;
+    use ic_ic00_types::CanisterInstallMode;
     use ic_types::{CanisterId, PrincipalId};
     use tokio::runtime::Runtime;
 
@@ -351,6 +363,228 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_max_instances() {
+        let rt = build_runtime();
+        let limits = InstanceLimits {
+            max_instances: Some(1),
+            ..Default::default()
+        };
+        let api_state = PocketIcApiStateBuilder::new()
+            .with_instance_limits(limits.clone())
+            .build();
+        assert_eq!(rt.block_on(api_state.add_instance(mocket_ic())), Ok(0));
+        assert_eq!(
+            rt.block_on(api_state.add_instance(mocket_ic())),
+            Err(::pocket_ic::common::rest::Quota::MaxInstances { max_instances: 1 })
+        );
+        rt.block_on(api_state.delete_instance(0));
+        assert_eq!(rt.block_on(api_state.add_instance(mocket_ic())), Ok(1));
+
+        let api_state = PocketIcApiStateBuilder::new()
+            .with_instance_limits(InstanceLimits {
+                evict_lru_instances: true,
+                ..limits
+            })
+            .build();
+        assert_eq!(rt.block_on(api_state.add_instance(mocket_ic())), Ok(0));
+        assert_eq!(rt.block_on(api_state.add_instance(mocket_ic())), Ok(1));
+        assert_eq!(api_state.live_instances(), vec![1]);
+    }
+
+    #[test]
+    fn test_memory_quota() {
+        let rt = build_runtime();
+        let api_state = PocketIcApiStateBuilder::new()
+            .with_instance_limits(InstanceLimits {
+                max_memory_bytes: Some(10),
+                ..Default::default()
+            })
+            .build();
+        let instance_id = rt.block_on(api_state.add_instance(mocket_ic())).unwrap();
+        let timeout = Some(Duration::from_secs(10));
+
+        let update = UpdateOp { payload: 11 };
+        rt.block_on(api_state.update_with_timeout(update.on_instance(instance_id), timeout))
+            .unwrap();
+        let Err(UpdateError::QuotaExceeded(quota)) =
+            rt.block_on(api_state.update(GetTime {}.on_instance(instance_id)))
+        else {
+            panic!("memory quota was not enforced")
+        };
+        assert_eq!(
+            quota,
+            ::pocket_ic::common::rest::Quota::MaxMemory {
+                memory_bytes: 11,
+                max_memory_bytes: 10
+            }
+        );
+    }
+
+    #[test]
+    fn test_idle_ttl() {
+        let rt = build_runtime();
+        let api_state = PocketIcApiStateBuilder::new()
+            .with_instance_limits(InstanceLimits {
+                idle_ttl: Some(Duration::from_millis(500)),
+                ..Default::default()
+            })
+            .build();
+        let idle = rt.block_on(api_state.add_instance(mocket_ic())).unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+        let used = rt.block_on(api_state.add_instance(mocket_ic())).unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+
+        assert_eq!(rt.block_on(api_state.delete_idle_instances()), vec![idle]);
+        assert_eq!(api_state.live_instances(), vec![used]);
+        assert!(matches!(
+            rt.block_on(api_state.update(GetTime {}.on_instance(idle))),
+            Err(UpdateError::InstanceDeleted)
+        ));
+    }
+
+    #[test]
+    fn test_instances_run_in_parallel() {
+        // Every instance executes an operation taking `DELAY`. Since operations on different
+        // instances do not wait for each other, executing them on all instances takes about
+        // `DELAY`, no matter how many instances there are, i.e., throughput scales linearly.
+        const DELAY: Duration = Duration::from_millis(500);
+        let rt = build_runtime();
+        let api_state = PocketIcApiStateBuilder::new().build();
+        for num_instances in [1, 2, 4, 8, 16] {
+            let instance_ids: Vec<_> = (0..num_instances)
+                .map(|_| rt.block_on(api_state.add_instance(mocket_ic())).unwrap())
+                .collect();
+
+            let start = std::time::Instant::now();
+            let results = rt.block_on(futures::future::join_all(instance_ids.iter().map(
+                |instance_id| {
+                    let delay = Delay { duration: DELAY };
+                    api_state.update_with_timeout(delay.on_instance(*instance_id), Some(20 * DELAY))
+                },
+            )));
+            let elapsed = start.elapsed();
+
+            for result in results {
+                assert_eq!(result.unwrap(), UpdateReply::Output(OpOut::NoOutput));
+            }
+            assert!(
+                elapsed < 2 * DELAY,
+                "{} instances took {:?} for operations of {:?} each",
+                num_instances,
+                elapsed,
+                DELAY
+            );
+            for instance_id in instance_ids {
+                rt.block_on(api_state.delete_instance(instance_id));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pocket_ic_instances_run_in_parallel() {
+        // Executing a long message on two instances at the same time takes clearly less than
+        // executing it on both, one after the other.
+        if std::thread::available_parallelism().map_or(1, |n| n.get()) < 2 {
+            return;
+        }
+        let rt = build_runtime();
+        let api_state = PocketIcApiStateBuilder::new().build();
+        let timeout = Some(Duration::from_secs(120));
+        let mut instances = vec![];
+        for _ in 0..2 {
+            let mut pocket_ic = PocketIc::default();
+            let create = ExecuteIngressMessage(CanisterCall {
+                sender: PrincipalId::default(),
+                canister_id: CanisterId::ic_00(),
+                method: "provisional_create_canister_with_cycles".to_string(),
+                payload: encode_args((CreateCanisterArgument { settings: None },)).unwrap(),
+            });
+            let OpOut::CanisterResult(Ok(WasmResult::Reply(bytes))) = create.compute(&mut pocket_ic)
+            else {
+                panic!("failed to create a canister");
+            };
+            let (CanisterIdRecord { canister_id },) = decode_args(&bytes).unwrap();
+            let canister_id = CanisterId::new(PrincipalId::from(canister_id)).unwrap();
+            let install = InstallCanisterAsController {
+                canister_id,
+                mode: CanisterInstallMode::Install,
+                module: wat::parse_str(SPIN_WAT).unwrap(),
+                payload: vec![],
+            };
+            assert_eq!(
+                install.compute(&mut pocket_ic),
+                OpOut::CanisterResult(Ok(WasmResult::Reply(vec![])))
+            );
+            let instance_id = rt.block_on(api_state.add_instance(pocket_ic)).unwrap();
+            instances.push((instance_id, canister_id));
+        }
+        let spin = |(instance_id, canister_id): (InstanceId, CanisterId)| {
+            let spin = ExecuteIngressMessage(CanisterCall {
+                sender: PrincipalId::default(),
+                canister_id,
+                method: "spin".to_string(),
+                payload: vec![],
+            });
+            let api_state = api_state.clone();
+            async move {
+                let result = api_state
+                    .update_with_timeout(spin.on_instance(instance_id), timeout)
+                    .await;
+                assert!(matches!(
+                    result,
+                    Ok(UpdateReply::Output(OpOut::CanisterResult(Ok(
+                        WasmResult::Reply(_)
+                    ))))
+                ));
+            }
+        };
+        // Warm up, so that neither measurement includes the compilation of the canister.
+        rt.block_on(futures::future::join_all(
+            instances.iter().copied().map(spin),
+        ));
+
+        let start = std::time::Instant::now();
+        for instance in instances.iter().copied() {
+            rt.block_on(spin(instance));
+        }
+        let sequential = start.elapsed();
+
+        let start = std::time::Instant::now();
+        rt.block_on(futures::future::join_all(
+            instances.iter().copied().map(spin),
+        ));
+        let parallel = start.elapsed();
+
+        assert!(
+            parallel.as_secs_f64() < 0.75 * sequential.as_secs_f64(),
+            "executing on two instances took {:?} in parallel and {:?} sequentially",
+            parallel,
+            sequential
+        );
+    }
+
+    // Counts down from 50M before replying.
+    const SPIN_WAT: &str = r#"
+(module
+  (import "ic0" "msg_reply" (func $msg_reply))
+  (func $spin
+    (local $i i32)
+    (local.set $i (i32.const 50000000))
+    (loop $loop
+      (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+      (br_if $loop (local.get $i)))
+    (call $msg_reply))
+  (export "canister_update spin" (func $spin))
+)"#;
+
+    fn mocket_ic() -> MocketIc {
+        MocketIc {
+            state: 0,
+            time: Time::from_nanos_since_unix_epoch(0),
+        }
+    }
+
     fn api_with_single_instance() -> (Runtime, PocketIcApiState<MocketIc>) {
         let rt = build_runtime();
         let mocket_ic = MocketIc {
@@ -1,5 +1,4 @@
 use axum::{
-    async_trait,
     extract::{DefaultBodyLimit, Path, State},
     http,
     http::{HeaderMap, StatusCode},
@@ -10,19 +9,29 @@ use axum::{
 };
 use clap::Parser;
 use ic_crypto_iccsa::{public_key_bytes_from_der, types::SignatureBytes, verify};
-use ic_crypto_sha2::Sha256;
+use ic_crypto_standalone_sig_verifier::{
+    user_public_key_from_bytes, verify_basic_sig_by_public_key, KeyBytesContentType,
+};
 use ic_crypto_utils_threshold_sig_der::parse_threshold_sig_key_from_der;
+use ic_types::crypto::Signable;
+use ic_types::messages::{Delegation, MessageId};
+use ic_types::{CanisterId, Time};
+use ic_utils::fs::{FileLock, LockMode};
 use pocket_ic::common::{
     blob::{BinaryBlob, BlobCompression, BlobId},
-    rest::RawVerifyCanisterSigArg,
+    rest::{ApiResponse, RawVerifyCanisterSigArg, RawVerifyRequestSigArg, RawVerifySignatureArg},
 };
+use pocket_ic_server::blob_store::{DiskBlobStore, InMemoryBlobStore};
 use pocket_ic_server::state_api::{
-    routes::{instances_routes, status, AppState, RouterExt},
-    state::PocketIcApiStateBuilder,
+    routes::{instances_routes, remove_deleted_instance_events, status, AppState, RouterExt},
+    state::{InstanceLimits, PocketIcApiStateBuilder},
 };
 use pocket_ic_server::BlobStore;
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::{collections::HashMap, sync::atomic::AtomicU64};
 use tokio::runtime::Runtime;
@@ -41,6 +50,14 @@ const DEFAULT_LOG_LEVELS: &str = "pocket_ic_server=info,tower_http=info,axum::re
 
 const LOG_DIR_PATH_ENV_NAME: &str = "POCKET_IC_LOG_DIR";
 const LOG_DIR_LEVELS_ENV_NAME: &str = "POCKET_IC_LOG_DIR_LEVELS";
+// The auth token if `--auth-token-file` is not given. Clients read the same variable, so that
+// servers they start require the token they send.
+const AUTH_TOKEN_ENV_NAME: &str = "POCKET_IC_AUTH_TOKEN";
+// The NNS Wasm directory if `--nns-wasm-dir` is not given. Servers started by clients inherit
+// their environment, so tests can bootstrap the NNS without starting the server themselves.
+const NNS_WASM_DIR_ENV_NAME: &str = "POCKET_IC_NNS_WASM_DIR";
+
+const DEFAULT_BLOB_STORE_MAX_SIZE_BYTES: u64 = 10 * 1024 * 1024 * 1024;
 
 /// Command line arguments to PocketIC server.
 #[derive(Parser)]
@@ -48,26 +65,92 @@ struct Args {
     /// A common identifier for all clients that use this instance of a PocketIC-server. In
     /// general, this is assumed to be the PID of the parent process of the test process. Thus, all
     /// tests of a `cargo test`-invocation (re-)use the same PocketIC-server instance.
+    /// If not specified, the server skips the port file handshake and must be reached at the
+    /// address given by `--ip` and `--port`.
+    #[clap(long, required_unless_present = "port")]
+    pid: Option<u32>,
+    /// The IP address the server binds to.
+    #[clap(long, default_value = "127.0.0.1")]
+    ip: IpAddr,
+    /// The port the server binds to. If not specified, the OS picks a free port which is
+    /// communicated through the port file.
+    #[clap(long)]
+    port: Option<u16>,
+    /// A directory in which the blob store keeps its blobs, so that they survive server restarts.
+    /// If not specified, blobs are only kept in memory.
+    #[clap(long)]
+    blob_store_dir: Option<PathBuf>,
+    /// The maximum total size of the blobs kept in the blob store directory. Once exceeded, the
+    /// least recently used blobs are evicted.
+    #[clap(long, default_value_t = DEFAULT_BLOB_STORE_MAX_SIZE_BYTES)]
+    blob_store_max_size_bytes: u64,
+    /// Instances that have not been used for this many seconds are deleted.
+    #[clap(long)]
+    instance_idle_ttl_secs: Option<u64>,
+    /// The maximum number of instances. Creating further instances fails with status code 429.
+    #[clap(long)]
+    max_instances: Option<usize>,
+    /// The maximum memory an instance may use. Once exceeded, requests to the instance fail with
+    /// status code 429.
     #[clap(long)]
-    pid: u32,
+    max_instance_memory_bytes: Option<u64>,
+    /// Instead of failing, creating an instance beyond `--max-instances` deletes the least
+    /// recently used instance.
+    #[clap(long, requires = "max_instances")]
+    evict_lru_instances: bool,
+    /// A file containing a token that requests to all routes but `/status` must carry as
+    /// `Authorization: Bearer <token>`. If not specified, the token is taken from the
+    /// `POCKET_IC_AUTH_TOKEN` environment variable, and if that is not set either, requests are
+    /// not authenticated.
+    #[clap(long)]
+    auth_token_file: Option<PathBuf>,
+    /// A directory containing the NNS canister Wasms that instances created with
+    /// `?bootstrap=nns` install: `registry-canister.wasm`, `governance-canister.wasm`,
+    /// `ledger-canister.wasm`, `cycles-minting-canister.wasm` and `root-canister.wasm`. If not
+    /// specified, the directory is taken from the `POCKET_IC_NNS_WASM_DIR` environment variable,
+    /// and if that is not set either, instances cannot bootstrap the NNS.
+    #[clap(long)]
+    nns_wasm_dir: Option<PathBuf>,
 }
 
 impl Args {
     fn validate(self) -> ValidatedArgs {
-        ValidatedArgs { pid: self.pid }
+        ValidatedArgs {
+            pid: self.pid,
+            addr: SocketAddr::new(self.ip, self.port.unwrap_or(0)),
+            blob_store_dir: self.blob_store_dir,
+            blob_store_max_size_bytes: self.blob_store_max_size_bytes,
+            instance_limits: InstanceLimits {
+                idle_ttl: self.instance_idle_ttl_secs.map(Duration::from_secs),
+                max_instances: self.max_instances,
+                max_memory_bytes: self.max_instance_memory_bytes,
+                evict_lru_instances: self.evict_lru_instances,
+            },
+            auth_token_file: self.auth_token_file,
+            nns_wasm_dir: self
+                .nns_wasm_dir
+                .or_else(|| std::env::var_os(NNS_WASM_DIR_ENV_NAME).map(PathBuf::from)),
+        }
     }
 }
 
 struct ValidatedArgs {
-    pub pid: u32,
+    pub pid: Option<u32>,
+    pub addr: SocketAddr,
+    pub blob_store_dir: Option<PathBuf>,
+    pub blob_store_max_size_bytes: u64,
+    pub instance_limits: InstanceLimits,
+    pub auth_token_file: Option<PathBuf>,
+    pub nns_wasm_dir: Option<PathBuf>,
 }
 
 fn main() {
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .worker_threads(16)
-        // we use the tokio rt to dispatch blocking operations in the background
-        .max_blocking_threads(16)
+        // We use the tokio rt to dispatch blocking operations in the background. An instance
+        // runs at most one blocking operation at a time, so the blocking threads are not
+        // capped: operations on different instances then never queue behind each other.
         .build()
         .expect("Failed to create tokio runtime!");
     let runtime_arc = Arc::new(rt);
@@ -79,37 +162,98 @@ async fn start(runtime: Arc<Runtime>) {
     // If log-dir is specified, a background thread is started that writes logs into the files in
     // batches. This guard ensures that at the end of the process execution, the buffer is flushed
     // to disk.
-    let port_file_path = std::env::temp_dir().join(format!("pocket_ic_{}.port", args.pid));
-    let ready_file_path = std::env::temp_dir().join(format!("pocket_ic_{}.ready", args.pid));
-    let mut new_port_file = match is_first_server(&port_file_path) {
-        Ok(f) => f,
-        Err(_) => {
-            return;
-        }
-    };
+    let mut port_files = None;
+    if let Some(pid) = args.pid {
+        let port_file_path = std::env::temp_dir().join(format!("pocket_ic_{}.port", pid));
+        let ready_file_path = std::env::temp_dir().join(format!("pocket_ic_{}.ready", pid));
+        // Only the first server for a PID gets the lock on the port file; the lock is released
+        // when this process exits, even if it crashes, so stale files never block a new server.
+        match FileLock::try_lock(&port_file_path, LockMode::Exclusive) {
+            Ok(Some(lock)) => {
+                // Clean up after a previous server for the same PID that did not shut down
+                // gracefully.
+                let _ = std::fs::remove_file(&ready_file_path);
+                if lock.file().set_len(0).is_err() {
+                    return;
+                }
+                port_files = Some((port_file_path, ready_file_path, lock));
+            }
+            Ok(None) | Err(_) => {
+                return;
+            }
+        };
+    }
     // This process is the one to start PocketIC.
     let _guard = setup_tracing(&args);
 
     // The shared, mutable state of the PocketIC process.
-    let api_state = PocketIcApiStateBuilder::default().build();
-    let instance_map = Arc::new(RwLock::new(HashMap::new()));
+    let api_state = PocketIcApiStateBuilder::default()
+        .with_instance_limits(args.instance_limits.clone())
+        .build();
     // A time-to-live mechanism: Requests bump this value, and the server
     // gracefully shuts down when the value wasn't bumped for a while
     let last_request = Arc::new(RwLock::new(Instant::now()));
+    let blob_store: Arc<dyn BlobStore> = match &args.blob_store_dir {
+        Some(dir) => match DiskBlobStore::new(dir.clone(), args.blob_store_max_size_bytes) {
+            Ok(store) => Arc::new(store),
+            Err(e) => {
+                error!(
+                    "Failed to open blob store directory {}: {}",
+                    dir.display(),
+                    e
+                );
+                remove_port_files(&port_files);
+                return;
+            }
+        },
+        None => Arc::new(InMemoryBlobStore::new()),
+    };
+    let auth_token = match &args.auth_token_file {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(token) => Some(token.trim().to_string()),
+            Err(e) => {
+                error!("Failed to read auth token file {}: {}", path.display(), e);
+                remove_port_files(&port_files);
+                return;
+            }
+        },
+        None => std::env::var(AUTH_TOKEN_ENV_NAME).ok(),
+    };
+    if auth_token.as_deref() == Some("") {
+        error!("The auth token must not be empty");
+        remove_port_files(&port_files);
+        return;
+    }
     let app_state = AppState {
-        instance_map,
         instances_sequence_counter: Arc::new(AtomicU64::from(0)),
         api_state,
         checkpoints: Arc::new(RwLock::new(HashMap::new())),
         last_request,
         runtime,
-        blob_store: Arc::new(InMemoryBlobStore::new()),
+        blob_store,
+        instance_events: Arc::new(RwLock::new(HashMap::new())),
+        nns_wasm_dir: args.nns_wasm_dir.clone(),
     };
 
+    if args.instance_limits.idle_ttl.is_some() {
+        let AppState {
+            api_state,
+            instance_events,
+            ..
+        } = app_state.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let deleted = api_state.delete_idle_instances().await;
+                if !deleted.is_empty() {
+                    info!("Deleted idle instances {:?}", deleted);
+                    remove_deleted_instance_events(&api_state, &instance_events).await;
+                }
+            }
+        });
+    }
+
     let app = Router::new()
-        //
-        // Get server health.
-        .directory_route("/status", get(status))
         //
         // Set a blob store entry.
         .directory_route("/blobstore", post(set_blob_store_entry))
@@ -124,7 +268,22 @@ async fn start(runtime: Arc<Runtime>) {
         .nest("/instances", instances_routes::<AppState>())
         //
         // List all checkpoints.
-        .directory_route("/checkpoints", get(list_checkpoints))
+        .directory_route("/checkpoints", get(list_checkpoints));
+    // Only applies to the routes above.
+    let app = match auth_token {
+        Some(auth_token) => app.route_layer(middleware::from_fn_with_state(
+            Arc::new(auth_token),
+            require_auth_token,
+        )),
+        None => app,
+    };
+    let app = app
+        //
+        // Get server health.
+        .directory_route("/status", get(status))
+        //
+        // Get the OpenAPI description of all routes.
+        .directory_route("/api.json", get(api_description))
         .layer(DefaultBodyLimit::disable())
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
@@ -135,23 +294,31 @@ async fn start(runtime: Arc<Runtime>) {
         .layer(TraceLayer::new_for_http())
         .with_state(app_state.clone());
 
-    // bind to port 0; the OS will give a specific port; communicate that to parent process
-    let server = Server::bind(&"127.0.0.1:0".parse().expect("Failed to parse address"))
-        .serve(app.into_make_service());
+    // If no port is given, bind to port 0; the OS will give a specific port; communicate that to
+    // parent process.
+    let server = match Server::try_bind(&args.addr) {
+        Ok(builder) => builder.serve(app.into_make_service()),
+        Err(e) => {
+            error!("Failed to bind to {}: {}", args.addr, e);
+            remove_port_files(&port_files);
+            return;
+        }
+    };
     let real_port = server.local_addr().port();
-    let _ = new_port_file.write_all(real_port.to_string().as_bytes());
-    let _ = new_port_file.flush();
-
-    let ready_file = File::options()
-        .read(true)
-        .write(true)
-        .create_new(true)
-        .open(&ready_file_path);
-    if ready_file.is_ok() {
-        info!("The PocketIC server is listening on port {}", real_port);
-    } else {
-        error!("The .ready file already exists; This should not happen unless the PID has been reused, and/or the tmp dir has not been properly cleaned up");
+    if let Some((_, ready_file_path, port_file_lock)) = port_files.as_ref() {
+        let mut port_file = port_file_lock.file();
+        let _ = port_file.write_all(real_port.to_string().as_bytes());
+        let _ = port_file.flush();
+
+        // The ready file signals that the port file is complete.
+        if let Err(e) = File::create(ready_file_path) {
+            error!("Failed to create the .ready file: {}", e);
+        }
     }
+    info!(
+        "The PocketIC server is listening on {}",
+        server.local_addr()
+    );
 
     // This is a safeguard against orphaning this child process.
     let shutdown_signal = async {
@@ -164,9 +331,7 @@ async fn start(runtime: Arc<Runtime>) {
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
         info!("The PocketIC server will terminate");
-        // Clean up tmpfiles.
-        let _ = std::fs::remove_file(ready_file_path);
-        let _ = std::fs::remove_file(port_file_path);
+        remove_port_files(&port_files);
     };
     let server = server.with_graceful_shutdown(shutdown_signal);
     server.await.expect("Failed to launch the PocketIC server");
@@ -194,10 +359,13 @@ fn setup_tracing(args: &ValidatedArgs) -> Option<WorkerGuard> {
     let guard = match std::env::var(LOG_DIR_PATH_ENV_NAME).map(std::path::PathBuf::from) {
         Ok(p) => {
             std::fs::create_dir_all(&p).expect("Could not create directory!");
-            let pid = args.pid;
+            let id = match args.pid {
+                Some(pid) => pid.to_string(),
+                None => format!("port_{}", args.addr.port()),
+            };
             let dt = OffsetDateTime::from(std::time::SystemTime::now());
             let ts = dt.format(&Rfc3339).unwrap().replace(':', "_");
-            let appender = tracing_appender::rolling::never(&p, format!("{ts}_pocket_ic_{pid}"));
+            let appender = tracing_appender::rolling::never(&p, format!("{ts}_pocket_ic_{id}"));
             let (non_blocking_appender, guard) = tracing_appender::non_blocking(appender);
 
             let log_dir_filter: EnvFilter =
@@ -221,20 +389,16 @@ fn setup_tracing(args: &ValidatedArgs) -> Option<WorkerGuard> {
     guard
 }
 
-/// Returns the opened file if it was successfully created and is readable, writeable. Otherwise,
-/// returns an error. Used to determine if this is the first process creating this file.
-fn is_first_server<P: AsRef<std::path::Path>>(port_file_path: P) -> std::io::Result<File> {
-    // .create_new(true) ensures atomically that this file was created newly, and gives an error otherwise.
-    File::options()
-        .read(true)
-        .write(true)
-        .create_new(true)
-        .open(&port_file_path)
+// Clean up tmpfiles, so that a new server can be started for the same PID.
+fn remove_port_files(port_files: &Option<(PathBuf, PathBuf, FileLock)>) {
+    if let Some((port_file_path, ready_file_path, _)) = port_files {
+        let _ = std::fs::remove_file(ready_file_path);
+        let _ = std::fs::remove_file(port_file_path);
+    }
 }
 
 async fn bump_last_request_timestamp<B>(
     State(AppState {
-        instance_map: _,
         instances_sequence_counter: _,
         api_state: _,
         checkpoints: _,
@@ -248,6 +412,36 @@ async fn bump_last_request_timestamp<B>(
     next.run(request).await
 }
 
+async fn require_auth_token<B>(
+    State(auth_token): State<Arc<String>>,
+    request: http::Request<B>,
+    next: Next<B>,
+) -> Response {
+    let authorized = request
+        .headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), auth_token.as_bytes()));
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(http::header::WWW_AUTHENTICATE, "Bearer")],
+            Json(ApiResponse::<()>::Error {
+                message: "Missing or invalid auth token".to_string(),
+            }),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+// Compares in time independent of where the inputs differ, so that the token cannot be guessed
+// byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 async fn get_blob_store_entry(
     State(AppState { blob_store, .. }): State<AppState>,
     Path(id): Path<String>,
@@ -314,6 +508,10 @@ async fn set_blob_store_entry(
     (StatusCode::OK, blob_id)
 }
 
+pub async fn api_description() -> Json<serde_json::Value> {
+    Json(pocket_ic_server::openapi::api_description())
+}
+
 pub async fn list_checkpoints(
     State(AppState { checkpoints, .. }): State<AppState>,
 ) -> Json<Vec<String>> {
@@ -327,12 +525,21 @@ pub async fn list_checkpoints(
 }
 
 pub async fn verify_signature(
-    axum::extract::Json(RawVerifyCanisterSigArg {
+    axum::extract::Json(arg): axum::extract::Json<RawVerifySignatureArg>,
+) -> (StatusCode, Json<Result<(), String>>) {
+    match arg {
+        RawVerifySignatureArg::CanisterSig(arg) => verify_canister_signature(arg),
+        RawVerifySignatureArg::RequestSig(arg) => verify_request_signature(arg),
+    }
+}
+
+fn verify_canister_signature(
+    RawVerifyCanisterSigArg {
         msg,
         sig,
         pubkey,
         root_pubkey,
-    }): axum::extract::Json<RawVerifyCanisterSigArg>,
+    }: RawVerifyCanisterSigArg,
 ) -> (StatusCode, Json<Result<(), String>>) {
     match public_key_bytes_from_der(&pubkey) {
         Ok(pubkey) => match parse_threshold_sig_key_from_der(&root_pubkey) {
@@ -364,32 +571,127 @@ pub async fn verify_signature(
     }
 }
 
-// -------------------------------------------------------------------------------------
-struct InMemoryBlobStore {
-    map: RwLock<HashMap<BlobId, BinaryBlob>>,
-}
-
-impl InMemoryBlobStore {
-    pub fn new() -> Self {
-        InMemoryBlobStore {
-            map: RwLock::new(HashMap::new()),
+/// Verifies the signature of an IC request id the way a replica authenticates a request: the
+/// delegations must form an unexpired chain rooted at the sender public key, and the last key
+/// of the chain must have signed the request id.
+fn verify_request_signature(
+    RawVerifyRequestSigArg {
+        request_id,
+        sig,
+        sender_pubkey,
+        delegations,
+        root_pubkey,
+        current_time_nanos,
+    }: RawVerifyRequestSigArg,
+) -> (StatusCode, Json<Result<(), String>>) {
+    let message_id = match MessageId::try_from(request_id.as_slice()) {
+        Ok(message_id) => message_id,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(Err(format!("Invalid request id: {}", err))),
+            )
         }
+    };
+    let current_time = current_time_nanos
+        .map(Time::from_nanos_since_unix_epoch)
+        .unwrap_or_else(ic_types::time::current_time);
+
+    let mut pubkey = sender_pubkey;
+    let mut seen_pubkeys = BTreeSet::from([pubkey.clone()]);
+    for (i, signed_delegation) in delegations.unwrap_or_default().into_iter().enumerate() {
+        let expiration = Time::from_nanos_since_unix_epoch(signed_delegation.expiration_nanos);
+        let delegation = match signed_delegation.targets {
+            None => Delegation::new(signed_delegation.pubkey.clone(), expiration),
+            Some(targets) => match targets
+                .into_iter()
+                .map(|target| CanisterId::try_from(target.canister_id))
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(targets) => Delegation::new_with_targets(
+                    signed_delegation.pubkey.clone(),
+                    expiration,
+                    targets,
+                ),
+                Err(err) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(Err(format!("Invalid target of delegation {}: {}", i, err))),
+                    )
+                }
+            },
+        };
+        if expiration < current_time {
+            return (
+                StatusCode::NOT_ACCEPTABLE,
+                Json(Err(format!(
+                    "Delegation {} expired at {}, current time is {}",
+                    i, expiration, current_time
+                ))),
+            );
+        }
+        if !seen_pubkeys.insert(signed_delegation.pubkey.clone()) {
+            return (
+                StatusCode::NOT_ACCEPTABLE,
+                Json(Err(format!(
+                    "Delegation {} closes a cycle in the chain of delegations",
+                    i
+                ))),
+            );
+        }
+        if let Err(err) = verify_user_signature(
+            &delegation.as_signed_bytes(),
+            &signed_delegation.signature,
+            &pubkey,
+            &root_pubkey,
+        ) {
+            return (
+                StatusCode::NOT_ACCEPTABLE,
+                Json(Err(format!(
+                    "Signature verification of delegation {} failed: {}",
+                    i, err
+                ))),
+            );
+        }
+        pubkey = signed_delegation.pubkey;
     }
-}
 
-#[async_trait]
-impl BlobStore for InMemoryBlobStore {
-    async fn store(&self, blob: BinaryBlob) -> BlobId {
-        let mut hasher = Sha256::new();
-        hasher.write(&blob.data);
-        let key = BlobId(hasher.finish());
-        let mut m = self.map.write().await;
-        m.insert(key.clone(), blob);
-        key
+    match verify_user_signature(&message_id.as_signed_bytes(), &sig, &pubkey, &root_pubkey) {
+        Ok(()) => (StatusCode::OK, Json(Ok(()))),
+        Err(err) => (
+            StatusCode::NOT_ACCEPTABLE,
+            Json(Err(format!(
+                "Request signature verification failed: {}",
+                err
+            ))),
+        ),
     }
+}
 
-    async fn fetch(&self, blob_id: BlobId) -> Option<BinaryBlob> {
-        let m = self.map.read().await;
-        m.get(&blob_id).cloned()
+/// Verifies a signature by a DER encoded user public key, which is an Ed25519, ECDSA or
+/// canister signature key.
+fn verify_user_signature(
+    msg: &[u8],
+    sig: &[u8],
+    pubkey: &[u8],
+    root_pubkey: &[u8],
+) -> Result<(), String> {
+    let (user_pubkey, content_type) =
+        user_public_key_from_bytes(pubkey).map_err(|err| err.to_string())?;
+    match content_type {
+        KeyBytesContentType::Ed25519PublicKeyDer
+        | KeyBytesContentType::EcdsaP256PublicKeyDer
+        | KeyBytesContentType::EcdsaSecp256k1PublicKeyDer => {
+            verify_basic_sig_by_public_key(user_pubkey.algorithm_id, msg, sig, &user_pubkey.key)
+                .map_err(|err| err.to_string())
+        }
+        KeyBytesContentType::IcCanisterSignatureAlgPublicKeyDer => {
+            let root_pubkey = parse_threshold_sig_key_from_der(root_pubkey)
+                .map_err(|err| format!("Failed to parse DER encoded root public key: {:?}", err))?;
+            let pubkey = public_key_bytes_from_der(pubkey).map_err(|err| err.to_string())?;
+            verify(msg, SignatureBytes(sig.to_vec()), pubkey, &root_pubkey)
+                .map_err(|err| err.to_string())
+        }
+        content_type => Err(format!("Unsupported public key type {:?}", content_type)),
     }
 }
@@ -0,0 +1,175 @@
+//! Bootstraps a minimal NNS (registry, governance, ledger, cycles minting canister and root) on
+//! the NNS subnet of an instance, so that SNS and cycles flows can be tested against PocketIC
+//! without each test setting up the NNS itself.
+//!
+//! The canister Wasms are not compiled into the server; they are read from the directory given
+//! by `--nns-wasm-dir` (or the `POCKET_IC_NNS_WASM_DIR` environment variable), which must contain
+//! the files listed in [NNS_WASM_FILES].
+
+use crate::pocket_ic::PocketIc;
+use candid::Encode;
+use cycles_minting_canister::CyclesCanisterInitPayload;
+use ic_ic00_types::CanisterSettingsArgsBuilder;
+use ic_nns_constants::{
+    ALL_NNS_CANISTER_IDS, CYCLES_MINTING_CANISTER_ID, GOVERNANCE_CANISTER_ID, LEDGER_CANISTER_ID,
+    LIFELINE_CANISTER_ID, REGISTRY_CANISTER_ID, ROOT_CANISTER_ID,
+};
+use ic_nns_governance::init::GovernanceCanisterInitPayloadBuilder;
+use ic_nns_handler_root::init::RootCanisterInitPayloadBuilder;
+use ic_state_machine_tests::Cycles;
+use ic_types::{CanisterId, PrincipalId};
+use icp_ledger::{
+    AccountIdentifier, LedgerCanisterInitPayload, Subaccount, Tokens, DEFAULT_TRANSFER_FEE,
+};
+use prost::Message;
+use registry_canister::init::RegistryCanisterInitPayloadBuilder;
+use std::path::Path;
+
+/// The files that the NNS Wasm directory must contain, in the order of the fields of [NnsWasms].
+pub const NNS_WASM_FILES: [&str; 5] = [
+    "registry-canister.wasm",
+    "governance-canister.wasm",
+    "ledger-canister.wasm",
+    "cycles-minting-canister.wasm",
+    "root-canister.wasm",
+];
+
+/// Cycles balance of each NNS canister. NNS canisters are not charged for their resources,
+/// but a balance keeps them working if an instance is configured with non-zero fees.
+const NNS_CANISTER_CYCLES: u128 = 100_000_000_000_000;
+
+/// The Wasms of the NNS canisters installed by [install_nns].
+pub struct NnsWasms {
+    registry: Vec<u8>,
+    governance: Vec<u8>,
+    ledger: Vec<u8>,
+    cycles_minting: Vec<u8>,
+    root: Vec<u8>,
+}
+
+impl NnsWasms {
+    /// Reads the Wasms from the given directory.
+    /// Returns an error listing every file that is missing or unreadable.
+    pub fn from_dir(dir: &Path) -> Result<Self, String> {
+        let mut defects = vec![];
+        let mut read = |file: &str| {
+            let path = dir.join(file);
+            std::fs::read(&path).unwrap_or_else(|e| {
+                defects.push(format!("failed to read {}: {}", path.display(), e));
+                vec![]
+            })
+        };
+        let [registry, governance, ledger, cycles_minting, root] = NNS_WASM_FILES;
+        let wasms = Self {
+            registry: read(registry),
+            governance: read(governance),
+            ledger: read(ledger),
+            cycles_minting: read(cycles_minting),
+            root: read(root),
+        };
+        if !defects.is_empty() {
+            return Err(format!(
+                "Cannot bootstrap the NNS, the server is missing the NNS canister Wasms:\n  -{}",
+                defects.join("\n  -")
+            ));
+        }
+        Ok(wasms)
+    }
+}
+
+/// Installs the NNS canisters at their well-known ids, with the NNS root (or, for root, the
+/// lifeline canister) as controller. Governance starts with the test neurons, whose stakes the
+/// ledger holds, and the registry starts empty. The instance must have an NNS subnet, i.e., it
+/// must have been created with [pocket_ic::common::rest::Topology::nns].
+///
+/// Fails if one of the canisters cannot be installed, e.g. because its Wasm is invalid.
+pub fn install_nns(pic: &PocketIc, wasms: NnsWasms) -> Result<(), String> {
+    let governance = GovernanceCanisterInitPayloadBuilder::new()
+        .with_test_neurons()
+        .build();
+    let neuron_stakes = governance
+        .neurons
+        .values()
+        .map(|neuron| {
+            let subaccount = Subaccount::try_from(neuron.account.as_slice())
+                .expect("Neuron subaccounts must be 32 bytes long");
+            (
+                AccountIdentifier::new(GOVERNANCE_CANISTER_ID.get(), Some(subaccount)),
+                Tokens::from_e8s(neuron.cached_neuron_stake_e8s),
+            )
+        })
+        .collect();
+    let ledger = LedgerCanisterInitPayload::builder()
+        .minting_account(GOVERNANCE_CANISTER_ID.get().into())
+        .initial_values(neuron_stakes)
+        .send_whitelist(ALL_NNS_CANISTER_IDS.iter().map(|&id| *id).collect())
+        .transfer_fee(DEFAULT_TRANSFER_FEE)
+        .build()
+        .unwrap();
+    let cycles_minting = Some(CyclesCanisterInitPayload {
+        ledger_canister_id: Some(LEDGER_CANISTER_ID),
+        governance_canister_id: Some(GOVERNANCE_CANISTER_ID),
+        exchange_rate_canister: None,
+        minting_account_id: Some(GOVERNANCE_CANISTER_ID.get().into()),
+        last_purged_notification: Some(1),
+    });
+
+    let canisters = [
+        (
+            REGISTRY_CANISTER_ID,
+            wasms.registry,
+            Encode!(&RegistryCanisterInitPayloadBuilder::new().build()).unwrap(),
+            ROOT_CANISTER_ID,
+        ),
+        (
+            GOVERNANCE_CANISTER_ID,
+            wasms.governance,
+            governance.encode_to_vec(),
+            ROOT_CANISTER_ID,
+        ),
+        (
+            LEDGER_CANISTER_ID,
+            wasms.ledger,
+            Encode!(&ledger).unwrap(),
+            ROOT_CANISTER_ID,
+        ),
+        (
+            CYCLES_MINTING_CANISTER_ID,
+            wasms.cycles_minting,
+            Encode!(&cycles_minting).unwrap(),
+            ROOT_CANISTER_ID,
+        ),
+        (
+            ROOT_CANISTER_ID,
+            wasms.root,
+            Encode!(&RootCanisterInitPayloadBuilder::new().build()).unwrap(),
+            LIFELINE_CANISTER_ID,
+        ),
+    ];
+    for (canister_id, wasm, payload, controller) in canisters {
+        install_canister(pic, canister_id, wasm, payload, controller.get())?;
+    }
+    Ok(())
+}
+
+fn install_canister(
+    pic: &PocketIc,
+    canister_id: CanisterId,
+    wasm: Vec<u8>,
+    payload: Vec<u8>,
+    controller: PrincipalId,
+) -> Result<(), String> {
+    let subnet = pic.subnet_for(canister_id);
+    subnet.create_canister_with_cycles(
+        Some(canister_id.get()),
+        Cycles::new(NNS_CANISTER_CYCLES),
+        Some(
+            CanisterSettingsArgsBuilder::new()
+                .with_controllers(vec![controller])
+                .build(),
+        ),
+    );
+    subnet
+        .install_existing_canister(canister_id, wasm, payload)
+        .map_err(|e| format!("Failed to install the NNS canister {}: {}", canister_id, e))
+}
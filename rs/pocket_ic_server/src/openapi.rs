@@ -0,0 +1,940 @@
+//! The OpenAPI description of the REST interface of the PocketIC server, served at `/api.json`
+//! so that clients in other languages can be generated from it.
+//!
+//! The document mirrors the routes of the server and the JSON encoding of the types in
+//! `pocket_ic::common::rest`; a route or type added there has to be described here as well.
+//! Binary data is base64 encoded unless stated otherwise, and enums use serde's default
+//! encoding, i.e., an object with the variant name as its only key.
+
+use serde_json::{json, Value};
+
+pub fn api_description() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "PocketIC server",
+            "description": "A local, deterministic emulation of the Internet Computer for testing canisters.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        // The bearer token is only required if the server was started with one.
+        "security": [{}, { "bearerAuth": [] }],
+        "paths": paths(),
+        "components": {
+            "schemas": schemas(),
+            "parameters": {
+                "InstanceId": {
+                    "name": "id",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "integer", "minimum": 0 },
+                },
+                "ProcessingTimeout": {
+                    "name": "processing-timeout-ms",
+                    "in": "header",
+                    "required": false,
+                    "description": "How long to wait for the operation before answering with 202, in which case the request can be repeated to get the result.",
+                    "schema": { "type": "integer", "minimum": 0 },
+                },
+            },
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer" },
+            },
+        },
+    })
+}
+
+fn schema(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn array_of(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn base64() -> Value {
+    json!({ "type": "string", "format": "byte" })
+}
+
+/// An object with the given properties, all of which are required.
+fn object(properties: Value) -> Value {
+    let required: Vec<String> = properties.as_object().unwrap().keys().cloned().collect();
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+/// A serde enum variant with data, encoded as an object with the variant name as its only key.
+fn variant(name: &str, data: Value) -> Value {
+    object(json!({ name: data }))
+}
+
+fn entries<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Object(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+/// The responses of an operation on an instance, i.e., the encodings of an `ApiResponse` with
+/// the given schema of the result.
+fn instance_operation_responses(response: Value) -> Value {
+    json!({
+        "200": { "description": "The result of the operation.", "content": json_content(response) },
+        "202": {
+            "description": "The operation has started but not completed within the processing timeout.",
+            "content": json_content(schema("StartedOrBusyResponse")),
+        },
+        "400": {
+            "description": "The request is invalid or the operation failed.",
+            "content": json_content(schema("ApiError")),
+        },
+        "409": {
+            "description": "The instance is busy with another operation.",
+            "content": json_content(schema("StartedOrBusyResponse")),
+        },
+        "429": {
+            "description": "The operation exceeds a quota of the server.",
+            "content": json_content(schema("QuotaExceeded")),
+        },
+    })
+}
+
+fn instance_operation(summary: &str, request: Option<Value>, response: Value) -> Value {
+    let mut operation = json!({
+        "summary": summary,
+        "parameters": [
+            { "$ref": "#/components/parameters/InstanceId" },
+            { "$ref": "#/components/parameters/ProcessingTimeout" },
+        ],
+        "responses": instance_operation_responses(response),
+    });
+    if let Some(request) = request {
+        operation["requestBody"] = json!({ "required": true, "content": json_content(request) });
+    }
+    operation
+}
+
+fn create_instance_responses() -> Value {
+    json!({
+        "201": {
+            "description": "The instance has been created.",
+            "content": json_content(schema("CreateInstanceResponse")),
+        },
+        "400": {
+            "description": "The instance cannot be created.",
+            "content": json_content(schema("CreateInstanceResponse")),
+        },
+        "429": {
+            "description": "The server manages the maximum number of instances.",
+            "content": json_content(schema("CreateInstanceResponse")),
+        },
+    })
+}
+
+fn paths() -> Value {
+    let unit = json!({ "type": "object", "nullable": true });
+    entries([
+        (
+            "/status",
+            json!({
+                "get": {
+                    "summary": "Checks that the server is up. Never requires authentication.",
+                    "security": [],
+                    "responses": { "200": { "description": "The server is up." } },
+                },
+            }),
+        ),
+        (
+            "/api.json",
+            json!({
+                "get": {
+                    "summary": "Returns this document. Never requires authentication.",
+                    "security": [],
+                    "responses": {
+                        "200": {
+                            "description": "The OpenAPI description of the server.",
+                            "content": json_content(json!({ "type": "object" })),
+                        },
+                    },
+                },
+            }),
+        ),
+        (
+            "/blobstore",
+            json!({
+                "post": {
+                    "summary": "Stores a blob, e.g. a Wasm module or stable memory, and returns its id.",
+                    "parameters": [{
+                        "name": "Content-Encoding",
+                        "in": "header",
+                        "required": false,
+                        "schema": { "type": "string", "enum": ["gzip"] },
+                    }],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/octet-stream": {
+                                "schema": { "type": "string", "format": "binary" },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The hex encoded id of the blob.",
+                            "content": { "text/plain": { "schema": { "type": "string" } } },
+                        },
+                        "400": { "description": "The content encoding is not supported." },
+                    },
+                },
+            }),
+        ),
+        (
+            "/blobstore/{id}",
+            json!({
+                "get": {
+                    "summary": "Returns a blob. Compressed blobs are returned with their content encoding.",
+                    "parameters": [
+                        {
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "description": "The hex encoded id of the blob.",
+                            "schema": { "type": "string" },
+                        },
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "The blob.",
+                            "content": {
+                                "application/octet-stream": {
+                                    "schema": { "type": "string", "format": "binary" },
+                                },
+                            },
+                        },
+                        "400": { "description": "The id is malformed." },
+                        "404": { "description": "The blob does not exist." },
+                    },
+                },
+            }),
+        ),
+        (
+            "/verify_signature",
+            json!({
+                "post": {
+                    "summary": "Verifies a canister signature, or the signature of an IC request id with its delegations.",
+                    "requestBody": {
+                        "required": true,
+                        "content": json_content(json!({
+                            "oneOf": [
+                                schema("RawVerifyCanisterSigArg"),
+                                schema("RawVerifyRequestSigArg"),
+                            ],
+                        })),
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The signature is valid.",
+                            "content": json_content(schema("VerificationResult")),
+                        },
+                        "400": {
+                            "description": "A key or the request id is malformed.",
+                            "content": json_content(schema("VerificationResult")),
+                        },
+                        "406": {
+                            "description": "The signature is invalid.",
+                            "content": json_content(schema("VerificationResult")),
+                        },
+                    },
+                },
+            }),
+        ),
+        (
+            "/checkpoints",
+            json!({
+                "get": {
+                    "summary": "Lists the names of the checkpoints instances can be created from.",
+                    "responses": {
+                        "200": {
+                            "description": "The checkpoint names.",
+                            "content": json_content(array_of(json!({ "type": "string" }))),
+                        },
+                    },
+                },
+            }),
+        ),
+        (
+            "/instances",
+            json!({
+                "get": {
+                    "summary": "Lists the instances by their state, e.g. `Available` or `Deleted`, indexed by instance id.",
+                    "responses": {
+                        "200": {
+                            "description": "The states of the instances.",
+                            "content": json_content(array_of(json!({ "type": "string" }))),
+                        },
+                    },
+                },
+                "post": {
+                    "summary": "Creates an instance, restored from a checkpoint or made of the subnets of a topology.",
+                    "parameters": [{
+                        "name": "bootstrap",
+                        "in": "query",
+                        "required": false,
+                        "description": "Installs a minimal NNS (registry, governance, ledger, cycles minting canister and root) on the NNS subnet, from the Wasms in the server's `--nns-wasm-dir`. Implies a topology with just an NNS subnet if none is given.",
+                        "schema": { "type": "string", "enum": ["nns"] },
+                    }],
+                    "requestBody": {
+                        "required": false,
+                        "content": json_content(schema("RawCreateInstance")),
+                    },
+                    "responses": create_instance_responses(),
+                },
+            }),
+        ),
+        (
+            "/instances/from_snapshot",
+            json!({
+                "post": {
+                    "summary": "Creates an instance from a snapshot directory.",
+                    "requestBody": {
+                        "required": true,
+                        "content": json_content(schema("RawSnapshot")),
+                    },
+                    "responses": create_instance_responses(),
+                },
+            }),
+        ),
+        (
+            "/instances/{id}",
+            json!({
+                "delete": {
+                    "summary": "Deletes an instance.",
+                    "parameters": [{ "$ref": "#/components/parameters/InstanceId" }],
+                    "responses": { "200": { "description": "The instance has been deleted." } },
+                },
+            }),
+        ),
+        (
+            "/instances/{id}/snapshot",
+            json!({
+                "post": instance_operation(
+                    "Writes a snapshot of the instance to a directory.",
+                    Some(schema("RawSnapshot")),
+                    unit.clone(),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/export_state",
+            json!({
+                "post": instance_operation(
+                    "Writes the state and registry of the instance in the layout of a replica, for `ic-replay` and `state-tool`.",
+                    Some(schema("RawExportState")),
+                    unit.clone(),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/events",
+            json!({
+                "get": {
+                    "summary": "Streams the activity of the instance as server-sent events with JSON data. A subscriber that falls behind gets a `lagged` event with the number of skipped events.",
+                    "parameters": [{ "$ref": "#/components/parameters/InstanceId" }],
+                    "responses": {
+                        "200": {
+                            "description": "The event stream.",
+                            "content": {
+                                "text/event-stream": { "schema": schema("InstanceEvent") },
+                            },
+                        },
+                        "404": {
+                            "description": "The instance does not exist.",
+                            "content": json_content(schema("ApiError")),
+                        },
+                    },
+                },
+            }),
+        ),
+        (
+            "/instances/{id}/ingress_history",
+            json!({
+                "get": instance_operation(
+                    "Lists the ingress messages executed on the instance.",
+                    None,
+                    array_of(schema("RawIngressRecord")),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/replay",
+            json!({
+                "post": instance_operation(
+                    "Re-executes recorded ingress messages on the instance.",
+                    Some(array_of(schema("RawIngressRecord"))),
+                    array_of(schema("RawCanisterResult")),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/batch",
+            json!({
+                "post": instance_operation(
+                    "Executes a list of operations in order, without other operations in between. Operations without a result, such as advancing the time, have a null result.",
+                    Some(array_of(schema("RawBatchOperation"))),
+                    array_of(json!({ "allOf": [schema("RawCanisterResult")], "nullable": true })),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/canisters/{canister_id}/logs",
+            json!({
+                "get": {
+                    "summary": "Returns the debug prints and traps of a canister, oldest first.",
+                    "parameters": [
+                        { "$ref": "#/components/parameters/InstanceId" },
+                        {
+                            "name": "canister_id",
+                            "in": "path",
+                            "required": true,
+                            "description": "The textual representation of the canister id.",
+                            "schema": { "type": "string" },
+                        },
+                        {
+                            "name": "since_nanos",
+                            "in": "query",
+                            "required": false,
+                            "description": "Only records at or after this time are returned.",
+                            "schema": { "type": "integer", "minimum": 0 },
+                        },
+                        { "$ref": "#/components/parameters/ProcessingTimeout" },
+                    ],
+                    "responses": instance_operation_responses(
+                        array_of(schema("RawCanisterLogRecord")),
+                    ),
+                },
+            }),
+        ),
+        (
+            "/instances/{id}/read/query",
+            json!({
+                "post": instance_operation(
+                    "Executes a query call.",
+                    Some(schema("RawCanisterCall")),
+                    schema("RawCanisterResult"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/read/get_time",
+            json!({
+                "get": instance_operation(
+                    "Returns the time of the instance.",
+                    None,
+                    schema("RawTime"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/read/get_cycles",
+            json!({
+                "post": instance_operation(
+                    "Returns the cycle balance of a canister.",
+                    Some(schema("RawCanisterId")),
+                    schema("RawCycles"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/read/get_stable_memory",
+            json!({
+                "post": instance_operation(
+                    "Returns the stable memory of a canister.",
+                    Some(schema("RawCanisterId")),
+                    schema("RawStableMemory"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/read/canister_exists",
+            json!({
+                "post": instance_operation(
+                    "Checks whether a canister exists.",
+                    Some(schema("RawCanisterId")),
+                    json!({ "type": "boolean" }),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/read/get_subnet",
+            json!({
+                "post": instance_operation(
+                    "Returns the subnet that hosts a canister, or that a canister with this id would be created on.",
+                    Some(schema("RawCanisterId")),
+                    schema("RawSubnetId"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/read/root_key",
+            json!({
+                "post": instance_operation(
+                    "Returns the DER encoded root key of the instance, as an array of bytes.",
+                    None,
+                    array_of(json!({ "type": "integer", "minimum": 0, "maximum": 255 })),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/execute_ingress_message",
+            json!({
+                "post": instance_operation(
+                    "Executes an update call as an ingress message until it completes.",
+                    Some(schema("RawCanisterCall")),
+                    schema("RawCanisterResult"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/set_time",
+            json!({
+                "post": instance_operation(
+                    "Sets the time of the instance.",
+                    Some(schema("RawTime")),
+                    unit.clone(),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/add_cycles",
+            json!({
+                "post": instance_operation(
+                    "Adds cycles to a canister and returns its new balance.",
+                    Some(schema("RawAddCycles")),
+                    schema("RawCycles"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/set_cycles",
+            json!({
+                "post": instance_operation(
+                    "Sets the cycle balance of a canister and returns it.",
+                    Some(schema("RawSetCycles")),
+                    schema("RawCycles"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/create_canister_with_id",
+            json!({
+                "post": instance_operation(
+                    "Creates a canister with the given id, controllers and cycles balance, on the given subnet or on the subnet the id is routed to. Fails if the canister already exists.",
+                    Some(schema("RawCreateCanisterWithId")),
+                    schema("RawCanisterId"),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/set_stable_memory",
+            json!({
+                "post": instance_operation(
+                    "Replaces the stable memory of a canister with a blob of the blob store.",
+                    Some(schema("RawSetStableMemory")),
+                    unit.clone(),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/create_checkpoint",
+            json!({
+                "post": instance_operation(
+                    "Checkpoints the state of the instance. Fails for instances with multiple subnets.",
+                    None,
+                    unit.clone(),
+                ),
+            }),
+        ),
+        (
+            "/instances/{id}/update/tick",
+            json!({
+                "post": instance_operation(
+                    "Executes a round on every subnet of the instance.",
+                    None,
+                    unit,
+                ),
+            }),
+        ),
+    ])
+}
+
+fn schemas() -> Value {
+    let principal = json!({
+        "type": "string",
+        "format": "byte",
+        "description": "The base64 encoded bytes of a principal.",
+    });
+    let uint64 = json!({ "type": "integer", "format": "int64", "minimum": 0 });
+    let uint128 = json!({ "type": "integer", "minimum": 0 });
+    let nullable_uint64 =
+        json!({ "type": "integer", "format": "int64", "minimum": 0, "nullable": true });
+    let nullable_uint128 = json!({ "type": "integer", "minimum": 0, "nullable": true });
+    entries([
+        (
+            "ApiError",
+            object(json!({ "message": { "type": "string" } })),
+        ),
+        (
+            "StartedOrBusyResponse",
+            object(json!({ "state_label": { "type": "string" }, "op_id": { "type": "string" } })),
+        ),
+        (
+            "Quota",
+            json!({
+                "oneOf": [
+                    variant("MaxInstances", object(json!({
+                        "max_instances": { "type": "integer", "minimum": 0 },
+                    }))),
+                    variant("MaxMemory", object(json!({
+                        "memory_bytes": uint64,
+                        "max_memory_bytes": uint64,
+                    }))),
+                ],
+            }),
+        ),
+        (
+            "QuotaExceeded",
+            object(json!({ "message": { "type": "string" }, "quota": schema("Quota") })),
+        ),
+        (
+            "Topology",
+            json!({
+                "type": "object",
+                "description": "The subnets an instance consists of. An instance created without a topology consists of a single system subnet.",
+                "properties": {
+                    "nns": { "type": "boolean", "default": false },
+                    "system": { "type": "boolean", "default": false },
+                    "application": { "type": "integer", "minimum": 0, "default": 0 },
+                    "bitcoin": { "type": "boolean", "default": false },
+                    "fiduciary": { "type": "boolean", "default": false },
+                },
+            }),
+        ),
+        (
+            "InstanceFeatures",
+            json!({
+                "type": "object",
+                "properties": {
+                    "ecdsa_keys": { "type": "array", "items": { "type": "string" }, "default": [] },
+                    "bitcoin": { "type": "boolean", "default": false },
+                    "cycles_fees": schema("CyclesFees"),
+                    "seed": {
+                        "type": "array",
+                        "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+                        "minItems": 32,
+                        "maxItems": 32,
+                        "nullable": true,
+                        "description": "Seeds the randomness and the tECDSA test key of all subnets.",
+                    },
+                },
+            }),
+        ),
+        (
+            "CyclesFees",
+            json!({
+                "type": "object",
+                "description": "Fees of application subnets in cycles; unset fees keep their default.",
+                "properties": {
+                    "update_message_execution_fee": nullable_uint128,
+                    "ten_update_instructions_execution_fee": nullable_uint128,
+                    "ingress_message_reception_fee": nullable_uint128,
+                    "ingress_byte_reception_fee": nullable_uint128,
+                    "xnet_call_fee": nullable_uint128,
+                    "xnet_byte_transmission_fee": nullable_uint128,
+                    "gib_storage_per_second_fee": nullable_uint128,
+                    "compute_percent_allocated_per_second_fee": nullable_uint128,
+                    "allocation_charge_interval_nanos": nullable_uint64,
+                },
+            }),
+        ),
+        (
+            "RawCreateInstance",
+            json!({
+                "type": "object",
+                "properties": {
+                    "checkpoint_name": { "type": "string", "nullable": true },
+                    "topology": { "allOf": [schema("Topology")], "nullable": true },
+                    "features": schema("InstanceFeatures"),
+                },
+            }),
+        ),
+        (
+            "CreateInstanceResponse",
+            json!({
+                "oneOf": [
+                    variant("Created", object(json!({
+                        "instance_id": { "type": "integer", "minimum": 0 },
+                    }))),
+                    variant("Error", schema("ApiError")),
+                    variant("QuotaExceeded", schema("QuotaExceeded")),
+                ],
+            }),
+        ),
+        (
+            "RawSnapshot",
+            object(json!({ "snapshot_dir": { "type": "string" } })),
+        ),
+        (
+            "RawExportState",
+            object(json!({ "export_dir": { "type": "string" } })),
+        ),
+        ("RawTime", object(json!({ "nanos_since_epoch": uint64 }))),
+        ("RawCanisterId", object(json!({ "canister_id": principal }))),
+        ("RawSubnetId", object(json!({ "subnet_id": principal }))),
+        (
+            "RawCreateCanisterWithId",
+            json!({
+                "type": "object",
+                "properties": {
+                    "canister_id": principal,
+                    "controllers": array_of(object(json!({ "principal_id": principal }))),
+                    "cycles": uint128,
+                    "subnet_id": { "allOf": [schema("RawSubnetId")], "nullable": true },
+                },
+                "required": ["canister_id", "controllers", "cycles"],
+            }),
+        ),
+        ("RawCycles", object(json!({ "cycles": uint128 }))),
+        (
+            "RawAddCycles",
+            object(json!({ "canister_id": principal, "amount": uint128 })),
+        ),
+        (
+            "RawSetCycles",
+            object(json!({ "canister_id": principal, "amount": uint128 })),
+        ),
+        ("RawStableMemory", object(json!({ "blob": base64() }))),
+        (
+            "RawSetStableMemory",
+            object(json!({
+                "canister_id": principal,
+                "blob_id": {
+                    "type": "array",
+                    "description": "The id of a blob of the blob store, as an array of bytes.",
+                    "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+                    "minItems": 32,
+                    "maxItems": 32,
+                },
+            })),
+        ),
+        (
+            "RawCanisterCall",
+            object(json!({
+                "sender": principal,
+                "canister_id": principal,
+                "method": { "type": "string" },
+                "payload": base64(),
+            })),
+        ),
+        (
+            "RawWasmResult",
+            json!({
+                "oneOf": [
+                    variant("Reply", base64()),
+                    variant("Reject", json!({ "type": "string" })),
+                ],
+            }),
+        ),
+        (
+            "UserError",
+            object(json!({
+                "code": {
+                    "type": "string",
+                    "description": "The name of the error code, e.g. `CanisterNotFound`.",
+                },
+                "description": { "type": "string" },
+            })),
+        ),
+        (
+            "RawCanisterResult",
+            json!({
+                "oneOf": [
+                    variant("Ok", schema("RawWasmResult")),
+                    variant("Err", schema("UserError")),
+                ],
+            }),
+        ),
+        (
+            "RawIngressRecord",
+            object(json!({ "time_nanos_since_epoch": uint64, "call": schema("RawCanisterCall") })),
+        ),
+        (
+            "RawBatchOperation",
+            json!({
+                "oneOf": [
+                    variant("Install", object(json!({
+                        "canister_id": principal,
+                        "mode": { "type": "string", "enum": ["install", "reinstall", "upgrade"] },
+                        "wasm_module": base64(),
+                        "arg": base64(),
+                    }))),
+                    variant("Call", schema("RawCanisterCall")),
+                    variant("Query", schema("RawCanisterCall")),
+                    variant("AdvanceTime", object(json!({ "nanos": uint64 }))),
+                ],
+            }),
+        ),
+        (
+            "RawCanisterLogRecord",
+            object(json!({
+                "time_nanos_since_epoch": uint64,
+                "kind": { "type": "string", "enum": ["DebugPrint", "Trap"] },
+                "message": { "type": "string" },
+            })),
+        ),
+        (
+            "InstanceEvent",
+            json!({
+                "oneOf": [
+                    variant("IngressSubmitted", object(json!({
+                        "message_id": { "type": "string" },
+                        "sender": principal,
+                        "canister_id": principal,
+                        "method": { "type": "string" },
+                    }))),
+                    variant("CanisterInstalled", object(json!({
+                        "canister_id": principal,
+                        "mode": { "type": "string" },
+                    }))),
+                    variant("CanisterTrapped", object(json!({
+                        "canister_id": principal,
+                        "message": { "type": "string" },
+                    }))),
+                    variant("CyclesConsumed", object(json!({
+                        "canister_id": principal,
+                        "cycles": uint128,
+                    }))),
+                ],
+            }),
+        ),
+        (
+            "RawVerifyCanisterSigArg",
+            object(json!({
+                "msg": base64(),
+                "sig": base64(),
+                "pubkey": base64(),
+                "root_pubkey": base64(),
+            })),
+        ),
+        (
+            "RawSignedDelegation",
+            json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": base64(),
+                    "expiration_nanos": uint64,
+                    "targets": {
+                        "type": "array",
+                        "items": schema("RawCanisterId"),
+                        "nullable": true,
+                    },
+                    "signature": base64(),
+                },
+                "required": ["pubkey", "expiration_nanos", "signature"],
+            }),
+        ),
+        (
+            "RawVerifyRequestSigArg",
+            json!({
+                "type": "object",
+                "properties": {
+                    "request_id": base64(),
+                    "sig": base64(),
+                    "sender_pubkey": base64(),
+                    "delegations": {
+                        "type": "array",
+                        "items": schema("RawSignedDelegation"),
+                        "nullable": true,
+                    },
+                    "root_pubkey": base64(),
+                    "current_time_nanos": {
+                        "type": "integer",
+                        "format": "int64",
+                        "minimum": 0,
+                        "nullable": true,
+                    },
+                },
+                "required": ["request_id", "sig", "sender_pubkey"],
+            }),
+        ),
+        (
+            "VerificationResult",
+            json!({
+                "oneOf": [
+                    object(json!({ "Ok": { "type": "object", "nullable": true } })),
+                    variant("Err", json!({ "type": "string" })),
+                ],
+            }),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(reference)) => refs.push(reference.clone()),
+                        _ => collect_refs(value, refs),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect_refs(value, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn all_references_resolve() {
+        let description = api_description();
+        let mut refs = vec![];
+        collect_refs(&description, &mut refs);
+        assert!(!refs.is_empty());
+        for reference in refs {
+            let pointer = reference.strip_prefix('#').unwrap();
+            assert!(
+                description.pointer(pointer).is_some(),
+                "Unresolved reference {}",
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn instance_operations_take_the_instance_id() {
+        let description = api_description();
+        for (path, item) in description["paths"].as_object().unwrap() {
+            if !path.starts_with("/instances/{id}") {
+                continue;
+            }
+            for (method, operation) in item.as_object().unwrap() {
+                assert!(
+                    operation["parameters"]
+                        .as_array()
+                        .unwrap()
+                        .contains(&json!({ "$ref": "#/components/parameters/InstanceId" })),
+                    "{} {} lacks the instance id",
+                    method,
+                    path
+                );
+            }
+        }
+    }
+}
@@ -1,66 +1,227 @@
+use crate::copy_dir;
+use crate::state_api::state::HasMemoryUsage;
 use crate::state_api::state::HasStateLabel;
 use crate::state_api::state::OpOut;
+use crate::state_api::state::PocketIcError;
 use crate::state_api::state::StateLabel;
 use crate::BlobStore;
 use crate::OpId;
 use crate::Operation;
+use ic_btc_interface::GetUtxosResponse;
 use ic_config::execution_environment;
 use ic_config::subnet_config::SubnetConfig;
 use ic_crypto::threshold_sig_public_key_to_der;
 use ic_crypto_sha2::Sha256;
-use ic_ic00_types::CanisterInstallMode;
+use ic_ic00_types::{
+    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgsBuilder, EcdsaCurve, EcdsaKeyId,
+    InstallCodeArgs, Payload, ProvisionalCreateCanisterWithCyclesArgs,
+};
 use ic_interfaces_state_manager::StateReader;
+use ic_registry_routing_table::{
+    routing_table_insert_subnet, CanisterIdRange, CanisterIdRanges, RoutingTable,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::Cycles;
 use ic_state_machine_tests::StateMachine;
 use ic_state_machine_tests::StateMachineBuilder;
 use ic_state_machine_tests::StateMachineConfig;
 use ic_state_machine_tests::Time;
-use ic_types::{CanisterId, PrincipalId};
+use ic_state_machine_tests::{ErrorCode, IngressState, IngressStatus};
+use ic_types::{xnet::StreamIndex, CanisterId, PrincipalId, SubnetId};
 use pocket_ic::common::blob::{BinaryBlob, BlobCompression};
+use pocket_ic::common::rest::CanisterLogKind;
+use pocket_ic::common::rest::CyclesFees;
+use pocket_ic::common::rest::InstanceEvent;
+use pocket_ic::common::rest::InstanceFeatures;
 use pocket_ic::common::rest::RawAddCycles;
+use pocket_ic::common::rest::RawBatchOperation;
 use pocket_ic::common::rest::RawCanisterCall;
+use pocket_ic::common::rest::RawCanisterLogRecord;
+use pocket_ic::common::rest::RawCreateCanisterWithId;
+use pocket_ic::common::rest::RawIngressRecord;
+use pocket_ic::common::rest::RawSetCycles;
 use pocket_ic::common::rest::RawSetStableMemory;
+use pocket_ic::common::rest::Topology;
 use serde::Deserialize;
 use serde::Serialize;
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use tempfile::TempDir;
-use tokio::runtime::Runtime;
+use tokio::{runtime::Runtime, sync::broadcast};
+
+/// Upper bound on the number of rounds of XNet message delivery after a single tick.
+const MAX_XNET_ROUNDS: usize = 100;
+/// Upper bound on the number of ticks to wait for an ingress message to complete.
+const MAX_INGRESS_TICKS: usize = 100;
+/// Number of events buffered for subscribers of an instance's event stream.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
 
 pub struct PocketIc {
-    subnet: StateMachine,
+    /// The subnets of this instance. Calls to the management canister that do not
+    /// target a specific canister, e.g. canister creation, go to the first subnet.
+    subnets: Vec<StateMachine>,
+    routing_table: RoutingTable,
+    events: broadcast::Sender<InstanceEvent>,
+    /// The ingress messages executed on this instance, in order.
+    ingress_history: Vec<IngressRecord>,
+    /// The traps of ingress messages executed on this instance, in order. Debug prints are
+    /// kept by the subnets themselves.
+    traps: Vec<(CanisterId, RawCanisterLogRecord)>,
 }
 
 #[allow(clippy::new_without_default)]
 impl PocketIc {
     pub fn new(sm: StateMachine) -> Self {
-        Self { subnet: sm }
+        Self::new_with_subnets(vec![sm], RoutingTable::new())
+    }
+
+    pub fn new_with_subnets(subnets: Vec<StateMachine>, routing_table: RoutingTable) -> Self {
+        assert!(!subnets.is_empty(), "An instance needs at least one subnet");
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            subnets,
+            routing_table,
+            events,
+            ingress_history: vec![],
+            traps: vec![],
+        }
+    }
+
+    /// The sender of this instance's events; subscribers can be added even
+    /// while the instance is busy.
+    pub fn event_sender(&self) -> broadcast::Sender<InstanceEvent> {
+        self.events.clone()
+    }
+
+    fn emit(&self, event: InstanceEvent) {
+        // Sending only fails if nobody is subscribed, in which case the event is dropped.
+        let _ = self.events.send(event);
+    }
+
+    /// Executes a round on the given subnet and reports the cycles each canister
+    /// burnt in it.
+    fn execute_round<R>(&self, subnet: &StateMachine, round: impl FnOnce(&StateMachine) -> R) -> R {
+        if self.events.receiver_count() == 0 {
+            return round(subnet);
+        }
+        let balances_before = cycle_balances(subnet);
+        let result = round(subnet);
+        for (canister_id, balance) in cycle_balances(subnet) {
+            match balances_before.get(&canister_id) {
+                Some(before) if *before > balance => self.emit(InstanceEvent::CyclesConsumed {
+                    canister_id: canister_id.get().to_vec(),
+                    cycles: before - balance,
+                }),
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// The subnet that hosts canisters created without a specified id.
+    fn default_subnet(&self) -> &StateMachine {
+        &self.subnets[0]
+    }
+
+    /// The subnet that hosts the given canister, or the default subnet if the
+    /// canister is not covered by the routing table.
+    pub(crate) fn subnet_for(&self, canister_id: CanisterId) -> &StateMachine {
+        self.routing_table
+            .route(canister_id.get())
+            .and_then(|subnet_id| {
+                self.subnets
+                    .iter()
+                    .find(|subnet| subnet.get_subnet_id() == subnet_id)
+            })
+            .unwrap_or_else(|| self.default_subnet())
+    }
+
+    /// Executes a round on every subnet and delivers the resulting XNet messages.
+    fn tick(&self) {
+        for subnet in &self.subnets {
+            self.execute_round(subnet, |subnet| subnet.tick());
+        }
+        self.execute_xnet();
+    }
+
+    /// Inducts the XNet streams between all pairs of subnets until no subnet has
+    /// undelivered messages left. Inducting a stream slice executes a round on the
+    /// receiving subnet, which may produce new messages, e.g. responses.
+    fn execute_xnet(&self) {
+        if self.subnets.len() < 2 {
+            return;
+        }
+        for _ in 0..MAX_XNET_ROUNDS {
+            let mut delivered = false;
+            for from in &self.subnets {
+                for to in &self.subnets {
+                    if from.get_subnet_id() == to.get_subnet_id() {
+                        continue;
+                    }
+                    let Some(messages_end) = from
+                        .get_latest_state()
+                        .get_stream(&to.get_subnet_id())
+                        .map(|stream| stream.messages_end())
+                    else {
+                        continue;
+                    };
+                    let expected = to
+                        .get_latest_state()
+                        .get_stream(&from.get_subnet_id())
+                        .map(|stream| stream.signals_end())
+                        .unwrap_or_else(|| StreamIndex::new(0));
+                    if messages_end <= expected {
+                        continue;
+                    }
+                    let xnet_payload = from
+                        .generate_xnet_payload(
+                            to.get_subnet_id(),
+                            Some(expected),
+                            Some(expected),
+                            None,
+                            None,
+                        )
+                        .expect("Failed to generate XNet payload");
+                    self.execute_round(to, |to| to.execute_block_with_xnet_payload(xnet_payload));
+                    delivered = true;
+                }
+            }
+            if !delivered {
+                return;
+            }
+        }
     }
 }
 impl Default for PocketIc {
     fn default() -> Self {
-        let hypervisor_config = execution_environment::Config {
-            default_provisional_cycles_balance: Cycles::new(0),
-            ..Default::default()
-        };
-        let config =
-            StateMachineConfig::new(SubnetConfig::new(SubnetType::System), hypervisor_config);
-        let sm = StateMachineBuilder::new().with_config(Some(config)).build();
+        let sm = StateMachineBuilder::new()
+            .with_config(Some(state_machine_config(
+                SubnetType::System,
+                &CyclesFees::default(),
+            )))
+            .with_canister_logs()
+            .build();
         Self::new(sm)
     }
 }
 
 impl HasStateLabel for PocketIc {
     fn get_state_label(&self) -> StateLabel {
-        let subnet_state_hash = self
-            .subnet
-            .state_manager
-            .latest_state_certification_hash()
-            .map(|(_, h)| h.0)
-            .unwrap_or_else(|| [0u8; 32].to_vec());
         let mut hasher = Sha256::new();
-        let nanos = systemtime_to_unix_epoch_nanos(self.subnet.time());
-        hasher.write(&subnet_state_hash[..]);
+        for subnet in &self.subnets {
+            let subnet_state_hash = subnet
+                .state_manager
+                .latest_state_certification_hash()
+                .map(|(_, h)| h.0)
+                .unwrap_or_else(|| [0u8; 32].to_vec());
+            hasher.write(&subnet_state_hash[..]);
+        }
+        let nanos = systemtime_to_unix_epoch_nanos(self.default_subnet().time());
         // XXX: We should make the nonce part of the environment.
         // hasher.write(&self.nonce.to_be_bytes());
         hasher.write(&nanos.to_be_bytes());
@@ -68,6 +229,20 @@ impl HasStateLabel for PocketIc {
     }
 }
 
+impl HasMemoryUsage for PocketIc {
+    fn memory_usage(&self) -> u64 {
+        self.subnets
+            .iter()
+            .map(|subnet| {
+                let memory_taken = subnet.get_latest_state().memory_taken();
+                memory_taken.execution().get()
+                    + memory_taken.messages().get()
+                    + memory_taken.canister_history().get()
+            })
+            .sum()
+    }
+}
+
 // ---------------------------------------------------------------------------------------- //
 // Operations on PocketIc
 
@@ -86,9 +261,11 @@ impl Operation for SetTime {
     type TargetType = PocketIc;
 
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        // XXX: for now, we use the StateMachine's time as the system time. Later, we will take
+        // XXX: for now, we keep the time of all subnets in sync. Later, we will take
         // StateMachine appart and have a system time that applies to all subnets.
-        pic.subnet.set_time(self.time.into());
+        for subnet in &pic.subnets {
+            subnet.set_time(self.time.into());
+        }
         OpOut::NoOutput
     }
 
@@ -104,7 +281,7 @@ impl Operation for GetTime {
     type TargetType = PocketIc;
 
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        let nanos = systemtime_to_unix_epoch_nanos(pic.subnet.time());
+        let nanos = systemtime_to_unix_epoch_nanos(pic.default_subnet().time());
         OpOut::Time(nanos)
     }
 
@@ -120,7 +297,7 @@ impl Operation for RootKey {
     type TargetType = PocketIc;
 
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        let bytes = threshold_sig_public_key_to_der(pic.subnet.root_key()).unwrap();
+        let bytes = threshold_sig_public_key_to_der(pic.default_subnet().root_key()).unwrap();
         OpOut::Bytes(bytes)
     }
 
@@ -136,7 +313,7 @@ impl Operation for Tick {
     type TargetType = PocketIc;
 
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        pic.subnet.tick();
+        pic.tick();
         OpOut::NoOutput
     }
 
@@ -152,14 +329,7 @@ impl Operation for ExecuteIngressMessage {
     type TargetType = PocketIc;
 
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        pic.subnet
-            .execute_ingress_as(
-                self.0.sender,
-                self.0.canister_id,
-                self.0.method,
-                self.0.payload,
-            )
-            .into()
+        execute_ingress(pic, self.0).into()
     }
 
     fn id(&self) -> OpId {
@@ -168,12 +338,207 @@ impl Operation for ExecuteIngressMessage {
     }
 }
 
+/// Executes an ingress message until it completes and records it in the ingress history.
+fn execute_ingress(
+    pic: &mut PocketIc,
+    call: CanisterCall,
+) -> Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError> {
+    let time_nanos_since_epoch = systemtime_to_unix_epoch_nanos(pic.default_subnet().time());
+    pic.ingress_history.push(IngressRecord {
+        time_nanos_since_epoch,
+        call: call.clone(),
+    });
+    let effective_canister_id = call.effective_canister_id();
+    let CanisterCall {
+        sender,
+        canister_id,
+        method,
+        payload,
+    } = call;
+    let subnet = pic.subnet_for(effective_canister_id);
+    let msg_id = pic.execute_round(subnet, |subnet| {
+        subnet.send_ingress(sender, canister_id, method.clone(), payload.clone())
+    });
+    pic.emit(InstanceEvent::IngressSubmitted {
+        message_id: msg_id.to_string(),
+        sender: sender.to_vec(),
+        canister_id: canister_id.get().to_vec(),
+        method: method.clone(),
+    });
+    pic.execute_xnet();
+    for _ in 0..MAX_INGRESS_TICKS {
+        if let IngressStatus::Known {
+            state: IngressState::Completed(_) | IngressState::Failed(_),
+            ..
+        } = subnet.ingress_status(&msg_id)
+        {
+            break;
+        }
+        pic.tick();
+    }
+    let result = subnet.await_ingress(msg_id, 1);
+    match &result {
+        Ok(ic_state_machine_tests::WasmResult::Reply(_))
+            if canister_id == CanisterId::ic_00() && method == "install_code" =>
+        {
+            if let Ok(args) = InstallCodeArgs::decode(&payload) {
+                pic.emit(InstanceEvent::CanisterInstalled {
+                    canister_id: args.canister_id.to_vec(),
+                    mode: args.mode.into(),
+                });
+            }
+        }
+        Err(error)
+            if matches!(
+                error.code(),
+                ErrorCode::CanisterTrapped | ErrorCode::CanisterCalledTrap
+            ) =>
+        {
+            pic.traps.push((
+                effective_canister_id,
+                RawCanisterLogRecord {
+                    time_nanos_since_epoch,
+                    kind: CanisterLogKind::Trap,
+                    message: error.description().to_string(),
+                },
+            ));
+            pic.emit(InstanceEvent::CanisterTrapped {
+                canister_id: effective_canister_id.get().to_vec(),
+                message: error.description().to_string(),
+            });
+        }
+        _ => {}
+    }
+    result
+}
+
+/// An ingress message executed on an instance, together with the time of the instance when the
+/// message was submitted.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct IngressRecord {
+    pub time_nanos_since_epoch: u64,
+    pub call: CanisterCall,
+}
+
+impl From<IngressRecord> for RawIngressRecord {
+    fn from(record: IngressRecord) -> Self {
+        Self {
+            time_nanos_since_epoch: record.time_nanos_since_epoch,
+            call: record.call.into(),
+        }
+    }
+}
+
+impl TryFrom<RawIngressRecord> for IngressRecord {
+    type Error = ConversionError;
+    fn try_from(raw: RawIngressRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            time_nanos_since_epoch: raw.time_nanos_since_epoch,
+            call: raw.call.try_into()?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GetIngressHistory;
+
+impl Operation for GetIngressHistory {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        OpOut::IngressHistory(pic.ingress_history.clone())
+    }
+
+    fn id(&self) -> OpId {
+        OpId("get_ingress_history".into())
+    }
+}
+
+/// Returns the debug prints and traps of a canister at or after the given time, oldest first.
+#[derive(Clone, Copy, Debug)]
+pub struct GetCanisterLogs {
+    pub canister_id: CanisterId,
+    pub since_nanos: u64,
+}
+
+impl Operation for GetCanisterLogs {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let debug_prints = pic
+            .subnet_for(self.canister_id)
+            .canister_log(self.canister_id)
+            .into_iter()
+            .map(|record| RawCanisterLogRecord {
+                time_nanos_since_epoch: record.time.as_nanos_since_unix_epoch(),
+                kind: CanisterLogKind::DebugPrint,
+                message: record.message,
+            });
+        let traps = pic
+            .traps
+            .iter()
+            .filter(|(canister_id, _)| *canister_id == self.canister_id)
+            .map(|(_, record)| record.clone());
+        let mut logs: Vec<_> = debug_prints
+            .chain(traps)
+            .filter(|record| record.time_nanos_since_epoch >= self.since_nanos)
+            .collect();
+        // The sort is stable, so records of the same time keep their order.
+        logs.sort_by_key(|record| record.time_nanos_since_epoch);
+        OpOut::CanisterLogs(logs)
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!(
+            "get_canister_logs({},{})",
+            self.canister_id, self.since_nanos
+        ))
+    }
+}
+
+/// Re-executes recorded ingress messages in order. Before each message, the time of the
+/// instance is advanced to the recorded time, unless the instance is already past it.
+#[derive(Clone, Debug)]
+pub struct Replay(pub Vec<IngressRecord>);
+
+impl Operation for Replay {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let mut results = vec![];
+        for record in self.0 {
+            let now = systemtime_to_unix_epoch_nanos(pic.default_subnet().time());
+            if record.time_nanos_since_epoch > now {
+                let time = Time::from_nanos_since_unix_epoch(record.time_nanos_since_epoch);
+                for subnet in &pic.subnets {
+                    subnet.set_time(time.into());
+                }
+            }
+            let result: OpOut = execute_ingress(pic, record.call).into();
+            let OpOut::CanisterResult(result) = result else {
+                unreachable!()
+            };
+            results.push(result);
+        }
+        OpOut::CanisterResults(results)
+    }
+
+    fn id(&self) -> OpId {
+        let mut hasher = Sha256::new();
+        for record in &self.0 {
+            hasher.write(&record.time_nanos_since_epoch.to_be_bytes());
+            hasher.write(record.call.id().0.as_bytes());
+        }
+        OpId(format!("replay({})", Digest(hasher.finish())))
+    }
+}
+
 pub struct Query(pub CanisterCall);
 
 impl Operation for Query {
     type TargetType = PocketIc;
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        pic.subnet
+        pic.subnet_for(self.0.effective_canister_id())
             .query_as(
                 self.0.sender,
                 self.0.canister_id,
@@ -189,7 +554,103 @@ impl Operation for Query {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BatchOperation {
+    Install(InstallCanisterAsController),
+    Call(CanisterCall),
+    Query(CanisterCall),
+    AdvanceTime(Duration),
+}
+
+impl TryFrom<RawBatchOperation> for BatchOperation {
+    type Error = ConversionError;
+    fn try_from(raw: RawBatchOperation) -> Result<Self, Self::Error> {
+        match raw {
+            RawBatchOperation::Install {
+                canister_id,
+                mode,
+                wasm_module,
+                arg,
+            } => {
+                let canister_id =
+                    CanisterId::try_from(canister_id).map_err(|_| ConversionError {
+                        message: "Bad canister id".to_string(),
+                    })?;
+                let mode = CanisterInstallMode::try_from(mode).map_err(|e| ConversionError {
+                    message: e.to_string(),
+                })?;
+                Ok(Self::Install(InstallCanisterAsController {
+                    canister_id,
+                    mode,
+                    module: wasm_module,
+                    payload: arg,
+                }))
+            }
+            RawBatchOperation::Call(call) => Ok(Self::Call(call.try_into()?)),
+            RawBatchOperation::Query(call) => Ok(Self::Query(call.try_into()?)),
+            RawBatchOperation::AdvanceTime { nanos } => {
+                Ok(Self::AdvanceTime(Duration::from_nanos(nanos)))
+            }
+        }
+    }
+}
+
+/// Executes the given operations in order, without any other operation on the instance in
+/// between. Failing calls do not abort the batch; the results are returned one per operation.
+pub struct Batch(pub Vec<BatchOperation>);
+
+impl Operation for Batch {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let mut results = vec![];
+        for op in self.0 {
+            let result = match op {
+                BatchOperation::Install(install) => install.compute(pic),
+                BatchOperation::Call(call) => ExecuteIngressMessage(call).compute(pic),
+                BatchOperation::Query(call) => Query(call).compute(pic),
+                BatchOperation::AdvanceTime(duration) => {
+                    let time = pic.default_subnet().time() + duration;
+                    for subnet in &pic.subnets {
+                        subnet.set_time(time);
+                    }
+                    OpOut::NoOutput
+                }
+            };
+            results.push(result);
+        }
+        OpOut::Batch(results)
+    }
+
+    fn id(&self) -> OpId {
+        let mut hasher = Sha256::new();
+        for op in &self.0 {
+            match op {
+                BatchOperation::Install(install) => {
+                    hasher.write(b"install");
+                    hasher.write(install.canister_id.get().as_slice());
+                    hasher.write(String::from(install.mode).as_bytes());
+                    hasher.write(&Sha256::hash(&install.module));
+                    hasher.write(&Sha256::hash(&install.payload));
+                }
+                BatchOperation::Call(call) => {
+                    hasher.write(b"call");
+                    hasher.write(call.id().0.as_bytes());
+                }
+                BatchOperation::Query(call) => {
+                    hasher.write(b"query");
+                    hasher.write(call.id().0.as_bytes());
+                }
+                BatchOperation::AdvanceTime(duration) => {
+                    hasher.write(b"advance_time");
+                    hasher.write(&duration.as_nanos().to_be_bytes());
+                }
+            }
+        }
+        OpId(format!("batch({})", Digest(hasher.finish())))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub struct CanisterCall {
     pub sender: PrincipalId,
     pub canister_id: CanisterId,
@@ -226,7 +687,34 @@ impl TryFrom<RawCanisterCall> for CanisterCall {
     }
 }
 
+impl From<CanisterCall> for RawCanisterCall {
+    fn from(call: CanisterCall) -> Self {
+        Self {
+            sender: call.sender.to_vec(),
+            canister_id: call.canister_id.get().to_vec(),
+            method: call.method,
+            payload: call.payload,
+        }
+    }
+}
+
 impl CanisterCall {
+    /// The canister whose subnet executes this call. For calls to the management
+    /// canister, this is the canister the call refers to, if any.
+    fn effective_canister_id(&self) -> CanisterId {
+        if self.canister_id != CanisterId::ic_00() {
+            return self.canister_id;
+        }
+        if let Ok(record) = CanisterIdRecord::decode(&self.payload) {
+            return record.get_canister_id();
+        }
+        ProvisionalCreateCanisterWithCyclesArgs::decode(&self.payload)
+            .ok()
+            .and_then(|args| args.specified_id)
+            .and_then(|id| CanisterId::try_from(id).ok())
+            .unwrap_or(self.canister_id)
+    }
+
     fn id(&self) -> OpId {
         let mut hasher = Sha256::new();
         hasher.write(&self.payload);
@@ -291,7 +779,7 @@ impl Operation for SetStableMemory {
     type TargetType = PocketIc;
     fn compute(self, pocket_ic: &mut Self::TargetType) -> OpOut {
         pocket_ic
-            .subnet
+            .subnet_for(self.canister_id)
             .set_stable_memory(self.canister_id, &self.data);
         OpOut::NoOutput
     }
@@ -316,7 +804,11 @@ pub struct GetStableMemory {
 impl Operation for GetStableMemory {
     type TargetType = PocketIc;
     fn compute(self, pocket_ic: &mut Self::TargetType) -> OpOut {
-        OpOut::Bytes(pocket_ic.subnet.stable_memory(self.canister_id))
+        OpOut::Bytes(
+            pocket_ic
+                .subnet_for(self.canister_id)
+                .stable_memory(self.canister_id),
+        )
     }
 
     fn id(&self) -> OpId {
@@ -332,7 +824,9 @@ pub struct GetCyclesBalance {
 impl Operation for GetCyclesBalance {
     type TargetType = PocketIc;
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        let result = pic.subnet.cycle_balance(self.canister_id);
+        let result = pic
+            .subnet_for(self.canister_id)
+            .cycle_balance(self.canister_id);
         OpOut::Cycles(result)
     }
 
@@ -350,7 +844,7 @@ impl Operation for CanisterExists {
     type TargetType = PocketIc;
     fn compute(self, pic: &mut PocketIc) -> OpOut {
         let result = pic
-            .subnet
+            .subnet_for(self.canister_id)
             .state_manager
             .get_latest_state()
             .take()
@@ -399,7 +893,9 @@ impl Operation for AddCycles {
     type TargetType = PocketIc;
 
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        let result = pic.subnet.add_cycles(self.canister_id, self.amount);
+        let result = pic
+            .subnet_for(self.canister_id)
+            .add_cycles(self.canister_id, self.amount);
         OpOut::Cycles(result)
     }
 
@@ -408,22 +904,224 @@ impl Operation for AddCycles {
     }
 }
 
+/// Set the cycle balance of a given canister.
+///
+/// # Panics
+///
+/// Panics if the canister does not exist.
+#[derive(Clone, Debug)]
+pub struct SetCycles {
+    canister_id: CanisterId,
+    amount: u128,
+}
+
+impl TryFrom<RawSetCycles> for SetCycles {
+    type Error = ConversionError;
+    fn try_from(
+        RawSetCycles {
+            canister_id,
+            amount,
+        }: RawSetCycles,
+    ) -> Result<Self, Self::Error> {
+        match CanisterId::try_from(canister_id) {
+            Ok(canister_id) => Ok(SetCycles {
+                canister_id,
+                amount,
+            }),
+            Err(_) => Err(ConversionError {
+                message: "Bad canister id".to_string(),
+            }),
+        }
+    }
+}
+
+impl Operation for SetCycles {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        pic.subnet_for(self.canister_id)
+            .set_cycles(self.canister_id, self.amount);
+        OpOut::Cycles(self.amount)
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("set_cycles({},{})", self.canister_id, self.amount))
+    }
+}
+
+/// Creates a canister with the given id, controllers and cycles balance, on the given subnet
+/// or, without a subnet, on the subnet that [PocketIc::subnet_for] returns for the id. If the
+/// id is not routed to that subnet, it is rerouted on all subnets of the instance.
+#[derive(Clone, Debug)]
+pub struct CreateCanisterWithId {
+    canister_id: CanisterId,
+    controllers: Vec<PrincipalId>,
+    cycles: u128,
+    subnet_id: Option<SubnetId>,
+}
+
+impl TryFrom<RawCreateCanisterWithId> for CreateCanisterWithId {
+    type Error = ConversionError;
+    fn try_from(
+        RawCreateCanisterWithId {
+            canister_id,
+            controllers,
+            cycles,
+            subnet_id,
+        }: RawCreateCanisterWithId,
+    ) -> Result<Self, Self::Error> {
+        let canister_id = CanisterId::try_from(canister_id).map_err(|_| ConversionError {
+            message: "Bad canister id".to_string(),
+        })?;
+        let controllers = controllers
+            .into_iter()
+            .map(|controller| PrincipalId::try_from(controller.principal_id))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ConversionError {
+                message: "Bad principal id".to_string(),
+            })?;
+        let subnet_id = subnet_id
+            .map(|subnet_id| PrincipalId::try_from(subnet_id.subnet_id).map(SubnetId::from))
+            .transpose()
+            .map_err(|_| ConversionError {
+                message: "Bad subnet id".to_string(),
+            })?;
+        Ok(CreateCanisterWithId {
+            canister_id,
+            controllers,
+            cycles,
+            subnet_id,
+        })
+    }
+}
+
+impl Operation for CreateCanisterWithId {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let fail = |message: String| OpOut::Error(PocketIcError::CanisterCreationFailed(message));
+        let subnet_id = match self.subnet_id {
+            Some(subnet_id) => subnet_id,
+            None => pic.subnet_for(self.canister_id).get_subnet_id(),
+        };
+        if !pic
+            .subnets
+            .iter()
+            .any(|subnet| subnet.get_subnet_id() == subnet_id)
+        {
+            return fail(format!("Subnet {} is not part of the instance.", subnet_id));
+        }
+        if pic
+            .subnets
+            .iter()
+            .any(|subnet| subnet.canister_exists(self.canister_id))
+        {
+            return fail(format!("Canister {} already exists.", self.canister_id));
+        }
+
+        // Instances with a single subnet have an empty routing table and rely on the routing
+        // table of the subnet, which is rerouted in any case.
+        if pic.routing_table.route(self.canister_id.get()) != Some(subnet_id) {
+            for subnet in &pic.subnets {
+                subnet.reroute_canister_range(self.canister_id..=self.canister_id, subnet_id);
+            }
+            if !pic.routing_table.is_empty() {
+                pic.routing_table
+                    .assign_ranges(
+                        CanisterIdRanges::try_from(vec![CanisterIdRange {
+                            start: self.canister_id,
+                            end: self.canister_id,
+                        }])
+                        .unwrap(),
+                        subnet_id,
+                    )
+                    .expect("Failed to update the routing table");
+            }
+        }
+
+        let args = ProvisionalCreateCanisterWithCyclesArgs {
+            settings: Some(
+                CanisterSettingsArgsBuilder::new()
+                    .with_controllers(self.controllers.clone())
+                    .build(),
+            ),
+            ..ProvisionalCreateCanisterWithCyclesArgs::new(
+                Some(self.cycles),
+                Some(self.canister_id.get()),
+            )
+        };
+        let subnet = pic.subnet_for(self.canister_id);
+        let result = pic.execute_round(subnet, |subnet| {
+            subnet.execute_ingress(
+                CanisterId::ic_00(),
+                "provisional_create_canister_with_cycles",
+                args.encode(),
+            )
+        });
+        match result {
+            Ok(ic_state_machine_tests::WasmResult::Reply(_)) => OpOut::CanisterId(self.canister_id),
+            Ok(ic_state_machine_tests::WasmResult::Reject(message)) => fail(message),
+            Err(error) => fail(error.description().to_string()),
+        }
+    }
+
+    fn id(&self) -> OpId {
+        let controllers: Vec<_> = self.controllers.iter().map(|c| c.to_string()).collect();
+        let subnet_id = self
+            .subnet_id
+            .map(|subnet_id| subnet_id.to_string())
+            .unwrap_or_default();
+        OpId(format!(
+            "create_canister_with_id({},[{}],{},{})",
+            self.canister_id,
+            controllers.join(","),
+            self.cycles,
+            subnet_id
+        ))
+    }
+}
+
+/// Returns the subnet that hosts the given canister, see [PocketIc::subnet_for].
+#[derive(Clone, Debug)]
+pub struct GetSubnet {
+    pub canister_id: CanisterId,
+}
+
+impl Operation for GetSubnet {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        OpOut::SubnetId(pic.subnet_for(self.canister_id).get_subnet_id())
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("get_subnet({})", self.canister_id))
+    }
+}
+
 /// Writes a checkpoint directory to the disk.
 /// This directory is saved in the state graph, so a later
 /// call could copy the directory and name it -> named checkpoints.
 /// This operation, however, is only concerned with persisting the
 /// subnet state to disk and storing the directory in the graph.
+/// Only instances with a single subnet can be checkpointed.
 #[derive(Clone, Debug, Copy)]
 pub struct Checkpoint;
 
 impl Operation for Checkpoint {
     type TargetType = PocketIc;
     fn compute(self, pocket_ic: &mut Self::TargetType) -> OpOut {
-        pocket_ic.subnet.set_checkpoints_enabled(true);
-        pocket_ic.subnet.tick();
-        pocket_ic.subnet.set_checkpoints_enabled(false);
+        if pocket_ic.subnets.len() > 1 {
+            return OpOut::Error(PocketIcError::CheckpointFailed(
+                "Checkpoints of instances with multiple subnets are not supported".to_string(),
+            ));
+        }
+        let subnet = pocket_ic.default_subnet();
+        subnet.set_checkpoints_enabled(true);
+        subnet.tick();
+        subnet.set_checkpoints_enabled(false);
 
-        let state_dir = pocket_ic.subnet.state_dir.path();
+        let state_dir = subnet.state_dir.path();
         // find most recent checkpoint in the state_dir/checkpoints/ directory
         let checkpoint_dir = std::fs::read_dir(state_dir)
             .expect("Failed to read state dir")
@@ -446,6 +1144,147 @@ impl Operation for Checkpoint {
     }
 }
 
+const SNAPSHOT_STATE_DIR: &str = "state";
+const SNAPSHOT_REGISTRY_FILE: &str = "registry.pb";
+const SNAPSHOT_METADATA_FILE: &str = "snapshot.json";
+
+/// Everything needed to resume an instance from a snapshot that is not part of
+/// the replicated state or the registry.
+#[derive(Serialize, Deserialize)]
+struct SnapshotMetadata {
+    time_nanos_since_epoch: u64,
+    nonce: u64,
+}
+
+/// Writes a self-contained snapshot of the instance to `snapshot_dir`: the
+/// state directory (including a fresh checkpoint), the registry, and the
+/// instance time and nonce. Unlike checkpoints, snapshots outlive the server
+/// and can be restored with [create_state_machine_from_snapshot].
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub snapshot_dir: PathBuf,
+}
+
+impl Operation for Snapshot {
+    type TargetType = PocketIc;
+    fn compute(self, pocket_ic: &mut Self::TargetType) -> OpOut {
+        if pocket_ic.subnets.len() > 1 {
+            return OpOut::Error(PocketIcError::SnapshotFailed(
+                "Snapshots of instances with multiple subnets are not supported".to_string(),
+            ));
+        }
+        let subnet = pocket_ic.default_subnet();
+        subnet.set_checkpoints_enabled(true);
+        subnet.tick();
+        subnet.set_checkpoints_enabled(false);
+        subnet.state_manager.flush_tip_channel();
+
+        match write_snapshot(subnet, &self.snapshot_dir) {
+            Ok(()) => OpOut::NoOutput,
+            Err(e) => OpOut::Error(PocketIcError::SnapshotFailed(format!(
+                "Failed to write snapshot to {}: {}",
+                self.snapshot_dir.display(),
+                e
+            ))),
+        }
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("snapshot({})", self.snapshot_dir.display()))
+    }
+}
+
+fn write_snapshot(sm: &StateMachine, snapshot_dir: &Path) -> std::io::Result<()> {
+    if snapshot_dir.exists() && snapshot_dir.read_dir()?.next().is_some() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "snapshot directory is not empty",
+        ));
+    }
+    copy_dir(sm.state_dir.path(), snapshot_dir.join(SNAPSHOT_STATE_DIR))?;
+    sm.write_registry_to_file(snapshot_dir.join(SNAPSHOT_REGISTRY_FILE));
+    let metadata = SnapshotMetadata {
+        time_nanos_since_epoch: systemtime_to_unix_epoch_nanos(sm.time()),
+        nonce: sm.nonce(),
+    };
+    std::fs::write(
+        snapshot_dir.join(SNAPSHOT_METADATA_FILE),
+        serde_json::to_vec(&metadata).expect("Failed to serialize snapshot metadata"),
+    )
+}
+
+const EXPORT_STATE_DIR: &str = "ic_state";
+const EXPORT_REGISTRY_LOCAL_STORE_DIR: &str = "ic_registry_local_store";
+const EXPORT_CONSENSUS_POOL_DIR: &str = "ic_consensus_pool";
+const EXPORT_CONFIG_FILE: &str = "ic.json5";
+
+/// Writes each subnet of the instance to `export_dir/<subnet id>` in the layout of a replica: the
+/// state directory (including a fresh checkpoint), the registry local store, an empty consensus
+/// pool and a replica config referring to them. A subnet can then be inspected with e.g.
+/// `ic-replay <dir>/ic.json5 --subnet-id <subnet id>` or `state-tool` on `<dir>/ic_state`.
+#[derive(Clone, Debug)]
+pub struct ExportState {
+    pub export_dir: PathBuf,
+}
+
+impl Operation for ExportState {
+    type TargetType = PocketIc;
+    fn compute(self, pocket_ic: &mut Self::TargetType) -> OpOut {
+        if self.export_dir.exists()
+            && self
+                .export_dir
+                .read_dir()
+                .map_or(true, |mut entries| entries.next().is_some())
+        {
+            return OpOut::Error(PocketIcError::ExportFailed(format!(
+                "Export directory {} is not empty",
+                self.export_dir.display()
+            )));
+        }
+        for subnet in &pocket_ic.subnets {
+            subnet.set_checkpoints_enabled(true);
+            subnet.tick();
+            subnet.set_checkpoints_enabled(false);
+            subnet.state_manager.flush_tip_channel();
+
+            let subnet_dir = self.export_dir.join(subnet.get_subnet_id().to_string());
+            if let Err(e) = export_state(subnet, &subnet_dir) {
+                return OpOut::Error(PocketIcError::ExportFailed(format!(
+                    "Failed to export state to {}: {}",
+                    subnet_dir.display(),
+                    e
+                )));
+            }
+        }
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("export_state({})", self.export_dir.display()))
+    }
+}
+
+fn export_state(sm: &StateMachine, subnet_dir: &Path) -> std::io::Result<()> {
+    // The config refers to absolute paths, so that it can be used from any directory.
+    std::fs::create_dir_all(subnet_dir)?;
+    let subnet_dir = subnet_dir.canonicalize()?;
+    let state_root = subnet_dir.join(EXPORT_STATE_DIR);
+    let local_store = subnet_dir.join(EXPORT_REGISTRY_LOCAL_STORE_DIR);
+    let consensus_pool = subnet_dir.join(EXPORT_CONSENSUS_POOL_DIR);
+    copy_dir(sm.state_dir.path(), &state_root)?;
+    sm.write_registry_to_local_store(&local_store)?;
+    std::fs::create_dir_all(&consensus_pool)?;
+    let config = serde_json::json!({
+        "registry_client": ic_config::registry_client::Config { local_store },
+        "state_manager": ic_config::state_manager::Config::new(state_root),
+        "artifact_pool": ic_config::artifact_pool::ArtifactPoolTomlConfig::new(consensus_pool, None),
+    });
+    std::fs::write(
+        subnet_dir.join(EXPORT_CONFIG_FILE),
+        serde_json::to_vec_pretty(&config).expect("Failed to serialize the replica config"),
+    )
+}
+
 struct Digest([u8; 32]);
 
 impl std::fmt::Debug for Digest {
@@ -478,9 +1317,19 @@ impl Operation for InstallCanisterAsController {
     type TargetType = PocketIc;
 
     fn compute(self, pic: &mut PocketIc) -> OpOut {
-        pic.subnet
-            .install_wasm_in_mode(self.canister_id, self.mode, self.module, self.payload)
-            .into()
+        let result = pic.subnet_for(self.canister_id).install_wasm_in_mode(
+            self.canister_id,
+            self.mode,
+            self.module,
+            self.payload,
+        );
+        if result.is_ok() {
+            pic.emit(InstanceEvent::CanisterInstalled {
+                canister_id: self.canister_id.get().to_vec(),
+                mode: self.mode.into(),
+            });
+        }
+        result.into()
     }
 
     fn id(&self) -> OpId {
@@ -491,26 +1340,302 @@ impl Operation for InstallCanisterAsController {
 // ================================================================================================================= //
 // Helpers
 
-pub fn create_state_machine(state_dir: Option<TempDir>, runtime: Arc<Runtime>) -> StateMachine {
+fn cycle_balances(subnet: &StateMachine) -> BTreeMap<CanisterId, u128> {
+    subnet
+        .get_latest_state()
+        .canisters_iter()
+        .map(|canister| {
+            (
+                canister.canister_id(),
+                canister.system_state.balance().get(),
+            )
+        })
+        .collect()
+}
+
+fn state_machine_config(subnet_type: SubnetType, fees: &CyclesFees) -> StateMachineConfig {
     let hypervisor_config = execution_environment::Config {
         default_provisional_cycles_balance: Cycles::new(0),
         ..Default::default()
     };
-    let config = StateMachineConfig::new(SubnetConfig::new(SubnetType::System), hypervisor_config);
+    let mut subnet_config = SubnetConfig::new(subnet_type);
+    let config = &mut subnet_config.cycles_account_manager_config;
+    let overrides = [
+        (
+            &mut config.update_message_execution_fee,
+            fees.update_message_execution_fee,
+        ),
+        (
+            &mut config.ten_update_instructions_execution_fee,
+            fees.ten_update_instructions_execution_fee,
+        ),
+        (
+            &mut config.ingress_message_reception_fee,
+            fees.ingress_message_reception_fee,
+        ),
+        (
+            &mut config.ingress_byte_reception_fee,
+            fees.ingress_byte_reception_fee,
+        ),
+        (&mut config.xnet_call_fee, fees.xnet_call_fee),
+        (
+            &mut config.xnet_byte_transmission_fee,
+            fees.xnet_byte_transmission_fee,
+        ),
+        (
+            &mut config.gib_storage_per_second_fee,
+            fees.gib_storage_per_second_fee,
+        ),
+        (
+            &mut config.compute_percent_allocated_per_second_fee,
+            fees.compute_percent_allocated_per_second_fee,
+        ),
+    ];
+    for (fee, value) in overrides {
+        if let Some(value) = value {
+            *fee = Cycles::new(value);
+        }
+    }
+    if let Some(nanos) = fees.allocation_charge_interval_nanos {
+        config.duration_between_allocation_charges = Duration::from_nanos(nanos);
+    }
+    StateMachineConfig::new(subnet_config, hypervisor_config)
+}
+
+pub fn create_state_machine(state_dir: Option<TempDir>, runtime: Arc<Runtime>) -> StateMachine {
+    let config = state_machine_config(SubnetType::System, &CyclesFees::default());
     if let Some(state_dir) = state_dir {
         StateMachineBuilder::new()
             .with_config(Some(config))
             .with_state_dir(state_dir)
             .with_runtime(runtime)
+            .with_canister_logs()
             .build()
     } else {
         StateMachineBuilder::new()
             .with_config(Some(config))
             .with_runtime(runtime)
+            .with_canister_logs()
             .build()
     }
 }
 
+/// Number of nodes of a fiduciary subnet, which determines its fees.
+const FIDUCIARY_SUBNET_SIZE: usize = 28;
+/// Cycles of the Bitcoin canister stubs, which are charged on application subnets.
+const BITCOIN_STUB_CYCLES: u128 = 100_000_000_000_000;
+
+/// The canisters the management canister routes Bitcoin testnet (and regtest) and mainnet
+/// requests to.
+fn bitcoin_canister_ids() -> [CanisterId; 2] {
+    [
+        execution_environment::BITCOIN_TESTNET_CANISTER_ID,
+        execution_environment::BITCOIN_MAINNET_CANISTER_ID,
+    ]
+    .map(|id| CanisterId::from_str(id).expect("Invalid Bitcoin canister id"))
+}
+
+/// A canister that answers the Bitcoin API as if the chain had no UTXOs.
+fn bitcoin_stub_wasm() -> Vec<u8> {
+    let replies = [
+        ("bitcoin_get_balance", candid::encode_one(0_u64)),
+        (
+            "bitcoin_get_utxos",
+            candid::encode_one(GetUtxosResponse {
+                utxos: vec![],
+                tip_block_hash: vec![],
+                tip_height: 0,
+                next_page: None,
+            }),
+        ),
+        (
+            "bitcoin_get_current_fee_percentiles",
+            candid::encode_one(vec![0_u64; 100]),
+        ),
+        ("bitcoin_send_transaction", candid::encode_args(())),
+    ];
+    let mut data = String::new();
+    let mut funcs = String::new();
+    let mut offset = 0;
+    for (method, reply) in replies {
+        let reply = reply.expect("Failed to encode a Bitcoin stub reply");
+        data.extend(reply.iter().map(|byte| format!("\\{:02x}", byte)));
+        funcs.push_str(&format!(
+            r#"(func (export "canister_update {method}")
+                 (call $msg_reply_data_append (i32.const {offset}) (i32.const {len}))
+                 (call $msg_reply))
+            "#,
+            len = reply.len()
+        ));
+        offset += reply.len();
+    }
+    wat::parse_str(format!(
+        r#"(module
+             (import "ic0" "msg_reply" (func $msg_reply))
+             (import "ic0" "msg_reply_data_append"
+               (func $msg_reply_data_append (param i32 i32)))
+             (memory $memory 1)
+             (data (i32.const 0) "{data}")
+             {funcs})"#
+    ))
+    .expect("Invalid Bitcoin stub")
+}
+
+/// Creates an instance with one state machine per subnet of the given topology.
+/// All subnets share a routing table in which the NNS subnet, if any, comes
+/// first, so that it hosts the well-known NNS canister ids. Canisters are created
+/// on the first application subnet, or on the first subnet if there is none.
+///
+/// With [InstanceFeatures::bitcoin], the Bitcoin canister stubs are hosted on the Bitcoin subnet,
+/// or on the subnet that hosts canisters by default if there is none.
+pub fn create_pocket_ic(
+    topology: &Topology,
+    features: &InstanceFeatures,
+    runtime: Arc<Runtime>,
+) -> PocketIc {
+    let mut subnet_specs: Vec<(SubnetType, Option<usize>)> = vec![];
+    if topology.nns {
+        subnet_specs.push((SubnetType::System, None));
+    }
+    if topology.system {
+        subnet_specs.push((SubnetType::System, None));
+    }
+    if topology.bitcoin {
+        subnet_specs.push((SubnetType::System, None));
+    }
+    if topology.fiduciary {
+        subnet_specs.push((SubnetType::Application, Some(FIDUCIARY_SUBNET_SIZE)));
+    }
+    for _ in 0..topology.application {
+        subnet_specs.push((SubnetType::Application, None));
+    }
+
+    let subnet_ids: Vec<SubnetId> = (1..=subnet_specs.len() as u64)
+        .map(|n| SubnetId::from(PrincipalId::new_subnet_test_id(n)))
+        .collect();
+    let mut routing_table = RoutingTable::new();
+    for subnet_id in &subnet_ids {
+        routing_table_insert_subnet(&mut routing_table, *subnet_id)
+            .expect("Failed to update the routing table");
+    }
+
+    let first_application_subnet = subnet_specs.len() - topology.application;
+    if features.bitcoin {
+        let bitcoin_subnet = if topology.bitcoin {
+            topology.nns as usize + topology.system as usize
+        } else if topology.application > 0 {
+            first_application_subnet
+        } else {
+            0
+        };
+        for canister_id in bitcoin_canister_ids() {
+            // Large topologies may already route the id to some subnet.
+            if routing_table.route(canister_id.get()).is_none() {
+                routing_table
+                    .insert(
+                        CanisterIdRange {
+                            start: canister_id,
+                            end: canister_id,
+                        },
+                        subnet_ids[bitcoin_subnet],
+                    )
+                    .expect("Failed to update the routing table");
+            }
+        }
+    }
+    let ecdsa_keys: Vec<_> = features
+        .ecdsa_keys
+        .iter()
+        .map(|name| EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: name.clone(),
+        })
+        .collect();
+
+    let mut subnets: Vec<StateMachine> = subnet_specs
+        .into_iter()
+        .zip(subnet_ids.iter())
+        .map(|((subnet_type, subnet_size), subnet_id)| {
+            let builder = match features.seed {
+                Some(seed) => StateMachineBuilder::new().with_seed(seed),
+                None => StateMachineBuilder::new(),
+            };
+            let builder = ecdsa_keys
+                .iter()
+                .fold(builder, |builder, key| builder.with_ecdsa_key(key.clone()))
+                .with_config(Some(state_machine_config(
+                    subnet_type,
+                    &features.cycles_fees,
+                )))
+                .with_subnet_type(subnet_type)
+                .with_subnet_id(*subnet_id)
+                .with_nns_subnet_id(subnet_ids[0])
+                .with_routing_table(routing_table.clone())
+                .with_runtime(runtime.clone())
+                .with_canister_logs();
+            match subnet_size {
+                Some(subnet_size) => builder.with_subnet_size(subnet_size),
+                None => builder,
+            }
+            .build()
+        })
+        .collect();
+
+    if topology.application > 0 {
+        subnets.swap(0, first_application_subnet);
+    }
+    let pic = PocketIc::new_with_subnets(subnets, routing_table);
+    if features.bitcoin {
+        for canister_id in bitcoin_canister_ids() {
+            let subnet = pic.subnet_for(canister_id);
+            subnet.create_canister_with_cycles(
+                Some(canister_id.get()),
+                Cycles::new(BITCOIN_STUB_CYCLES),
+                None,
+            );
+            subnet
+                .install_existing_canister(canister_id, bitcoin_stub_wasm(), vec![])
+                .expect("Failed to install the Bitcoin canister stub");
+        }
+    }
+    pic
+}
+
+/// Restores a state machine from a snapshot written by the [Snapshot] operation.
+/// The snapshot directory itself is left untouched, so it can be restored many times.
+pub fn create_state_machine_from_snapshot(
+    snapshot_dir: &Path,
+    runtime: Arc<Runtime>,
+) -> std::io::Result<StateMachine> {
+    let metadata: SnapshotMetadata =
+        serde_json::from_slice(&std::fs::read(snapshot_dir.join(SNAPSHOT_METADATA_FILE))?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let registry_file = snapshot_dir.join(SNAPSHOT_REGISTRY_FILE);
+    if !registry_file.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("missing registry file {}", registry_file.display()),
+        ));
+    }
+    let state_dir = TempDir::new()?;
+    copy_dir(snapshot_dir.join(SNAPSHOT_STATE_DIR), state_dir.path())?;
+
+    Ok(StateMachineBuilder::new()
+        .with_config(Some(state_machine_config(
+            SubnetType::System,
+            &CyclesFees::default(),
+        )))
+        .with_state_dir(state_dir)
+        .with_registry_from_file(registry_file)
+        .with_time(Time::from_nanos_since_unix_epoch(
+            metadata.time_nanos_since_epoch,
+        ))
+        .with_nonce(metadata.nonce)
+        .with_runtime(runtime)
+        .with_canister_logs()
+        .build())
+}
+
 fn systemtime_to_unix_epoch_nanos(st: SystemTime) -> u64 {
     st.duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
@@ -522,6 +1647,10 @@ fn systemtime_to_unix_epoch_nanos(st: SystemTime) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ic_ic00_types::{
+        BitcoinGetBalanceArgs, BitcoinNetwork, DerivationPath, ECDSAPublicKeyArgs,
+        ECDSAPublicKeyResponse,
+    };
     use pocket_ic::WasmResult;
 
     #[test]
@@ -529,9 +1658,9 @@ mod tests {
         let pic = PocketIc::default();
 
         let state0 = pic.get_state_label();
-        let canister_id = pic.subnet.create_canister(None);
+        let canister_id = pic.default_subnet().create_canister(None);
         let state1 = pic.get_state_label();
-        let _ = pic.subnet.delete_canister(canister_id);
+        let _ = pic.default_subnet().delete_canister(canister_id);
         let state2 = pic.get_state_label();
 
         assert!(state0 != state1);
@@ -619,6 +1748,594 @@ mod tests {
         assert_eq!(final_balance, changed_balance + amount);
     }
 
+    #[test]
+    fn test_snapshot_restore() {
+        let (mut pic, canister_id) = new_pic_counter_installed();
+        let (query, update) = query_update_constructors(canister_id);
+
+        compute_assert_state_change(&mut pic, update("write"));
+        let time = Time::from_nanos_since_unix_epoch(1_620_328_630_000_000_000);
+        compute_assert_state_change(&mut pic, SetTime { time });
+        let OpOut::CanisterResult(Ok(WasmResult::Reply(expected_bytes))) =
+            compute_assert_state_immutable(&mut pic, query("read"))
+        else {
+            unreachable!()
+        };
+
+        let tmp = TempDir::new().unwrap();
+        let snapshot_dir = tmp.path().join("snapshot");
+        let op = Snapshot {
+            snapshot_dir: snapshot_dir.clone(),
+        };
+        assert_eq!(op.compute(&mut pic), OpOut::NoOutput);
+        let OpOut::Time(expected_time) = compute_assert_state_immutable(&mut pic, GetTime {})
+        else {
+            unreachable!()
+        };
+
+        let runtime = Arc::new(Runtime::new().unwrap());
+        let mut restored =
+            PocketIc::new(create_state_machine_from_snapshot(&snapshot_dir, runtime).unwrap());
+        assert_eq!(
+            compute_assert_state_immutable(&mut restored, GetTime {}),
+            OpOut::Time(expected_time)
+        );
+        let OpOut::CanisterResult(Ok(WasmResult::Reply(restored_bytes))) =
+            compute_assert_state_immutable(&mut restored, query("read"))
+        else {
+            unreachable!()
+        };
+        assert_eq!(restored_bytes, expected_bytes);
+
+        // A snapshot never overwrites an existing one.
+        let op = Snapshot { snapshot_dir };
+        assert!(matches!(
+            op.compute(&mut pic),
+            OpOut::Error(PocketIcError::SnapshotFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_state() {
+        let (mut pic, _) = new_pic_counter_installed();
+        let tmp = TempDir::new().unwrap();
+        let export_dir = tmp.path().join("export");
+        let op = ExportState {
+            export_dir: export_dir.clone(),
+        };
+        assert_eq!(op.compute(&mut pic), OpOut::NoOutput);
+
+        let subnet_dir = export_dir
+            .join(pic.default_subnet().get_subnet_id().to_string())
+            .canonicalize()
+            .unwrap();
+        let config = ic_config::Config::load_with_default(
+            &ic_config::ConfigSource::File(subnet_dir.join(EXPORT_CONFIG_FILE)),
+            ic_config::Config::new(tmp.path().join("default")),
+        )
+        .unwrap();
+        assert_eq!(
+            config.state_manager.state_root(),
+            subnet_dir.join(EXPORT_STATE_DIR)
+        );
+        assert_eq!(
+            config.registry_client.local_store,
+            subnet_dir.join(EXPORT_REGISTRY_LOCAL_STORE_DIR)
+        );
+        let checkpoints = subnet_dir.join(EXPORT_STATE_DIR).join("checkpoints");
+        assert!(checkpoints.read_dir().unwrap().next().is_some());
+        assert!(config
+            .registry_client
+            .local_store
+            .read_dir()
+            .unwrap()
+            .next()
+            .is_some());
+
+        // An export never overwrites an existing one.
+        let op = ExportState { export_dir };
+        assert!(matches!(
+            op.compute(&mut pic),
+            OpOut::Error(PocketIcError::ExportFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_topology() {
+        let topology = Topology {
+            nns: true,
+            application: 2,
+            ..Default::default()
+        };
+        let mut pic = create_pocket_ic(
+            &topology,
+            &InstanceFeatures::default(),
+            Arc::new(Runtime::new().unwrap()),
+        );
+        assert_eq!(pic.subnets.len(), 3);
+
+        // The NNS subnet owns the first canister range.
+        let nns_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        assert_eq!(
+            pic.subnet_for(CanisterId::from_u64(0)).get_subnet_id(),
+            nns_subnet_id
+        );
+
+        // Canisters are created on the first application subnet.
+        let canister_id = pic.default_subnet().create_canister(None);
+        assert_ne!(pic.default_subnet().get_subnet_id(), nns_subnet_id);
+        assert_eq!(
+            pic.subnet_for(canister_id).get_subnet_id(),
+            pic.default_subnet().get_subnet_id()
+        );
+        assert_eq!(
+            compute_assert_state_immutable(&mut pic, CanisterExists { canister_id }),
+            OpOut::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_of_multiple_subnets_fails() {
+        let topology = Topology {
+            nns: true,
+            application: 1,
+            ..Default::default()
+        };
+        let mut pic = create_pocket_ic(
+            &topology,
+            &InstanceFeatures::default(),
+            Arc::new(Runtime::new().unwrap()),
+        );
+
+        assert!(matches!(
+            Checkpoint.compute(&mut pic),
+            OpOut::Error(PocketIcError::CheckpointFailed(_))
+        ));
+        let tmp = TempDir::new().unwrap();
+        let op = Snapshot {
+            snapshot_dir: tmp.path().join("snapshot"),
+        };
+        assert!(matches!(
+            op.compute(&mut pic),
+            OpOut::Error(PocketIcError::SnapshotFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_canister_with_id() {
+        let topology = Topology {
+            nns: true,
+            application: 1,
+            ..Default::default()
+        };
+        let mut pic = create_pocket_ic(
+            &topology,
+            &InstanceFeatures::default(),
+            Arc::new(Runtime::new().unwrap()),
+        );
+        let nns_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let application_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(2));
+        let controller = PrincipalId::new_user_test_id(1);
+        let create = |canister_id, subnet_id| CreateCanisterWithId {
+            canister_id,
+            controllers: vec![controller],
+            cycles: 1_000_000,
+            subnet_id,
+        };
+
+        // Without a subnet, the canister is created on the subnet the id is routed to.
+        let ledger_id = CanisterId::from_u64(2);
+        assert_eq!(
+            compute_assert_state_change(&mut pic, create(ledger_id, None)),
+            OpOut::CanisterId(ledger_id)
+        );
+        let nns_subnet = pic.subnet_for(ledger_id);
+        assert_eq!(nns_subnet.get_subnet_id(), nns_subnet_id);
+        assert_eq!(nns_subnet.cycle_balance(ledger_id), 1_000_000);
+        assert!(nns_subnet
+            .get_latest_state()
+            .canister_state(&ledger_id)
+            .unwrap()
+            .system_state
+            .controllers
+            .contains(&controller));
+        assert!(matches!(
+            create(ledger_id, None).compute(&mut pic),
+            OpOut::Error(PocketIcError::CanisterCreationFailed(_))
+        ));
+
+        // With a subnet, the id is rerouted to it.
+        let canister_id = CanisterId::from_u64(7);
+        assert_eq!(
+            compute_assert_state_change(&mut pic, create(canister_id, Some(application_subnet_id))),
+            OpOut::CanisterId(canister_id)
+        );
+        assert_eq!(
+            compute_assert_state_immutable(&mut pic, GetSubnet { canister_id }),
+            OpOut::SubnetId(application_subnet_id)
+        );
+        assert_eq!(
+            compute_assert_state_immutable(&mut pic, CanisterExists { canister_id }),
+            OpOut::Bool(true)
+        );
+
+        let unknown_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(42));
+        assert!(matches!(
+            create(CanisterId::from_u64(8), Some(unknown_subnet_id)).compute(&mut pic),
+            OpOut::Error(PocketIcError::CanisterCreationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_bitcoin_stub() {
+        let topology = Topology {
+            application: 1,
+            ..Default::default()
+        };
+        let features = InstanceFeatures {
+            bitcoin: true,
+            ..Default::default()
+        };
+        let mut pic = create_pocket_ic(&topology, &features, Arc::new(Runtime::new().unwrap()));
+        let proxy = install_management_canister_proxy(&mut pic, "bitcoin_get_balance");
+        for network in [BitcoinNetwork::Testnet, BitcoinNetwork::Mainnet] {
+            let args = BitcoinGetBalanceArgs {
+                address: "bcrt1qu58aj62urda83c00eylc6w34yl2s6e5rkzqet7".to_string(),
+                network,
+                min_confirmations: None,
+            };
+            let get_balance = ExecuteIngressMessage(CanisterCall {
+                sender: PrincipalId::new_anonymous(),
+                canister_id: proxy,
+                method: "call".into(),
+                payload: candid::encode_one(args).unwrap(),
+            });
+            let OpOut::CanisterResult(Ok(WasmResult::Reply(bytes))) =
+                compute_assert_state_change(&mut pic, get_balance)
+            else {
+                unreachable!()
+            };
+            assert_eq!(candid::decode_one::<u64>(&bytes).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn test_ecdsa_keys() {
+        let topology = Topology {
+            application: 1,
+            ..Default::default()
+        };
+        let features = InstanceFeatures {
+            ecdsa_keys: vec!["key_1".to_string()],
+            ..Default::default()
+        };
+        let mut pic = create_pocket_ic(&topology, &features, Arc::new(Runtime::new().unwrap()));
+        let proxy = install_management_canister_proxy(&mut pic, "ecdsa_public_key");
+        let mut public_key = |name: &str| {
+            let args = ECDSAPublicKeyArgs {
+                canister_id: None,
+                derivation_path: DerivationPath::new(vec![]),
+                key_id: EcdsaKeyId {
+                    curve: EcdsaCurve::Secp256k1,
+                    name: name.to_string(),
+                },
+            };
+            let get_public_key = ExecuteIngressMessage(CanisterCall {
+                sender: PrincipalId::new_anonymous(),
+                canister_id: proxy,
+                method: "call".into(),
+                payload: args.encode(),
+            });
+            match compute_assert_state_change(&mut pic, get_public_key) {
+                OpOut::CanisterResult(Ok(WasmResult::Reply(bytes))) => {
+                    Ok(ECDSAPublicKeyResponse::decode(&bytes).unwrap().public_key)
+                }
+                OpOut::CanisterResult(Err(error)) => Err(error),
+                out => panic!("Unexpected result: {:?}", out),
+            }
+        };
+
+        let key_1 = public_key("key_1").unwrap();
+        assert!(!key_1.is_empty());
+        // All keys are backed by the same test secret key.
+        assert_eq!(public_key("master_ecdsa_public_key").unwrap(), key_1);
+        assert!(public_key("key_2").is_err());
+    }
+
+    /// Installs a canister on the default subnet whose `call` method forwards its argument to
+    /// `method` of the management canister and replies with the response. Rejects make the
+    /// canister trap with the reject message.
+    fn install_management_canister_proxy(pic: &mut PocketIc, method: &str) -> CanisterId {
+        let canister_id = pic.default_subnet().create_canister(None);
+        compute_assert_state_change(
+            pic,
+            SetCycles {
+                canister_id,
+                amount: 100_000_000_000_000,
+            },
+        );
+        let module = wat::parse_str(format!(
+            r#"(module
+                 (import "ic0" "call_new"
+                   (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+                 (import "ic0" "call_data_append" (func $call_data_append (param i32 i32)))
+                 (import "ic0" "call_perform" (func $call_perform (result i32)))
+                 (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+                 (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+                 (import "ic0" "msg_reject_msg_size" (func $msg_reject_msg_size (result i32)))
+                 (import "ic0" "msg_reject_msg_copy"
+                   (func $msg_reject_msg_copy (param i32 i32 i32)))
+                 (import "ic0" "msg_reply_data_append"
+                   (func $msg_reply_data_append (param i32 i32)))
+                 (import "ic0" "msg_reply" (func $msg_reply))
+                 (import "ic0" "trap" (func $trap (param i32 i32)))
+                 (func $call
+                   (call $msg_arg_data_copy (i32.const 1024) (i32.const 0) (call $msg_arg_data_size))
+                   ;; The management canister has an empty id.
+                   (call $call_new (i32.const 0) (i32.const 0) (i32.const 0) (i32.const {len})
+                     (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 0))
+                   (call $call_data_append (i32.const 1024) (call $msg_arg_data_size))
+                   (drop (call $call_perform)))
+                 (func $on_reply (param i32)
+                   (call $msg_arg_data_copy (i32.const 1024) (i32.const 0) (call $msg_arg_data_size))
+                   (call $msg_reply_data_append (i32.const 1024) (call $msg_arg_data_size))
+                   (call $msg_reply))
+                 (func $on_reject (param i32)
+                   (call $msg_reject_msg_copy
+                     (i32.const 1024) (i32.const 0) (call $msg_reject_msg_size))
+                   (call $trap (i32.const 1024) (call $msg_reject_msg_size)))
+                 (table funcref (elem $on_reply $on_reject))
+                 (memory $memory 1)
+                 (data (i32.const 0) "{method}")
+                 (export "memory" (memory $memory))
+                 (export "canister_update call" (func $call)))"#,
+            len = method.len()
+        ))
+        .unwrap();
+        compute_assert_state_change(
+            pic,
+            InstallCanisterAsController {
+                canister_id,
+                mode: CanisterInstallMode::Install,
+                module,
+                payload: vec![],
+            },
+        );
+        canister_id
+    }
+
+    #[test]
+    fn test_cycles_fees() {
+        let topology = Topology {
+            application: 1,
+            ..Default::default()
+        };
+        let fee: u128 = 100_000_000_000;
+        let features = InstanceFeatures {
+            cycles_fees: CyclesFees {
+                update_message_execution_fee: Some(fee),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pic = create_pocket_ic(&topology, &features, Arc::new(Runtime::new().unwrap()));
+        let canister_id = pic.default_subnet().create_canister(None);
+        compute_assert_state_change(
+            &mut pic,
+            SetCycles {
+                canister_id,
+                amount: 100_000_000_000_000,
+            },
+        );
+        compute_assert_state_change(
+            &mut pic,
+            InstallCanisterAsController {
+                canister_id,
+                mode: CanisterInstallMode::Install,
+                module: counter_wasm(),
+                payload: vec![],
+            },
+        );
+        let write = || {
+            ExecuteIngressMessage(CanisterCall {
+                sender: PrincipalId::new_anonymous(),
+                canister_id,
+                method: "write".into(),
+                payload: vec![],
+            })
+        };
+
+        let amount = 10 * fee;
+        compute_assert_state_change(
+            &mut pic,
+            SetCycles {
+                canister_id,
+                amount,
+            },
+        );
+        let OpOut::CanisterResult(Ok(_)) = compute_assert_state_change(&mut pic, write()) else {
+            unreachable!()
+        };
+        let OpOut::Cycles(balance) =
+            compute_assert_state_immutable(&mut pic, GetCyclesBalance { canister_id })
+        else {
+            unreachable!()
+        };
+        assert!(balance <= amount - fee);
+
+        // A canister without cycles cannot execute update calls.
+        compute_assert_state_change(
+            &mut pic,
+            SetCycles {
+                canister_id,
+                amount: 0,
+            },
+        );
+        let OpOut::CanisterResult(Err(_)) = write().compute(&mut pic) else {
+            unreachable!()
+        };
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut pic = PocketIc::default();
+        let canister_id = pic.default_subnet().create_canister(None);
+        let call = |method: &str| CanisterCall {
+            sender: PrincipalId::new_anonymous(),
+            canister_id,
+            method: method.into(),
+            payload: vec![],
+        };
+        let OpOut::Time(time_before) = compute_assert_state_immutable(&mut pic, GetTime {}) else {
+            unreachable!()
+        };
+
+        let batch = Batch(vec![
+            BatchOperation::Install(InstallCanisterAsController {
+                canister_id,
+                mode: CanisterInstallMode::Install,
+                module: counter_wasm(),
+                payload: vec![],
+            }),
+            BatchOperation::Call(call("write")),
+            BatchOperation::AdvanceTime(Duration::from_secs(60)),
+            BatchOperation::Call(call("write")),
+            BatchOperation::Query(call("read")),
+            BatchOperation::Call(call("missing")),
+        ]);
+        let OpOut::Batch(results) = compute_assert_state_change(&mut pic, batch) else {
+            unreachable!()
+        };
+
+        assert_eq!(results.len(), 6);
+        assert_eq!(
+            results[0],
+            OpOut::CanisterResult(Ok(WasmResult::Reply(vec![])))
+        );
+        assert_eq!(results[2], OpOut::NoOutput);
+        assert_eq!(
+            results[4],
+            OpOut::CanisterResult(Ok(WasmResult::Reply(vec![2, 0, 0, 0])))
+        );
+        // A failing call does not abort the batch.
+        assert!(matches!(results[5], OpOut::CanisterResult(Err(_))));
+        let OpOut::Time(time_after) = compute_assert_state_immutable(&mut pic, GetTime {}) else {
+            unreachable!()
+        };
+        assert!(time_after >= time_before + Duration::from_secs(60).as_nanos() as u64);
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut pic = PocketIc::default();
+        let management_call = |method: &str, payload| {
+            ExecuteIngressMessage(CanisterCall {
+                sender: PrincipalId::new_anonymous(),
+                canister_id: CanisterId::ic_00(),
+                method: method.into(),
+                payload,
+            })
+        };
+        let create = management_call(
+            "provisional_create_canister_with_cycles",
+            ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode(),
+        );
+        let OpOut::CanisterResult(Ok(WasmResult::Reply(bytes))) =
+            compute_assert_state_change(&mut pic, create)
+        else {
+            unreachable!()
+        };
+        let canister_id = CanisterIdRecord::decode(&bytes).unwrap().get_canister_id();
+        let install = management_call(
+            "install_code",
+            InstallCodeArgs::new(
+                CanisterInstallMode::Install,
+                canister_id,
+                counter_wasm(),
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .encode(),
+        );
+        compute_assert_state_change(&mut pic, install);
+        let (query, update) = query_update_constructors(canister_id);
+        compute_assert_state_change(&mut pic, update("write"));
+        let OpOut::CanisterResult(expected_result) =
+            compute_assert_state_change(&mut pic, update("write"))
+        else {
+            unreachable!()
+        };
+
+        let OpOut::IngressHistory(history) =
+            compute_assert_state_immutable(&mut pic, GetIngressHistory)
+        else {
+            unreachable!()
+        };
+        assert_eq!(history.len(), 4);
+
+        let mut replayed = PocketIc::default();
+        let OpOut::CanisterResults(results) = Replay(history).compute(&mut replayed) else {
+            unreachable!()
+        };
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(results[3], expected_result);
+        assert_eq!(
+            compute_assert_state_immutable(&mut replayed, query("read")),
+            compute_assert_state_immutable(&mut pic, query("read"))
+        );
+    }
+
+    #[test]
+    fn test_canister_logs() {
+        let mut pic = PocketIc::default();
+        let canister_id = pic.default_subnet().create_canister(None);
+        let install_op = InstallCanisterAsController {
+            canister_id,
+            mode: CanisterInstallMode::Install,
+            module: wat::parse_str(LOGGER_WAT).unwrap(),
+            payload: vec![],
+        };
+        compute_assert_state_change(&mut pic, install_op);
+        let (_, update) = query_update_constructors(canister_id);
+
+        compute_assert_state_change(&mut pic, update("log"));
+        let OpOut::CanisterResult(Err(_)) = compute_assert_state_change(&mut pic, update("fail"))
+        else {
+            unreachable!()
+        };
+
+        let OpOut::CanisterLogs(logs) = compute_assert_state_immutable(
+            &mut pic,
+            GetCanisterLogs {
+                canister_id,
+                since_nanos: 0,
+            },
+        ) else {
+            unreachable!()
+        };
+        let kinds: Vec<_> = logs.iter().map(|record| record.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![CanisterLogKind::DebugPrint, CanisterLogKind::Trap]
+        );
+        assert_eq!(logs[0].message, "hello");
+        assert!(logs[1].message.contains("boom"));
+
+        let OpOut::CanisterLogs(later_logs) = compute_assert_state_immutable(
+            &mut pic,
+            GetCanisterLogs {
+                canister_id,
+                since_nanos: logs[1].time_nanos_since_epoch + 1,
+            },
+        ) else {
+            unreachable!()
+        };
+        assert!(later_logs.is_empty());
+    }
+
     fn query_update_constructors(
         canister_id: CanisterId,
     ) -> (
@@ -640,7 +2357,7 @@ mod tests {
 
     fn new_pic_counter_installed() -> (PocketIc, CanisterId) {
         let mut pic = PocketIc::default();
-        let canister_id = pic.subnet.create_canister(None);
+        let canister_id = pic.default_subnet().create_canister(None);
 
         let module = counter_wasm();
         let install_op = InstallCanisterAsController {
@@ -681,6 +2398,27 @@ mod tests {
         wat::parse_str(COUNTER_WAT).unwrap().as_slice().to_vec()
     }
 
+    const LOGGER_WAT: &str = r#"
+(module
+  (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+  (import "ic0" "trap" (func $trap (param i32 i32)))
+  (import "ic0" "msg_reply" (func $msg_reply))
+
+  (func $log
+    (call $debug_print (i32.const 0) (i32.const 5))
+    (call $msg_reply))
+
+  (func $fail
+    (call $trap (i32.const 5) (i32.const 4)))
+
+  (memory $memory 1)
+  (data (i32.const 0) "helloboom")
+  (export "memory" (memory $memory))
+  (export "canister_update log" (func $log))
+  (export "canister_update fail" (func $fail))
+)
+    "#;
+
     const COUNTER_WAT: &str = r#"
 ;; Counter with global variable ;;
 (module
@@ -4,15 +4,23 @@
 /// body. This has to be canonicalized into a PocketIc Operation before we can
 /// deterministically update the PocketIc state machine.
 ///
-use super::state::{InstanceState, OpOut, PocketIcApiState, UpdateReply};
+use super::state::{
+    InstanceState, OpOut, PocketIcApiState, PocketIcError, UpdateError, UpdateReply,
+};
+use crate::pocket_ic::{
+    AddCycles, CreateCanisterWithId, ExecuteIngressMessage, GetCyclesBalance, GetStableMemory,
+    GetSubnet, GetTime, Query, RootKey, SetCycles, SetStableMemory, SetTime, Tick,
+};
 use crate::pocket_ic::{
-    AddCycles, ExecuteIngressMessage, GetCyclesBalance, GetStableMemory, GetTime, Query, RootKey,
-    SetStableMemory, SetTime, Tick,
+    Batch, BatchOperation, CanisterExists, Checkpoint, ExportState, GetCanisterLogs,
+    GetIngressHistory, IngressRecord, Replay, Snapshot,
 };
-use crate::pocket_ic::{CanisterExists, Checkpoint};
 use crate::{
     copy_dir,
-    pocket_ic::{create_state_machine, PocketIc},
+    nns::{install_nns, NnsWasms},
+    pocket_ic::{
+        create_pocket_ic, create_state_machine, create_state_machine_from_snapshot, PocketIc,
+    },
     BindOperation, BlobStore, InstanceId, Operation,
 };
 use axum::body::HttpBody;
@@ -21,34 +29,40 @@ use axum::{
     extract::{self, Path, State},
     headers,
     http::{self, HeaderMap, HeaderName, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
     routing::{delete, get, post},
     Json, Router,
 };
-use ic_state_machine_tests::StateMachine;
+use futures::stream::{self, Stream};
 use ic_types::CanisterId;
 use pocket_ic::common::rest::{
-    self, ApiResponse, RawAddCycles, RawCanisterCall, RawCanisterId, RawCanisterResult, RawCycles,
-    RawSetStableMemory, RawStableMemory, RawTime, RawWasmResult,
+    self, ApiResponse, InstanceEvent, RawAddCycles, RawBatchOperation, RawCanisterCall,
+    RawCanisterId, RawCanisterLogRecord, RawCanisterResult, RawCreateCanisterWithId, RawCycles,
+    RawIngressRecord, RawSetCycles, RawSetStableMemory, RawStableMemory, RawSubnetId, RawTime,
+    RawWasmResult,
 };
 use pocket_ic::WasmResult;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::atomic::AtomicU64;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, convert::Infallible, str::FromStr, sync::Arc, time::Duration};
 use tempfile::TempDir;
-use tokio::{runtime::Runtime, sync::RwLock, time::Instant};
+use tokio::{
+    runtime::Runtime,
+    sync::{broadcast, RwLock},
+    time::Instant,
+};
 
 /// Name of a header that allows clients to specify for how long their are willing to wait for a
 /// response on a open http request.
 pub static TIMEOUT_HEADER_NAME: HeaderName = HeaderName::from_static("processing-timeout-ms");
 
-pub type InstanceMap = Arc<RwLock<HashMap<InstanceId, RwLock<StateMachine>>>>;
-
 pub type ApiState = PocketIcApiState<PocketIc>;
 
+pub type InstanceEvents = Arc<RwLock<HashMap<InstanceId, broadcast::Sender<InstanceEvent>>>>;
+
 #[derive(Clone)]
 pub struct AppState {
-    // temporary
-    pub instance_map: InstanceMap,
     pub instances_sequence_counter: Arc<AtomicU64>,
     //
     pub api_state: ApiState,
@@ -56,6 +70,9 @@ pub struct AppState {
     pub last_request: Arc<RwLock<Instant>>,
     pub runtime: Arc<Runtime>,
     pub blob_store: Arc<dyn BlobStore>,
+    pub instance_events: InstanceEvents,
+    /// The directory with the NNS canister Wasms, required to bootstrap the NNS.
+    pub nns_wasm_dir: Option<PathBuf>,
 }
 
 pub fn instance_read_routes<S>() -> Router<S>
@@ -70,6 +87,7 @@ where
         .directory_route("/get_cycles", post(handler_get_cycles))
         .directory_route("/get_stable_memory", post(handler_get_stable_memory))
         .directory_route("/canister_exists", post(handler_canister_exists))
+        .directory_route("/get_subnet", post(handler_get_subnet))
         .directory_route("/root_key", post(handler_root_key))
 }
 
@@ -85,6 +103,11 @@ where
         )
         .directory_route("/set_time", post(handler_set_time))
         .directory_route("/add_cycles", post(handler_add_cycles))
+        .directory_route("/set_cycles", post(handler_set_cycles))
+        .directory_route(
+            "/create_canister_with_id",
+            post(handler_create_canister_with_id),
+        )
         .directory_route("/set_stable_memory", post(handler_set_stable_memory))
         .directory_route("/create_checkpoint", post(handler_create_checkpoint))
         .directory_route("/tick", post(handler_tick))
@@ -102,12 +125,40 @@ where
         //
         // Create a new IC instance. Returns an InstanceId.
         // If the body contains an existing checkpoint name, the instance is restored from that,
-        // otherwise a new instance is created.
+        // otherwise a new instance is created, made of the subnets of the given topology.
         .route("/", post(create_instance))
         //
+        // Create a new IC instance from a snapshot directory. Returns an InstanceId.
+        .directory_route("/from_snapshot", post(create_instance_from_snapshot))
+        //
         // Deletes an instance.
         .directory_route("/:id", delete(delete_instance))
         //
+        // Writes a snapshot of an instance to a directory.
+        .directory_route("/:id/snapshot", post(handler_snapshot))
+        //
+        // Writes the state and registry of an instance in the layout of a replica, for
+        // `ic-replay` and `state-tool`.
+        .directory_route("/:id/export_state", post(handler_export_state))
+        //
+        // Streams the activity of an instance as server-sent events.
+        .directory_route("/:id/events", get(handler_events))
+        //
+        // Lists the ingress messages executed on an instance.
+        .directory_route("/:id/ingress_history", get(handler_ingress_history))
+        //
+        // Re-executes recorded ingress messages on an instance.
+        .directory_route("/:id/replay", post(handler_replay))
+        //
+        // Executes a list of installs, calls, queries and time advances in one request.
+        .directory_route("/:id/batch", post(handler_batch))
+        //
+        // Returns the debug prints and traps of a canister.
+        .directory_route(
+            "/:id/canisters/:canister_id/logs",
+            get(handler_canister_logs),
+        )
+        //
         // All the read-only endpoints
         .nest("/:id/read", instance_read_routes())
         //
@@ -128,6 +179,10 @@ where
         .update_with_timeout(op.on_instance(instance_id), timeout)
         .await
     {
+        Err(UpdateError::QuotaExceeded(quota)) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            ApiResponse::QuotaExceeded(quota.into()),
+        ),
         Err(e) => (
             // TODO: what StatusCode should we use here?
             StatusCode::BAD_REQUEST,
@@ -181,6 +236,11 @@ impl From<OpOut> for (StatusCode, ApiResponse<()>) {
         match value {
             OpOut::NoOutput => (StatusCode::OK, ApiResponse::Success(())),
             OpOut::Checkpoint(_) => (StatusCode::OK, ApiResponse::Success(())),
+            OpOut::Error(
+                PocketIcError::CheckpointFailed(message)
+                | PocketIcError::SnapshotFailed(message)
+                | PocketIcError::ExportFailed(message),
+            ) => (StatusCode::BAD_REQUEST, ApiResponse::Error { message }),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ApiResponse::Error {
@@ -224,21 +284,98 @@ impl From<OpOut> for (StatusCode, ApiResponse<RawStableMemory>) {
     }
 }
 
+fn raw_canister_result(result: Result<WasmResult, pocket_ic::UserError>) -> RawCanisterResult {
+    match result {
+        Ok(WasmResult::Reply(wasm_result)) => {
+            RawCanisterResult::Ok(RawWasmResult::Reply(wasm_result))
+        }
+        Ok(WasmResult::Reject(error_message)) => {
+            RawCanisterResult::Ok(RawWasmResult::Reject(error_message))
+        }
+        Err(user_error) => RawCanisterResult::Err(user_error),
+    }
+}
+
 impl From<OpOut> for (StatusCode, ApiResponse<RawCanisterResult>) {
     fn from(value: OpOut) -> Self {
         match value {
-            OpOut::CanisterResult(wasm_result) => {
-                let inner = match wasm_result {
-                    Ok(WasmResult::Reply(wasm_result)) => {
-                        RawCanisterResult::Ok(RawWasmResult::Reply(wasm_result))
-                    }
-                    Ok(WasmResult::Reject(error_message)) => {
-                        RawCanisterResult::Ok(RawWasmResult::Reject(error_message))
-                    }
-                    Err(user_error) => RawCanisterResult::Err(user_error),
-                };
-                (StatusCode::OK, ApiResponse::Success(inner))
-            }
+            OpOut::CanisterResult(wasm_result) => (
+                StatusCode::OK,
+                ApiResponse::Success(raw_canister_result(wasm_result)),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<Vec<RawCanisterResult>>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::CanisterResults(results) => (
+                StatusCode::OK,
+                ApiResponse::Success(results.into_iter().map(raw_canister_result).collect()),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<Vec<Option<RawCanisterResult>>>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::Batch(results) => (
+                StatusCode::OK,
+                ApiResponse::Success(
+                    results
+                        .into_iter()
+                        .map(|result| match result {
+                            OpOut::CanisterResult(result) => Some(raw_canister_result(result)),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<Vec<RawIngressRecord>>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::IngressHistory(history) => (
+                StatusCode::OK,
+                ApiResponse::Success(history.into_iter().map(RawIngressRecord::from).collect()),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<Vec<RawCanisterLogRecord>>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::CanisterLogs(logs) => (StatusCode::OK, ApiResponse::Success(logs)),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ApiResponse::Error {
@@ -258,6 +395,28 @@ impl From<OpOut> for (StatusCode, ApiResponse<RawCanisterId>) {
                     canister_id: canister_id.get().to_vec(),
                 }),
             ),
+            OpOut::Error(PocketIcError::CanisterCreationFailed(message)) => {
+                (StatusCode::BAD_REQUEST, ApiResponse::Error { message })
+            }
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<RawSubnetId>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::SubnetId(subnet_id) => (
+                StatusCode::OK,
+                ApiResponse::Success(RawSubnetId {
+                    subnet_id: subnet_id.get().to_vec(),
+                }),
+            ),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ApiResponse::Error {
@@ -400,6 +559,28 @@ pub async fn handler_canister_exists(
     }
 }
 
+pub async fn handler_get_subnet(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    axum::extract::Json(raw_canister_id): axum::extract::Json<RawCanisterId>,
+) -> (StatusCode, Json<ApiResponse<RawSubnetId>>) {
+    let timeout = timeout_or_default(headers);
+    match CanisterId::try_from(raw_canister_id.canister_id) {
+        Ok(canister_id) => {
+            let op = GetSubnet { canister_id };
+            let (code, res) = run_operation(api_state, instance_id, timeout, op).await;
+            (code, Json(res))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
 pub async fn handler_root_key(
     State(AppState { api_state, .. }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
@@ -471,15 +652,58 @@ pub async fn handler_add_cycles(
     }
 }
 
+pub async fn handler_set_cycles(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(raw_set_cycles): extract::Json<RawSetCycles>,
+) -> (StatusCode, Json<ApiResponse<RawCycles>>) {
+    let timeout = timeout_or_default(headers);
+    match SetCycles::try_from(raw_set_cycles) {
+        Ok(set_op) => {
+            let (code, response) = run_operation(api_state, instance_id, timeout, set_op).await;
+            (code, Json(response))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
+pub async fn handler_create_canister_with_id(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(raw_create_canister): extract::Json<RawCreateCanisterWithId>,
+) -> (StatusCode, Json<ApiResponse<RawCanisterId>>) {
+    let timeout = timeout_or_default(headers);
+    match CreateCanisterWithId::try_from(raw_create_canister) {
+        Ok(create_op) => {
+            let (code, response) = run_operation(api_state, instance_id, timeout, create_op).await;
+            (code, Json(response))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
 pub async fn handler_set_stable_memory(
     State(AppState {
-        instance_map: _,
         instances_sequence_counter: _,
         api_state,
         checkpoints: _,
         last_request: _,
         runtime: _,
         blob_store,
+        instance_events: _,
+        nns_wasm_dir: _,
     }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
     headers: HeaderMap,
@@ -514,6 +738,129 @@ pub async fn handler_create_checkpoint(
     (code, Json(res))
 }
 
+pub async fn handler_snapshot(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    axum::extract::Json(raw): axum::extract::Json<rest::RawSnapshot>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    let op = Snapshot {
+        snapshot_dir: raw.snapshot_dir.into(),
+    };
+    let (code, res) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(res))
+}
+
+pub async fn handler_export_state(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    axum::extract::Json(raw): axum::extract::Json<rest::RawExportState>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    let op = ExportState {
+        export_dir: raw.export_dir.into(),
+    };
+    let (code, res) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(res))
+}
+
+pub async fn handler_ingress_history(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<ApiResponse<Vec<RawIngressRecord>>>) {
+    let timeout = timeout_or_default(headers);
+    let op = GetIngressHistory;
+    let (code, res) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(res))
+}
+
+pub async fn handler_replay(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(raw_history): extract::Json<Vec<RawIngressRecord>>,
+) -> (StatusCode, Json<ApiResponse<Vec<RawCanisterResult>>>) {
+    let timeout = timeout_or_default(headers);
+    match raw_history
+        .into_iter()
+        .map(IngressRecord::try_from)
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(history) => {
+            let (code, res) = run_operation(api_state, instance_id, timeout, Replay(history)).await;
+            (code, Json(res))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
+pub async fn handler_batch(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(raw_ops): extract::Json<Vec<RawBatchOperation>>,
+) -> (
+    StatusCode,
+    Json<ApiResponse<Vec<Option<RawCanisterResult>>>>,
+) {
+    let timeout = timeout_or_default(headers);
+    match raw_ops
+        .into_iter()
+        .map(BatchOperation::try_from)
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(ops) => {
+            let (code, res) = run_operation(api_state, instance_id, timeout, Batch(ops)).await;
+            (code, Json(res))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CanisterLogsQuery {
+    /// Only records at or after this time are returned.
+    since_nanos: Option<u64>,
+}
+
+pub async fn handler_canister_logs(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path((instance_id, canister_id)): Path<(InstanceId, String)>,
+    extract::Query(query): extract::Query<CanisterLogsQuery>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<ApiResponse<Vec<RawCanisterLogRecord>>>) {
+    let timeout = timeout_or_default(headers);
+    match CanisterId::from_str(&canister_id) {
+        Ok(canister_id) => {
+            let op = GetCanisterLogs {
+                canister_id,
+                since_nanos: query.since_nanos.unwrap_or_default(),
+            };
+            let (code, res) = run_operation(api_state, instance_id, timeout, op).await;
+            (code, Json(res))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("Invalid canister id {}: {}", canister_id, e),
+            }),
+        ),
+    }
+}
+
 pub async fn handler_tick(
     State(AppState { api_state, .. }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
@@ -532,51 +879,184 @@ pub async fn status() -> StatusCode {
     StatusCode::OK
 }
 
-/// Create a new empty IC instance or restore from checkpoint
+/// The canisters to install into a new instance along with its subnets.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Bootstrap {
+    /// A minimal NNS: registry, governance, ledger, cycles minting canister and root.
+    Nns,
+}
+
+#[derive(Deserialize)]
+pub struct CreateInstanceQuery {
+    bootstrap: Option<Bootstrap>,
+}
+
+/// Create a new empty IC instance with the given topology or restore from checkpoint
 /// The new InstanceId will be returned
 pub async fn create_instance(
     State(AppState {
-        instance_map: _,
         instances_sequence_counter: _,
         api_state,
         checkpoints,
         last_request: _,
         runtime,
         blob_store: _,
+        instance_events,
+        nns_wasm_dir,
     }): State<AppState>,
-    body: Option<extract::Json<rest::RawCheckpoint>>,
+    extract::Query(query): extract::Query<CreateInstanceQuery>,
+    body: Option<extract::Json<rest::RawCreateInstance>>,
 ) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
-    let sm = match body {
-        None => tokio::task::spawn_blocking(|| create_state_machine(None, runtime))
+    let rest::RawCreateInstance {
+        checkpoint_name,
+        topology,
+        features,
+    } = body.map(|extract::Json(body)| body).unwrap_or_default();
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(rest::CreateInstanceResponse::Error { message }),
+        )
+    };
+    let nns_wasms = match query.bootstrap {
+        None => None,
+        Some(Bootstrap::Nns) => {
+            if checkpoint_name.is_some() {
+                return bad_request(
+                    "Instances restored from a checkpoint cannot bootstrap the NNS.".to_string(),
+                );
+            }
+            if topology.as_ref().is_some_and(|topology| !topology.nns) {
+                return bad_request(
+                    "Bootstrapping the NNS requires a topology with an NNS subnet.".to_string(),
+                );
+            }
+            let Some(nns_wasm_dir) = nns_wasm_dir else {
+                return bad_request(
+                    "Bootstrapping the NNS requires a server started with --nns-wasm-dir."
+                        .to_string(),
+                );
+            };
+            match NnsWasms::from_dir(&nns_wasm_dir) {
+                Ok(wasms) => Some(wasms),
+                Err(message) => return bad_request(message),
+            }
+        }
+    };
+    // The NNS is installed on the NNS subnet, and features are set up along with the
+    // subnets, so both imply a default topology.
+    let topology = topology.or_else(|| {
+        if nns_wasms.is_some() {
+            Some(rest::Topology {
+                nns: true,
+                ..Default::default()
+            })
+        } else {
+            (features != rest::InstanceFeatures::default()).then(|| rest::Topology {
+                system: true,
+                ..Default::default()
+            })
+        }
+    });
+    let pocket_ic = match (checkpoint_name, topology) {
+        (Some(_), Some(_)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(rest::CreateInstanceResponse::Error {
+                    message:
+                        "Instances restored from a checkpoint cannot specify a topology or features."
+                            .to_string(),
+                }),
+            );
+        }
+        (None, Some(topology)) => {
+            if topology.num_subnets() == 0 {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(rest::CreateInstanceResponse::Error {
+                        message: "The topology must contain at least one subnet.".to_string(),
+                    }),
+                );
+            }
+            // The instance is dropped on the blocking thread if installing the NNS fails.
+            let pocket_ic = tokio::task::spawn_blocking(move || {
+                let pocket_ic = create_pocket_ic(&topology, &features, runtime);
+                if let Some(nns_wasms) = nns_wasms {
+                    install_nns(&pocket_ic, nns_wasms)?;
+                }
+                Ok::<_, String>(pocket_ic)
+            })
             .await
-            .expect("Failed to launch a state machine"),
-        Some(body) => {
+            .expect("Failed to launch the subnets");
+            match pocket_ic {
+                Ok(pocket_ic) => pocket_ic,
+                Err(message) => return bad_request(message),
+            }
+        }
+        (None, None) => PocketIc::new(
+            tokio::task::spawn_blocking(|| create_state_machine(None, runtime))
+                .await
+                .expect("Failed to launch a state machine"),
+        ),
+        (Some(checkpoint_name), None) => {
             let checkpoints = checkpoints.read().await;
-            if !checkpoints.contains_key(&body.checkpoint_name) {
+            if !checkpoints.contains_key(&checkpoint_name) {
                 return (
                     StatusCode::BAD_REQUEST,
                     Json(rest::CreateInstanceResponse::Error {
-                        message: format!("Checkpoint '{}' does not exist.", body.checkpoint_name),
+                        message: format!("Checkpoint '{}' does not exist.", checkpoint_name),
                     }),
                 );
             }
-            let proto_dir = checkpoints.get(&body.checkpoint_name).unwrap();
+            let proto_dir = checkpoints.get(&checkpoint_name).unwrap();
             let new_instance_dir = TempDir::new().expect("Failed to create tempdir");
             copy_dir(proto_dir.path(), new_instance_dir.path())
                 .expect("Failed to copy state directory");
             drop(checkpoints);
             // create instance
-            tokio::task::spawn_blocking(|| create_state_machine(Some(new_instance_dir), runtime))
+            PocketIc::new(
+                tokio::task::spawn_blocking(|| {
+                    create_state_machine(Some(new_instance_dir), runtime)
+                })
                 .await
-                .expect("Failed to launch a state machine")
+                .expect("Failed to launch a state machine"),
+            )
         }
     };
-    let pocket_ic = PocketIc::new(sm);
-    let instance_id = api_state.add_instance(pocket_ic).await;
-    (
-        StatusCode::CREATED,
-        Json(rest::CreateInstanceResponse::Created { instance_id }),
-    )
+    add_instance(&api_state, &instance_events, pocket_ic).await
+}
+
+/// Create a new IC instance from a snapshot written by `/instances/:id/snapshot`.
+/// The new InstanceId will be returned
+pub async fn create_instance_from_snapshot(
+    State(AppState {
+        api_state,
+        runtime,
+        instance_events,
+        ..
+    }): State<AppState>,
+    extract::Json(body): extract::Json<rest::RawSnapshot>,
+) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    let snapshot_dir = std::path::PathBuf::from(body.snapshot_dir);
+    let sm = tokio::task::spawn_blocking(move || {
+        create_state_machine_from_snapshot(&snapshot_dir, runtime).map_err(|e| {
+            format!(
+                "Failed to restore snapshot from {}: {}",
+                snapshot_dir.display(),
+                e
+            )
+        })
+    })
+    .await
+    .expect("Failed to launch a state machine");
+    match sm {
+        Ok(sm) => add_instance(&api_state, &instance_events, PocketIc::new(sm)).await,
+        Err(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(rest::CreateInstanceResponse::Error { message }),
+        ),
+    }
 }
 
 pub async fn list_instances(
@@ -597,13 +1077,91 @@ pub async fn list_instances(
 }
 
 pub async fn delete_instance(
-    State(AppState { api_state, .. }): State<AppState>,
+    State(AppState {
+        api_state,
+        instance_events,
+        ..
+    }): State<AppState>,
     Path(id): Path<InstanceId>,
 ) -> StatusCode {
     api_state.delete_instance(id).await;
+    // Event streams end once the instance and this sender are dropped.
+    instance_events.write().await.remove(&id);
     StatusCode::OK
 }
 
+async fn add_instance(
+    api_state: &ApiState,
+    instance_events: &InstanceEvents,
+    pocket_ic: PocketIc,
+) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    let events = pocket_ic.event_sender();
+    match api_state.add_instance(pocket_ic).await {
+        Ok(instance_id) => {
+            instance_events.write().await.insert(instance_id, events);
+            // Adding the instance may have evicted another one.
+            remove_deleted_instance_events(api_state, instance_events).await;
+            (
+                StatusCode::CREATED,
+                Json(rest::CreateInstanceResponse::Created { instance_id }),
+            )
+        }
+        Err(quota) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(rest::CreateInstanceResponse::QuotaExceeded(quota.into())),
+        ),
+    }
+}
+
+/// Drops the event senders of instances that have been deleted, which ends their event streams.
+pub async fn remove_deleted_instance_events(
+    api_state: &ApiState,
+    instance_events: &InstanceEvents,
+) {
+    // Locking first ensures that concurrently added instances are part of `live_instances`.
+    let mut instance_events = instance_events.write().await;
+    let live_instances = api_state.live_instances();
+    instance_events.retain(|instance_id, _| live_instances.contains(instance_id));
+}
+
+/// Streams the events of an instance as server-sent events with JSON data. A
+/// subscriber that falls behind gets a `lagged` event with the number of
+/// skipped events.
+pub async fn handler_events(
+    State(AppState {
+        instance_events, ..
+    }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<ApiResponse<()>>)>
+{
+    let Some(receiver) = instance_events
+        .read()
+        .await
+        .get(&instance_id)
+        .map(|events| events.subscribe())
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::Error {
+                message: format!("Instance {} does not exist.", instance_id),
+            }),
+        ));
+    };
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = match receiver.recv().await {
+            Ok(event) => Event::default()
+                .json_data(event)
+                .expect("Failed to serialize instance event"),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                Event::default().event("lagged").data(skipped.to_string())
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        };
+        Some((Ok(event), receiver))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
 pub trait RouterExt<S, B>
 where
     B: HttpBody + Send + 'static,
@@ -2,14 +2,21 @@
 /// Axum handlers operate on a global state of type PocketIcApiState, whose
 /// interface guarantees consistency and determinism.
 ///
+use crate::pocket_ic::IngressRecord;
 use crate::InstanceId;
 use crate::{Computation, OpId, Operation};
 use base64;
-use ic_types::CanisterId;
+use ic_types::{CanisterId, SubnetId};
 use ic_utils::thread::JoinOnDrop;
+use pocket_ic::common::rest::{Quota, RawCanisterLogRecord};
 use pocket_ic::{ErrorCode, UserError, WasmResult};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, thread::Builder as ThreadBuilder, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as SyncMutex, RwLock as SyncRwLock},
+    thread::Builder as ThreadBuilder,
+    time::{Duration, Instant},
+};
 use tokio::{
     sync::{mpsc, Mutex, RwLock},
     task::spawn_blocking,
@@ -69,9 +76,14 @@ impl<T> Clone for PocketIcApiState<T> {
     }
 }
 
+/// A single instance, locked independently of all other instances.
+type InstanceMutex<T> = Arc<Mutex<InstanceState<T>>>;
+
 struct InnerApiState<T> {
-    // impl note: If locks are acquired on both fields, acquire first on instances, then on graph.
-    instances: RwLock<Vec<Mutex<InstanceState<T>>>>,
+    // Only held briefly to look up (or add) an instance, never across an await point, so that
+    // operations on different instances never wait for each other. The lock of an instance is
+    // acquired after releasing this one.
+    instances: SyncRwLock<Vec<InstanceMutex<T>>>,
     graph: RwLock<HashMap<StateLabel, Computations>>,
     sync_wait_time: Duration,
     // dropping the PocketIC instance might be an expensive operation (the state machine is
@@ -79,11 +91,45 @@ struct InnerApiState<T> {
     // PocketIC instance to a background worker and drop it there.
     drop_sender: mpsc::UnboundedSender<T>,
     _drop_worker_handle: JoinOnDrop<()>,
+    limits: InstanceLimits,
+    // The usage of all instances that have not been deleted. Only held briefly, never across
+    // an await point.
+    usage: SyncMutex<HashMap<InstanceId, InstanceUsage>>,
+}
+
+/// Limits on the instances of a [PocketIcApiState]. Unset limits are not enforced.
+#[derive(Clone, Debug, Default)]
+pub struct InstanceLimits {
+    /// Instances that have not been used for this long are deleted by
+    /// [PocketIcApiState::delete_idle_instances].
+    pub idle_ttl: Option<Duration>,
+    /// The maximum number of instances that exist at the same time.
+    pub max_instances: Option<usize>,
+    /// Once an instance uses more memory, all operations on it are refused.
+    pub max_memory_bytes: Option<u64>,
+    /// If set, adding an instance beyond `max_instances` deletes the least recently used
+    /// instance that is not busy instead of failing.
+    pub evict_lru_instances: bool,
+}
+
+struct InstanceUsage {
+    last_used: Instant,
+    memory_bytes: u64,
+}
+
+impl InstanceUsage {
+    fn new(memory_bytes: u64) -> Self {
+        Self {
+            last_used: Instant::now(),
+            memory_bytes,
+        }
+    }
 }
 
 pub struct PocketIcApiStateBuilder<T> {
     initial_instances: Vec<T>,
     sync_wait_time: Option<Duration>,
+    limits: InstanceLimits,
 }
 
 impl<T> PocketIcApiStateBuilder<T>
@@ -103,6 +149,11 @@ where
         }
     }
 
+    /// Limits the number, idle time and memory usage of the instances.
+    pub fn with_instance_limits(self, limits: InstanceLimits) -> Self {
+        Self { limits, ..self }
+    }
+
     /// Will make the given instance available in the initial state.
     pub fn add_initial_instance(mut self, instance: T) -> Self {
         self.initial_instances.push(instance);
@@ -117,12 +168,18 @@ where
             .collect();
         let graph = RwLock::new(graph);
 
+        // The memory usage of initial instances is only known after their first operation.
+        let usage = (0..self.initial_instances.len())
+            .map(|instance_id| (instance_id, InstanceUsage::new(0)))
+            .collect();
+        let usage = SyncMutex::new(usage);
+
         let instances: Vec<_> = self
             .initial_instances
             .into_iter()
-            .map(|inst| Mutex::new(InstanceState::Available(inst)))
+            .map(|inst| Arc::new(Mutex::new(InstanceState::Available(inst))))
             .collect();
-        let instances = RwLock::new(instances);
+        let instances = SyncRwLock::new(instances);
 
         let sync_wait_time = self.sync_wait_time.unwrap_or(DEFAULT_SYNC_WAIT_DURATION);
 
@@ -142,6 +199,8 @@ where
             sync_wait_time,
             drop_sender,
             _drop_worker_handle: JoinOnDrop::new(drop_handle),
+            limits: self.limits,
+            usage,
         });
         PocketIcApiState { inner }
     }
@@ -152,6 +211,7 @@ impl<T> Default for PocketIcApiStateBuilder<T> {
         Self {
             initial_instances: vec![],
             sync_wait_time: None,
+            limits: InstanceLimits::default(),
         }
     }
 }
@@ -162,9 +222,14 @@ pub enum OpOut {
     Time(u64),
     CanisterResult(Result<WasmResult, UserError>),
     CanisterId(CanisterId),
+    SubnetId(SubnetId),
     Cycles(u128),
     Bytes(Vec<u8>),
     Bool(bool),
+    CanisterResults(Vec<Result<WasmResult, UserError>>),
+    IngressHistory(Vec<IngressRecord>),
+    CanisterLogs(Vec<RawCanisterLogRecord>),
+    Batch(Vec<OpOut>),
     // only stored in the graph, not returned to user
     Checkpoint(String),
     Error(PocketIcError),
@@ -173,6 +238,10 @@ pub enum OpOut {
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub enum PocketIcError {
     CanisterNotFound(CanisterId),
+    CheckpointFailed(String),
+    SnapshotFailed(String),
+    ExportFailed(String),
+    CanisterCreationFailed(String),
 }
 
 impl From<Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>> for OpOut {
@@ -215,12 +284,31 @@ impl std::fmt::Debug for OpOut {
             OpOut::NoOutput => write!(f, "NoOutput"),
             OpOut::Time(x) => write!(f, "Time({})", x),
             OpOut::CanisterId(cid) => write!(f, "CanisterId({})", cid),
+            OpOut::SubnetId(subnet_id) => write!(f, "SubnetId({})", subnet_id),
             OpOut::Cycles(x) => write!(f, "Cycles({})", x),
             OpOut::CanisterResult(Ok(x)) => write!(f, "CanisterResult: Ok({:?})", x),
             OpOut::CanisterResult(Err(x)) => write!(f, "CanisterResult: Err({})", x),
+            OpOut::CanisterResults(results) => write!(f, "CanisterResults({:?})", results),
+            OpOut::IngressHistory(history) => {
+                write!(f, "IngressHistory({} messages)", history.len())
+            }
+            OpOut::CanisterLogs(logs) => write!(f, "CanisterLogs({} records)", logs.len()),
+            OpOut::Batch(results) => write!(f, "Batch({:?})", results),
             OpOut::Error(PocketIcError::CanisterNotFound(cid)) => {
                 write!(f, "CanisterNotFound({})", cid)
             }
+            OpOut::Error(PocketIcError::CheckpointFailed(msg)) => {
+                write!(f, "CheckpointFailed({})", msg)
+            }
+            OpOut::Error(PocketIcError::SnapshotFailed(msg)) => {
+                write!(f, "SnapshotFailed({})", msg)
+            }
+            OpOut::Error(PocketIcError::ExportFailed(msg)) => {
+                write!(f, "ExportFailed({})", msg)
+            }
+            OpOut::Error(PocketIcError::CanisterCreationFailed(msg)) => {
+                write!(f, "CanisterCreationFailed({})", msg)
+            }
             OpOut::Bytes(bytes) => write!(f, "Bytes({})", base64::encode(bytes)),
             OpOut::Checkpoint(path) => write!(f, "Checkpoint({})", path),
             OpOut::Bool(val) => write!(f, "BooleanResult({})", val),
@@ -246,8 +334,10 @@ pub enum InstanceState<T> {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpdateError {
-    message: String,
+pub enum UpdateError {
+    InstanceNotFound,
+    InstanceDeleted,
+    QuotaExceeded(Quota),
 }
 
 pub type UpdateResult = std::result::Result<UpdateReply, UpdateError>;
@@ -291,9 +381,14 @@ pub trait HasStateLabel {
     fn get_state_label(&self) -> StateLabel;
 }
 
+/// The memory usage of an instance, checked against [InstanceLimits::max_memory_bytes].
+pub trait HasMemoryUsage {
+    fn memory_usage(&self) -> u64;
+}
+
 impl<T> PocketIcApiState<T>
 where
-    T: HasStateLabel + Send + Sync + 'static,
+    T: HasStateLabel + HasMemoryUsage + Send + Sync + 'static,
 {
     /// For polling:
     /// The client lib dispatches a long running operation and gets a Busy {state_label, op_id}.
@@ -317,27 +412,116 @@ where
         }
     }
 
-    pub async fn add_instance(&self, instance: T) -> InstanceId {
-        let mut instances = self.inner.instances.write().await;
-        instances.push(Mutex::new(InstanceState::Available(instance)));
-        instances.len() - 1
+    /// Adds an instance, unless the maximum number of instances is reached and no instance can
+    /// be evicted.
+    pub async fn add_instance(&self, instance: T) -> Result<InstanceId, Quota> {
+        let mut instances = self.inner.instances.write().unwrap();
+        if let Some(max_instances) = self.inner.limits.max_instances {
+            let num_instances = self.inner.usage.lock().unwrap().len();
+            if num_instances >= max_instances
+                && !(self.inner.limits.evict_lru_instances && self.evict_lru_instance(&instances))
+            {
+                return Err(Quota::MaxInstances { max_instances });
+            }
+        }
+        let memory_bytes = instance.memory_usage();
+        instances.push(Arc::new(Mutex::new(InstanceState::Available(instance))));
+        let instance_id = instances.len() - 1;
+        self.inner
+            .usage
+            .lock()
+            .unwrap()
+            .insert(instance_id, InstanceUsage::new(memory_bytes));
+        Ok(instance_id)
+    }
+
+    // Deletes the least recently used instance that is not busy. Returns whether an instance
+    // was deleted.
+    fn evict_lru_instance(&self, instances: &[InstanceMutex<T>]) -> bool {
+        let mut usage = self.inner.usage.lock().unwrap();
+        let mut candidates: Vec<_> = usage
+            .iter()
+            .map(|(instance_id, usage)| (usage.last_used, *instance_id))
+            .collect();
+        candidates.sort();
+        for (_, instance_id) in candidates {
+            // A locked instance is about to become busy.
+            let Ok(mut instance_state) = instances[instance_id].try_lock() else {
+                continue;
+            };
+            if let InstanceState::Available(_) = &*instance_state {
+                let InstanceState::Available(pocket_ic) =
+                    std::mem::replace(&mut *instance_state, InstanceState::Deleted)
+                else {
+                    unreachable!()
+                };
+                self.inner.drop_sender.send(pocket_ic).unwrap();
+                usage.remove(&instance_id);
+                trace!("evict_lru_instance instance_id={}", instance_id);
+                return true;
+            }
+        }
+        false
     }
 
     pub async fn delete_instance(&self, instance_id: InstanceId) {
-        let instances = self.inner.instances.read().await;
-        let mut instance_state = instances[instance_id].lock().await;
+        let Some(instance) = self.get_instance(instance_id) else {
+            return;
+        };
+        let mut instance_state = instance.lock().await;
         if let InstanceState::Available(pocket_ic) =
             std::mem::replace(&mut *instance_state, InstanceState::Deleted)
         {
             self.inner.drop_sender.send(pocket_ic).unwrap();
         }
+        self.inner.usage.lock().unwrap().remove(&instance_id);
+    }
+
+    /// Deletes the instances that are not busy and have not been used for longer than
+    /// [InstanceLimits::idle_ttl]. Returns the ids of the deleted instances.
+    pub async fn delete_idle_instances(&self) -> Vec<InstanceId> {
+        let Some(idle_ttl) = self.inner.limits.idle_ttl else {
+            return vec![];
+        };
+        let idle: Vec<InstanceId> = self
+            .inner
+            .usage
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, usage)| usage.last_used.elapsed() > idle_ttl)
+            .map(|(instance_id, _)| *instance_id)
+            .collect();
+        let mut deleted = vec![];
+        for instance_id in idle {
+            let Some(instance) = self.get_instance(instance_id) else {
+                continue;
+            };
+            let mut instance_state = instance.lock().await;
+            if let InstanceState::Available(_) = &*instance_state {
+                let InstanceState::Available(pocket_ic) =
+                    std::mem::replace(&mut *instance_state, InstanceState::Deleted)
+                else {
+                    unreachable!()
+                };
+                self.inner.drop_sender.send(pocket_ic).unwrap();
+                self.inner.usage.lock().unwrap().remove(&instance_id);
+                deleted.push(instance_id);
+            }
+        }
+        deleted
+    }
+
+    /// Returns the ids of all instances that have not been deleted.
+    pub fn live_instances(&self) -> Vec<InstanceId> {
+        self.inner.usage.lock().unwrap().keys().copied().collect()
     }
 
     pub async fn list_instances(&self) -> Vec<InstanceState<()>> {
-        let instances = self.inner.instances.read().await;
+        let instances = self.inner.instances.read().unwrap().clone();
         let mut res = vec![];
 
-        for instance_state in &*instances {
+        for instance_state in &instances {
             let guard = instance_state.lock().await;
             let inst = match &*guard {
                 InstanceState::Busy { state_label, op_id } => InstanceState::Busy {
@@ -390,18 +574,14 @@ where
             computation.instance_id
         );
         let sync_wait_time = sync_wait_time.unwrap_or(self.inner.sync_wait_time);
-        let st = self.inner.clone();
-        let instances = st.instances.read().await;
         let (bg_task, busy_outcome) = if let Some(instance_mutex) =
-            instances.get(computation.instance_id)
+            self.get_instance(computation.instance_id)
         {
             let mut instance_state = instance_mutex.lock().await;
             // If this instance is busy, return the running op and initial state
             match &*instance_state {
                 InstanceState::Deleted => {
-                    return Err(UpdateError {
-                        message: "Instance was deleted".to_string(),
-                    });
+                    return Err(UpdateError::InstanceDeleted);
                 }
                 // TODO: cache lookup possible with this state_label and our own op_id
                 InstanceState::Busy { state_label, op_id } => {
@@ -411,6 +591,7 @@ where
                     });
                 }
                 InstanceState::Available(pocket_ic) => {
+                    self.check_memory_quota(computation.instance_id)?;
                     // move pocket_ic out
 
                     let state_label = pocket_ic.get_state_label();
@@ -432,6 +613,7 @@ where
                         let op_id = op_id.clone();
                         let state_label = state_label.clone();
                         let st = self.inner.clone();
+                        let instance_mutex = instance_mutex.clone();
                         move || {
                             trace!(
                                 "bg_task::start op_id={} instance_id={}",
@@ -440,15 +622,19 @@ where
                             );
                             let result = op.compute(&mut pocket_ic);
                             let new_state_label = pocket_ic.get_state_label();
+                            if let Some(usage) = st.usage.lock().unwrap().get_mut(&instance_id) {
+                                *usage = InstanceUsage::new(pocket_ic.memory_usage());
+                            }
                             // add result to graph
-                            let instances = st.instances.blocking_read();
-                            let mut guard = st.graph.blocking_write();
-                            let cached_computations =
-                                guard.entry(state_label.clone()).or_insert(HashMap::new());
-                            cached_computations
-                                .insert(op_id.clone(), (new_state_label, result.clone()));
-
-                            let mut instance_state = instances[instance_id].blocking_lock();
+                            {
+                                let mut guard = st.graph.blocking_write();
+                                let cached_computations =
+                                    guard.entry(state_label.clone()).or_insert(HashMap::new());
+                                cached_computations
+                                    .insert(op_id.clone(), (new_state_label, result.clone()));
+                            }
+
+                            let mut instance_state = instance_mutex.blocking_lock();
                             if let InstanceState::Deleted = &*instance_state {
                                 st.drop_sender.send(pocket_ic).unwrap();
                             } else {
@@ -464,15 +650,12 @@ where
                 }
             }
         } else {
-            return Err(UpdateError {
-                message: "Instance not found".to_string(),
-            });
+            return Err(UpdateError::InstanceNotFound);
         };
-        // drop lock, otherwise we end up with a deadlock
-        std::mem::drop(instances);
 
-        // We schedule a blocking background task on the tokio runtime. Note that if all
-        // blocking workers are busy, the task is put on a queue (which is what we want).
+        // We schedule a blocking background task on the tokio runtime. Since an instance runs
+        // at most one computation at a time, the number of these tasks is bounded by the
+        // number of instances, and computations on different instances run in parallel.
         //
         // Note: One issue here is that we drop the join handle "on the floor". Threads
         // that are not awaited upon before exiting the process are known to cause spurios
@@ -506,6 +689,38 @@ where
     }
 }
 
+impl<T> PocketIcApiState<T> {
+    // Returns the lock of the given instance. The lock on the list of instances is released
+    // before returning, so that callers may await the instance lock without blocking other
+    // instances.
+    fn get_instance(&self, instance_id: InstanceId) -> Option<InstanceMutex<T>> {
+        self.inner
+            .instances
+            .read()
+            .unwrap()
+            .get(instance_id)
+            .cloned()
+    }
+
+    // Marks the instance as used, unless it exceeds its memory quota.
+    fn check_memory_quota(&self, instance_id: InstanceId) -> Result<(), UpdateError> {
+        let mut usage = self.inner.usage.lock().unwrap();
+        let Some(usage) = usage.get_mut(&instance_id) else {
+            return Ok(());
+        };
+        if let Some(max_memory_bytes) = self.inner.limits.max_memory_bytes {
+            if usage.memory_bytes > max_memory_bytes {
+                return Err(UpdateError::QuotaExceeded(Quota::MaxMemory {
+                    memory_bytes: usage.memory_bytes,
+                    max_memory_bytes,
+                }));
+            }
+        }
+        usage.last_used = Instant::now();
+        Ok(())
+    }
+}
+
 impl<T: HasStateLabel> std::fmt::Debug for InstanceState<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -521,7 +736,7 @@ impl<T: HasStateLabel> std::fmt::Debug for InstanceState<T> {
 
 impl<T: HasStateLabel> std::fmt::Debug for InnerApiState<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let instances = self.instances.blocking_read();
+        let instances = self.instances.read().unwrap().clone();
         let graph = self.graph.blocking_read();
 
         writeln!(f, "Instances:")?;
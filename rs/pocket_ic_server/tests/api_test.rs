@@ -1,5 +1,17 @@
+use candid::Principal;
+use ic_canister_client_sender::{ed25519_public_key_to_der, Ed25519KeyPair};
+use ic_ic00_types::{Method, Payload, ProvisionalCreateCanisterWithCyclesArgs};
+use ic_types::crypto::Signable;
+use ic_types::messages::{Delegation, MessageId};
+use ic_types::Time;
+use pocket_ic::common::rest::{
+    CreateInstanceResponse, InstanceEvent, RawCanisterCall, RawCreateInstance, RawSignedDelegation,
+    RawVerifyRequestSigArg, Topology,
+};
+use pocket_ic::PocketIc;
 use reqwest::{StatusCode, Url};
 
+use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 use std::process::Command;
 use std::time::{Duration, Instant};
@@ -161,6 +173,75 @@ fn test_blob_store_wrong_encoding() {
         .contains("bad encoding"));
 }
 
+#[test]
+fn test_instance_events() {
+    let url = start_server();
+    let client = reqwest::blocking::Client::new();
+    let response = client.post(url.join("instances").unwrap()).send().unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let instance_id = match response.json::<CreateInstanceResponse>().unwrap() {
+        CreateInstanceResponse::Created { instance_id } => instance_id,
+        response => panic!("Unexpected response: {:?}", response),
+    };
+
+    let response = client
+        .get(
+            url.join(&format!("instances/{}/events", instance_id))
+                .unwrap(),
+        )
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[reqwest::header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+    let mut events = BufReader::new(response).lines();
+
+    let method = Method::ProvisionalCreateCanisterWithCycles.to_string();
+    let response = client
+        .post(
+            url.join(&format!(
+                "instances/{}/update/execute_ingress_message",
+                instance_id
+            ))
+            .unwrap(),
+        )
+        .header("processing-timeout-ms", 300_000)
+        .json(&RawCanisterCall {
+            sender: Principal::anonymous().as_slice().to_vec(),
+            canister_id: Principal::management_canister().as_slice().to_vec(),
+            method: method.clone(),
+            payload: ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode(),
+        })
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let data = events
+        .find_map(|line| line.unwrap().strip_prefix("data:").map(str::to_string))
+        .expect("The event stream ended without any event");
+    match serde_json::from_str::<InstanceEvent>(data.trim()).unwrap() {
+        InstanceEvent::IngressSubmitted {
+            sender,
+            canister_id,
+            method: submitted_method,
+            ..
+        } => {
+            assert_eq!(sender, Principal::anonymous().as_slice());
+            assert_eq!(canister_id, Principal::management_canister().as_slice());
+            assert_eq!(submitted_method, method);
+        }
+        event => panic!("Unexpected event: {:?}", event),
+    }
+
+    let response = client
+        .get(url.join("instances/999999/events").unwrap())
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
 // TODO: fixme, use new API
 // #[test]
 // fn test_saving_and_loading_checkpoint() {
@@ -283,6 +364,186 @@ fn test_blob_store_wrong_encoding() {
 //     assert!(!instances.contains(&instance_id));
 // }
 
+#[test]
+fn test_auth_token() {
+    let port = std::net::TcpListener::bind((LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let token_file = std::env::temp_dir().join(format!("pocket_ic_{}.token", port));
+    std::fs::write(&token_file, "s3cr3t\n").unwrap();
+    let bin_path = std::env::var_os("POCKET_IC_BIN").expect("Missing PocketIC binary");
+    Command::new(PathBuf::from(bin_path))
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--auth-token-file")
+        .arg(&token_file)
+        .spawn()
+        .expect("Failed to start PocketIC binary");
+    let url = Url::parse(&format!("http://{}:{}/", LOCALHOST, port)).unwrap();
+    let client = reqwest::blocking::Client::new();
+
+    // The status route is not authenticated.
+    let start = Instant::now();
+    while client.get(url.join("status").unwrap()).send().is_err() {
+        if start.elapsed() > Duration::from_secs(5) {
+            panic!("Failed to start PocketIC service in time");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    std::fs::remove_file(&token_file).unwrap();
+
+    let instances = url.join("instances").unwrap();
+    let response = client.get(instances.clone()).send().unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = client
+        .get(instances.clone())
+        .bearer_auth("wrong")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = client.get(instances).bearer_auth("s3cr3t").send().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn test_nns_bootstrap() {
+    let pic = PocketIc::new_with_nns(Topology {
+        nns: true,
+        application: 1,
+        ..Default::default()
+    });
+    // Registry, governance, ledger, root and cycles minting canister.
+    for canister_id in [
+        "rwlgt-iiaaa-aaaaa-aaaaa-cai",
+        "rrkah-fqaaa-aaaaa-aaaaq-cai",
+        "ryjl3-tyaaa-aaaaa-aaaba-cai",
+        "r7inp-6aaaa-aaaaa-aaabq-cai",
+        "rkp4c-7iaaa-aaaaa-aaaca-cai",
+    ] {
+        assert!(pic.canister_exists(Principal::from_text(canister_id).unwrap()));
+    }
+}
+
+#[test]
+fn test_nns_bootstrap_requires_nns_subnet() {
+    let url = start_server();
+    let client = reqwest::blocking::Client::new();
+    let create = |body: RawCreateInstance| {
+        client
+            .post(url.join("instances?bootstrap=nns").unwrap())
+            .json(&body)
+            .send()
+            .unwrap()
+    };
+
+    let response = create(RawCreateInstance {
+        topology: Some(Topology {
+            application: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = create(RawCreateInstance {
+        checkpoint_name: Some("checkpoint".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = create(RawCreateInstance::default());
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[test]
+fn test_nns_bootstrap_with_invalid_wasm() {
+    let port = std::net::TcpListener::bind((LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let nns_wasm_dir = std::env::temp_dir().join(format!("pocket_ic_{}_nns_wasms", port));
+    std::fs::create_dir_all(&nns_wasm_dir).unwrap();
+    for file in [
+        "registry-canister.wasm",
+        "governance-canister.wasm",
+        "ledger-canister.wasm",
+        "cycles-minting-canister.wasm",
+        "root-canister.wasm",
+    ] {
+        std::fs::write(nns_wasm_dir.join(file), b"not a wasm").unwrap();
+    }
+    let bin_path = std::env::var_os("POCKET_IC_BIN").expect("Missing PocketIC binary");
+    Command::new(PathBuf::from(bin_path))
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--nns-wasm-dir")
+        .arg(&nns_wasm_dir)
+        .spawn()
+        .expect("Failed to start PocketIC binary");
+    let url = Url::parse(&format!("http://{}:{}/", LOCALHOST, port)).unwrap();
+    let client = reqwest::blocking::Client::new();
+    let start = Instant::now();
+    while client.get(url.join("status").unwrap()).send().is_err() {
+        if start.elapsed() > Duration::from_secs(5) {
+            panic!("Failed to start PocketIC service in time");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let response = client
+        .post(url.join("instances?bootstrap=nns").unwrap())
+        .json(&RawCreateInstance::default())
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // The server keeps serving requests.
+    let response = client
+        .post(url.join("instances").unwrap())
+        .json(&RawCreateInstance::default())
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    std::fs::remove_dir_all(&nns_wasm_dir).unwrap();
+}
+
+#[test]
+fn test_verify_request_signature() {
+    let url = start_server().join("verify_signature").unwrap();
+    let client = reqwest::blocking::Client::new();
+    let mut rng = rand::thread_rng();
+    let identity = Ed25519KeyPair::generate(&mut rng);
+    let session = Ed25519KeyPair::generate(&mut rng);
+    let session_pubkey = ed25519_public_key_to_der(session.public_key.to_vec());
+    let expiration = Time::from_nanos_since_unix_epoch(1_000);
+    let delegation = Delegation::new(session_pubkey.clone(), expiration);
+    let message_id = MessageId::from([7; 32]);
+    let verify = |current_time_nanos: u64, sig: Vec<u8>| {
+        let arg = RawVerifyRequestSigArg {
+            request_id: message_id.as_bytes().to_vec(),
+            sig,
+            sender_pubkey: ed25519_public_key_to_der(identity.public_key.to_vec()),
+            delegations: Some(vec![RawSignedDelegation {
+                pubkey: session_pubkey.clone(),
+                expiration_nanos: 1_000,
+                targets: None,
+                signature: identity.sign(&delegation.as_signed_bytes()).to_vec(),
+            }]),
+            root_pubkey: vec![],
+            current_time_nanos: Some(current_time_nanos),
+        };
+        client.post(url.clone()).json(&arg).send().unwrap().status()
+    };
+
+    let sig = session.sign(&message_id.as_signed_bytes()).to_vec();
+    assert_eq!(verify(999, sig.clone()), StatusCode::OK);
+    // The delegation has expired.
+    assert_eq!(verify(1_001, sig), StatusCode::NOT_ACCEPTABLE);
+    // The request id must be signed by the delegated key.
+    let sig = identity.sign(&message_id.as_signed_bytes()).to_vec();
+    assert_eq!(verify(999, sig), StatusCode::NOT_ACCEPTABLE);
+}
+
 fn start_server() -> Url {
     let parent_pid = std::os::unix::process::parent_id();
     let bin_path = std::env::var_os("POCKET_IC_BIN").expect("Missing PocketIC binary");
@@ -211,17 +211,23 @@ fn current_authz() {
 #[export_name = "canister_query get_changes_since"]
 fn get_changes_since() {
     let response_pb = match deserialize_get_changes_since_request(arg_data()) {
-        Ok(version) => {
+        Ok(req) => {
             let registry = registry();
+            let version = req.version;
 
-            let max_versions = registry
-                .count_fitting_deltas(version, MAX_REGISTRY_DELTAS_SIZE)
-                .min(MAX_VERSIONS_PER_QUERY);
+            let max_versions = max_versions_per_response(registry, version, req.max_versions);
 
             RegistryGetChangesSinceResponse {
                 error: None,
                 version: registry.latest_version(),
-                deltas: registry.get_changes_since(version, Some(max_versions)),
+                deltas: registry.get_changes_since_with_key_prefix(
+                    version,
+                    Some(max_versions),
+                    &req.key_prefix,
+                ),
+                up_to_version: version
+                    .saturating_add(max_versions as u64)
+                    .min(registry.latest_version()),
             }
         }
         Err(error) => RegistryGetChangesSinceResponse {
@@ -232,6 +238,7 @@ fn get_changes_since() {
             }),
             version: 0,
             deltas: Vec::<RegistryDelta>::default(),
+            up_to_version: 0,
         },
     };
     let bytes =
@@ -240,6 +247,24 @@ fn get_changes_since() {
     reply(&bytes);
 }
 
+/// Returns the number of versions after `version` whose deltas are included
+/// in a response to get_changes_since, respecting both the canister's limits
+/// and the limit requested by the caller (if any).
+fn max_versions_per_response(
+    registry: &Registry,
+    version: u64,
+    requested_max_versions: Option<u64>,
+) -> usize {
+    let requested_max_versions = requested_max_versions
+        .map(|max_versions| usize::try_from(max_versions).unwrap_or(usize::MAX))
+        .unwrap_or(usize::MAX);
+
+    registry
+        .count_fitting_deltas(version, MAX_REGISTRY_DELTAS_SIZE)
+        .min(MAX_VERSIONS_PER_QUERY)
+        .min(requested_max_versions)
+}
+
 #[export_name = "canister_query get_certified_changes_since"]
 fn get_certified_changes_since() {
     over(
@@ -249,9 +274,7 @@ fn get_certified_changes_since() {
             let latest_version = registry().latest_version();
             let from_version = EncodedVersion::from(req.version.saturating_add(1));
 
-            let max_versions = registry()
-                .count_fitting_deltas(req.version, MAX_REGISTRY_DELTAS_SIZE)
-                .min(MAX_VERSIONS_PER_QUERY);
+            let max_versions = max_versions_per_response(registry(), req.version, req.max_versions);
 
             let to_version = EncodedVersion::from(req.version.saturating_add(max_versions as u64));
             let delta_tree = registry()
@@ -108,6 +108,18 @@ impl Registry {
         &self,
         version: u64,
         max_versions: Option<usize>,
+    ) -> Vec<RegistryDelta> {
+        self.get_changes_since_with_key_prefix(version, max_versions, &[])
+    }
+
+    /// Same as `get_changes_since`, but only returns the deltas of the keys
+    /// that start with `key_prefix`. Only the keys in that prefix range are
+    /// visited, so narrow prefixes are cheap even on a large registry.
+    pub fn get_changes_since_with_key_prefix(
+        &self,
+        version: u64,
+        max_versions: Option<usize>,
+        key_prefix: &[u8],
     ) -> Vec<RegistryDelta> {
         let max_version = match max_versions {
             Some(max_versions) => version.saturating_add(max_versions as u64),
@@ -115,7 +127,8 @@ impl Registry {
         };
 
         self.store
-            .iter()
+            .range(key_prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(key_prefix))
             // For every key create a delta with values versioned `(version, max_version]`.
             .map(|(key, values)| RegistryDelta {
                 key: key.clone(),
@@ -658,6 +671,50 @@ mod tests {
         serialize_then_deserialize(registry);
     }
 
+    #[test]
+    fn test_get_changes_since_with_key_prefix() {
+        let mut registry = Registry::new();
+        let value = vec![1, 2, 3];
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![
+                insert("node_record_1", &value),
+                insert("node_record_2", &value),
+                insert("subnet_record_1", &value),
+            ]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![
+                update("node_record_2", &value),
+                insert("node_operator", &value)
+            ]
+        ));
+
+        let keys = |deltas: Vec<RegistryDelta>| -> Vec<Vec<u8>> {
+            deltas.into_iter().map(|delta| delta.key).collect()
+        };
+
+        assert_eq!(
+            keys(registry.get_changes_since_with_key_prefix(0, None, b"node_record_")),
+            vec![b"node_record_1".to_vec(), b"node_record_2".to_vec()]
+        );
+        assert_eq!(
+            keys(registry.get_changes_since_with_key_prefix(1, None, b"node_")),
+            vec![b"node_operator".to_vec(), b"node_record_2".to_vec()]
+        );
+        assert_eq!(
+            keys(registry.get_changes_since_with_key_prefix(0, Some(1), b"subnet_")),
+            vec![b"subnet_record_1".to_vec()]
+        );
+        assert_empty!(registry.get_changes_since_with_key_prefix(1, None, b"subnet_"));
+        assert_empty!(registry.get_changes_since_with_key_prefix(0, None, b"unknown_"));
+        assert_eq!(
+            registry.get_changes_since_with_key_prefix(0, None, &[]),
+            registry.get_changes_since(0, None)
+        );
+    }
+
     #[test]
     fn test_insert() {
         let mut registry = Registry::new();
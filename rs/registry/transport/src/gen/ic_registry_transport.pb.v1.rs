@@ -96,6 +96,16 @@ pub struct RegistryDelta {
 pub struct RegistryGetChangesSinceRequest {
     #[prost(uint64, tag = "1")]
     pub version: u64,
+    /// If non-empty, only changes to keys that start with this prefix are
+    /// returned. Ignored by get_certified_changes_since, as the certified
+    /// deltas are whole mutations.
+    #[prost(bytes = "vec", tag = "2")]
+    pub key_prefix: ::prost::alloc::vec::Vec<u8>,
+    /// If set, limits the response to changes applied in the versions
+    /// (version, version + max_versions\]. The registry canister may return
+    /// fewer versions to keep the response within its size limit.
+    #[prost(uint64, optional, tag = "3")]
+    pub max_versions: ::core::option::Option<u64>,
 }
 /// Message corresponding to the response from the registry
 /// canister to a get_latest_version() request.
@@ -114,6 +124,11 @@ pub struct RegistryGetChangesSinceResponse {
     /// requested.
     #[prost(message, repeated, tag = "3")]
     pub deltas: ::prost::alloc::vec::Vec<RegistryDelta>,
+    /// The deltas cover all changes (to keys matching the requested prefix)
+    /// applied in the versions (requested version, up_to_version\]. To fetch the
+    /// next page, request the changes since this version.
+    #[prost(uint64, tag = "4")]
+    pub up_to_version: u64,
 }
 /// Message to retrieve a version of some registry key
 /// from the registry canister.
@@ -196,9 +196,14 @@ pub fn deserialize_get_latest_version_response(response: Vec<u8>) -> Result<u64,
 
 /// Deserializes the response obtained from the registry canister for a
 /// get_changes_since() call, from protobuf.
-pub fn deserialize_get_changes_since_request(request: Vec<u8>) -> Result<u64, Error> {
+//
+// Note: This uses the PB structs directly as this function is meant to
+// be used in the registry canister only.
+pub fn deserialize_get_changes_since_request(
+    request: Vec<u8>,
+) -> Result<pb::v1::RegistryGetChangesSinceRequest, Error> {
     match pb::v1::RegistryGetChangesSinceRequest::decode(&request[..]) {
-        Ok(request) => Ok(request.version),
+        Ok(request) => Ok(request),
         Err(error) => Err(Error::MalformedMessage(error.to_string())),
     }
 }
@@ -226,7 +231,22 @@ pub fn serialize_get_changes_since_response(
 // be used in the registry canister only and thus there is no problem with
 // leaking the PB structs to the rest of the code base.
 pub fn serialize_get_changes_since_request(version: u64) -> Result<Vec<u8>, Error> {
-    let request = pb::v1::RegistryGetChangesSinceRequest { version };
+    serialize_get_changes_since_request_with_filter(version, vec![], None)
+}
+
+/// Serializes a request for a get_changes_since() request to the registry
+/// canister, restricted to the keys starting with `key_prefix` (if
+/// non-empty) and to at most `max_versions` versions (if set).
+pub fn serialize_get_changes_since_request_with_filter(
+    version: u64,
+    key_prefix: Vec<u8>,
+    max_versions: Option<u64>,
+) -> Result<Vec<u8>, Error> {
+    let request = pb::v1::RegistryGetChangesSinceRequest {
+        version,
+        key_prefix,
+        max_versions,
+    };
     let mut buf = Vec::new();
     match request.encode(&mut buf) {
         Ok(_) => Ok(buf),
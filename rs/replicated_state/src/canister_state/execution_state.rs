@@ -137,8 +137,8 @@ impl TryFrom<pb::Global> for Global {
 pub struct ExportedFunctions {
     /// Since the value is only shared when taking a snapshot, there is no
     /// problem with serializing this field.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     exported_functions: Arc<BTreeSet<WasmMethod>>,
 
     /// Cached info about exporting a heartbeat method to skip expensive BTreeSet lookup.
@@ -650,8 +650,8 @@ impl TryFrom<pb::WasmCustomSection> for CustomSection {
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct WasmMetadata {
     /// Arc is used to make cheap clones of this during snapshots.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     custom_sections: Arc<BTreeMap<String, CustomSection>>,
     /// Memory usage that the Wasm custom sections contribute.
     ///
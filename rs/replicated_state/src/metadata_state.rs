@@ -167,11 +167,11 @@ pub struct SystemMetadata {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NetworkTopology {
     pub subnets: BTreeMap<SubnetId, SubnetTopology>,
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub routing_table: Arc<RoutingTable>,
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub canister_migrations: Arc<CanisterMigrations>,
     pub nns_subnet_id: SubnetId,
     /// Mapping from ECDSA key_id to a list of subnets which can sign with the
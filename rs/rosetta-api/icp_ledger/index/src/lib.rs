@@ -1,6 +1,7 @@
-use candid::{CandidType, Deserialize, Principal};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_ledger_core::block::EncodedBlock;
 use icp_ledger::{AccountIdentifier, BlockIndex, Transaction};
+use icrc_ledger_types::icrc1::account::Account;
 pub mod logs;
 
 #[derive(CandidType, Debug, Deserialize)]
@@ -16,6 +17,18 @@ pub struct GetBlocksResponse {
     // The blocks in the requested range.
     pub blocks: Vec<EncodedBlock>,
 }
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct GetAccountTransactionsArgs {
+    pub account: Account,
+    // The txid of the last transaction seen by the client.
+    // If None then the results will start from the most recent
+    // txid. If set then the results will start from the next
+    // most recent txid after start (start won't be included).
+    pub start: Option<Nat>,
+    // Maximum number of transactions to fetch.
+    pub max_results: Nat,
+}
+
 #[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
 pub struct GetAccountIdentifierTransactionsArgs {
     pub account_identifier: AccountIdentifier,
@@ -6,10 +6,9 @@ use ic_cdk_timers::TimerId;
 use ic_icp_index::logs::{P0, P1};
 use ic_icp_index::{
     GetAccountIdentifierTransactionsArgs, GetAccountIdentifierTransactionsResponse,
-    GetAccountIdentifierTransactionsResult, GetAccountTransactionsResult, InitArg, Log, LogEntry,
-    Priority, Status, TransactionWithId,
+    GetAccountIdentifierTransactionsResult, GetAccountTransactionsArgs,
+    GetAccountTransactionsResult, InitArg, Log, LogEntry, Priority, Status, TransactionWithId,
 };
-use ic_icrc1_index_ng::GetAccountTransactionsArgs;
 use ic_ledger_core::block::{BlockType, EncodedBlock};
 use ic_stable_structures::memory_manager::{MemoryId, VirtualMemory};
 use ic_stable_structures::{
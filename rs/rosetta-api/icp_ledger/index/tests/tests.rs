@@ -2,9 +2,8 @@ use candid::{Decode, Encode, Nat};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_icp_index::{
     GetAccountIdentifierTransactionsArgs, GetAccountIdentifierTransactionsResponse,
-    GetAccountIdentifierTransactionsResult, Status, TransactionWithId,
+    GetAccountIdentifierTransactionsResult, GetAccountTransactionsArgs, Status, TransactionWithId,
 };
-use ic_icrc1_index_ng::GetAccountTransactionsArgs;
 use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_core::block::BlockType;
 use ic_ledger_core::timestamp::TimeStamp;
@@ -101,6 +100,8 @@ fn default_archive_options() -> ArchiveOptions {
         controller_id: PrincipalId::new_user_test_id(100),
         cycles_for_archive_creation: None,
         max_transactions_per_response: None,
+        more_controller_ids: None,
+        archive_created_callback: None,
     }
 }
 
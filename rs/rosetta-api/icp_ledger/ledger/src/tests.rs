@@ -419,6 +419,8 @@ fn duplicate_txns() {
         controller_id: CanisterId::from_u64(876).into(),
         cycles_for_archive_creation: Some(0),
         max_transactions_per_response: None,
+        more_controller_ids: None,
+        archive_created_callback: None,
     }))));
 
     let user1 = PrincipalId::new_user_test_id(1).into();
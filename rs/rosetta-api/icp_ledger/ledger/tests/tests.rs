@@ -330,6 +330,8 @@ fn check_query_blocks_coherence() {
             controller_id: PrincipalId::new_anonymous(),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         })
         .minting_account(MINTER.into())
         .icrc1_minting_account(MINTER)
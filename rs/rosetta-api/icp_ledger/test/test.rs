@@ -386,6 +386,8 @@ fn archive_blocks_small_test() {
             controller_id: CanisterId::from_u64(876).into(),
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         println!("[test] installing ledger canister");
@@ -519,6 +521,8 @@ fn archive_blocks_large_test() {
             controller_id: CanisterId::from_u64(876).into(),
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         println!("[test] installing ledger canister");
@@ -693,6 +697,8 @@ fn archived_blocks_ranges() {
             controller_id: CanisterId::from_u64(876).into(),
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         let minting_account = create_sender(0);
@@ -870,6 +876,8 @@ fn notify_test() {
             num_blocks_to_archive: 3,
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         let ledger_canister = proj
@@ -1067,6 +1075,8 @@ fn notify_disabled_test() {
             num_blocks_to_archive: 3,
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         let ledger_canister = proj
@@ -1539,6 +1549,8 @@ fn get_block_test() {
             controller_id: CanisterId::from_u64(876).into(),
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         println!(
@@ -1699,6 +1711,8 @@ fn get_multiple_blocks_test() {
             controller_id: CanisterId::from_u64(876).into(),
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         println!(
@@ -1904,6 +1918,8 @@ fn only_ledger_can_append_blocks_to_archive_nodes() {
             controller_id: CanisterId::from_u64(876).into(),
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
 
         println!(
@@ -2035,6 +2051,8 @@ fn test_archives_endpoint() {
                             controller_id: minting_canister_id.into(),
                             cycles_for_archive_creation: None,
                             max_transactions_per_response: None,
+                            more_controller_ids: None,
+                            archive_created_callback: None,
                         })
                         .build()
                         .unwrap(),
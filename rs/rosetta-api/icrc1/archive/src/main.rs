@@ -1,15 +1,20 @@
-use candid::{candid_method, Principal};
+use candid::{candid_method, Nat, Principal};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk_macros::{init, post_upgrade, query, update};
-use ic_icrc1::{blocks::encoded_block_to_generic_block, Block};
+use ic_icrc1::{
+    blocks::{encoded_block_to_generic_block, encoded_block_to_icrc3_block},
+    Block,
+};
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock};
 use ic_stable_structures::memory_manager::{MemoryId, VirtualMemory};
 use ic_stable_structures::{
     cell::Cell as StableCell, log::Log as StableLog, memory_manager::MemoryManager,
     DefaultMemoryImpl, RestrictedMemory, Storable,
 };
-use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
+use icrc_ledger_types::icrc3::blocks::{
+    BlockRange, BlockWithId, GetBlocksRequest, GetBlocksResult,
+};
 
 use icrc_ledger_types::icrc3::transactions::Transaction;
 use icrc_ledger_types::icrc3::transactions::{GetTransactionsRequest, TransactionRange};
@@ -271,6 +276,55 @@ fn get_blocks(req: GetTransactionsRequest) -> BlockRange {
     BlockRange { blocks }
 }
 
+/// Get the blocks in the specified ranges as defined by ICRC-3.
+///
+/// Blocks this archive does not store are skipped. The archive does not know
+/// the length of the ledger's block log, it reports the index following its
+/// last block as `log_length` instead.
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let (idx_offset, max_blocks) = with_archive_opts(|opts| {
+        (
+            opts.block_index_offset,
+            opts.max_transactions_per_response as usize,
+        )
+    });
+
+    let mut result = vec![];
+    let log_end = with_blocks(|blocks| {
+        let log_end = idx_offset + blocks.len();
+        for arg in args {
+            // Blocks with an index that does not fit into u64 do not exist.
+            let start = match u64::try_from(&arg.start.0) {
+                Ok(start) => start,
+                Err(_) => continue,
+            };
+            let length = u64::try_from(&arg.length.0).unwrap_or(u64::MAX);
+            let remaining = max_blocks.saturating_sub(result.len()) as u64;
+            let from = start.max(idx_offset);
+            let to = start
+                .saturating_add(length)
+                .min(log_end)
+                .min(from.saturating_add(remaining));
+            for id in from..to {
+                let block = EncodedBlock::from(blocks.get(id - idx_offset).unwrap());
+                result.push(BlockWithId {
+                    id: Nat::from(id),
+                    block: encoded_block_to_icrc3_block(&block),
+                });
+            }
+        }
+        log_end
+    });
+
+    GetBlocksResult {
+        log_length: Nat::from(log_end),
+        blocks: result,
+        archived_blocks: vec![],
+    }
+}
+
 #[query]
 fn __get_candid_interface_tmp_hack() -> &'static str {
     include_str!(env!("ARCHIVE_DID_PATH"))
@@ -0,0 +1,336 @@
+//! An ICRC-1/ICRC-2 client which retries calls that fail because the
+//! ledger is temporarily unavailable, for instance while it is being
+//! upgraded.
+//!
+//! Retrying a call which modifies the ledger is only safe if the ledger
+//! can recognize the retry as a duplicate. Unless the caller already did
+//! so, the client therefore sets `created_at_time` before the first
+//! attempt, and keeps the same arguments for all attempts. The ledger then
+//! deduplicates the transaction based on its arguments, including
+//! `created_at_time` and `memo`. If a retry is rejected as a duplicate,
+//! the index of the block containing the original transaction is
+//! returned.
+
+#[cfg(test)]
+mod tests;
+
+use async_trait::async_trait;
+use candid::{Nat, Principal};
+use ic_ledger_core::block::BlockIndex;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+use num_traits::ToPrimitive;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+pub use ic_icrc1_client::{ICRC1Client, Runtime};
+pub use ic_icrc1_client_cdk::CdkRuntime;
+
+/// Reject code of calls which failed due to a transient system error,
+/// e.g., because the output queue was full.
+pub const SYS_TRANSIENT: i32 = 2;
+/// Reject code of calls to a canister which trapped or is not running,
+/// e.g., because it is stopped for an upgrade.
+pub const CANISTER_ERROR: i32 = 5;
+
+/// Returns true if a call rejected with the given code may succeed when
+/// retried.
+pub fn is_transient_reject_code(code: i32) -> bool {
+    code == SYS_TRANSIENT || code == CANISTER_ERROR
+}
+
+/// How often, and how quickly, failed calls are retried.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetryConfig {
+    /// The maximum number of times a call is attempted, including the
+    /// first attempt. Must be at least 1.
+    pub max_attempts: u32,
+    /// The delay before the first retry. The delay doubles with each
+    /// subsequent retry.
+    pub initial_backoff: Duration,
+    /// The upper bound on the delay between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl RetryConfig {
+    /// A configuration which makes a single attempt. The arguments of
+    /// calls are then passed to the ledger unchanged.
+    pub const fn no_retries() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    fn retries_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// The delay after the given (1-based) failed attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1_u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+/// The runtime functionality needed on top of [`Runtime`] to retry calls.
+#[async_trait]
+pub trait RetryRuntime: Runtime + Sync {
+    /// Returns the current time in nanoseconds since the UNIX epoch.
+    fn time(&self) -> u64;
+
+    /// Waits for (at least) the given duration before the next attempt.
+    async fn sleep(&self, duration: Duration);
+}
+
+#[async_trait]
+impl RetryRuntime for CdkRuntime {
+    fn time(&self) -> u64 {
+        ic_cdk::api::time()
+    }
+
+    /// Canisters cannot block, and resuming from a timer would lose the
+    /// call context of the caller, so retries are not delayed. Each attempt
+    /// is a separate inter-canister call, hence attempts are still at
+    /// least one round apart.
+    async fn sleep(&self, _duration: Duration) {}
+}
+
+/// The ledger could not be called successfully.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CallError {
+    /// The reject code of the last attempt
+    pub code: i32,
+    /// The reject message of the last attempt
+    pub message: String,
+    /// The number of attempts made
+    pub attempts: u32,
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to call the ledger after {} attempt(s), error code: {}, message: {}",
+            self.attempts, self.code, self.message
+        )
+    }
+}
+
+/// An error returned by a call which modifies the ledger.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LedgerClientError<E> {
+    /// The ledger could not be called.
+    Call(CallError),
+    /// The ledger rejected the request.
+    Ledger(E),
+}
+
+impl<E: fmt::Display> fmt::Display for LedgerClientError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Call(error) => write!(f, "{}", error),
+            Self::Ledger(error) => write!(f, "the ledger rejected the request: {}", error),
+        }
+    }
+}
+
+/// Common properties of the errors returned by ledger endpoints which
+/// create a transaction.
+pub trait LedgerError {
+    /// Returns true if the ledger asked for the request to be retried.
+    fn is_temporarily_unavailable(&self) -> bool;
+
+    /// Returns the index of the block containing the transaction if the
+    /// request was rejected as a duplicate.
+    fn duplicate_of(&self) -> Option<BlockIndex>;
+}
+
+fn nat_to_block_index(n: &Nat) -> Option<BlockIndex> {
+    n.0.to_u64()
+}
+
+macro_rules! impl_ledger_error {
+    ($error:ty) => {
+        impl LedgerError for $error {
+            fn is_temporarily_unavailable(&self) -> bool {
+                matches!(self, Self::TemporarilyUnavailable)
+            }
+
+            fn duplicate_of(&self) -> Option<BlockIndex> {
+                match self {
+                    Self::Duplicate { duplicate_of } => nat_to_block_index(duplicate_of),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_ledger_error!(TransferError);
+impl_ledger_error!(TransferFromError);
+impl_ledger_error!(ApproveError);
+
+/// An [`ICRC1Client`] which retries failed calls as configured.
+pub struct RetryingICRC1Client<R: RetryRuntime> {
+    client: ICRC1Client<R>,
+    config: RetryConfig,
+}
+
+impl<R: RetryRuntime> RetryingICRC1Client<R> {
+    pub fn new(runtime: R, ledger_canister_id: Principal, config: RetryConfig) -> Self {
+        assert!(
+            config.max_attempts > 0,
+            "BUG: at least one attempt is required"
+        );
+        Self {
+            client: ICRC1Client {
+                runtime,
+                ledger_canister_id,
+            },
+            config,
+        }
+    }
+
+    pub fn ledger_canister_id(&self) -> Principal {
+        self.client.ledger_canister_id
+    }
+
+    pub fn config(&self) -> &RetryConfig {
+        &self.config
+    }
+
+    pub async fn balance_of(&self, account: Account) -> Result<u64, CallError> {
+        self.query_with_retries(|| self.client.balance_of(account))
+            .await
+    }
+
+    pub async fn fee(&self) -> Result<u64, CallError> {
+        self.query_with_retries(|| self.client.fee()).await
+    }
+
+    pub async fn total_supply(&self) -> Result<u64, CallError> {
+        self.query_with_retries(|| self.client.total_supply()).await
+    }
+
+    pub async fn transfer(
+        &self,
+        mut args: TransferArg,
+    ) -> Result<BlockIndex, LedgerClientError<TransferError>> {
+        self.set_created_at_time(&mut args.created_at_time);
+        self.update_with_retries(|| self.client.transfer(args.clone()))
+            .await
+    }
+
+    pub async fn transfer_from(
+        &self,
+        mut args: TransferFromArgs,
+    ) -> Result<BlockIndex, LedgerClientError<TransferFromError>> {
+        self.set_created_at_time(&mut args.created_at_time);
+        self.update_with_retries(|| self.client.transfer_from(args.clone()))
+            .await
+    }
+
+    pub async fn approve(
+        &self,
+        mut args: ApproveArgs,
+    ) -> Result<BlockIndex, LedgerClientError<ApproveError>> {
+        self.set_created_at_time(&mut args.created_at_time);
+        self.update_with_retries(|| self.client.approve(args.clone()))
+            .await
+    }
+
+    fn set_created_at_time(&self, created_at_time: &mut Option<u64>) {
+        if self.config.retries_enabled() {
+            created_at_time.get_or_insert_with(|| self.client.runtime.time());
+        }
+    }
+
+    async fn query_with_retries<T, F, Fut>(&self, call: F) -> Result<T, CallError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, (i32, String)>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Ok(result) => return Ok(result),
+                Err((code, message)) => {
+                    if !is_transient_reject_code(code) || attempt >= self.config.max_attempts {
+                        return Err(CallError {
+                            code,
+                            message,
+                            attempts: attempt,
+                        });
+                    }
+                }
+            }
+            self.client
+                .runtime
+                .sleep(self.config.backoff(attempt))
+                .await;
+            attempt += 1;
+        }
+    }
+
+    async fn update_with_retries<E, F, Fut>(
+        &self,
+        call: F,
+    ) -> Result<BlockIndex, LedgerClientError<E>>
+    where
+        E: LedgerError,
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<Result<BlockIndex, E>, (i32, String)>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Ok(Ok(block_index)) => return Ok(block_index),
+                Ok(Err(error)) => {
+                    // A previous attempt may have succeeded even though its
+                    // response was lost.
+                    if attempt > 1 {
+                        if let Some(block_index) = error.duplicate_of() {
+                            return Ok(block_index);
+                        }
+                    }
+                    if !error.is_temporarily_unavailable() || attempt >= self.config.max_attempts {
+                        return Err(LedgerClientError::Ledger(error));
+                    }
+                }
+                Err((code, message)) => {
+                    if !is_transient_reject_code(code) || attempt >= self.config.max_attempts {
+                        return Err(LedgerClientError::Call(CallError {
+                            code,
+                            message,
+                            attempts: attempt,
+                        }));
+                    }
+                }
+            }
+            self.client
+                .runtime
+                .sleep(self.config.backoff(attempt))
+                .await;
+            attempt += 1;
+        }
+    }
+}
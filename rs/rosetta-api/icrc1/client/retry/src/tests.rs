@@ -0,0 +1,283 @@
+use super::*;
+use candid::utils::{ArgumentDecoder, ArgumentEncoder};
+use candid::{CandidType, Decode};
+use futures::executor::block_on;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+const NOW: u64 = 1_700_000_000_000_000_000;
+
+type Reply = Result<Vec<u8>, (i32, String)>;
+
+#[derive(Default)]
+struct MockRuntime {
+    replies: Mutex<VecDeque<Reply>>,
+    requests: Mutex<Vec<(String, Vec<u8>)>>,
+    sleeps: Mutex<Vec<Duration>>,
+}
+
+impl MockRuntime {
+    fn with_replies(replies: Vec<Reply>) -> Self {
+        Self {
+            replies: Mutex::new(replies.into()),
+            ..Default::default()
+        }
+    }
+
+    fn transfer_args(&self) -> Vec<TransferArg> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(method, args)| {
+                assert_eq!(method, "icrc1_transfer");
+                Decode!(args, TransferArg).unwrap()
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Runtime for MockRuntime {
+    async fn call<In, Out>(
+        &self,
+        _id: Principal,
+        method: &str,
+        args: In,
+    ) -> Result<Out, (i32, String)>
+    where
+        In: ArgumentEncoder + Send,
+        Out: for<'a> ArgumentDecoder<'a>,
+    {
+        let args = candid::utils::encode_args(args).unwrap();
+        self.requests
+            .lock()
+            .unwrap()
+            .push((method.to_string(), args));
+        let reply = self
+            .replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("unexpected call");
+        reply.map(|bytes| candid::utils::decode_args(&bytes).unwrap())
+    }
+}
+
+#[async_trait]
+impl RetryRuntime for MockRuntime {
+    fn time(&self) -> u64 {
+        NOW
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.sleeps.lock().unwrap().push(duration);
+    }
+}
+
+fn reply<T: CandidType>(value: T) -> Reply {
+    Ok(candid::encode_one(value).unwrap())
+}
+
+fn transfer_ok(block_index: u64) -> Reply {
+    reply(Ok::<Nat, TransferError>(Nat::from(block_index)))
+}
+
+fn transfer_err(error: TransferError) -> Reply {
+    reply(Err::<Nat, TransferError>(error))
+}
+
+fn reject(code: i32) -> Reply {
+    Err((code, format!("rejected with code {}", code)))
+}
+
+fn client(runtime: MockRuntime, config: RetryConfig) -> RetryingICRC1Client<MockRuntime> {
+    RetryingICRC1Client::new(runtime, Principal::management_canister(), config)
+}
+
+fn transfer_arg() -> TransferArg {
+    TransferArg {
+        from_subaccount: None,
+        to: Account {
+            owner: Principal::anonymous(),
+            subaccount: None,
+        },
+        fee: None,
+        created_at_time: None,
+        memo: None,
+        amount: Nat::from(1_000_u64),
+    }
+}
+
+#[test]
+fn should_retry_transient_rejects_with_identical_arguments() {
+    let client = client(
+        MockRuntime::with_replies(vec![
+            reject(SYS_TRANSIENT),
+            reject(CANISTER_ERROR),
+            transfer_ok(42),
+        ]),
+        RetryConfig::default(),
+    );
+
+    assert_eq!(block_on(client.transfer(transfer_arg())), Ok(42));
+
+    let requests = client.client.runtime.transfer_args();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].created_at_time, Some(NOW));
+    assert!(requests.iter().all(|args| args == &requests[0]));
+    assert_eq!(
+        *client.client.runtime.sleeps.lock().unwrap(),
+        vec![Duration::from_secs(1), Duration::from_secs(2)]
+    );
+}
+
+#[test]
+fn should_keep_created_at_time_set_by_the_caller() {
+    let client = client(
+        MockRuntime::with_replies(vec![transfer_ok(1)]),
+        RetryConfig::default(),
+    );
+
+    let args = TransferArg {
+        created_at_time: Some(NOW - 1),
+        ..transfer_arg()
+    };
+    assert_eq!(block_on(client.transfer(args.clone())), Ok(1));
+    assert_eq!(client.client.runtime.transfer_args(), vec![args]);
+}
+
+#[test]
+fn should_return_original_block_when_retry_is_a_duplicate() {
+    let client = client(
+        MockRuntime::with_replies(vec![
+            reject(SYS_TRANSIENT),
+            transfer_err(TransferError::Duplicate {
+                duplicate_of: Nat::from(7_u64),
+            }),
+        ]),
+        RetryConfig::default(),
+    );
+
+    assert_eq!(block_on(client.transfer(transfer_arg())), Ok(7));
+}
+
+#[test]
+fn should_not_treat_first_attempt_duplicate_as_success() {
+    let duplicate = TransferError::Duplicate {
+        duplicate_of: Nat::from(7_u64),
+    };
+    let client = client(
+        MockRuntime::with_replies(vec![transfer_err(duplicate.clone())]),
+        RetryConfig::default(),
+    );
+
+    assert_eq!(
+        block_on(client.transfer(transfer_arg())),
+        Err(LedgerClientError::Ledger(duplicate))
+    );
+}
+
+#[test]
+fn should_retry_when_ledger_is_temporarily_unavailable() {
+    let client = client(
+        MockRuntime::with_replies(vec![
+            transfer_err(TransferError::TemporarilyUnavailable),
+            transfer_ok(3),
+        ]),
+        RetryConfig::default(),
+    );
+
+    assert_eq!(block_on(client.transfer(transfer_arg())), Ok(3));
+}
+
+#[test]
+fn should_not_retry_permanent_errors() {
+    let insufficient_funds = TransferError::InsufficientFunds {
+        balance: Nat::from(0_u64),
+    };
+    let client = client(
+        MockRuntime::with_replies(vec![transfer_err(insufficient_funds.clone())]),
+        RetryConfig::default(),
+    );
+    assert_eq!(
+        block_on(client.transfer(transfer_arg())),
+        Err(LedgerClientError::Ledger(insufficient_funds))
+    );
+
+    let client = self::client(
+        MockRuntime::with_replies(vec![reject(3)]),
+        RetryConfig::default(),
+    );
+    assert_eq!(
+        block_on(client.transfer(transfer_arg())),
+        Err(LedgerClientError::Call(CallError {
+            code: 3,
+            message: "rejected with code 3".to_string(),
+            attempts: 1,
+        }))
+    );
+}
+
+#[test]
+fn should_give_up_after_max_attempts() {
+    let config = RetryConfig {
+        max_attempts: 4,
+        initial_backoff: Duration::from_secs(3),
+        max_backoff: Duration::from_secs(10),
+    };
+    let client = client(
+        MockRuntime::with_replies(vec![reject(SYS_TRANSIENT); 4]),
+        config,
+    );
+
+    assert_eq!(
+        block_on(client.transfer(transfer_arg())),
+        Err(LedgerClientError::Call(CallError {
+            code: SYS_TRANSIENT,
+            message: "rejected with code 2".to_string(),
+            attempts: 4,
+        }))
+    );
+    assert_eq!(
+        *client.client.runtime.sleeps.lock().unwrap(),
+        vec![
+            Duration::from_secs(3),
+            Duration::from_secs(6),
+            Duration::from_secs(10)
+        ]
+    );
+}
+
+#[test]
+fn should_pass_arguments_unchanged_without_retries() {
+    let client = client(
+        MockRuntime::with_replies(vec![reject(SYS_TRANSIENT)]),
+        RetryConfig::no_retries(),
+    );
+
+    assert_matches_call_error(block_on(client.transfer(transfer_arg())), 1);
+    assert_eq!(client.client.runtime.transfer_args(), vec![transfer_arg()]);
+}
+
+#[test]
+fn should_retry_queries() {
+    let client = client(
+        MockRuntime::with_replies(vec![reject(CANISTER_ERROR), reply(Nat::from(10_u64))]),
+        RetryConfig::default(),
+    );
+
+    assert_eq!(block_on(client.fee()), Ok(10));
+}
+
+fn assert_matches_call_error<E: fmt::Debug>(
+    result: Result<BlockIndex, LedgerClientError<E>>,
+    expected_attempts: u32,
+) {
+    match result {
+        Err(LedgerClientError::Call(CallError { attempts, .. })) => {
+            assert_eq!(attempts, expected_attempts)
+        }
+        other => panic!("expected a call error, got {:?}", other),
+    }
+}
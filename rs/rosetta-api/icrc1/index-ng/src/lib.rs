@@ -42,6 +42,25 @@ pub struct GetAccountTransactionsArgs {
     pub start: Option<BlockIndex>,
     // Maximum number of transactions to fetch.
     pub max_results: Nat,
+    // If set then only the transactions with a memo starting
+    // with this prefix are returned. Transactions without memo
+    // never match.
+    pub memo_prefix: Option<Vec<u8>>,
+    // If set then only the transactions of blocks whose timestamp
+    // is within the range are returned.
+    pub time_range: Option<TimeRange>,
+    // The next_page_token returned by a previous call with the same
+    // arguments. If set then the results will start after the last
+    // entry inspected by that call.
+    pub page_token: Option<Vec<u8>>,
+}
+
+/// A range of block timestamps in nanoseconds since the Unix epoch.
+/// The start is inclusive, the end is exclusive.
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: u64,
+    pub end: u64,
 }
 
 #[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -56,6 +75,9 @@ pub struct GetAccountTransactionsResponse {
     pub transactions: Vec<TransactionWithId>,
     // The txid of the oldest transaction the account has
     pub oldest_tx_id: Option<BlockIndex>,
+    // Set if there may be more transactions matching the filters.
+    // Pass it as page_token to fetch the next page.
+    pub next_page_token: Option<Vec<u8>>,
 }
 
 #[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
@@ -66,6 +88,42 @@ pub struct GetAccountTransactionsError {
 pub type GetAccountTransactionsResult =
     Result<GetAccountTransactionsResponse, GetAccountTransactionsError>;
 
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct GetBlockAccountsArgs {
+    pub block_index: BlockIndex,
+}
+
+/// The role of an account in a block.
+#[derive(CandidType, Clone, Copy, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccountRole {
+    From,
+    To,
+    Spender,
+    // The account that received the fee of a transfer.
+    FeeCollector,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct BlockAccount {
+    pub role: AccountRole,
+    pub account: Account,
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct GetBlockAccountsResponse {
+    pub block_index: BlockIndex,
+    // The accounts involved in the block, ordered by role.
+    pub accounts: Vec<BlockAccount>,
+    pub transaction: Transaction,
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct GetBlockAccountsError {
+    pub message: String,
+}
+
+pub type GetBlockAccountsResult = Result<GetBlockAccountsResponse, GetBlockAccountsError>;
+
 #[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
 pub struct ListSubaccountsArgs {
     pub owner: Principal,
@@ -9,9 +9,11 @@ use ic_crypto_sha2::Sha256;
 use ic_icrc1::blocks::{encoded_block_to_generic_block, generic_block_to_encoded_block};
 use ic_icrc1::{Block, Operation};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, IndexArg, ListSubaccountsArgs, Log, LogEntry, Status,
-    TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    AccountRole, BlockAccount, FeeCollectorRanges, GetAccountTransactionsArgs,
+    GetAccountTransactionsError, GetAccountTransactionsResponse, GetAccountTransactionsResult,
+    GetBlockAccountsArgs, GetBlockAccountsError, GetBlockAccountsResponse, GetBlockAccountsResult,
+    IndexArg, ListSubaccountsArgs, Log, LogEntry, Status, TimeRange, TransactionWithId,
+    DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_ledger_core::block::{BlockIndex as BlockIndex64, BlockType, EncodedBlock};
 use ic_ledger_core::tokens::{CheckedAdd, CheckedSub, Zero};
@@ -26,7 +28,6 @@ use icrc_ledger_types::icrc3::archive::{ArchivedRange, QueryBlockArchiveFn};
 use icrc_ledger_types::icrc3::blocks::{
     BlockRange, GenericBlock, GetBlocksRequest, GetBlocksResponse,
 };
-use icrc_ledger_types::icrc3::transactions::Transaction;
 use num_traits::ToPrimitive;
 use scopeguard::{guard, ScopeGuard};
 use serde::{Deserialize, Serialize};
@@ -38,7 +39,7 @@ use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::ops::Bound::{Excluded, Included};
-use std::ops::Range;
+use std::ops::{Range, RangeInclusive};
 use std::time::Duration;
 
 pub mod logs;
@@ -50,6 +51,16 @@ const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const ACCOUNT_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
 const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const ACCOUNT_MEMO_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(5);
+const BLOCK_TIMESTAMPS_MEMORY_ID: MemoryId = MemoryId::new(6);
+const BLOCK_ACCOUNTS_MEMORY_ID: MemoryId = MemoryId::new(7);
+
+/// The number of leading memo bytes stored in the memo index.
+const MEMO_KEY_LEN: usize = 32;
+
+/// The maximum number of index entries inspected by a single
+/// [get_account_transactions] call when filters are set.
+const MAX_ENTRIES_SCANNED_PER_QUERY: usize = 10_000;
 
 const DEFAULT_MAX_WAIT_TIME: Duration = Duration::from_secs(2);
 const DEFAULT_RETRY_WAIT_TIME: Duration = Duration::from_secs(1);
@@ -73,6 +84,22 @@ type AccountBlockIdsMap = StableBTreeMap<AccountBlockIdsMapKey, (), VM>;
 type AccountDataMapKey = (AccountDataType, (Blob<29>, [u8; 32]));
 type AccountDataMap = StableBTreeMap<AccountDataMapKey, Tokens, VM>;
 
+// The first MEMO_KEY_LEN bytes of a memo, padded with zeros.
+type MemoKey = [u8; MEMO_KEY_LEN];
+// The block indexes are grouped by memo and then stored in reverse order
+// like in [AccountBlockIdsMap].
+type AccountMemoBlockIdsMapKey = ([u8; Sha256::DIGEST_LEN], (MemoKey, Reverse<u64>));
+type AccountMemoBlockIdsMap = StableBTreeMap<AccountMemoBlockIdsMapKey, (), VM>;
+
+// The key is the timestamp of the block and the block index.
+type BlockTimestampsMap = StableBTreeMap<(u64, u64), (), VM>;
+
+// The key is the block index and the role of the account in the block
+// (see [account_role_to_byte]). The account is represented like in
+// [AccountDataMapKey].
+type BlockAccountsMapKey = (u64, u8);
+type BlockAccountsMap = StableBTreeMap<BlockAccountsMapKey, (Blob<29>, [u8; 32]), VM>;
+
 thread_local! {
     /// Static memory manager to manage the memory available for stable structures.
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -100,6 +127,22 @@ thread_local! {
         RefCell::new(AccountDataMap::init(memory_manager.get(ACCOUNT_DATA_MEMORY_ID)))
     });
 
+    /// Map that contains the block ids of an account grouped by memo.
+    /// Blocks without memo are not in this map.
+    static ACCOUNT_MEMO_BLOCK_IDS: RefCell<AccountMemoBlockIdsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AccountMemoBlockIdsMap::init(memory_manager.get(ACCOUNT_MEMO_BLOCK_IDS_MEMORY_ID)))
+    });
+
+    /// Map from the timestamp of a block to its index.
+    static BLOCK_TIMESTAMPS: RefCell<BlockTimestampsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(BlockTimestampsMap::init(memory_manager.get(BLOCK_TIMESTAMPS_MEMORY_ID)))
+    });
+
+    /// Map from the index of a block to the accounts involved in the block.
+    static BLOCK_ACCOUNTS: RefCell<BlockAccountsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(BlockAccountsMap::init(memory_manager.get(BLOCK_ACCOUNTS_MEMORY_ID)))
+    });
+
     /// Profiling data to understand cycles usage
     static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
 }
@@ -123,6 +166,18 @@ struct State {
 
     /// This fee is used if no fee nor effetive_fee is found in Approve blocks.
     pub last_fee: Option<Tokens>,
+
+    /// The number of blocks, starting from block 0, that have been added to
+    /// [ACCOUNT_MEMO_BLOCK_IDS] and [BLOCK_TIMESTAMPS]. Blocks stored before
+    /// those maps existed are added by [backfill_secondary_indexes].
+    #[serde(default)]
+    num_blocks_in_secondary_indexes: u64,
+
+    /// The number of blocks, starting from block 0, that have been added to
+    /// [BLOCK_ACCOUNTS]. Blocks stored before that map existed are added by
+    /// [backfill_block_accounts].
+    #[serde(default)]
+    num_blocks_in_block_accounts: u64,
 }
 
 // NOTE: the default configuration is dysfunctional, but it's convenient to have
@@ -136,6 +191,8 @@ impl Default for State {
             last_wait_time: Duration::from_secs(0),
             fee_collectors: Default::default(),
             last_fee: None,
+            num_blocks_in_secondary_indexes: 0,
+            num_blocks_in_block_accounts: 0,
         }
     }
 }
@@ -230,6 +287,21 @@ fn with_account_data<R>(f: impl FnOnce(&mut AccountDataMap) -> R) -> R {
     ACCOUNT_DATA.with(|cell| f(&mut cell.borrow_mut()))
 }
 
+/// A helper function to access the account block ids grouped by memo.
+fn with_account_memo_block_ids<R>(f: impl FnOnce(&mut AccountMemoBlockIdsMap) -> R) -> R {
+    ACCOUNT_MEMO_BLOCK_IDS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the block timestamps.
+fn with_block_timestamps<R>(f: impl FnOnce(&mut BlockTimestampsMap) -> R) -> R {
+    BLOCK_TIMESTAMPS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the accounts of the blocks.
+fn with_block_accounts<R>(f: impl FnOnce(&mut BlockAccountsMap) -> R) -> R {
+    BLOCK_ACCOUNTS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
 /// A helper function that returns a decoded block stored in the
 /// block log at the given index or None if there is no block at that index.
 /// This function can trap if the index at the given block cannot be decoded
@@ -263,11 +335,19 @@ fn change_balance(account: Account, f: impl FnOnce(Tokens) -> Tokens) {
 }
 
 fn balance_key(account: Account) -> (AccountDataType, (Blob<29>, [u8; 32])) {
+    (AccountDataType::Balance, account_key(account))
+}
+
+fn account_key(account: Account) -> (Blob<29>, [u8; 32]) {
     let owner = Blob::try_from(account.owner.as_slice()).unwrap();
-    (
-        AccountDataType::Balance,
-        (owner, *account.effective_subaccount()),
-    )
+    (owner, *account.effective_subaccount())
+}
+
+fn account_from_key((owner, subaccount): (Blob<29>, [u8; 32])) -> Account {
+    Account {
+        owner: Principal::from_slice(owner.as_slice()),
+        subaccount: (subaccount != [0; 32]).then_some(subaccount),
+    }
 }
 
 #[init]
@@ -392,6 +472,8 @@ pub async fn build_index() -> Option<()> {
     let failure_guard = guard((), |_| {
         set_build_index_timer(DEFAULT_RETRY_WAIT_TIME);
     });
+    backfill_secondary_indexes();
+    backfill_block_accounts();
     let next_txid = with_blocks(|blocks| blocks.len());
     let res = get_blocks_from_ledger(next_txid).await?;
     let mut tx_indexed_count: usize = 0;
@@ -413,7 +495,11 @@ pub async fn build_index() -> Option<()> {
     }
     tx_indexed_count += res.blocks.len();
     append_blocks(res.blocks);
-    let wait_time = compute_wait_time(tx_indexed_count);
+    let wait_time = if secondary_indexes_are_complete() && block_accounts_are_complete() {
+        compute_wait_time(tx_indexed_count)
+    } else {
+        Duration::ZERO
+    };
     log!(
         P1,
         "Indexed: {} waiting : {:?}",
@@ -466,6 +552,17 @@ fn append_block(block_index: BlockIndex64, block: GenericBlock) {
             }
         });
 
+        // add the block to the memo and timestamp indexes unless
+        // older blocks still need to be backfilled
+        if with_state(|state| state.num_blocks_in_secondary_indexes) == block_index {
+            index_memo_and_timestamp(block_index, &decoded_block);
+            mutate_state(|state| state.num_blocks_in_secondary_indexes = block_index + 1);
+        }
+        if with_state(|state| state.num_blocks_in_block_accounts) == block_index {
+            index_block_accounts(block_index, &decoded_block);
+            mutate_state(|state| state.num_blocks_in_block_accounts = block_index + 1);
+        }
+
         // add the block to the fee_collector if one is set
         index_fee_collector(block_index, &decoded_block);
 
@@ -484,6 +581,133 @@ fn append_blocks(new_blocks: Vec<GenericBlock>) {
     }
 }
 
+fn index_memo_and_timestamp(block_index: BlockIndex64, block: &Block<Tokens>) {
+    if let Some(memo) = &block.transaction.memo {
+        let memo_key = memo_key(&memo.0, 0x00);
+        with_account_memo_block_ids(|account_memo_block_ids| {
+            for account in get_accounts(block) {
+                account_memo_block_ids.insert(
+                    (account_sha256(account), (memo_key, Reverse(block_index))),
+                    (),
+                );
+            }
+        });
+    }
+    with_block_timestamps(|block_timestamps| {
+        block_timestamps.insert((block.timestamp, block_index), ())
+    });
+}
+
+/// Adds to the memo and timestamp indexes a batch of the blocks that were
+/// stored before those indexes were introduced.
+fn backfill_secondary_indexes() {
+    let (start, batch_size) = with_state(|state| {
+        (
+            state.num_blocks_in_secondary_indexes,
+            state.max_blocks_per_response,
+        )
+    });
+    let end = with_blocks(|blocks| blocks.len()).min(start.saturating_add(batch_size));
+    if start >= end {
+        return;
+    }
+    measure_span(
+        &PROFILING_DATA,
+        "build_index.backfill_secondary_indexes",
+        || {
+            for block_index in start..end {
+                let block = get_decoded_block(block_index).unwrap_or_else(|| {
+                    trap(&format!(
+                        "Block {} not found in the block log while backfilling the indexes",
+                        block_index
+                    ))
+                });
+                index_memo_and_timestamp(block_index, &block);
+            }
+        },
+    );
+    mutate_state(|state| state.num_blocks_in_secondary_indexes = end);
+    log!(
+        P1,
+        "Backfilled the memo and timestamp indexes up to block {}",
+        end
+    );
+}
+
+fn secondary_indexes_are_complete() -> bool {
+    with_state(|state| state.num_blocks_in_secondary_indexes) == with_blocks(|blocks| blocks.len())
+}
+
+fn index_block_accounts(block_index: BlockIndex64, block: &Block<Tokens>) {
+    let accounts = get_accounts_with_roles(block_index, block);
+    with_block_accounts(|block_accounts| {
+        for (role, account) in accounts {
+            block_accounts.insert(
+                (block_index, account_role_to_byte(role)),
+                account_key(account),
+            );
+        }
+    });
+}
+
+/// Adds to [BLOCK_ACCOUNTS] a batch of the blocks that were stored
+/// before that map was introduced.
+fn backfill_block_accounts() {
+    let (start, batch_size) = with_state(|state| {
+        (
+            state.num_blocks_in_block_accounts,
+            state.max_blocks_per_response,
+        )
+    });
+    let end = with_blocks(|blocks| blocks.len()).min(start.saturating_add(batch_size));
+    if start >= end {
+        return;
+    }
+    measure_span(
+        &PROFILING_DATA,
+        "build_index.backfill_block_accounts",
+        || {
+            for block_index in start..end {
+                let block = get_decoded_block(block_index).unwrap_or_else(|| {
+                    trap(&format!(
+                        "Block {} not found in the block log while backfilling the block accounts",
+                        block_index
+                    ))
+                });
+                index_block_accounts(block_index, &block);
+            }
+        },
+    );
+    mutate_state(|state| state.num_blocks_in_block_accounts = end);
+    log!(P1, "Backfilled the block accounts up to block {}", end);
+}
+
+fn block_accounts_are_complete() -> bool {
+    with_state(|state| state.num_blocks_in_block_accounts) == with_blocks(|blocks| blocks.len())
+}
+
+fn account_role_to_byte(role: AccountRole) -> u8 {
+    match role {
+        AccountRole::From => 0,
+        AccountRole::To => 1,
+        AccountRole::Spender => 2,
+        AccountRole::FeeCollector => 3,
+    }
+}
+
+fn account_role_from_byte(byte: u8) -> AccountRole {
+    match byte {
+        0 => AccountRole::From,
+        1 => AccountRole::To,
+        2 => AccountRole::Spender,
+        3 => AccountRole::FeeCollector,
+        _ => trap(&format!(
+            "Unknown account role {} in the block accounts",
+            byte
+        )),
+    }
+}
+
 fn index_fee_collector(block_index: BlockIndex64, block: &Block<Tokens>) {
     if let Some(fee_collector) = get_fee_collector(block_index, block) {
         mutate_state(|s| {
@@ -618,6 +842,36 @@ fn get_accounts(block: &Block<Tokens>) -> Vec<Account> {
     }
 }
 
+/// Returns the accounts involved in the block with their roles. Unlike
+/// [get_accounts], this includes the spender and the fee collector.
+fn get_accounts_with_roles(
+    block_index: BlockIndex64,
+    block: &Block<Tokens>,
+) -> Vec<(AccountRole, Account)> {
+    let (from, to, spender, fee_collector) = match block.transaction.operation {
+        Operation::Burn { from, spender, .. } => (Some(from), None, spender, None),
+        Operation::Mint { to, .. } => (None, Some(to), None, None),
+        Operation::Transfer {
+            from, to, spender, ..
+        } => (
+            Some(from),
+            Some(to),
+            spender,
+            get_fee_collector(block_index, block),
+        ),
+        Operation::Approve { from, spender, .. } => (Some(from), None, Some(spender), None),
+    };
+    [
+        (AccountRole::From, from),
+        (AccountRole::To, to),
+        (AccountRole::Spender, spender),
+        (AccountRole::FeeCollector, fee_collector),
+    ]
+    .into_iter()
+    .filter_map(|(role, account)| account.map(|account| (role, account)))
+    .collect()
+}
+
 fn get_fee_collector(block_index: BlockIndex64, block: &Block<Tokens>) -> Option<Account> {
     if block.fee_collector.is_some() {
         block.fee_collector
@@ -645,6 +899,14 @@ fn account_block_ids_key(account: Account, block_index: BlockIndex64) -> Account
     (account_sha256(account), Reverse(block_index))
 }
 
+/// Returns the first MEMO_KEY_LEN bytes of the memo padded with the given byte.
+fn memo_key(memo: &[u8], padding: u8) -> MemoKey {
+    let mut key = [padding; MEMO_KEY_LEN];
+    let len = memo.len().min(MEMO_KEY_LEN);
+    key[..len].copy_from_slice(&memo[..len]);
+    key
+}
+
 fn decode_icrc1_block(_txid: u64, bytes: Vec<u8>) -> GenericBlock {
     let encoded_block = EncodedBlock::from(bytes);
     encoded_block_to_generic_block(&encoded_block)
@@ -675,6 +937,53 @@ fn decode_block_range<R>(start: u64, length: u64, decoder: impl Fn(u64, Vec<u8>)
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn get_block_accounts(arg: GetBlockAccountsArgs) -> GetBlockAccountsResult {
+    let error = |message: String| GetBlockAccountsError { message };
+    let Some(block_index) = arg.block_index.0.to_u64() else {
+        return Err(error(format!(
+            "Block index {} is out of range",
+            arg.block_index
+        )));
+    };
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    if block_index >= num_blocks {
+        return Err(error(format!(
+            "Block {} is not indexed yet, the index has {} blocks",
+            block_index, num_blocks
+        )));
+    }
+    let num_blocks_in_block_accounts = with_state(|state| state.num_blocks_in_block_accounts);
+    if block_index >= num_blocks_in_block_accounts {
+        return Err(error(format!(
+            "The block accounts are being indexed ({} out of {} blocks indexed), block {} is not available yet",
+            num_blocks_in_block_accounts, num_blocks, block_index
+        )));
+    }
+
+    let accounts: Vec<BlockAccount> = with_block_accounts(|block_accounts| {
+        block_accounts
+            .range((block_index, 0)..=(block_index, u8::MAX))
+            .map(|((_, role), account)| BlockAccount {
+                role: account_role_from_byte(role),
+                account: account_from_key(account),
+            })
+            .collect()
+    });
+    let block = get_decoded_block(block_index).unwrap_or_else(|| {
+        trap(&format!(
+            "Block {} not found in the block log, the block log is corrupted!",
+            block_index
+        ))
+    });
+    Ok(GetBlockAccountsResponse {
+        block_index: block_index.into(),
+        accounts,
+        transaction: block.into(),
+    })
+}
+
 #[query]
 #[candid_method(query)]
 fn ledger_id() -> Principal {
@@ -691,58 +1000,224 @@ fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransa
         .expect("The length must be a u64!")
         .min(with_state(|opts| opts.max_blocks_per_response))
         .min(usize::MAX as u64) as usize;
-    // TODO: deal with the user setting start to u64::MAX
+    let memo_prefix = arg.memo_prefix.as_deref();
+    let time_range = arg.time_range.as_ref();
+    if (memo_prefix.is_some() || time_range.is_some()) && !secondary_indexes_are_complete() {
+        return Err(GetAccountTransactionsError {
+            message: format!(
+                "The memo and timestamp indexes are being built ({} out of {} blocks indexed), filters are not available yet",
+                with_state(|state| state.num_blocks_in_secondary_indexes),
+                with_blocks(|blocks| blocks.len()),
+            ),
+        });
+    }
+    let page_token = match &arg.page_token {
+        Some(bytes) => Some(decode_page_token(bytes, memo_prefix)?),
+        None => None,
+    };
+
+    // the range of block indices that can match the filters
     let start = arg
         .start
-        .map_or(u64::MAX, |n| n.0.to_u64().expect("start must be a u64!"));
-    let key = account_block_ids_key(arg.account, start);
-    let mut transactions = vec![];
-    let indices = with_account_block_ids(|account_block_ids| {
-        account_block_ids
-            .range(key..)
-            // old txs of the requested account and skip the start index
-            .take_while(|(k, _)| k.0 == key.0)
-            .filter(|(k, _)| k.1 .0 < start)
-            .take(length)
-            .map(|(k, _)| k.1 .0)
-            .collect::<Vec<BlockIndex64>>()
-    });
-    for id in indices {
-        let block = with_blocks(|blocks| {
-            blocks.get(id).unwrap_or_else(|| {
-                trap(&format!(
-                    "Block {} not found in the block log, account blocks map is corrupted!",
-                    id
-                ))
-            })
-        });
-        let transaction = encoded_block_bytes_to_flat_transaction(id, block);
-        let transaction_with_idx = TransactionWithId {
-            id: id.into(),
-            transaction,
-        };
-        transactions.push(transaction_with_idx);
+        .map(|n| n.0.to_u64().expect("start must be a u64!"));
+    let mut block_range = match start {
+        // the start index itself is not included
+        Some(start) => match start.checked_sub(1) {
+            Some(newest) => 0..=newest,
+            None => empty_block_range(),
+        },
+        None => 0..=u64::MAX,
+    };
+    if let Some(time_range) = time_range {
+        block_range = intersect(block_range, block_range_for_time_range(time_range));
     }
+
+    let account_hash = account_sha256(arg.account);
+    let (blocks, next_page_token) = if block_range.is_empty() {
+        (vec![], None)
+    } else if let Some(memo_prefix) = memo_prefix {
+        let lower_bound = match page_token {
+            Some(PageToken {
+                memo: Some(memo),
+                last,
+            }) => Excluded((account_hash, (memo, Reverse(last)))),
+            _ => Included((
+                account_hash,
+                (memo_key(memo_prefix, 0x00), Reverse(u64::MAX)),
+            )),
+        };
+        let upper_bound = Included((account_hash, (memo_key(memo_prefix, 0xFF), Reverse(0))));
+        with_account_memo_block_ids(|account_memo_block_ids| {
+            let candidates = account_memo_block_ids
+                .range((lower_bound, upper_bound))
+                .map(|((_, (memo, block_index)), _)| (Some(memo), block_index.0));
+            collect_matching_blocks(candidates, &block_range, length, memo_prefix, time_range)
+        })
+    } else {
+        let newest = match page_token {
+            Some(PageToken { last, .. }) => last.checked_sub(1),
+            None => Some(u64::MAX),
+        };
+        match newest.map(|newest| newest.min(*block_range.end())) {
+            Some(newest) if newest >= *block_range.start() => {
+                let key = (account_hash, Reverse(newest));
+                let oldest = *block_range.start();
+                with_account_block_ids(|account_block_ids| {
+                    let candidates = account_block_ids
+                        .range(key..)
+                        // old txs of the requested account up to the oldest block in range
+                        .take_while(|(k, _)| k.0 == account_hash && k.1 .0 >= oldest)
+                        .map(|(k, _)| (None, k.1 .0));
+                    collect_matching_blocks(candidates, &block_range, length, None, time_range)
+                })
+            }
+            _ => (vec![], None),
+        }
+    };
+
+    let transactions = blocks
+        .into_iter()
+        .map(|(id, block)| TransactionWithId {
+            id: id.into(),
+            transaction: block.into(),
+        })
+        .collect();
     let oldest_tx_id = get_oldest_tx_id(arg.account).map(|tx_id| tx_id.into());
     let balance = get_balance(arg.account).into();
     Ok(GetAccountTransactionsResponse {
         balance,
         transactions,
         oldest_tx_id,
+        next_page_token: next_page_token.map(|token| token.encode()),
     })
 }
 
-fn encoded_block_bytes_to_flat_transaction(
-    block_index: BlockIndex64,
-    block: Vec<u8>,
-) -> Transaction {
-    let block = Block::<Tokens>::decode(EncodedBlock::from(block)).unwrap_or_else(|e| {
-        trap(&format!(
-            "Unable to decode encoded block at index {}. Error: {}",
-            block_index, e
-        ))
-    });
-    block.into()
+/// The position of a [get_account_transactions] scan, returned to
+/// the client as an opaque page token.
+#[derive(Debug, Serialize, Deserialize)]
+struct PageToken {
+    /// The memo key of the last inspected entry if the scan was
+    /// on the memo index.
+    memo: Option<MemoKey>,
+    /// The index of the last inspected block.
+    last: BlockIndex64,
+}
+
+impl PageToken {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        ciborium::ser::into_writer(self, &mut buf).expect("failed to encode page token");
+        buf
+    }
+}
+
+fn decode_page_token(
+    bytes: &[u8],
+    memo_prefix: Option<&[u8]>,
+) -> Result<PageToken, GetAccountTransactionsError> {
+    let invalid = |reason: &str| GetAccountTransactionsError {
+        message: format!("Invalid page token: {}", reason),
+    };
+    let token: PageToken =
+        ciborium::de::from_reader(bytes).map_err(|_| invalid("cannot decode the token"))?;
+    match (&token.memo, memo_prefix) {
+        (None, None) => Ok(token),
+        (Some(memo), Some(memo_prefix)) => {
+            let len = memo_prefix.len().min(MEMO_KEY_LEN);
+            if memo[..len] == memo_prefix[..len] {
+                Ok(token)
+            } else {
+                Err(invalid("the token was issued for a different memo_prefix"))
+            }
+        }
+        _ => Err(invalid("the token was issued for a different memo_prefix")),
+    }
+}
+
+/// Returns up to `length` blocks among the `candidates` that are in
+/// `block_range` and match the filters, together with a page token if
+/// there may be more matching candidates. At most
+/// [MAX_ENTRIES_SCANNED_PER_QUERY] candidates are inspected.
+fn collect_matching_blocks(
+    candidates: impl Iterator<Item = (Option<MemoKey>, BlockIndex64)>,
+    block_range: &RangeInclusive<BlockIndex64>,
+    length: usize,
+    memo_prefix: Option<&[u8]>,
+    time_range: Option<&TimeRange>,
+) -> (Vec<(BlockIndex64, Block<Tokens>)>, Option<PageToken>) {
+    let mut blocks = vec![];
+    let mut last_inspected = None;
+    for (inspected, (memo, block_index)) in candidates.enumerate() {
+        if blocks.len() == length || inspected == MAX_ENTRIES_SCANNED_PER_QUERY {
+            let token = last_inspected.map(|(memo, last)| PageToken { memo, last });
+            return (blocks, token);
+        }
+        last_inspected = Some((memo, block_index));
+        if !block_range.contains(&block_index) {
+            continue;
+        }
+        let block = get_decoded_block(block_index).unwrap_or_else(|| {
+            trap(&format!(
+                "Block {} not found in the block log, account blocks map is corrupted!",
+                block_index
+            ))
+        });
+        if matches_filters(&block, memo_prefix, time_range) {
+            blocks.push((block_index, block));
+        }
+    }
+    (blocks, None)
+}
+
+fn matches_filters(
+    block: &Block<Tokens>,
+    memo_prefix: Option<&[u8]>,
+    time_range: Option<&TimeRange>,
+) -> bool {
+    let memo_matches = match (memo_prefix, &block.transaction.memo) {
+        (None, _) => true,
+        (Some(memo_prefix), Some(memo)) => memo.0.starts_with(memo_prefix),
+        (Some(_), None) => false,
+    };
+    let time_matches = match time_range {
+        None => true,
+        Some(time_range) => time_range.start <= block.timestamp && block.timestamp < time_range.end,
+    };
+    memo_matches && time_matches
+}
+
+/// Returns the range of indices of the blocks with timestamp in `time_range`.
+/// The ledger assigns non-decreasing timestamps to blocks so the blocks
+/// in between the first and the last one are in the time range too.
+fn block_range_for_time_range(time_range: &TimeRange) -> RangeInclusive<BlockIndex64> {
+    if time_range.start >= time_range.end {
+        return empty_block_range();
+    }
+    with_block_timestamps(|block_timestamps| {
+        let first = block_timestamps
+            .range((time_range.start, 0)..)
+            .next()
+            .map(|((_, block_index), _)| block_index);
+        let last = block_timestamps
+            .iter_upper_bound(&(time_range.end, 0))
+            .next()
+            .map(|((_, block_index), _)| block_index);
+        match (first, last) {
+            (Some(first), Some(last)) => first..=last,
+            _ => empty_block_range(),
+        }
+    })
+}
+
+fn empty_block_range() -> RangeInclusive<BlockIndex64> {
+    RangeInclusive::new(1, 0)
+}
+
+fn intersect(
+    a: RangeInclusive<BlockIndex64>,
+    b: RangeInclusive<BlockIndex64>,
+) -> RangeInclusive<BlockIndex64> {
+    *a.start().max(b.start())..=*a.end().min(b.end())
 }
 
 fn get_oldest_tx_id(account: Account) -> Option<BlockIndex64> {
@@ -871,6 +1346,16 @@ pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> st
             .min(f64::MAX as u128) as f64,
         "Last amount of time waited between two transactions fetch.",
     )?;
+    w.encode_gauge(
+        "index_num_blocks_in_secondary_indexes",
+        with_state(|state| state.num_blocks_in_secondary_indexes) as f64,
+        "Number of blocks added to the memo and timestamp indexes.",
+    )?;
+    w.encode_gauge(
+        "index_num_blocks_in_block_accounts",
+        with_state(|state| state.num_blocks_in_block_accounts) as f64,
+        "Number of blocks whose accounts were added to the block accounts index.",
+    )?;
     PROFILING_DATA.with(|cell| -> std::io::Result<()> {
         cell.borrow().record_metrics(w.histogram_vec(
             "index_ng_profile_instructions",
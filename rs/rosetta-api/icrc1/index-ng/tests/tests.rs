@@ -4,9 +4,10 @@ use ic_canisters_http_types::{HttpRequest, HttpResponse};
 use ic_icrc1::blocks::generic_block_to_encoded_block;
 use ic_icrc1::Block;
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
-    ListSubaccountsArgs, Log, Status, TransactionWithId, UpgradeArg as IndexUpgradeArg,
+    AccountRole, BlockAccount, FeeCollectorRanges, GetAccountTransactionsArgs,
+    GetAccountTransactionsResponse, GetAccountTransactionsResult, GetBlockAccountsArgs,
+    GetBlockAccountsResult, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
+    ListSubaccountsArgs, Log, Status, TimeRange, TransactionWithId, UpgradeArg as IndexUpgradeArg,
     DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_icrc1_ledger::{
@@ -19,7 +20,7 @@ use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_core::block::BlockType;
 use ic_state_machine_tests::{StateMachine, WasmResult};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
-use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use icrc_ledger_types::icrc1::transfer::{BlockIndex, Memo, TransferArg, TransferError};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc3::blocks::{BlockRange, GenericBlock, GetBlocksRequest};
 use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
@@ -90,6 +91,8 @@ fn default_archive_options() -> ArchiveOptions {
         controller_id: PrincipalId::new_user_test_id(100),
         cycles_for_archive_creation: None,
         max_transactions_per_response: Some(MAX_BLOCKS_FROM_ARCHIVE),
+        more_controller_ids: None,
+        archive_created_callback: None,
     }
 }
 
@@ -373,6 +376,17 @@ fn transfer(
     from: Account,
     to: Account,
     amount: u64,
+) -> BlockIndex {
+    transfer_with_memo(env, ledger_id, from, to, amount, None)
+}
+
+fn transfer_with_memo(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    from: Account,
+    to: Account,
+    amount: u64,
+    memo: Option<Vec<u8>>,
 ) -> BlockIndex {
     let Account { owner, subaccount } = from;
     let req = TransferArg {
@@ -381,7 +395,7 @@ fn transfer(
         amount: amount.into(),
         created_at_time: None,
         fee: None,
-        memo: None,
+        memo: memo.map(Memo::from),
     };
     icrc1_transfer(env, ledger_id, owner.into(), req)
 }
@@ -468,7 +482,19 @@ fn get_account_transactions(
         account,
         start: start.map(|n| n.into()),
         max_results: max_results.into(),
+        memo_prefix: None,
+        time_range: None,
+        page_token: None,
     };
+    get_account_transactions_with_args(env, index_id, req)
+        .expect("Failed to perform GetAccountTransactionsArgs")
+}
+
+fn get_account_transactions_with_args(
+    env: &StateMachine,
+    index_id: CanisterId,
+    req: GetAccountTransactionsArgs,
+) -> GetAccountTransactionsResult {
     let req = Encode!(&req).expect("Failed to encode GetAccountTransactionsArgs");
     let res = env
         .execute_ingress(index_id, "get_account_transactions", req)
@@ -476,7 +502,40 @@ fn get_account_transactions(
         .bytes();
     Decode!(&res, GetAccountTransactionsResult)
         .expect("Failed to decode GetAccountTransactionsArgs")
-        .expect("Failed to perform GetAccountTransactionsArgs")
+}
+
+// Returns the ids of all the transactions of the account matching the filters
+// by following the page tokens returned by the index.
+fn get_all_account_transaction_ids_with_filters(
+    env: &StateMachine,
+    index_id: CanisterId,
+    account: Account,
+    memo_prefix: Option<Vec<u8>>,
+    time_range: Option<TimeRange>,
+    max_results: u64,
+) -> Vec<u64> {
+    let mut ids = vec![];
+    let mut page_token = None;
+    loop {
+        let res = get_account_transactions_with_args(
+            env,
+            index_id,
+            GetAccountTransactionsArgs {
+                account,
+                start: None,
+                max_results: max_results.into(),
+                memo_prefix: memo_prefix.clone(),
+                time_range: time_range.clone(),
+                page_token,
+            },
+        )
+        .expect("Failed to perform GetAccountTransactionsArgs");
+        ids.extend(res.transactions.iter().map(|tx| tx.id.0.to_u64().unwrap()));
+        match res.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => return ids,
+        }
+    }
 }
 
 fn list_subaccounts(
@@ -513,6 +572,27 @@ fn get_fee_collectors_ranges(env: &StateMachine, index: CanisterId) -> FeeCollec
     .expect("failed to decode get_fee_collectors_ranges response")
 }
 
+fn get_block_accounts(
+    env: &StateMachine,
+    index: CanisterId,
+    block_index: u64,
+) -> GetBlockAccountsResult {
+    Decode!(
+        &env.execute_ingress(
+            index,
+            "get_block_accounts",
+            Encode!(&GetBlockAccountsArgs {
+                block_index: block_index.into(),
+            })
+            .unwrap()
+        )
+        .expect("failed to get_block_accounts")
+        .bytes(),
+        GetBlockAccountsResult
+    )
+    .expect("failed to decode get_block_accounts response")
+}
+
 // Assert that the index canister contains the same blocks as the ledger.
 #[track_caller]
 fn assert_ledger_index_parity(env: &StateMachine, ledger_id: CanisterId, index_id: CanisterId) {
@@ -894,6 +974,184 @@ fn test_get_account_transactions_pagination() {
     }
 }
 
+#[test]
+fn test_get_account_transactions_memo_prefix() {
+    let initial_balances: Vec<_> = vec![(account(1, 0), 1_000_000_000)];
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(env, initial_balances, default_archive_options(), None);
+    let index_id = install_index_ng(env, ledger_id);
+
+    let memos: Vec<Option<Vec<u8>>> = vec![
+        Some(vec![1, 2, 3]),
+        Some(vec![1, 2, 4]),
+        None,
+        Some(vec![5]),
+        Some(vec![1, 2, 3]),
+        Some(vec![1]),
+    ];
+    let mut ids = vec![];
+    for memo in memos {
+        let id = transfer_with_memo(env, ledger_id, account(1, 0), account(2, 0), 1, memo);
+        ids.push(id.0.to_u64().unwrap());
+    }
+
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let get_ids = |account: Account, memo_prefix: &[u8], max_results: u64| {
+        let mut res = get_all_account_transaction_ids_with_filters(
+            env,
+            index_id,
+            account,
+            Some(memo_prefix.to_vec()),
+            None,
+            max_results,
+        );
+        res.sort_unstable();
+        res
+    };
+
+    // Both the sender and the receiver are indexed by memo.
+    for account in [account(1, 0), account(2, 0)] {
+        for max_results in [1, 2, 100] {
+            assert_eq!(
+                vec![ids[0], ids[1], ids[4]],
+                get_ids(account, &[1, 2], max_results)
+            );
+            assert_eq!(
+                vec![ids[0], ids[4]],
+                get_ids(account, &[1, 2, 3], max_results)
+            );
+            assert_eq!(
+                vec![ids[0], ids[1], ids[4], ids[5]],
+                get_ids(account, &[1], max_results)
+            );
+            assert_eq!(vec![ids[3]], get_ids(account, &[5], max_results));
+            assert_eq!(Vec::<u64>::new(), get_ids(account, &[6], max_results));
+            assert_eq!(
+                Vec::<u64>::new(),
+                get_ids(account, &[1, 2, 3, 4], max_results)
+            );
+        }
+    }
+    // The empty prefix matches all the transactions with a memo.
+    assert_eq!(
+        vec![ids[0], ids[1], ids[3], ids[4], ids[5]],
+        get_ids(account(1, 0), &[], 100)
+    );
+    // account(3, 0) has no transactions.
+    assert_eq!(Vec::<u64>::new(), get_ids(account(3, 0), &[1], 100));
+
+    // A page token issued for a memo prefix cannot be used for another one.
+    let res = get_account_transactions_with_args(
+        env,
+        index_id,
+        GetAccountTransactionsArgs {
+            account: account(1, 0),
+            start: None,
+            max_results: 1.into(),
+            memo_prefix: Some(vec![1, 2]),
+            time_range: None,
+            page_token: None,
+        },
+    )
+    .unwrap();
+    let res = get_account_transactions_with_args(
+        env,
+        index_id,
+        GetAccountTransactionsArgs {
+            account: account(1, 0),
+            start: None,
+            max_results: 1.into(),
+            memo_prefix: Some(vec![5]),
+            time_range: None,
+            page_token: res.next_page_token,
+        },
+    );
+    assert!(res.is_err(), "expected an error but got {:?}", res);
+}
+
+#[test]
+fn test_get_account_transactions_time_range() {
+    let initial_balances: Vec<_> = vec![(account(1, 0), 1_000_000_000)];
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(env, initial_balances, default_archive_options(), None);
+    let index_id = install_index_ng(env, ledger_id);
+
+    for i in 0..10 {
+        env.advance_time(Duration::from_secs(10));
+        let memo = if i % 2 == 0 { Some(vec![0]) } else { None };
+        transfer_with_memo(env, ledger_id, account(1, 0), account(2, 0), 1, memo);
+    }
+
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    // Block 0 is the initial mint, blocks 1 to 10 are the transfers.
+    let txs = get_account_transactions(env, index_id, account(1, 0), None, u64::MAX).transactions;
+    assert_eq!(11, txs.len());
+    let timestamp = |id: u64| {
+        txs.iter()
+            .find(|tx| tx.id == Nat::from(id))
+            .unwrap()
+            .transaction
+            .timestamp
+    };
+
+    let get_ids = |memo_prefix: Option<Vec<u8>>, start: u64, end: u64, max_results: u64| {
+        get_all_account_transaction_ids_with_filters(
+            env,
+            index_id,
+            account(1, 0),
+            memo_prefix,
+            Some(TimeRange { start, end }),
+            max_results,
+        )
+    };
+
+    for max_results in [1, 3, 100] {
+        // The start of the range is inclusive and the end is exclusive.
+        assert_eq!(
+            vec![6, 5, 4, 3],
+            get_ids(None, timestamp(3), timestamp(7), max_results)
+        );
+        assert_eq!(
+            vec![6, 5, 4, 3],
+            get_ids(None, timestamp(3), timestamp(6) + 1, max_results)
+        );
+        assert_eq!(
+            vec![10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+            get_ids(None, 0, u64::MAX, max_results)
+        );
+        assert_eq!(
+            Vec::<u64>::new(),
+            get_ids(None, timestamp(7), timestamp(7), max_results)
+        );
+        assert_eq!(
+            Vec::<u64>::new(),
+            get_ids(None, timestamp(10) + 1, u64::MAX, max_results)
+        );
+        // Transfers 1, 3, 5, ... have a memo.
+        let mut with_memo = get_ids(Some(vec![0]), timestamp(2), timestamp(9), max_results);
+        with_memo.sort_unstable();
+        assert_eq!(vec![3, 5, 7], with_memo);
+    }
+
+    // account(2, 0) has only the transfers.
+    assert_eq!(
+        vec![2, 1],
+        get_all_account_transaction_ids_with_filters(
+            env,
+            index_id,
+            account(2, 0),
+            None,
+            Some(TimeRange {
+                start: 0,
+                end: timestamp(3)
+            }),
+            100,
+        )
+    );
+}
+
 #[test]
 fn test_icrc1_balance_of() {
     // 1 case only because the test is expensive to run.
@@ -1226,6 +1484,66 @@ fn test_fee_collector() {
     );
 }
 
+#[test]
+fn test_get_block_accounts() {
+    let env = &StateMachine::new();
+    let fee_collector = account(42, 0);
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)], // txid: 0
+        default_archive_options(),
+        Some(fee_collector),
+    );
+    let index_id = install_index_ng(env, ledger_id);
+
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 100_000); // txid: 1
+    approve(env, ledger_id, account(1, 0), account(3, 0), 100_000); // txid: 2
+                                                                    // Archive the blocks above.
+    for _ in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(env, ledger_id, account(1, 0), account(1, 1), 1);
+    }
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let block_accounts = |block_index: u64| {
+        let response = get_block_accounts(env, index_id, block_index).unwrap();
+        assert_eq!(response.block_index, Nat::from(block_index));
+        (response.accounts, response.transaction.kind)
+    };
+    let block_account = |role, account| BlockAccount { role, account };
+    assert_eq!(
+        block_accounts(0),
+        (
+            vec![block_account(AccountRole::To, account(1, 0))],
+            "mint".to_string()
+        )
+    );
+    assert_eq!(
+        block_accounts(1),
+        (
+            vec![
+                block_account(AccountRole::From, account(1, 0)),
+                block_account(AccountRole::To, account(2, 0)),
+                block_account(AccountRole::FeeCollector, fee_collector),
+            ],
+            "transfer".to_string()
+        )
+    );
+    assert_eq!(
+        block_accounts(2),
+        (
+            vec![
+                block_account(AccountRole::From, account(1, 0)),
+                block_account(AccountRole::Spender, account(3, 0)),
+            ],
+            "approve".to_string()
+        )
+    );
+
+    let num_blocks = 3 + ARCHIVE_TRIGGER_THRESHOLD;
+    assert!(get_block_accounts(env, index_id, num_blocks - 1).is_ok());
+    assert!(get_block_accounts(env, index_id, num_blocks).is_err());
+}
+
 #[test]
 fn test_get_account_transactions_vs_old_index() {
     let mut runner = TestRunner::new(TestRunnerConfig::with_cases(1));
@@ -95,6 +95,8 @@ fn default_archive_options() -> ArchiveOptions {
         controller_id: PrincipalId::new_user_test_id(100),
         cycles_for_archive_creation: None,
         max_transactions_per_response: None,
+        more_controller_ids: None,
+        archive_created_callback: None,
     }
 }
 
@@ -590,6 +592,8 @@ fn test_index_archived_txs() {
                 MINT_BLOCKS_PER_ARCHIVE * mint_block().size_bytes() as u64,
             ),
             ..default_archive_options()
+            more_controller_ids: None,
+            archive_created_callback: None,
         },
     );
     for idx in 0..num_txs {
@@ -633,6 +637,8 @@ fn test_index_archived_txs_paging() {
             num_blocks_to_archive: NUM_ARCHIVED_TXS,
             max_transactions_per_response: Some(MAX_TXS_PER_GET_TRANSACTIONS_RESPONSE),
             ..default_archive_options()
+            more_controller_ids: None,
+            archive_created_callback: None,
         },
     );
     for idx in 0..ARCHIVE_TRIGGER_THRESHOLD {
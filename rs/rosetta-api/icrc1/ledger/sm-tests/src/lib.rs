@@ -8,15 +8,19 @@ use ic_ledger_core::block::{BlockIndex, BlockType};
 use ic_ledger_hash_of::HashOf;
 use ic_state_machine_tests::{CanisterId, ErrorCode, StateMachine, WasmResult};
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
-use icrc_ledger_types::icrc3::archive::ArchiveInfo;
+use icrc_ledger_types::icrc3::archive::{
+    ArchiveInfo, GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo,
+};
 use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
 use icrc_ledger_types::icrc3::blocks::GetBlocksResponse;
+use icrc_ledger_types::icrc3::blocks::{GetBlocksRequest, GetBlocksResult};
 use icrc_ledger_types::icrc3::transactions::GetTransactionsRequest;
 use icrc_ledger_types::icrc3::transactions::GetTransactionsResponse;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
@@ -310,6 +314,47 @@ fn get_archive_blocks(
     get_transactions_as(env, archive, start, length, "get_blocks".to_string())
 }
 
+fn blocks_request(start: u64, length: u64) -> GetBlocksRequest {
+    GetBlocksRequest {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    }
+}
+
+fn icrc3_get_blocks(
+    env: &StateMachine,
+    canister: Principal,
+    args: Vec<GetBlocksRequest>,
+) -> GetBlocksResult {
+    let canister_id =
+        CanisterId::new(canister.into()).expect("failed to convert Principal to CanisterId");
+    Decode!(
+        &env.query(canister_id, "icrc3_get_blocks", Encode!(&args).unwrap())
+            .expect("failed to query icrc3_get_blocks")
+            .bytes(),
+        GetBlocksResult
+    )
+    .expect("failed to decode icrc3_get_blocks response")
+}
+
+fn icrc3_get_archives(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Option<Principal>,
+) -> GetArchivesResult {
+    Decode!(
+        &env.query(
+            ledger,
+            "icrc3_get_archives",
+            Encode!(&GetArchivesArgs { from }).unwrap()
+        )
+        .expect("failed to query icrc3_get_archives")
+        .bytes(),
+        GetArchivesResult
+    )
+    .expect("failed to decode icrc3_get_archives response")
+}
+
 fn get_phash(block: &IcrcBlock) -> Result<Option<Hash>, String> {
     match block {
         IcrcBlock::Map(map) => {
@@ -583,6 +628,8 @@ fn init_args(initial_balances: Vec<(Account, u64)>) -> InitArgs {
             controller_id: PrincipalId::new_user_test_id(100),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         },
         feature_flags: Some(FeatureFlags { icrc2: true }),
         maximum_number_of_accounts: None,
@@ -1361,6 +1408,143 @@ where
     assert_eq!(0, missing_blocks_reply.archived_blocks.len());
 }
 
+pub fn test_icrc3_get_blocks<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 10_000_000)],
+    );
+
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, canister_id, p1.0, p2.0, 10_000 + i * 10_000).expect("transfer failed");
+    }
+
+    env.run_until_completion(/*max_ticks=*/ 10);
+
+    let chain_length = ARCHIVE_TRIGGER_THRESHOLD + 1;
+    let resp = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        vec![blocks_request(0, 1_000_000)],
+    );
+    assert_eq!(resp.log_length, Nat::from(chain_length));
+    assert_eq!(
+        resp.blocks.len(),
+        (chain_length - NUM_BLOCKS_TO_ARCHIVE) as usize
+    );
+    assert_eq!(resp.archived_blocks.len(), 1);
+
+    let archive_canister_id = list_archives(&env, canister_id)[0].canister_id;
+    let archived = &resp.archived_blocks[0];
+    assert_eq!(archived.callback.canister_id, archive_canister_id);
+    assert_eq!(archived.callback.method, "icrc3_get_blocks");
+    assert_eq!(
+        archived.args,
+        vec![blocks_request(0, NUM_BLOCKS_TO_ARCHIVE)]
+    );
+
+    let archived_resp = icrc3_get_blocks(&env, archive_canister_id, archived.args.clone());
+    assert_eq!(archived_resp.blocks.len(), NUM_BLOCKS_TO_ARCHIVE as usize);
+    assert!(archived_resp.archived_blocks.is_empty());
+
+    // The ICRC-3 blocks must agree with the legacy blocks, including their hashes.
+    let legacy_blocks: Vec<_> =
+        get_archive_blocks(&env, archive_canister_id, 0, NUM_BLOCKS_TO_ARCHIVE as usize)
+            .blocks
+            .into_iter()
+            .chain(get_blocks(&env, canister_id.get().0, 0, 1_000_000).blocks)
+            .collect();
+    let icrc3_blocks: Vec<_> = archived_resp
+        .blocks
+        .into_iter()
+        .chain(resp.blocks)
+        .collect();
+    assert_eq!(icrc3_blocks.len(), legacy_blocks.len());
+    for (i, (block, legacy_block)) in icrc3_blocks.iter().zip(legacy_blocks.iter()).enumerate() {
+        assert_eq!(block.id, Nat::from(i));
+        assert_eq!(block.block, ICRC3Value::from(legacy_block.clone()));
+        assert_eq!(block.block.hash(), legacy_block.hash());
+    }
+
+    // Ranges spanning the archive and the ledger are split accordingly.
+    let resp = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        vec![
+            blocks_request(3, 4),
+            blocks_request(9, 1),
+            blocks_request(100, 5),
+        ],
+    );
+    let ids: Vec<_> = resp.blocks.iter().map(|b| b.id.clone()).collect();
+    assert_eq!(
+        ids,
+        vec![Nat::from(5_u64), Nat::from(6_u64), Nat::from(9_u64)]
+    );
+    assert_eq!(resp.archived_blocks.len(), 1);
+    assert_eq!(resp.archived_blocks[0].args, vec![blocks_request(3, 2)]);
+
+    let archived_resp = icrc3_get_blocks(
+        &env,
+        archive_canister_id,
+        vec![blocks_request(3, 2), blocks_request(4, 100)],
+    );
+    let ids: Vec<_> = archived_resp.blocks.iter().map(|b| b.id.clone()).collect();
+    assert_eq!(
+        ids,
+        vec![Nat::from(3_u64), Nat::from(4_u64), Nat::from(4_u64)]
+    );
+
+    // Requesting non-existing blocks does not crash the ledger or the archive.
+    let resp = icrc3_get_blocks(&env, canister_id.get().0, vec![blocks_request(100, 5)]);
+    assert!(resp.blocks.is_empty());
+    assert!(resp.archived_blocks.is_empty());
+    let resp = icrc3_get_blocks(&env, archive_canister_id, vec![blocks_request(100, 5)]);
+    assert!(resp.blocks.is_empty());
+}
+
+pub fn test_icrc3_get_archives<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 10_000_000)],
+    );
+
+    assert_eq!(icrc3_get_archives(&env, canister_id, None), vec![]);
+
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, canister_id, p1.0, p2.0, 10_000 + i).expect("transfer failed");
+    }
+
+    env.run_until_completion(/*max_ticks=*/ 10);
+
+    let archive_canister_id = list_archives(&env, canister_id)[0].canister_id;
+    assert_eq!(
+        icrc3_get_archives(&env, canister_id, None),
+        vec![ICRC3ArchiveInfo {
+            canister_id: archive_canister_id,
+            start: Nat::from(0_u64),
+            end: Nat::from(NUM_BLOCKS_TO_ARCHIVE - 1),
+        }]
+    );
+    assert_eq!(
+        icrc3_get_archives(&env, canister_id, Some(archive_canister_id)),
+        vec![]
+    );
+}
+
 // Generate random blocks and check that their CBOR encoding complies with the CDDL spec.
 pub fn block_encoding_agrees_with_the_schema() {
     use std::path::PathBuf;
@@ -9,7 +9,7 @@ use candid::{
     CandidType, Principal,
 };
 use ic_crypto_tree_hash::{Label, MixedHashTree};
-use ic_icrc1::blocks::encoded_block_to_generic_block;
+use ic_icrc1::blocks::{encoded_block_to_generic_block, encoded_block_to_icrc3_block};
 use ic_icrc1::{Block, LedgerBalances, Transaction};
 pub use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_canister_core::{
@@ -28,11 +28,15 @@ use ic_ledger_core::{
 use ic_ledger_hash_of::HashOf;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
-use icrc_ledger_types::icrc3::{blocks::GetBlocksResponse, transactions::GetTransactionsResponse};
+use icrc_ledger_types::icrc3::{
+    blocks::{ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResponse, GetBlocksResult},
+    transactions::GetTransactionsResponse,
+};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
-    icrc3::archive::{ArchivedRange, QueryBlockArchiveFn, QueryTxArchiveFn},
+    icrc3::archive::{ArchivedRange, ICRC3QueryArchiveFn, QueryBlockArchiveFn, QueryTxArchiveFn},
 };
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::borrow::Cow;
@@ -136,6 +140,8 @@ impl InitArgsBuilder {
                 controller_id: default_owner.into(),
                 cycles_for_archive_creation: None,
                 max_transactions_per_response: None,
+                more_controller_ids: None,
+                archive_created_callback: None,
             },
             max_memo_length: None,
             feature_flags: None,
@@ -609,18 +615,25 @@ impl<Tokens: TokensType> Ledger<Tokens> {
         self.construct_hash_tree().digest().0
     }
 
+    /// Returns the hash tree certifying the tip of the block log as specified
+    /// by ICRC-3: the `last_block_index` label holds the LEB-128 encoded index
+    /// of the last block and the `last_block_hash` label holds its hash.
     pub fn construct_hash_tree(&self) -> MixedHashTree {
         match self.blockchain().last_hash {
             Some(hash) => {
                 let last_block_index = self.blockchain().chain_length().checked_sub(1).unwrap();
+                let mut last_block_index_encoded = vec![];
+                Nat::from(last_block_index)
+                    .encode(&mut last_block_index_encoded)
+                    .expect("bug: cannot encode a Nat");
                 MixedHashTree::Fork(Box::new((
                     MixedHashTree::Labeled(
-                        Label::from("last_block_index"),
-                        Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
+                        Label::from("last_block_hash"),
+                        Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
                     ),
                     MixedHashTree::Labeled(
-                        Label::from("tip_hash"),
-                        Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
+                        Label::from("last_block_index"),
+                        Box::new(MixedHashTree::Leaf(last_block_index_encoded)),
                     ),
                 )))
             }
@@ -698,4 +711,59 @@ impl<Tokens: TokensType> Ledger<Tokens> {
             archived_blocks,
         }
     }
+
+    /// Returns the blocks in the specified ranges as defined by ICRC-3.
+    ///
+    /// The ledger returns at most [MAX_TRANSACTIONS_PER_REQUEST] blocks in
+    /// total. Ranges of archived blocks are grouped by archive.
+    pub fn icrc3_get_blocks(&self, args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+        let mut blocks = vec![];
+        let mut archived: BTreeMap<Principal, Vec<GetBlocksRequest>> = BTreeMap::new();
+
+        for arg in args {
+            // Blocks with an index that does not fit into u64 do not exist.
+            let start = match arg.start.0.to_u64() {
+                Some(start) => start,
+                None => continue,
+            };
+            let length = arg.length.0.to_usize().unwrap_or(usize::MAX);
+            let locations = block_locations(self, start, length);
+
+            let local_blocks_range = range_utils::take(
+                &locations.local_blocks,
+                MAX_TRANSACTIONS_PER_REQUEST.saturating_sub(blocks.len()),
+            );
+            blocks.extend(
+                local_blocks_range
+                    .clone()
+                    .zip(self.blockchain.block_slice(local_blocks_range).iter())
+                    .map(|(id, block)| BlockWithId {
+                        id: Nat::from(id),
+                        block: encoded_block_to_icrc3_block(block),
+                    }),
+            );
+
+            for (canister_id, slice) in locations.archived_blocks {
+                archived
+                    .entry(canister_id.get().0)
+                    .or_default()
+                    .push(GetBlocksRequest {
+                        start: Nat::from(slice.start),
+                        length: Nat::from(range_utils::range_len(&slice)),
+                    });
+            }
+        }
+
+        GetBlocksResult {
+            log_length: Nat::from(self.blockchain.chain_length()),
+            blocks,
+            archived_blocks: archived
+                .into_iter()
+                .map(|(canister_id, args)| ArchivedBlocks {
+                    args,
+                    callback: ICRC3QueryArchiveFn::new(canister_id, "icrc3_get_blocks"),
+                })
+                .collect(),
+        }
+    }
 }
@@ -17,12 +17,12 @@ use ic_ledger_core::tokens::Zero;
 use ic_ledger_core::{approvals::Approvals, timestamp::TimeStamp};
 use icrc_ledger_types::icrc1::transfer::Memo;
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
-use icrc_ledger_types::icrc3::blocks::DataCertificate;
+use icrc_ledger_types::icrc3::blocks::{DataCertificate, ICRC3DataCertificate};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
     icrc3::{
-        archive::ArchiveInfo,
-        blocks::{GetBlocksRequest, GetBlocksResponse},
+        archive::{ArchiveInfo, GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo},
+        blocks::{GetBlocksRequest, GetBlocksResponse, GetBlocksResult},
         transactions::{GetTransactionsRequest, GetTransactionsResponse},
     },
 };
@@ -116,6 +116,9 @@ fn post_upgrade(args: Option<LedgerArgument>) {
             }
         }
     }
+
+    // The layout of the certified hash tree may change between versions.
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
 }
 
 fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
@@ -467,6 +470,25 @@ fn archives() -> Vec<ArchiveInfo> {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
+    let archives = archives();
+    let first = args
+        .from
+        .and_then(|from| archives.iter().position(|a| a.canister_id == from))
+        .map_or(0, |pos| pos + 1);
+    archives
+        .into_iter()
+        .skip(first)
+        .map(|archive| ICRC3ArchiveInfo {
+            canister_id: archive.canister_id,
+            start: archive.block_range_start,
+            end: archive.block_range_end,
+        })
+        .collect()
+}
+
 #[query(name = "icrc1_supported_standards")]
 #[candid_method(query, rename = "icrc1_supported_standards")]
 fn supported_standards() -> Vec<StandardRecord> {
@@ -514,6 +536,25 @@ fn get_data_certificate() -> DataCertificate {
     }
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    Access::with_ledger(|ledger| ledger.icrc3_get_blocks(args))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = ByteBuf::from(ic_cdk::api::data_certificate()?);
+    let hash_tree = Access::with_ledger(|ledger| ledger.construct_hash_tree());
+    let mut tree_buf = vec![];
+    ciborium::ser::into_writer(&hash_tree, &mut tree_buf).unwrap();
+    Some(ICRC3DataCertificate {
+        certificate,
+        hash_tree: ByteBuf::from(tree_buf),
+    })
+}
+
 #[update]
 #[candid_method(update)]
 async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
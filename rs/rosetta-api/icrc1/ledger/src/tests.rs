@@ -55,6 +55,8 @@ fn default_init_args() -> InitArgs {
             controller_id: PrincipalId::new_user_test_id(100),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         },
         max_memo_length: None,
         feature_flags: None,
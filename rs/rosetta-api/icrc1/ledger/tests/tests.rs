@@ -85,6 +85,8 @@ fn encode_init_args(args: ic_icrc1_ledger_sm_tests::InitArgs) -> LedgerArgument
             controller_id: PrincipalId::new_user_test_id(100),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         },
         max_memo_length: None,
         feature_flags: args.feature_flags,
@@ -137,6 +139,81 @@ fn test_get_blocks() {
     ic_icrc1_ledger_sm_tests::test_get_blocks(ledger_wasm(), encode_init_args);
 }
 
+#[test]
+fn test_icrc3_get_blocks() {
+    ic_icrc1_ledger_sm_tests::test_icrc3_get_blocks(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc3_get_archives() {
+    ic_icrc1_ledger_sm_tests::test_icrc3_get_archives(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc3_get_tip_certificate() {
+    use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
+    use icrc_ledger_types::icrc3::blocks::{
+        GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate,
+    };
+
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+    let (env, canister_id) = ic_icrc1_ledger_sm_tests::setup(
+        ledger_wasm(),
+        encode_init_args,
+        vec![(Account::from(p1.0), 10_000_000)],
+    );
+
+    let get_tip_certificate = || {
+        Decode!(
+            &env.query(canister_id, "icrc3_get_tip_certificate", Encode!().unwrap())
+                .expect("failed to query icrc3_get_tip_certificate")
+                .bytes(),
+            Option<ICRC3DataCertificate>
+        )
+        .expect("failed to decode icrc3_get_tip_certificate response")
+        .expect("no certificate returned in a query call")
+    };
+
+    for expected_last_block_index in [0_u64, 1] {
+        if expected_last_block_index > 0 {
+            ic_icrc1_ledger_sm_tests::transfer(&env, canister_id, p1.0, p2.0, 10_000)
+                .expect("transfer failed");
+        }
+        let certificate = get_tip_certificate();
+        let hash_tree: MixedHashTree = ciborium::de::from_reader(certificate.hash_tree.as_slice())
+            .expect("failed to decode the hash tree");
+
+        let mut last_block_index = vec![];
+        leb128::write::unsigned(&mut last_block_index, expected_last_block_index).unwrap();
+        assert_eq!(
+            hash_tree.lookup(&[b"last_block_index"]),
+            LookupStatus::Found(&MixedHashTree::Leaf(last_block_index))
+        );
+
+        let blocks = Decode!(
+            &env.query(
+                canister_id,
+                "icrc3_get_blocks",
+                Encode!(&vec![GetBlocksRequest {
+                    start: Nat::from(expected_last_block_index),
+                    length: Nat::from(1_u64),
+                }])
+                .unwrap()
+            )
+            .expect("failed to query icrc3_get_blocks")
+            .bytes(),
+            GetBlocksResult
+        )
+        .unwrap()
+        .blocks;
+        assert_eq!(
+            hash_tree.lookup(&[b"last_block_hash"]),
+            LookupStatus::Found(&MixedHashTree::Leaf(blocks[0].block.hash().to_vec()))
+        );
+    }
+}
+
 // Generate random blocks and check that their CBOR encoding complies with the CDDL spec.
 #[test]
 fn block_encoding_agrees_with_the_schema() {
@@ -374,6 +451,8 @@ fn test_upgrade_from_first_version() {
             controller_id: PrincipalId::new_user_test_id(100),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         },
     })
     .unwrap();
@@ -411,13 +411,11 @@ async fn fetch_blockchain_tip_data(agent: Arc<Icrc1Agent>) -> anyhow::Result<(u6
     // Extract the last block index from the hash tree.
     let last_block_index = match hash_tree.lookup(&[b"last_block_index"]) {
         Found(x) => match x {
-            MixedHashTree::Leaf(l) => {
-                let mut bytes: [u8; 8] = [0u8; 8];
-                for (i, e) in l.iter().enumerate() {
-                    bytes[i] = *e;
-                }
-                Ok(u64::from_be_bytes(bytes))
-            }
+            MixedHashTree::Leaf(l) => Nat::decode(&mut l.as_slice())
+                .map_err(|err| anyhow::Error::msg(err.to_string()))?
+                .0
+                .to_u64()
+                .ok_or_else(|| anyhow::Error::msg("Last block index does not fit into u64")),
             _ => Err(anyhow::Error::msg(
                 "Last block index was found, but MixedHashTree is no a Leaf",
             )),
@@ -428,7 +426,7 @@ async fn fetch_blockchain_tip_data(agent: Arc<Icrc1Agent>) -> anyhow::Result<(u6
     }?;
 
     // Extract the last block hash from the hash tree.
-    let last_block_hash = match hash_tree.lookup(&[b"tip_hash"]) {
+    let last_block_hash = match hash_tree.lookup(&[b"last_block_hash"]) {
         Found(x) => match x {
             MixedHashTree::Leaf(l) => {
                 let mut bytes: Hash = [0u8; 32];
@@ -99,6 +99,8 @@ pub async fn deploy_icrc_ledger_with_default_args(context: &ReplicaContext) -> C
             controller_id: PrincipalId::new_user_test_id(100),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         })
         .with_metadata_entry(NAT_META_KEY, NAT_META_VALUE)
         .with_metadata_entry(INT_META_KEY, INT_META_VALUE)
@@ -76,6 +76,8 @@ proptest! {
                 controller_id: PrincipalId::new_user_test_id(100),
                 cycles_for_archive_creation: None,
                 max_transactions_per_response: None,
+                more_controller_ids: None,
+                archive_created_callback: None,
             })
             .build()
         ).await;
@@ -151,6 +153,8 @@ proptest! {
             controller_id: PrincipalId::new_user_test_id(100),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         })
         .build()
     ).await;
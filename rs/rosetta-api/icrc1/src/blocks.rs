@@ -5,7 +5,7 @@ use ciborium::value::{Integer, Value as CiboriumValue};
 use ic_ledger_core::block::{BlockType, EncodedBlock};
 use ic_ledger_core::tokens::TokensType;
 use icrc_ledger_types::icrc::generic_value::Value as GenericValue;
-use icrc_ledger_types::icrc3::blocks::GenericBlock;
+use icrc_ledger_types::icrc3::blocks::{GenericBlock, ICRC3GenericBlock};
 use icrc_ledger_types::icrc3::transactions::GenericTransaction;
 use num_traits::ToPrimitive;
 use serde_bytes::ByteBuf;
@@ -70,6 +70,12 @@ pub fn encoded_block_to_generic_block(encoded_block: &EncodedBlock) -> GenericBl
     icrc1_block_from_value(value, 0).expect("failed to decode encoded block")
 }
 
+/// Converts an encoded block into the generic block representation defined
+/// by ICRC-3. The hash of the resulting value equals the block hash.
+pub fn encoded_block_to_icrc3_block(encoded_block: &EncodedBlock) -> ICRC3GenericBlock {
+    ICRC3GenericBlock::from(encoded_block_to_generic_block(encoded_block))
+}
+
 #[derive(Debug, Error)]
 enum ValueDecodingError {
     #[error("CBOR value depth must not exceed {max_depth}")]
@@ -31,6 +31,22 @@ pub struct ArchiveOptions {
     // Max transactions returned by the [get_transactions] endpoint.
     #[serde(default)]
     pub max_transactions_per_response: Option<u64>,
+    // Controllers of the archive canisters in addition to [controller_id].
+    #[serde(default)]
+    pub more_controller_ids: Option<Vec<PrincipalId>>,
+    // Method to call with the id of each newly created archive canister.
+    #[serde(default)]
+    pub archive_created_callback: Option<ArchiveCreatedCallback>,
+}
+
+/// A canister method that the ledger calls every time it creates an archive
+/// canister, e.g., to let the canister that oversees the ledger track the
+/// archives without polling. The method takes the principal of the new
+/// archive as its only argument.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveCreatedCallback {
+    pub canister_id: CanisterId,
+    pub method: String,
 }
 
 /// A scope guard for block archiving.
@@ -89,6 +105,12 @@ pub struct Archive<Rt: Runtime, Wasm: ArchiveCanisterWasm> {
 
     controller_id: PrincipalId,
 
+    #[serde(default)]
+    more_controller_ids: Vec<PrincipalId>,
+
+    #[serde(default)]
+    archive_created_callback: Option<ArchiveCreatedCallback>,
+
     // BlockIndices of Blocks stored in each archive node.
 
     // We need this because Blocks are stored in encoded format as
@@ -140,6 +162,8 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Archive<Rt, Wasm> {
         Self {
             nodes: vec![],
             controller_id: options.controller_id,
+            more_controller_ids: options.more_controller_ids.unwrap_or_default(),
+            archive_created_callback: options.archive_created_callback,
             nodes_block_ranges: vec![],
             node_max_memory_size_bytes: options
                 .node_max_memory_size_bytes
@@ -170,6 +194,17 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Archive<Rt, Wasm> {
     pub fn nodes(&self) -> &[CanisterId] {
         &self.nodes
     }
+
+    /// Returns the controllers set on newly created archive canisters.
+    pub fn controllers(&self) -> Vec<PrincipalId> {
+        let mut controllers = vec![self.controller_id];
+        for controller in &self.more_controller_ids {
+            if !controllers.contains(controller) {
+                controllers.push(*controller);
+            }
+        }
+        controllers
+    }
 }
 
 /// Grabs a write lock on the archive and executes a synchronous function under the lock.
@@ -304,7 +339,7 @@ async fn create_and_initialize_node_canister<Rt: Runtime, Wasm: ArchiveCanisterW
         cycles_for_archive_creation,
         node_block_height_offset,
         node_max_memory_size_bytes,
-        controller_id,
+        controllers,
         max_transactions_per_response,
     ) = inspect_archive(archive, |archive| {
         let node_block_height_offset: u64 = archive
@@ -316,7 +351,7 @@ async fn create_and_initialize_node_canister<Rt: Runtime, Wasm: ArchiveCanisterW
             archive.cycles_for_archive_creation,
             node_block_height_offset,
             archive.node_max_memory_size_bytes,
-            archive.controller_id,
+            archive.controllers(),
             archive.max_transactions_per_response,
         )
     });
@@ -350,8 +385,8 @@ async fn create_and_initialize_node_canister<Rt: Runtime, Wasm: ArchiveCanisterW
 
     log!(
         log_sink,
-        "[archive] setting controller_id for archive node: {}",
-        controller_id
+        "[archive] setting controllers for archive node: {:?}",
+        controllers
     );
 
     let res: Result<(), (i32, String)> = Rt::call(
@@ -361,7 +396,7 @@ async fn create_and_initialize_node_canister<Rt: Runtime, Wasm: ArchiveCanisterW
         (ic_ic00_types::UpdateSettingsArgs::new(
             node_canister_id,
             ic_ic00_types::CanisterSettingsArgsBuilder::new()
-                .with_controllers(vec![controller_id])
+                .with_controllers(controllers)
                 .build(),
         ),),
     )
@@ -375,11 +410,36 @@ async fn create_and_initialize_node_canister<Rt: Runtime, Wasm: ArchiveCanisterW
         FailedToArchiveBlocks(s)
     })?;
 
-    let node_index = inspect_archive(archive, |archive| {
+    let (node_index, archive_created_callback) = inspect_archive(archive, |archive| {
         archive.nodes.push(node_canister_id);
-        archive.last_node_index()
+        (
+            archive.last_node_index(),
+            archive.archive_created_callback.clone(),
+        )
     });
 
+    if let Some(callback) = archive_created_callback {
+        // The archive is already registered, so a failed notification must
+        // not stop the archiving. The callee can still poll the archives.
+        let res: Result<(), (i32, String)> = Rt::call(
+            callback.canister_id,
+            &callback.method,
+            0,
+            (node_canister_id.get(),),
+        )
+        .await;
+        if let Err((code, msg)) = res {
+            log!(
+                log_sink,
+                "[archive] failed to notify {} about archive node {}: code {}: {}",
+                callback.canister_id,
+                node_canister_id,
+                code,
+                msg
+            );
+        }
+    }
+
     let (remaining_capacity,): (u64,) = Rt::call(node_canister_id, "remaining_capacity", 0, ())
         .await
         .map_err(|(_, msg)| FailedToArchiveBlocks(msg))?;
@@ -24,8 +24,8 @@ pub struct Blockchain<Rt: Runtime, Wasm: ArchiveCanisterWasm> {
 
     /// This `Arc` is safe to (de)serialize because uniqueness is guaranteed
     /// by the canister upgrade procedure.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub archive: Arc<RwLock<Option<Archive<Rt, Wasm>>>>,
 
     /// How many blocks have been sent to the archive
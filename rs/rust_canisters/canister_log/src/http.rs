@@ -0,0 +1,170 @@
+use crate::{GlobalBuffer, LogEntry, Severity};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// 1 Mi. Keeps the response well below the maximum size of a query response.
+const MAX_LOGS_RESPONSE_SIZE: usize = 1 << 20;
+
+/// The order of the entries in the response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Sort {
+    Ascending,
+    Descending,
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "asc" => Ok(Self::Ascending),
+            "desc" => Ok(Self::Descending),
+            _ => Err(format!("Unknown sort order: {}", s)),
+        }
+    }
+}
+
+/// Renders the entries of the given buffers as the JSON body of the response
+/// to an HTTP request for `url`, e.g. `/logs?severity=Info&time=0`.
+///
+/// Supported query parameters (last occurrence wins):
+///   severity:
+///     possible values: Trace, Debug, Info, Warn, Error. Default: all.
+///     meaning: Selects entries of the same or greater severity.
+///   time:
+///     value: integer nanoseconds since UNIX epoch. Default: 0.
+///     meaning: Selects entries that are at least as recent as this.
+///   sort:
+///     possible values: asc (default), desc.
+///     meaning: Orders the entries chronologically or newest first.
+///
+/// The response looks like this:
+///   {
+///     "entries": [
+///       {
+///         "timestamp": nanoseconds_since_unix_epoch,
+///         "counter": 42,
+///         "message": "Hello, world!",
+///         "severity": "Info",
+///         "module": "my_canister::state",
+///         "file": "src/state.rs",
+///         "line": 17
+///       },
+///       // Etc.
+///     ]
+///   }
+///
+/// If the response would exceed 1 MiB, the entries that come last in the
+/// requested order are omitted; clients can fetch them by setting `time` to
+/// the timestamp of the last entry they received.
+///
+/// Returns a description of the problem if the query parameters are invalid.
+///
+/// ```
+/// use ic_canister_log::{declare_log_buffer, export_logs_http, log};
+///
+/// declare_log_buffer!(name = INFO, capacity = 100);
+/// declare_log_buffer!(name = ERROR, capacity = 100, severity = Error);
+///
+/// log!(INFO, "Hello, {}!", "world");
+/// log!(ERROR, "Oops");
+///
+/// let body = export_logs_http("/logs?severity=Error", &[&INFO, &ERROR]).unwrap();
+/// assert!(body.contains("Oops"));
+/// assert!(!body.contains("Hello"));
+///
+/// assert!(export_logs_http("/logs?time=yesterday", &[&INFO, &ERROR]).is_err());
+/// ```
+pub fn export_logs_http(url: &str, buffers: &[&'static GlobalBuffer]) -> Result<String, String> {
+    let query = query_parameters_map(url);
+
+    let mut defects = vec![];
+    let min_severity = match query.get("severity").map(|v| Severity::from_str(v)) {
+        None => Severity::Trace,
+        Some(Ok(severity)) => severity,
+        Some(Err(err)) => {
+            defects.push(format!(
+                "Invalid value for query parameter `severity`: {}",
+                err
+            ));
+            Severity::Trace
+        }
+    };
+    let time = match query.get("time").map(|v| u64::from_str(v)) {
+        None => 0,
+        Some(Ok(time)) => time,
+        Some(Err(err)) => {
+            defects.push(format!("Invalid value for query parameter `time`: {}", err));
+            0
+        }
+    };
+    let sort = match query.get("sort").map(|v| Sort::from_str(v)) {
+        None => Sort::Ascending,
+        Some(Ok(sort)) => sort,
+        Some(Err(err)) => {
+            defects.push(format!("Invalid value for query parameter `sort`: {}", err));
+            Sort::Ascending
+        }
+    };
+    if !defects.is_empty() {
+        return Err(format!(
+            "Invalid request for the following reason(s):\n  -{}",
+            defects.join("\n  -"),
+        ));
+    }
+
+    let mut entries: Vec<LogEntry> = vec![];
+    for buffer in buffers {
+        buffer.with(|cell| {
+            let buffer = cell.borrow();
+            if buffer.severity() >= min_severity {
+                entries.extend(
+                    buffer
+                        .entries_partition_point(|entry| entry.timestamp < time)
+                        .cloned(),
+                );
+            }
+        });
+    }
+    // Entries with equal timestamps are ordered by their counter.
+    entries.sort_by_key(|entry| (entry.timestamp, entry.counter));
+    if sort == Sort::Descending {
+        entries.reverse();
+    }
+
+    Ok(render_json(&entries, MAX_LOGS_RESPONSE_SIZE))
+}
+
+/// Renders `{"entries": [...]}`, with as many of the entries as fit in
+/// `max_size` bytes.
+fn render_json(entries: &[LogEntry], max_size: usize) -> String {
+    const PREFIX: &str = "{\"entries\":[";
+    const SUFFIX: &str = "]}";
+
+    let mut body = String::from(PREFIX);
+    for entry in entries {
+        let entry = serde_json::to_string(entry).expect("failed to serialize a log entry");
+        let separator = if body.len() > PREFIX.len() { "," } else { "" };
+        if body.len() + separator.len() + entry.len() + SUFFIX.len() > max_size {
+            break;
+        }
+        body.push_str(separator);
+        body.push_str(&entry);
+    }
+    body.push_str(SUFFIX);
+    body
+}
+
+/// The "right" way to implement this is to use the url crate, but that causes
+/// our WASMs to be inordinately larger.
+fn query_parameters_map(url: &str) -> HashMap<&str, &str> {
+    let query_string = match url.split_once('?') {
+        Some((_path, query_string)) => query_string,
+        None => return HashMap::new(),
+    };
+    query_string
+        .split('&')
+        .filter(|chunk| !chunk.is_empty())
+        .map(|chunk| chunk.split_once('=').unwrap_or((chunk, "")))
+        .collect()
+}
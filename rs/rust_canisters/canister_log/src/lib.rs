@@ -1,15 +1,53 @@
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::fmt;
+use std::str::FromStr;
 use std::thread::LocalKey;
 
+mod http;
+
+pub use http::export_logs_http;
+
 /// Declares a new canister log buffer.
+///
+/// The buffer's entries have severity `Info` unless specified otherwise, and
+/// the number of entries the buffer accepts per second can be limited:
+///
+/// ```
+/// use ic_canister_log::declare_log_buffer;
+///
+/// declare_log_buffer!(name = INFO, capacity = 100);
+/// declare_log_buffer!(name = DEBUG, capacity = 100, severity = Debug);
+/// // Keep a hot error loop from evicting all other entries.
+/// declare_log_buffer!(name = ERROR, capacity = 100, severity = Error, max_entries_per_second = 10);
+/// ```
 #[macro_export]
 macro_rules! declare_log_buffer {
     (name = $name:ident, capacity = $cap:expr) => {
+        $crate::declare_log_buffer!(name = $name, capacity = $cap, severity = Info);
+    };
+    (name = $name:ident, capacity = $cap:expr, severity = $severity:ident) => {
+        thread_local! {
+            pub static $name: std::cell::RefCell<$crate::LogBuffer> =
+                std::cell::RefCell::new(
+                    $crate::LogBuffer::with_capacity($cap)
+                        .with_severity($crate::Severity::$severity)
+                );
+        }
+    };
+    (
+        name = $name:ident,
+        capacity = $cap:expr,
+        severity = $severity:ident,
+        max_entries_per_second = $max_entries_per_second:expr
+    ) => {
         thread_local! {
             pub static $name: std::cell::RefCell<$crate::LogBuffer> =
-                std::cell::RefCell::new($crate::LogBuffer::with_capacity($cap));
+                std::cell::RefCell::new(
+                    $crate::LogBuffer::with_capacity($cap)
+                        .with_severity($crate::Severity::$severity)
+                        .with_max_entries_per_second($max_entries_per_second)
+                );
         }
     };
 }
@@ -56,6 +94,8 @@ macro_rules! log {
         (&$sink).append($crate::LogEntry {
             timestamp: $crate::now(),
             message,
+            severity: (&$sink).severity(),
+            module: std::module_path!(),
             file: std::file!(),
             line: std::line!(),
             counter: $crate::entry_counter::increment()
@@ -65,6 +105,52 @@ macro_rules! log {
 
 pub trait Sink {
     fn append(&self, entry: LogEntry);
+
+    /// The severity of the entries appended to this sink.
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+}
+
+/// The severity of a log entry, from the least to the most severe.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Severity {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Trace => "Trace",
+            Self::Debug => "Debug",
+            Self::Info => "Info",
+            Self::Warn => "Warn",
+            Self::Error => "Error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    /// Parses a severity name, ignoring the case.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("Unknown log severity name: {}", name)),
+        }
+    }
 }
 
 /// An entry in the canister log.
@@ -74,6 +160,9 @@ pub struct LogEntry {
     // The index of this entry starting from the last canister upgrade.
     pub counter: u64,
     pub message: String,
+    pub severity: Severity,
+    // The path of the module that logged this entry, e.g. `my_canister::state`.
+    pub module: &'static str,
     pub file: &'static str,
     pub line: u32,
 }
@@ -82,8 +171,8 @@ impl fmt::Display for LogEntry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "[{}] {}:{} {}",
-            self.timestamp, self.file, self.line, self.message
+            "[{}] {} {}:{} {}",
+            self.timestamp, self.severity, self.file, self.line, self.message
         )
     }
 }
@@ -95,10 +184,19 @@ impl Sink for DevNull {
     fn append(&self, _: LogEntry) {}
 }
 
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
 /// A circular buffer for log messages.
 pub struct LogBuffer {
     max_capacity: usize,
     entries: VecDeque<LogEntry>,
+    severity: Severity,
+    max_entries_per_second: Option<u64>,
+    // The second (since the Unix epoch) of the most recent entry, and the
+    // number of entries accepted during that second.
+    current_second: u64,
+    entries_in_current_second: u64,
+    dropped_entries: u64,
 }
 
 impl LogBuffer {
@@ -107,17 +205,71 @@ impl LogBuffer {
         Self {
             max_capacity,
             entries: VecDeque::with_capacity(max_capacity),
+            severity: Severity::Info,
+            max_entries_per_second: None,
+            current_second: 0,
+            entries_in_current_second: 0,
+            dropped_entries: 0,
         }
     }
 
+    /// Sets the severity of the entries logged to this buffer.
+    pub fn with_severity(self, severity: Severity) -> Self {
+        Self { severity, ..self }
+    }
+
+    /// Limits the number of entries the buffer accepts per second (according
+    /// to the entries' timestamps). Entries over the limit are dropped and
+    /// counted in [`LogBuffer::dropped_entries`].
+    pub fn with_max_entries_per_second(self, max_entries_per_second: u64) -> Self {
+        Self {
+            max_entries_per_second: Some(max_entries_per_second),
+            ..self
+        }
+    }
+
+    /// Returns the severity of the entries logged to this buffer.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the number of entries dropped because of the rate limit.
+    pub fn dropped_entries(&self) -> u64 {
+        self.dropped_entries
+    }
+
     /// Adds a new entry to the buffer, potentially evicting older entries.
+    /// Drops the entry if the buffer's rate limit is exceeded.
     pub fn append(&mut self, entry: LogEntry) {
+        if self.is_rate_limited(entry.timestamp) {
+            self.dropped_entries += 1;
+            return;
+        }
         if self.entries.len() >= self.max_capacity {
             self.entries.pop_front();
         }
         self.entries.push_back(entry);
     }
 
+    /// Returns true if an entry with the given timestamp exceeds the rate
+    /// limit, and otherwise counts it towards the limit.
+    fn is_rate_limited(&mut self, timestamp: u64) -> bool {
+        let max_entries_per_second = match self.max_entries_per_second {
+            Some(max_entries_per_second) => max_entries_per_second,
+            None => return false,
+        };
+        let second = timestamp / NANOS_PER_SECOND;
+        if second != self.current_second {
+            self.current_second = second;
+            self.entries_in_current_second = 0;
+        }
+        if self.entries_in_current_second >= max_entries_per_second {
+            return true;
+        }
+        self.entries_in_current_second += 1;
+        false
+    }
+
     /// Returns an iterator over entries in the order of their insertion.
     pub fn iter(&self) -> impl Iterator<Item = &LogEntry> {
         self.entries.iter()
@@ -158,6 +310,10 @@ impl Sink for &'static GlobalBuffer {
     fn append(&self, entry: LogEntry) {
         self.with(|cell| cell.borrow_mut().append(entry))
     }
+
+    fn severity(&self) -> Severity {
+        self.with(|cell| cell.borrow().severity())
+    }
 }
 
 mod private {
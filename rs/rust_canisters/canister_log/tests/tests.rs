@@ -1,4 +1,4 @@
-use ic_canister_log::{declare_log_buffer, export, log};
+use ic_canister_log::{declare_log_buffer, export, export_logs_http, log, Severity};
 
 mod buf_mod {
     use ic_canister_log::declare_log_buffer;
@@ -62,3 +62,125 @@ fn test_log_rotation() {
     assert_eq!(entries[0].message, "entry 2");
     assert_eq!(entries[1].message, "entry 3");
 }
+
+declare_log_buffer!(name = DEBUG, capacity = 100, severity = Debug);
+
+#[test]
+fn test_entries_record_severity_and_module() {
+    log!(DEBUG, "debug message");
+    log!(INFO, "info message");
+
+    let debug = export(&DEBUG);
+    assert_eq!(debug[0].severity, Severity::Debug);
+    assert_eq!(debug[0].module, module_path!());
+
+    let info = export(&INFO);
+    assert_eq!(info.last().unwrap().severity, Severity::Info);
+}
+
+declare_log_buffer!(
+    name = RATE_LIMITED,
+    capacity = 100,
+    severity = Error,
+    max_entries_per_second = 3
+);
+
+#[test]
+fn test_rate_limit() {
+    for i in 0..10 {
+        log!(RATE_LIMITED, "entry {}", i);
+    }
+
+    let entries = export(&RATE_LIMITED);
+    let dropped = RATE_LIMITED.with(|cell| cell.borrow().dropped_entries());
+
+    // All entries may not have been logged within the same second.
+    assert!(entries.len() >= 3, "{:?}", entries);
+    assert_eq!(entries.len() as u64 + dropped, 10);
+    assert_eq!(entries[0].message, "entry 0");
+    assert_eq!(entries[2].message, "entry 2");
+}
+
+#[derive(serde::Deserialize)]
+struct LogsResponseBody {
+    entries: Vec<JsonLogEntry>,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonLogEntry {
+    timestamp: u64,
+    severity: Severity,
+    message: String,
+}
+
+fn export_http(url: &str) -> Vec<JsonLogEntry> {
+    let body = export_logs_http(url, &[&http_buf::INFO, &http_buf::WARN]).unwrap();
+    serde_json::from_str::<LogsResponseBody>(&body)
+        .unwrap()
+        .entries
+}
+
+mod http_buf {
+    use ic_canister_log::declare_log_buffer;
+
+    declare_log_buffer!(name = INFO, capacity = 100);
+    declare_log_buffer!(name = WARN, capacity = 100, severity = Warn);
+}
+
+#[test]
+fn test_export_logs_http() {
+    log!(http_buf::INFO, "first");
+    log!(http_buf::WARN, "second");
+    log!(http_buf::INFO, "third");
+
+    let messages = |entries: Vec<JsonLogEntry>| -> Vec<String> {
+        entries.into_iter().map(|entry| entry.message).collect()
+    };
+
+    let entries = export_http("/logs");
+    assert_eq!(entries[1].severity, Severity::Warn);
+    assert_eq!(messages(entries), vec!["first", "second", "third"]);
+
+    assert_eq!(
+        messages(export_http("/logs?sort=desc")),
+        vec!["third", "second", "first"]
+    );
+    assert_eq!(messages(export_http("/logs?severity=warn")), vec!["second"]);
+
+    let first_timestamp = export_http("/logs")[0].timestamp;
+    let last_timestamp = export_http("/logs")[2].timestamp;
+    assert_eq!(
+        export_http(&format!("/logs?time={}", first_timestamp)).len(),
+        3
+    );
+    assert!(export_http(&format!("/logs?time={}", last_timestamp + 1)).is_empty());
+
+    let err = export_logs_http("/logs?severity=loud&sort=up", &[&http_buf::INFO]).unwrap_err();
+    assert!(err.contains("severity"), "{}", err);
+    assert!(err.contains("sort"), "{}", err);
+}
+
+declare_log_buffer!(name = LARGE, capacity = 10);
+
+#[test]
+fn test_export_logs_http_truncates_large_responses() {
+    for i in 0..5 {
+        log!(LARGE, "{}{}", i, "x".repeat(300_000));
+    }
+
+    let body = export_logs_http("/logs", &[&LARGE]).unwrap();
+    assert!(body.len() <= 1 << 20, "{}", body.len());
+    let entries = serde_json::from_str::<LogsResponseBody>(&body)
+        .unwrap()
+        .entries;
+    assert_eq!(entries.len(), 3);
+    assert!(entries[0].message.starts_with('0'));
+
+    // Newest entries come first in descending order.
+    let body = export_logs_http("/logs?sort=desc", &[&LARGE]).unwrap();
+    let entries = serde_json::from_str::<LogsResponseBody>(&body)
+        .unwrap()
+        .entries;
+    assert_eq!(entries.len(), 3);
+    assert!(entries[0].message.starts_with('4'));
+}
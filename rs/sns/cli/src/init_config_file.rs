@@ -515,6 +515,7 @@ impl TryFrom<SnsCliInitConfig> for SnsInitPayload {
             neurons_fund_participants: None,
             token_logo: None,
             neurons_fund_participation_constraints: None,
+            participation_eligibility_canister_id: None,
         })
     }
 }
@@ -1241,6 +1242,7 @@ wait_for_quiet_deadline_increase_seconds: 1000
             neurons_fund_participants: _,
             token_logo: _,
             neurons_fund_participation_constraints: _,
+            participation_eligibility_canister_id: _,
         } = sns_init_payload;
 
         assert_eq!(
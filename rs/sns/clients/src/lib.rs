@@ -0,0 +1,207 @@
+//! The plumbing of the inter-canister calls made by SNS canisters.
+//!
+//! Typed clients (e.g. the SNS root client used by the swap canister) are
+//! thin wrappers around a [`CanisterClient`], which sends Candid-encoded
+//! bytes to other canisters. Production code uses [`DfnCanisterClient`],
+//! while tests can substitute a [`MockCanisterClient`] to inspect the calls
+//! and script the replies, without a mock per typed client.
+//!
+//! This crate only provides the untyped plumbing. The typed clients stay next
+//! to the types they exchange (e.g. `ic_sns_swap::clients`), since those types
+//! are defined by the canister crates themselves, and `ic-sns-root` depends on
+//! `ic-sns-swap`, which depends on this crate. The management canister client
+//! is shared through `ic_nervous_system_clients` already.
+
+use async_trait::async_trait;
+use candid::{CandidType, Decode, Encode};
+use ic_base_types::CanisterId;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// The reject code (if any) and the description of a failed call.
+pub type CallError = (Option<i32>, String);
+
+#[async_trait]
+pub trait CanisterClient: Send {
+    /// Calls `method` of `canister_id` with the Candid-encoded `args`, and
+    /// returns the Candid-encoded reply.
+    async fn call_bytes(
+        &mut self,
+        canister_id: CanisterId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, CallError>;
+
+    /// Calls `method` of `canister_id` with `request` as its sole argument,
+    /// and decodes the (single) value of the reply.
+    async fn call<Request, Response>(
+        &mut self,
+        canister_id: CanisterId,
+        method: &str,
+        request: Request,
+    ) -> Result<Response, CallError>
+    where
+        Self: Sized,
+        Request: CandidType + Send,
+        Response: CandidType + DeserializeOwned + Send,
+    {
+        let args = Encode!(&request).map_err(|err| {
+            (
+                None,
+                format!("Unable to encode the argument of {method}: {err}"),
+            )
+        })?;
+        let reply = self.call_bytes(canister_id, method, args).await?;
+        Decode!(&reply, Response).map_err(|err| {
+            (
+                None,
+                format!("Unable to decode the reply of {method}: {err}"),
+            )
+        })
+    }
+}
+
+/// Makes actual inter-canister calls, using `dfn_core`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DfnCanisterClient;
+
+#[async_trait]
+impl CanisterClient for DfnCanisterClient {
+    async fn call_bytes(
+        &mut self,
+        canister_id: CanisterId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, CallError> {
+        dfn_core::api::call_bytes(canister_id, method, &args, dfn_core::api::Funds::zero()).await
+    }
+}
+
+/// A call recorded by a [`MockCanisterClient`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockCall {
+    pub canister_id: CanisterId,
+    pub method: String,
+    pub args: Vec<u8>,
+}
+
+impl MockCall {
+    /// Decodes the (single) argument of the call.
+    pub fn decode_args<Request>(&self) -> Request
+    where
+        Request: CandidType + DeserializeOwned,
+    {
+        Decode!(&self.args, Request).expect("Unable to decode the argument of the call")
+    }
+}
+
+/// A CanisterClient for tests. It records the calls that are made, and
+/// answers them with the replies that were pushed, in order. Clones share
+/// the same calls and replies, so a test can keep a clone to inspect the
+/// calls made through a typed client that owns the original.
+#[derive(Clone, Debug, Default)]
+pub struct MockCanisterClient {
+    calls: Arc<Mutex<Vec<MockCall>>>,
+    replies: Arc<Mutex<VecDeque<Result<Vec<u8>, CallError>>>>,
+}
+
+impl MockCanisterClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_calls_snapshot(&self) -> Vec<MockCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    pub fn assert_all_replies_consumed(&self) {
+        let replies = self.replies.lock().unwrap();
+        assert!(
+            replies.is_empty(),
+            "{} replies were not consumed",
+            replies.len()
+        );
+    }
+
+    /// Queues the reply to the next call that is not answered yet.
+    pub fn push_reply<Response: CandidType>(&mut self, reply: Result<Response, CallError>) {
+        let reply = reply.map(|response| Encode!(&response).expect("Unable to encode the reply"));
+        self.replies.lock().unwrap().push_back(reply);
+    }
+}
+
+#[async_trait]
+impl CanisterClient for MockCanisterClient {
+    async fn call_bytes(
+        &mut self,
+        canister_id: CanisterId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, CallError> {
+        self.calls.lock().unwrap().push(MockCall {
+            canister_id,
+            method: method.to_string(),
+            args,
+        });
+        let reply = self.replies.lock().unwrap().pop_front();
+        reply.unwrap_or_else(|| {
+            panic!("Expected there to be a reply to the call of {method} on {canister_id}")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn test_mock_canister_client_records_calls_and_replies_in_order() {
+        let canister_id = CanisterId::from_u64(42);
+        let mut client = MockCanisterClient::new();
+        client.push_reply(Ok(7_u64));
+        client.push_reply::<u64>(Err((Some(4), "Canister rejected the call".to_string())));
+
+        let first: Result<u64, CallError> = client
+            .call(canister_id, "increment", 6_u64)
+            .now_or_never()
+            .unwrap();
+        assert_eq!(first, Ok(7));
+        let second: Result<u64, CallError> = client
+            .call(canister_id, "increment", 7_u64)
+            .now_or_never()
+            .unwrap();
+        assert_eq!(
+            second,
+            Err((Some(4), "Canister rejected the call".to_string()))
+        );
+
+        client.assert_all_replies_consumed();
+        let calls = client.get_calls_snapshot();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].canister_id, canister_id);
+        assert_eq!(calls[0].method, "increment");
+        assert_eq!(calls[0].decode_args::<u64>(), 6);
+        assert_eq!(calls[1].decode_args::<u64>(), 7);
+    }
+
+    #[test]
+    fn test_call_reports_undecodable_replies() {
+        let mut client = MockCanisterClient::new();
+        client.push_reply(Ok("not a number".to_string()));
+
+        let result: Result<u64, CallError> = client
+            .call(CanisterId::from_u64(42), "increment", 6_u64)
+            .now_or_never()
+            .unwrap();
+        let (code, description) = result.unwrap_err();
+        assert_eq!(code, None);
+        assert!(
+            description.contains("Unable to decode the reply of increment"),
+            "{description}"
+        );
+    }
+}
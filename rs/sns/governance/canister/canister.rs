@@ -39,14 +39,15 @@ use ic_sns_governance::{
     logs::{ERROR, INFO},
     pb::v1::{
         governance, ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse,
-        FailStuckUpgradeInProgressRequest, FailStuckUpgradeInProgressResponse,
-        GetMaturityModulationRequest, GetMaturityModulationResponse, GetMetadataRequest,
-        GetMetadataResponse, GetMode, GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal,
-        GetProposalResponse, GetRunningSnsVersionRequest, GetRunningSnsVersionResponse,
-        GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
-        Governance as GovernanceProto, ListNervousSystemFunctionsResponse, ListNeurons,
-        ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
-        ManageNeuronResponse, NervousSystemParameters, RewardEvent, SetMode, SetModeResponse,
+        FailStuckUpgradeInProgressRequest, FailStuckUpgradeInProgressResponse, GetFollowers,
+        GetFollowersResponse, GetMaturityModulationRequest, GetMaturityModulationResponse,
+        GetMetadataRequest, GetMetadataResponse, GetMode, GetModeResponse, GetNeuron,
+        GetNeuronResponse, GetProposal, GetProposalResponse, GetRunningSnsVersionRequest,
+        GetRunningSnsVersionResponse, GetSnsInitializationParametersRequest,
+        GetSnsInitializationParametersResponse, Governance as GovernanceProto,
+        ListNervousSystemFunctionsResponse, ListNeurons, ListNeuronsResponse, ListProposals,
+        ListProposalsResponse, ManageNeuron, ManageNeuronResponse, NervousSystemParameters,
+        RewardEvent, SetMode, SetModeResponse,
     },
     types::{Environment, HeapGrowthPotential},
 };
@@ -397,6 +398,24 @@ fn get_neuron_(get_neuron: GetNeuron) -> GetNeuronResponse {
     governance().get_neuron(get_neuron)
 }
 
+/// Returns the IDs of the neurons that directly follow the neuron with ID
+/// `neuron_id` on the nervous system function `function_id`, paginated in the
+/// same way as `list_neurons`.
+///
+/// The followers are read from the followee index that governance maintains
+/// incrementally, so the cost does not depend on the total number of neurons.
+#[export_name = "canister_query get_followers"]
+fn get_followers() {
+    log!(INFO, "get_followers");
+    over(candid_one, get_followers_)
+}
+
+/// Internal method for calling get_followers.
+#[candid_method(query, rename = "get_followers")]
+fn get_followers_(get_followers: GetFollowers) -> GetFollowersResponse {
+    governance().get_followers(&get_followers)
+}
+
 /// Returns a list of neurons of size `limit` using `start_page_at` to
 /// indicate the start of the list. Specifying `of_principal` will return
 /// Neurons of which the given PrincipalId has permissions.
@@ -1756,6 +1756,11 @@ pub mod manage_neuron {
     /// a percentage of the current maturity to disburse to the ledger account. The
     /// resulting amount to disburse must be greater than or equal to the
     /// transaction fee.
+    ///
+    /// Optionally, the disbursement can be vested, i.e., split into
+    /// `number_of_tranches` equal tranches that are spread evenly over
+    /// `vesting_period_seconds`. In that case, each tranche must be greater than
+    /// or equal to the transaction fee.
     #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1766,6 +1771,17 @@ pub mod manage_neuron {
         /// The (optional) principal to which to transfer the stake.
         #[prost(message, optional, tag = "2")]
         pub to_account: ::core::option::Option<super::Account>,
+        /// The number of tranches in which the maturity is disbursed, from 1 to
+        /// MAX_DISBURSE_MATURITY_TRANCHES. If not set, a single tranche is used.
+        #[prost(uint32, optional, tag = "3")]
+        pub number_of_tranches: ::core::option::Option<u32>,
+        /// The period over which the tranches are spread. The i-th tranche
+        /// (counting from 1) is scheduled at i * vesting_period_seconds /
+        /// number_of_tranches after the request, and is disbursed once the usual
+        /// disbursement delay has passed from that point on. Must be positive if
+        /// there is more than one tranche. If not set, 0 is used.
+        #[prost(uint64, optional, tag = "4")]
+        pub vesting_period_seconds: ::core::option::Option<u64>,
     }
     #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
     #[allow(clippy::derive_partial_eq_without_eq)]
@@ -2234,6 +2250,40 @@ pub struct ListNeuronsResponse {
     #[prost(message, repeated, tag = "1")]
     pub neurons: ::prost::alloc::vec::Vec<Neuron>,
 }
+/// An operation that lists the neurons that directly follow a given neuron
+/// on a given nervous system function, in a paginated fashion.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFollowers {
+    /// The followee whose followers are listed.
+    #[prost(message, optional, tag = "1")]
+    pub neuron_id: ::core::option::Option<NeuronId>,
+    /// The id of the nervous system function on which the followers follow
+    /// `neuron_id`. The id 0 (i.e., Action::Unspecified) lists the catch-all
+    /// followers, which follow `neuron_id` on all functions for which they have
+    /// no specific followees.
+    #[prost(uint64, tag = "2")]
+    pub function_id: u64,
+    /// Limit the number of followers returned in each page, from 1 to 100.
+    /// If a value outside of this range is provided, 100 will be used.
+    #[prost(uint32, tag = "3")]
+    pub limit: u32,
+    /// Used to indicate where the next page of followers should start. Should be
+    /// set to the last follower of the previously returned page and will not be
+    /// included in the next page. Followers are returned ordered by neuron ID.
+    #[prost(message, optional, tag = "4")]
+    pub start_page_at: ::core::option::Option<NeuronId>,
+}
+/// A response to the GetFollowers command.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFollowersResponse {
+    /// The IDs of the returned followers.
+    #[prost(message, repeated, tag = "1")]
+    pub follower_neuron_ids: ::prost::alloc::vec::Vec<NeuronId>,
+}
 /// The response to the list_nervous_system_functions query.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -43,18 +43,18 @@ use crate::{
             ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus, DefaultFollowees,
             DeregisterDappCanisters, DisburseMaturityInProgress, Empty,
             ExecuteGenericNervousSystemFunction, FailStuckUpgradeInProgressRequest,
-            FailStuckUpgradeInProgressResponse, GetMaturityModulationRequest,
-            GetMaturityModulationResponse, GetMetadataRequest, GetMetadataResponse, GetMode,
-            GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal, GetProposalResponse,
-            GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
-            Governance as GovernanceProto, GovernanceError, ListNervousSystemFunctionsResponse,
-            ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
-            ManageNeuronResponse, ManageSnsMetadata, NervousSystemFunction,
-            NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-            NeuronPermissionType, Proposal, ProposalData, ProposalDecisionStatus, ProposalId,
-            ProposalRewardStatus, RegisterDappCanisters, RewardEvent, Tally,
-            TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
-            VotingRewardsParameters, WaitForQuietState,
+            FailStuckUpgradeInProgressResponse, GetFollowers, GetFollowersResponse,
+            GetMaturityModulationRequest, GetMaturityModulationResponse, GetMetadataRequest,
+            GetMetadataResponse, GetMode, GetModeResponse, GetNeuron, GetNeuronResponse,
+            GetProposal, GetProposalResponse, GetSnsInitializationParametersRequest,
+            GetSnsInitializationParametersResponse, Governance as GovernanceProto, GovernanceError,
+            ListNervousSystemFunctionsResponse, ListNeurons, ListNeuronsResponse, ListProposals,
+            ListProposalsResponse, ManageNeuron, ManageNeuronResponse, ManageSnsMetadata,
+            NervousSystemFunction, NervousSystemParameters, Neuron, NeuronId, NeuronPermission,
+            NeuronPermissionList, NeuronPermissionType, Proposal, ProposalData,
+            ProposalDecisionStatus, ProposalId, ProposalRewardStatus, RegisterDappCanisters,
+            RewardEvent, Tally, TransferSnsTreasuryFunds, UpgradeSnsControlledCanister,
+            UpgradeSnsToNextVersion, Vote, VotingRewardsParameters, WaitForQuietState,
         },
     },
     proposal::{
@@ -65,7 +65,10 @@ use crate::{
         get_all_sns_canisters, get_running_version, get_upgrade_params, get_wasm, SnsCanisterType,
         UpgradeSnsParams,
     },
-    types::{is_registered_function_id, Environment, HeapGrowthPotential, LedgerUpdateLock},
+    types::{
+        is_registered_function_id, Environment, HeapGrowthPotential, LedgerUpdateLock,
+        ONE_YEAR_SECONDS,
+    },
 };
 use candid::{Decode, Encode};
 use dfn_core::api::{spawn, CanisterId};
@@ -127,6 +130,16 @@ const WASM32_PAGE_SIZE_IN_KIB: usize = 64;
 pub const ONE_DAY_SECONDS: u64 = 24 * 60 * 60;
 const SEVEN_DAYS_IN_SECONDS: u64 = 7 * 24 * 3600;
 
+/// The maximum number of tranches in which a single maturity disbursement can be vested.
+pub const MAX_DISBURSE_MATURITY_TRANCHES: u32 = 48;
+
+/// The maximum period over which a single maturity disbursement can be vested.
+pub const MAX_DISBURSE_MATURITY_VESTING_PERIOD_SECONDS: u64 = 4 * ONE_YEAR_SECONDS;
+
+/// The maximum number of maturity disbursements (i.e., tranches) that a neuron
+/// can have in progress at the same time.
+pub const MAX_NEURON_DISBURSEMENTS_IN_PROGRESS: usize = 100;
+
 /// The max number of wasm32 pages for the heap after which we consider that there
 /// is a risk to the ability to grow the heap.
 ///
@@ -1053,6 +1066,37 @@ impl Governance {
         }
     }
 
+    /// Lists the neurons that directly follow `req.neuron_id` on
+    /// `req.function_id`, as recorded in `function_followee_index`.
+    /// See `GetFollowers` in the Governance's proto for details.
+    pub fn get_followers(&self, req: &GetFollowers) -> GetFollowersResponse {
+        let limit = if req.limit == 0 || req.limit > MAX_LIST_NEURONS_RESULTS {
+            MAX_LIST_NEURONS_RESULTS
+        } else {
+            req.limit
+        } as usize;
+
+        let followers = req.neuron_id.as_ref().and_then(|neuron_id| {
+            self.function_followee_index
+                .get(&req.function_id)?
+                .get(&neuron_id.to_string())
+        });
+
+        let follower_neuron_ids = match (followers, &req.start_page_at) {
+            (None, _) => vec![],
+            (Some(followers), Some(start_page_at)) => followers
+                .range((Excluded(start_page_at), Unbounded))
+                .take(limit)
+                .cloned()
+                .collect(),
+            (Some(followers), None) => followers.iter().take(limit).cloned().collect(),
+        };
+
+        GetFollowersResponse {
+            follower_neuron_ids,
+        }
+    }
+
     /// Disburse the stake of a neuron.
     ///
     /// This causes the stake of a neuron to be disbursed to the provided
@@ -1575,16 +1619,25 @@ impl Governance {
     /// This causes the neuron's maturity to be disbursed to the provided
     /// ledger account. If no ledger account is given, the caller's default
     /// account is used.
-    /// The caller can choose a percentage of maturity to disburse.
+    /// The caller can choose a percentage of maturity to disburse, and
+    /// optionally vest the disbursement in equal tranches over a period of time.
+    /// Each tranche becomes a separate entry in the neuron's
+    /// `disburse_maturity_in_progress`, which is kept sorted by disbursement
+    /// timestamp, and is finalized by `maybe_finalize_disburse_maturity`.
     ///
     /// Pre-conditions:
     /// - The neuron exists
     /// - The caller is authorized to perform this neuron operation
     ///   (NeuronPermissionType::DisburseMaturity)
     /// - The given percentage_to_merge is between 1 and 100 (inclusive)
+    /// - The number of tranches is between 1 and MAX_DISBURSE_MATURITY_TRANCHES
+    ///   (inclusive), and the vesting period is at most
+    ///   MAX_DISBURSE_MATURITY_VESTING_PERIOD_SECONDS, and positive if there is
+    ///   more than one tranche
+    /// - The neuron does not exceed MAX_NEURON_DISBURSEMENTS_IN_PROGRESS
     /// - The neuron's id is not yet in the list of neurons with ongoing operations
-    /// - The e8s equivalent of the amount of maturity to disburse is more
-    ///   than the transaction fee.
+    /// - The e8s equivalent of the amount of maturity to disburse in each
+    ///   tranche is more than the transaction fee.
     pub fn disburse_maturity(
         &mut self,
         id: &NeuronId,
@@ -1620,6 +1673,48 @@ impl Governance {
                 "The percentage of maturity to disburse must be a value between 1 and 100 (inclusive)."));
         }
 
+        let number_of_tranches = disburse_maturity.number_of_tranches.unwrap_or(1);
+        if number_of_tranches == 0 || number_of_tranches > MAX_DISBURSE_MATURITY_TRANCHES {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "The number of tranches must be a value between 1 and {} (inclusive).",
+                    MAX_DISBURSE_MATURITY_TRANCHES
+                ),
+            ));
+        }
+
+        let vesting_period_seconds = disburse_maturity.vesting_period_seconds.unwrap_or(0);
+        if vesting_period_seconds > MAX_DISBURSE_MATURITY_VESTING_PERIOD_SECONDS {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "The vesting period must be at most {} seconds.",
+                    MAX_DISBURSE_MATURITY_VESTING_PERIOD_SECONDS
+                ),
+            ));
+        }
+        if number_of_tranches > 1 && vesting_period_seconds == 0 {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                "The vesting period must be positive when disbursing in more than one tranche.",
+            ));
+        }
+
+        if neuron.disburse_maturity_in_progress.len() + number_of_tranches as usize
+            > MAX_NEURON_DISBURSEMENTS_IN_PROGRESS
+        {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "The neuron already has {} maturity disbursements in progress, and can have \
+                     at most {}.",
+                    neuron.disburse_maturity_in_progress.len(),
+                    MAX_NEURON_DISBURSEMENTS_IN_PROGRESS
+                ),
+            ));
+        }
+
         // The amount to deduct = the amount in the neuron * request.percentage / 100.
         let maturity_to_deduct = neuron
             .maturity_e8s_equivalent
@@ -1631,9 +1726,13 @@ impl Governance {
 
         let maturity_to_deduct = maturity_to_deduct as u64;
 
+        // All tranches have the same amount, except for the last one, which
+        // additionally gets the remainder of the division.
+        let tranche_amount_e8s = maturity_to_deduct / number_of_tranches as u64;
+
         let transaction_fee_e8s = self.transaction_fee_e8s_or_panic();
         let worst_case_maturity_modulation =
-            apply_maturity_modulation(maturity_to_deduct, MIN_MATURITY_MODULATION_PERMYRIAD)
+            apply_maturity_modulation(tranche_amount_e8s, MIN_MATURITY_MODULATION_PERMYRIAD)
                 // Applying maturity modulation is a safe operation.
                 // However, in the case that the method fails to apply the equation, return an
                 // error instead of throwing a panic.
@@ -1660,11 +1759,21 @@ impl Governance {
             ));
         }
 
-        let disbursement_in_progress = DisburseMaturityInProgress {
-            amount_e8s: maturity_to_deduct,
-            timestamp_of_disbursement_seconds: self.env.now(),
-            account_to_disburse_to: Some(to_account_proto),
-        };
+        let now_seconds = self.env.now();
+        let disbursements_in_progress = (1..=number_of_tranches as u64).map(|tranche| {
+            let amount_e8s = if tranche == number_of_tranches as u64 {
+                maturity_to_deduct - tranche_amount_e8s * (number_of_tranches as u64 - 1)
+            } else {
+                tranche_amount_e8s
+            };
+            let vesting_offset_seconds = (vesting_period_seconds as u128 * tranche as u128
+                / number_of_tranches as u128) as u64;
+            DisburseMaturityInProgress {
+                amount_e8s,
+                timestamp_of_disbursement_seconds: now_seconds + vesting_offset_seconds,
+                account_to_disburse_to: Some(to_account_proto.clone()),
+            }
+        });
 
         // Re-borrow the neuron mutably to update now that the maturity has been
         // deducted and is waiting until the end of the window to modulate and disburse.
@@ -1672,9 +1781,17 @@ impl Governance {
         neuron.maturity_e8s_equivalent = neuron
             .maturity_e8s_equivalent
             .saturating_sub(maturity_to_deduct);
-        neuron
-            .disburse_maturity_in_progress
-            .push(disbursement_in_progress);
+        // Keep the entries sorted by timestamp, as only the first entry is
+        // considered for finalization.
+        for disbursement_in_progress in disbursements_in_progress {
+            let index = neuron.disburse_maturity_in_progress.partition_point(|d| {
+                d.timestamp_of_disbursement_seconds
+                    <= disbursement_in_progress.timestamp_of_disbursement_seconds
+            });
+            neuron
+                .disburse_maturity_in_progress
+                .insert(index, disbursement_in_progress);
+        }
 
         Ok(DisburseMaturityResponse {
             // TODO(NNS1-2576) - deprecate amount_disbursed_e8s
@@ -3114,16 +3231,15 @@ impl Governance {
 
                 // Take note of the followers of current_neuron_id, and add them
                 // to the next "tier" in the BFS.
-                let mut specific_follower_neuron_ids = neuron_id_to_follower_neuron_ids_on_function
-                    .get(current_neuron_id)
-                    .cloned()
-                    .unwrap_or_default();
-                let mut blanket_follower_neuron_ids = neuron_id_to_blanket_follower_neuron_ids
-                    .get(current_neuron_id)
-                    .cloned()
-                    .unwrap_or_default();
-                follower_neuron_ids.append(&mut specific_follower_neuron_ids);
-                follower_neuron_ids.append(&mut blanket_follower_neuron_ids);
+                for followers in [
+                    neuron_id_to_follower_neuron_ids_on_function.get(current_neuron_id),
+                    neuron_id_to_blanket_follower_neuron_ids.get(current_neuron_id),
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    follower_neuron_ids.extend(followers.iter().cloned());
+                }
             }
 
             // Prepare for the next iteration of the (outer most) loop by
@@ -6012,6 +6128,79 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_get_followers() {
+        let followee = NeuronId::new_test_neuron_id(1);
+        let follower_ids: Vec<NeuronId> = (2..6).map(NeuronId::new_test_neuron_id).collect();
+        let motion_function_id = u64::from(&Action::Motion(Default::default()));
+
+        // The first follower follows `followee` on all functions, the others only on motions.
+        let mut neurons = btreemap! {
+            followee.to_string() => Neuron {
+                id: Some(followee.clone()),
+                ..Default::default()
+            },
+        };
+        for (i, follower_id) in follower_ids.iter().enumerate() {
+            let function_id = if i == 0 { 0 } else { motion_function_id };
+            neurons.insert(
+                follower_id.to_string(),
+                Neuron {
+                    id: Some(follower_id.clone()),
+                    followees: btreemap! {
+                        function_id => Followees { followees: vec![followee.clone()] },
+                    },
+                    ..Default::default()
+                },
+            );
+        }
+
+        let governance = Governance::new(
+            GovernanceProto {
+                neurons,
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::<NativeEnvironment>::default(),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        let get_followers = |function_id, limit, start_page_at: Option<&NeuronId>| {
+            governance
+                .get_followers(&GetFollowers {
+                    neuron_id: Some(followee.clone()),
+                    function_id,
+                    limit,
+                    start_page_at: start_page_at.cloned(),
+                })
+                .follower_neuron_ids
+        };
+
+        let mut motion_followers = follower_ids[1..].to_vec();
+        motion_followers.sort();
+        assert_eq!(get_followers(motion_function_id, 0, None), motion_followers);
+        assert_eq!(get_followers(0, 0, None), vec![follower_ids[0].clone()]);
+        assert_eq!(
+            get_followers(
+                u64::from(&Action::ManageSnsMetadata(Default::default())),
+                0,
+                None
+            ),
+            vec![]
+        );
+
+        // Paginate through the motion followers one at a time.
+        let first_page = get_followers(motion_function_id, 1, None);
+        assert_eq!(first_page, motion_followers[..1].to_vec());
+        assert_eq!(
+            get_followers(motion_function_id, 2, first_page.last()),
+            motion_followers[1..].to_vec()
+        );
+    }
+
     #[should_panic]
     #[test]
     fn test_disallow_set_mode_not_normal() {
@@ -8745,6 +8934,8 @@ mod tests {
         let disburse_maturity = DisburseMaturity {
             percentage_to_disburse: 100,
             to_account: None,
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         };
         let result = setup.governance.disburse_maturity(
             &setup.neuron_id,
@@ -8798,6 +8989,8 @@ mod tests {
                 owner: Some(target_principal),
                 subaccount: None,
             }),
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         };
         let result = setup.governance.disburse_maturity(
             &setup.neuron_id,
@@ -8847,6 +9040,8 @@ mod tests {
         let disburse_maturity = DisburseMaturity {
             percentage_to_disburse: partial_percentage,
             to_account: None,
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         };
         let result = setup.governance.disburse_maturity(
             &setup.neuron_id,
@@ -8903,6 +9098,8 @@ mod tests {
             let disburse_maturity = DisburseMaturity {
                 percentage_to_disburse: *percentage_to_disburse,
                 to_account: None,
+                number_of_tranches: None,
+                vesting_period_seconds: None,
             };
             let result = setup.governance.disburse_maturity(
                 &setup.neuron_id,
@@ -8960,6 +9157,8 @@ mod tests {
         let disburse_maturity = DisburseMaturity {
             percentage_to_disburse: 100,
             to_account: None,
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         };
         let result = setup.governance.disburse_maturity(
             &non_existing_neuron_id,
@@ -8984,6 +9183,8 @@ mod tests {
         let disburse_maturity = DisburseMaturity {
             percentage_to_disburse: 100,
             to_account: None,
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         };
         let result = setup.governance.disburse_maturity(
             &setup.neuron_id,
@@ -9008,6 +9209,8 @@ mod tests {
         let disburse_maturity = DisburseMaturity {
             percentage_to_disburse: 100,
             to_account: None,
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         };
         let result = setup.governance.disburse_maturity(
             &setup.neuron_id,
@@ -9032,6 +9235,8 @@ mod tests {
             let disburse_maturity = DisburseMaturity {
                 percentage_to_disburse: *percentage,
                 to_account: None,
+                number_of_tranches: None,
+                vesting_period_seconds: None,
             };
             let result = setup.governance.disburse_maturity(
                 &setup.neuron_id,
@@ -9049,6 +9254,106 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_disburse_maturity_succeeds_with_vesting() {
+        // Step 1: Prepare the world and parameters.
+        let earned_maturity_e8s = 100_000_002;
+        let mut setup = prepare_setup_for_disburse_maturity_tests(earned_maturity_e8s);
+        let now = setup.governance.env.now();
+
+        // Step 2: Run code under test. A non-vested disbursement requested after
+        // a vested one must still be sorted before the later tranches.
+        let vested = DisburseMaturity {
+            percentage_to_disburse: 50,
+            to_account: None,
+            number_of_tranches: Some(4),
+            vesting_period_seconds: Some(4 * ONE_DAY_SECONDS),
+        };
+        let response = setup
+            .governance
+            .disburse_maturity(&setup.neuron_id, &setup.controller, &vested)
+            .expect("Operation failed unexpectedly.");
+        assert_eq!(response.amount_deducted_e8s, Some(50_000_001));
+        let not_vested = DisburseMaturity {
+            percentage_to_disburse: 100,
+            to_account: None,
+            number_of_tranches: None,
+            vesting_period_seconds: None,
+        };
+        assert_is_ok!(setup.governance.disburse_maturity(
+            &setup.neuron_id,
+            &setup.controller,
+            &not_vested
+        ));
+
+        // Step 3: Inspect result(s).
+        let neuron = setup
+            .governance
+            .proto
+            .neurons
+            .get(&setup.neuron_id.to_string())
+            .expect("Missing neuron!");
+        assert_eq!(neuron.maturity_e8s_equivalent, 0);
+        let schedule: Vec<(u64, u64)> = neuron
+            .disburse_maturity_in_progress
+            .iter()
+            .map(|d| (d.amount_e8s, d.timestamp_of_disbursement_seconds))
+            .collect();
+        assert_eq!(
+            schedule,
+            vec![
+                (50_000_001, now),
+                (12_500_000, now + ONE_DAY_SECONDS),
+                (12_500_000, now + 2 * ONE_DAY_SECONDS),
+                (12_500_000, now + 3 * ONE_DAY_SECONDS),
+                (12_500_001, now + 4 * ONE_DAY_SECONDS),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disburse_maturity_fails_if_invalid_vesting() {
+        // Step 1: Prepare the world and parameters.
+        let mut setup = prepare_setup_for_disburse_maturity_tests(1_000_000_000);
+
+        for (number_of_tranches, vesting_period_seconds, expected_message) in [
+            (Some(0), None, "number of tranches"),
+            (
+                Some(MAX_DISBURSE_MATURITY_TRANCHES + 1),
+                Some(ONE_DAY_SECONDS),
+                "number of tranches",
+            ),
+            (Some(2), None, "vesting period must be positive"),
+            (
+                None,
+                Some(MAX_DISBURSE_MATURITY_VESTING_PERIOD_SECONDS + 1),
+                "vesting period must be at most",
+            ),
+        ] {
+            // Step 2: Run code under test.
+            let disburse_maturity = DisburseMaturity {
+                percentage_to_disburse: 100,
+                to_account: None,
+                number_of_tranches,
+                vesting_period_seconds,
+            };
+            let result = setup.governance.disburse_maturity(
+                &setup.neuron_id,
+                &setup.controller,
+                &disburse_maturity,
+            );
+
+            // Step 3: Inspect result(s).
+            assert_matches!(
+                result,
+                Err(GovernanceError{error_type: code, error_message: msg})
+                    if code == ErrorType::PreconditionFailed as i32 && msg.contains(expected_message),
+                "Didn't reject number_of_tranches {:?} with vesting_period_seconds {:?}",
+                number_of_tranches, vesting_period_seconds
+            );
+        }
+    }
+
     struct SplitNeuronTestSetup {
         pub governance: Governance,
         pub neuron_id: NeuronId,
@@ -3,14 +3,17 @@ use candid::{types::number::Nat, Principal};
 use dfn_candid::{ArgumentDecoder, ArgumentEncoder};
 use dfn_core::CanisterId;
 use ic_base_types::PrincipalId;
-use ic_icrc1_client::{ICRC1Client, Runtime};
 use ic_ledger_core::{block::BlockIndex, Tokens};
 pub use ic_nervous_system_common::ledger::ICRC1Ledger;
 use ic_nervous_system_common::NervousSystemError;
+use icrc_ledger_client_retry::{
+    CallError, LedgerClientError, RetryConfig, RetryRuntime, RetryingICRC1Client, Runtime,
+};
 use icrc_ledger_types::icrc1::{
     account::{Account, Subaccount},
     transfer::{Memo, TransferArg},
 };
+use std::time::{Duration, SystemTime};
 
 // A ICRC1 client runtime that uses dfn_* functionalities
 struct DfnRuntime {}
@@ -41,21 +44,50 @@ impl Runtime for DfnRuntime {
     }
 }
 
+#[async_trait]
+impl RetryRuntime for DfnRuntime {
+    fn time(&self) -> u64 {
+        dfn_core::api::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_nanos() as u64
+    }
+
+    // A canister cannot block, so retries are made right away.
+    async fn sleep(&self, _duration: Duration) {}
+}
+
 pub struct LedgerCanister {
-    client: ICRC1Client<DfnRuntime>,
+    client: RetryingICRC1Client<DfnRuntime>,
 }
 
 impl LedgerCanister {
+    /// Creates a ledger stub which calls the ledger exactly once per
+    /// request.
     pub fn new(ledger_canister_id: CanisterId) -> Self {
+        Self::new_with_retry_config(ledger_canister_id, RetryConfig::no_retries())
+    }
+
+    /// Creates a ledger stub which retries requests failing due to the
+    /// ledger being temporarily unavailable, as specified by `config`.
+    pub fn new_with_retry_config(ledger_canister_id: CanisterId, config: RetryConfig) -> Self {
         Self {
-            client: ICRC1Client::<DfnRuntime> {
-                runtime: DfnRuntime {},
-                ledger_canister_id: ledger_canister_id.get().into(),
-            },
+            client: RetryingICRC1Client::new(
+                DfnRuntime {},
+                ledger_canister_id.get().into(),
+                config,
+            ),
         }
     }
 }
 
+fn call_error_message(method: &str, error: CallError) -> String {
+    format!(
+        "Error calling method '{}' of the ledger canister. Code: {:?}. Message: {}",
+        method, error.code, error.message
+    )
+}
+
 #[async_trait]
 impl ICRC1Ledger for LedgerCanister {
     async fn transfer_funds(
@@ -74,49 +106,42 @@ impl ICRC1Ledger for LedgerCanister {
             amount: Nat::from(amount_e8s),
             memo: Some(Memo::from(memo)),
         };
-        let res = self.client.transfer(args).await
-            .map_err(|(code, msg)| {
+        self.client.transfer(args).await.map_err(|err| match err {
+            LedgerClientError::Call(CallError { code, message, .. }) => {
                 NervousSystemError::new_with_message(format!(
                     "Error calling method 'icrc1_transfer' of the icrc1 ledger canister. Code: {:?}. Message: {}",
-                    code, msg
+                    code, message
                 ))
-            })?;
-        res.map_err(|err| {
-            NervousSystemError::new_with_message(format!(
+            }
+            LedgerClientError::Ledger(err) => NervousSystemError::new_with_message(format!(
                 "'icrc1_transfer' of the icrc1 ledger canister failed. Error: {:?}",
                 err
-            ))
+            )),
         })
     }
 
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
-        self.client.total_supply().await
+        self.client
+            .total_supply()
+            .await
             .map(Tokens::from_e8s)
-            .map_err(|(code, msg)| {
-                NervousSystemError::new_with_message(
-                    format!(
-                        "Error calling method 'icrc1_total_supply' of the ledger canister. Code: {:?}. Message: {}",
-                        code, msg
-                    )
-                )
+            .map_err(|err| {
+                NervousSystemError::new_with_message(call_error_message("icrc1_total_supply", err))
             })
     }
 
     async fn account_balance(&self, account: Account) -> Result<Tokens, NervousSystemError> {
-        self.client.balance_of(account).await
+        self.client
+            .balance_of(account)
+            .await
             .map(Tokens::from_e8s)
-            .map_err(|(code, msg)| {
-                NervousSystemError::new_with_message(
-                    format!(
-                        "Error calling method 'icrc1_balance_of' of the ledger canister. Code: {:?}. Message: {}",
-                        code, msg
-                    )
-                )
+            .map_err(|err| {
+                NervousSystemError::new_with_message(call_error_message("icrc1_balance_of", err))
             })
     }
 
     fn canister_id(&self) -> CanisterId {
-        let principal_id = PrincipalId::from(self.client.ledger_canister_id);
+        let principal_id = PrincipalId::from(self.client.ledger_canister_id());
         // TODO(NNS1-1992) – CanisterId::new always returns `Ok(_)` so this
         // check does nothing.
         CanisterId::new(principal_id).expect("Expected the Ledger's target to be a Canister")
@@ -235,6 +235,8 @@ fn test_disburse_maturity_succeeds_to_self() {
             manage_neuron::Command::DisburseMaturity(DisburseMaturity {
                 percentage_to_disburse: 100,
                 to_account: None,
+                number_of_tranches: None,
+                vesting_period_seconds: None,
             }),
             env.controller,
         )
@@ -342,6 +344,8 @@ fn test_disburse_maturity_succeeds_to_other() {
             manage_neuron::Command::DisburseMaturity(DisburseMaturity {
                 percentage_to_disburse: 100,
                 to_account: Some(destination_account_proto.clone()),
+                number_of_tranches: None,
+                vesting_period_seconds: None,
             }),
             env.controller,
         )
@@ -445,6 +449,8 @@ fn test_disburse_maturity_succeeds_with_multiple_operations() {
                 manage_neuron::Command::DisburseMaturity(DisburseMaturity {
                     percentage_to_disburse: *percentage,
                     to_account: Some(destination_account_proto.clone()),
+                    number_of_tranches: None,
+                    vesting_period_seconds: None,
                 }),
                 env.controller,
             )
@@ -524,6 +530,8 @@ fn test_disburse_maturity_fails_if_maturity_too_low() {
             manage_neuron::Command::DisburseMaturity(DisburseMaturity {
                 percentage_to_disburse: 100,
                 to_account: None,
+                number_of_tranches: None,
+                vesting_period_seconds: None,
             }),
             env.controller,
         )
@@ -548,6 +556,8 @@ fn test_disburse_maturity_fails_if_not_authorized() {
             manage_neuron::Command::DisburseMaturity(DisburseMaturity {
                 percentage_to_disburse: 100,
                 to_account: None,
+                number_of_tranches: None,
+                vesting_period_seconds: None,
             }),
             unauthorized_caller,
         )
@@ -573,6 +583,8 @@ fn test_disburse_maturity_fails_on_non_existing_neuron() {
             manage_neuron::Command::DisburseMaturity(DisburseMaturity {
                 percentage_to_disburse: 100,
                 to_account: None,
+                number_of_tranches: None,
+                vesting_period_seconds: None,
             }),
             env.controller,
         )
@@ -598,6 +610,8 @@ fn test_disburse_maturity_fails_if_invalid_percentage_to_disburse() {
                 manage_neuron::Command::DisburseMaturity(DisburseMaturity {
                     percentage_to_disburse: *percentage,
                     to_account: None,
+                    number_of_tranches: None,
+                    vesting_period_seconds: None,
                 }),
                 env.controller,
             )
@@ -678,6 +692,8 @@ fn test_vesting_neuron_manage_neuron_operations() {
     let disburse_maturity = DisburseMaturity {
         percentage_to_disburse: 10,
         to_account: None,
+        number_of_tranches: None,
+        vesting_period_seconds: None,
     };
 
     let disburse_maturity_response = DisburseMaturityResponse {
@@ -2620,6 +2636,8 @@ async fn assert_disburse_maturity_with_modulation_disburses_correctly(
                 owner: Some(user_principal),
                 subaccount: None,
             }),
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         }),
         user_principal,
     );
@@ -2718,6 +2736,8 @@ async fn test_disburse_maturity_applied_modulation_at_end_of_window() {
                 owner: Some(user_principal),
                 subaccount: None,
             }),
+            number_of_tranches: None,
+            vesting_period_seconds: None,
         }),
         user_principal,
     );
@@ -188,6 +188,12 @@ pub struct SnsInitPayload {
     #[prost(message, optional, tag = "37")]
     pub neurons_fund_participation_constraints:
         ::core::option::Option<::ic_sns_swap::pb::v1::NeuronsFundParticipationConstraints>,
+    /// An optional canister that decides which principals may participate in the
+    /// swap, and with how much ICP. Passed on to the Swap canister's init
+    /// (`Init.participation_eligibility_canister_id`).
+    #[prost(string, optional, tag = "38")]
+    pub participation_eligibility_canister_id:
+        ::core::option::Option<::prost::alloc::string::String>,
     /// The initial tokens and neurons available at genesis will be distributed according
     /// to the strategy and configuration picked via the initial_token_distribution
     /// parameter.
@@ -6,7 +6,7 @@ use crate::pb::v1::{
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_icrc1_index::InitArgs as IndexInitArgs;
 use ic_icrc1_ledger::{InitArgsBuilder as LedgerInitArgsBuilder, LedgerArgument};
-use ic_ledger_canister_core::archive::ArchiveOptions;
+use ic_ledger_canister_core::archive::{ArchiveCreatedCallback, ArchiveOptions};
 use ic_ledger_core::Tokens;
 use ic_nervous_system_common::E8;
 use ic_nervous_system_proto::pb::v1::{Canister, Countries};
@@ -461,6 +461,7 @@ impl SnsInitPayload {
             nns_proposal_id: None,
             neurons_fund_participants: None,
             neurons_fund_participation_constraints: None,
+            participation_eligibility_canister_id: None,
         }
     }
 
@@ -634,6 +635,13 @@ impl SnsInitPayload {
                     // 10 Trillion cycles
                     cycles_for_archive_creation: Some(10_000_000_000_000),
                     max_transactions_per_response: None,
+                    more_controller_ids: None,
+                    // Lets root learn about new archives without waiting for
+                    // its next poll of the ledger archives.
+                    archive_created_callback: Some(ArchiveCreatedCallback {
+                        canister_id: root_canister_id,
+                        method: "notify_ledger_archive_created".to_string(),
+                    }),
                 });
 
         if let Some(token_logo) = &self.token_logo {
@@ -683,6 +691,23 @@ impl SnsInitPayload {
             latest_ledger_archive_poll_timestamp_seconds: None,
             index_canister_id: Some(sns_canister_ids.index),
             testflight,
+            lost_control_dapp_canister_ids: vec![],
+            latest_dapp_control_check_timestamp_seconds: None,
+            events: vec![],
+            extension_canister_ids: vec![],
+            canister_status_reader_principal_ids: vec![],
+            canister_alert_thresholds: vec![],
+            latest_canister_alert_check_timestamp_seconds: None,
+            low_cycles_alert_count: 0,
+            high_memory_alert_count: 0,
+            cycles_refueling_config: None,
+            latest_cycles_refueling_timestamp_seconds: None,
+            dapp_controllers_policy: None,
+            forced_canister_list_update_count: 0,
+            dropped_event_count: 0,
+            pending_top_ups: vec![],
+            refueling_day: 0,
+            icp_e8s_spent_on_refueling_today: 0,
         }
     }
 
@@ -742,6 +767,9 @@ impl SnsInitPayload {
             neurons_fund_participation_constraints: self
                 .neurons_fund_participation_constraints
                 .clone(),
+            participation_eligibility_canister_id: self
+                .participation_eligibility_canister_id
+                .clone(),
         })
     }
 
@@ -824,6 +852,7 @@ impl SnsInitPayload {
             neurons_fund_participants: _,
             token_logo: _,
             neurons_fund_participation_constraints: _,
+            participation_eligibility_canister_id: _,
         } = self.clone();
 
         let voting_rewards_parameters = Some(VotingRewardsParameters {
@@ -888,6 +917,7 @@ impl SnsInitPayload {
             self.validate_wait_for_quiet_deadline_increase_seconds(),
             self.validate_confirmation_text(),
             self.validate_restricted_countries(),
+            self.validate_participation_eligibility_canister_id(),
             self.validate_parameters_are_legacy(),
             self.validate_neurons_fund_participation_constraints(true),
         ];
@@ -925,6 +955,7 @@ impl SnsInitPayload {
             self.validate_dapp_canisters(),
             self.validate_confirmation_text(),
             self.validate_restricted_countries(),
+            self.validate_participation_eligibility_canister_id(),
             self.validate_all_non_legacy_pre_execution_swap_parameters_are_set(),
             self.validate_neuron_basket_construction_params(),
             self.validate_min_participants(),
@@ -971,6 +1002,7 @@ impl SnsInitPayload {
             self.validate_dapp_canisters(),
             self.validate_confirmation_text(),
             self.validate_restricted_countries(),
+            self.validate_participation_eligibility_canister_id(),
             self.validate_all_non_legacy_pre_execution_swap_parameters_are_set(),
             self.validate_all_post_execution_swap_parameters_are_set(),
             self.validate_neuron_basket_construction_params(),
@@ -1596,6 +1628,26 @@ impl SnsInitPayload {
         Ok(())
     }
 
+    fn validate_participation_eligibility_canister_id(&self) -> Result<(), String> {
+        if let Some(canister_id) = &self.participation_eligibility_canister_id {
+            let principal_id = PrincipalId::from_str(canister_id).map_err(|err| {
+                format!(
+                    "Error: participation_eligibility_canister_id ({}) is not a valid \
+                     principal ID: {}",
+                    canister_id, err
+                )
+            })?;
+            CanisterId::new(principal_id).map_err(|err| {
+                format!(
+                    "Error: participation_eligibility_canister_id ({}) is not a valid \
+                     canister ID: {}",
+                    canister_id, err
+                )
+            })?;
+        }
+        Ok(())
+    }
+
     fn validate_neuron_basket_construction_params(&self) -> Result<(), String> {
         let neuron_basket_construction_parameters = self
             .neuron_basket_construction_parameters
@@ -2145,6 +2197,7 @@ mod test {
     };
     use ic_base_types::{CanisterId, PrincipalId};
     use ic_icrc1_ledger::LedgerArgument;
+    use ic_ledger_canister_core::archive::ArchiveCreatedCallback;
     use ic_nervous_system_proto::pb::v1::{Canister, Countries};
     use ic_sns_governance::{
         governance::ValidGovernanceProto, pb::v1::governance::SnsMetadata, types::ONE_MONTH_SECONDS,
@@ -2355,6 +2408,13 @@ mod test {
 
         if let LedgerArgument::Init(ledger) = sns_canisters_init_payloads.ledger {
             assert_eq!(ledger.archive_options.controller_id, sns_canister_ids.root);
+            assert_eq!(
+                ledger.archive_options.archive_created_callback,
+                Some(ArchiveCreatedCallback {
+                    canister_id: CanisterId::new(sns_canister_ids.root).unwrap(),
+                    method: "notify_ledger_archive_created".to_string(),
+                })
+            );
             assert_eq!(
                 ledger.minting_account,
                 Account {
@@ -2635,6 +2695,47 @@ mod test {
         swap.validate().unwrap();
     }
 
+    #[test]
+    fn test_participation_eligibility_canister_id_is_passed_to_swap() {
+        let sns_canister_ids = create_canister_ids();
+        // Test that `participation_eligibility_canister_id` is indeed optional.
+        {
+            let canister_payloads = SnsInitPayload::with_valid_values_for_testing()
+                .build_canister_payloads(&sns_canister_ids, None, false)
+                .unwrap();
+            assert_eq!(
+                canister_payloads.swap.participation_eligibility_canister_id,
+                None
+            );
+        }
+        // Test that a valid canister ID is passed on to the Swap canister.
+        {
+            let eligibility_canister_id = CanisterId::from_u64(1234).to_string();
+            let sns_init_payload = SnsInitPayload {
+                participation_eligibility_canister_id: Some(eligibility_canister_id.clone()),
+                ..SnsInitPayload::with_valid_values_for_testing()
+            };
+            let canister_payloads = sns_init_payload
+                .build_canister_payloads(&sns_canister_ids, None, false)
+                .unwrap();
+            assert_eq!(
+                canister_payloads.swap.participation_eligibility_canister_id,
+                Some(eligibility_canister_id)
+            );
+            canister_payloads.swap.validate().unwrap();
+        }
+        // Test that an invalid principal is rejected.
+        {
+            let sns_init_payload = SnsInitPayload {
+                participation_eligibility_canister_id: Some("not a principal".to_string()),
+                ..SnsInitPayload::with_valid_values_for_testing()
+            };
+            assert!(sns_init_payload
+                .build_canister_payloads(&sns_canister_ids, None, false)
+                .is_err());
+        }
+    }
+
     #[test]
     fn test_confirmation_text_is_valid() {
         // Create valid CanisterIds
@@ -756,6 +756,8 @@ fn test_disburse_maturity_succeeds_to_self() {
                     command: Some(Command::DisburseMaturity(DisburseMaturity {
                         percentage_to_disburse: 100,
                         to_account: None,
+                        number_of_tranches: None,
+                        vesting_period_seconds: None,
                     })),
                 },
                 &user,
@@ -825,6 +827,8 @@ fn test_disburse_maturity_succeeds_to_other_account() {
                             owner: Some(maturity_receiver.get_principal_id()),
                             subaccount: None,
                         }),
+                        number_of_tranches: None,
+                        vesting_period_seconds: None,
                     })),
                 },
                 &maturity_owner,
@@ -932,6 +936,8 @@ fn test_disburse_maturity_fails_if_no_maturity() {
                     command: Some(Command::DisburseMaturity(DisburseMaturity {
                         percentage_to_disburse: 100,
                         to_account: None,
+                        number_of_tranches: None,
+                        vesting_period_seconds: None,
                     })),
                 },
                 &user,
@@ -16,7 +16,10 @@ use ic_sns_test_utils::state_test_helpers::{
     get_buyer_state, get_buyers_total, get_lifecycle, get_open_ticket, get_sns_sale_parameters,
     new_sale_ticket, notify_payment_failure, open_sale, refresh_buyer_tokens,
 };
-use ic_state_machine_tests::StateMachine;
+use ic_state_machine_tests::{
+    icrc1::{assert_balances, install_icrc1_ledger_at, InitialBalances},
+    StateMachine,
+};
 use icp_ledger::{
     AccountIdentifier, LedgerCanisterInitPayload as IcpInitArgs, DEFAULT_TRANSFER_FEE,
 };
@@ -72,6 +75,8 @@ lazy_static! {
         controller_id: PrincipalId::new_anonymous(),
         cycles_for_archive_creation: None,
         max_transactions_per_response: None,
+        more_controller_ids: None,
+        archive_created_callback: None,
     };
 }
 
@@ -104,10 +109,12 @@ impl PaymentProtocolTestSetup {
                 "ledger-canister",
                 &[],
             );
-            let args = Encode!(&PaymentProtocolTestSetup::default_icp_init_args()).unwrap();
-            state_machine
-                .install_existing_canister(icp_ledger_id, wasm, args)
-                .unwrap();
+            install_icrc1_ledger_at(
+                &state_machine,
+                icp_ledger_id,
+                wasm,
+                &PaymentProtocolTestSetup::default_icp_init_args(),
+            );
         }
         // install the sns ledger
         {
@@ -116,13 +123,17 @@ impl PaymentProtocolTestSetup {
                 "ic-icrc1-ledger",
                 &[],
             );
-            let args = Encode!(&LedgerArgument::Init(
-                PaymentProtocolTestSetup::default_icrc1_init_args()
-            ))
-            .unwrap();
-            state_machine
-                .install_existing_canister(sns_ledger_id, wasm, args)
-                .unwrap();
+            install_icrc1_ledger_at(
+                &state_machine,
+                sns_ledger_id,
+                wasm,
+                &LedgerArgument::Init(PaymentProtocolTestSetup::default_icrc1_init_args()),
+            );
+            assert_balances(
+                &state_machine,
+                sns_ledger_id,
+                PaymentProtocolTestSetup::default_icrc1_initial_balances(),
+            );
         }
 
         // install the sale canister
@@ -153,16 +164,23 @@ impl PaymentProtocolTestSetup {
             .build()
             .unwrap()
     }
+    pub fn default_icrc1_initial_balances() -> InitialBalances {
+        InitialBalances::new().with(
+            DEFAULT_SNS_SALE_CANISTER_ID.get().0,
+            *DEFAULT_INITIAL_BALANCE,
+        )
+    }
+
     pub fn default_icrc1_init_args() -> Icrc1InitArgs {
-        ic_icrc1_ledger::InitArgsBuilder::with_symbol_and_name("STK", "SNS Token")
-            .with_minting_account(*DEFAULT_MINTING_ACCOUNT)
-            .with_transfer_fee(DEFAULT_TRANSFER_FEE)
-            .with_archive_options(DEFAULT_ICRC1_ARCHIVE_OPTIONS.clone())
-            .with_initial_balance(
-                DEFAULT_SNS_SALE_CANISTER_ID.get().0,
-                *DEFAULT_INITIAL_BALANCE,
-            )
-            .build()
+        let mut init_args =
+            ic_icrc1_ledger::InitArgsBuilder::with_symbol_and_name("STK", "SNS Token")
+                .with_minting_account(*DEFAULT_MINTING_ACCOUNT)
+                .with_transfer_fee(DEFAULT_TRANSFER_FEE)
+                .with_archive_options(DEFAULT_ICRC1_ARCHIVE_OPTIONS.clone())
+                .build();
+        init_args.initial_balances =
+            PaymentProtocolTestSetup::default_icrc1_initial_balances().to_icrc1_initial_balances();
+        init_args
     }
 
     pub fn default_sns_sale_init_args() -> Init {
@@ -194,6 +212,7 @@ impl PaymentProtocolTestSetup {
             neurons_fund_participants: None,             // TODO[NNS1-2339]
             should_auto_finalize: Some(true),
             neurons_fund_participation_constraints: None,
+            participation_eligibility_canister_id: None,
         }
     }
 
@@ -620,6 +639,26 @@ fn test_multiple_payment_flows() {
             .unwrap()
             .is_none())
     }
+
+    // Check that the committed ICP ended up in the buyer's subaccount of the sale canister
+    let sns_sale_principal_id: PrincipalId = payment_flow_protocol.sns_sale_canister_id.into();
+    assert_balances(
+        &payment_flow_protocol.state_machine,
+        payment_flow_protocol.icp_ledger_canister_id,
+        [
+            (
+                Account {
+                    owner: sns_sale_principal_id.0,
+                    subaccount: Some(principal_to_subaccount(&user0)),
+                },
+                amount_committed,
+            ),
+            (
+                Account::from(user0.0),
+                100 * E8 - amount_committed - 3 * DEFAULT_TRANSFER_FEE.get_e8s(),
+            ),
+        ],
+    );
 }
 
 #[test]
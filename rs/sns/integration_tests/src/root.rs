@@ -38,6 +38,23 @@ fn test_get_status() {
                 latest_ledger_archive_poll_timestamp_seconds: None,
                 index_canister_id: Some(PrincipalId::new_user_test_id(45)),
                 testflight: false,
+                lost_control_dapp_canister_ids: vec![],
+                latest_dapp_control_check_timestamp_seconds: None,
+                events: vec![],
+                extension_canister_ids: vec![],
+                canister_status_reader_principal_ids: vec![],
+                canister_alert_thresholds: vec![],
+                latest_canister_alert_check_timestamp_seconds: None,
+                low_cycles_alert_count: 0,
+                high_memory_alert_count: 0,
+                cycles_refueling_config: None,
+                latest_cycles_refueling_timestamp_seconds: None,
+                dapp_controllers_policy: None,
+                forced_canister_list_update_count: 0,
+                dropped_event_count: 0,
+                pending_top_ups: vec![],
+                refueling_day: 0,
+                icp_e8s_spent_on_refueling_today: 0,
             },
         )
         .await;
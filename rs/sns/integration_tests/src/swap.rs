@@ -2683,6 +2683,7 @@ fn test_upgrade() {
         neurons_fund_participants: None,             // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
         neurons_fund_participation_constraints: None,
+        participation_eligibility_canister_id: None,
     })
     .unwrap();
     let canister_id = state_machine
@@ -3055,6 +3056,8 @@ fn test_last_man_less_than_min() {
             controller_id: Principal::anonymous().into(),
             cycles_for_archive_creation: None,
             max_transactions_per_response: None
+            more_controller_ids: None,
+            archive_created_callback: None,
         },
         max_memo_length: None,
         feature_flags: None,
@@ -3092,6 +3095,7 @@ fn test_last_man_less_than_min() {
         neurons_fund_participants: None,             // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
         neurons_fund_participation_constraints: None,
+        participation_eligibility_canister_id: None,
     })
     .unwrap();
     state_machine
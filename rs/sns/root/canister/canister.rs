@@ -1,32 +1,43 @@
 use async_trait::async_trait;
-use candid::candid_method;
+use candid::{candid_method, Nat};
+use cycles_minting_canister::{NotifyError, NotifyTopUp, MEMO_TOP_UP_CANISTER};
 use ic_base_types::{CanisterId, PrincipalId};
-use ic_canister_log::log;
+use ic_canister_log::{export_logs_http, log};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
-use ic_cdk_macros::{heartbeat, init, post_upgrade, pre_upgrade, query, update};
+use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_nervous_system_clients::canister_id_record::CanisterIdRecord;
-use ic_nervous_system_clients::canister_status::CanisterStatusResult;
+use ic_nervous_system_clients::canister_status::{CanisterStatusResult, CanisterStatusResultV2};
 use ic_nervous_system_clients::management_canister_client::ManagementCanisterClientImpl;
 use ic_nervous_system_common::{
     dfn_core_stable_mem_utils::{BufferedStableMemReader, BufferedStableMemWriter},
-    serve_logs, serve_logs_v2, serve_metrics, NANO_SECONDS_PER_SECOND,
+    serve_logs, serve_metrics, NANO_SECONDS_PER_SECOND,
 };
 use ic_nervous_system_root::change_canister::ChangeCanisterProposal;
 use ic_nervous_system_runtime::{CdkRuntime, Runtime};
+use ic_nns_constants::{CYCLES_MINTING_CANISTER_ID, LEDGER_CANISTER_ID as ICP_LEDGER_CANISTER_ID};
 use ic_sns_root::{
-    logs::{ERROR, INFO},
+    logs::{ERROR, INFO, WARN},
     pb::v1::{
-        CanisterCallError, ListSnsCanistersRequest, ListSnsCanistersResponse,
-        RegisterDappCanisterRequest, RegisterDappCanisterResponse, RegisterDappCanistersRequest,
-        RegisterDappCanistersResponse, SetDappControllersRequest, SetDappControllersResponse,
-        SnsRootCanister,
+        CanisterCallError, GetRootEventsRequest, GetRootEventsResponse, ListSnsCanistersRequest,
+        ListSnsCanistersResponse, RegisterDappCanisterRequest, RegisterDappCanisterResponse,
+        RegisterDappCanistersRequest, RegisterDappCanistersResponse,
+        RegisterExtensionCanisterRequest, RegisterExtensionCanisterResponse,
+        SetCanisterAlertThresholdsRequest, SetCanisterAlertThresholdsResponse,
+        SetCanisterStatusReadersRequest, SetCanisterStatusReadersResponse,
+        SetCyclesRefuelingConfigRequest, SetCyclesRefuelingConfigResponse,
+        SetDappControllersPolicyRequest, SetDappControllersPolicyResponse,
+        SetDappControllersRequest, SetDappControllersResponse, SnsRootCanister,
     },
     types::Environment,
-    GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse, LedgerCanisterClient,
+    CyclesMintingClient, GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse,
+    LedgerCanisterClient, NotifyTopUpError, PeriodicTask,
+};
+use icp_ledger::{
+    AccountIdentifier, Subaccount, Tokens, TransferArgs, TransferError, DEFAULT_TRANSFER_FEE,
 };
 use icrc_ledger_types::icrc3::archive::ArchiveInfo;
 use prost::Message;
-use std::cell::RefCell;
+use std::{cell::RefCell, time::Duration};
 
 const STABLE_MEM_BUFFER_SIZE: u32 = 100 * 1024 * 1024; // 100MiB
 
@@ -82,6 +93,83 @@ fn create_ledger_client() -> RealLedgerCanisterClient {
     RealLedgerCanisterClient::new(ledger_canister_id)
 }
 
+/// Converts ICP from the default account of this canister into cycles, by
+/// transferring it to the NNS cycles minting canister (CMC) and notifying the
+/// CMC of the transfer.
+struct RealCyclesMintingClient {}
+
+#[async_trait]
+impl CyclesMintingClient for RealCyclesMintingClient {
+    async fn transfer_for_top_up(
+        &self,
+        canister_id: PrincipalId,
+        icp_e8s: u64,
+    ) -> Result<u64, String> {
+        let transfer_args = TransferArgs {
+            memo: MEMO_TOP_UP_CANISTER,
+            amount: Tokens::from_e8s(icp_e8s),
+            fee: DEFAULT_TRANSFER_FEE,
+            from_subaccount: None,
+            to: AccountIdentifier::new(
+                CYCLES_MINTING_CANISTER_ID.get(),
+                Some(Subaccount::from(&canister_id)),
+            )
+            .to_address(),
+            created_at_time: None,
+        };
+        CanisterRuntime::call_with_cleanup(ICP_LEDGER_CANISTER_ID, "transfer", (transfer_args,))
+            .await
+            .map_err(|(code, message)| {
+                format!("Call to the ICP ledger failed with code {code}: {message}")
+            })
+            .and_then(|(result,): (Result<u64, TransferError>,)| {
+                result.map_err(|err| format!("{err:?}"))
+            })
+    }
+
+    async fn notify_top_up(
+        &self,
+        canister_id: PrincipalId,
+        block_index: u64,
+    ) -> Result<u128, NotifyTopUpError> {
+        let canister_id = CanisterId::try_from(canister_id)
+            .map_err(|err| NotifyTopUpError::Permanent(format!("{err:?}")))?;
+        let notify_top_up = NotifyTopUp {
+            block_index,
+            canister_id,
+        };
+        CanisterRuntime::call_with_cleanup(
+            CYCLES_MINTING_CANISTER_ID,
+            "notify_top_up",
+            (notify_top_up,),
+        )
+        .await
+        .map_err(|(code, message)| {
+            NotifyTopUpError::Transient(format!(
+                "Call to the CMC failed with code {code}: {message}"
+            ))
+        })
+        .and_then(|(result,): (Result<Nat, NotifyError>,)| {
+            result.map_err(|err| match err {
+                NotifyError::Processing | NotifyError::Other { .. } => {
+                    NotifyTopUpError::Transient(format!("{err}"))
+                }
+                NotifyError::Refunded { .. }
+                | NotifyError::InvalidTransaction(_)
+                | NotifyError::TransactionTooOld(_) => {
+                    NotifyTopUpError::Permanent(format!("{err}"))
+                }
+            })
+        })
+        .and_then(|cycles| {
+            // The CMC did convert the ICP, so notifying again would not help.
+            u128::try_from(cycles.0).map_err(|err| {
+                NotifyTopUpError::Permanent(format!("Invalid number of cycles: {err}"))
+            })
+        })
+    }
+}
+
 thread_local! {
     static STATE: RefCell<SnsRootCanister> = RefCell::new(Default::default());
 }
@@ -102,6 +190,8 @@ fn canister_init_(init_payload: SnsRootCanister) {
         *state = init_payload;
     });
 
+    init_timers();
+
     log!(INFO, "canister_init: Done!");
 }
 
@@ -148,6 +238,112 @@ async fn canister_status(id: CanisterIdRecord) -> CanisterStatusResult {
         .unwrap()
 }
 
+/// Returns the status of a single canister that this canister (SNS root)
+/// controls: one of the SNS canisters (other than swap), or a registered dapp or
+/// extension canister.
+///
+/// Caller must be the Governance canister, or one of the principals set via
+/// set_canister_status_readers. Otherwise, the request will be rejected.
+#[candid_method(update)]
+#[update]
+async fn canister_status_of(request: CanisterIdRecord) -> CanisterStatusResultV2 {
+    log!(INFO, "canister_status_of");
+    SnsRootCanister::canister_status_of(
+        &STATE,
+        &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
+        ic_cdk::api::id(),
+        PrincipalId(ic_cdk::api::caller()),
+        request.get_canister_id().get(),
+    )
+    .await
+    .unwrap_or_else(|err| panic!("canister_status_of failed: {err}"))
+}
+
+/// Replaces the principals (besides the Governance canister) that are allowed
+/// to call canister_status_of.
+///
+/// Caller must be the Governance canister.
+#[candid_method(update)]
+#[update]
+fn set_canister_status_readers(
+    request: SetCanisterStatusReadersRequest,
+) -> SetCanisterStatusReadersResponse {
+    log!(INFO, "set_canister_status_readers");
+    assert_eq_governance_canister_id(PrincipalId(ic_cdk::api::caller()));
+    STATE.with(|state| state.borrow_mut().set_canister_status_readers(request))
+}
+
+/// Sets (or removes) the cycles and memory thresholds beyond which root raises
+/// alerts about the listed canisters. Root checks these thresholds
+/// periodically, and reports each crossed threshold with a WARN log entry and
+/// the sns_root_alerts_total metric.
+///
+/// Caller must be the Governance canister.
+#[candid_method(update)]
+#[update]
+fn set_canister_alert_thresholds(
+    request: SetCanisterAlertThresholdsRequest,
+) -> SetCanisterAlertThresholdsResponse {
+    log!(INFO, "set_canister_alert_thresholds");
+    assert_eq_governance_canister_id(PrincipalId(ic_cdk::api::caller()));
+    STATE
+        .with(|state| {
+            state
+                .borrow_mut()
+                .set_canister_alert_thresholds(ic_cdk::api::id(), request)
+        })
+        .unwrap_or_else(|err| panic!("set_canister_alert_thresholds failed: {err}"))
+}
+
+/// Enables (or, if no config is given, disables) the automatic refueling of
+/// the canisters that root controls. While enabled, root periodically tops up
+/// those canisters that are low on cycles with ICP from its own account on the
+/// ICP ledger, up to a daily cap. Each top up is recorded in the event log
+/// (see get_root_events).
+///
+/// Caller must be the Governance canister.
+#[candid_method(update)]
+#[update]
+fn set_cycles_refueling_config(
+    request: SetCyclesRefuelingConfigRequest,
+) -> SetCyclesRefuelingConfigResponse {
+    log!(INFO, "set_cycles_refueling_config");
+    let caller = PrincipalId(ic_cdk::api::caller());
+    assert_eq_governance_canister_id(caller);
+    let now = CanisterEnvironment {}.now();
+    STATE
+        .with(|state| {
+            state
+                .borrow_mut()
+                .set_cycles_refueling_config(caller, request, now)
+        })
+        .unwrap_or_else(|err| panic!("set_cycles_refueling_config failed: {err}"))
+}
+
+/// Sets (or, if no policy is given, removes) the dapp controllers policy, i.e.
+/// the principals that may remain controllers of dapp canisters besides root
+/// when they are registered via register_dapp_canisters (e.g. a deployment
+/// orchestrator). Other controllers are still removed upon registration.
+///
+/// Caller must be the Governance canister.
+#[candid_method(update)]
+#[update]
+fn set_dapp_controllers_policy(
+    request: SetDappControllersPolicyRequest,
+) -> SetDappControllersPolicyResponse {
+    log!(INFO, "set_dapp_controllers_policy");
+    let caller = PrincipalId(ic_cdk::api::caller());
+    assert_eq_governance_canister_id(caller);
+    let now = CanisterEnvironment {}.now();
+    STATE
+        .with(|state| {
+            state
+                .borrow_mut()
+                .set_dapp_controllers_policy(ic_cdk::api::id(), caller, request, now)
+        })
+        .unwrap_or_else(|err| panic!("set_dapp_controllers_policy failed: {err}"))
+}
+
 /// Return the canister status of all SNS canisters that this root canister
 /// is part of, as well as of all registered dapp canisters (See
 /// SnsRootCanister::register_dapp_canister).
@@ -158,10 +354,6 @@ async fn get_sns_canisters_summary(
 ) -> GetSnsCanistersSummaryResponse {
     log!(INFO, "get_sns_canisters_summary");
     let update_canister_list = request.update_canister_list.unwrap_or(false);
-    // Only governance can set this parameter to true
-    if update_canister_list {
-        assert_eq_governance_canister_id(PrincipalId(ic_cdk::api::caller()));
-    }
 
     let canister_env = CanisterEnvironment {};
     SnsRootCanister::get_sns_canisters_summary(
@@ -170,9 +362,11 @@ async fn get_sns_canisters_summary(
         &create_ledger_client(),
         &canister_env,
         update_canister_list,
+        PrincipalId(ic_cdk::api::caller()),
         PrincipalId(ic_cdk::api::id()),
     )
     .await
+    .unwrap_or_else(|err| panic!("get_sns_canisters_summary failed: {err}"))
 }
 
 /// Return the `PrincipalId`s of all SNS canisters that this root canister
@@ -245,6 +439,8 @@ async fn register_dapp_canister(
         &STATE,
         &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
         ic_cdk::api::id(),
+        PrincipalId(ic_cdk::api::caller()),
+        CanisterEnvironment {}.now(),
         request,
     )
     .await;
@@ -272,6 +468,32 @@ async fn register_dapp_canisters(
         &STATE,
         &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
         ic_cdk::api::id(),
+        PrincipalId(ic_cdk::api::caller()),
+        CanisterEnvironment {}.now(),
+        request,
+    )
+    .await
+}
+
+/// Tells this canister (SNS root) about an SNS extension canister that it
+/// controls. Extension canisters are tracked separately from dapp canisters,
+/// and may keep controllers besides root (see
+/// `request.extra_controller_principal_ids`).
+///
+/// Caller must be the Governance canister.
+#[candid_method(update)]
+#[update]
+async fn register_extension_canister(
+    request: RegisterExtensionCanisterRequest,
+) -> RegisterExtensionCanisterResponse {
+    log!(INFO, "register_extension_canister");
+    assert_eq_governance_canister_id(PrincipalId(ic_cdk::api::caller()));
+    SnsRootCanister::register_extension_canister(
+        &STATE,
+        &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
+        ic_cdk::api::id(),
+        PrincipalId(ic_cdk::api::caller()),
+        CanisterEnvironment {}.now(),
         request,
     )
     .await
@@ -302,11 +524,42 @@ async fn set_dapp_controllers(request: SetDappControllersRequest) -> SetDappCont
         &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
         ic_cdk::api::id(),
         PrincipalId(ic_cdk::api::caller()),
+        CanisterEnvironment {}.now(),
         &request,
     )
     .await
 }
 
+/// Tells this canister (SNS root) that the ledger created a new archive
+/// canister. Root also polls the ledger for its archives periodically, this
+/// only makes new archives show up sooner.
+///
+/// Caller must be the Ledger canister.
+#[candid_method(update)]
+#[update]
+fn notify_ledger_archive_created(archive_canister_id: PrincipalId) {
+    log!(INFO, "notify_ledger_archive_created");
+    assert_eq_ledger_canister_id(PrincipalId(ic_cdk::api::caller()));
+    STATE.with(|state| {
+        state
+            .borrow_mut()
+            .register_ledger_archive(archive_canister_id)
+    });
+}
+
+/// Returns a page of the log of changes to the registered dapp and extension
+/// canisters and their controllers (i.e. registrations, controller changes,
+/// and loss of control detected by root), oldest first.
+///
+/// At most 100 events are returned per call; use `request.start` to page
+/// through the log.
+#[candid_method(query)]
+#[query]
+fn get_root_events(request: GetRootEventsRequest) -> GetRootEventsResponse {
+    log!(INFO, "get_root_events");
+    STATE.with(|state| state.borrow().get_root_events(request))
+}
+
 fn assert_state_is_valid(state: &SnsRootCanister) {
     assert!(state.governance_canister_id.is_some());
     assert!(state.ledger_canister_id.is_some());
@@ -332,14 +585,62 @@ fn assert_eq_governance_canister_id(id: PrincipalId) {
     });
 }
 
-#[heartbeat]
-async fn heartbeat() {
-    // Asynchronous method called for the canister_heartbeat that injects
-    // dependencies to run_periodic_tasks.
+fn assert_eq_ledger_canister_id(id: PrincipalId) {
+    STATE.with(|state: &RefCell<SnsRootCanister>| {
+        let state = state.borrow();
+        let ledger_canister_id = state
+            .ledger_canister_id
+            .expect("STATE.ledger_canister_id is not populated");
+        assert_eq!(id, ledger_canister_id);
+    });
+}
+
+/// Schedules each periodic task for when it is next due. Timers do not survive
+/// upgrades, so this is called on init and post_upgrade.
+fn init_timers() {
+    let now = CanisterEnvironment {}.now();
+    for task in PeriodicTask::ALL {
+        let delay_seconds =
+            STATE.with(|state| state.borrow().seconds_until_periodic_task_due(task, now));
+        schedule_periodic_task(task, delay_seconds, 0);
+    }
+}
+
+fn schedule_periodic_task(task: PeriodicTask, delay_seconds: u64, consecutive_failures: u32) {
+    ic_cdk_timers::set_timer(Duration::from_secs(delay_seconds), move || {
+        ic_cdk::spawn(run_periodic_task(task, consecutive_failures))
+    });
+}
+
+/// Runs `task`, and then schedules its next run. Each task has its own timer,
+/// so a task that keeps failing backs off without delaying the others.
+async fn run_periodic_task(task: PeriodicTask, consecutive_failures: u32) {
     let now = CanisterEnvironment {}.now();
     let ledger_client = create_ledger_client();
 
-    SnsRootCanister::heartbeat(&STATE, &ledger_client, now).await
+    let result = SnsRootCanister::run_periodic_task(
+        &STATE,
+        task,
+        &ledger_client,
+        &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
+        &RealCyclesMintingClient {},
+        PrincipalId(ic_cdk::api::id()),
+        now,
+    )
+    .await;
+
+    let consecutive_failures = match result {
+        Ok(()) => 0,
+        Err(err) => {
+            log!(ERROR, "Periodic task {:?} failed: {}", task, err);
+            consecutive_failures.saturating_add(1)
+        }
+    };
+    schedule_periodic_task(
+        task,
+        task.next_delay_seconds(consecutive_failures),
+        consecutive_failures,
+    );
 }
 
 // Resources to serve for a given http_request
@@ -347,18 +648,52 @@ async fn heartbeat() {
 fn http_request(request: HttpRequest) -> HttpResponse {
     match request.path() {
         "/metrics" => serve_metrics(encode_metrics),
-        "/logs" => serve_logs_v2(request, &INFO, &ERROR),
+        "/logs" => match export_logs_http(&request.url, &[&INFO, &WARN, &ERROR]) {
+            Ok(body) => HttpResponseBuilder::ok()
+                .header("Content-Type", "application/json")
+                .with_body_and_content_length(body)
+                .build(),
+            Err(message) => HttpResponseBuilder::bad_request()
+                .with_body_and_content_length(message)
+                .build(),
+        },
 
         // These are obsolete.
         "/log/info" => serve_logs(&INFO),
         "/log/error" => serve_logs(&ERROR),
+        "/log/warn" => serve_logs(&WARN),
 
         _ => HttpResponseBuilder::not_found().build(),
     }
 }
 
 /// Encode the metrics in a format that can be understood by Prometheus.
-fn encode_metrics(_w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
+fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
+    let (low_cycles_alert_count, high_memory_alert_count, forced_canister_list_update_count) =
+        STATE.with(|state| {
+            let state = state.borrow();
+            (
+                state.low_cycles_alert_count,
+                state.high_memory_alert_count,
+                state.forced_canister_list_update_count,
+            )
+        });
+
+    w.counter_vec(
+        "sns_root_alerts_total",
+        "The number of times that a canister controlled by SNS root was found to be beyond one \
+         of its alert thresholds.",
+    )?
+    .value(&[("kind", "low_cycles")], low_cycles_alert_count as f64)?
+    .value(&[("kind", "high_memory")], high_memory_alert_count as f64)?;
+
+    w.encode_counter(
+        "sns_root_forced_canister_list_updates_total",
+        forced_canister_list_update_count as f64,
+        "The number of times that SNS governance made root update its list of canisters \
+         (by polling the ledger for archives) before summarizing them.",
+    )?;
+
     Ok(())
 }
 
@@ -43,6 +43,101 @@ pub struct SnsRootCanister {
     /// controllers beyond SNS root are allowed when registering a dapp.
     #[prost(bool, tag = "8")]
     pub testflight: bool,
+    /// Dapp canisters that were registered with this canister, but that this
+    /// canister (SNS root) has since found it no longer controls. Such canisters
+    /// are moved here from dapp_canister_ids by a periodic check (see
+    /// latest_dapp_control_check_timestamp_seconds).
+    #[prost(message, repeated, tag = "9")]
+    pub lost_control_dapp_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    /// The timestamp of the latest check of whether this canister still controls
+    /// the registered dapp canisters, in seconds since the Unix epoch.
+    #[prost(uint64, optional, tag = "10")]
+    pub latest_dapp_control_check_timestamp_seconds: ::core::option::Option<u64>,
+    /// An append-only log of changes to the registered dapp canisters and their
+    /// controllers, kept for auditing purposes. See get_root_events. Only the
+    /// most recent events are kept (see dropped_event_count).
+    #[prost(message, repeated, tag = "11")]
+    pub events: ::prost::alloc::vec::Vec<RootEvent>,
+    /// Extension canister IDs. Like dapps, extensions (e.g. treasury managers)
+    /// are controlled by this canister (SNS root), but unlike dapps, they may
+    /// have additional controllers (see RegisterExtensionCanisterRequest).
+    #[prost(message, repeated, tag = "12")]
+    pub extension_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    /// Principals (besides SNS governance) that are allowed to call
+    /// canister_status_of.
+    #[prost(message, repeated, tag = "13")]
+    pub canister_status_reader_principal_ids:
+        ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    /// Per-canister thresholds that root periodically checks the canisters it
+    /// controls against (see CanisterAlertThresholds).
+    #[prost(message, repeated, tag = "14")]
+    pub canister_alert_thresholds: ::prost::alloc::vec::Vec<CanisterAlertThresholds>,
+    /// The timestamp, in seconds, of the most recent check of
+    /// canister_alert_thresholds.
+    #[prost(uint64, optional, tag = "15")]
+    pub latest_canister_alert_check_timestamp_seconds: ::core::option::Option<u64>,
+    /// The number of times that a canister was found to have fewer cycles than
+    /// its min_cycles threshold.
+    #[prost(uint64, tag = "16")]
+    pub low_cycles_alert_count: u64,
+    /// The number of times that a canister was found to use more memory than its
+    /// max_memory_bytes threshold.
+    #[prost(uint64, tag = "17")]
+    pub high_memory_alert_count: u64,
+    /// If set, root periodically tops up the canisters that it controls with
+    /// cycles when they run low (see CyclesRefuelingConfig). Unset means that
+    /// refueling is disabled.
+    #[prost(message, optional, tag = "18")]
+    pub cycles_refueling_config: ::core::option::Option<CyclesRefuelingConfig>,
+    /// The timestamp, in seconds, of the most recent check of whether any
+    /// canister needs to be refueled.
+    #[prost(uint64, optional, tag = "19")]
+    pub latest_cycles_refueling_timestamp_seconds: ::core::option::Option<u64>,
+    /// Which controllers besides SNS root dapp canisters may keep when they are
+    /// registered (see DappControllersPolicy). Unset means that root becomes the
+    /// sole controller of registered dapp canisters.
+    #[prost(message, optional, tag = "20")]
+    pub dapp_controllers_policy: ::core::option::Option<DappControllersPolicy>,
+    /// The number of times that root updated its canister list because the
+    /// governance canister called get_sns_canisters_summary with
+    /// update_canister_list set.
+    #[prost(uint64, tag = "21")]
+    pub forced_canister_list_update_count: u64,
+    /// The number of events that were dropped from the start of events to keep
+    /// the event log bounded. The index of events\[i\] in get_root_events is
+    /// dropped_event_count + i.
+    #[prost(uint64, tag = "22")]
+    pub dropped_event_count: u64,
+    /// Top ups whose ICP was transferred to the CMC, but that the CMC has not
+    /// converted into cycles yet. Root notifies the CMC of them again on later
+    /// refueling runs, until the CMC either converts or refunds the ICP.
+    #[prost(message, repeated, tag = "23")]
+    pub pending_top_ups: ::prost::alloc::vec::Vec<PendingTopUp>,
+    /// The day (counted in whole days since the Unix epoch, UTC) to which
+    /// icp_e8s_spent_on_refueling_today refers.
+    #[prost(uint64, tag = "24")]
+    pub refueling_day: u64,
+    /// The amount of ICP, in e8s and including transfer fees, that root spent on
+    /// top ups during refueling_day. Checked against
+    /// CyclesRefuelingConfig.max_icp_e8s_per_day.
+    #[prost(uint64, tag = "25")]
+    pub icp_e8s_spent_on_refueling_today: u64,
+}
+/// Thresholds beyond which root raises an alert (i.e. logs a warning and
+/// increments a metric) about a canister that it controls. Unset thresholds are
+/// not checked.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterAlertThresholds {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// Alert if the canister has fewer cycles than this.
+    #[prost(uint64, optional, tag = "2")]
+    pub min_cycles: ::core::option::Option<u64>,
+    /// Alert if the canister uses more memory, in bytes, than this.
+    #[prost(uint64, optional, tag = "3")]
+    pub max_memory_bytes: ::core::option::Option<u64>,
 }
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -68,6 +163,24 @@ pub struct RegisterDappCanistersRequest {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RegisterDappCanistersResponse {}
+/// Tells SNS root about an SNS framework extension canister (e.g. a treasury
+/// manager) that it controls.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterExtensionCanisterRequest {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The controllers that the extension canister should have in addition to
+    /// SNS root. May be empty, in which case SNS root becomes its sole
+    /// controller.
+    #[prost(message, repeated, tag = "2")]
+    pub extra_controller_principal_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterExtensionCanisterResponse {}
 /// Change control of the listed canisters to the listed principal id.
 /// Same proto in governance.proto. TODO(NNS1-1589)
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -108,6 +221,110 @@ pub mod set_dapp_controllers_response {
         pub err: ::core::option::Option<super::CanisterCallError>,
     }
 }
+/// Replaces the principals (besides SNS governance) that are allowed to call
+/// canister_status_of.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetCanisterStatusReadersRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub principal_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetCanisterStatusReadersResponse {}
+/// Replaces the alert thresholds of each listed canister. An entry with no
+/// thresholds set removes the thresholds of that canister.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetCanisterAlertThresholdsRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub canister_alert_thresholds: ::prost::alloc::vec::Vec<CanisterAlertThresholds>,
+}
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetCanisterAlertThresholdsResponse {}
+/// Configures the automatic refueling of the canisters that SNS root controls
+/// (including itself). Root converts ICP into cycles via the cycles minting
+/// canister (CMC) of the NNS.
+///
+/// The ICP comes from root's own default account on the ICP ledger, NOT from
+/// the SNS treasury, which is held by governance and which root cannot spend
+/// from. Refueling only works after the SNS funds root's account, e.g. with a
+/// TransferSnsTreasuryFunds proposal whose recipient is root, and it stops once
+/// that account runs dry.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CyclesRefuelingConfig {
+    /// Canisters with fewer cycles than this are topped up.
+    #[prost(uint64, tag = "1")]
+    pub min_cycles: u64,
+    /// The amount of ICP, in e8s, converted into cycles by each top up. The ICP
+    /// ledger transfer fee is paid on top of this.
+    #[prost(uint64, tag = "2")]
+    pub icp_e8s_per_top_up: u64,
+    /// The maximum amount of ICP, in e8s and including transfer fees, that root
+    /// spends on top ups per day (UTC).
+    #[prost(uint64, tag = "3")]
+    pub max_icp_e8s_per_day: u64,
+}
+/// Sets (or, if config is unset, removes) SnsRootCanister.cycles_refueling_config.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetCyclesRefuelingConfigRequest {
+    #[prost(message, optional, tag = "1")]
+    pub config: ::core::option::Option<CyclesRefuelingConfig>,
+}
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetCyclesRefuelingConfigResponse {}
+/// A top up whose ICP was transferred to the CMC, but that the CMC has not
+/// converted into cycles yet (see SnsRootCanister.pending_top_ups).
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PendingTopUp {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The index of the ICP ledger block of the transfer.
+    #[prost(uint64, tag = "2")]
+    pub block_index: u64,
+}
+/// Controls which controllers a dapp canister keeps when it is registered via
+/// register_dapp_canisters. Controllers other than SNS root that are not listed
+/// here are removed (unless the SNS is a testflight).
+///
+/// For example, this allows a deployment orchestrator to keep managing the
+/// dapp canisters alongside the SNS.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DappControllersPolicy {
+    /// Principals that may remain controllers of dapp canisters besides SNS
+    /// root. A principal that does not already control a dapp canister is not
+    /// added as a controller.
+    #[prost(message, repeated, tag = "1")]
+    pub allowed_extra_controller_principal_ids:
+        ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
+/// Sets (or, if policy is unset, removes) SnsRootCanister.dapp_controllers_policy.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetDappControllersPolicyRequest {
+    #[prost(message, optional, tag = "1")]
+    pub policy: ::core::option::Option<DappControllersPolicy>,
+}
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetDappControllersPolicyResponse {}
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -147,4 +364,189 @@ pub struct ListSnsCanistersResponse {
     pub archives: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
     #[prost(message, optional, tag = "7")]
     pub index: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, repeated, tag = "8")]
+    pub extensions: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
+/// An entry in the SNS root event log (see SnsRootCanister.events).
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RootEvent {
+    /// When the event was recorded, in seconds since the Unix epoch.
+    #[prost(uint64, tag = "1")]
+    pub timestamp_seconds: u64,
+    /// The principal whose request caused the event. Not set for events that
+    /// were caused by SNS root itself (e.g. during periodic tasks).
+    #[prost(message, optional, tag = "2")]
+    pub caller: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(oneof = "root_event::Payload", tags = "3, 4, 5, 6, 7, 8, 9, 10")]
+    pub payload: ::core::option::Option<root_event::Payload>,
+}
+/// Nested message and enum types in `RootEvent`.
+pub mod root_event {
+    /// Dapp canisters were added to SnsRootCanister.dapp_canister_ids.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct DappCanistersRegistered {
+        #[prost(message, repeated, tag = "1")]
+        pub dapp_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+        /// The controllers besides SNS root that the canisters kept (see
+        /// SnsRootCanister.dapp_controllers_policy).
+        #[prost(message, repeated, tag = "2")]
+        pub extra_controller_principal_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    }
+    /// Dapp canisters were removed from SnsRootCanister.dapp_canister_ids,
+    /// because SNS root handed over control of them.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct DappCanistersDeregistered {
+        #[prost(message, repeated, tag = "1")]
+        pub dapp_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    }
+    /// The controllers of dapp canisters were set via set_dapp_controllers.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct DappControllersSet {
+        /// The canisters whose controllers were successfully set.
+        #[prost(message, repeated, tag = "1")]
+        pub dapp_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+        #[prost(message, repeated, tag = "2")]
+        pub controller_principal_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+        /// The canisters whose controllers could not be set.
+        #[prost(message, repeated, tag = "3")]
+        pub failed_dapp_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    }
+    /// SNS root found that it no longer controls dapp canisters, and moved them to
+    /// SnsRootCanister.lost_control_dapp_canister_ids.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct DappCanistersControlLost {
+        #[prost(message, repeated, tag = "1")]
+        pub dapp_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    }
+    /// An extension canister was registered (or re-registered), and its
+    /// controllers were set to SNS root plus extra_controller_principal_ids.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ExtensionCanisterRegistered {
+        #[prost(message, optional, tag = "1")]
+        pub extension_canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+        #[prost(message, repeated, tag = "2")]
+        pub extra_controller_principal_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    }
+    /// SnsRootCanister.cycles_refueling_config was set (or, if config is unset,
+    /// removed).
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CyclesRefuelingConfigSet {
+        #[prost(message, optional, tag = "1")]
+        pub config: ::core::option::Option<super::CyclesRefuelingConfig>,
+    }
+    /// SNS root transferred ICP to the CMC to top up a canister with cycles.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CanisterRefueled {
+        #[prost(message, optional, tag = "1")]
+        pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+        /// The amount of ICP transferred, excluding the transfer fee.
+        #[prost(uint64, tag = "2")]
+        pub icp_e8s: u64,
+        /// The index of the ICP ledger block of the transfer.
+        #[prost(uint64, tag = "3")]
+        pub block_index: u64,
+        /// The number of cycles that the canister was topped up with, if the CMC
+        /// was notified successfully.
+        #[prost(uint64, optional, tag = "4")]
+        pub cycles: ::core::option::Option<u64>,
+        /// Why notifying the CMC failed, if it did. Unless the failure is
+        /// permanent, root notifies the CMC again on later runs, and records a
+        /// PendingTopUpResolved event once the CMC is done with the transfer.
+        #[prost(string, optional, tag = "5")]
+        pub notify_error: ::core::option::Option<::prost::alloc::string::String>,
+    }
+    /// The CMC was done with a top up that it was not done with when the
+    /// CanisterRefueled event of the top up was recorded (see
+    /// SnsRootCanister.pending_top_ups).
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PendingTopUpResolved {
+        #[prost(message, optional, tag = "1")]
+        pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+        /// The index of the ICP ledger block of the transfer.
+        #[prost(uint64, tag = "2")]
+        pub block_index: u64,
+        /// The number of cycles that the canister was topped up with, if the CMC
+        /// converted the ICP.
+        #[prost(uint64, optional, tag = "3")]
+        pub cycles: ::core::option::Option<u64>,
+        /// Why the CMC did not convert the ICP, if it did not.
+        #[prost(string, optional, tag = "4")]
+        pub notify_error: ::core::option::Option<::prost::alloc::string::String>,
+    }
+    /// SnsRootCanister.dapp_controllers_policy was set (or, if policy is unset,
+    /// removed).
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct DappControllersPolicySet {
+        #[prost(message, optional, tag = "1")]
+        pub policy: ::core::option::Option<super::DappControllersPolicy>,
+    }
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Payload {
+        #[prost(message, tag = "3")]
+        DappCanistersRegistered(DappCanistersRegistered),
+        #[prost(message, tag = "4")]
+        DappCanistersDeregistered(DappCanistersDeregistered),
+        #[prost(message, tag = "5")]
+        DappControllersSet(DappControllersSet),
+        #[prost(message, tag = "6")]
+        DappCanistersControlLost(DappCanistersControlLost),
+        #[prost(message, tag = "7")]
+        ExtensionCanisterRegistered(ExtensionCanisterRegistered),
+        #[prost(message, tag = "8")]
+        CyclesRefuelingConfigSet(CyclesRefuelingConfigSet),
+        #[prost(message, tag = "9")]
+        CanisterRefueled(CanisterRefueled),
+        #[prost(message, tag = "10")]
+        DappControllersPolicySet(DappControllersPolicySet),
+        #[prost(message, tag = "11")]
+        PendingTopUpResolved(PendingTopUpResolved),
+    }
+}
+/// Request struct for the get_root_events API on the SNS Root canister.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRootEventsRequest {
+    /// The index of the first event to return.
+    #[prost(uint64, tag = "1")]
+    pub start: u64,
+    /// The maximum number of events to return.
+    #[prost(uint64, tag = "2")]
+    pub length: u64,
+}
+/// Response struct for the get_root_events API on the SNS Root canister.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRootEventsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub events: ::prost::alloc::vec::Vec<RootEvent>,
+    #[prost(uint64, tag = "2")]
+    pub total_event_count: u64,
+    /// The index of the oldest event that is still kept. Older events were
+    /// dropped to keep the event log bounded.
+    #[prost(uint64, tag = "3")]
+    pub first_event_index: u64,
 }
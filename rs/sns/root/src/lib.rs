@@ -1,9 +1,16 @@
 use crate::{
-    logs::{ERROR, INFO},
+    logs::{ERROR, INFO, WARN},
     pb::v1::{
-        set_dapp_controllers_response, CanisterCallError, ListSnsCanistersResponse,
-        RegisterDappCanistersRequest, RegisterDappCanistersResponse, SetDappControllersRequest,
-        SetDappControllersResponse, SnsRootCanister,
+        root_event, set_dapp_controllers_response, CanisterAlertThresholds, CanisterCallError,
+        CyclesRefuelingConfig, DappControllersPolicy, GetRootEventsRequest, GetRootEventsResponse,
+        ListSnsCanistersResponse, PendingTopUp, RegisterDappCanistersRequest,
+        RegisterDappCanistersResponse, RegisterExtensionCanisterRequest,
+        RegisterExtensionCanisterResponse, RootEvent, SetCanisterAlertThresholdsRequest,
+        SetCanisterAlertThresholdsResponse, SetCanisterStatusReadersRequest,
+        SetCanisterStatusReadersResponse, SetCyclesRefuelingConfigRequest,
+        SetCyclesRefuelingConfigResponse, SetDappControllersPolicyRequest,
+        SetDappControllersPolicyResponse, SetDappControllersRequest, SetDappControllersResponse,
+        SnsRootCanister,
     },
     types::Environment,
 };
@@ -12,6 +19,7 @@ use candid::{Decode, Encode};
 use futures::{future::join_all, join};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_log::log;
+use ic_cdk::api::call::RejectionCode;
 use ic_nervous_system_clients::{
     canister_id_record::CanisterIdRecord,
     canister_status::CanisterStatusResultV2,
@@ -27,8 +35,25 @@ pub mod pb;
 pub mod types;
 
 const ONE_DAY_SECONDS: u64 = 24 * 60 * 60;
+const ONE_HOUR_SECONDS: u64 = 60 * 60;
 // The number of dapp canisters that can be registered with the SNS Root
 const DAPP_CANISTER_REGISTRATION_LIMIT: usize = 100;
+// The number of extension canisters that can be registered with the SNS Root
+const EXTENSION_CANISTER_REGISTRATION_LIMIT: usize = 10;
+// The maximum number of events returned by a single get_root_events call.
+const MAX_EVENTS_PER_RESPONSE: u64 = 100;
+// The maximum number of events kept in the event log. Periodic tasks record
+// events on every run, so the log must not grow without bounds: it is part of
+// the state that is serialized into a fixed-size buffer on upgrade.
+const MAX_EVENTS: usize = 10_000;
+// After consecutive failures, the interval of a periodic task is doubled, up to
+// this many times.
+const MAX_PERIODIC_TASK_BACKOFF_EXPONENT: u32 = 3;
+// The fee of an ICP ledger transfer, which is paid on top of each top up.
+const ICP_TRANSFER_FEE_E8S: u64 = 10_000;
+// The maximum number of extra controllers that a dapp controllers policy may
+// allow. A canister can have at most 10 controllers, one of which is root.
+const MAX_ALLOWED_EXTRA_DAPP_CONTROLLERS: usize = 9;
 
 impl From<(i32, String)> for CanisterCallError {
     fn from((code, description): (i32, String)) -> Self {
@@ -46,6 +71,92 @@ pub trait LedgerCanisterClient {
     async fn archives(&self) -> Result<Vec<ArchiveInfo>, CanisterCallError>;
 }
 
+/// A trait for converting ICP into cycles via the cycles minting canister
+/// (CMC) of the NNS, used by SNS root to refuel the canisters it controls.
+#[async_trait]
+pub trait CyclesMintingClient {
+    /// Transfers `icp_e8s` (plus the transfer fee) from the default ICP account
+    /// of SNS root (not the SNS treasury) to the account of the CMC for topping
+    /// up `canister_id`. Returns the index of the ICP ledger block of the
+    /// transfer.
+    async fn transfer_for_top_up(
+        &self,
+        canister_id: PrincipalId,
+        icp_e8s: u64,
+    ) -> Result<u64, String>;
+
+    /// Asks the CMC to convert the ICP transferred in `block_index` into cycles
+    /// for `canister_id`. Returns the number of cycles deposited. The CMC
+    /// converts each transfer at most once, so this can be retried after a
+    /// transient error.
+    async fn notify_top_up(
+        &self,
+        canister_id: PrincipalId,
+        block_index: u64,
+    ) -> Result<u128, NotifyTopUpError>;
+}
+
+/// Why the CMC did not convert a top up transfer into cycles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotifyTopUpError {
+    /// The CMC may still convert the transfer, e.g. because it could not be
+    /// reached, or because it is still processing the transfer.
+    Transient(String),
+    /// The CMC will never convert the transfer, e.g. because it refunded it.
+    Permanent(String),
+}
+
+impl std::fmt::Display for NotifyTopUpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transient(message) | Self::Permanent(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// The tasks that SNS root runs periodically, each on its own timer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PeriodicTask {
+    /// Asks the ledger about its archive canisters.
+    PollLedgerArchives,
+    /// Refreshes the status of registered dapp canisters, to detect those
+    /// that root no longer controls.
+    CheckDappCanistersControl,
+    /// Refreshes the status of canisters that have alert thresholds, and
+    /// raises alerts for those that are beyond them.
+    CheckCanisterAlertThresholds,
+    /// Tops up the canisters that are low on cycles, if cycles refueling is
+    /// enabled (see SnsRootCanister.cycles_refueling_config).
+    RefuelCanisters,
+}
+
+impl PeriodicTask {
+    pub const ALL: [PeriodicTask; 4] = [
+        PeriodicTask::PollLedgerArchives,
+        PeriodicTask::CheckDappCanistersControl,
+        PeriodicTask::CheckCanisterAlertThresholds,
+        PeriodicTask::RefuelCanisters,
+    ];
+
+    /// How often the task runs when it succeeds.
+    pub fn interval_seconds(self) -> u64 {
+        match self {
+            PeriodicTask::PollLedgerArchives => ONE_DAY_SECONDS,
+            PeriodicTask::CheckDappCanistersControl => ONE_HOUR_SECONDS,
+            PeriodicTask::CheckCanisterAlertThresholds => ONE_HOUR_SECONDS,
+            PeriodicTask::RefuelCanisters => ONE_HOUR_SECONDS,
+        }
+    }
+
+    /// How long to wait before running the task again, given how many times in
+    /// a row it has failed. Each failure doubles the interval, up to a limit,
+    /// so that a task that keeps failing does not keep burning cycles.
+    pub fn next_delay_seconds(self, consecutive_failures: u32) -> u64 {
+        let backoff_factor = 1_u64 << consecutive_failures.min(MAX_PERIODIC_TASK_BACKOFF_EXPONENT);
+        self.interval_seconds().saturating_mul(backoff_factor)
+    }
+}
+
 fn swap_remove_if<T>(v: &mut Vec<T>, predicate: impl Fn(&T) -> bool) {
     let mut i = 0;
     while i < v.len() {
@@ -67,10 +178,32 @@ pub struct GetSnsCanistersSummaryRequest {
     /// If set to true, root will update the list of canisters it owns before building the
     /// GetSnsCanistersSummaryResponse. This currently amounts to asking ledger about its archive
     /// canisters.
-    /// Only the SNS governance canister can set this field to true currently.
+    /// Only the SNS governance canister can set this field to true; requests
+    /// by other callers that set it are rejected.
     pub update_canister_list: Option<bool>,
 }
 
+/// The reason why SnsRootCanister::get_sns_canisters_summary refused to
+/// serve a request.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GetSnsCanistersSummaryError {
+    /// update_canister_list was set by a caller other than the SNS governance
+    /// canister.
+    UnauthorizedCanisterListUpdate { caller: PrincipalId },
+}
+
+impl std::fmt::Display for GetSnsCanistersSummaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnauthorizedCanisterListUpdate { caller } => write!(
+                f,
+                "Only the SNS governance canister may set update_canister_list, but the \
+                 caller is {caller}."
+            ),
+        }
+    }
+}
+
 #[derive(Default, PartialEq, Eq, Clone, Debug, candid::CandidType, candid::Deserialize)]
 pub struct GetSnsCanistersSummaryResponse {
     pub root: Option<CanisterSummary>,
@@ -80,6 +213,16 @@ pub struct GetSnsCanistersSummaryResponse {
     pub dapps: Vec<CanisterSummary>,
     pub archives: Vec<CanisterSummary>,
     pub index: Option<CanisterSummary>,
+    /// Dapp canisters that were registered, but that root has since found it
+    /// no longer controls. These are not included in `dapps`.
+    pub lost_control_dapp_canister_ids: Option<Vec<PrincipalId>>,
+    /// Registered extension canisters (See
+    /// SnsRootCanister::register_extension_canister).
+    pub extensions: Option<Vec<CanisterSummary>>,
+    /// The SNS canisters (excluding dapps and extensions) whose reported
+    /// controllers differ from the ones they are expected to have. A
+    /// non-empty list is a strong indication that something is wrong.
+    pub controller_drift: Option<Vec<ControllerDrift>>,
 }
 
 impl GetSnsCanistersSummaryResponse {
@@ -107,6 +250,10 @@ impl GetSnsCanistersSummaryResponse {
         &self.archives
     }
 
+    pub fn extension_canister_summaries(&self) -> &[CanisterSummary] {
+        self.extensions.as_deref().unwrap_or_default()
+    }
+
     pub fn index_canister_summary(&self) -> &CanisterSummary {
         self.index.as_ref().unwrap()
     }
@@ -135,6 +282,15 @@ impl CanisterSummary {
     }
 }
 
+/// An SNS canister whose controllers, as reported in its status, are not the
+/// expected ones.
+#[derive(Default, PartialEq, Eq, Clone, Debug, candid::CandidType, candid::Deserialize)]
+pub struct ControllerDrift {
+    pub canister_id: Option<PrincipalId>,
+    pub expected_controllers: Vec<PrincipalId>,
+    pub actual_controllers: Vec<PrincipalId>,
+}
+
 impl SnsRootCanister {
     pub fn governance_canister_id(&self) -> PrincipalId {
         self.governance_canister_id
@@ -159,19 +315,39 @@ impl SnsRootCanister {
     /// Return the canister status of all SNS canisters that this root canister
     /// is part of, as well as of all registered dapp canisters (See
     /// SnsRootCanister::register_dapp_canister).
+    ///
+    /// Updating the canister list first (see
+    /// GetSnsCanistersSummaryRequest::update_canister_list) polls the ledger,
+    /// and so only the governance canister may request it. Each such update is
+    /// logged and counted (see forced_canister_list_update_count).
     pub async fn get_sns_canisters_summary(
         self_ref: &'static LocalKey<RefCell<Self>>,
         management_canister_client: &impl ManagementCanisterClient,
         ledger_canister_client: &impl LedgerCanisterClient,
         env: &impl Environment,
         update_canister_list: bool,
+        caller: PrincipalId,
         root_canister_id: PrincipalId,
-    ) -> GetSnsCanistersSummaryResponse {
+    ) -> Result<GetSnsCanistersSummaryResponse, GetSnsCanistersSummaryError> {
         let current_timestamp_seconds = env.now();
 
         // Optionally update the canister list
         if update_canister_list {
-            Self::poll_for_new_archive_canisters(
+            let governance_canister_id =
+                self_ref.with(|self_ref| self_ref.borrow().governance_canister_id());
+            if caller != governance_canister_id {
+                return Err(GetSnsCanistersSummaryError::UnauthorizedCanisterListUpdate { caller });
+            }
+            log!(
+                INFO,
+                "Updating the canister list as requested by {}",
+                caller
+            );
+            self_ref.with(|self_ref| self_ref.borrow_mut().forced_canister_list_update_count += 1);
+
+            // Failures are logged by poll_for_new_archive_canisters, and the
+            // previously known archives are reported instead.
+            let _ = Self::poll_for_new_archive_canisters(
                 self_ref,
                 ledger_canister_client,
                 current_timestamp_seconds,
@@ -187,6 +363,8 @@ impl SnsRootCanister {
             dapp_canister_ids,
             archive_canister_ids,
             index_canister_id,
+            lost_control_dapp_canister_ids,
+            extension_canister_ids,
         ) = self_ref.with(|self_ref| {
             let self_ref = self_ref.borrow();
             (
@@ -196,6 +374,8 @@ impl SnsRootCanister {
                 self_ref.dapp_canister_ids.clone(),
                 self_ref.archive_canister_ids.clone(),
                 self_ref.index_canister_id(),
+                self_ref.lost_control_dapp_canister_ids.clone(),
+                self_ref.extension_canister_ids.clone(),
             )
         });
 
@@ -207,6 +387,7 @@ impl SnsRootCanister {
             swap_canister_summary,
             dapp_canister_summaries,
             archive_canister_summaries,
+            extension_canister_summaries,
         ) = join!(
             // Safe because canisters can get their own status summary
             get_owned_canister_summary(management_canister_client, root_canister_id),
@@ -219,10 +400,41 @@ impl SnsRootCanister {
             })),
             join_all(archive_canister_ids.into_iter().map(|archive_canister_id| {
                 get_owned_canister_summary(management_canister_client, archive_canister_id)
-            }))
+            })),
+            join_all(
+                extension_canister_ids
+                    .into_iter()
+                    .map(|extension_canister_id| {
+                        get_owned_canister_summary(
+                            management_canister_client,
+                            extension_canister_id,
+                        )
+                    })
+            )
         );
 
-        GetSnsCanistersSummaryResponse {
+        // Root controls Ledger, Index, and the archives, as well as Governance,
+        // which in turn controls Root. Swap is controlled by NNS Root.
+        let nns_root_canister_id = PrincipalId::from(ic_nns_constants::ROOT_CANISTER_ID);
+        let controller_drift: Vec<ControllerDrift> = [
+            (&root_canister_summary, governance_canister_id),
+            (&governance_canister_summary, root_canister_id),
+            (&ledger_canister_summary, root_canister_id),
+            (&index_canister_summary, root_canister_id),
+            (&swap_canister_summary, nns_root_canister_id),
+        ]
+        .into_iter()
+        .chain(
+            archive_canister_summaries
+                .iter()
+                .map(|summary| (summary, root_canister_id)),
+        )
+        .filter_map(|(summary, expected_controller)| {
+            detect_controller_drift(summary, &[expected_controller])
+        })
+        .collect();
+
+        Ok(GetSnsCanistersSummaryResponse {
             root: Some(root_canister_summary),
             governance: Some(governance_canister_summary),
             ledger: Some(ledger_canister_summary),
@@ -230,12 +442,16 @@ impl SnsRootCanister {
             dapps: dapp_canister_summaries.into_iter().collect(),
             archives: archive_canister_summaries.into_iter().collect(),
             index: Some(index_canister_summary),
-        }
+            lost_control_dapp_canister_ids: Some(lost_control_dapp_canister_ids),
+            extensions: Some(extension_canister_summaries),
+            controller_drift: Some(controller_drift),
+        })
     }
 
     /// Return the `PrincipalId`s of all SNS canisters that this root canister
-    /// is part of, as well as of all registered dapp canisters (See
-    /// SnsRootCanister::register_dapp_canister).
+    /// is part of, as well as of all registered dapp and extension canisters
+    /// (See SnsRootCanister::register_dapp_canister and
+    /// SnsRootCanister::register_extension_canister).
     pub fn list_sns_canisters(
         &self,
         root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
@@ -248,9 +464,308 @@ impl SnsRootCanister {
             dapps: self.dapp_canister_ids.clone(),
             archives: self.archive_canister_ids.clone(),
             index: self.index_canister_id,
+            extensions: self.extension_canister_ids.clone(),
+        }
+    }
+
+    /// Appends an event to the event log, dropping the oldest event if the
+    /// log already holds MAX_EVENTS events.
+    fn record_event(
+        &mut self,
+        timestamp_seconds: u64,
+        caller: Option<PrincipalId>,
+        payload: root_event::Payload,
+    ) {
+        self.events.push(RootEvent {
+            timestamp_seconds,
+            caller,
+            payload: Some(payload),
+        });
+        if self.events.len() > MAX_EVENTS {
+            let excess = self.events.len() - MAX_EVENTS;
+            self.events.drain(..excess);
+            self.dropped_event_count += excess as u64;
+        }
+    }
+
+    /// Returns up to `request.length` events (but no more than
+    /// MAX_EVENTS_PER_RESPONSE) from the event log, starting at index
+    /// `request.start`. Events that were dropped from the log are skipped.
+    pub fn get_root_events(&self, request: GetRootEventsRequest) -> GetRootEventsResponse {
+        let GetRootEventsRequest { start, length } = request;
+
+        let events = self
+            .events
+            .iter()
+            .skip(start.saturating_sub(self.dropped_event_count) as usize)
+            .take(length.min(MAX_EVENTS_PER_RESPONSE) as usize)
+            .cloned()
+            .collect();
+
+        GetRootEventsResponse {
+            events,
+            total_event_count: self.dropped_event_count + self.events.len() as u64,
+            first_event_index: self.dropped_event_count,
+        }
+    }
+
+    /// Replaces the principals (besides SNS governance) that are allowed to
+    /// call canister_status_of.
+    pub fn set_canister_status_readers(
+        &mut self,
+        request: SetCanisterStatusReadersRequest,
+    ) -> SetCanisterStatusReadersResponse {
+        let SetCanisterStatusReadersRequest { mut principal_ids } = request;
+        principal_ids.sort();
+        principal_ids.dedup();
+
+        self.canister_status_reader_principal_ids = principal_ids;
+        SetCanisterStatusReadersResponse {}
+    }
+
+    /// Records an archive canister that the ledger reports having just
+    /// created, so that it is known before the next poll of the ledger
+    /// archives.
+    pub fn register_ledger_archive(&mut self, archive_canister_id: PrincipalId) {
+        if !self.archive_canister_ids.contains(&archive_canister_id) {
+            self.archive_canister_ids.push(archive_canister_id);
+        }
+    }
+
+    /// Whether `canister_id` is one of the canisters that this canister (SNS
+    /// root) controls, i.e. one of the SNS canisters (other than swap), or a
+    /// registered dapp or extension canister.
+    fn is_owned_canister(
+        &self,
+        root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        canister_id: PrincipalId,
+    ) -> bool {
+        let ListSnsCanistersResponse {
+            root,
+            governance,
+            ledger,
+            // Swap is controlled by the NNS, not by root.
+            swap: _,
+            dapps,
+            archives,
+            index,
+            extensions,
+        } = self.list_sns_canisters(root_canister_id);
+
+        [root, governance, ledger, index]
+            .into_iter()
+            .flatten()
+            .chain(dapps)
+            .chain(archives)
+            .chain(extensions)
+            .any(|owned_canister_id| owned_canister_id == canister_id)
+    }
+
+    /// Sets (or, if none are given, removes) the alert thresholds of each
+    /// listed canister. Thresholds of canisters that are not listed are left
+    /// unchanged. The canisters must be controlled by this canister (SNS root).
+    pub fn set_canister_alert_thresholds(
+        &mut self,
+        root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        request: SetCanisterAlertThresholdsRequest,
+    ) -> Result<SetCanisterAlertThresholdsResponse, String> {
+        let SetCanisterAlertThresholdsRequest {
+            canister_alert_thresholds,
+        } = request;
+
+        // Validate the whole request before changing anything.
+        for thresholds in &canister_alert_thresholds {
+            let canister_id = thresholds.canister_id.ok_or_else(|| {
+                "Invalid SetCanisterAlertThresholdsRequest: canister_id field must be set."
+                    .to_string()
+            })?;
+            if !self.is_owned_canister(root_canister_id, canister_id) {
+                return Err(format!(
+                    "Canister {} is not controlled by this SNS root canister",
+                    canister_id
+                ));
+            }
+        }
+
+        for thresholds in canister_alert_thresholds {
+            self.canister_alert_thresholds
+                .retain(|existing| existing.canister_id != thresholds.canister_id);
+            if thresholds.min_cycles.is_some() || thresholds.max_memory_bytes.is_some() {
+                self.canister_alert_thresholds.push(thresholds);
+            }
+        }
+
+        Ok(SetCanisterAlertThresholdsResponse {})
+    }
+
+    /// Sets (or, if none is given, removes) the configuration of cycles
+    /// refueling. While a configuration is set, root periodically tops up
+    /// the canisters that it controls with cycles (see refuel_canisters).
+    pub fn set_cycles_refueling_config(
+        &mut self,
+        caller: PrincipalId,
+        request: SetCyclesRefuelingConfigRequest,
+        current_timestamp_seconds: u64,
+    ) -> Result<SetCyclesRefuelingConfigResponse, String> {
+        let SetCyclesRefuelingConfigRequest { config } = request;
+
+        if let Some(config) = &config {
+            let CyclesRefuelingConfig {
+                min_cycles: _,
+                icp_e8s_per_top_up,
+                max_icp_e8s_per_day,
+            } = config;
+            if *icp_e8s_per_top_up == 0 {
+                return Err(
+                    "Invalid SetCyclesRefuelingConfigRequest: icp_e8s_per_top_up must be positive."
+                        .to_string(),
+                );
+            }
+            if icp_e8s_per_top_up.saturating_add(ICP_TRANSFER_FEE_E8S) > *max_icp_e8s_per_day {
+                return Err(format!(
+                    "Invalid SetCyclesRefuelingConfigRequest: max_icp_e8s_per_day ({}) does not \
+                     allow a single top up of {} e8s plus the transfer fee of {} e8s.",
+                    max_icp_e8s_per_day, icp_e8s_per_top_up, ICP_TRANSFER_FEE_E8S
+                ));
+            }
+        }
+
+        self.cycles_refueling_config = config.clone();
+        self.record_event(
+            current_timestamp_seconds,
+            Some(caller),
+            root_event::Payload::CyclesRefuelingConfigSet(root_event::CyclesRefuelingConfigSet {
+                config,
+            }),
+        );
+
+        Ok(SetCyclesRefuelingConfigResponse {})
+    }
+
+    /// Sets (or, if `request.policy` is unset, removes) the policy that
+    /// determines which controllers besides root dapp canisters keep when they
+    /// are registered (see register_dapp_canisters).
+    pub fn set_dapp_controllers_policy(
+        &mut self,
+        root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        caller: PrincipalId,
+        request: SetDappControllersPolicyRequest,
+        current_timestamp_seconds: u64,
+    ) -> Result<SetDappControllersPolicyResponse, String> {
+        let SetDappControllersPolicyRequest { mut policy } = request;
+
+        if let Some(DappControllersPolicy {
+            allowed_extra_controller_principal_ids,
+        }) = &mut policy
+        {
+            allowed_extra_controller_principal_ids.sort();
+            allowed_extra_controller_principal_ids.dedup();
+            if allowed_extra_controller_principal_ids.contains(&PrincipalId(root_canister_id)) {
+                return Err(
+                    "Invalid SetDappControllersPolicyRequest: SNS root is always a controller of \
+                     dapp canisters, and so cannot be an extra controller."
+                        .to_string(),
+                );
+            }
+            if allowed_extra_controller_principal_ids.len() > MAX_ALLOWED_EXTRA_DAPP_CONTROLLERS {
+                return Err(format!(
+                    "Invalid SetDappControllersPolicyRequest: at most {} extra controllers \
+                     can be allowed, but {} were given.",
+                    MAX_ALLOWED_EXTRA_DAPP_CONTROLLERS,
+                    allowed_extra_controller_principal_ids.len()
+                ));
+            }
+        }
+
+        self.dapp_controllers_policy = policy.clone();
+        self.record_event(
+            current_timestamp_seconds,
+            Some(caller),
+            root_event::Payload::DappControllersPolicySet(root_event::DappControllersPolicySet {
+                policy,
+            }),
+        );
+
+        Ok(SetDappControllersPolicyResponse {})
+    }
+
+    /// The controllers besides root that dapp canisters may keep when they are
+    /// registered, according to the dapp controllers policy.
+    fn allowed_extra_dapp_controllers(&self) -> BTreeSet<PrincipalId> {
+        self.dapp_controllers_policy
+            .iter()
+            .flat_map(|policy| {
+                policy
+                    .allowed_extra_controller_principal_ids
+                    .iter()
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// The amount of ICP, in e8s and including transfer fees, that was spent on
+    /// top ups on the day (UTC) of `current_timestamp_seconds`.
+    fn icp_e8s_spent_on_refueling_today(&self, current_timestamp_seconds: u64) -> u64 {
+        if self.refueling_day == current_timestamp_seconds / ONE_DAY_SECONDS {
+            self.icp_e8s_spent_on_refueling_today
+        } else {
+            0
         }
     }
 
+    /// Adds `icp_e8s` to the ICP spent on top ups on the day (UTC) of
+    /// `current_timestamp_seconds`.
+    fn add_icp_e8s_spent_on_refueling(&mut self, current_timestamp_seconds: u64, icp_e8s: u64) {
+        self.icp_e8s_spent_on_refueling_today = self
+            .icp_e8s_spent_on_refueling_today(current_timestamp_seconds)
+            .saturating_add(icp_e8s);
+        self.refueling_day = current_timestamp_seconds / ONE_DAY_SECONDS;
+    }
+
+    /// Returns the status of a single canister that this canister (SNS root)
+    /// controls, i.e. one of the SNS canisters (other than swap), or a
+    /// registered dapp or extension canister. This is much cheaper than
+    /// get_sns_canisters_summary when only one canister is of interest.
+    ///
+    /// The caller must be SNS governance, or one of
+    /// canister_status_reader_principal_ids.
+    pub async fn canister_status_of(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        caller: PrincipalId,
+        canister_id: PrincipalId,
+    ) -> Result<CanisterStatusResultV2, String> {
+        let (is_authorized, is_owned) = self_ref.with(|state| {
+            let state = state.borrow();
+            let is_authorized = state.governance_canister_id == Some(caller)
+                || state.canister_status_reader_principal_ids.contains(&caller);
+            let is_owned = state.is_owned_canister(root_canister_id, canister_id);
+
+            (is_authorized, is_owned)
+        });
+
+        if !is_authorized {
+            return Err(format!(
+                "Caller {} is not authorized to get canister statuses from SNS root",
+                caller
+            ));
+        }
+        if !is_owned {
+            return Err(format!(
+                "Canister {} is not controlled by this SNS root canister",
+                canister_id
+            ));
+        }
+
+        let canister_id_record = CanisterIdRecord::try_from(canister_id)?;
+        management_canister_client
+            .canister_status(canister_id_record)
+            .await
+            .map(CanisterStatusResultV2::from)
+            .map_err(|err| format!("Canister status unavailable: {err:?}"))
+    }
+
     /// Tells this canister (SNS root) about a list of dapp canisters that it controls.
     ///
     /// The canisters must not be one of the distinguished SNS canisters
@@ -259,7 +774,8 @@ impl SnsRootCanister {
     /// be rejected.
     ///
     /// If there are any controllers on the canister besides root, they will be
-    /// removed.
+    /// removed, unless the dapp controllers policy allows them to remain (see
+    /// set_dapp_controllers_policy).
     ///
     /// Registered dapp canisters are used by at least two methods:
     ///   1. get_sns_canisters_summary
@@ -268,12 +784,16 @@ impl SnsRootCanister {
         self_ref: &'static LocalKey<RefCell<Self>>,
         management_canister_client: &impl ManagementCanisterClient,
         root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        caller: PrincipalId,
+        current_timestamp_seconds: u64,
         request: RegisterDappCanistersRequest,
     ) -> RegisterDappCanistersResponse {
         let result = Self::try_register_dapp_canisters(
             self_ref,
             management_canister_client,
             root_canister_id,
+            caller,
+            current_timestamp_seconds,
             request,
         )
         .await;
@@ -299,6 +819,8 @@ impl SnsRootCanister {
         self_ref: &'static LocalKey<RefCell<Self>>,
         management_canister_client: &impl ManagementCanisterClient,
         root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        caller: PrincipalId,
+        current_timestamp_seconds: u64,
         request: RegisterDappCanistersRequest,
     ) -> Result<RegisterDappCanistersResponse, Vec<(PrincipalId, String)>> {
         let testflight = self_ref.with(|self_ref| self_ref.borrow().testflight);
@@ -324,6 +846,7 @@ impl SnsRootCanister {
                 dapps,
                 archives,
                 index,
+                extensions,
             } = self_ref.with(|s| {
                 let s = s.borrow();
                 s.list_sns_canisters(root_canister_id)
@@ -338,6 +861,8 @@ impl SnsRootCanister {
             ]
             .into_iter()
             .chain(archives.into_iter())
+            // Extensions are tracked separately, and must not also be dapps.
+            .chain(extensions.into_iter())
             .collect();
             (sns_canister_ids, dapps)
         };
@@ -358,6 +883,8 @@ impl SnsRootCanister {
                 &dapps[..],
                 *canister_to_register,
                 testflight,
+                caller,
+                current_timestamp_seconds,
             )
             .await
             {
@@ -388,6 +915,8 @@ impl SnsRootCanister {
         dapps: &[PrincipalId],
         canister_to_register: PrincipalId,
         testflight: bool,
+        caller: PrincipalId,
+        current_timestamp_seconds: u64,
     ) -> Result<(), String> {
         // Reject if canister_to_register is one of the distinguished canisters in the SNS.
         if sns_canister_ids.contains(&canister_to_register) {
@@ -411,24 +940,39 @@ impl SnsRootCanister {
             .map_err(|err| format!("Canister status unavailable: {err:?}"))?;
 
         // Reject if we do not have control.
-        if !canister_status
+        let root_principal_id = PrincipalId(root_canister_id);
+        let current_controllers = canister_status
             .controllers()
-            .contains(&PrincipalId(root_canister_id))
-        {
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        if !current_controllers.contains(&root_principal_id) {
             Err("Canister is not controlled by this SNS root canister")?;
         }
 
-        // If testflight is not active, we want to make sure root is the
-        // only controller.
-        let root_is_only_controller =
-            canister_status.controllers() == vec![PrincipalId(root_canister_id)];
-        if !testflight && !root_is_only_controller {
-            // Remove all controllers except for root.
+        // If testflight is not active, we want to make sure root is the only
+        // controller, except for the controllers that the dapp controllers
+        // policy allows to remain.
+        let allowed_extra_controllers =
+            self_ref.with(|s| s.borrow().allowed_extra_dapp_controllers());
+        let desired_controllers = if testflight {
+            current_controllers.clone()
+        } else {
+            current_controllers
+                .iter()
+                .filter(|controller| {
+                    **controller == root_principal_id
+                        || allowed_extra_controllers.contains(controller)
+                })
+                .cloned()
+                .collect::<BTreeSet<_>>()
+        };
+        if current_controllers != desired_controllers {
+            // Remove all controllers except for root and the allowed ones.
             management_canister_client
                 .update_settings(UpdateSettings {
                     canister_id: canister_to_register.into(),
                     settings: CanisterSettings {
-                        controllers: Some(vec![PrincipalId(root_canister_id)]),
+                        controllers: Some(desired_controllers.iter().cloned().collect()),
                         ..Default::default()
                     },
                     sender_canister_version: management_canister_client.canister_version(),
@@ -436,25 +980,201 @@ impl SnsRootCanister {
                 .await
                 .map_err(|err| format!("Controller change failed: {err:?}"))?;
 
-            // Verify that we are the only controller.
+            // Verify that the controllers are now as desired.
             // This is a sanity check, and should never fail.
             let canister_status = management_canister_client
                 .canister_status(canister_to_register.into())
                 .await
                 .map_err(|err| format!("Canister status unavailable: {err:?}"))?;
-            if canister_status.controllers() != vec![PrincipalId(root_canister_id)] {
+            let controllers = canister_status
+                .controllers()
+                .into_iter()
+                .collect::<BTreeSet<_>>();
+            if controllers != desired_controllers {
                 Err("Controller change failed")?;
             }
         }
-        // Add canister_to_register to self.dapp_canister_ids.
+        let extra_controllers = desired_controllers
+            .into_iter()
+            .filter(|controller| *controller != root_principal_id)
+            .collect::<Vec<_>>();
+        if !extra_controllers.is_empty() {
+            log!(
+                INFO,
+                "Registering {canister_to_register} as a dapp canister, keeping the controllers \
+                 {extra_controllers:?} besides root."
+            );
+        }
+        // Add canister_to_register to self.dapp_canister_ids. If root had
+        // previously lost control of it, it is no longer considered lost.
         self_ref.with(|s| {
             let mut s = s.borrow_mut();
             let canister_to_register = PrincipalId::from(canister_to_register);
+            swap_remove_if(&mut s.lost_control_dapp_canister_ids, |element| {
+                *element == canister_to_register
+            });
             s.dapp_canister_ids.push(canister_to_register);
+            s.record_event(
+                current_timestamp_seconds,
+                Some(caller),
+                root_event::Payload::DappCanistersRegistered(root_event::DappCanistersRegistered {
+                    dapp_canister_ids: vec![canister_to_register],
+                    extra_controller_principal_ids: extra_controllers,
+                }),
+            );
         });
         Ok(())
     }
 
+    /// Tells this canister (SNS root) about an SNS framework extension canister
+    /// (e.g. a treasury manager) that it controls.
+    ///
+    /// The canister must not be one of the distinguished SNS canisters, nor a
+    /// registered dapp canister. Furthermore, the canister must be controlled
+    /// by this canister (i.e. SNS root). Otherwise, the request will be
+    /// rejected.
+    ///
+    /// Unlike dapp canisters, extension canisters may have controllers besides
+    /// root: their controllers are set to root plus
+    /// `request.extra_controller_principal_ids`. Registering an extension that
+    /// is already registered updates its controllers in the same way.
+    pub async fn register_extension_canister(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        caller: PrincipalId,
+        current_timestamp_seconds: u64,
+        request: RegisterExtensionCanisterRequest,
+    ) -> RegisterExtensionCanisterResponse {
+        let result = Self::try_register_extension_canister(
+            self_ref,
+            management_canister_client,
+            root_canister_id,
+            caller,
+            current_timestamp_seconds,
+            request,
+        )
+        .await;
+        match result {
+            Ok(response) => response,
+            Err(reason) => panic!("Registering extension canister failed: {reason}"),
+        }
+    }
+
+    // Helper function for `register_extension_canister`, which returns an
+    // error instead of panicking (for use in tests).
+    async fn try_register_extension_canister(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        root_canister_id: ic_cdk::api::management_canister::main::CanisterId,
+        caller: PrincipalId,
+        current_timestamp_seconds: u64,
+        request: RegisterExtensionCanisterRequest,
+    ) -> Result<RegisterExtensionCanisterResponse, String> {
+        let RegisterExtensionCanisterRequest {
+            canister_id,
+            extra_controller_principal_ids,
+        } = request;
+        let root_principal_id = PrincipalId(root_canister_id);
+
+        // Validate/unpack request.
+        let canister_to_register = canister_id.ok_or_else(|| {
+            "Invalid RegisterExtensionCanisterRequest: canister_id field must be set.".to_string()
+        })?;
+        let desired_controllers = std::iter::once(root_principal_id)
+            .chain(extra_controller_principal_ids)
+            .collect::<BTreeSet<_>>();
+
+        let ListSnsCanistersResponse {
+            root,
+            governance,
+            ledger,
+            swap,
+            dapps,
+            archives,
+            index,
+            extensions,
+        } = self_ref.with(|s| s.borrow().list_sns_canisters(root_canister_id));
+
+        let is_distinguished_sns_canister = [root, governance, ledger, index, swap]
+            .into_iter()
+            .flatten()
+            .chain(archives)
+            .any(|sns_canister_id| sns_canister_id == canister_to_register);
+        if is_distinguished_sns_canister {
+            return Err(
+                "Canister is a distinguished SNS canister and so cannot be registered".to_string(),
+            );
+        }
+        if dapps.contains(&canister_to_register) {
+            return Err(
+                "Canister is a registered dapp canister and so cannot be registered as an \
+                 extension"
+                    .to_string(),
+            );
+        }
+        let is_already_registered = extensions.contains(&canister_to_register);
+        if !is_already_registered && extensions.len() >= EXTENSION_CANISTER_REGISTRATION_LIMIT {
+            return Err(format!(
+                "Extension canister registration limit of {} was reached.",
+                EXTENSION_CANISTER_REGISTRATION_LIMIT
+            ));
+        }
+        let canister_to_register =
+            CanisterId::new(canister_to_register).map_err(|_| "Canister ID invalid")?;
+
+        // Make sure we are a controller by querying the management canister.
+        let canister_status = management_canister_client
+            .canister_status(canister_to_register.into())
+            .await
+            .map_err(|err| format!("Canister status unavailable: {err:?}"))?;
+        if !canister_status.controllers().contains(&root_principal_id) {
+            return Err("Canister is not controlled by this SNS root canister".to_string());
+        }
+
+        // Set the controllers, unless they are already as desired.
+        let current_controllers = canister_status
+            .controllers()
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        if current_controllers != desired_controllers {
+            management_canister_client
+                .update_settings(UpdateSettings {
+                    canister_id: canister_to_register.into(),
+                    settings: CanisterSettings {
+                        controllers: Some(desired_controllers.iter().cloned().collect()),
+                        ..Default::default()
+                    },
+                    sender_canister_version: management_canister_client.canister_version(),
+                })
+                .await
+                .map_err(|err| format!("Controller change failed: {err:?}"))?;
+        }
+
+        self_ref.with(|s| {
+            let mut s = s.borrow_mut();
+            let canister_to_register = PrincipalId::from(canister_to_register);
+            if !s.extension_canister_ids.contains(&canister_to_register) {
+                s.extension_canister_ids.push(canister_to_register);
+            }
+            s.record_event(
+                current_timestamp_seconds,
+                Some(caller),
+                root_event::Payload::ExtensionCanisterRegistered(
+                    root_event::ExtensionCanisterRegistered {
+                        extension_canister_id: Some(canister_to_register),
+                        extra_controller_principal_ids: desired_controllers
+                            .into_iter()
+                            .filter(|controller| *controller != root_principal_id)
+                            .collect(),
+                    },
+                ),
+            );
+        });
+
+        Ok(RegisterExtensionCanisterResponse {})
+    }
+
     /// Sets the controllers of registered dapp canisters.
     ///
     /// Dapp canisters can be registered via the register_dapp_canisters method.
@@ -474,6 +1194,7 @@ impl SnsRootCanister {
         management_canister_client: &'a impl ManagementCanisterClient,
         own_canister_id: ic_cdk::api::management_canister::main::CanisterId,
         caller: PrincipalId,
+        current_timestamp_seconds: u64,
         request: &'a SetDappControllersRequest,
     ) -> SetDappControllersResponse {
         let is_authorized = self_ref.with(|self_ref| {
@@ -548,6 +1269,7 @@ impl SnsRootCanister {
         // changes to external state, and we want to stay abreast of those
         // changes by not rolling back due to panic.
         let mut failed_updates = vec![];
+        let mut updated_canister_ids = vec![];
         for dapp_canister_id in &dapp_canister_ids {
             // Prepare to call management canister.
             let request = UpdateSettings {
@@ -582,6 +1304,7 @@ impl SnsRootCanister {
                     continue;
                 }
             }
+            updated_canister_ids.push(*dapp_canister_id);
 
             // If necessary, remove dapp_canister_id from self_ref.
             if !still_controlled_by_this_canister {
@@ -593,44 +1316,655 @@ impl SnsRootCanister {
             }
         }
 
+        // Record what happened.
+        self_ref.with(|self_ref| {
+            let mut self_ref = self_ref.borrow_mut();
+            self_ref.record_event(
+                current_timestamp_seconds,
+                Some(caller),
+                root_event::Payload::DappControllersSet(root_event::DappControllersSet {
+                    dapp_canister_ids: updated_canister_ids.clone(),
+                    controller_principal_ids: request.controller_principal_ids.clone(),
+                    failed_dapp_canister_ids: failed_updates
+                        .iter()
+                        .filter_map(|failed_update| failed_update.dapp_canister_id)
+                        .collect(),
+                }),
+            );
+            if !still_controlled_by_this_canister && !updated_canister_ids.is_empty() {
+                self_ref.record_event(
+                    current_timestamp_seconds,
+                    Some(caller),
+                    root_event::Payload::DappCanistersDeregistered(
+                        root_event::DappCanistersDeregistered {
+                            dapp_canister_ids: updated_canister_ids,
+                        },
+                    ),
+                );
+            }
+        });
+
         // Report what happened.
         SetDappControllersResponse { failed_updates }
     }
 
-    /// Runs periodic tasks that are not directly triggered by user input.
-    pub async fn heartbeat(
+    /// Runs one of the periodic tasks that are not directly triggered by user
+    /// input. Does nothing if the task is not yet due (e.g. because its timer
+    /// was re-armed by an upgrade).
+    ///
+    /// Returns an error if the task ran, but did not complete successfully, so
+    /// that the caller can back off before running it again.
+    pub async fn run_periodic_task(
         self_ref: &'static LocalKey<RefCell<Self>>,
+        task: PeriodicTask,
         ledger_client: &impl LedgerCanisterClient,
+        management_canister_client: &impl ManagementCanisterClient,
+        cycles_minting_client: &impl CyclesMintingClient,
+        root_canister_id: PrincipalId,
         current_timestamp_seconds: u64,
-    ) {
-        let should_poll_archives = self_ref.with(|state| {
-            let latest_poll_timestamp = state.borrow().latest_ledger_archive_poll_timestamp_seconds;
-            Self::should_poll_for_new_archive_canisters(
-                latest_poll_timestamp,
-                current_timestamp_seconds,
-            )
-        });
+    ) -> Result<(), String> {
+        if !self_ref.with(|state| {
+            state
+                .borrow()
+                .is_periodic_task_due(task, current_timestamp_seconds)
+        }) {
+            return Ok(());
+        }
 
-        if should_poll_archives {
-            SnsRootCanister::poll_for_new_archive_canisters(
-                self_ref,
-                ledger_client,
-                current_timestamp_seconds,
-            )
-            .await;
+        match task {
+            PeriodicTask::PollLedgerArchives => {
+                SnsRootCanister::poll_for_new_archive_canisters(
+                    self_ref,
+                    ledger_client,
+                    current_timestamp_seconds,
+                )
+                .await
+            }
+            PeriodicTask::CheckDappCanistersControl => {
+                SnsRootCanister::check_dapp_canisters_control(
+                    self_ref,
+                    management_canister_client,
+                    root_canister_id,
+                    current_timestamp_seconds,
+                )
+                .await
+            }
+            PeriodicTask::CheckCanisterAlertThresholds => {
+                SnsRootCanister::check_canister_alert_thresholds(
+                    self_ref,
+                    management_canister_client,
+                    current_timestamp_seconds,
+                )
+                .await
+            }
+            PeriodicTask::RefuelCanisters => {
+                SnsRootCanister::refuel_canisters(
+                    self_ref,
+                    management_canister_client,
+                    cycles_minting_client,
+                    root_canister_id,
+                    current_timestamp_seconds,
+                )
+                .await
+            }
         }
     }
 
-    /// Polls for new archives canisters from the
+    fn is_periodic_task_due(&self, task: PeriodicTask, current_timestamp_seconds: u64) -> bool {
+        match task {
+            PeriodicTask::PollLedgerArchives => Self::should_poll_for_new_archive_canisters(
+                self.latest_ledger_archive_poll_timestamp_seconds,
+                current_timestamp_seconds,
+            ),
+            PeriodicTask::CheckDappCanistersControl => Self::should_check_dapp_canisters_control(
+                self.latest_dapp_control_check_timestamp_seconds,
+                current_timestamp_seconds,
+            ),
+            PeriodicTask::CheckCanisterAlertThresholds => {
+                self.seconds_until_periodic_task_due(task, current_timestamp_seconds) == 0
+            }
+            PeriodicTask::RefuelCanisters => {
+                self.cycles_refueling_config.is_some()
+                    && self.seconds_until_periodic_task_due(task, current_timestamp_seconds) == 0
+            }
+        }
+    }
+
+    /// Returns how long to wait before `task` is next due, based on when it
+    /// last ran. Used to schedule the periodic tasks on (re)start.
+    pub fn seconds_until_periodic_task_due(
+        &self,
+        task: PeriodicTask,
+        current_timestamp_seconds: u64,
+    ) -> u64 {
+        let latest_run_timestamp_seconds = match task {
+            PeriodicTask::PollLedgerArchives => self.latest_ledger_archive_poll_timestamp_seconds,
+            PeriodicTask::CheckDappCanistersControl => {
+                self.latest_dapp_control_check_timestamp_seconds
+            }
+            PeriodicTask::CheckCanisterAlertThresholds => {
+                self.latest_canister_alert_check_timestamp_seconds
+            }
+            PeriodicTask::RefuelCanisters => self.latest_cycles_refueling_timestamp_seconds,
+        };
+        match latest_run_timestamp_seconds {
+            Some(latest_run_timestamp_seconds) => latest_run_timestamp_seconds
+                .saturating_add(task.interval_seconds())
+                .saturating_sub(current_timestamp_seconds),
+            None => 0,
+        }
+    }
+
+    /// Determine if SNS Root should check whether it still controls the
+    /// registered dapp canisters.
+    ///
+    /// Check if:
+    ///    - The latest_dapp_control_check_timestamp_seconds field is unset
+    ///    - It has been more than one hour since the last check
+    fn should_check_dapp_canisters_control(
+        latest_dapp_control_check_timestamp_seconds: Option<u64>,
+        current_timestamp_seconds: u64,
+    ) -> bool {
+        match latest_dapp_control_check_timestamp_seconds {
+            Some(latest_check_timestamp_seconds) => {
+                current_timestamp_seconds.saturating_sub(latest_check_timestamp_seconds)
+                    >= ONE_HOUR_SECONDS
+            }
+            None => true,
+        }
+    }
+
+    /// Verifies that this canister (SNS root) is still a controller of each
+    /// registered dapp canister. Dapps that root no longer controls (e.g.
+    /// because a controller was changed out-of-band, or because the canister
+    /// was deleted) are moved from dapp_canister_ids to
+    /// lost_control_dapp_canister_ids.
+    ///
+    /// Dapps whose status cannot be determined for other reasons (e.g. a
+    /// transient error) are left alone, and will be checked again later.
+    async fn check_dapp_canisters_control(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        root_canister_id: PrincipalId,
+        current_timestamp_seconds: u64,
+    ) -> Result<(), String> {
+        log!(INFO, "Checking control of dapp canisters");
+
+        // Set the latest_dapp_control_check_timestamp_seconds first so that we
+        // don't retry right away if something goes wrong.
+        let dapp_canister_ids = self_ref.with(|state| {
+            let mut state = state.borrow_mut();
+            state.latest_dapp_control_check_timestamp_seconds = Some(current_timestamp_seconds);
+            state.dapp_canister_ids.clone()
+        });
+
+        let mut lost_control_dapp_canister_ids = vec![];
+        let mut unknown_status_dapp_canister_ids = vec![];
+        for dapp_canister_id in dapp_canister_ids {
+            let is_controlled = is_canister_controlled_by(
+                management_canister_client,
+                dapp_canister_id,
+                root_canister_id,
+            )
+            .await;
+
+            match is_controlled {
+                Ok(true) => (),
+                Ok(false) => {
+                    log!(
+                        ERROR,
+                        "SNS root is no longer a controller of dapp canister {}. \
+                         It will no longer be treated as a registered dapp canister.",
+                        dapp_canister_id
+                    );
+                    lost_control_dapp_canister_ids.push(dapp_canister_id);
+                }
+                Err(err) => {
+                    log!(
+                        ERROR,
+                        "Unable to determine whether SNS root controls dapp canister {}: {}",
+                        dapp_canister_id,
+                        err
+                    );
+                    unknown_status_dapp_canister_ids.push(dapp_canister_id);
+                }
+            }
+        }
+
+        let result = if unknown_status_dapp_canister_ids.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unable to determine whether SNS root controls dapp canisters {:?}",
+                unknown_status_dapp_canister_ids
+            ))
+        };
+
+        if lost_control_dapp_canister_ids.is_empty() {
+            return result;
+        }
+
+        self_ref.with(|state| {
+            let mut state = state.borrow_mut();
+            swap_remove_if(&mut state.dapp_canister_ids, |element| {
+                lost_control_dapp_canister_ids.contains(element)
+            });
+            state.record_event(
+                current_timestamp_seconds,
+                None,
+                root_event::Payload::DappCanistersControlLost(
+                    root_event::DappCanistersControlLost {
+                        dapp_canister_ids: lost_control_dapp_canister_ids.clone(),
+                    },
+                ),
+            );
+            for dapp_canister_id in lost_control_dapp_canister_ids {
+                if !state
+                    .lost_control_dapp_canister_ids
+                    .contains(&dapp_canister_id)
+                {
+                    state.lost_control_dapp_canister_ids.push(dapp_canister_id);
+                }
+            }
+        });
+
+        result
+    }
+
+    /// Compares the status of each canister that has alert thresholds against
+    /// them. Each threshold that is crossed is reported with a WARN log entry,
+    /// and counted (see low_cycles_alert_count and high_memory_alert_count).
+    async fn check_canister_alert_thresholds(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        current_timestamp_seconds: u64,
+    ) -> Result<(), String> {
+        // Set the latest_canister_alert_check_timestamp_seconds first so that
+        // we don't retry right away if something goes wrong.
+        let canister_alert_thresholds = self_ref.with(|state| {
+            let mut state = state.borrow_mut();
+            state.latest_canister_alert_check_timestamp_seconds = Some(current_timestamp_seconds);
+            state.canister_alert_thresholds.clone()
+        });
+
+        let mut unknown_status_canister_ids = vec![];
+        for thresholds in canister_alert_thresholds {
+            let CanisterAlertThresholds {
+                canister_id,
+                min_cycles,
+                max_memory_bytes,
+            } = thresholds;
+            let Some(canister_id) = canister_id else {
+                continue;
+            };
+
+            let canister_status = match CanisterIdRecord::try_from(canister_id) {
+                Ok(canister_id_record) => management_canister_client
+                    .canister_status(canister_id_record)
+                    .await
+                    .map(CanisterStatusResultV2::from)
+                    .map_err(|err| format!("{err:?}")),
+                Err(err) => Err(err),
+            };
+            let canister_status = match canister_status {
+                Ok(canister_status) => canister_status,
+                Err(err) => {
+                    log!(
+                        ERROR,
+                        "Unable to get the status of canister {} to check its alert thresholds: {}",
+                        canister_id,
+                        err
+                    );
+                    unknown_status_canister_ids.push(canister_id);
+                    continue;
+                }
+            };
+
+            let cycles = canister_status.cycles();
+            let is_low_on_cycles = min_cycles
+                .map(|min_cycles| cycles < u128::from(min_cycles))
+                .unwrap_or(false);
+            if is_low_on_cycles {
+                log!(
+                    WARN,
+                    "SNS canister alert: kind=low_cycles canister_id={} cycles={} min_cycles={}",
+                    canister_id,
+                    cycles,
+                    min_cycles.unwrap_or_default()
+                );
+            }
+
+            let memory_bytes = canister_status.memory_size().get();
+            let is_high_on_memory = max_memory_bytes
+                .map(|max_memory_bytes| memory_bytes > max_memory_bytes)
+                .unwrap_or(false);
+            if is_high_on_memory {
+                log!(
+                    WARN,
+                    "SNS canister alert: kind=high_memory canister_id={} memory_bytes={} \
+                     max_memory_bytes={}",
+                    canister_id,
+                    memory_bytes,
+                    max_memory_bytes.unwrap_or_default()
+                );
+            }
+
+            self_ref.with(|state| {
+                let mut state = state.borrow_mut();
+                if is_low_on_cycles {
+                    state.low_cycles_alert_count += 1;
+                }
+                if is_high_on_memory {
+                    state.high_memory_alert_count += 1;
+                }
+            });
+        }
+
+        if unknown_status_canister_ids.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unable to check the alert thresholds of canisters {:?}",
+                unknown_status_canister_ids
+            ))
+        }
+    }
+
+    /// Tops up each canister that this canister (SNS root) controls and that
+    /// has fewer than cycles_refueling_config.min_cycles cycles, by converting
+    /// ICP into cycles via the CMC. Each top up is recorded as a
+    /// CanisterRefueled event, and top ups stop once the ICP spent today (UTC)
+    /// would exceed max_icp_e8s_per_day.
+    ///
+    /// The ICP comes from root's own default account on the ICP ledger, not
+    /// from the SNS treasury, so the SNS has to fund that account (e.g. with a
+    /// TransferSnsTreasuryFunds proposal) for refueling to work.
+    ///
+    /// Top ups whose ICP was transferred, but that the CMC has not converted
+    /// into cycles yet (see pending_top_ups), are completed first. Canisters
+    /// with such a pending top up are not topped up again.
+    ///
+    /// Does nothing if cycles refueling is disabled.
+    async fn refuel_canisters(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        cycles_minting_client: &impl CyclesMintingClient,
+        root_canister_id: PrincipalId,
+        current_timestamp_seconds: u64,
+    ) -> Result<(), String> {
+        // Set the latest_cycles_refueling_timestamp_seconds first so that we
+        // don't retry right away if something goes wrong.
+        let (config, canister_ids) = self_ref.with(|state| {
+            let mut state = state.borrow_mut();
+            state.latest_cycles_refueling_timestamp_seconds = Some(current_timestamp_seconds);
+            let ListSnsCanistersResponse {
+                root,
+                governance,
+                ledger,
+                // Swap is controlled by the NNS, not by root.
+                swap: _,
+                dapps,
+                archives,
+                index,
+                extensions,
+            } = state.list_sns_canisters(root_canister_id.0);
+            let canister_ids: Vec<PrincipalId> = [root, governance, ledger, index]
+                .into_iter()
+                .flatten()
+                .chain(dapps)
+                .chain(archives)
+                .chain(extensions)
+                .collect();
+            (state.cycles_refueling_config.clone(), canister_ids)
+        });
+        let Some(CyclesRefuelingConfig {
+            min_cycles,
+            icp_e8s_per_top_up,
+            max_icp_e8s_per_day,
+        }) = config
+        else {
+            return Ok(());
+        };
+
+        let mut failed_canister_ids = vec![];
+
+        let pending_top_ups = self_ref.with(|state| state.borrow().pending_top_ups.clone());
+        for pending_top_up in pending_top_ups {
+            let PendingTopUp {
+                canister_id: Some(canister_id),
+                block_index,
+            } = pending_top_up
+            else {
+                continue;
+            };
+            let notify_result = Self::notify_pending_top_up(
+                self_ref,
+                cycles_minting_client,
+                canister_id,
+                block_index,
+            )
+            .await;
+            match &notify_result {
+                Ok(cycles) => log!(
+                    INFO,
+                    "Refueled canister {} with {} cycles (block index {})",
+                    canister_id,
+                    cycles,
+                    block_index
+                ),
+                Err(NotifyTopUpError::Permanent(err)) => log!(
+                    ERROR,
+                    "The CMC did not convert the top up of canister {} in block {}: {}",
+                    canister_id,
+                    block_index,
+                    err
+                ),
+                Err(NotifyTopUpError::Transient(err)) => {
+                    log!(
+                        ERROR,
+                        "Still unable to notify the CMC of the top up of canister {} in \
+                         block {}: {}",
+                        canister_id,
+                        block_index,
+                        err
+                    );
+                    failed_canister_ids.push(canister_id);
+                    continue;
+                }
+            }
+
+            self_ref.with(|state| {
+                state.borrow_mut().record_event(
+                    current_timestamp_seconds,
+                    None,
+                    root_event::Payload::PendingTopUpResolved(root_event::PendingTopUpResolved {
+                        canister_id: Some(canister_id),
+                        block_index,
+                        cycles: notify_result
+                            .as_ref()
+                            .ok()
+                            .map(|cycles| u64::try_from(*cycles).unwrap_or(u64::MAX)),
+                        notify_error: notify_result.err().map(|err| err.to_string()),
+                    }),
+                )
+            });
+        }
+
+        for canister_id in canister_ids {
+            // Topping up a canister again before the CMC converted its pending
+            // top up would waste ICP.
+            let has_pending_top_up = self_ref.with(|state| {
+                state
+                    .borrow()
+                    .pending_top_ups
+                    .iter()
+                    .any(|top_up| top_up.canister_id == Some(canister_id))
+            });
+            if has_pending_top_up {
+                continue;
+            }
+
+            let canister_status = match CanisterIdRecord::try_from(canister_id) {
+                Ok(canister_id_record) => management_canister_client
+                    .canister_status(canister_id_record)
+                    .await
+                    .map(CanisterStatusResultV2::from)
+                    .map_err(|err| format!("{err:?}")),
+                Err(err) => Err(err),
+            };
+            let cycles = match canister_status {
+                Ok(canister_status) => canister_status.cycles(),
+                Err(err) => {
+                    log!(
+                        ERROR,
+                        "Unable to get the status of canister {} to check whether it needs \
+                         to be refueled: {}",
+                        canister_id,
+                        err
+                    );
+                    failed_canister_ids.push(canister_id);
+                    continue;
+                }
+            };
+            if cycles >= u128::from(min_cycles) {
+                continue;
+            }
+
+            let icp_e8s_spent_today = self_ref.with(|state| {
+                state
+                    .borrow()
+                    .icp_e8s_spent_on_refueling_today(current_timestamp_seconds)
+            });
+            let top_up_cost_e8s = icp_e8s_per_top_up.saturating_add(ICP_TRANSFER_FEE_E8S);
+            if icp_e8s_spent_today.saturating_add(top_up_cost_e8s) > max_icp_e8s_per_day {
+                log!(
+                    WARN,
+                    "Not refueling canister {} (cycles={}): {} e8s of ICP were already spent \
+                     on top ups today, and max_icp_e8s_per_day is {}",
+                    canister_id,
+                    cycles,
+                    icp_e8s_spent_today,
+                    max_icp_e8s_per_day
+                );
+                break;
+            }
+
+            let block_index = match cycles_minting_client
+                .transfer_for_top_up(canister_id, icp_e8s_per_top_up)
+                .await
+            {
+                Ok(block_index) => block_index,
+                Err(err) => {
+                    log!(
+                        ERROR,
+                        "Unable to transfer ICP to the CMC to refuel canister {}: {}",
+                        canister_id,
+                        err
+                    );
+                    failed_canister_ids.push(canister_id);
+                    continue;
+                }
+            };
+
+            // Remember the transfer before notifying the CMC, so that the top
+            // up is completed by a later run if notifying fails.
+            self_ref.with(|state| {
+                let mut state = state.borrow_mut();
+                state.add_icp_e8s_spent_on_refueling(current_timestamp_seconds, top_up_cost_e8s);
+                state.pending_top_ups.push(PendingTopUp {
+                    canister_id: Some(canister_id),
+                    block_index,
+                });
+            });
+
+            let notify_result = Self::notify_pending_top_up(
+                self_ref,
+                cycles_minting_client,
+                canister_id,
+                block_index,
+            )
+            .await;
+            match &notify_result {
+                Ok(cycles) => log!(
+                    INFO,
+                    "Refueled canister {} with {} cycles (block index {})",
+                    canister_id,
+                    cycles,
+                    block_index
+                ),
+                Err(err) => {
+                    log!(
+                        ERROR,
+                        "Unable to notify the CMC of the top up of canister {} in block {}: {}",
+                        canister_id,
+                        block_index,
+                        err
+                    );
+                    failed_canister_ids.push(canister_id);
+                }
+            }
+
+            self_ref.with(|state| {
+                state.borrow_mut().record_event(
+                    current_timestamp_seconds,
+                    None,
+                    root_event::Payload::CanisterRefueled(root_event::CanisterRefueled {
+                        canister_id: Some(canister_id),
+                        icp_e8s: icp_e8s_per_top_up,
+                        block_index,
+                        cycles: notify_result
+                            .as_ref()
+                            .ok()
+                            .map(|cycles| u64::try_from(*cycles).unwrap_or(u64::MAX)),
+                        notify_error: notify_result.err().map(|err| err.to_string()),
+                    }),
+                )
+            });
+        }
+
+        if failed_canister_ids.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unable to refuel canisters {:?}",
+                failed_canister_ids
+            ))
+        }
+    }
+
+    /// Notifies the CMC of the top up of `canister_id` in `block_index`, which
+    /// must be in pending_top_ups. The top up is removed from pending_top_ups,
+    /// unless notifying fails transiently.
+    async fn notify_pending_top_up(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        cycles_minting_client: &impl CyclesMintingClient,
+        canister_id: PrincipalId,
+        block_index: u64,
+    ) -> Result<u128, NotifyTopUpError> {
+        let result = cycles_minting_client
+            .notify_top_up(canister_id, block_index)
+            .await;
+        if !matches!(result, Err(NotifyTopUpError::Transient(_))) {
+            self_ref.with(|state| {
+                state
+                    .borrow_mut()
+                    .pending_top_ups
+                    .retain(|top_up| top_up.block_index != block_index)
+            });
+        }
+        result
+    }
+
+    /// Polls for new archives canisters from the
     async fn poll_for_new_archive_canisters(
         self_ref: &'static LocalKey<RefCell<Self>>,
         ledger_client: &impl LedgerCanisterClient,
         current_timestamp_seconds: u64,
-    ) {
+    ) -> Result<(), String> {
         log!(INFO, "Polling for new archive canisters");
 
         // Set the latest_ledger_archive_poll_timestamp_seconds so that if the call fails,
-        // we won't retry on every heartbeat
+        // we won't retry right away
         self_ref.with(|state| {
             state
                 .borrow_mut()
@@ -649,7 +1983,10 @@ impl SnsRootCanister {
                     "Unable to get the Ledger Archives: {:?}",
                     canister_call_error
                 );
-                return;
+                return Err(format!(
+                    "Unable to get the Ledger Archives: {:?}",
+                    canister_call_error
+                ));
             }
         };
 
@@ -672,11 +2009,15 @@ impl SnsRootCanister {
                     "Defects detected between polls of archive canisters: {}",
                     defects
                 );
-                return;
+                return Err(format!(
+                    "Defects detected between polls of archive canisters: {}",
+                    defects
+                ));
             }
 
             state.borrow_mut().archive_canister_ids = archive_principals_ids;
-        });
+            Ok(())
+        })
     }
 
     /// Determine if SNS Root should poll for new SNS Ledger archive canisters.
@@ -719,6 +2060,35 @@ impl SnsRootCanister {
     }
 }
 
+/// Returns whether `controller` is among the controllers of `canister_id`.
+///
+/// The management canister rejects `canister_status` requests from
+/// non-controllers with CanisterError, and requests about nonexistent
+/// canisters with DestinationInvalid. In both cases, `controller` (assumed to
+/// be the caller) does not control the canister. Any other error is returned
+/// as is, since it says nothing conclusive about control.
+async fn is_canister_controlled_by(
+    management_canister_client: &impl ManagementCanisterClient,
+    canister_id: PrincipalId,
+    controller: PrincipalId,
+) -> Result<bool, String> {
+    let canister_id_record = CanisterIdRecord::try_from(canister_id)
+        .map_err(|err| format!("Invalid canister ID: {err}"))?;
+
+    match management_canister_client
+        .canister_status(canister_id_record)
+        .await
+    {
+        Ok(canister_status) => Ok(canister_status.controllers().contains(&controller)),
+        Err((code, description)) => match RejectionCode::from(code) {
+            RejectionCode::CanisterError | RejectionCode::DestinationInvalid => Ok(false),
+            _ => Err(format!(
+                "Canister status unavailable: code {code}: {description}"
+            )),
+        },
+    }
+}
+
 async fn get_swap_status(env: &impl Environment, swap_id: PrincipalId) -> CanisterSummary {
     let Ok(canister_id) = CanisterId::new(swap_id) else {
         log!(
@@ -760,6 +2130,36 @@ async fn get_swap_status(env: &impl Environment, swap_id: PrincipalId) -> Canist
     }
 }
 
+/// Returns a `ControllerDrift` if the controllers reported in the status of the
+/// canister are not exactly `expected_controllers`. Nothing is reported for
+/// canisters whose status could not be fetched.
+fn detect_controller_drift(
+    summary: &CanisterSummary,
+    expected_controllers: &[PrincipalId],
+) -> Option<ControllerDrift> {
+    let status = summary.status.as_ref()?;
+    let actual_controllers = status.controllers();
+
+    let expected: BTreeSet<_> = expected_controllers.iter().collect();
+    let actual: BTreeSet<_> = actual_controllers.iter().collect();
+    if expected == actual {
+        return None;
+    }
+
+    log!(
+        WARN,
+        "The controllers of canister {:?} are {:?}, but {:?} were expected.",
+        summary.canister_id,
+        actual_controllers,
+        expected_controllers,
+    );
+    Some(ControllerDrift {
+        canister_id: summary.canister_id,
+        expected_controllers: expected_controllers.to_vec(),
+        actual_controllers: actual_controllers.to_vec(),
+    })
+}
+
 async fn get_owned_canister_summary(
     management_canister_client: &impl ManagementCanisterClient,
     canister_id: PrincipalId,
@@ -852,42 +2252,115 @@ mod tests {
     }
 
     #[derive(Debug, Clone)]
-    enum EnvironmentCall {
-        CallCanister {
-            expected_canister: CanisterId,
-            expected_method: String,
-            expected_bytes: Option<Vec<u8>>,
-            result: Result<Vec<u8>, (i32, String)>,
+    enum CyclesMintingClientCall {
+        TransferForTopUp {
+            expected_canister_id: PrincipalId,
+            expected_icp_e8s: u64,
+            result: Result<u64, String>,
+        },
+        NotifyTopUp {
+            expected_canister_id: PrincipalId,
+            expected_block_index: u64,
+            result: Result<u128, NotifyTopUpError>,
         },
     }
 
-    struct TestEnvironment {
-        calls: Arc<Mutex<VecDeque<EnvironmentCall>>>,
+    #[derive(Debug, Clone)]
+    struct MockCyclesMintingClient {
+        calls: Arc<futures::lock::Mutex<VecDeque<CyclesMintingClientCall>>>,
     }
 
-    #[async_trait]
-    impl Environment for TestEnvironment {
-        fn now(&self) -> u64 {
-            NOW
+    impl MockCyclesMintingClient {
+        fn new<T>(calls: T) -> Self
+        where
+            VecDeque<CyclesMintingClientCall>: From<T>,
+        {
+            Self {
+                calls: Arc::new(futures::lock::Mutex::new(calls.into())),
+            }
         }
+    }
 
-        async fn call_canister(
+    #[async_trait]
+    impl CyclesMintingClient for MockCyclesMintingClient {
+        async fn transfer_for_top_up(
             &self,
-            canister_id: CanisterId,
-            method_name: &str,
-            arg: Vec<u8>,
-        ) -> Result<Vec<u8>, (i32, String)> {
-            let mut calls = self.calls.lock().unwrap();
+            canister_id: PrincipalId,
+            icp_e8s: u64,
+        ) -> Result<u64, String> {
+            let mut calls = self.calls.lock().await;
             match calls.pop_front().unwrap() {
-                EnvironmentCall::CallCanister {
-                    expected_canister,
-                    expected_method,
-                    expected_bytes,
+                CyclesMintingClientCall::TransferForTopUp {
+                    expected_canister_id,
+                    expected_icp_e8s,
                     result,
                 } => {
-                    if expected_canister != canister_id || !expected_method.eq(method_name) {
-                        panic!(
-                            "An unexpected call_canister call was made. \
+                    assert_eq!(canister_id, expected_canister_id);
+                    assert_eq!(icp_e8s, expected_icp_e8s);
+                    result
+                }
+                call => panic!("Unexpected call to transfer_for_top_up, expected {call:?}"),
+            }
+        }
+
+        async fn notify_top_up(
+            &self,
+            canister_id: PrincipalId,
+            block_index: u64,
+        ) -> Result<u128, NotifyTopUpError> {
+            let mut calls = self.calls.lock().await;
+            match calls.pop_front().unwrap() {
+                CyclesMintingClientCall::NotifyTopUp {
+                    expected_canister_id,
+                    expected_block_index,
+                    result,
+                } => {
+                    assert_eq!(canister_id, expected_canister_id);
+                    assert_eq!(block_index, expected_block_index);
+                    result
+                }
+                call => panic!("Unexpected call to notify_top_up, expected {call:?}"),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum EnvironmentCall {
+        CallCanister {
+            expected_canister: CanisterId,
+            expected_method: String,
+            expected_bytes: Option<Vec<u8>>,
+            result: Result<Vec<u8>, (i32, String)>,
+        },
+    }
+
+    struct TestEnvironment {
+        calls: Arc<Mutex<VecDeque<EnvironmentCall>>>,
+    }
+
+    #[async_trait]
+    impl Environment for TestEnvironment {
+        fn now(&self) -> u64 {
+            NOW
+        }
+
+        async fn call_canister(
+            &self,
+            canister_id: CanisterId,
+            method_name: &str,
+            arg: Vec<u8>,
+        ) -> Result<Vec<u8>, (i32, String)> {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.pop_front().unwrap() {
+                EnvironmentCall::CallCanister {
+                    expected_canister,
+                    expected_method,
+                    expected_bytes,
+                    result,
+                } => {
+                    if expected_canister != canister_id || !expected_method.eq(method_name) {
+                        panic!(
+                            "An unexpected call_canister call was made. \
                             Should have been {expected_canister:#?}, {expected_method}. \
                             instead: {canister_id:#?} {method_name} (bytes omitted)\n \
                             {} calls remaining on stack",
@@ -919,6 +2392,41 @@ mod tests {
             latest_ledger_archive_poll_timestamp_seconds: None,
             index_canister_id: Some(PrincipalId::new_user_test_id(4)),
             testflight,
+            lost_control_dapp_canister_ids: vec![],
+            latest_dapp_control_check_timestamp_seconds: None,
+            events: vec![],
+            extension_canister_ids: vec![],
+            canister_status_reader_principal_ids: vec![],
+            canister_alert_thresholds: vec![],
+            latest_canister_alert_check_timestamp_seconds: None,
+            low_cycles_alert_count: 0,
+            high_memory_alert_count: 0,
+            cycles_refueling_config: None,
+            latest_cycles_refueling_timestamp_seconds: None,
+            dapp_controllers_policy: None,
+            forced_canister_list_update_count: 0,
+            dropped_event_count: 0,
+            pending_top_ups: vec![],
+            refueling_day: 0,
+            icp_e8s_spent_on_refueling_today: 0,
+        }
+    }
+
+    // Helper function to build the event that registering a dapp canister is
+    // expected to record, when done by governance at NOW.
+    fn dapp_canister_registered_event(
+        dapp_canister_id: PrincipalId,
+        extra_controller_principal_ids: Vec<PrincipalId>,
+    ) -> RootEvent {
+        RootEvent {
+            timestamp_seconds: NOW,
+            caller: Some(PrincipalId::new_user_test_id(1)),
+            payload: Some(root_event::Payload::DappCanistersRegistered(
+                root_event::DappCanistersRegistered {
+                    dapp_canister_ids: vec![dapp_canister_id],
+                    extra_controller_principal_ids,
+                },
+            )),
         }
     }
 
@@ -974,6 +2482,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![dapp_canister_id_1, dapp_canister_id_2],
             },
@@ -990,6 +2500,10 @@ mod tests {
                     // dapp, since that is the whole point of calling notify_*,
                     // the code under test.
                     dapp_canister_ids: vec![dapp_canister_id_1, dapp_canister_id_2],
+                    events: vec![
+                        dapp_canister_registered_event(dapp_canister_id_1, vec![user_id]),
+                        dapp_canister_registered_event(dapp_canister_id_2, vec![user_id])
+                    ],
                     ..original_sns_root_canister
                 }
             );
@@ -1039,6 +2553,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![dapp_canister_id_1, dapp_canister_id_2],
             },
@@ -1054,6 +2570,10 @@ mod tests {
                     // Most importantly, root became aware that it controls the
                     // dapp
                     dapp_canister_ids: vec![dapp_canister_id_1, dapp_canister_id_2],
+                    events: vec![
+                        dapp_canister_registered_event(dapp_canister_id_1, vec![]),
+                        dapp_canister_registered_event(dapp_canister_id_2, vec![])
+                    ],
                     ..original_sns_root_canister
                 }
             );
@@ -1097,6 +2617,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![dapp_canister_id_1, dapp_canister_id_1],
             },
@@ -1112,6 +2634,7 @@ mod tests {
                     // Most importantly, root became aware that it controls the
                     // dapp
                     dapp_canister_ids: vec![dapp_canister_id_1],
+                    events: vec![dapp_canister_registered_event(dapp_canister_id_1, vec![])],
                     ..original_sns_root_canister
                 }
             );
@@ -1154,6 +2677,8 @@ mod tests {
                 &SNS_ROOT_CANISTER,
                 &management_canister_client,
                 sns_root_canister_id.into(),
+                SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+                NOW,
                 RegisterDappCanistersRequest {
                     canister_ids: vec![dapp_canister_id_1],
                 },
@@ -1168,6 +2693,7 @@ mod tests {
                     // Most importantly, root became aware that it controls the
                     // dapp
                     dapp_canister_ids: vec![dapp_canister_id_1],
+                    events: vec![dapp_canister_registered_event(dapp_canister_id_1, vec![])],
                     ..original_sns_root_canister
                 }
             );
@@ -1229,6 +2755,8 @@ mod tests {
                         &SNS_ROOT_CANISTER,
                         &management_canister_client,
                         sns_root_canister_id.into(),
+                        SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+                        NOW,
                         RegisterDappCanistersRequest {
                             canister_ids: vec![canister_id],
                         },
@@ -1266,6 +2794,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![dapp_canister_id_1],
             },
@@ -1295,6 +2825,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![dapp_canister_id],
             },
@@ -1355,6 +2887,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![
                     dapp_canister_id_1,
@@ -1438,6 +2972,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![dapp_canister_id_1],
             },
@@ -1504,6 +3040,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![dapp_canister_id_1, dapp_canister_id_2, dapp_canister_id_3],
             },
@@ -1523,6 +3061,11 @@ mod tests {
                         dapp_canister_id_2,
                         dapp_canister_id_3
                     ],
+                    events: vec![
+                        dapp_canister_registered_event(dapp_canister_id_1, vec![]),
+                        dapp_canister_registered_event(dapp_canister_id_2, vec![]),
+                        dapp_canister_registered_event(dapp_canister_id_3, vec![])
+                    ],
                     ..original_sns_root_canister
                 }
             );
@@ -1579,6 +3122,108 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn register_dapp_canisters_keeps_controllers_allowed_by_policy() {
+        // Step 1: Prepare the world.
+        let orchestrator_id = PrincipalId::new_user_test_id(50);
+        thread_local! {
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                dapp_controllers_policy: Some(DappControllersPolicy {
+                    allowed_extra_controller_principal_ids: vec![PrincipalId::new_user_test_id(50)],
+                }),
+                ..build_test_sns_root_canister(false)
+            });
+        }
+        let original_sns_root_canister = SNS_ROOT_CANISTER.with(|r| r.borrow().clone());
+        let sns_root_canister_id = PrincipalId::new_user_test_id(4);
+        let dapp_canister_id_1 = PrincipalId::new_user_test_id(5);
+        let dapp_canister_id_2 = PrincipalId::new_user_test_id(6);
+        let user_id = PrincipalId::new_user_test_id(51);
+
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            // The first canister is controlled by root, the orchestrator and a
+            // user. Only the user is removed.
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    sns_root_canister_id,
+                    orchestrator_id,
+                    user_id,
+                ]),
+            )),
+            MockManagementCanisterClientReply::UpdateSettings(Ok(())),
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    sns_root_canister_id,
+                    orchestrator_id,
+                ]),
+            )),
+            // The second canister is already controlled only by root and the
+            // orchestrator, so its controllers are left alone.
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    orchestrator_id,
+                    sns_root_canister_id,
+                ]),
+            )),
+        ]);
+
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::register_dapp_canisters(
+            &SNS_ROOT_CANISTER,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
+            RegisterDappCanistersRequest {
+                canister_ids: vec![dapp_canister_id_1, dapp_canister_id_2],
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(result, RegisterDappCanistersResponse {}, "{result:#?}");
+        SNS_ROOT_CANISTER.with(|r| {
+            assert_eq!(
+                *r.borrow(),
+                SnsRootCanister {
+                    dapp_canister_ids: vec![dapp_canister_id_1, dapp_canister_id_2],
+                    events: vec![
+                        dapp_canister_registered_event(dapp_canister_id_1, vec![orchestrator_id]),
+                        dapp_canister_registered_event(dapp_canister_id_2, vec![orchestrator_id])
+                    ],
+                    ..original_sns_root_canister
+                }
+            );
+        });
+
+        let mut expected_controllers = vec![sns_root_canister_id, orchestrator_id];
+        expected_controllers.sort();
+        let actual_management_canister_calls = management_canister_client.get_calls_snapshot();
+        let expected_management_canister_calls = vec![
+            MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
+                canister_id: CanisterId::try_from(dapp_canister_id_1).unwrap(),
+            }),
+            MockManagementCanisterClientCall::UpdateSettings(UpdateSettings {
+                canister_id: dapp_canister_id_1,
+                settings: CanisterSettings {
+                    controllers: Some(expected_controllers),
+                    ..Default::default()
+                },
+                sender_canister_version: None,
+            }),
+            MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
+                canister_id: CanisterId::try_from(dapp_canister_id_1).unwrap(),
+            }),
+            MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
+                canister_id: CanisterId::try_from(dapp_canister_id_2).unwrap(),
+            }),
+        ];
+        assert_eq!(
+            actual_management_canister_calls,
+            expected_management_canister_calls
+        );
+    }
+
     #[tokio::test]
     async fn register_dapp_canisters_redundant() {
         // Step 1: Prepare the world.
@@ -1604,6 +3249,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![DAPP_CANISTER_ID.with(|i| *i)],
             },
@@ -1668,6 +3315,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: canister_ids.clone(),
             },
@@ -1687,6 +3336,8 @@ mod tests {
             &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
             RegisterDappCanistersRequest {
                 canister_ids: vec![CanisterId::from(201).get()],
             },
@@ -1694,73 +3345,78 @@ mod tests {
         .await;
     }
 
-    #[test]
-    fn test_swap_remove_if() {
-        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-        swap_remove_if(&mut v, |e| e % 2 == 0);
-        assert_eq!(v, vec![1, 9, 3, 7, 5],);
-    }
-
     #[tokio::test]
-    async fn test_set_dapp_controllers_set_all() {
+    async fn register_extension_canister_happy() {
         // Step 1: Prepare the world.
         thread_local! {
-            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
-                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
-                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
-                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
-                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
-                archive_canister_ids: vec![],
-                index_canister_id: Some(PrincipalId::new_user_test_id(4)),
-                ..Default::default()
-            });
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
         }
-        let sns_root_canister_id = PrincipalId::new_user_test_id(4);
-        let new_controller_principal_id = PrincipalId::new_user_test_id(5);
+        let sns_root_canister_id = PrincipalId::new_user_test_id(5);
+        let extension_canister_id = PrincipalId::new_user_test_id(6);
+        let extra_controller_id = PrincipalId::new_user_test_id(7);
+        let developer_id = PrincipalId::new_user_test_id(8);
 
-        // Step 1.1: Prepare helpers.
         let management_canister_client = MockManagementCanisterClient::new(vec![
             MockManagementCanisterClientReply::CanisterStatus(Ok(
                 CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
                     sns_root_canister_id,
+                    developer_id,
                 ]),
             )),
             MockManagementCanisterClientReply::UpdateSettings(Ok(())),
         ]);
 
-        // Step 2: Run code under test.
-        let response = SnsRootCanister::set_dapp_controllers(
-            &STATE,
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::try_register_extension_canister(
+            &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
-            STATE.with(|state| state.borrow().swap_canister_id.unwrap()),
-            &SetDappControllersRequest {
-                // Change controller to all dapps controlled by the root canister.
-                canister_ids: None,
-                controller_principal_ids: vec![new_controller_principal_id],
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
+            RegisterExtensionCanisterRequest {
+                canister_id: Some(extension_canister_id),
+                extra_controller_principal_ids: vec![extra_controller_id, extra_controller_id],
             },
         )
         .await;
 
         // Step 3: Inspect results.
-        assert_eq!(
-            response,
-            SetDappControllersResponse {
-                failed_updates: vec![]
-            }
-        );
-        let state = &STATE.with(|state| state.borrow().clone());
-        assert!(state.dapp_canister_ids.is_empty(), "{state:#?}",);
+        assert_eq!(result, Ok(RegisterExtensionCanisterResponse {}));
+        SNS_ROOT_CANISTER.with(|state| {
+            let state = state.borrow();
+            assert_eq!(state.extension_canister_ids, vec![extension_canister_id]);
+            // Extensions are not dapps.
+            assert_eq!(state.dapp_canister_ids, vec![]);
+            assert_eq!(
+                state.events,
+                vec![RootEvent {
+                    timestamp_seconds: NOW,
+                    caller: Some(PrincipalId::new_user_test_id(1)),
+                    payload: Some(root_event::Payload::ExtensionCanisterRegistered(
+                        root_event::ExtensionCanisterRegistered {
+                            extension_canister_id: Some(extension_canister_id),
+                            extra_controller_principal_ids: vec![extra_controller_id],
+                        },
+                    )),
+                }]
+            );
+        });
 
         let actual_management_canister_calls = management_canister_client.get_calls_snapshot();
         let expected_management_canister_calls = vec![
             MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
-                canister_id: CanisterId::try_from(PrincipalId::new_user_test_id(3)).unwrap(),
+                canister_id: CanisterId::try_from(extension_canister_id).unwrap(),
             }),
             MockManagementCanisterClientCall::UpdateSettings(UpdateSettings {
-                canister_id: PrincipalId::new_user_test_id(3),
+                canister_id: extension_canister_id,
                 settings: CanisterSettings {
-                    controllers: Some(vec![new_controller_principal_id]),
+                    controllers: Some(
+                        vec![sns_root_canister_id, extra_controller_id]
+                            .into_iter()
+                            .collect::<BTreeSet<_>>()
+                            .into_iter()
+                            .collect(),
+                    ),
                     ..Default::default()
                 },
                 sender_canister_version: None,
@@ -1770,59 +3426,228 @@ mod tests {
             actual_management_canister_calls,
             expected_management_canister_calls
         );
+        SNS_ROOT_CANISTER.with(|state| {
+            assert_eq!(
+                state
+                    .borrow()
+                    .list_sns_canisters(sns_root_canister_id.into())
+                    .extensions,
+                vec![extension_canister_id]
+            );
+        });
     }
 
     #[tokio::test]
-    #[should_panic]
-    async fn test_set_dapp_controllers_set_all_not_usable_by_governance() {
+    async fn register_extension_canister_sad_root_not_controller() {
         // Step 1: Prepare the world.
         thread_local! {
-            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
-                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
-                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
-                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
-                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
-                archive_canister_ids: vec![],
-                index_canister_id: Some(PrincipalId::new_user_test_id(4)),
-                ..Default::default()
-            });
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
         }
-        let sns_root_canister_id = PrincipalId::new_user_test_id(4);
-        let new_controller_principal_id = PrincipalId::new_user_test_id(5);
+        let sns_root_canister_id = PrincipalId::new_user_test_id(5);
+        let extension_canister_id = PrincipalId::new_user_test_id(6);
 
-        // Step 1.1: Prepare helpers.
-        let management_canister_client = MockManagementCanisterClient::new(vec![]);
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    PrincipalId::new_user_test_id(8),
+                ]),
+            )),
+        ]);
 
-        // Step 2: Run code under test.
-        // We except to panic here, because we're passing the governance canister
-        // as the caller while attempting to deregister all canisters. That functionality
-        // can only be used by the swap canister – see NNS1-1989.
-        let _response = SnsRootCanister::set_dapp_controllers(
-            &STATE,
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::try_register_extension_canister(
+            &SNS_ROOT_CANISTER,
             &management_canister_client,
             sns_root_canister_id.into(),
-            STATE.with(|state| state.borrow().governance_canister_id.unwrap()),
-            &SetDappControllersRequest {
-                // Change controller to all dapps controlled by the root canister.
-                canister_ids: None,
-                controller_principal_ids: vec![new_controller_principal_id],
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
+            RegisterExtensionCanisterRequest {
+                canister_id: Some(extension_canister_id),
+                extra_controller_principal_ids: vec![],
             },
         )
         .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(
+            result,
+            Err("Canister is not controlled by this SNS root canister".to_string())
+        );
+        SNS_ROOT_CANISTER.with(|state| {
+            let state = state.borrow();
+            assert_eq!(state.extension_canister_ids, vec![]);
+            assert_eq!(state.events, vec![]);
+        });
     }
 
     #[tokio::test]
-    #[should_panic]
-    async fn test_set_dapp_controllers_set_some_usable_by_governance() {
+    async fn register_extension_canister_rejects_dapp_canister() {
         // Step 1: Prepare the world.
+        let dapp_canister_id = PrincipalId::new_user_test_id(6);
         thread_local! {
-            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
-                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
-                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
-                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
-                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
-                archive_canister_ids: vec![],
-                index_canister_id: Some(PrincipalId::new_user_test_id(4)),
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(6)],
+                ..build_test_sns_root_canister(false)
+            });
+        }
+        let sns_root_canister_id = PrincipalId::new_user_test_id(5);
+
+        // No calls to the management canister are expected.
+        let management_canister_client = MockManagementCanisterClient::new(vec![]);
+
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::try_register_extension_canister(
+            &SNS_ROOT_CANISTER,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
+            RegisterExtensionCanisterRequest {
+                canister_id: Some(dapp_canister_id),
+                extra_controller_principal_ids: vec![],
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        let err = result.unwrap_err();
+        assert!(err.contains("registered dapp canister"), "{err}");
+        SNS_ROOT_CANISTER.with(|state| {
+            assert_eq!(state.borrow().extension_canister_ids, vec![]);
+        });
+    }
+
+    #[test]
+    fn test_swap_remove_if() {
+        let mut v = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
+        swap_remove_if(&mut v, |e| e % 2 == 0);
+        assert_eq!(v, vec![1, 9, 3, 7, 5],);
+    }
+
+    #[tokio::test]
+    async fn test_set_dapp_controllers_set_all() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
+                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                archive_canister_ids: vec![],
+                index_canister_id: Some(PrincipalId::new_user_test_id(4)),
+                ..Default::default()
+            });
+        }
+        let sns_root_canister_id = PrincipalId::new_user_test_id(4);
+        let new_controller_principal_id = PrincipalId::new_user_test_id(5);
+
+        // Step 1.1: Prepare helpers.
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    sns_root_canister_id,
+                ]),
+            )),
+            MockManagementCanisterClientReply::UpdateSettings(Ok(())),
+        ]);
+
+        // Step 2: Run code under test.
+        let response = SnsRootCanister::set_dapp_controllers(
+            &STATE,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            STATE.with(|state| state.borrow().swap_canister_id.unwrap()),
+            NOW,
+            &SetDappControllersRequest {
+                // Change controller to all dapps controlled by the root canister.
+                canister_ids: None,
+                controller_principal_ids: vec![new_controller_principal_id],
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(
+            response,
+            SetDappControllersResponse {
+                failed_updates: vec![]
+            }
+        );
+        let state = &STATE.with(|state| state.borrow().clone());
+        assert!(state.dapp_canister_ids.is_empty(), "{state:#?}",);
+
+        let actual_management_canister_calls = management_canister_client.get_calls_snapshot();
+        let expected_management_canister_calls = vec![
+            MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
+                canister_id: CanisterId::try_from(PrincipalId::new_user_test_id(3)).unwrap(),
+            }),
+            MockManagementCanisterClientCall::UpdateSettings(UpdateSettings {
+                canister_id: PrincipalId::new_user_test_id(3),
+                settings: CanisterSettings {
+                    controllers: Some(vec![new_controller_principal_id]),
+                    ..Default::default()
+                },
+                sender_canister_version: None,
+            }),
+        ];
+        assert_eq!(
+            actual_management_canister_calls,
+            expected_management_canister_calls
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_set_dapp_controllers_set_all_not_usable_by_governance() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
+                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                archive_canister_ids: vec![],
+                index_canister_id: Some(PrincipalId::new_user_test_id(4)),
+                ..Default::default()
+            });
+        }
+        let sns_root_canister_id = PrincipalId::new_user_test_id(4);
+        let new_controller_principal_id = PrincipalId::new_user_test_id(5);
+
+        // Step 1.1: Prepare helpers.
+        let management_canister_client = MockManagementCanisterClient::new(vec![]);
+
+        // Step 2: Run code under test.
+        // We except to panic here, because we're passing the governance canister
+        // as the caller while attempting to deregister all canisters. That functionality
+        // can only be used by the swap canister – see NNS1-1989.
+        let _response = SnsRootCanister::set_dapp_controllers(
+            &STATE,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            STATE.with(|state| state.borrow().governance_canister_id.unwrap()),
+            NOW,
+            &SetDappControllersRequest {
+                // Change controller to all dapps controlled by the root canister.
+                canister_ids: None,
+                controller_principal_ids: vec![new_controller_principal_id],
+            },
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_set_dapp_controllers_set_some_usable_by_governance() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
+                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                archive_canister_ids: vec![],
+                index_canister_id: Some(PrincipalId::new_user_test_id(4)),
                 ..Default::default()
             });
         }
@@ -1848,6 +3673,7 @@ mod tests {
             &management_canister_client,
             sns_root_canister_id.into(),
             STATE.with(|state| state.borrow().governance_canister_id.unwrap()),
+            NOW,
             &SetDappControllersRequest {
                 // Change controller to all dapps controlled by the root canister.
                 canister_ids: Some(CanisterIds {
@@ -1922,6 +3748,7 @@ mod tests {
             &management_canister_client,
             sns_root_canister_id.into(),
             STATE.with(|state| state.borrow().swap_canister_id.unwrap()),
+            NOW,
             &SetDappControllersRequest {
                 // Change controller to all dapps controlled by the root canister.
                 canister_ids: Some(CanisterIds {
@@ -1949,6 +3776,37 @@ mod tests {
             vec![PrincipalId::new_user_test_id(3)],
             "{state:#?}",
         );
+        let swap_canister_id = PrincipalId::new_user_test_id(99);
+        let updated_canister_ids = vec![
+            PrincipalId::new_user_test_id(4),
+            PrincipalId::new_user_test_id(5),
+            PrincipalId::new_user_test_id(6),
+        ];
+        assert_eq!(
+            state.events,
+            vec![
+                RootEvent {
+                    timestamp_seconds: NOW,
+                    caller: Some(swap_canister_id),
+                    payload: Some(root_event::Payload::DappControllersSet(
+                        root_event::DappControllersSet {
+                            dapp_canister_ids: updated_canister_ids.clone(),
+                            controller_principal_ids: vec![new_controller_principal_id],
+                            failed_dapp_canister_ids: vec![],
+                        }
+                    )),
+                },
+                RootEvent {
+                    timestamp_seconds: NOW,
+                    caller: Some(swap_canister_id),
+                    payload: Some(root_event::Payload::DappCanistersDeregistered(
+                        root_event::DappCanistersDeregistered {
+                            dapp_canister_ids: updated_canister_ids,
+                        }
+                    )),
+                },
+            ],
+        );
 
         let actual_management_canister_calls = management_canister_client.get_calls_snapshot();
         let expected_management_canister_calls = vec![
@@ -2029,6 +3887,7 @@ mod tests {
             &management_canister_client,
             sns_root_canister_id.into(),
             not_authorized,
+            NOW,
             &SetDappControllersRequest {
                 // Change controller to all dapps controlled by the root canister.
                 canister_ids: None,
@@ -2070,51 +3929,935 @@ mod tests {
             MockManagementCanisterClientReply::UpdateSettings(Ok(())),
         ]);
 
-        // Step 2: Run code under test.
-        let original_state = STATE.with(|state| state.borrow().clone());
-        let response = SnsRootCanister::set_dapp_controllers(
-            &STATE,
+        // Step 2: Run code under test.
+        let original_state = STATE.with(|state| state.borrow().clone());
+        let response = SnsRootCanister::set_dapp_controllers(
+            &STATE,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            STATE.with(|state| state.borrow().swap_canister_id.unwrap()),
+            NOW,
+            &SetDappControllersRequest {
+                // Change controller to all dapps controlled by the root canister.
+                canister_ids: None,
+                controller_principal_ids: vec![new_controller_principal_id, sns_root_canister_id],
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(
+            response,
+            SetDappControllersResponse {
+                failed_updates: vec![]
+            }
+        );
+
+        // State should be unchanged, because sns root is STILL a controller of dapp_canisters.
+        let state = STATE.with(|state| state.borrow().clone());
+        assert_eq!(state, original_state, "{state:#?}");
+
+        let actual_management_canister_calls = management_canister_client.get_calls_snapshot();
+        let expected_management_canister_calls = vec![
+            MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
+                canister_id: CanisterId::try_from(PrincipalId::new_user_test_id(3)).unwrap(),
+            }),
+            MockManagementCanisterClientCall::UpdateSettings(UpdateSettings {
+                canister_id: PrincipalId::new_user_test_id(3),
+                settings: CanisterSettings {
+                    controllers: Some(vec![new_controller_principal_id, sns_root_canister_id]),
+                    ..Default::default()
+                },
+                sender_canister_version: None,
+            }),
+        ];
+        assert_eq!(
+            actual_management_canister_calls,
+            expected_management_canister_calls
+        );
+    }
+
+    #[test]
+    fn test_get_root_events_paginates() {
+        let events = (0..(MAX_EVENTS_PER_RESPONSE + 10))
+            .map(|i| RootEvent {
+                timestamp_seconds: i,
+                caller: None,
+                payload: Some(root_event::Payload::DappCanistersControlLost(
+                    root_event::DappCanistersControlLost {
+                        dapp_canister_ids: vec![PrincipalId::new_user_test_id(i)],
+                    },
+                )),
+            })
+            .collect::<Vec<_>>();
+        let state = SnsRootCanister {
+            events: events.clone(),
+            ..build_test_sns_root_canister(false)
+        };
+
+        let response = state.get_root_events(GetRootEventsRequest {
+            start: 5,
+            length: 3,
+        });
+        assert_eq!(
+            response,
+            GetRootEventsResponse {
+                events: events[5..8].to_vec(),
+                total_event_count: MAX_EVENTS_PER_RESPONSE + 10,
+                first_event_index: 0,
+            }
+        );
+
+        // Responses are capped at MAX_EVENTS_PER_RESPONSE events.
+        let response = state.get_root_events(GetRootEventsRequest {
+            start: 0,
+            length: u64::MAX,
+        });
+        assert_eq!(response.events, events[..MAX_EVENTS_PER_RESPONSE as usize]);
+
+        // Starting past the end yields no events.
+        let response = state.get_root_events(GetRootEventsRequest {
+            start: MAX_EVENTS_PER_RESPONSE + 10,
+            length: 1,
+        });
+        assert_eq!(response.events, vec![]);
+    }
+
+    #[test]
+    fn test_record_event_drops_oldest_events() {
+        let mut state = build_test_sns_root_canister(false);
+        let payload = |i| {
+            root_event::Payload::DappCanistersControlLost(root_event::DappCanistersControlLost {
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(i)],
+            })
+        };
+
+        for i in 0..(MAX_EVENTS as u64 + 5) {
+            state.record_event(i, None, payload(i));
+        }
+        assert_eq!(state.events.len(), MAX_EVENTS);
+        assert_eq!(state.dropped_event_count, 5);
+        assert_eq!(state.events[0].timestamp_seconds, 5);
+
+        // Indices keep referring to the same events after older ones are dropped.
+        let response = state.get_root_events(GetRootEventsRequest {
+            start: 7,
+            length: 2,
+        });
+        assert_eq!(response.first_event_index, 5);
+        assert_eq!(response.total_event_count, MAX_EVENTS as u64 + 5);
+        assert_eq!(
+            response
+                .events
+                .iter()
+                .map(|event| event.timestamp_seconds)
+                .collect::<Vec<_>>(),
+            vec![7, 8]
+        );
+
+        // Asking for dropped events yields the oldest events still kept.
+        let response = state.get_root_events(GetRootEventsRequest {
+            start: 0,
+            length: 1,
+        });
+        assert_eq!(response.events[0].timestamp_seconds, 5);
+    }
+
+    #[test]
+    fn test_set_canister_status_readers_deduplicates() {
+        let mut state = build_test_sns_root_canister(false);
+        let reader_1 = PrincipalId::new_user_test_id(10);
+        let reader_2 = PrincipalId::new_user_test_id(11);
+
+        state.set_canister_status_readers(SetCanisterStatusReadersRequest {
+            principal_ids: vec![reader_2, reader_1, reader_2],
+        });
+
+        let mut expected = vec![reader_1, reader_2];
+        expected.sort();
+        assert_eq!(state.canister_status_reader_principal_ids, expected);
+
+        // The new list replaces the old one.
+        state.set_canister_status_readers(SetCanisterStatusReadersRequest {
+            principal_ids: vec![],
+        });
+        assert_eq!(state.canister_status_reader_principal_ids, vec![]);
+    }
+
+    #[test]
+    fn test_register_ledger_archive() {
+        let mut state = build_test_sns_root_canister(false);
+        let archive_1 = PrincipalId::new_user_test_id(20);
+        let archive_2 = PrincipalId::new_user_test_id(21);
+
+        state.register_ledger_archive(archive_1);
+        state.register_ledger_archive(archive_2);
+        // A repeated notification does not duplicate the archive.
+        state.register_ledger_archive(archive_1);
+
+        assert_eq!(state.archive_canister_ids, vec![archive_1, archive_2]);
+    }
+
+    #[tokio::test]
+    async fn test_canister_status_of() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(6)],
+                canister_status_reader_principal_ids: vec![PrincipalId::new_user_test_id(10)],
+                ..build_test_sns_root_canister(false)
+            });
+        }
+        let sns_root_canister_id = PrincipalId::new_user_test_id(5);
+        let dapp_canister_id = PrincipalId::new_user_test_id(6);
+        let reader_id = PrincipalId::new_user_test_id(10);
+        let governance_canister_id =
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id());
+
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    sns_root_canister_id,
+                ]),
+            )),
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    governance_canister_id,
+                ]),
+            )),
+        ]);
+
+        // Step 2 & 3: Call the code under test and inspect results.
+
+        // A configured reader can get the status of a dapp.
+        let result = SnsRootCanister::canister_status_of(
+            &SNS_ROOT_CANISTER,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            reader_id,
+            dapp_canister_id,
+        )
+        .await;
+        assert_eq!(
+            result,
+            Ok(CanisterStatusResultV2::from(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    sns_root_canister_id
+                ])
+            ))
+        );
+
+        // Governance can get the status of root itself.
+        let result = SnsRootCanister::canister_status_of(
+            &SNS_ROOT_CANISTER,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            governance_canister_id,
+            sns_root_canister_id,
+        )
+        .await;
+        assert!(result.is_ok(), "{result:?}");
+
+        // Other callers are rejected.
+        let err = SnsRootCanister::canister_status_of(
+            &SNS_ROOT_CANISTER,
+            &management_canister_client,
+            sns_root_canister_id.into(),
+            PrincipalId::new_user_test_id(11),
+            dapp_canister_id,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("not authorized"), "{err}");
+
+        // Canisters that root does not control are rejected, including swap.
+        for canister_id in [
+            PrincipalId::new_user_test_id(12),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().swap_canister_id()),
+        ] {
+            let err = SnsRootCanister::canister_status_of(
+                &SNS_ROOT_CANISTER,
+                &management_canister_client,
+                sns_root_canister_id.into(),
+                reader_id,
+                canister_id,
+            )
+            .await
+            .unwrap_err();
+            assert!(
+                err.contains("is not controlled by this SNS root canister"),
+                "{err}"
+            );
+        }
+
+        // Only the two successful requests reached the management canister.
+        assert_eq!(
+            management_canister_client.get_calls_snapshot(),
+            vec![
+                MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
+                    canister_id: CanisterId::try_from(dapp_canister_id).unwrap(),
+                }),
+                MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
+                    canister_id: CanisterId::try_from(sns_root_canister_id).unwrap(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_canister_alert_thresholds() {
+        let mut state = SnsRootCanister {
+            dapp_canister_ids: vec![PrincipalId::new_user_test_id(6)],
+            ..build_test_sns_root_canister(false)
+        };
+        let sns_root_canister_id = PrincipalId::new_user_test_id(5);
+        let dapp_canister_id = PrincipalId::new_user_test_id(6);
+        let ledger_canister_id = state.ledger_canister_id();
+
+        // Set thresholds for two canisters.
+        state
+            .set_canister_alert_thresholds(
+                sns_root_canister_id.into(),
+                SetCanisterAlertThresholdsRequest {
+                    canister_alert_thresholds: vec![
+                        CanisterAlertThresholds {
+                            canister_id: Some(dapp_canister_id),
+                            min_cycles: Some(1_000),
+                            max_memory_bytes: None,
+                        },
+                        CanisterAlertThresholds {
+                            canister_id: Some(ledger_canister_id),
+                            min_cycles: None,
+                            max_memory_bytes: Some(2_000),
+                        },
+                    ],
+                },
+            )
+            .unwrap();
+        assert_eq!(state.canister_alert_thresholds.len(), 2);
+
+        // Replace the thresholds of one, and remove those of the other.
+        state
+            .set_canister_alert_thresholds(
+                sns_root_canister_id.into(),
+                SetCanisterAlertThresholdsRequest {
+                    canister_alert_thresholds: vec![
+                        CanisterAlertThresholds {
+                            canister_id: Some(dapp_canister_id),
+                            min_cycles: Some(3_000),
+                            max_memory_bytes: Some(4_000),
+                        },
+                        CanisterAlertThresholds {
+                            canister_id: Some(ledger_canister_id),
+                            min_cycles: None,
+                            max_memory_bytes: None,
+                        },
+                    ],
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            state.canister_alert_thresholds,
+            vec![CanisterAlertThresholds {
+                canister_id: Some(dapp_canister_id),
+                min_cycles: Some(3_000),
+                max_memory_bytes: Some(4_000),
+            }]
+        );
+
+        // Canisters that root does not control are rejected, and nothing
+        // changes.
+        let err = state
+            .set_canister_alert_thresholds(
+                sns_root_canister_id.into(),
+                SetCanisterAlertThresholdsRequest {
+                    canister_alert_thresholds: vec![
+                        CanisterAlertThresholds {
+                            canister_id: Some(dapp_canister_id),
+                            min_cycles: None,
+                            max_memory_bytes: None,
+                        },
+                        CanisterAlertThresholds {
+                            canister_id: Some(state.swap_canister_id()),
+                            min_cycles: Some(1),
+                            max_memory_bytes: None,
+                        },
+                    ],
+                },
+            )
+            .unwrap_err();
+        assert!(
+            err.contains("is not controlled by this SNS root canister"),
+            "{err}"
+        );
+        assert_eq!(state.canister_alert_thresholds.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_periodic_task_raises_canister_alerts() {
+        // Step 1: Prepare the world.
+        let root_canister_id = PrincipalId::new_user_test_id(5);
+        let low_cycles_canister_id = PrincipalId::new_user_test_id(6);
+        let high_memory_canister_id = PrincipalId::new_user_test_id(7);
+        let healthy_canister_id = PrincipalId::new_user_test_id(8);
+        let unavailable_canister_id = PrincipalId::new_user_test_id(9);
+
+        thread_local! {
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
+        }
+        SNS_ROOT_CANISTER.with(|r| {
+            // The dummy canister status has 47 cycles, and uses 42 bytes of memory.
+            r.borrow_mut().canister_alert_thresholds = vec![
+                CanisterAlertThresholds {
+                    canister_id: Some(low_cycles_canister_id),
+                    min_cycles: Some(48),
+                    max_memory_bytes: Some(42),
+                },
+                CanisterAlertThresholds {
+                    canister_id: Some(high_memory_canister_id),
+                    min_cycles: Some(47),
+                    max_memory_bytes: Some(41),
+                },
+                CanisterAlertThresholds {
+                    canister_id: Some(healthy_canister_id),
+                    min_cycles: Some(47),
+                    max_memory_bytes: Some(42),
+                },
+                CanisterAlertThresholds {
+                    canister_id: Some(unavailable_canister_id),
+                    min_cycles: Some(1_000),
+                    max_memory_bytes: None,
+                },
+            ];
+        });
+
+        let ledger_canister_client = MockLedgerCanisterClient::new(vec![]);
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    root_canister_id,
+                ]),
+            )),
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    root_canister_id,
+                ]),
+            )),
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    root_canister_id,
+                ]),
+            )),
+            MockManagementCanisterClientReply::CanisterStatus(Err((
+                RejectionCode::SysTransient as i32,
+                "Canister is temporarily unavailable.".to_string(),
+            ))),
+        ]);
+
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::CheckCanisterAlertThresholds,
+            &ledger_canister_client,
+            &management_canister_client,
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
+            NOW,
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        let err = result.unwrap_err();
+        assert!(err.contains(&unavailable_canister_id.to_string()), "{err}");
+        SNS_ROOT_CANISTER.with(|r| {
+            let state = r.borrow();
+            assert_eq!(state.low_cycles_alert_count, 1);
+            assert_eq!(state.high_memory_alert_count, 1);
+            assert_eq!(
+                state.latest_canister_alert_check_timestamp_seconds,
+                Some(NOW)
+            );
+        });
+
+        // The task is not due again until an hour has passed.
+        SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::CheckCanisterAlertThresholds,
+            &ledger_canister_client,
+            &management_canister_client,
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
+            NOW + ONE_HOUR_SECONDS - 1,
+        )
+        .await
+        .unwrap();
+        SNS_ROOT_CANISTER.with(|r| {
+            let state = r.borrow();
+            assert_eq!(state.low_cycles_alert_count, 1);
+            assert_eq!(state.high_memory_alert_count, 1);
+        });
+    }
+
+    #[test]
+    fn test_set_cycles_refueling_config() {
+        let mut state = build_test_sns_root_canister(false);
+        let governance_canister_id = state.governance_canister_id();
+        let config = CyclesRefuelingConfig {
+            min_cycles: 1_000_000_000_000,
+            icp_e8s_per_top_up: 100_000_000,
+            max_icp_e8s_per_day: 300_000_000,
+        };
+
+        state
+            .set_cycles_refueling_config(
+                governance_canister_id,
+                SetCyclesRefuelingConfigRequest {
+                    config: Some(config.clone()),
+                },
+                NOW,
+            )
+            .unwrap();
+        assert_eq!(state.cycles_refueling_config, Some(config.clone()));
+
+        // A daily cap that does not allow a single top up is rejected, and
+        // nothing changes.
+        let err = state
+            .set_cycles_refueling_config(
+                governance_canister_id,
+                SetCyclesRefuelingConfigRequest {
+                    config: Some(CyclesRefuelingConfig {
+                        max_icp_e8s_per_day: 100_000_000,
+                        ..config.clone()
+                    }),
+                },
+                NOW + 1,
+            )
+            .unwrap_err();
+        assert!(err.contains("max_icp_e8s_per_day"), "{err}");
+        assert_eq!(state.cycles_refueling_config, Some(config.clone()));
+
+        // Refueling can be disabled.
+        state
+            .set_cycles_refueling_config(
+                governance_canister_id,
+                SetCyclesRefuelingConfigRequest { config: None },
+                NOW + 2,
+            )
+            .unwrap();
+        assert_eq!(state.cycles_refueling_config, None);
+
+        assert_eq!(
+            state.events,
+            vec![
+                RootEvent {
+                    timestamp_seconds: NOW,
+                    caller: Some(governance_canister_id),
+                    payload: Some(root_event::Payload::CyclesRefuelingConfigSet(
+                        root_event::CyclesRefuelingConfigSet {
+                            config: Some(config),
+                        }
+                    )),
+                },
+                RootEvent {
+                    timestamp_seconds: NOW + 2,
+                    caller: Some(governance_canister_id),
+                    payload: Some(root_event::Payload::CyclesRefuelingConfigSet(
+                        root_event::CyclesRefuelingConfigSet { config: None }
+                    )),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_dapp_controllers_policy() {
+        let mut state = build_test_sns_root_canister(false);
+        let governance_canister_id = state.governance_canister_id();
+        let sns_root_canister_id = PrincipalId::new_user_test_id(4);
+        let orchestrator_id = PrincipalId::new_user_test_id(50);
+
+        // Duplicates are removed.
+        state
+            .set_dapp_controllers_policy(
+                sns_root_canister_id.into(),
+                governance_canister_id,
+                SetDappControllersPolicyRequest {
+                    policy: Some(DappControllersPolicy {
+                        allowed_extra_controller_principal_ids: vec![
+                            orchestrator_id,
+                            orchestrator_id,
+                        ],
+                    }),
+                },
+                NOW,
+            )
+            .unwrap();
+        let policy = DappControllersPolicy {
+            allowed_extra_controller_principal_ids: vec![orchestrator_id],
+        };
+        assert_eq!(state.dapp_controllers_policy, Some(policy.clone()));
+
+        // Root itself is rejected, and nothing changes.
+        let err = state
+            .set_dapp_controllers_policy(
+                sns_root_canister_id.into(),
+                governance_canister_id,
+                SetDappControllersPolicyRequest {
+                    policy: Some(DappControllersPolicy {
+                        allowed_extra_controller_principal_ids: vec![sns_root_canister_id],
+                    }),
+                },
+                NOW + 1,
+            )
+            .unwrap_err();
+        assert!(err.contains("SNS root"), "{err}");
+
+        // So are too many extra controllers.
+        let err = state
+            .set_dapp_controllers_policy(
+                sns_root_canister_id.into(),
+                governance_canister_id,
+                SetDappControllersPolicyRequest {
+                    policy: Some(DappControllersPolicy {
+                        allowed_extra_controller_principal_ids: (100..110)
+                            .map(PrincipalId::new_user_test_id)
+                            .collect(),
+                    }),
+                },
+                NOW + 1,
+            )
+            .unwrap_err();
+        assert!(err.contains("at most 9"), "{err}");
+        assert_eq!(state.dapp_controllers_policy, Some(policy.clone()));
+
+        // The policy can be removed.
+        state
+            .set_dapp_controllers_policy(
+                sns_root_canister_id.into(),
+                governance_canister_id,
+                SetDappControllersPolicyRequest { policy: None },
+                NOW + 2,
+            )
+            .unwrap();
+        assert_eq!(state.dapp_controllers_policy, None);
+
+        assert_eq!(
+            state.events,
+            vec![
+                RootEvent {
+                    timestamp_seconds: NOW,
+                    caller: Some(governance_canister_id),
+                    payload: Some(root_event::Payload::DappControllersPolicySet(
+                        root_event::DappControllersPolicySet {
+                            policy: Some(policy),
+                        }
+                    )),
+                },
+                RootEvent {
+                    timestamp_seconds: NOW + 2,
+                    caller: Some(governance_canister_id),
+                    payload: Some(root_event::Payload::DappControllersPolicySet(
+                        root_event::DappControllersPolicySet { policy: None }
+                    )),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_periodic_task_refuels_canisters() {
+        // Step 1: Prepare the world.
+        let root_canister_id = PrincipalId::new_user_test_id(5);
+        let icp_e8s_per_top_up = 100_000_000;
+
+        thread_local! {
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
+        }
+        let (governance_canister_id, ledger_canister_id) = SNS_ROOT_CANISTER.with(|r| {
+            let mut state = r.borrow_mut();
+            // Enough for two top ups a day.
+            state.cycles_refueling_config = Some(CyclesRefuelingConfig {
+                min_cycles: 1_000,
+                icp_e8s_per_top_up,
+                max_icp_e8s_per_day: 2 * (icp_e8s_per_top_up + ICP_TRANSFER_FEE_E8S),
+            });
+            (state.governance_canister_id(), state.ledger_canister_id())
+        });
+
+        let canister_status_with_cycles = |cycles: u64| {
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister {
+                    cycles: candid::Nat::from(cycles),
+                    ..CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                        root_canister_id,
+                    ])
+                },
+            ))
+        };
+        // Root, governance, ledger, and index, in that order.
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            canister_status_with_cycles(999),
+            canister_status_with_cycles(1_000),
+            canister_status_with_cycles(0),
+            canister_status_with_cycles(0),
+        ]);
+        let cycles_minting_client = MockCyclesMintingClient::new(vec![
+            CyclesMintingClientCall::TransferForTopUp {
+                expected_canister_id: root_canister_id,
+                expected_icp_e8s: icp_e8s_per_top_up,
+                result: Ok(10),
+            },
+            CyclesMintingClientCall::NotifyTopUp {
+                expected_canister_id: root_canister_id,
+                expected_block_index: 10,
+                result: Ok(7_000_000_000_000),
+            },
+            CyclesMintingClientCall::TransferForTopUp {
+                expected_canister_id: ledger_canister_id,
+                expected_icp_e8s: icp_e8s_per_top_up,
+                result: Ok(11),
+            },
+            CyclesMintingClientCall::NotifyTopUp {
+                expected_canister_id: ledger_canister_id,
+                expected_block_index: 11,
+                result: Err(NotifyTopUpError::Transient(
+                    "The CMC is unavailable.".to_string(),
+                )),
+            },
+        ]);
+
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::RefuelCanisters,
+            &MockLedgerCanisterClient::new(vec![]),
+            &management_canister_client,
+            &cycles_minting_client,
+            root_canister_id,
+            NOW,
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        // The index canister is not topped up, because the daily cap was reached.
+        let err = result.unwrap_err();
+        assert!(err.contains(&ledger_canister_id.to_string()), "{err}");
+        assert!(!err.contains(&governance_canister_id.to_string()), "{err}");
+        assert!(cycles_minting_client.calls.lock().await.is_empty());
+        SNS_ROOT_CANISTER.with(|r| {
+            let state = r.borrow();
+            assert_eq!(state.latest_cycles_refueling_timestamp_seconds, Some(NOW));
+            assert_eq!(
+                state.events,
+                vec![
+                    RootEvent {
+                        timestamp_seconds: NOW,
+                        caller: None,
+                        payload: Some(root_event::Payload::CanisterRefueled(
+                            root_event::CanisterRefueled {
+                                canister_id: Some(root_canister_id),
+                                icp_e8s: icp_e8s_per_top_up,
+                                block_index: 10,
+                                cycles: Some(7_000_000_000_000),
+                                notify_error: None,
+                            }
+                        )),
+                    },
+                    RootEvent {
+                        timestamp_seconds: NOW,
+                        caller: None,
+                        payload: Some(root_event::Payload::CanisterRefueled(
+                            root_event::CanisterRefueled {
+                                canister_id: Some(ledger_canister_id),
+                                icp_e8s: icp_e8s_per_top_up,
+                                block_index: 11,
+                                cycles: None,
+                                notify_error: Some("The CMC is unavailable.".to_string()),
+                            }
+                        )),
+                    },
+                ]
+            );
+            // The ledger canister's top up is completed by a later run.
+            assert_eq!(
+                state.pending_top_ups,
+                vec![PendingTopUp {
+                    canister_id: Some(ledger_canister_id),
+                    block_index: 11,
+                }]
+            );
+            // The cap applies per day.
+            assert_eq!(
+                state.icp_e8s_spent_on_refueling_today(NOW),
+                2 * (icp_e8s_per_top_up + ICP_TRANSFER_FEE_E8S)
+            );
+            assert_eq!(
+                state.icp_e8s_spent_on_refueling_today(NOW + ONE_DAY_SECONDS),
+                0
+            );
+        });
+
+        // The task is not due again until an hour has passed.
+        SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::RefuelCanisters,
+            &MockLedgerCanisterClient::new(vec![]),
+            &MockManagementCanisterClient::new(vec![]),
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
+            NOW + ONE_HOUR_SECONDS - 1,
+        )
+        .await
+        .unwrap();
+
+        // Nothing happens while refueling is disabled.
+        SNS_ROOT_CANISTER.with(|r| r.borrow_mut().cycles_refueling_config = None);
+        SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::RefuelCanisters,
+            &MockLedgerCanisterClient::new(vec![]),
+            &MockManagementCanisterClient::new(vec![]),
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
+            NOW + ONE_DAY_SECONDS,
+        )
+        .await
+        .unwrap();
+        SNS_ROOT_CANISTER.with(|r| {
+            let state = r.borrow();
+            assert_eq!(state.latest_cycles_refueling_timestamp_seconds, Some(NOW));
+            assert_eq!(state.events.len(), 2);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_run_periodic_task_completes_pending_top_ups() {
+        // Step 1: Prepare the world.
+        let root_canister_id = PrincipalId::new_user_test_id(5);
+
+        thread_local! {
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
+        }
+        let (governance_canister_id, ledger_canister_id, index_canister_id) = SNS_ROOT_CANISTER
+            .with(|r| {
+                let mut state = r.borrow_mut();
+                state.cycles_refueling_config = Some(CyclesRefuelingConfig {
+                    min_cycles: 1_000,
+                    icp_e8s_per_top_up: 100_000_000,
+                    max_icp_e8s_per_day: 1_000_000_000,
+                });
+                let governance_canister_id = state.governance_canister_id();
+                let ledger_canister_id = state.ledger_canister_id();
+                let index_canister_id = state.index_canister_id();
+                state.pending_top_ups = [
+                    (ledger_canister_id, 11),
+                    (governance_canister_id, 12),
+                    (index_canister_id, 13),
+                ]
+                .into_iter()
+                .map(|(canister_id, block_index)| PendingTopUp {
+                    canister_id: Some(canister_id),
+                    block_index,
+                })
+                .collect();
+                (
+                    governance_canister_id,
+                    ledger_canister_id,
+                    index_canister_id,
+                )
+            });
+
+        let cycles_minting_client = MockCyclesMintingClient::new(vec![
+            CyclesMintingClientCall::NotifyTopUp {
+                expected_canister_id: ledger_canister_id,
+                expected_block_index: 11,
+                result: Ok(7_000_000_000_000),
+            },
+            CyclesMintingClientCall::NotifyTopUp {
+                expected_canister_id: governance_canister_id,
+                expected_block_index: 12,
+                result: Err(NotifyTopUpError::Transient("Processing.".to_string())),
+            },
+            CyclesMintingClientCall::NotifyTopUp {
+                expected_canister_id: index_canister_id,
+                expected_block_index: 13,
+                result: Err(NotifyTopUpError::Permanent("Refunded.".to_string())),
+            },
+        ]);
+        // Governance still has a pending top up, so only root, ledger, and
+        // index are checked. None of them needs a top up.
+        let canister_status = MockManagementCanisterClientReply::CanisterStatus(Ok(
+            CanisterStatusResultFromManagementCanister {
+                cycles: candid::Nat::from(1_000_u64),
+                ..CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    root_canister_id,
+                ])
+            },
+        ));
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            canister_status.clone(),
+            canister_status.clone(),
+            canister_status,
+        ]);
+
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::RefuelCanisters,
+            &MockLedgerCanisterClient::new(vec![]),
             &management_canister_client,
-            sns_root_canister_id.into(),
-            STATE.with(|state| state.borrow().swap_canister_id.unwrap()),
-            &SetDappControllersRequest {
-                // Change controller to all dapps controlled by the root canister.
-                canister_ids: None,
-                controller_principal_ids: vec![new_controller_principal_id, sns_root_canister_id],
-            },
+            &cycles_minting_client,
+            root_canister_id,
+            NOW,
         )
         .await;
 
         // Step 3: Inspect results.
-        assert_eq!(
-            response,
-            SetDappControllersResponse {
-                failed_updates: vec![]
-            }
-        );
-
-        // State should be unchanged, because sns root is STILL a controller of dapp_canisters.
-        let state = STATE.with(|state| state.borrow().clone());
-        assert_eq!(state, original_state, "{state:#?}");
-
-        let actual_management_canister_calls = management_canister_client.get_calls_snapshot();
-        let expected_management_canister_calls = vec![
-            MockManagementCanisterClientCall::CanisterStatus(CanisterIdRecord {
-                canister_id: CanisterId::try_from(PrincipalId::new_user_test_id(3)).unwrap(),
-            }),
-            MockManagementCanisterClientCall::UpdateSettings(UpdateSettings {
-                canister_id: PrincipalId::new_user_test_id(3),
-                settings: CanisterSettings {
-                    controllers: Some(vec![new_controller_principal_id, sns_root_canister_id]),
-                    ..Default::default()
-                },
-                sender_canister_version: None,
-            }),
-        ];
-        assert_eq!(
-            actual_management_canister_calls,
-            expected_management_canister_calls
-        );
+        let err = result.unwrap_err();
+        assert!(err.contains(&governance_canister_id.to_string()), "{err}");
+        assert!(!err.contains(&ledger_canister_id.to_string()), "{err}");
+        assert!(cycles_minting_client.calls.lock().await.is_empty());
+        SNS_ROOT_CANISTER.with(|r| {
+            let state = r.borrow();
+            assert_eq!(
+                state.pending_top_ups,
+                vec![PendingTopUp {
+                    canister_id: Some(governance_canister_id),
+                    block_index: 12,
+                }]
+            );
+            assert_eq!(
+                state.events,
+                vec![
+                    RootEvent {
+                        timestamp_seconds: NOW,
+                        caller: None,
+                        payload: Some(root_event::Payload::PendingTopUpResolved(
+                            root_event::PendingTopUpResolved {
+                                canister_id: Some(ledger_canister_id),
+                                block_index: 11,
+                                cycles: Some(7_000_000_000_000),
+                                notify_error: None,
+                            }
+                        )),
+                    },
+                    RootEvent {
+                        timestamp_seconds: NOW,
+                        caller: None,
+                        payload: Some(root_event::Payload::PendingTopUpResolved(
+                            root_event::PendingTopUpResolved {
+                                canister_id: Some(index_canister_id),
+                                block_index: 13,
+                                cycles: None,
+                                notify_error: Some("Refunded.".to_string()),
+                            }
+                        )),
+                    },
+                ]
+            );
+            // Completing earlier top ups costs nothing.
+            assert_eq!(state.icp_e8s_spent_on_refueling_today(NOW), 0);
+        });
     }
 
     #[test]
@@ -2142,6 +4885,7 @@ mod tests {
                 dapps: state.dapp_canister_ids,
                 archives: state.archive_canister_ids,
                 index: state.index_canister_id,
+                extensions: state.extension_canister_ids,
             }
         )
     }
@@ -2170,7 +4914,8 @@ mod tests {
             &ledger_canister_client,
             NOW,
         )
-        .await;
+        .await
+        .unwrap();
 
         // Step 3: Inspect results.
         assert_archive_poll_state_change(&SNS_ROOT_CANISTER, &[expected_archive_canister_id], NOW);
@@ -2208,7 +4953,8 @@ mod tests {
             &ledger_canister_client,
             NOW,
         )
-        .await;
+        .await
+        .unwrap();
 
         // Step 3: Inspect results.
         assert_archive_poll_state_change(
@@ -2258,7 +5004,8 @@ mod tests {
             &ledger_canister_client,
             NOW,
         )
-        .await;
+        .await
+        .unwrap();
 
         // Step 3: Inspect results.
         assert_archive_poll_state_change(
@@ -2272,7 +5019,8 @@ mod tests {
             &ledger_canister_client,
             NOW + ONE_DAY_SECONDS,
         )
-        .await;
+        .await
+        .unwrap();
 
         assert_archive_poll_state_change(
             &SNS_ROOT_CANISTER,
@@ -2337,7 +5085,8 @@ mod tests {
             &ledger_canister_client,
             NOW,
         )
-        .await;
+        .await
+        .unwrap();
 
         // Step 3: Inspect results.
         assert_archive_poll_state_change(
@@ -2353,9 +5102,10 @@ mod tests {
             &ledger_canister_client,
             NOW + ONE_DAY_SECONDS,
         )
-        .await;
+        .await
+        .unwrap_err();
 
-        // Since the error happens in canister_heartbeat, this should result in a 'do nothing'
+        // Since the error happens in a periodic task, this should result in a 'do nothing'
         // operation. The latest_ledger_archive_poll_timestamp_seconds should be updated,
         // and the canisters should be the same as before
         assert_archive_poll_state_change(
@@ -2419,7 +5169,8 @@ mod tests {
             &ledger_canister_client,
             NOW,
         )
-        .await;
+        .await
+        .unwrap();
 
         // Step 3: Inspect results.
         assert_archive_poll_state_change(
@@ -2435,7 +5186,8 @@ mod tests {
             &ledger_canister_client,
             NOW + ONE_DAY_SECONDS,
         )
-        .await;
+        .await
+        .unwrap_err();
 
         assert_archive_poll_state_change(
             &SNS_ROOT_CANISTER,
@@ -2451,7 +5203,8 @@ mod tests {
             &ledger_canister_client,
             NOW + (2 * ONE_DAY_SECONDS),
         )
-        .await;
+        .await
+        .unwrap();
 
         assert_archive_poll_state_change(
             &SNS_ROOT_CANISTER,
@@ -2466,7 +5219,8 @@ mod tests {
             &ledger_canister_client,
             NOW + (3 * ONE_DAY_SECONDS),
         )
-        .await;
+        .await
+        .unwrap_err();
 
         assert_archive_poll_state_change(
             &SNS_ROOT_CANISTER,
@@ -2505,7 +5259,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_heartbeat() {
+    async fn test_run_periodic_task_polls_for_archives() {
         // Step 1: Prepare the world.
         thread_local! {
             static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
@@ -2538,8 +5292,21 @@ mod tests {
             },
         ]);
 
+        let management_canister_client = MockManagementCanisterClient::new(vec![]);
+        let root_canister_id = PrincipalId::new_user_test_id(5);
+
         // Step 2: Call the code under test.
-        SnsRootCanister::heartbeat(&SNS_ROOT_CANISTER, &ledger_canister_client, NOW).await;
+        SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::PollLedgerArchives,
+            &ledger_canister_client,
+            &management_canister_client,
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
+            NOW,
+        )
+        .await
+        .unwrap();
 
         // Step 3: Inspect results.
         assert_archive_poll_state_change(
@@ -2550,7 +5317,17 @@ mod tests {
 
         // Running periodic tasks one second in the future should
         // result in no change to state.
-        SnsRootCanister::heartbeat(&SNS_ROOT_CANISTER, &ledger_canister_client, NOW + 1).await;
+        SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::PollLedgerArchives,
+            &ledger_canister_client,
+            &management_canister_client,
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
+            NOW + 1,
+        )
+        .await
+        .unwrap();
 
         assert_archive_poll_state_change(
             &SNS_ROOT_CANISTER,
@@ -2560,12 +5337,17 @@ mod tests {
 
         // Running periodic tasks one dat in the future should
         // result in a new poll.
-        SnsRootCanister::heartbeat(
+        SnsRootCanister::run_periodic_task(
             &SNS_ROOT_CANISTER,
+            PeriodicTask::PollLedgerArchives,
             &ledger_canister_client,
+            &management_canister_client,
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
             NOW + ONE_DAY_SECONDS,
         )
-        .await;
+        .await
+        .unwrap();
 
         assert_archive_poll_state_change(
             &SNS_ROOT_CANISTER,
@@ -2574,6 +5356,185 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_should_check_dapp_canisters_control() {
+        assert!(SnsRootCanister::should_check_dapp_canisters_control(
+            None, NOW
+        ));
+        assert!(!SnsRootCanister::should_check_dapp_canisters_control(
+            Some(NOW),
+            NOW + ONE_HOUR_SECONDS - 1
+        ));
+        assert!(SnsRootCanister::should_check_dapp_canisters_control(
+            Some(NOW),
+            NOW + ONE_HOUR_SECONDS
+        ));
+    }
+
+    #[test]
+    fn test_periodic_task_next_delay_seconds_backs_off() {
+        let task = PeriodicTask::CheckDappCanistersControl;
+        assert_eq!(task.next_delay_seconds(0), ONE_HOUR_SECONDS);
+        assert_eq!(task.next_delay_seconds(1), 2 * ONE_HOUR_SECONDS);
+        assert_eq!(task.next_delay_seconds(2), 4 * ONE_HOUR_SECONDS);
+        assert_eq!(task.next_delay_seconds(3), 8 * ONE_HOUR_SECONDS);
+        // The backoff is capped.
+        assert_eq!(task.next_delay_seconds(100), 8 * ONE_HOUR_SECONDS);
+
+        let task = PeriodicTask::PollLedgerArchives;
+        assert_eq!(task.next_delay_seconds(0), ONE_DAY_SECONDS);
+        assert_eq!(task.next_delay_seconds(1), 2 * ONE_DAY_SECONDS);
+    }
+
+    #[test]
+    fn test_seconds_until_periodic_task_due() {
+        let mut state = build_test_sns_root_canister(false);
+
+        // Tasks that never ran are due right away.
+        for task in PeriodicTask::ALL {
+            assert_eq!(state.seconds_until_periodic_task_due(task, NOW), 0);
+        }
+
+        state.latest_ledger_archive_poll_timestamp_seconds = Some(NOW);
+        state.latest_dapp_control_check_timestamp_seconds = Some(NOW - 2 * ONE_HOUR_SECONDS);
+        assert_eq!(
+            state.seconds_until_periodic_task_due(PeriodicTask::PollLedgerArchives, NOW + 10),
+            ONE_DAY_SECONDS - 10
+        );
+        // Overdue tasks are due right away.
+        assert_eq!(
+            state.seconds_until_periodic_task_due(PeriodicTask::CheckDappCanistersControl, NOW),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_periodic_task_detects_lost_control_of_dapp_canisters() {
+        // Step 1: Prepare the world.
+        let root_canister_id = PrincipalId::new_user_test_id(5);
+        let controlled_dapp_canister_id = PrincipalId::new_user_test_id(6);
+        let reassigned_dapp_canister_id = PrincipalId::new_user_test_id(7);
+        let rejected_dapp_canister_id = PrincipalId::new_user_test_id(8);
+        let unavailable_dapp_canister_id = PrincipalId::new_user_test_id(9);
+        let user_id = PrincipalId::new_user_test_id(10);
+
+        thread_local! {
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
+        }
+        SNS_ROOT_CANISTER.with(|r| {
+            r.borrow_mut().dapp_canister_ids = vec![
+                controlled_dapp_canister_id,
+                reassigned_dapp_canister_id,
+                rejected_dapp_canister_id,
+                unavailable_dapp_canister_id,
+            ];
+        });
+
+        let ledger_canister_client = MockLedgerCanisterClient::new(vec![]);
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    root_canister_id,
+                ]),
+            )),
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![user_id]),
+            )),
+            MockManagementCanisterClientReply::CanisterStatus(Err((
+                RejectionCode::CanisterError as i32,
+                "Only the controllers of the canister can control it.".to_string(),
+            ))),
+            MockManagementCanisterClientReply::CanisterStatus(Err((
+                RejectionCode::SysTransient as i32,
+                "Canister is temporarily unavailable.".to_string(),
+            ))),
+        ]);
+
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::CheckDappCanistersControl,
+            &ledger_canister_client,
+            &management_canister_client,
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id,
+            NOW,
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        // The unavailable dapp makes the task fail, so that it backs off.
+        let err = result.unwrap_err();
+        assert!(
+            err.contains(&unavailable_dapp_canister_id.to_string()),
+            "{err}"
+        );
+        SNS_ROOT_CANISTER.with(|r| {
+            let state = r.borrow();
+            let dapp_canister_ids = state
+                .dapp_canister_ids
+                .iter()
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            assert_eq!(
+                dapp_canister_ids,
+                BTreeSet::from([controlled_dapp_canister_id, unavailable_dapp_canister_id])
+            );
+            assert_eq!(
+                state.lost_control_dapp_canister_ids,
+                vec![reassigned_dapp_canister_id, rejected_dapp_canister_id]
+            );
+            assert_eq!(state.latest_dapp_control_check_timestamp_seconds, Some(NOW));
+            assert_eq!(
+                state.events,
+                vec![RootEvent {
+                    timestamp_seconds: NOW,
+                    caller: None,
+                    payload: Some(root_event::Payload::DappCanistersControlLost(
+                        root_event::DappCanistersControlLost {
+                            dapp_canister_ids: vec![
+                                reassigned_dapp_canister_id,
+                                rejected_dapp_canister_id
+                            ],
+                        }
+                    )),
+                }]
+            );
+        });
+
+        // Re-registering a dapp that root regained control of removes it from
+        // the lost control list.
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::CanisterStatus(Ok(
+                CanisterStatusResultFromManagementCanister::dummy_with_controllers(vec![
+                    root_canister_id,
+                ]),
+            )),
+        ]);
+        SnsRootCanister::register_dapp_canisters(
+            &SNS_ROOT_CANISTER,
+            &management_canister_client,
+            root_canister_id.into(),
+            SNS_ROOT_CANISTER.with(|state| state.borrow().governance_canister_id()),
+            NOW,
+            RegisterDappCanistersRequest {
+                canister_ids: vec![reassigned_dapp_canister_id],
+            },
+        )
+        .await;
+
+        SNS_ROOT_CANISTER.with(|r| {
+            let state = r.borrow();
+            assert!(state
+                .dapp_canister_ids
+                .contains(&reassigned_dapp_canister_id));
+            assert_eq!(
+                state.lost_control_dapp_canister_ids,
+                vec![rejected_dapp_canister_id]
+            );
+        });
+    }
+
     #[tokio::test]
     async fn list_of_canisters_updates_when_update_canister_list_is_true() {
         // Step 1: Prepare the world.
@@ -2708,7 +5669,17 @@ mod tests {
             };
 
         // Step 2: Call the code under test.
-        SnsRootCanister::heartbeat(&SNS_ROOT_CANISTER, &ledger_canister_client, NOW).await;
+        SnsRootCanister::run_periodic_task(
+            &SNS_ROOT_CANISTER,
+            PeriodicTask::PollLedgerArchives,
+            &ledger_canister_client,
+            &management_canister_client,
+            &MockCyclesMintingClient::new(vec![]),
+            root_canister_id.get(),
+            NOW,
+        )
+        .await
+        .unwrap();
 
         // We should now have a single Archive canister registered.
         assert_archive_poll_state_change(
@@ -2723,9 +5694,11 @@ mod tests {
             &ledger_canister_client,
             &env,
             false,
+            governance_canister_id,
             root_canister_id.into(),
         )
-        .await;
+        .await
+        .unwrap();
 
         // No change should happen after our first call as it doesn't force an update.
         assert_archive_poll_state_change(
@@ -2740,9 +5713,11 @@ mod tests {
             &ledger_canister_client,
             &env,
             true,
+            governance_canister_id,
             root_canister_id.into(),
         )
-        .await;
+        .await
+        .unwrap();
 
         assert_eq!(
             first_result
@@ -2803,6 +5778,52 @@ mod tests {
             actual_management_canister_calls,
             expected_management_canister_calls
         );
+
+        // Only the second call forced an update.
+        SNS_ROOT_CANISTER.with(|sns_root| {
+            assert_eq!(sns_root.borrow().forced_canister_list_update_count, 1);
+        });
+    }
+
+    #[tokio::test]
+    async fn update_canister_list_is_rejected_unless_caller_is_governance() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static SNS_ROOT_CANISTER: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister(false));
+        }
+        let root_canister_id = CanisterId::from_u64(1000);
+        let caller = PrincipalId::new_user_test_id(42);
+
+        // No calls are expected, as the request is rejected before any work is done.
+        let management_canister_client = MockManagementCanisterClient::new(vec![]);
+        let ledger_canister_client = MockLedgerCanisterClient::new(vec![]);
+        let env = TestEnvironment {
+            calls: Default::default(),
+        };
+
+        // Step 2: Call the code under test.
+        let result = SnsRootCanister::get_sns_canisters_summary(
+            &SNS_ROOT_CANISTER,
+            &management_canister_client,
+            &ledger_canister_client,
+            &env,
+            true,
+            caller,
+            root_canister_id.into(),
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(
+            result,
+            Err(GetSnsCanistersSummaryError::UnauthorizedCanisterListUpdate { caller })
+        );
+        assert_eq!(management_canister_client.get_calls_snapshot(), vec![]);
+        SNS_ROOT_CANISTER.with(|sns_root| {
+            let sns_root = sns_root.borrow();
+            assert_eq!(sns_root.forced_canister_list_update_count, 0);
+            assert_eq!(sns_root.latest_ledger_archive_poll_timestamp_seconds, None);
+        });
     }
 
     #[tokio::test]
@@ -2942,9 +5963,11 @@ mod tests {
             &ledger_canister_client,
             &env,
             false,
+            governance_canister_id,
             root_canister_id.into(),
         )
-        .await;
+        .await
+        .unwrap();
 
         // Assert the dapp canister_id[0] and has some status
         assert_eq!(
@@ -2967,9 +5990,11 @@ mod tests {
             &ledger_canister_client,
             &env,
             false,
+            governance_canister_id,
             root_canister_id.into(),
         )
-        .await;
+        .await
+        .unwrap();
 
         // Assert the dapp canister_id[0] and has NO status
         assert_eq!(
@@ -3171,9 +6196,11 @@ mod tests {
             &ledger_canister_client,
             &env,
             false,
+            governance_canister_id,
             root_canister_id.into(),
         )
-        .await;
+        .await
+        .unwrap();
 
         // Assert the archive canister_id[0] and has some status
         assert_eq!(
@@ -3196,9 +6223,11 @@ mod tests {
             &ledger_canister_client,
             &env,
             false,
+            governance_canister_id,
             root_canister_id.into(),
         )
-        .await;
+        .await
+        .unwrap();
 
         // Assert the archive canister_id[0] and has NO status
         assert_eq!(
@@ -3262,4 +6291,48 @@ mod tests {
             expected_management_canister_calls
         );
     }
+
+    #[test]
+    fn test_detect_controller_drift() {
+        let canister_id = CanisterId::from_u64(42).get();
+        let root_canister_id = CanisterId::from_u64(1).get();
+        let intruder = PrincipalId::new_user_test_id(666);
+        let summary_with_controllers = |controllers: Vec<PrincipalId>| CanisterSummary {
+            canister_id: Some(canister_id),
+            status: Some(CanisterStatusResultV2::dummy_with_controllers(controllers)),
+        };
+
+        // The expected controllers do not cause a warning.
+        assert_eq!(
+            detect_controller_drift(
+                &summary_with_controllers(vec![root_canister_id]),
+                &[root_canister_id]
+            ),
+            None
+        );
+
+        // Canisters whose status is unknown are not reported.
+        assert_eq!(
+            detect_controller_drift(
+                &CanisterSummary::new_with_no_status(canister_id),
+                &[root_canister_id]
+            ),
+            None
+        );
+
+        // Added, replaced, and removed controllers are all reported.
+        for actual_controllers in [vec![root_canister_id, intruder], vec![intruder], vec![]] {
+            assert_eq!(
+                detect_controller_drift(
+                    &summary_with_controllers(actual_controllers.clone()),
+                    &[root_canister_id]
+                ),
+                Some(ControllerDrift {
+                    canister_id: Some(canister_id),
+                    expected_controllers: vec![root_canister_id],
+                    actual_controllers,
+                })
+            );
+        }
+    }
 }
@@ -4,4 +4,7 @@ use ic_canister_log::declare_log_buffer;
 declare_log_buffer!(name = INFO, capacity = 1000);
 
 // Error log messages.
-declare_log_buffer!(name = ERROR, capacity = 1000);
+declare_log_buffer!(name = ERROR, capacity = 1000, severity = Error);
+
+// Warning log messages, e.g. canister alerts.
+declare_log_buffer!(name = WARN, capacity = 1000, severity = Warn);
@@ -18,26 +18,30 @@ use ic_nervous_system_common::{
 use ic_nervous_system_runtime::DfnRuntime;
 use ic_sns_governance::ledger::LedgerCanister;
 use ic_sns_swap::{
-    clients::RealSnsRootClient,
+    clients::{RealParticipationEligibilityClient, RealSnsRootClient},
     logs::{ERROR, INFO},
     memory::UPGRADES_MEMORY,
     pb::v1::{
-        ErrorRefundIcpRequest, ErrorRefundIcpResponse, FinalizeSwapRequest, FinalizeSwapResponse,
+        AmendOpenSaleParamsRequest, AmendOpenSaleParamsResponse, ErrorRefundIcpRequest,
+        ErrorRefundIcpResponse, FinalizeSwapRequest, FinalizeSwapResponse,
         GetAutoFinalizationStatusRequest, GetAutoFinalizationStatusResponse, GetBuyerStateRequest,
         GetBuyerStateResponse, GetBuyersTotalRequest, GetBuyersTotalResponse,
-        GetCanisterStatusRequest, GetDerivedStateRequest, GetDerivedStateResponse, GetInitRequest,
-        GetInitResponse, GetLifecycleRequest, GetLifecycleResponse, GetOpenTicketRequest,
-        GetOpenTicketResponse, GetSaleParametersRequest, GetSaleParametersResponse,
-        GetStateRequest, GetStateResponse, Init, ListCommunityFundParticipantsRequest,
-        ListCommunityFundParticipantsResponse, ListDirectParticipantsRequest,
-        ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest, ListSnsNeuronRecipesResponse,
-        NewSaleTicketRequest, NewSaleTicketResponse, NotifyPaymentFailureRequest,
-        NotifyPaymentFailureResponse, OpenRequest, OpenResponse, RefreshBuyerTokensRequest,
-        RefreshBuyerTokensResponse, RestoreDappControllersRequest, RestoreDappControllersResponse,
-        Swap,
+        GetCanisterStatusRequest, GetDerivedStateRequest, GetDerivedStateResponse,
+        GetFinalizeStatusRequest, GetFinalizeStatusResponse, GetInitRequest, GetInitResponse,
+        GetLifecycleRequest, GetLifecycleResponse, GetOpenTicketRequest, GetOpenTicketResponse,
+        GetSaleParametersRequest, GetSaleParametersResponse, GetStateRequest, GetStateResponse,
+        Init, ListCommunityFundParticipantsRequest, ListCommunityFundParticipantsResponse,
+        ListDirectParticipantsRequest, ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest,
+        ListSnsNeuronRecipesResponse, NewSaleTicketRequest, NewSaleTicketResponse,
+        NotifyPaymentFailureRequest, NotifyPaymentFailureResponse, OpenRequest, OpenResponse,
+        RefreshBuyerTokensRequest, RefreshBuyerTokensResponse, RestoreDappControllersRequest,
+        RestoreDappControllersResponse, SimulateParticipationRequest,
+        SimulateParticipationResponse, Swap, ValidateOpenSaleParamsRequest,
+        ValidateOpenSaleParamsResponse,
     },
 };
 use ic_stable_structures::{writer::Writer, Memory};
+use icrc_ledger_client_retry::RetryConfig;
 use prost::Message;
 use std::{
     str::FromStr,
@@ -275,6 +279,24 @@ async fn restore_dapp_controllers_(
         .await
 }
 
+/// Extends the due date and/or raises the maximum amount of ICP of the swap
+/// while it is open. `amend_open_sale_params` is only callable by NNS
+/// Governance.
+#[export_name = "canister_update amend_open_sale_params"]
+fn amend_open_sale_params() {
+    over(candid_one, amend_open_sale_params_)
+}
+
+/// See `amend_open_sale_params`.
+#[candid_method(update, rename = "amend_open_sale_params")]
+fn amend_open_sale_params_(request: AmendOpenSaleParamsRequest) -> AmendOpenSaleParamsResponse {
+    log!(INFO, "amend_open_sale_params");
+    match swap_mut().amend_open_sale_params(caller(), now_seconds(), request) {
+        Ok(response) => response,
+        Err(msg) => panic!("{}", msg),
+    }
+}
+
 /// Return the current lifecycle stage (e.g. Open, Committed, etc)
 #[export_name = "canister_query get_lifecycle"]
 fn get_lifecycle() {
@@ -301,6 +323,45 @@ fn get_auto_finalization_status_(
     swap().get_auto_finalization_status(&request)
 }
 
+/// Return the progress of the finalization
+#[export_name = "canister_query get_finalize_status"]
+fn get_finalize_status() {
+    over(candid_one, get_finalize_status_)
+}
+
+#[candid_method(query, rename = "get_finalize_status")]
+fn get_finalize_status_(request: GetFinalizeStatusRequest) -> GetFinalizeStatusResponse {
+    log!(INFO, "get_finalize_status");
+    swap().get_finalize_status(&request)
+}
+
+/// Estimates what a participant would get for a given amount of ICP
+#[export_name = "canister_query simulate_participation"]
+fn simulate_participation() {
+    over(candid_one, simulate_participation_)
+}
+
+#[candid_method(query, rename = "simulate_participation")]
+fn simulate_participation_(request: SimulateParticipationRequest) -> SimulateParticipationResponse {
+    log!(INFO, "simulate_participation");
+    swap().simulate_participation(&request)
+}
+
+/// Checks the parameters of an OpenSnsTokenSwap proposal without opening the
+/// swap, and returns all violated invariants
+#[export_name = "canister_query validate_open_sale_params"]
+fn validate_open_sale_params() {
+    over(candid_one, validate_open_sale_params_)
+}
+
+#[candid_method(query, rename = "validate_open_sale_params")]
+fn validate_open_sale_params_(
+    request: ValidateOpenSaleParamsRequest,
+) -> ValidateOpenSaleParamsResponse {
+    log!(INFO, "validate_open_sale_params");
+    swap().validate_open_sale_params(now_seconds(), &request)
+}
+
 /// Returns the initialization data of the canister
 #[export_name = "canister_query get_init"]
 fn get_init() {
@@ -348,7 +409,22 @@ fn new_sale_ticket() {
 #[candid_method(update, rename = "new_sale_ticket")]
 async fn new_sale_ticket_(request: NewSaleTicketRequest) -> NewSaleTicketResponse {
     log!(INFO, "new_sale_ticket");
-    swap_mut().new_sale_ticket(&request, caller(), dfn_core::api::time_nanos())
+    let caller = caller();
+    // Consult the participation eligibility canister, if the swap has one.
+    if let Ok(Some(eligibility_canister_id)) = swap()
+        .init()
+        .and_then(|init| init.participation_eligibility_canister())
+    {
+        let mut eligibility_client =
+            RealParticipationEligibilityClient::new(eligibility_canister_id);
+        if let Err(response) = swap_mut()
+            .check_participation_eligibility(caller, now_seconds(), &mut eligibility_client)
+            .await
+        {
+            return response;
+        }
+    }
+    swap_mut().new_sale_ticket(&request, caller, dfn_core::api::time_nanos())
 }
 
 /// Lists direct participants in the Swap.
@@ -417,9 +493,10 @@ fn create_real_icp_ledger(id: CanisterId) -> ic_nervous_system_common::ledger::I
 
 /// Returns a real ledger stub that communicates with the specified
 /// canister, which is assumed to be a canister that implements the
-/// ICRC1 interface.
+/// ICRC1 interface. Calls failing due to the ledger being temporarily
+/// unavailable are retried.
 fn create_real_icrc1_ledger(id: CanisterId) -> LedgerCanister {
-    LedgerCanister::new(id)
+    LedgerCanister::new_with_retry_config(id, RetryConfig::default())
 }
 
 #[export_name = "canister_init"]
@@ -1,9 +1,11 @@
 use crate::pb::v1::{
-    CanisterCallError, GovernanceError, SetDappControllersRequest, SetDappControllersResponse,
+    CanisterCallError, CheckParticipationEligibilityRequest, CheckParticipationEligibilityResponse,
+    GovernanceError, SetDappControllersRequest, SetDappControllersResponse,
     SettleCommunityFundParticipation,
 };
 use async_trait::async_trait;
 use ic_base_types::CanisterId;
+use ic_sns_clients::{CanisterClient, DfnCanisterClient};
 use ic_sns_governance::pb::v1::{
     ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse, ManageNeuron, ManageNeuronResponse, SetMode,
     SetModeResponse,
@@ -17,30 +19,36 @@ pub trait SnsRootClient {
     ) -> Result<SetDappControllersResponse, CanisterCallError>;
 }
 
-pub struct RealSnsRootClient {
+pub struct RealSnsRootClient<C = DfnCanisterClient> {
     canister_id: CanisterId,
+    client: C,
 }
 
 impl RealSnsRootClient {
     pub fn new(canister_id: CanisterId) -> Self {
-        Self { canister_id }
+        Self::with_client(canister_id, DfnCanisterClient)
+    }
+}
+
+impl<C: CanisterClient> RealSnsRootClient<C> {
+    pub fn with_client(canister_id: CanisterId, client: C) -> Self {
+        Self {
+            canister_id,
+            client,
+        }
     }
 }
 
 #[async_trait]
-impl SnsRootClient for RealSnsRootClient {
+impl<C: CanisterClient> SnsRootClient for RealSnsRootClient<C> {
     async fn set_dapp_controllers(
         &mut self,
         request: SetDappControllersRequest,
     ) -> Result<SetDappControllersResponse, CanisterCallError> {
-        dfn_core::api::call(
-            self.canister_id,
-            "set_dapp_controllers",
-            dfn_candid::candid_one,
-            request,
-        )
-        .await
-        .map_err(CanisterCallError::from)
+        self.client
+            .call(self.canister_id, "set_dapp_controllers", request)
+            .await
+            .map_err(CanisterCallError::from)
     }
 }
 
@@ -59,58 +67,53 @@ pub trait SnsGovernanceClient {
     ) -> Result<ClaimSwapNeuronsResponse, CanisterCallError>;
 }
 
-pub struct RealSnsGovernanceClient {
+pub struct RealSnsGovernanceClient<C = DfnCanisterClient> {
     canister_id: CanisterId,
+    client: C,
 }
 
 impl RealSnsGovernanceClient {
     pub fn new(canister_id: CanisterId) -> Self {
-        Self { canister_id }
+        Self::with_client(canister_id, DfnCanisterClient)
+    }
+}
+
+impl<C: CanisterClient> RealSnsGovernanceClient<C> {
+    pub fn with_client(canister_id: CanisterId, client: C) -> Self {
+        Self {
+            canister_id,
+            client,
+        }
     }
 }
 
 #[async_trait]
-impl SnsGovernanceClient for RealSnsGovernanceClient {
+impl<C: CanisterClient> SnsGovernanceClient for RealSnsGovernanceClient<C> {
     async fn manage_neuron(
         &mut self,
         request: ManageNeuron,
     ) -> Result<ManageNeuronResponse, CanisterCallError> {
-        dfn_core::api::call(
-            self.canister_id,
-            "manage_neuron",
-            dfn_candid::candid_one,
-            request,
-        )
-        .await
-        .map_err(CanisterCallError::from)
+        self.client
+            .call(self.canister_id, "manage_neuron", request)
+            .await
+            .map_err(CanisterCallError::from)
     }
 
     async fn set_mode(&mut self, request: SetMode) -> Result<SetModeResponse, CanisterCallError> {
-        // TODO: Eliminate repetitive code. At least textually, the only
-        // difference is the second argument that gets passed to
-        // dfn_core::api::call (the name of the method).
-        dfn_core::api::call(
-            self.canister_id,
-            "set_mode",
-            dfn_candid::candid_one,
-            request,
-        )
-        .await
-        .map_err(CanisterCallError::from)
+        self.client
+            .call(self.canister_id, "set_mode", request)
+            .await
+            .map_err(CanisterCallError::from)
     }
 
     async fn claim_swap_neurons(
         &mut self,
         request: ClaimSwapNeuronsRequest,
     ) -> Result<ClaimSwapNeuronsResponse, CanisterCallError> {
-        dfn_core::api::call(
-            self.canister_id,
-            "claim_swap_neurons",
-            dfn_candid::candid_one,
-            request,
-        )
-        .await
-        .map_err(CanisterCallError::from)
+        self.client
+            .call(self.canister_id, "claim_swap_neurons", request)
+            .await
+            .map_err(CanisterCallError::from)
     }
 }
 
@@ -122,29 +125,82 @@ pub trait NnsGovernanceClient {
     ) -> Result<Result<(), GovernanceError>, CanisterCallError>;
 }
 
-pub struct RealNnsGovernanceClient {
+pub struct RealNnsGovernanceClient<C = DfnCanisterClient> {
     canister_id: CanisterId,
+    client: C,
 }
 
 impl RealNnsGovernanceClient {
     pub fn new(canister_id: CanisterId) -> Self {
-        Self { canister_id }
+        Self::with_client(canister_id, DfnCanisterClient)
+    }
+}
+
+impl<C: CanisterClient> RealNnsGovernanceClient<C> {
+    pub fn with_client(canister_id: CanisterId, client: C) -> Self {
+        Self {
+            canister_id,
+            client,
+        }
     }
 }
 
 #[async_trait]
-impl NnsGovernanceClient for RealNnsGovernanceClient {
+impl<C: CanisterClient> NnsGovernanceClient for RealNnsGovernanceClient<C> {
     async fn settle_community_fund_participation(
         &mut self,
         request: SettleCommunityFundParticipation,
     ) -> Result<Result<(), GovernanceError>, CanisterCallError> {
-        dfn_core::api::call(
-            self.canister_id,
-            "settle_community_fund_participation",
-            dfn_candid::candid_one,
-            request,
-        )
-        .await
-        .map_err(CanisterCallError::from)
+        self.client
+            .call(
+                self.canister_id,
+                "settle_community_fund_participation",
+                request,
+            )
+            .await
+            .map_err(CanisterCallError::from)
+    }
+}
+
+/// A canister that decides which principals may participate in the swap (see
+/// `Init.participation_eligibility_canister_id`).
+#[async_trait]
+pub trait ParticipationEligibilityClient {
+    async fn check_participation_eligibility(
+        &mut self,
+        request: CheckParticipationEligibilityRequest,
+    ) -> Result<CheckParticipationEligibilityResponse, CanisterCallError>;
+}
+
+pub struct RealParticipationEligibilityClient<C = DfnCanisterClient> {
+    canister_id: CanisterId,
+    client: C,
+}
+
+impl RealParticipationEligibilityClient {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self::with_client(canister_id, DfnCanisterClient)
+    }
+}
+
+impl<C: CanisterClient> RealParticipationEligibilityClient<C> {
+    pub fn with_client(canister_id: CanisterId, client: C) -> Self {
+        Self {
+            canister_id,
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl<C: CanisterClient> ParticipationEligibilityClient for RealParticipationEligibilityClient<C> {
+    async fn check_participation_eligibility(
+        &mut self,
+        request: CheckParticipationEligibilityRequest,
+    ) -> Result<CheckParticipationEligibilityResponse, CanisterCallError> {
+        self.client
+            .call(self.canister_id, "check_participation_eligibility", request)
+            .await
+            .map_err(CanisterCallError::from)
     }
 }
@@ -221,6 +221,38 @@ pub struct Swap {
     /// Amount of contributions from the Neurons' Fund committed to this SNS so far.
     #[prost(uint64, optional, tag = "20")]
     pub neurons_fund_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The key of the next buyer whose subaccount should be checked for residual
+    /// ICP by the refund routine running in the heartbeat. Not set while no
+    /// refund round is in progress.
+    #[prost(string, optional, tag = "21")]
+    pub residual_icp_refund_next_buyer: ::core::option::Option<::prost::alloc::string::String>,
+    /// The last time a round of the residual ICP refund routine was completed.
+    #[prost(uint64, optional, tag = "22")]
+    pub residual_icp_refund_last_completion_timestamp_seconds: ::core::option::Option<u64>,
+    /// The progress of the finalization of the swap. Not set until finalization
+    /// has started.
+    #[prost(message, optional, tag = "23")]
+    pub finalize_swap_progress: ::core::option::Option<FinalizeSwapProgress>,
+    /// The results of consulting the participation eligibility canister (see
+    /// `Init.participation_eligibility_canister_id`), cached per principal. The
+    /// key is the textual representation of the principal.
+    #[prost(btree_map = "string, message", tag = "24")]
+    pub participation_eligibility: ::prost::alloc::collections::BTreeMap<
+        ::prost::alloc::string::String,
+        ParticipationEligibility,
+    >,
+    /// The amendments made to `params` while the swap was open (see
+    /// `amend_open_sale_params`), oldest first.
+    #[prost(message, repeated, tag = "25")]
+    pub sale_params_amendments: ::prost::alloc::vec::Vec<SaleParamsAmendment>,
+    /// The key of the first buyer of the next chunk of buyers whose ICP should be
+    /// swept by finalization. Not set until a chunk has been swept, and cleared
+    /// once all buyers have been swept.
+    #[prost(string, optional, tag = "26")]
+    pub sweep_icp_next_buyer: ::core::option::Option<::prost::alloc::string::String>,
+    /// The last time expired entries were purged from `participation_eligibility`.
+    #[prost(uint64, optional, tag = "27")]
+    pub participation_eligibility_purge_timestamp_seconds: ::core::option::Option<u64>,
 }
 /// The initialisation data of the canister. Always specified on
 /// canister creation, and cannot be modified afterwards.
@@ -352,6 +384,13 @@ pub struct Init {
     #[prost(message, optional, tag = "29")]
     pub neurons_fund_participation_constraints:
         ::core::option::Option<NeuronsFundParticipationConstraints>,
+    /// An optional canister that decides which principals may participate in the
+    /// swap, and with how much ICP (e.g., a geofencing or KYC whitelist maintained
+    /// by the dapp team). If set, the swap consults it in `new_sale_ticket`, and
+    /// only accepts the participation of principals it found eligible.
+    #[prost(string, optional, tag = "30")]
+    pub participation_eligibility_canister_id:
+        ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Constraints for the Neurons' Fund participation in an SNS swap.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable, Eq)]
@@ -585,6 +624,113 @@ pub struct BuyerState {
     /// * ABORTED - owned by the buyer, can be transferred out
     #[prost(message, optional, tag = "5")]
     pub icp: ::core::option::Option<TransferableAmount>,
+    /// The status of the automatic refund of the ICP left in this buyer's
+    /// subaccount once the swap is over. Not set until the refund routine has
+    /// inspected the subaccount.
+    #[prost(message, optional, tag = "6")]
+    pub residual_icp_refund: ::core::option::Option<ResidualIcpRefund>,
+    /// The confirmation text this buyer accepted when its participation was last
+    /// accepted, if the SNS specified one (see `Init.confirmation_text`).
+    #[prost(string, optional, tag = "7")]
+    pub confirmation_text: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Once a swap is committed or aborted and the ICP of a buyer has been swept,
+/// any ICP left in the buyer's subaccount (e.g., ICP that was sent but never
+/// accepted by the swap) is refunded to the buyer without the buyer having to
+/// call `error_refund_icp`.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResidualIcpRefund {
+    #[prost(enumeration = "residual_icp_refund::Status", optional, tag = "1")]
+    pub status: ::core::option::Option<i32>,
+    /// When the subaccount was last inspected.
+    #[prost(uint64, tag = "2")]
+    pub timestamp_seconds: u64,
+    /// The amount refunded to the buyer (minus the transfer fee).
+    #[prost(uint64, optional, tag = "3")]
+    pub amount_refunded_e8s: ::core::option::Option<u64>,
+    /// The ICP ledger block height of the refund.
+    #[prost(uint64, optional, tag = "4")]
+    pub block_height: ::core::option::Option<u64>,
+    /// Why the last attempt failed, if it did.
+    #[prost(string, optional, tag = "5")]
+    pub error_message: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Nested message and enum types in `ResidualIcpRefund`.
+pub mod residual_icp_refund {
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        serde::Serialize,
+        comparable::Comparable,
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration,
+    )]
+    #[repr(i32)]
+    pub enum Status {
+        /// Unused, here for PB lint purposes.
+        Unspecified = 0,
+        /// The subaccount did not hold more than the transfer fee.
+        NothingToRefund = 1,
+        /// The residual ICP (minus the transfer fee) was refunded to the buyer.
+        Refunded = 2,
+        /// The balance of the subaccount could not be determined or the transfer
+        /// failed. The refund is retried in the next round.
+        Failed = 3,
+    }
+    impl Status {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Status::Unspecified => "STATUS_UNSPECIFIED",
+                Status::NothingToRefund => "STATUS_NOTHING_TO_REFUND",
+                Status::Refunded => "STATUS_REFUNDED",
+                Status::Failed => "STATUS_FAILED",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+                "STATUS_NOTHING_TO_REFUND" => Some(Self::NothingToRefund),
+                "STATUS_REFUNDED" => Some(Self::Refunded),
+                "STATUS_FAILED" => Some(Self::Failed),
+                _ => None,
+            }
+        }
+    }
+}
+/// The (cached) eligibility of a principal to participate in the swap, as
+/// determined by the participation eligibility canister.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParticipationEligibility {
+    #[prost(bool, optional, tag = "1")]
+    pub eligible: ::core::option::Option<bool>,
+    /// Why the principal is not eligible, if it is not.
+    #[prost(string, optional, tag = "2")]
+    pub reason: ::core::option::Option<::prost::alloc::string::String>,
+    /// Bounds on the participation of the principal, in addition to
+    /// `Params.min_participant_icp_e8s` and `Params.max_participant_icp_e8s`.
+    #[prost(uint64, optional, tag = "3")]
+    pub min_participant_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub max_participant_icp_e8s: ::core::option::Option<u64>,
+    /// When the participation eligibility canister was consulted.
+    #[prost(uint64, optional, tag = "5")]
+    pub timestamp_seconds: ::core::option::Option<u64>,
 }
 /// Information about a direct investor.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -892,6 +1038,102 @@ pub struct FinalizeSwapResponse {
     #[prost(string, optional, tag = "7")]
     pub error_message: ::core::option::Option<::prost::alloc::string::String>,
 }
+/// The progress of the finalization of the swap. Finalization is a sequence of
+/// idempotent steps. Automatic finalization performs one step per heartbeat, so
+/// that the work of large swaps is spread across many messages, and retries a
+/// failed step in a later heartbeat.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FinalizeSwapProgress {
+    /// The next step to perform.
+    #[prost(enumeration = "finalize_swap_progress::Step", optional, tag = "1")]
+    pub next_step: ::core::option::Option<i32>,
+    /// The results of the steps performed so far. `error_message` is set while
+    /// `next_step` keeps failing.
+    #[prost(message, optional, tag = "2")]
+    pub response: ::core::option::Option<FinalizeSwapResponse>,
+    /// The number of consecutive failed attempts of `next_step`.
+    #[prost(uint32, optional, tag = "3")]
+    pub failed_attempts: ::core::option::Option<u32>,
+    /// When the last step was performed.
+    #[prost(uint64, optional, tag = "4")]
+    pub last_step_timestamp_seconds: ::core::option::Option<u64>,
+}
+/// Nested message and enum types in `FinalizeSwapProgress`.
+pub mod finalize_swap_progress {
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        serde::Serialize,
+        comparable::Comparable,
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration,
+    )]
+    #[repr(i32)]
+    pub enum Step {
+        /// Unused, here for PB lint purposes.
+        Unspecified = 0,
+        /// Transfer the ICP of the buyers.
+        SweepIcp = 1,
+        /// Settle the participation of the Neurons' Fund.
+        SettleCommunityFundParticipation = 2,
+        /// Restore the controllers of the dapp canisters (if the swap was aborted).
+        SetDappControllers = 3,
+        /// Transfer the SNS tokens to the accounts of the neurons.
+        SweepSns = 4,
+        /// Claim the SNS neurons of the participants. Repeated until all neurons
+        /// have been claimed.
+        ClaimNeurons = 5,
+        /// Set SNS Governance to normal mode.
+        SetMode = 6,
+        /// Finalization has completed.
+        Done = 7,
+    }
+    impl Step {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Step::Unspecified => "STEP_UNSPECIFIED",
+                Step::SweepIcp => "STEP_SWEEP_ICP",
+                Step::SettleCommunityFundParticipation => {
+                    "STEP_SETTLE_COMMUNITY_FUND_PARTICIPATION"
+                }
+                Step::SetDappControllers => "STEP_SET_DAPP_CONTROLLERS",
+                Step::SweepSns => "STEP_SWEEP_SNS",
+                Step::ClaimNeurons => "STEP_CLAIM_NEURONS",
+                Step::SetMode => "STEP_SET_MODE",
+                Step::Done => "STEP_DONE",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "STEP_UNSPECIFIED" => Some(Self::Unspecified),
+                "STEP_SWEEP_ICP" => Some(Self::SweepIcp),
+                "STEP_SETTLE_COMMUNITY_FUND_PARTICIPATION" => {
+                    Some(Self::SettleCommunityFundParticipation)
+                }
+                "STEP_SET_DAPP_CONTROLLERS" => Some(Self::SetDappControllers),
+                "STEP_SWEEP_SNS" => Some(Self::SweepSns),
+                "STEP_CLAIM_NEURONS" => Some(Self::ClaimNeurons),
+                "STEP_SET_MODE" => Some(Self::SetMode),
+                "STEP_DONE" => Some(Self::Done),
+                _ => None,
+            }
+        }
+    }
+}
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1428,6 +1670,20 @@ pub struct GetAutoFinalizationStatusResponse {
     #[prost(message, optional, tag = "3")]
     pub auto_finalize_swap_response: ::core::option::Option<FinalizeSwapResponse>,
 }
+/// Request struct for the method `get_finalize_status`
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFinalizeStatusRequest {}
+/// Response struct for the method `get_finalize_status`
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFinalizeStatusResponse {
+    /// The progress of the finalization. Not set until finalization has started.
+    #[prost(message, optional, tag = "1")]
+    pub progress: ::core::option::Option<FinalizeSwapProgress>,
+}
 /// Request struct for the method `get_init`
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -1617,6 +1873,11 @@ pub struct NewSaleTicketRequest {
     /// The subaccount of the caller to be used for the ticket
     #[prost(bytes = "vec", optional, tag = "2")]
     pub subaccount: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// To accept the swap participation confirmation, a participant should send
+    /// the confirmation text when requesting a ticket, matching the text set
+    /// during SNS initialization.
+    #[prost(string, optional, tag = "3")]
+    pub confirmation_text: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Response struct for the method `new_sale_ticket`
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -1701,6 +1962,12 @@ pub mod new_sale_ticket_response {
             InvalidSubaccount = 5,
             /// The specified principal is forbidden from creating tickets.
             InvalidPrincipal = 6,
+            /// The confirmation text was missing or did not match the text set
+            /// during SNS initialization.
+            InvalidConfirmationText = 7,
+            /// The participation eligibility canister did not find the caller
+            /// eligible to participate, or could not be consulted.
+            NotEligible = 8,
         }
         impl Type {
             /// String value of the enum field names used in the ProtoBuf definition.
@@ -1716,6 +1983,8 @@ pub mod new_sale_ticket_response {
                     Type::InvalidUserAmount => "TYPE_INVALID_USER_AMOUNT",
                     Type::InvalidSubaccount => "TYPE_INVALID_SUBACCOUNT",
                     Type::InvalidPrincipal => "TYPE_INVALID_PRINCIPAL",
+                    Type::InvalidConfirmationText => "TYPE_INVALID_CONFIRMATION_TEXT",
+                    Type::NotEligible => "TYPE_NOT_ELIGIBLE",
                 }
             }
             /// Creates an enum from field names used in the ProtoBuf definition.
@@ -1728,6 +1997,8 @@ pub mod new_sale_ticket_response {
                     "TYPE_INVALID_USER_AMOUNT" => Some(Self::InvalidUserAmount),
                     "TYPE_INVALID_SUBACCOUNT" => Some(Self::InvalidSubaccount),
                     "TYPE_INVALID_PRINCIPAL" => Some(Self::InvalidPrincipal),
+                    "TYPE_INVALID_CONFIRMATION_TEXT" => Some(Self::InvalidConfirmationText),
+                    "TYPE_NOT_ELIGIBLE" => Some(Self::NotEligible),
                     _ => None,
                 }
             }
@@ -1743,6 +2014,112 @@ pub mod new_sale_ticket_response {
         Err(Err),
     }
 }
+/// Request struct for the method `check_participation_eligibility`, which the
+/// participation eligibility canister (if any) must implement.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CheckParticipationEligibilityRequest {
+    /// The principal that wants to participate in the swap.
+    #[prost(message, optional, tag = "1")]
+    pub principal_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+}
+/// Response struct for the method `check_participation_eligibility`.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CheckParticipationEligibilityResponse {
+    #[prost(bool, optional, tag = "1")]
+    pub eligible: ::core::option::Option<bool>,
+    /// Why the principal is not eligible, if it is not.
+    #[prost(string, optional, tag = "2")]
+    pub reason: ::core::option::Option<::prost::alloc::string::String>,
+    /// Optional bounds on the participation of the principal.
+    #[prost(uint64, optional, tag = "3")]
+    pub min_participant_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub max_participant_icp_e8s: ::core::option::Option<u64>,
+}
+/// Request struct for the method `simulate_participation`.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SimulateParticipationRequest {
+    /// The amount of ICP e8s that the user considers contributing.
+    #[prost(uint64, tag = "1")]
+    pub amount_icp_e8s: u64,
+}
+/// Response struct for the method `simulate_participation`. All fields are
+/// unset if the swap has not been opened yet.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SimulateParticipationResponse {
+    /// The part of `amount_icp_e8s` that fits into the remaining capacity
+    /// for direct participation and into `Params.max_participant_icp_e8s`.
+    /// Zero if that is less than `Params.min_participant_icp_e8s`.
+    #[prost(uint64, optional, tag = "1")]
+    pub accepted_amount_icp_e8s: ::core::option::Option<u64>,
+    /// The SNS tokens that the participant would receive, assuming that
+    /// nobody else participates. Further participation lowers this amount.
+    #[prost(uint64, optional, tag = "2")]
+    pub estimated_sns_token_e8s: ::core::option::Option<u64>,
+    /// How `estimated_sns_token_e8s` would be split into a basket of neurons
+    /// according to `Params.neuron_basket_construction_parameters`.
+    #[prost(message, repeated, tag = "3")]
+    pub neuron_basket: ::prost::alloc::vec::Vec<simulate_participation_response::SimulatedNeuron>,
+    /// The current fill level of the swap.
+    #[prost(uint64, optional, tag = "4")]
+    pub current_total_participation_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub max_total_participation_e8s: ::core::option::Option<u64>,
+}
+/// Nested message and enum types in `SimulateParticipationResponse`.
+pub mod simulate_participation_response {
+    /// A neuron of the basket that the participant would receive.
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SimulatedNeuron {
+        #[prost(uint64, optional, tag = "1")]
+        pub amount_e8s: ::core::option::Option<u64>,
+        #[prost(uint64, optional, tag = "2")]
+        pub dissolve_delay_seconds: ::core::option::Option<u64>,
+    }
+}
+/// Request struct for the method `validate_open_sale_params`.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateOpenSaleParamsRequest {
+    /// The parameters that an OpenSnsTokenSwap proposal would pass to `open`.
+    #[prost(message, optional, tag = "1")]
+    pub params: ::core::option::Option<Params>,
+}
+/// Response struct for the method `validate_open_sale_params`. The swap
+/// would accept `params` if opened now iff `violations` is empty.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateOpenSaleParamsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub violations: ::prost::alloc::vec::Vec<validate_open_sale_params_response::ParamsViolation>,
+}
+/// Nested message and enum types in `ValidateOpenSaleParamsResponse`.
+pub mod validate_open_sale_params_response {
+    /// An invariant of `Params` that does not hold.
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ParamsViolation {
+        /// The name of the offending field of `Params`, e.g. "min_icp_e8s".
+        #[prost(string, optional, tag = "1")]
+        pub field: ::core::option::Option<::prost::alloc::string::String>,
+        /// A human-readable description of the invariant.
+        #[prost(string, optional, tag = "2")]
+        pub description: ::core::option::Option<::prost::alloc::string::String>,
+    }
+}
 /// Request struct for the method `list_direct_participants`. This method
 /// paginates over all direct participants in the decentralization swap.
 /// Direct participants are participants who did not participate via the
@@ -1806,6 +2183,54 @@ pub struct GetSaleParametersRequest {}
 pub struct GetSaleParametersResponse {
     #[prost(message, optional, tag = "1")]
     pub params: ::core::option::Option<Params>,
+    /// The amendments that led to the current `params`, oldest first.
+    #[prost(message, repeated, tag = "2")]
+    pub amendments: ::prost::alloc::vec::Vec<SaleParamsAmendment>,
+}
+/// Request struct for the method `amend_open_sale_params`, which NNS
+/// governance calls to amend the parameters of an open swap. Unset fields are
+/// left unchanged.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AmendOpenSaleParamsRequest {
+    /// The new due date of the swap. Must be later than the current one, and
+    /// must keep the swap at most 90 days long.
+    #[prost(uint64, optional, tag = "1")]
+    pub swap_due_timestamp_seconds: ::core::option::Option<u64>,
+    /// The new maximum amount of ICP that can be collected. Must be at least the
+    /// current one.
+    #[prost(uint64, optional, tag = "2")]
+    pub max_icp_e8s: ::core::option::Option<u64>,
+    /// The ID of the NNS proposal that amends the swap, kept for auditing.
+    #[prost(uint64, optional, tag = "3")]
+    pub proposal_id: ::core::option::Option<u64>,
+}
+/// Response struct for the method `amend_open_sale_params`.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AmendOpenSaleParamsResponse {}
+/// An amendment of the parameters of an open swap. The old and new values are
+/// only set for the parameters that the amendment changed.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SaleParamsAmendment {
+    /// When the amendment was made, in seconds since the Unix epoch.
+    #[prost(uint64, tag = "1")]
+    pub timestamp_seconds: u64,
+    /// The ID of the NNS proposal that amended the swap, if known.
+    #[prost(uint64, optional, tag = "2")]
+    pub proposal_id: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub old_swap_due_timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub new_swap_due_timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub old_max_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "6")]
+    pub new_max_icp_e8s: ::core::option::Option<u64>,
 }
 /// Request struct for the method `list_community_fund_participants`.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
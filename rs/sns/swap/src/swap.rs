@@ -1,34 +1,43 @@
 use crate::{
-    clients::{NnsGovernanceClient, SnsGovernanceClient, SnsRootClient},
+    clients::{
+        NnsGovernanceClient, ParticipationEligibilityClient, SnsGovernanceClient, SnsRootClient,
+    },
     environment::CanisterEnvironment,
     logs::{ERROR, INFO},
     memory,
     pb::v1::{
-        get_open_ticket_response, new_sale_ticket_response, restore_dapp_controllers_response,
-        set_dapp_controllers_call_result, set_mode_call_result,
+        finalize_swap_progress::Step as FinalizeSwapStep,
+        get_open_ticket_response, new_sale_ticket_response, residual_icp_refund,
+        restore_dapp_controllers_response, set_dapp_controllers_call_result, set_mode_call_result,
         set_mode_call_result::SetModeResult,
-        settle_community_fund_participation_result,
+        settle_community_fund_participation_result, simulate_participation_response,
         sns_neuron_recipe::{ClaimedStatus, Investor, NeuronAttributes},
-        BuyerState, CanisterCallError, CfInvestment, DerivedState, DirectInvestment,
-        ErrorRefundIcpRequest, ErrorRefundIcpResponse, FinalizeSwapResponse,
+        validate_open_sale_params_response::ParamsViolation,
+        AmendOpenSaleParamsRequest, AmendOpenSaleParamsResponse, BuyerState, CanisterCallError,
+        CfInvestment, CheckParticipationEligibilityRequest, DerivedState, DirectInvestment,
+        ErrorRefundIcpRequest, ErrorRefundIcpResponse, FinalizeSwapProgress, FinalizeSwapResponse,
         GetAutoFinalizationStatusRequest, GetAutoFinalizationStatusResponse, GetBuyerStateRequest,
         GetBuyerStateResponse, GetBuyersTotalResponse, GetDerivedStateResponse,
-        GetLifecycleRequest, GetLifecycleResponse, GetOpenTicketRequest, GetOpenTicketResponse,
+        GetFinalizeStatusRequest, GetFinalizeStatusResponse, GetLifecycleRequest,
+        GetLifecycleResponse, GetOpenTicketRequest, GetOpenTicketResponse,
         GetSaleParametersRequest, GetSaleParametersResponse, GetStateResponse, Init, Lifecycle,
         LinearScalingCoefficient, ListCommunityFundParticipantsRequest,
         ListCommunityFundParticipantsResponse, ListDirectParticipantsRequest,
         ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest, ListSnsNeuronRecipesResponse,
         NeuronBasketConstructionParameters, NeuronId as SaleNeuronId, NewSaleTicketRequest,
-        NewSaleTicketResponse, OpenRequest, OpenResponse, Participant, RefreshBuyerTokensResponse,
-        RestoreDappControllersResponse, SetDappControllersCallResult, SetModeCallResult,
-        SettleCommunityFundParticipationResult, SnsNeuronRecipe, Swap, SweepResult, Ticket,
-        TransferableAmount,
+        NewSaleTicketResponse, OpenRequest, OpenResponse, Params, Participant,
+        ParticipationEligibility, RefreshBuyerTokensResponse, ResidualIcpRefund,
+        RestoreDappControllersResponse, SaleParamsAmendment, SetDappControllersCallResult,
+        SetModeCallResult, SettleCommunityFundParticipationResult, SimulateParticipationRequest,
+        SimulateParticipationResponse, SnsNeuronRecipe, Swap, SweepResult, Ticket,
+        TransferableAmount, ValidateOpenSaleParamsRequest, ValidateOpenSaleParamsResponse,
     },
     types::{ScheduledVestingEvent, TransferResult},
 };
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
 use dfn_core::CanisterId;
+use futures::stream::{self, StreamExt};
 use ic_base_types::PrincipalId;
 use ic_canister_log::log;
 use ic_ledger_core::Tokens;
@@ -52,7 +61,7 @@ use rust_decimal::prelude::ToPrimitive;
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt,
     num::{NonZeroU128, NonZeroU64},
     ops::{
@@ -104,6 +113,31 @@ pub const FIRST_PRINCIPAL_BYTES: [u8; PrincipalId::MAX_LENGTH_IN_BYTES] =
 /// 2. Avoid having the SNS Governance canister hit the instruction limit per message.
 pub const CLAIM_SWAP_NEURONS_BATCH_SIZE: usize = 500;
 
+/// The minimum time between the starts of two rounds of refunding the ICP left
+/// in the subaccounts of buyers after the swap is over.
+pub const RESIDUAL_ICP_REFUND_INTERVAL_SECONDS: u64 = 60 * 60; // 1 hour
+
+/// The number of buyers whose ICP is swept together by `sweep_icp`. Once all
+/// buyers of a chunk are swept, the progress is recorded, so that a failed sweep
+/// resumes with the chunk that failed.
+pub const SWEEP_ICP_CHUNK_SIZE: usize = 100;
+
+/// The maximum number of ICP transfers in flight at once while sweeping a chunk.
+pub const SWEEP_ICP_MAX_CONCURRENT_TRANSFERS: usize = 10;
+
+/// The number of attempts made to transfer the ICP of the buyers of a chunk,
+/// after which `sweep_icp` gives up until the next sweep.
+pub const SWEEP_ICP_MAX_CHUNK_ATTEMPTS: u32 = 3;
+
+/// The number of consecutive failed attempts of a step of automatic finalization
+/// after which automatic finalization gives up. Manually calling finalize is still
+/// allowed afterwards.
+pub const MAX_AUTO_FINALIZE_STEP_ATTEMPTS: u32 = 3;
+
+/// How long the participation eligibility of a principal is cached before the
+/// participation eligibility canister is consulted again.
+pub const PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS: u64 = 60 * 60; // 1 hour
+
 impl From<(Option<i32>, String)> for CanisterCallError {
     fn from((code, description): (Option<i32>, String)) -> Self {
         Self { code, description }
@@ -475,6 +509,7 @@ mod swap_participation {
         logs::ERROR,
         swap::{Lifecycle, Swap},
     };
+    use ic_base_types::PrincipalId;
     use ic_canister_log::log;
 
     impl Swap {
@@ -530,6 +565,31 @@ mod swap_participation {
                 (None, None) => Ok(()),
             }
         }
+
+        /// If the swap has a participation eligibility canister, the buyer must have been
+        /// found eligible by it when requesting a ticket (see `new_sale_ticket`).
+        pub fn validate_participation_eligibility(
+            &self,
+            buyer: &PrincipalId,
+        ) -> Result<(), String> {
+            if self
+                .init_or_panic()
+                .participation_eligibility_canister_id
+                .is_none()
+            {
+                return Ok(());
+            }
+            match self.participation_eligibility.get(&buyer.to_string()) {
+                Some(eligibility) if eligibility.eligible() => Ok(()),
+                Some(_) => Err(format!(
+                    "The principal {buyer} is not eligible to participate in the swap."
+                )),
+                None => Err(format!(
+                    "The eligibility of the principal {buyer} to participate in the swap is unknown. \
+                    It is determined when calling `new_sale_ticket`."
+                )),
+            }
+        }
     }
 
     pub fn context_before_awaiting_icp_ledger_response(err: String) -> String {
@@ -567,6 +627,13 @@ impl Swap {
             auto_finalize_swap_response: None,
             direct_participation_icp_e8s: None,
             neurons_fund_participation_icp_e8s: None,
+            residual_icp_refund_next_buyer: None,
+            residual_icp_refund_last_completion_timestamp_seconds: None,
+            finalize_swap_progress: None,
+            participation_eligibility: Default::default(),
+            sale_params_amendments: vec![],
+            sweep_icp_next_buyer: None,
+            participation_eligibility_purge_timestamp_seconds: None,
         };
         if init.is_swap_init_for_one_proposal_flow() {
             // Automatically fill out the fields that the (legacy) open request
@@ -762,9 +829,12 @@ impl Swap {
         true
     }
 
-    /// Attempts to finalize the swap. If this function calls [`Self::finalize`],
-    /// it will set `self.already_tried_to_auto_finalize` to `Some(true)`, and
-    /// won't try to finalize the swap again, even if called again.
+    /// Attempts to finalize the swap in a single call. If this function calls
+    /// [`Self::finalize`], it will set `self.already_tried_to_auto_finalize` to
+    /// `Some(true)`, and won't try to finalize the swap again, even if called again.
+    ///
+    /// The heartbeat finalizes the swap one step at a time instead, see
+    /// [`Self::start_auto_finalization`].
     ///
     /// The argument 'now_fn' is a function that returns the current time
     /// for bookkeeping of transfers. For easier testing, it is given
@@ -1071,6 +1141,8 @@ impl Swap {
             MAX_NUMBER_OF_PRINCIPALS_TO_INSPECT,
         );
 
+        self.try_purge_expired_participation_eligibility(heartbeat_start_seconds);
+
         // Automatically transition the state. Only one state transition per heartbeat.
 
         // Auto-open the swap
@@ -1093,16 +1165,23 @@ impl Swap {
                 heartbeat_start_seconds
             );
         }
-        // Auto-finalize the swap
+        // Start auto-finalizing the swap
         // We discard the error, if there is one, because to log it would mean
         // it would be logged every heartbeat where we fall through to this
         // point (and we don't want to spam the logs).
-        else if self.can_auto_finalize().is_ok() {
-            // First, record when the finalization started, in case this function is
-            // refactored to `await` before this point.
-            let auto_finalization_start_seconds = now_fn(false);
-
-            // Then, get the environment
+        else if self
+            .start_auto_finalization(heartbeat_start_seconds)
+            .is_ok()
+        {
+            log!(
+                INFO,
+                "Swap auto-finalization started at timestamp {}",
+                heartbeat_start_seconds
+            );
+        }
+        // Perform the next step of the auto-finalization. Spreading the steps
+        // across heartbeats keeps each of them within the instruction limit.
+        else if self.is_auto_finalization_in_progress() {
             let environment = self
                 .init
                 .as_ref()
@@ -1117,26 +1196,41 @@ impl Swap {
                     );
                 }
                 Ok(mut environment) => {
-                    // Then, attempt the auto-finalization
-                    // `try_auto_finalize` will never return `Error` here
-                    // because we already checked `self.can_auto_finalize()`
-                    // above, and `try_auto_finalize` will only return an error
-                    // if `can_auto_finalize` does.
-                    // The FinalizeSwapResponse from finalization will be logged
-                    // by `Self::finalize`.
-                    if self
-                        .try_auto_finalize(now_fn, &mut environment)
+                    if let Some(progress) = self
+                        .try_advance_auto_finalization(
+                            now_fn,
+                            &mut environment,
+                            CLAIM_SWAP_NEURONS_BATCH_SIZE,
+                        )
                         .await
-                        .is_ok()
                     {
-                        // The current time is now probably different than the time when
-                        // auto-finalization began, due to the `await`.
-                        let auto_finalization_finish_seconds = now_fn(true);
-                        log!(INFO, "Swap auto-finalization finished at timestamp {auto_finalization_finish_seconds} (started at timestamp {auto_finalization_start_seconds})");
+                        log!(
+                            INFO,
+                            "Swap auto-finalization step done, next step: {:?}",
+                            progress.next_step()
+                        );
                     }
                 }
             }
         }
+
+        // Refund the ICP left in the subaccounts of buyers once the swap is over.
+        const MAX_NUMBER_OF_BUYERS_TO_REFUND: u64 = 100;
+        if self.lifecycle_is_terminal() {
+            if let Ok(environment) = self.init().and_then(|init| init.environment()) {
+                if let Some(sweep_result) = self
+                    .try_refund_residual_icp(
+                        now_fn,
+                        dfn_core::api::id(),
+                        environment.icp_ledger(),
+                        MAX_NUMBER_OF_BUYERS_TO_REFUND,
+                    )
+                    .await
+                {
+                    log!(INFO, "Residual ICP refund batch done: {:?}", sweep_result);
+                }
+            }
+        }
     }
 
     /*
@@ -1185,7 +1279,8 @@ impl Swap {
             .map_err(context_before_awaiting_icp_ledger_response)?;
 
         // User input validation doesn't expire after await, so this check doesn't need repetition.
-        self.validate_confirmation_text(confirmation_text)?;
+        self.validate_confirmation_text(confirmation_text.clone())?;
+        self.validate_participation_eligibility(&buyer)?;
 
         // Look for the token balance of the specified principal's subaccount on 'this' canister.
         let e8s = {
@@ -1214,15 +1309,17 @@ impl Swap {
         // Subtraction safe because of the preceding if-statement.
         let max_increment_e8s = self.available_direct_participation_e8s();
 
+        let (min_participant_icp_e8s, max_participant_icp_e8s) =
+            self.participant_icp_e8s_bounds(params, &buyer);
+
         // Check that the minimum amount has been transferred before
         // actually creating an entry for the buyer.
-        if e8s < params.min_participant_icp_e8s {
+        if e8s < min_participant_icp_e8s {
             return Err(format!(
                 "Amount transferred: {}; minimum required to participate: {}",
-                e8s, params.min_participant_icp_e8s
+                e8s, min_participant_icp_e8s
             ));
         }
-        let max_participant_icp_e8s = params.max_participant_icp_e8s;
 
         let old_amount_icp_e8s = self
             .buyers
@@ -1255,10 +1352,10 @@ impl Swap {
 
         // Check that the new_balance_e8s is bigger than the minimum required for
         // participating.
-        if new_balance_e8s < params.min_participant_icp_e8s {
+        if new_balance_e8s < min_participant_icp_e8s {
             return Err(format!(
                 "Rejecting participation of effective amount {}; minimum required to participate: {}",
-                new_balance_e8s, params.min_participant_icp_e8s
+                new_balance_e8s, min_participant_icp_e8s
             ));
         }
 
@@ -1300,15 +1397,20 @@ impl Swap {
                 })?;
         }
 
-        self.buyers
+        let buyer_state = self
+            .buyers
             .entry(buyer.to_string())
             .or_insert_with(|| BuyerState {
                 icp: Some(TransferableAmount {
                     amount_e8s: 0,
                     ..TransferableAmount::default()
                 }),
-            })
-            .set_amount_icp_e8s(new_balance_e8s);
+                residual_icp_refund: None,
+                confirmation_text: None,
+            });
+        buyer_state.set_amount_icp_e8s(new_balance_e8s);
+        // Record the text that the buyer accepted with this (latest) participation.
+        buyer_state.confirmation_text = confirmation_text;
         // We compute the current participation amounts once and store the result in Swap's state,
         // for efficiency reasons.
         self.update_total_participation_amounts();
@@ -1379,6 +1481,106 @@ impl Swap {
         }
     }
 
+    /// Extends the due date and/or raises the maximum amount of ICP of an open
+    /// swap, and records the amendment in `sale_params_amendments`.
+    ///
+    /// Only callable by NNS Governance, while the swap is open and not yet due.
+    /// The amended parameters must still satisfy all the invariants of
+    /// `Params`, and the swap may be at most 90 days long.
+    pub fn amend_open_sale_params(
+        &mut self,
+        caller: PrincipalId,
+        now_seconds: u64,
+        request: AmendOpenSaleParamsRequest,
+    ) -> Result<AmendOpenSaleParamsResponse, String> {
+        // Require authorization.
+        let nns_governance = self.init_or_panic().nns_governance_or_panic();
+        if caller != nns_governance.get() {
+            return Err(format!(
+                "This method can only be called by NNS Governance({}). Current caller is {}",
+                nns_governance, caller,
+            ));
+        }
+
+        if self.lifecycle() != Lifecycle::Open {
+            return Err(format!(
+                "The swap can only be amended while it is open, but its lifecycle is {:?}",
+                self.lifecycle()
+            ));
+        }
+        if self.swap_due(now_seconds) {
+            return Err("The swap is already due and can no longer be amended".to_string());
+        }
+
+        let AmendOpenSaleParamsRequest {
+            swap_due_timestamp_seconds,
+            max_icp_e8s,
+            proposal_id,
+        } = request;
+        if swap_due_timestamp_seconds.is_none() && max_icp_e8s.is_none() {
+            return Err("The request does not amend any parameter".to_string());
+        }
+
+        let old_params = self
+            .params
+            .clone()
+            .expect("Expected params to be set because lifecycle is OPEN");
+        let mut new_params = old_params.clone();
+
+        if let Some(swap_due_timestamp_seconds) = swap_due_timestamp_seconds {
+            if swap_due_timestamp_seconds <= old_params.swap_due_timestamp_seconds {
+                return Err(format!(
+                    "The new swap_due_timestamp_seconds ({}) must be later than the current one ({})",
+                    swap_due_timestamp_seconds, old_params.swap_due_timestamp_seconds
+                ));
+            }
+            let open_timestamp_seconds = self
+                .decentralization_sale_open_timestamp_seconds
+                .unwrap_or(now_seconds);
+            let duration_seconds =
+                swap_due_timestamp_seconds.saturating_sub(open_timestamp_seconds);
+            if duration_seconds > Params::MAX_SALE_DURATION_SECONDS {
+                return Err(format!(
+                    "The new swap_due_timestamp_seconds ({}) would make the swap last {} seconds, \
+                     but it can last at most {} seconds",
+                    swap_due_timestamp_seconds,
+                    duration_seconds,
+                    Params::MAX_SALE_DURATION_SECONDS
+                ));
+            }
+            new_params.swap_due_timestamp_seconds = swap_due_timestamp_seconds;
+        }
+
+        if let Some(max_icp_e8s) = max_icp_e8s {
+            if max_icp_e8s < old_params.max_icp_e8s {
+                return Err(format!(
+                    "The new max_icp_e8s ({}) must be at least the current one ({})",
+                    max_icp_e8s, old_params.max_icp_e8s
+                ));
+            }
+            new_params.max_icp_e8s = max_icp_e8s;
+        }
+
+        new_params
+            .validate(self.init_or_panic())
+            .map_err(|err| format!("The amended parameters are invalid: {}", err))?;
+
+        let amendment = SaleParamsAmendment {
+            timestamp_seconds: now_seconds,
+            proposal_id,
+            old_swap_due_timestamp_seconds: swap_due_timestamp_seconds
+                .map(|_| old_params.swap_due_timestamp_seconds),
+            new_swap_due_timestamp_seconds: swap_due_timestamp_seconds,
+            old_max_icp_e8s: max_icp_e8s.map(|_| old_params.max_icp_e8s),
+            new_max_icp_e8s: max_icp_e8s,
+        };
+        log!(INFO, "Amending the parameters of the swap: {:?}", amendment);
+        self.params = Some(new_params);
+        self.sale_params_amendments.push(amendment);
+
+        Ok(AmendOpenSaleParamsResponse {})
+    }
+
     // Returns the ticket if a ticket was found for the caller and the ticket
     // was removed successfully. Returns None if no ticket was found for the caller.
     // Only the owner of a ticket can remove it.
@@ -1567,65 +1769,229 @@ impl Swap {
         now_fn: fn(bool) -> u64,
         environment: &mut impl CanisterEnvironment,
     ) -> FinalizeSwapResponse {
-        let mut finalize_swap_response = FinalizeSwapResponse::default();
-
         if let Err(e) = self.can_finalize() {
-            finalize_swap_response.set_error_message(e);
-            return finalize_swap_response;
+            return FinalizeSwapResponse::with_error(e);
         }
 
-        // Transfer the ICP tokens from the Swap canister.
-        finalize_swap_response
-            .set_sweep_icp_result(self.sweep_icp(now_fn, environment.icp_ledger()).await);
-        if finalize_swap_response.has_error_message() {
-            return finalize_swap_response;
-        }
-
-        // Settle the CommunityFund's participation in the Swap (if any).
-        finalize_swap_response.set_settle_community_fund_participation_result(
-            self.settle_community_fund_participation(environment.nns_governance_mut())
-                .await,
-        );
-        if finalize_swap_response.has_error_message() {
-            return finalize_swap_response;
+        // Every step is idempotent, so a manual finalization performs all of them,
+        // regardless of the progress of previous finalizations.
+        self.finalize_swap_progress = Some(FinalizeSwapProgress::default());
+        let mut step = FinalizeSwapStep::SweepIcp;
+        loop {
+            let progress = self
+                .perform_finalize_step(step, now_fn, environment, usize::MAX)
+                .await;
+            let next_step = progress.next_step();
+            let finalize_swap_response = progress.response.unwrap_or_default();
+            if finalize_swap_response.has_error_message()
+                || next_step == FinalizeSwapStep::Done
+                || next_step == step
+            {
+                return finalize_swap_response;
+            }
+            step = next_step;
         }
+    }
 
-        if self.should_restore_dapp_control() {
-            // Restore controllers of dapp canisters to their original
-            // owners (i.e. self.init.fallback_controller_principal_ids).
-            finalize_swap_response.set_set_dapp_controllers_result(
-                self.set_dapp_controllers_for_finalize(environment.sns_root_mut())
+    /// Performs `step` of the finalization, records its result in
+    /// `self.finalize_swap_progress`, and returns the updated progress.
+    ///
+    /// If the step fails, the progress remains at `step`, so that it is retried
+    /// next time. The claiming of neurons is limited to `max_neurons_to_claim`
+    /// neurons per step, and the step is repeated until all neurons are claimed.
+    ///
+    /// The caller must hold the `finalize_swap` lock.
+    async fn perform_finalize_step(
+        &mut self,
+        step: FinalizeSwapStep,
+        now_fn: fn(bool) -> u64,
+        environment: &mut impl CanisterEnvironment,
+        max_neurons_to_claim: usize,
+    ) -> FinalizeSwapProgress {
+        let mut progress = self.finalize_swap_progress.clone().unwrap_or_default();
+        let mut response = progress.response.take().unwrap_or_default();
+        response.error_message = None;
+
+        let next_step = match step {
+            FinalizeSwapStep::Unspecified | FinalizeSwapStep::Done => {
+                log!(ERROR, "Not a finalization step to perform: {:?}", step);
+                progress.response = Some(response);
+                return progress;
+            }
+            FinalizeSwapStep::SweepIcp => {
+                // Transfer the ICP tokens from the Swap canister.
+                response
+                    .set_sweep_icp_result(self.sweep_icp(now_fn, environment.icp_ledger()).await);
+                FinalizeSwapStep::SettleCommunityFundParticipation
+            }
+            FinalizeSwapStep::SettleCommunityFundParticipation => {
+                // Settle the CommunityFund's participation in the Swap (if any).
+                response.set_settle_community_fund_participation_result(
+                    self.settle_community_fund_participation(environment.nns_governance_mut())
+                        .await,
+                );
+                if self.should_restore_dapp_control() {
+                    FinalizeSwapStep::SetDappControllers
+                } else {
+                    FinalizeSwapStep::SweepSns
+                }
+            }
+            FinalizeSwapStep::SetDappControllers => {
+                // Restore controllers of dapp canisters to their original
+                // owners (i.e. self.init.fallback_controller_principal_ids).
+                // In that case, finalization need not do any more work.
+                response.set_set_dapp_controllers_result(
+                    self.set_dapp_controllers_for_finalize(environment.sns_root_mut())
+                        .await,
+                );
+                FinalizeSwapStep::Done
+            }
+            FinalizeSwapStep::SweepSns => {
+                // Transfer the SNS tokens from the Swap canister.
+                response
+                    .set_sweep_sns_result(self.sweep_sns(now_fn, environment.sns_ledger()).await);
+                FinalizeSwapStep::ClaimNeurons
+            }
+            FinalizeSwapStep::ClaimNeurons => {
+                // Once SNS tokens have been distributed to the correct accounts, claim
+                // them as neurons on behalf of the Swap participants.
+                response.set_claim_neuron_result(
+                    self.claim_swap_neurons_batch(
+                        environment.sns_governance_mut(),
+                        max_neurons_to_claim,
+                    )
                     .await,
-            );
+                );
+                if self.has_unclaimed_neuron_recipes() {
+                    FinalizeSwapStep::ClaimNeurons
+                } else {
+                    FinalizeSwapStep::SetMode
+                }
+            }
+            FinalizeSwapStep::SetMode => {
+                response.set_set_mode_call_result(
+                    Self::set_sns_governance_to_normal_mode(environment.sns_governance_mut()).await,
+                );
+                FinalizeSwapStep::Done
+            }
+        };
 
-            // In the case of returning control of the dapp(s) to the fallback
-            // controllers, finalize() need not do any more work, so always return
-            // and end execution.
-            return finalize_swap_response;
+        if response.has_error_message() {
+            let failed_attempts = if progress.next_step() == step {
+                progress.failed_attempts.unwrap_or_default()
+            } else {
+                0
+            };
+            progress.set_next_step(step);
+            progress.failed_attempts = Some(failed_attempts.saturating_add(1));
+        } else {
+            progress.set_next_step(next_step);
+            progress.failed_attempts = Some(0);
         }
+        progress.response = Some(response);
+        progress.last_step_timestamp_seconds = Some(now_fn(true));
 
-        // Transfer the SNS tokens from the Swap canister.
-        finalize_swap_response
-            .set_sweep_sns_result(self.sweep_sns(now_fn, environment.sns_ledger()).await);
-        if finalize_swap_response.has_error_message() {
-            return finalize_swap_response;
-        }
+        self.finalize_swap_progress = Some(progress.clone());
+        progress
+    }
+
+    /// Returns true if some neuron recipe still needs to be claimed, i.e., it was
+    /// neither claimed successfully nor marked as invalid.
+    fn has_unclaimed_neuron_recipes(&self) -> bool {
+        self.neuron_recipes.iter().any(|recipe| {
+            !matches!(
+                recipe.claimed_status.and_then(ClaimedStatus::from_i32),
+                Some(ClaimedStatus::Success | ClaimedStatus::Invalid)
+            )
+        })
+    }
 
-        // Once SNS tokens have been distributed to the correct accounts, claim
-        // them as neurons on behalf of the Swap participants.
-        finalize_swap_response.set_claim_neuron_result(
-            self.claim_swap_neurons(environment.sns_governance_mut())
-                .await,
+    /// Starts the automatic finalization of the swap, which the heartbeat then
+    /// performs one step at a time (see [`Self::try_advance_auto_finalization`]).
+    ///
+    /// Returns an error if the swap cannot be auto-finalized (see
+    /// [`Self::can_auto_finalize`]).
+    pub fn start_auto_finalization(&mut self, now_seconds: u64) -> Result<(), String> {
+        self.can_auto_finalize()?;
+
+        log!(
+            INFO,
+            "Starting to automatically finalize the swap at timestamp {}.",
+            now_seconds
         );
-        if finalize_swap_response.has_error_message() {
-            return finalize_swap_response;
+        self.already_tried_to_auto_finalize = Some(true);
+        let mut progress = FinalizeSwapProgress::default();
+        progress.set_next_step(FinalizeSwapStep::SweepIcp);
+        self.finalize_swap_progress = Some(progress);
+
+        Ok(())
+    }
+
+    /// Returns true if automatic finalization has started, but its result has not
+    /// been recorded yet.
+    pub fn is_auto_finalization_in_progress(&self) -> bool {
+        self.already_tried_to_auto_finalize == Some(true)
+            && self.auto_finalize_swap_response.is_none()
+            && self.finalize_swap_progress.is_some()
+    }
+
+    /// Performs the next step of the automatic finalization, if it is in progress
+    /// and no other finalization holds the `finalize_swap` lock. Returns the
+    /// updated progress if a step was performed.
+    ///
+    /// Once finalization is done, or the same step has failed
+    /// MAX_AUTO_FINALIZE_STEP_ATTEMPTS times in a row, the accumulated
+    /// FinalizeSwapResponse is recorded in `self.auto_finalize_swap_response`,
+    /// and no further steps are performed automatically.
+    pub async fn try_advance_auto_finalization(
+        &mut self,
+        now_fn: fn(bool) -> u64,
+        environment: &mut impl CanisterEnvironment,
+        max_neurons_to_claim: usize,
+    ) -> Option<FinalizeSwapProgress> {
+        if !self.is_auto_finalization_in_progress() {
+            return None;
         }
 
-        finalize_swap_response.set_set_mode_call_result(
-            Self::set_sns_governance_to_normal_mode(environment.sns_governance_mut()).await,
-        );
+        let step = self
+            .finalize_swap_progress
+            .as_ref()
+            .map(|progress| progress.next_step())
+            .unwrap_or(FinalizeSwapStep::Unspecified);
+        let progress = match step {
+            // A manual finalization (re)started or completed finalization in the meantime.
+            FinalizeSwapStep::Unspecified => return None,
+            FinalizeSwapStep::Done => self.finalize_swap_progress.clone()?,
+            _ => {
+                self.lock_finalize_swap().ok()?;
+                let progress = self
+                    .perform_finalize_step(step, now_fn, environment, max_neurons_to_claim)
+                    .await;
+                self.unlock_finalize_swap();
+                progress
+            }
+        };
 
-        finalize_swap_response
+        let response = progress.response.clone().unwrap_or_default();
+        if progress.next_step() == FinalizeSwapStep::Done {
+            log!(
+                INFO,
+                "The swap finalized automatically. \n\
+                finalize_swap_response: {response:?}"
+            );
+            self.auto_finalize_swap_response = Some(response);
+        } else if progress.failed_attempts.unwrap_or_default() >= MAX_AUTO_FINALIZE_STEP_ATTEMPTS {
+            log!(
+                ERROR,
+                "Giving up on automatic finalization after {} failed attempts of {:?}. \n\
+                finalize_swap_response: {response:?}",
+                MAX_AUTO_FINALIZE_STEP_ATTEMPTS,
+                progress.next_step(),
+            );
+            self.auto_finalize_swap_response = Some(response);
+        }
+
+        Some(progress)
     }
 
     /// In state COMMITTED. Claims SNS Neurons on behalf of participants.
@@ -1639,6 +2005,17 @@ impl Swap {
     pub async fn claim_swap_neurons(
         &mut self,
         sns_governance_client: &mut impl SnsGovernanceClient,
+    ) -> SweepResult {
+        self.claim_swap_neurons_batch(sns_governance_client, usize::MAX)
+            .await
+    }
+
+    /// Like [`Self::claim_swap_neurons`], but claims at most `max_neurons_to_claim`
+    /// neurons. The remaining recipes are left for a later call.
+    pub async fn claim_swap_neurons_batch(
+        &mut self,
+        sns_governance_client: &mut impl SnsGovernanceClient,
+        max_neurons_to_claim: usize,
     ) -> SweepResult {
         if self.lifecycle() != Lifecycle::Committed {
             log!(
@@ -1676,6 +2053,10 @@ impl Swap {
         let mut neuron_parameters = vec![];
 
         for recipe in &mut self.neuron_recipes {
+            if neuron_parameters.len() >= max_neurons_to_claim {
+                break;
+            }
+
             let (hotkey, controller, source_nns_neuron_id) = match recipe.investor.as_ref() {
                 Some(Investor::Direct(DirectInvestment { buyer_principal })) => {
                     let parsed_buyer_principal = match string_to_principal(buyer_principal) {
@@ -2134,9 +2515,111 @@ impl Swap {
         }
     }
 
+    /// Refunds the ICP left in the subaccounts of buyers once the swap is
+    /// ABORTED or COMMITTED, so that buyers do not need to call
+    /// `error_refund_icp` themselves.
+    ///
+    /// Buyers are inspected in rounds, at most `max_number_to_inspect` buyers
+    /// per call. A buyer is only refunded once the ICP it committed has been
+    /// swept, and only until a refund succeeds or the subaccount turns out to
+    /// hold nothing to refund; failed refunds are retried in the next round. A
+    /// new round starts `RESIDUAL_ICP_REFUND_INTERVAL_SECONDS` after the last
+    /// one completed. The outcome for each buyer is recorded in its
+    /// `BuyerState`.
+    ///
+    /// Returns None if no buyers were inspected, and otherwise the number of
+    /// refunded (success), failed, skipped, and invalid buyers.
+    pub async fn try_refund_residual_icp(
+        &mut self,
+        now_fn: fn(bool) -> u64,
+        self_canister_id: CanisterId,
+        icp_ledger: &dyn ICRC1Ledger,
+        max_number_to_inspect: u64,
+    ) -> Option<SweepResult> {
+        // While finalization is in progress, the ICP of buyers may be in the
+        // middle of being swept.
+        if !self.lifecycle_is_terminal() || self.is_finalize_swap_locked() {
+            return None;
+        }
+
+        let now_seconds = now_fn(false);
+        let first_buyer = match self.residual_icp_refund_next_buyer.take() {
+            Some(next_buyer) => next_buyer,
+            None => {
+                let last_completion_seconds = self
+                    .residual_icp_refund_last_completion_timestamp_seconds
+                    .unwrap_or(0);
+                if now_seconds
+                    < last_completion_seconds.saturating_add(RESIDUAL_ICP_REFUND_INTERVAL_SECONDS)
+                {
+                    return None;
+                }
+                String::new()
+            }
+        };
+
+        // Move on to the next batch before making any calls, so that concurrent
+        // heartbeats never work on the same buyers.
+        let max_number_to_inspect = max_number_to_inspect as usize;
+        let mut batch: Vec<String> = self
+            .buyers
+            .range(first_buyer..)
+            .map(|(buyer, _)| buyer.clone())
+            .take(max_number_to_inspect + 1)
+            .collect();
+        if batch.len() > max_number_to_inspect {
+            self.residual_icp_refund_next_buyer = batch.pop();
+        } else {
+            self.residual_icp_refund_last_completion_timestamp_seconds = Some(now_seconds);
+        }
+
+        let mut sweep_result = SweepResult::default();
+        for buyer in batch {
+            let principal = match string_to_principal(&buyer) {
+                Some(principal) => principal,
+                None => {
+                    sweep_result.invalid += 1;
+                    continue;
+                }
+            };
+            let needs_refund = self
+                .buyers
+                .get(&buyer)
+                .map_or(false, |buyer_state| buyer_state.needs_residual_icp_refund());
+            if !needs_refund {
+                sweep_result.skipped += 1;
+                continue;
+            }
+
+            let residual_icp_refund =
+                refund_residual_icp(now_fn, self_canister_id, principal, icp_ledger).await;
+            match residual_icp_refund.status() {
+                residual_icp_refund::Status::Refunded => sweep_result.success += 1,
+                residual_icp_refund::Status::Failed => sweep_result.failure += 1,
+                residual_icp_refund::Status::NothingToRefund
+                | residual_icp_refund::Status::Unspecified => sweep_result.skipped += 1,
+            }
+            if let Some(buyer_state) = self.buyers.get_mut(&buyer) {
+                buyer_state.residual_icp_refund = Some(residual_icp_refund);
+            }
+        }
+
+        Some(sweep_result)
+    }
+
     /// Transfers ICP tokens from buyer's subaccounts to the SNS governance
     /// canister if COMMITTED or back to the buyer if ABORTED.
     ///
+    /// Buyers are swept in chunks of `SWEEP_ICP_CHUNK_SIZE`, with at most
+    /// `SWEEP_ICP_MAX_CONCURRENT_TRANSFERS` transfers in flight at once. The
+    /// failed transfers of a chunk are attempted again, up to
+    /// `SWEEP_ICP_MAX_CHUNK_ATTEMPTS` times in total. Once a chunk is done, the
+    /// first buyer of the next chunk is recorded in `sweep_icp_next_buyer`. If
+    /// a chunk still has failed transfers or invalid buyers after all attempts,
+    /// the sweep stops there, and the next sweep resumes with that chunk
+    /// instead of starting over. Thus, the returned counts only cover the
+    /// buyers inspected by this call.
+    ///
     /// Returns the following values:
     /// - the number of skipped buyers due operation already in progress
     /// - the number of successful transfers
@@ -2167,10 +2650,112 @@ impl Swap {
 
         // The following methods are safe to call since we validated Init in the above block
         let sns_governance = init.sns_governance_or_panic();
+        let dst_owner = if lifecycle == Lifecycle::Committed {
+            // This Account should be given a name, such as SNS ICP Treasury...
+            Some(sns_governance.get())
+        } else {
+            None
+        };
+
+        let mut sweep_result = SweepResult::default();
+        loop {
+            let first_buyer = self.sweep_icp_next_buyer.clone().unwrap_or_default();
+            let mut chunk: Vec<String> = self
+                .buyers
+                .range(first_buyer..)
+                .map(|(buyer, _)| buyer.clone())
+                .take(SWEEP_ICP_CHUNK_SIZE + 1)
+                .collect();
+            let next_buyer = if chunk.len() > SWEEP_ICP_CHUNK_SIZE {
+                chunk.pop()
+            } else {
+                None
+            };
+
+            let chunk_result = self
+                .sweep_icp_chunk(now_fn, dst_owner, chunk, icp_ledger)
+                .await;
+            let chunk_done = chunk_result.failure == 0 && chunk_result.invalid == 0;
+            sweep_result.consume(chunk_result);
+            if !chunk_done {
+                log!(
+                    ERROR,
+                    "Halting sweep_icp() at buyer {:?}, the chunk has failed transfers \
+                    or invalid buyers. Progress so far: {:?}",
+                    self.sweep_icp_next_buyer,
+                    sweep_result
+                );
+                return sweep_result;
+            }
+
+            self.sweep_icp_next_buyer = next_buyer;
+            if self.sweep_icp_next_buyer.is_none() {
+                return sweep_result;
+            }
+        }
+    }
+
+    /// Sweeps the ICP of the given buyers, making up to
+    /// `SWEEP_ICP_MAX_CHUNK_ATTEMPTS` attempts for the transfers that fail.
+    /// Only the failures of the last attempt are counted.
+    ///
+    /// If `dst_owner` is set, the ICP is transferred to its default account,
+    /// and otherwise back to the buyer.
+    async fn sweep_icp_chunk(
+        &mut self,
+        now_fn: fn(bool) -> u64,
+        dst_owner: Option<PrincipalId>,
+        chunk: Vec<String>,
+        icp_ledger: &dyn ICRC1Ledger,
+    ) -> SweepResult {
+        let mut chunk_result = SweepResult::default();
+        let mut pending = chunk;
+        for attempt in 1..=SWEEP_ICP_MAX_CHUNK_ATTEMPTS {
+            let (attempt_result, failed) = self
+                .sweep_icp_buyers(now_fn, dst_owner, &pending, icp_ledger)
+                .await;
+            if failed.is_empty() || attempt == SWEEP_ICP_MAX_CHUNK_ATTEMPTS {
+                chunk_result.consume(attempt_result);
+                break;
+            }
+            log!(
+                INFO,
+                "Attempt {} of sweeping the ICP of a chunk of buyers had {} failed transfers, \
+                retrying them.",
+                attempt,
+                failed.len()
+            );
+            chunk_result.consume(SweepResult {
+                failure: 0,
+                ..attempt_result
+            });
+            pending = failed;
+        }
+        chunk_result
+    }
 
+    /// Sweeps the ICP of the given buyers concurrently, with at most
+    /// `SWEEP_ICP_MAX_CONCURRENT_TRANSFERS` transfers in flight at once.
+    /// Returns the result and the buyers whose transfer failed.
+    async fn sweep_icp_buyers(
+        &mut self,
+        now_fn: fn(bool) -> u64,
+        dst_owner: Option<PrincipalId>,
+        buyers: &[String],
+        icp_ledger: &dyn ICRC1Ledger,
+    ) -> (SweepResult, Vec<String>) {
         let mut sweep_result = SweepResult::default();
+        let (Some(first_buyer), Some(last_buyer)) = (buyers.first(), buyers.last()) else {
+            return (sweep_result, vec![]);
+        };
+        let buyers: BTreeSet<&String> = buyers.iter().collect();
 
-        for (principal_str, buyer_state) in self.buyers.iter_mut() {
+        let mut transfers = vec![];
+        for (principal_str, buyer_state) in self
+            .buyers
+            .range_mut(first_buyer.clone()..=last_buyer.clone())
+            .filter(|(principal_str, _)| buyers.contains(principal_str))
+        {
             // principal_str should always be parseable as a PrincipalId as that is enforced
             // in `refresh_buyer_tokens`. In the case of a bug due to programmer error, increment
             // the invalid field. This will require a manual intervention via an upgrade to correct
@@ -2183,17 +2768,9 @@ impl Swap {
             };
 
             let subaccount = principal_to_subaccount(&principal);
-            let dst = if lifecycle == Lifecycle::Committed {
-                // This Account should be given a name, such as SNS ICP Treasury...
-                Account {
-                    owner: sns_governance.get().0,
-                    subaccount: None,
-                }
-            } else {
-                Account {
-                    owner: principal.0,
-                    subaccount: None,
-                }
+            let dst = Account {
+                owner: dst_owner.unwrap_or(principal).0,
+                subaccount: None,
             };
 
             let icp_transferable_amount = match buyer_state.icp.as_mut() {
@@ -2213,15 +2790,38 @@ impl Swap {
                 }
             };
 
-            let result = icp_transferable_amount
-                .transfer_helper(
-                    now_fn,
-                    DEFAULT_TRANSFER_FEE,
-                    Some(subaccount),
-                    &dst,
-                    icp_ledger,
-                )
-                .await;
+            transfers.push(async move {
+                let result = icp_transferable_amount
+                    .transfer_helper(
+                        now_fn,
+                        DEFAULT_TRANSFER_FEE,
+                        Some(subaccount),
+                        &dst,
+                        icp_ledger,
+                    )
+                    .await;
+
+                // Update the buyer state to indicate funds that have been successfully committed or refunded.
+                if result.is_success() {
+                    // Record transfer fee
+                    icp_transferable_amount.transfer_fee_paid_e8s =
+                        Some(DEFAULT_TRANSFER_FEE.get_e8s());
+                    // Record the amount minus transfer fee that was refunded or committed.
+                    let amount_transferred_e8s =
+                        Some(icp_transferable_amount.amount_e8s - DEFAULT_TRANSFER_FEE.get_e8s());
+                    icp_transferable_amount.amount_transferred_e8s = amount_transferred_e8s;
+                }
+                (principal_str.clone(), result)
+            });
+        }
+
+        let results: Vec<(String, TransferResult)> = stream::iter(transfers)
+            .buffer_unordered(SWEEP_ICP_MAX_CONCURRENT_TRANSFERS)
+            .collect()
+            .await;
+
+        let mut failed = vec![];
+        for (principal_str, result) in results {
             match result {
                 // AmountToSmall should never happen as the amount contributed is checked in
                 // `refresh_buyer_tokens`. In the case of a bug due to programmer error,
@@ -2238,22 +2838,14 @@ impl Swap {
                 }
                 TransferResult::Failure(_) => {
                     sweep_result.failure += 1;
+                    failed.push(principal_str);
                 }
             }
-
-            // Update the buyer state to indicate funds that have been successfully committed or refunded.
-            if result.is_success() {
-                // Record transfer fee
-                icp_transferable_amount.transfer_fee_paid_e8s =
-                    Some(DEFAULT_TRANSFER_FEE.get_e8s());
-                // Record the amount minus transfer fee that was refunded or committed.
-                let amount_transferred_e8s =
-                    Some(icp_transferable_amount.amount_e8s - DEFAULT_TRANSFER_FEE.get_e8s());
-                icp_transferable_amount.amount_transferred_e8s = amount_transferred_e8s;
-            }
         }
+        // Retry in key order, regardless of the order in which transfers completed.
+        failed.sort();
 
-        sweep_result
+        (sweep_result, failed)
     }
 
     /// In state COMMITTED. Transfers SNS tokens from the swap
@@ -2476,6 +3068,13 @@ impl Swap {
             return NewSaleTicketResponse::err_invalid_principal();
         }
 
+        if self
+            .validate_confirmation_text(request.confirmation_text.clone())
+            .is_err()
+        {
+            return NewSaleTicketResponse::err_invalid_confirmation_text();
+        }
+
         // subaccounts must be 32 bytes
         if request
             .subaccount
@@ -2498,11 +3097,13 @@ impl Swap {
             .buyers
             .get(&caller.to_string())
             .map_or(0, |buyer_state| buyer_state.amount_icp_e8s());
+        let (min_participant_icp_e8s, max_participant_icp_e8s) =
+            self.participant_icp_e8s_bounds(params, &caller);
         let amount_icp_e8s = match compute_participation_increment(
             self.current_total_participation_e8s(),
             params.max_icp_e8s,
-            params.min_participant_icp_e8s,
-            params.max_participant_icp_e8s,
+            min_participant_icp_e8s,
+            max_participant_icp_e8s,
             old_balance_e8s,
             request.amount_icp_e8s,
         ) {
@@ -2531,6 +3132,151 @@ impl Swap {
         NewSaleTicketResponse::ok(ticket)
     }
 
+    /// Consults the participation eligibility canister about `caller`, unless a
+    /// result younger than PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS is cached,
+    /// and caches the result. Called before [`Self::new_sale_ticket`] if the swap
+    /// has a participation eligibility canister.
+    ///
+    /// Returns a NewSaleTicketResponse with a TYPE_NOT_ELIGIBLE error if the
+    /// caller is not eligible, or if the canister could not be consulted.
+    pub async fn check_participation_eligibility(
+        &mut self,
+        caller: PrincipalId,
+        now_seconds: u64,
+        eligibility_client: &mut impl ParticipationEligibilityClient,
+    ) -> Result<(), NewSaleTicketResponse> {
+        // Leave the reporting of these errors to new_sale_ticket, without
+        // bothering the participation eligibility canister.
+        if self.lifecycle() != Lifecycle::Open || caller.is_anonymous() {
+            return Ok(());
+        }
+
+        let key = caller.to_string();
+        let cached_eligibility = self
+            .participation_eligibility
+            .get(&key)
+            .filter(|eligibility| {
+                now_seconds.saturating_sub(eligibility.timestamp_seconds.unwrap_or_default())
+                    < PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS
+            })
+            .cloned();
+        let eligibility = match cached_eligibility {
+            Some(eligibility) => eligibility,
+            None => {
+                let response = match eligibility_client
+                    .check_participation_eligibility(CheckParticipationEligibilityRequest {
+                        principal_id: Some(caller),
+                    })
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(canister_call_error) => {
+                        log!(
+                            ERROR,
+                            "Failed to check the participation eligibility of {}: {:?}",
+                            caller,
+                            canister_call_error,
+                        );
+                        return Err(NewSaleTicketResponse::err_not_eligible());
+                    }
+                };
+                let eligibility = ParticipationEligibility {
+                    eligible: response.eligible,
+                    reason: response.reason,
+                    min_participant_icp_e8s: response.min_participant_icp_e8s,
+                    max_participant_icp_e8s: response.max_participant_icp_e8s,
+                    timestamp_seconds: Some(now_seconds),
+                };
+                self.participation_eligibility
+                    .insert(key, eligibility.clone());
+                eligibility
+            }
+        };
+
+        if eligibility.eligible() {
+            Ok(())
+        } else {
+            log!(
+                INFO,
+                "Principal {} is not eligible to participate: {:?}",
+                caller,
+                eligibility.reason,
+            );
+            Err(NewSaleTicketResponse::err_not_eligible())
+        }
+    }
+
+    /// Returns the minimum and maximum participation of `buyer`, i.e., the bounds
+    /// in `params`, narrowed down by the cached participation eligibility of
+    /// `buyer` (if any).
+    fn participant_icp_e8s_bounds(&self, params: &Params, buyer: &PrincipalId) -> (u64, u64) {
+        let eligibility = self.participation_eligibility.get(&buyer.to_string());
+        let min_participant_icp_e8s = eligibility
+            .and_then(|eligibility| eligibility.min_participant_icp_e8s)
+            .map_or(params.min_participant_icp_e8s, |min| {
+                min.max(params.min_participant_icp_e8s)
+            });
+        let max_participant_icp_e8s = eligibility
+            .and_then(|eligibility| eligibility.max_participant_icp_e8s)
+            .map_or(params.max_participant_icp_e8s, |max| {
+                max.min(params.max_participant_icp_e8s)
+            });
+        (min_participant_icp_e8s, max_participant_icp_e8s)
+    }
+
+    /// Removes the expired entries of `participation_eligibility`, at most once
+    /// every PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS. The entries of buyers and
+    /// of principals with an open ticket are kept, as they are still needed to
+    /// validate their participation (see `validate_participation_eligibility`).
+    ///
+    /// Returns None if the purge was not run, and the number of removed entries
+    /// otherwise.
+    pub fn try_purge_expired_participation_eligibility(
+        &mut self,
+        now_seconds: u64,
+    ) -> Option<usize> {
+        let last_purge_timestamp_seconds = self
+            .participation_eligibility_purge_timestamp_seconds
+            .unwrap_or_default();
+        if self.participation_eligibility.is_empty()
+            || now_seconds
+                < last_purge_timestamp_seconds + PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS
+        {
+            return None;
+        }
+
+        let len_before = self.participation_eligibility.len();
+        let buyers = &self.buyers;
+        self.participation_eligibility
+            .retain(|principal, eligibility| {
+                let expired = now_seconds
+                    .saturating_sub(eligibility.timestamp_seconds.unwrap_or_default())
+                    >= PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS;
+                if !expired || buyers.contains_key(principal) {
+                    return true;
+                }
+                match PrincipalId::from_str(principal) {
+                    Ok(principal) => {
+                        let principal = Blob::from_bytes(principal.as_slice().into());
+                        memory::OPEN_TICKETS_MEMORY
+                            .with(|tickets| tickets.borrow().contains_key(&principal))
+                    }
+                    Err(_) => false,
+                }
+            });
+        self.participation_eligibility_purge_timestamp_seconds = Some(now_seconds);
+
+        let purged = len_before - self.participation_eligibility.len();
+        if purged > 0 {
+            log!(
+                INFO,
+                "Purged the expired participation eligibility of {} principals",
+                purged
+            );
+        }
+        Some(purged)
+    }
+
     /// Calls purge_old_tickets when needed.
     ///
     /// The conditions to call purge_old_tickets are the following:
@@ -2883,6 +3629,8 @@ impl Swap {
             cf_participants: vec![],
             neuron_recipes: vec![],
             buyers: btreemap! {},
+            participation_eligibility: btreemap! {},
+            sale_params_amendments: vec![],
             ..self.clone()
         };
 
@@ -2963,6 +3711,110 @@ impl Swap {
         }
     }
 
+    /// Returns the progress of the finalization of the swap.
+    pub fn get_finalize_status(
+        &self,
+        _request: &GetFinalizeStatusRequest,
+    ) -> GetFinalizeStatusResponse {
+        GetFinalizeStatusResponse {
+            progress: self.finalize_swap_progress.clone(),
+        }
+    }
+
+    /// Estimates what a direct participant contributing
+    /// `request.amount_icp_e8s` would receive if the swap committed with
+    /// the current participation (plus that contribution), along with the
+    /// current fill level of the swap.
+    ///
+    /// Like `refresh_buyer_token_e8s`, the contribution is limited by the
+    /// remaining capacity of the swap and by `Params.max_participant_icp_e8s`,
+    /// and nothing is accepted if that leaves less than
+    /// `Params.min_participant_icp_e8s`.
+    pub fn simulate_participation(
+        &self,
+        request: &SimulateParticipationRequest,
+    ) -> SimulateParticipationResponse {
+        let Some(params) = self.params.as_ref() else {
+            return SimulateParticipationResponse::default();
+        };
+
+        let current_total_participation_e8s = self.current_total_participation_e8s();
+        let accepted_amount_icp_e8s = request
+            .amount_icp_e8s
+            .min(self.available_direct_participation_e8s())
+            .min(params.max_participant_icp_e8s);
+        let accepted_amount_icp_e8s = if accepted_amount_icp_e8s < params.min_participant_icp_e8s {
+            0
+        } else {
+            accepted_amount_icp_e8s
+        };
+        let estimated_sns_token_e8s = match NonZeroU64::new(
+            current_total_participation_e8s.saturating_add(accepted_amount_icp_e8s),
+        ) {
+            Some(total_participant_icp_e8s) => Swap::scale(
+                accepted_amount_icp_e8s,
+                params.sns_token_e8s,
+                total_participant_icp_e8s,
+            ),
+            None => 0,
+        };
+        let neuron_basket = params
+            .neuron_basket_construction_parameters
+            .as_ref()
+            .filter(|parameters| parameters.count > 0 && estimated_sns_token_e8s > 0)
+            .map(|parameters| {
+                parameters
+                    .generate_vesting_schedule(estimated_sns_token_e8s)
+                    .into_iter()
+                    .map(|event| simulate_participation_response::SimulatedNeuron {
+                        amount_e8s: Some(event.amount_e8s),
+                        dissolve_delay_seconds: Some(event.dissolve_delay_seconds),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        SimulateParticipationResponse {
+            accepted_amount_icp_e8s: Some(accepted_amount_icp_e8s),
+            estimated_sns_token_e8s: Some(estimated_sns_token_e8s),
+            neuron_basket,
+            current_total_participation_e8s: Some(current_total_participation_e8s),
+            max_total_participation_e8s: Some(params.max_icp_e8s),
+        }
+    }
+
+    /// Checks `request.params` against all the invariants that `open` would
+    /// check if it were called at `now_seconds`, and returns every violation
+    /// rather than only the first one. This allows proposal UIs to validate
+    /// the parameters of an OpenSnsTokenSwap proposal before submitting it.
+    ///
+    /// The lifecycle of the swap and its SNS token balance are not checked.
+    pub fn validate_open_sale_params(
+        &self,
+        now_seconds: u64,
+        request: &ValidateOpenSaleParamsRequest,
+    ) -> ValidateOpenSaleParamsResponse {
+        let Some(params) = request.params.as_ref() else {
+            return ValidateOpenSaleParamsResponse {
+                violations: vec![ParamsViolation {
+                    field: None,
+                    description: Some("The parameters of the swap are missing.".to_string()),
+                }],
+            };
+        };
+
+        let mut violations = vec![];
+        if let Some(description) = params.duration_violation_if_initiated_at(now_seconds) {
+            violations.push(ParamsViolation {
+                field: Some("swap_due_timestamp_seconds".to_string()),
+                description: Some(description),
+            });
+        }
+        violations.extend(params.violations(self.init_or_panic()));
+
+        ValidateOpenSaleParamsResponse { violations }
+    }
+
     /// If there is an open swap ticket for the caller then it returns it;
     /// otherwise returns none.
     ///
@@ -3032,6 +3884,7 @@ impl Swap {
     ) -> GetSaleParametersResponse {
         GetSaleParametersResponse {
             params: self.params.clone(),
+            amendments: self.sale_params_amendments.clone(),
         }
     }
 
@@ -3187,6 +4040,79 @@ pub fn principal_to_subaccount(principal_id: &PrincipalId) -> Subaccount {
     subaccount
 }
 
+/// Transfers the ICP in the subaccount of `buyer` (minus the transfer fee) back
+/// to the buyer, and returns the outcome.
+async fn refund_residual_icp(
+    now_fn: fn(bool) -> u64,
+    self_canister_id: CanisterId,
+    buyer: PrincipalId,
+    icp_ledger: &dyn ICRC1Ledger,
+) -> ResidualIcpRefund {
+    let mut refund = ResidualIcpRefund {
+        timestamp_seconds: now_fn(false),
+        ..Default::default()
+    };
+    let subaccount = principal_to_subaccount(&buyer);
+
+    let balance_e8s = match icp_ledger
+        .account_balance(Account {
+            owner: self_canister_id.into(),
+            subaccount: Some(subaccount),
+        })
+        .await
+    {
+        Ok(balance) => balance.get_e8s(),
+        Err(err) => {
+            refund.set_status(residual_icp_refund::Status::Failed);
+            refund.error_message = Some(format!(
+                "Unable to get the balance for the subaccount of {}: {:?}",
+                buyer, err,
+            ));
+            return refund;
+        }
+    };
+    let fee_e8s = DEFAULT_TRANSFER_FEE.get_e8s();
+    if balance_e8s <= fee_e8s {
+        refund.set_status(residual_icp_refund::Status::NothingToRefund);
+        return refund;
+    }
+
+    let amount_e8s = balance_e8s - fee_e8s;
+    let dst = Account {
+        owner: buyer.0,
+        subaccount: None,
+    };
+    match icp_ledger
+        .transfer_funds(amount_e8s, fee_e8s, Some(subaccount), dst, 0)
+        .await
+    {
+        Ok(block_height) => {
+            log!(
+                INFO,
+                "Residual refund - transferred {} ICP (e8s) from the subaccount of {} at height {}",
+                amount_e8s,
+                buyer,
+                block_height,
+            );
+            refund.set_status(residual_icp_refund::Status::Refunded);
+            refund.amount_refunded_e8s = Some(amount_e8s);
+            refund.block_height = Some(block_height);
+        }
+        Err(err) => {
+            log!(
+                ERROR,
+                "Residual refund - failed to transfer {} ICP (e8s) from the subaccount of {}: {}",
+                amount_e8s,
+                buyer,
+                err,
+            );
+            refund.set_status(residual_icp_refund::Status::Failed);
+            refund.error_message = Some(format!("Transfer request failed: {}", err));
+        }
+    }
+    refund
+}
+
 /// A common pattern throughout the Swap canister is parsing the String
 /// representation of a PrincipalId and logging the error if any.
 fn string_to_principal(maybe_principal_id: &String) -> Option<PrincipalId> {
@@ -3435,6 +4361,22 @@ impl NewSaleTicketResponse {
         })
     }
 
+    pub fn err_invalid_confirmation_text() -> Self {
+        Self::err(new_sale_ticket_response::Err {
+            error_type: new_sale_ticket_response::err::Type::InvalidConfirmationText as i32,
+            invalid_user_amount: None,
+            existing_ticket: None,
+        })
+    }
+
+    pub fn err_not_eligible() -> Self {
+        Self::err(new_sale_ticket_response::Err {
+            error_type: new_sale_ticket_response::err::Type::NotEligible as i32,
+            invalid_user_amount: None,
+            existing_ticket: None,
+        })
+    }
+
     pub fn err_ticket_exists(ticket: Ticket) -> Self {
         Self::err(new_sale_ticket_response::Err {
             error_type: new_sale_ticket_response::err::Type::TicketExists as i32,
@@ -3515,6 +4457,13 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
             neuron_recipes,
             direct_participation_icp_e8s,
             neurons_fund_participation_icp_e8s,
+            residual_icp_refund_next_buyer,
+            residual_icp_refund_last_completion_timestamp_seconds,
+            finalize_swap_progress,
+            participation_eligibility,
+            sale_params_amendments,
+            sweep_icp_next_buyer,
+            participation_eligibility_purge_timestamp_seconds,
         } = self.swap;
 
         formatter
@@ -3560,6 +4509,28 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
                 "neurons_fund_participation_icp_e8s",
                 neurons_fund_participation_icp_e8s,
             )
+            .field(
+                "residual_icp_refund_next_buyer",
+                residual_icp_refund_next_buyer,
+            )
+            .field(
+                "residual_icp_refund_last_completion_timestamp_seconds",
+                residual_icp_refund_last_completion_timestamp_seconds,
+            )
+            .field("finalize_swap_progress", finalize_swap_progress)
+            .field(
+                "participation_eligibility",
+                &format!("<len={}>", participation_eligibility.len()),
+            )
+            .field(
+                "sale_params_amendments",
+                &format!("<len={}>", sale_params_amendments.len()),
+            )
+            .field("sweep_icp_next_buyer", sweep_icp_next_buyer)
+            .field(
+                "participation_eligibility_purge_timestamp_seconds",
+                participation_eligibility_purge_timestamp_seconds,
+            )
             .finish()
     }
 }
@@ -3610,6 +4581,7 @@ mod tests {
             neurons_fund_participants: None,             // TODO[NNS1-2339]
             should_auto_finalize: Some(true),
             neurons_fund_participation_constraints: None,
+            participation_eligibility_canister_id: None,
         });
     }
 
@@ -3825,6 +4797,7 @@ mod tests {
             swap.get_sale_parameters(&GetSaleParametersRequest {}),
             GetSaleParametersResponse {
                 params: Some(PARAMS),
+                amendments: vec![],
             },
         );
     }
@@ -4238,6 +5211,7 @@ mod tests {
                     neurons_fund_participants: None, // TODO[NNS1-2339]
                     should_auto_finalize: Some(true),
                     neurons_fund_participation_constraints: None,
+                    participation_eligibility_canister_id: None,
                 }),
                 params: Some(Params {
                     min_participants: 1,
@@ -4266,11 +5240,18 @@ mod tests {
                 auto_finalize_swap_response: None,
                 direct_participation_icp_e8s: None,
                 neurons_fund_participation_icp_e8s: None,
+                residual_icp_refund_next_buyer: None,
+                residual_icp_refund_last_completion_timestamp_seconds: None,
+                finalize_swap_progress: None,
+                participation_eligibility: btreemap! {},
+                sale_params_amendments: vec![],
+                sweep_icp_next_buyer: None,
+                participation_eligibility_purge_timestamp_seconds: None,
             };
             let mut ticket_ids = HashSet::new();
             for pid in pids {
                 let principal = PrincipalId::new_user_test_id(pid);
-                let ticket = match swap.new_sale_ticket(&NewSaleTicketRequest { amount_icp_e8s: 10_000, subaccount: None}, principal, 0).result.unwrap() {
+                let ticket = match swap.new_sale_ticket(&NewSaleTicketRequest { amount_icp_e8s: 10_000, subaccount: None, confirmation_text: None }, principal, 0).result.unwrap() {
                     new_sale_ticket_response::Result::Ok(Ok { ticket }) => ticket.unwrap(),
                     new_sale_ticket_response::Result::Err(e) => panic!("{:?}", e),
                 };
@@ -4316,6 +5297,8 @@ mod tests {
                     amount_e8s: 1,
                     ..TransferableAmount::default()
                 }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
         };
         let mut swap = Swap {
@@ -4349,6 +5332,8 @@ mod tests {
                     amount_e8s: 10,
                     ..TransferableAmount::default()
                 }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
         };
         let mut swap = Swap {
@@ -4384,6 +5369,8 @@ mod tests {
                     amount_e8s: 20,
                     ..TransferableAmount::default()
                 }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
         };
         let mut swap = Swap {
@@ -4474,6 +5461,8 @@ mod tests {
                     amount_e8s: 20,
                     ..TransferableAmount::default()
                 }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
         };
         let mut swap = Swap {
@@ -4548,6 +5537,7 @@ mod tests {
                 neurons_fund_participants: None,             // TODO[NNS1-2339]
                 should_auto_finalize: Some(true),
                 neurons_fund_participation_constraints: None,
+                participation_eligibility_canister_id: None,
             }),
             params: Some(Params {
                 min_participants: 0,
@@ -4576,6 +5566,13 @@ mod tests {
             auto_finalize_swap_response: None,
             direct_participation_icp_e8s: None,
             neurons_fund_participation_icp_e8s: None,
+            residual_icp_refund_next_buyer: None,
+            residual_icp_refund_last_completion_timestamp_seconds: None,
+            finalize_swap_progress: None,
+            participation_eligibility: btreemap! {},
+            sale_params_amendments: vec![],
+            sweep_icp_next_buyer: None,
+            participation_eligibility_purge_timestamp_seconds: None,
         };
 
         let try_purge_old_tickets = |sale: &mut Swap, time: u64| loop {
@@ -4613,7 +5610,8 @@ mod tests {
                 .new_sale_ticket(
                     &NewSaleTicketRequest {
                         amount_icp_e8s: min_participant_icp_e8s,
-                        subaccount: None
+                        subaccount: None,
+                        confirmation_text: None,
                     },
                     *principal,
                     0
@@ -4640,7 +5638,8 @@ mod tests {
                 .new_sale_ticket(
                     &NewSaleTicketRequest {
                         amount_icp_e8s: min_participant_icp_e8s,
-                        subaccount: None
+                        subaccount: None,
+                        confirmation_text: None,
                     },
                     *principal,
                     ONE_DAY
@@ -4722,7 +5721,8 @@ mod tests {
                 .new_sale_ticket(
                     &NewSaleTicketRequest {
                         amount_icp_e8s: min_participant_icp_e8s,
-                        subaccount: None
+                        subaccount: None,
+                        confirmation_text: None,
                     },
                     *principal,
                     ONE_DAY * 2 + TEN_MINUTES
@@ -3,10 +3,12 @@ use crate::{
     environment::{CanisterClients, CanisterEnvironment},
     logs::{ERROR, INFO},
     pb::v1::{
-        error_refund_icp_response, set_dapp_controllers_call_result, set_mode_call_result,
+        error_refund_icp_response, residual_icp_refund, set_dapp_controllers_call_result,
+        set_mode_call_result,
         set_mode_call_result::SetModeResult,
         settle_community_fund_participation_result,
         sns_neuron_recipe::{ClaimedStatus, Investor},
+        validate_open_sale_params_response::ParamsViolation,
         BuyerState, CfInvestment, CfNeuron, CfParticipant, DirectInvestment,
         ErrorRefundIcpResponse, FinalizeSwapResponse, Init, Lifecycle, NeuronId as SaleNeuronId,
         OpenRequest, Params, SetDappControllersCallResult, SetModeCallResult,
@@ -21,7 +23,7 @@ use ic_nervous_system_common::{ledger::ICRC1Ledger, SECONDS_PER_DAY};
 use ic_sns_governance::pb::v1::{ClaimedSwapNeuronStatus, NeuronId};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use maplit::btreemap;
-use std::{collections::BTreeMap, str::FromStr};
+use std::{collections::BTreeMap, num::NonZeroU64, str::FromStr};
 
 pub fn validate_principal(p: &str) -> Result<(), String> {
     let _ = PrincipalId::from_str(p).map_err(|x| {
@@ -187,9 +189,19 @@ impl Init {
             .expect("could not get canister id of icp ledger")
     }
 
+    /// Returns the canister ID of the participation eligibility canister, if the
+    /// swap has one.
+    pub fn participation_eligibility_canister(&self) -> Result<Option<CanisterId>, String> {
+        self.participation_eligibility_canister_id
+            .as_ref()
+            .map(|canister_id| principal_string_to_canister_id(canister_id))
+            .transpose()
+    }
+
     pub fn environment(&self) -> Result<impl CanisterEnvironment, String> {
         use ic_nervous_system_common::ledger::IcpLedgerCanister;
         use ic_sns_governance::ledger::LedgerCanister;
+        use icrc_ledger_client_retry::RetryConfig;
 
         let sns_root = {
             let sns_root_canister_id = self
@@ -217,7 +229,7 @@ impl Init {
             let sns_ledger_canister_id = self
                 .sns_ledger()
                 .map_err(|s| format!("unable to get sns ledger canister id: {s}"))?;
-            LedgerCanister::new(sns_ledger_canister_id)
+            LedgerCanister::new_with_retry_config(sns_ledger_canister_id, RetryConfig::default())
         };
 
         let nns_governance = {
@@ -504,129 +516,165 @@ impl Init {
             return Err("should_auto_finalize is required.".to_string());
         }
 
+        if let Some(canister_id) = &self.participation_eligibility_canister_id {
+            validate_canister_id(canister_id)?;
+        }
+
         Ok(())
     }
 }
 
 impl Params {
     const MIN_SALE_DURATION_SECONDS: u64 = SECONDS_PER_DAY;
-    const MAX_SALE_DURATION_SECONDS: u64 = 90 * SECONDS_PER_DAY;
+    pub(crate) const MAX_SALE_DURATION_SECONDS: u64 = 90 * SECONDS_PER_DAY;
 
     pub fn validate(&self, init: &Init) -> Result<(), String> {
+        match self.violations(init).into_iter().next() {
+            None => Ok(()),
+            Some(violation) => Err(violation.description.unwrap_or_default()),
+        }
+    }
+
+    /// Checks the same invariants as `validate`, in the same order, but
+    /// returns all violations rather than only the first one. Never panics.
+    pub fn violations(&self, init: &Init) -> Vec<ParamsViolation> {
+        let mut violations = vec![];
+        let mut violation = |field: &str, description: String| {
+            violations.push(ParamsViolation {
+                field: Some(field.to_string()),
+                description: Some(description),
+            })
+        };
+
         if self.min_icp_e8s == 0 {
-            return Err("min_icp_e8s must be > 0".to_string());
+            violation("min_icp_e8s", "min_icp_e8s must be > 0".to_string());
         }
 
         if self.min_participants == 0 {
-            return Err("min_participants must be > 0".to_string());
+            violation(
+                "min_participants",
+                "min_participants must be > 0".to_string(),
+            );
         }
 
-        let transaction_fee_e8s = init
-            .transaction_fee_e8s
-            .expect("transaction_fee_e8s was not supplied.");
-
-        let neuron_minimum_stake_e8s = init
-            .neuron_minimum_stake_e8s
-            .expect("neuron_minimum_stake_e8s was not supplied");
-
-        let neuron_basket_count = self
-            .neuron_basket_construction_parameters
-            .as_ref()
-            .expect("participant_neuron_basket not populated.")
-            .count as u128;
-
-        let min_participant_sns_e8s = self.min_participant_icp_e8s as u128
-            * self.sns_token_e8s as u128
-            / self.max_icp_e8s as u128;
-
-        let min_participant_icp_e8s_big_enough = min_participant_sns_e8s
-            >= neuron_basket_count * (neuron_minimum_stake_e8s + transaction_fee_e8s) as u128;
-
-        if !min_participant_icp_e8s_big_enough {
-            return Err(format!(
-                "min_participant_icp_e8s={} is too small. It needs to be \
-                 large enough to ensure that participants will end up with \
-                 enough SNS tokens to form {} SNS neurons, each of which \
-                 require at least {} SNS e8s, plus {} e8s in transaction \
-                 fees. More precisely, the following inequality must hold: \
-                 min_participant_icp_e8s >= neuron_basket_count * (neuron_minimum_stake_e8s + transaction_fee_e8s) * max_icp_e8s / sns_token_e8s \
-                 (where / denotes floor division).",
-                self.min_participant_icp_e8s,
-                neuron_basket_count,
-                neuron_minimum_stake_e8s,
-                transaction_fee_e8s,
-            ));
+        // The remaining checks of the basket size are done below.
+        if let (Some(neuron_basket), Some(max_icp_e8s)) = (
+            self.neuron_basket_construction_parameters.as_ref(),
+            NonZeroU64::new(self.max_icp_e8s),
+        ) {
+            // Both are required by `Init::validate`.
+            let transaction_fee_e8s = init.transaction_fee_e8s.unwrap_or_default();
+            let neuron_minimum_stake_e8s = init.neuron_minimum_stake_e8s.unwrap_or_default();
+            let neuron_basket_count = neuron_basket.count as u128;
+
+            let min_participant_sns_e8s = self.min_participant_icp_e8s as u128
+                * self.sns_token_e8s as u128
+                / max_icp_e8s.get() as u128;
+
+            let min_participant_icp_e8s_big_enough = min_participant_sns_e8s
+                >= neuron_basket_count
+                    * (neuron_minimum_stake_e8s as u128 + transaction_fee_e8s as u128);
+
+            if !min_participant_icp_e8s_big_enough {
+                violation(
+                    "min_participant_icp_e8s",
+                    format!(
+                        "min_participant_icp_e8s={} is too small. It needs to be \
+                         large enough to ensure that participants will end up with \
+                         enough SNS tokens to form {} SNS neurons, each of which \
+                         require at least {} SNS e8s, plus {} e8s in transaction \
+                         fees. More precisely, the following inequality must hold: \
+                         min_participant_icp_e8s >= neuron_basket_count * (neuron_minimum_stake_e8s + transaction_fee_e8s) * max_icp_e8s / sns_token_e8s \
+                         (where / denotes floor division).",
+                        self.min_participant_icp_e8s,
+                        neuron_basket_count,
+                        neuron_minimum_stake_e8s,
+                        transaction_fee_e8s,
+                    ),
+                );
+            }
         }
 
         if self.sns_token_e8s == 0 {
-            return Err("sns_token_e8s must be > 0".to_string());
+            violation("sns_token_e8s", "sns_token_e8s must be > 0".to_string());
         }
 
         if self.max_participant_icp_e8s < self.min_participant_icp_e8s {
-            return Err(format!(
-                "max_participant_icp_e8s ({}) must be >= min_participant_icp_e8s ({})",
-                self.max_participant_icp_e8s, self.min_participant_icp_e8s
-            ));
+            violation(
+                "max_participant_icp_e8s",
+                format!(
+                    "max_participant_icp_e8s ({}) must be >= min_participant_icp_e8s ({})",
+                    self.max_participant_icp_e8s, self.min_participant_icp_e8s
+                ),
+            );
         }
 
         if self.min_icp_e8s > self.max_icp_e8s {
-            return Err(format!(
-                "min_icp_e8s ({}) must be <= max_icp_e8s ({})",
-                self.min_icp_e8s, self.max_icp_e8s
-            ));
+            violation(
+                "min_icp_e8s",
+                format!(
+                    "min_icp_e8s ({}) must be <= max_icp_e8s ({})",
+                    self.min_icp_e8s, self.max_icp_e8s
+                ),
+            );
         }
 
         if self.max_participant_icp_e8s > self.max_icp_e8s {
-            return Err(format!(
-                "max_participant_icp_e8s ({}) must be <= max_icp_e8s ({})",
-                self.max_participant_icp_e8s, self.max_icp_e8s
-            ));
+            violation(
+                "max_participant_icp_e8s",
+                format!(
+                    "max_participant_icp_e8s ({}) must be <= max_icp_e8s ({})",
+                    self.max_participant_icp_e8s, self.max_icp_e8s
+                ),
+            );
         }
 
         // Cap `max_icp_e8s` at 1 billion ICP
         if self.max_icp_e8s > /* 1B */ 1_000_000_000 * /* e8s per ICP */ 100_000_000 {
-            return Err(format!(
-                "max_icp_e8s ({}) can be at most 1B ICP",
-                self.max_icp_e8s
-            ));
+            violation(
+                "max_icp_e8s",
+                format!("max_icp_e8s ({}) can be at most 1B ICP", self.max_icp_e8s),
+            );
         }
 
-        // 100 * 1B * E8S should fit in a u64.
-        assert!(self
-            .max_icp_e8s
-            .checked_mul(self.min_participants as u64)
-            .is_some());
-
         if self.max_icp_e8s
             < (self.min_participants as u64).saturating_mul(self.min_participant_icp_e8s)
         {
-            return Err(format!(
-                "max_icp_e8s ({}) must be >= min_participants ({}) * min_participant_icp_e8s ({})",
-                self.max_icp_e8s, self.min_participants, self.min_participant_icp_e8s
-            ));
-        }
-
-        if self.neuron_basket_construction_parameters.is_none() {
-            return Err("neuron_basket_construction_parameters must be provided".to_string());
+            violation(
+                "max_icp_e8s",
+                format!(
+                    "max_icp_e8s ({}) must be >= min_participants ({}) * min_participant_icp_e8s ({})",
+                    self.max_icp_e8s, self.min_participants, self.min_participant_icp_e8s
+                ),
+            );
         }
 
-        let neuron_basket = self
-            .neuron_basket_construction_parameters
-            .as_ref()
-            .expect("Expected neuron_basket_construction_parameters to be set");
+        let Some(neuron_basket) = self.neuron_basket_construction_parameters.as_ref() else {
+            violation(
+                "neuron_basket_construction_parameters",
+                "neuron_basket_construction_parameters must be provided".to_string(),
+            );
+            return violations;
+        };
 
         if neuron_basket.count < 2 {
-            return Err(format!(
-                "neuron_basket_construction_parameters.count ({}) must be >= 2",
-                neuron_basket.count,
-            ));
+            violation(
+                "neuron_basket_construction_parameters",
+                format!(
+                    "neuron_basket_construction_parameters.count ({}) must be >= 2",
+                    neuron_basket.count,
+                ),
+            );
         }
 
         if neuron_basket.dissolve_delay_interval_seconds == 0 {
-            return Err(format!(
-                "neuron_basket_construction_parameters.dissolve_delay_interval_seconds ({}) must be > 0",
-                neuron_basket.dissolve_delay_interval_seconds,
-            ));
+            violation(
+                "neuron_basket_construction_parameters",
+                format!(
+                    "neuron_basket_construction_parameters.dissolve_delay_interval_seconds ({}) must be > 0",
+                    neuron_basket.dissolve_delay_interval_seconds,
+                ),
+            );
         }
 
         let maximum_dissolve_delay = neuron_basket
@@ -635,13 +683,32 @@ impl Params {
             .saturating_add(1);
 
         if maximum_dissolve_delay == u64::MAX {
-            return Err(
+            violation(
+                "neuron_basket_construction_parameters",
                 "Chosen neuron_basket_construction_parameters will result in u64 overflow"
                     .to_string(),
             );
         }
 
-        Ok(())
+        violations
+    }
+
+    /// Returns a description of why the swap would be too short or too long
+    /// if it were opened at `now_seconds`, or `None` if the duration is valid.
+    pub fn duration_violation_if_initiated_at(&self, now_seconds: u64) -> Option<String> {
+        if self.is_valid_if_initiated_at(now_seconds) {
+            return None;
+        }
+        let open_timestamp_seconds =
+            now_seconds.saturating_add(self.sale_delay_seconds.unwrap_or(0));
+        Some(format!(
+            "swap_due_timestamp_seconds ({}) must be between {} and {} seconds after \
+             the swap opens at {} (now plus sale_delay_seconds)",
+            self.swap_due_timestamp_seconds,
+            Self::MIN_SALE_DURATION_SECONDS,
+            Self::MAX_SALE_DURATION_SECONDS,
+            open_timestamp_seconds,
+        ))
     }
 
     pub fn is_valid_if_initiated_at(&self, now_seconds: u64) -> bool {
@@ -675,6 +742,8 @@ impl BuyerState {
                 amount_transferred_e8s: Some(0),
                 transfer_fee_paid_e8s: Some(0),
             }),
+            residual_icp_refund: None,
+            confirmation_text: None,
         }
     }
     pub fn validate(&self) -> Result<(), String> {
@@ -692,6 +761,24 @@ impl BuyerState {
         0
     }
 
+    /// Whether the ICP left in this buyer's subaccount should be refunded,
+    /// i.e., the committed ICP has been swept, and no earlier attempt has
+    /// refunded the rest or found nothing to refund.
+    pub fn needs_residual_icp_refund(&self) -> bool {
+        let swept = self
+            .icp
+            .as_ref()
+            .map_or(false, |icp| icp.transfer_success_timestamp_seconds != 0);
+        let settled = self.residual_icp_refund.as_ref().map_or(false, |refund| {
+            matches!(
+                refund.status(),
+                residual_icp_refund::Status::Refunded
+                    | residual_icp_refund::Status::NothingToRefund
+            )
+        });
+        swept && !settled
+    }
+
     pub fn set_amount_icp_e8s(&mut self, val: u64) {
         if let Some(ref mut icp) = &mut self.icp {
             icp.amount_e8s = val;
@@ -1267,6 +1354,53 @@ mod tests {
         params.validate(&INIT).unwrap();
     }
 
+    #[test]
+    fn params_violations_lists_every_violated_invariant() {
+        assert!(PARAMS.violations(&INIT).is_empty());
+
+        let params = Params {
+            min_icp_e8s: 0,
+            max_participant_icp_e8s: PARAMS.min_participant_icp_e8s - 1,
+            neuron_basket_construction_parameters: None,
+            ..PARAMS
+        };
+        let fields: Vec<String> = params
+            .violations(&INIT)
+            .into_iter()
+            .map(|violation| violation.field.unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "min_icp_e8s",
+                "max_participant_icp_e8s",
+                "neuron_basket_construction_parameters"
+            ]
+        );
+        // validate reports the first violation only.
+        assert_eq!(
+            params.validate(&INIT),
+            Err("min_icp_e8s must be > 0".to_string())
+        );
+    }
+
+    #[test]
+    fn params_violations_do_not_panic_on_zero_max_icp() {
+        let params = Params {
+            max_icp_e8s: 0,
+            ..PARAMS
+        };
+        let fields: Vec<String> = params
+            .violations(&INIT)
+            .into_iter()
+            .map(|violation| violation.field.unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["min_icp_e8s", "max_participant_icp_e8s", "max_icp_e8s"]
+        );
+    }
+
     #[test]
     fn open_request_validate_invalid_params() {
         let request = OpenRequest {
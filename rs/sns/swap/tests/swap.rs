@@ -32,6 +32,7 @@ use ic_nervous_system_common_test_utils::{
     SpyLedger,
 };
 use ic_nervous_system_proto::pb::v1::Countries;
+use ic_sns_clients::MockCanisterClient;
 use ic_sns_governance::{
     pb::v1::{
         claim_swap_neurons_request::NeuronParameters,
@@ -41,6 +42,7 @@ use ic_sns_governance::{
     types::ONE_MONTH_SECONDS,
 };
 use ic_sns_swap::{
+    clients::RealParticipationEligibilityClient,
     environment::CanisterClients,
     memory,
     pb::v1::{
@@ -51,7 +53,9 @@ use ic_sns_swap::{
     },
     swap::{
         apportion_approximately_equally, principal_to_subaccount, CLAIM_SWAP_NEURONS_BATCH_SIZE,
-        FIRST_PRINCIPAL_BYTES, NEURON_BASKET_MEMO_RANGE_START,
+        FIRST_PRINCIPAL_BYTES, MAX_AUTO_FINALIZE_STEP_ATTEMPTS, NEURON_BASKET_MEMO_RANGE_START,
+        PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS, RESIDUAL_ICP_REFUND_INTERVAL_SECONDS,
+        SWEEP_ICP_CHUNK_SIZE, SWEEP_ICP_MAX_CHUNK_ATTEMPTS,
     },
 };
 use icp_ledger::DEFAULT_TRANSFER_FEE;
@@ -114,6 +118,7 @@ fn init_with_confirmation_text(confirmation_text: Option<String>) -> Init {
         neurons_fund_participants: None,             // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
         neurons_fund_participation_constraints: None,
+        participation_eligibility_canister_id: None,
     };
     assert_is_ok!(result.validate());
     result
@@ -176,6 +181,13 @@ fn create_generic_committed_swap() -> Swap {
         auto_finalize_swap_response: None,
         direct_participation_icp_e8s: None,
         neurons_fund_participation_icp_e8s: None,
+        residual_icp_refund_next_buyer: None,
+        residual_icp_refund_last_completion_timestamp_seconds: None,
+        finalize_swap_progress: None,
+        participation_eligibility: btreemap! {},
+        sale_params_amendments: vec![],
+        sweep_icp_next_buyer: None,
+        participation_eligibility_purge_timestamp_seconds: None,
     }
 }
 
@@ -1184,6 +1196,13 @@ async fn test_finalize_swap_ok() {
         auto_finalize_swap_response: None,
         direct_participation_icp_e8s: None,
         neurons_fund_participation_icp_e8s: None,
+        residual_icp_refund_next_buyer: None,
+        residual_icp_refund_last_completion_timestamp_seconds: None,
+        finalize_swap_progress: None,
+        participation_eligibility: btreemap! {},
+        sale_params_amendments: vec![],
+        sweep_icp_next_buyer: None,
+        participation_eligibility_purge_timestamp_seconds: None,
     };
     swap.update_derived_fields();
 
@@ -1497,7 +1516,9 @@ async fn test_finalize_swap_ok() {
                         transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS + 10,
                         amount_transferred_e8s: Some(expected_amount_committed_e8s),
                         transfer_fee_paid_e8s: Some(fee_e8s)
-                    })
+                    }),
+                    residual_icp_refund: None,
+                    confirmation_text: None,
                 }
             );
         });
@@ -1548,6 +1569,13 @@ async fn test_finalize_swap_abort() {
         auto_finalize_swap_response: None,
         direct_participation_icp_e8s: None,
         neurons_fund_participation_icp_e8s: None,
+        residual_icp_refund_next_buyer: None,
+        residual_icp_refund_last_completion_timestamp_seconds: None,
+        finalize_swap_progress: None,
+        participation_eligibility: btreemap! {},
+        sale_params_amendments: vec![],
+        sweep_icp_next_buyer: None,
+        participation_eligibility_purge_timestamp_seconds: None,
     };
 
     // Step 1.5: Attempt to auto-finalize the swap. It should not work, since
@@ -2507,7 +2535,9 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                 icp: Some(TransferableAmount {
                     amount_e8s: DEFAULT_TRANSFER_FEE.get_e8s() - 1,
                     ..Default::default()
-                })
+                }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
             // This Buyer has already had its transfer succeed, and should result in
             // as Skipped field increment
@@ -2517,7 +2547,9 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                     transfer_start_timestamp_seconds: END_TIMESTAMP_SECONDS,
                     transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS + 1,
                     ..Default::default()
-                })
+                }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
             // This buyer's state is valid, and a mock call to the ledger will allow it
             // to succeed, which should result in a success field increment
@@ -2525,7 +2557,9 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                 icp: Some(TransferableAmount {
                     amount_e8s: 10 * E8,
                     ..Default::default()
-                })
+                }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
             // This buyer's state is valid, but a mock call to the ledger will fail the transfer,
             // which should result in a failure field increment.
@@ -2533,20 +2567,26 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                 icp: Some(TransferableAmount {
                     amount_e8s: 10 * E8,
                     ..Default::default()
-                })
+                }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
         },
         ..Default::default()
     };
 
     // Mock the replies from the ledger
-    let icp_ledger = SpyLedger::new(vec![
+    let mut replies = vec![
         // This mocked reply should produce a successful transfer in SweepResult
         LedgerReply::TransferFunds(Ok(1000)),
-        LedgerReply::TransferFunds(Err(NervousSystemError::new_with_message(
-            "Error when transferring funds",
-        ))),
-    ]);
+    ];
+    // The failed transfer is attempted again before giving up.
+    for _ in 0..SWEEP_ICP_MAX_CHUNK_ATTEMPTS {
+        replies.push(LedgerReply::TransferFunds(Err(
+            NervousSystemError::new_with_message("Error when transferring funds"),
+        )));
+    }
+    let icp_ledger = SpyLedger::new(replies);
 
     // Step 2: Call sweep_icp
     let sweep_result = swap.sweep_icp(now_fn, &icp_ledger).await;
@@ -2562,9 +2602,13 @@ async fn test_sweep_icp_handles_ledger_transfers() {
         }
     );
 
-    // Assert that only two calls were issued by finalize.
+    // Assert that only the successful transfer and the attempts of the failed
+    // transfer were issued by finalize.
     let observed_icp_ledger_calls = icp_ledger.get_calls_snapshot();
-    assert_eq!(observed_icp_ledger_calls.len(), 2);
+    assert_eq!(
+        observed_icp_ledger_calls.len(),
+        1 + SWEEP_ICP_MAX_CHUNK_ATTEMPTS as usize
+    );
 }
 
 /// Tests that if transferring does not complete fully, finalize will halt finalization
@@ -2585,7 +2629,9 @@ async fn test_finalization_halts_when_sweep_icp_fails() {
                 icp: Some(TransferableAmount {
                     amount_e8s: DEFAULT_TRANSFER_FEE.get_e8s() - 1,
                     ..Default::default()
-                })
+                }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
             // This buyer's state is valid, but a mock call to the ledger will fail the transfer,
             // which should result in a failure field increment.
@@ -2593,19 +2639,24 @@ async fn test_finalization_halts_when_sweep_icp_fails() {
                 icp: Some(TransferableAmount {
                     amount_e8s: 10 * E8,
                     ..Default::default()
-                })
+                }),
+                residual_icp_refund: None,
+                confirmation_text: None,
             },
         },
         ..Default::default()
     };
 
     let mut clients = CanisterClients {
-        icp_ledger: SpyLedger::new(vec![
-            // This mocked reply should produce a successful transfer in SweepResult
-            LedgerReply::TransferFunds(Err(NervousSystemError::new_with_message(
-                "Error when transferring funds",
-            ))),
-        ]),
+        icp_ledger: SpyLedger::new(
+            (0..SWEEP_ICP_MAX_CHUNK_ATTEMPTS)
+                .map(|_| {
+                    LedgerReply::TransferFunds(Err(NervousSystemError::new_with_message(
+                        "Error when transferring funds",
+                    )))
+                })
+                .collect(),
+        ),
         ..spy_clients()
     };
 
@@ -2842,6 +2893,69 @@ async fn test_sweep_sns_handles_ledger_transfers() {
     assert_eq!(observed_icp_ledger_calls.len(), 2);
 }
 
+/// Tests that sweep_icp retries failed transfers, and that a sweep that fails
+/// resumes with the chunk that failed.
+#[tokio::test]
+async fn test_sweep_icp_resumes_from_failed_chunk() {
+    // Step 1: Prepare the world. One buyer more than fits in a chunk.
+    let mut swap = Swap {
+        lifecycle: Committed as i32,
+        init: Some(init()),
+        params: Some(params()),
+        buyers: (0..=SWEEP_ICP_CHUNK_SIZE as u64)
+            .map(|i| (i2principal_id_string(1000 + i), BuyerState::new(10 * E8)))
+            .collect(),
+        ..Default::default()
+    };
+    let last_buyer = swap.buyers.keys().last().cloned();
+    let transfer_failure = || {
+        LedgerReply::TransferFunds(Err(NervousSystemError::new_with_message(
+            "Error when transferring funds",
+        )))
+    };
+
+    // Step 2: Sweep. The first transfer fails once and is retried, and the
+    // transfer of the last buyer fails every time.
+    let mut replies = vec![transfer_failure()];
+    replies.extend((0..SWEEP_ICP_CHUNK_SIZE as u64).map(|i| LedgerReply::TransferFunds(Ok(i))));
+    replies.extend((0..SWEEP_ICP_MAX_CHUNK_ATTEMPTS).map(|_| transfer_failure()));
+    let icp_ledger = SpyLedger::new(replies);
+    let sweep_result = swap.sweep_icp(now_fn, &icp_ledger).await;
+
+    // Step 3: Inspect the results. The first chunk is done, and the sweep
+    // stopped at the second chunk.
+    assert_eq!(
+        sweep_result,
+        SweepResult {
+            success: SWEEP_ICP_CHUNK_SIZE as u32,
+            failure: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        icp_ledger.get_calls_snapshot().len(),
+        SWEEP_ICP_CHUNK_SIZE + 1 + SWEEP_ICP_MAX_CHUNK_ATTEMPTS as usize
+    );
+    assert_eq!(swap.sweep_icp_next_buyer, last_buyer);
+
+    // Step 4: Sweep again. Only the buyers of the failed chunk are inspected.
+    let icp_ledger = SpyLedger::new(vec![LedgerReply::TransferFunds(Ok(1000))]);
+    let sweep_result = swap.sweep_icp(now_fn, &icp_ledger).await;
+    assert_eq!(
+        sweep_result,
+        SweepResult {
+            success: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(icp_ledger.get_calls_snapshot().len(), 1);
+    assert_eq!(swap.sweep_icp_next_buyer, None);
+    assert!(swap.buyers.values().all(|buyer_state| {
+        buyer_state.icp.as_ref().unwrap().amount_transferred_e8s
+            == Some(10 * E8 - DEFAULT_TRANSFER_FEE.get_e8s())
+    }));
+}
+
 /// Tests that if transferring does not complete fully, finalize will halt finalization
 #[tokio::test]
 async fn test_finalization_halts_when_sweep_sns_fails() {
@@ -3078,6 +3192,166 @@ async fn test_finalize_swap_abort_executes_correct_subactions() {
     assert!(!swap.is_finalize_swap_locked());
 }
 
+/// Tests that automatic finalization performs one step at a time, and records the
+/// progress of every step.
+#[tokio::test]
+async fn test_auto_finalize_swap_abort_step_by_step() {
+    use finalize_swap_progress::Step;
+
+    // Step 1: Prepare the world
+    let mut swap = Swap {
+        lifecycle: Aborted as i32,
+        init: Some(init()),
+        params: Some(params()),
+        buyers: btreemap! {
+            i2principal_id_string(1001) => BuyerState::new(50 * E8),
+        },
+        already_tried_to_auto_finalize: Some(false),
+        ..Default::default()
+    };
+
+    let mut clients = CanisterClients {
+        sns_root: SpySnsRootClient::new(
+            vec![SnsRootClientReply::successful_set_dapp_controllers()],
+        ),
+        icp_ledger: SpyLedger::new(vec![LedgerReply::TransferFunds(Ok(1000))]),
+        ..spy_clients()
+    };
+
+    // Nothing happens before auto-finalization has started.
+    assert_eq!(
+        swap.try_advance_auto_finalization(now_fn, &mut clients, CLAIM_SWAP_NEURONS_BATCH_SIZE)
+            .await,
+        None
+    );
+    assert_eq!(
+        swap.get_finalize_status(&GetFinalizeStatusRequest {}),
+        GetFinalizeStatusResponse { progress: None }
+    );
+
+    // Step 2: Start auto-finalization. It can only be started once.
+    swap.start_auto_finalization(END_TIMESTAMP_SECONDS).unwrap();
+    assert!(swap.start_auto_finalization(END_TIMESTAMP_SECONDS).is_err());
+    assert!(swap.is_auto_finalization_in_progress());
+
+    // Step 3: Perform the steps one by one.
+    let mut next_steps = vec![];
+    while let Some(progress) = swap
+        .try_advance_auto_finalization(now_fn, &mut clients, CLAIM_SWAP_NEURONS_BATCH_SIZE)
+        .await
+    {
+        assert_eq!(progress.failed_attempts, Some(0));
+        next_steps.push(progress.next_step());
+        assert!(!swap.is_finalize_swap_locked());
+    }
+
+    // Step 4: Inspect the results
+    assert_eq!(
+        next_steps,
+        vec![
+            Step::SettleCommunityFundParticipation,
+            Step::SetDappControllers,
+            Step::Done,
+        ]
+    );
+    assert!(!swap.is_auto_finalization_in_progress());
+
+    let response = swap.auto_finalize_swap_response.clone().unwrap();
+    assert_eq!(
+        response.sweep_icp_result,
+        Some(SweepResult {
+            success: 1,
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        response.settle_community_fund_participation_result,
+        Some(successful_settle_community_fund_participation_result()),
+    );
+    assert_eq!(
+        response.set_dapp_controllers_call_result,
+        Some(successful_set_dapp_controllers_call_result()),
+    );
+    assert!(response.sweep_sns_result.is_none());
+    assert!(response.claim_neuron_result.is_none());
+    assert!(response.set_mode_call_result.is_none());
+    assert_eq!(response.error_message, None);
+
+    let progress = swap
+        .get_finalize_status(&GetFinalizeStatusRequest {})
+        .progress
+        .unwrap();
+    assert_eq!(progress.next_step(), Step::Done);
+    assert_eq!(progress.response, Some(response));
+}
+
+/// Tests that automatic finalization retries a failing step, and gives up after
+/// MAX_AUTO_FINALIZE_STEP_ATTEMPTS attempts.
+#[tokio::test]
+async fn test_auto_finalize_swap_retries_failed_step() {
+    use finalize_swap_progress::Step;
+
+    // Step 1: Prepare the world
+    let mut swap = Swap {
+        lifecycle: Aborted as i32,
+        init: Some(init()),
+        params: Some(params()),
+        buyers: btreemap! {
+            i2principal_id_string(1001) => BuyerState::new(50 * E8),
+        },
+        already_tried_to_auto_finalize: Some(false),
+        ..Default::default()
+    };
+
+    // The ICP ledger fails every transfer.
+    let mut clients = CanisterClients {
+        icp_ledger: SpyLedger::new(
+            (0..MAX_AUTO_FINALIZE_STEP_ATTEMPTS * SWEEP_ICP_MAX_CHUNK_ATTEMPTS)
+                .map(|_| {
+                    LedgerReply::TransferFunds(Err(NervousSystemError::new_with_message(
+                        "Error when transferring funds",
+                    )))
+                })
+                .collect(),
+        ),
+        ..spy_clients_exploding_root()
+    };
+
+    // Step 2: Run the code under test
+    swap.start_auto_finalization(END_TIMESTAMP_SECONDS).unwrap();
+    for attempt in 1..=MAX_AUTO_FINALIZE_STEP_ATTEMPTS {
+        let progress = swap
+            .try_advance_auto_finalization(now_fn, &mut clients, CLAIM_SWAP_NEURONS_BATCH_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(progress.next_step(), Step::SweepIcp);
+        assert_eq!(progress.failed_attempts, Some(attempt));
+        assert!(progress.response.unwrap().has_error_message());
+    }
+
+    // Step 3: Inspect the results. Automatic finalization gave up, and
+    // recorded the error.
+    assert!(!swap.is_auto_finalization_in_progress());
+    assert_eq!(
+        swap.try_advance_auto_finalization(now_fn, &mut clients, CLAIM_SWAP_NEURONS_BATCH_SIZE)
+            .await,
+        None
+    );
+    let response = swap.auto_finalize_swap_response.clone().unwrap();
+    assert!(response.has_error_message());
+    assert_eq!(
+        response.sweep_icp_result,
+        Some(SweepResult {
+            failure: 1,
+            ..Default::default()
+        })
+    );
+    assert!(response
+        .settle_community_fund_participation_result
+        .is_none());
+    assert!(!swap.is_finalize_swap_locked());
+}
+
 /// Test the restore_dapp_controllers API happy case
 #[tokio::test]
 async fn test_restore_dapp_controllers_happy() {
@@ -3413,6 +3687,8 @@ fn test_derived_state() {
             transfer_success_timestamp_seconds: 12,
             ..Default::default()
         }),
+        residual_icp_refund: None,
+        confirmation_text: None,
     };
     let buyers = btreemap! {
         "".to_string() => buyer_state,
@@ -4712,6 +4988,10 @@ fn test_swap_participation_confirmation() {
         open_swap(&mut swap, &params());
         // A.1. The texts match
         assert_is_ok!(buy_token(&mut swap, Some(confirmation_text.clone())));
+        assert_eq!(
+            swap.buyers[&user.to_string()].confirmation_text,
+            Some(confirmation_text.clone())
+        );
         // A.2. The texts do not match
         assert_is_err!(buy_token(&mut swap, Some(another_text)));
     }
@@ -4735,9 +5015,425 @@ fn test_swap_participation_confirmation() {
         let mut swap = Swap::new(init_with_confirmation_text(None));
         open_swap(&mut swap, &params());
         assert_is_ok!(buy_token(&mut swap, None));
+        assert_eq!(swap.buyers[&user.to_string()].confirmation_text, None);
     }
 }
 
+/// Test that the `new_sale_ticket` function handles confirmations correctly.
+#[test]
+fn test_new_sale_ticket_confirmation() {
+    let confirmation_text = "Please confirm that 2+2=4".to_string();
+    let another_text = "Please confirm that 2+2=5".to_string();
+    let account = Account {
+        owner: SWAP_CANISTER_ID.get().into(),
+        subaccount: None,
+    };
+    let params = params();
+
+    let new_swap = |confirmation_text: Option<String>| {
+        let mut swap = Swap::new(init_with_confirmation_text(confirmation_text));
+        assert!(swap
+            .open(
+                SWAP_CANISTER_ID,
+                &mock_stub(vec![LedgerExpect::AccountBalance(
+                    account,
+                    Ok(Tokens::from_e8s(params.sns_token_e8s)),
+                )]),
+                START_TIMESTAMP_SECONDS,
+                OpenRequest {
+                    params: Some(params.clone()),
+                    cf_participants: vec![],
+                    open_sns_token_swap_proposal_id: Some(OPEN_SNS_TOKEN_SWAP_PROPOSAL_ID),
+                }
+            )
+            .now_or_never()
+            .unwrap()
+            .is_ok());
+        swap
+    };
+
+    let new_sale_ticket = |swap: &mut Swap, user: u64, confirmation_text: Option<String>| {
+        swap.new_sale_ticket(
+            &NewSaleTicketRequest {
+                amount_icp_e8s: params.min_participant_icp_e8s,
+                subaccount: None,
+                confirmation_text,
+            },
+            PrincipalId::new_user_test_id(user),
+            START_TIMESTAMP_SECONDS,
+        )
+        .ticket()
+        .map_err(|err| err.error_type())
+    };
+
+    use new_sale_ticket_response::err::Type::InvalidConfirmationText;
+
+    // SNS specifies a confirmation text.
+    let mut swap = new_swap(Some(confirmation_text.clone()));
+    assert_is_ok!(new_sale_ticket(
+        &mut swap,
+        1,
+        Some(confirmation_text.clone())
+    ));
+    assert_eq!(
+        new_sale_ticket(&mut swap, 2, Some(another_text)),
+        Err(InvalidConfirmationText)
+    );
+    assert_eq!(
+        new_sale_ticket(&mut swap, 3, None),
+        Err(InvalidConfirmationText)
+    );
+
+    // SNS does not specify a confirmation text. Open tickets live in stable memory, which
+    // outlives the swap, hence the fresh users.
+    let mut swap = new_swap(None);
+    assert_is_ok!(new_sale_ticket(&mut swap, 4, None));
+    assert_eq!(
+        new_sale_ticket(&mut swap, 5, Some(confirmation_text)),
+        Err(InvalidConfirmationText)
+    );
+}
+
+/// Test that the participation eligibility canister is consulted (and its answers cached)
+/// before issuing sale tickets, and that the per-principal bounds it returns are enforced.
+#[test]
+fn test_new_sale_ticket_participation_eligibility() {
+    let account = Account {
+        owner: SWAP_CANISTER_ID.get().into(),
+        subaccount: None,
+    };
+    let params = params();
+    let eligibility_canister_id = CanisterId::from_u64(4242);
+    let mut swap = Swap::new(Init {
+        participation_eligibility_canister_id: Some(eligibility_canister_id.to_string()),
+        ..init()
+    });
+    assert!(swap
+        .open(
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![LedgerExpect::AccountBalance(
+                account,
+                Ok(Tokens::from_e8s(params.sns_token_e8s)),
+            )]),
+            START_TIMESTAMP_SECONDS,
+            OpenRequest {
+                params: Some(params.clone()),
+                cf_participants: vec![],
+                open_sns_token_swap_proposal_id: Some(OPEN_SNS_TOKEN_SWAP_PROPOSAL_ID),
+            }
+        )
+        .now_or_never()
+        .unwrap()
+        .is_ok());
+
+    let not_eligible = PrincipalId::new_user_test_id(21);
+    let eligible = PrincipalId::new_user_test_id(22);
+    let unreachable = PrincipalId::new_user_test_id(23);
+    let max_participant_icp_e8s = params.min_participant_icp_e8s + E8;
+
+    let mut canister_client = MockCanisterClient::new();
+    canister_client.push_reply(Ok(CheckParticipationEligibilityResponse {
+        eligible: Some(false),
+        reason: Some("Not on the allow list".to_string()),
+        min_participant_icp_e8s: None,
+        max_participant_icp_e8s: None,
+    }));
+    canister_client.push_reply(Ok(CheckParticipationEligibilityResponse {
+        eligible: Some(true),
+        reason: None,
+        min_participant_icp_e8s: None,
+        max_participant_icp_e8s: Some(max_participant_icp_e8s),
+    }));
+    canister_client.push_reply::<CheckParticipationEligibilityResponse>(Err((
+        None,
+        "The eligibility canister is unreachable".to_string(),
+    )));
+    let mut eligibility_client = RealParticipationEligibilityClient::with_client(
+        eligibility_canister_id,
+        canister_client.clone(),
+    );
+
+    let mut check = |swap: &mut Swap, caller: PrincipalId, now_seconds: u64| {
+        swap.check_participation_eligibility(caller, now_seconds, &mut eligibility_client)
+            .now_or_never()
+            .unwrap()
+    };
+
+    // An ineligible principal is rejected, and the answer is cached.
+    assert_eq!(
+        check(&mut swap, not_eligible, START_TIMESTAMP_SECONDS),
+        Err(NewSaleTicketResponse::err_not_eligible())
+    );
+    assert_eq!(
+        check(&mut swap, not_eligible, START_TIMESTAMP_SECONDS + 1),
+        Err(NewSaleTicketResponse::err_not_eligible())
+    );
+    assert_eq!(
+        swap.participation_eligibility[&not_eligible.to_string()].reason(),
+        "Not on the allow list"
+    );
+
+    // An eligible principal may buy up to its own maximum.
+    assert_is_ok!(check(&mut swap, eligible, START_TIMESTAMP_SECONDS));
+    let new_sale_ticket = |swap: &mut Swap, amount_icp_e8s: u64| {
+        swap.new_sale_ticket(
+            &NewSaleTicketRequest {
+                amount_icp_e8s,
+                subaccount: None,
+                confirmation_text: None,
+            },
+            eligible,
+            START_TIMESTAMP_SECONDS,
+        )
+    };
+    assert_eq!(
+        new_sale_ticket(&mut swap, max_participant_icp_e8s + 1),
+        NewSaleTicketResponse::err_invalid_user_amount(
+            params.min_participant_icp_e8s,
+            max_participant_icp_e8s
+        )
+    );
+    assert_is_ok!(new_sale_ticket(&mut swap, max_participant_icp_e8s).ticket());
+
+    // A failed call is treated as ineligibility, but is not cached.
+    assert_eq!(
+        check(&mut swap, unreachable, START_TIMESTAMP_SECONDS),
+        Err(NewSaleTicketResponse::err_not_eligible())
+    );
+    assert!(!swap
+        .participation_eligibility
+        .contains_key(&unreachable.to_string()));
+
+    // Only the first call for each principal reached the eligibility canister.
+    canister_client.assert_all_replies_consumed();
+    let calls = canister_client.get_calls_snapshot();
+    assert!(calls
+        .iter()
+        .all(|call| call.canister_id == eligibility_canister_id
+            && call.method == "check_participation_eligibility"));
+    assert_eq!(
+        calls
+            .iter()
+            .map(|call| call
+                .decode_args::<CheckParticipationEligibilityRequest>()
+                .principal_id
+                .unwrap())
+            .collect::<Vec<_>>(),
+        vec![not_eligible, eligible, unreachable]
+    );
+
+    // Cached answers expire.
+    canister_client.push_reply(Ok(CheckParticipationEligibilityResponse {
+        eligible: Some(true),
+        ..Default::default()
+    }));
+    assert_is_ok!(check(
+        &mut swap,
+        not_eligible,
+        START_TIMESTAMP_SECONDS + PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS
+    ));
+    assert_eq!(canister_client.get_calls_snapshot().len(), 4);
+}
+
+/// Test that expired participation eligibility entries are purged, except those of
+/// buyers and of principals with an open ticket, and at most once per cache TTL.
+#[tokio::test]
+async fn test_try_purge_expired_participation_eligibility() {
+    let params = params();
+    let mut swap = Swap::new(init());
+    open_swap(&mut swap, &params).await;
+
+    let buyer = PrincipalId::new_user_test_id(31);
+    let ticket_holder = PrincipalId::new_user_test_id(32);
+    let expired = PrincipalId::new_user_test_id(33);
+    let fresh = PrincipalId::new_user_test_id(34);
+    let eligibility = |timestamp_seconds: u64| ParticipationEligibility {
+        eligible: Some(true),
+        timestamp_seconds: Some(timestamp_seconds),
+        ..Default::default()
+    };
+    let purge_timestamp_seconds =
+        START_TIMESTAMP_SECONDS + PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS;
+    swap.participation_eligibility = btreemap! {
+        buyer.to_string() => eligibility(START_TIMESTAMP_SECONDS),
+        ticket_holder.to_string() => eligibility(START_TIMESTAMP_SECONDS),
+        expired.to_string() => eligibility(START_TIMESTAMP_SECONDS),
+        fresh.to_string() => eligibility(purge_timestamp_seconds),
+    };
+    swap.buyers.insert(
+        buyer.to_string(),
+        BuyerState::new(params.min_participant_icp_e8s),
+    );
+    assert_is_ok!(swap
+        .new_sale_ticket(
+            &NewSaleTicketRequest {
+                amount_icp_e8s: params.min_participant_icp_e8s,
+                subaccount: None,
+                confirmation_text: None,
+            },
+            ticket_holder,
+            START_TIMESTAMP_SECONDS,
+        )
+        .ticket());
+
+    // Nothing has expired yet.
+    assert_eq!(
+        swap.try_purge_expired_participation_eligibility(purge_timestamp_seconds - 1),
+        Some(0)
+    );
+    // The purge runs at most once per cache TTL.
+    assert_eq!(
+        swap.try_purge_expired_participation_eligibility(purge_timestamp_seconds),
+        None
+    );
+    assert_eq!(swap.participation_eligibility.len(), 4);
+
+    assert_eq!(
+        swap.try_purge_expired_participation_eligibility(
+            purge_timestamp_seconds - 1 + PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS
+        ),
+        Some(1)
+    );
+    assert_eq!(
+        swap.participation_eligibility
+            .keys()
+            .cloned()
+            .collect::<HashSet<_>>(),
+        HashSet::from([
+            buyer.to_string(),
+            ticket_holder.to_string(),
+            fresh.to_string()
+        ])
+    );
+    assert_eq!(
+        swap.participation_eligibility_purge_timestamp_seconds,
+        Some(purge_timestamp_seconds - 1 + PARTICIPATION_ELIGIBILITY_CACHE_TTL_SECONDS)
+    );
+}
+
+/// Test that `simulate_participation` estimates the SNS tokens and the neuron basket
+/// that a direct participant would receive.
+#[tokio::test]
+async fn test_simulate_participation() {
+    let params = params();
+    let mut swap = Swap::new(init());
+    let simulate = |swap: &Swap, amount_icp_e8s: u64| {
+        swap.simulate_participation(&SimulateParticipationRequest { amount_icp_e8s })
+    };
+
+    // Nothing can be estimated before the swap is open.
+    assert_eq!(
+        simulate(&swap, 300 * E8),
+        SimulateParticipationResponse::default()
+    );
+
+    open_swap(&mut swap, &params).await;
+    swap.direct_participation_icp_e8s = Some(700 * E8);
+
+    // 300 out of 1000 ICP in total buy 30% of the SNS tokens, split into a basket of 3 neurons.
+    let dissolve_delay_interval_seconds = params
+        .neuron_basket_construction_parameters
+        .as_ref()
+        .unwrap()
+        .dissolve_delay_interval_seconds;
+    let simulated_neuron = |i: u64| simulate_participation_response::SimulatedNeuron {
+        amount_e8s: Some(100_000 * E8),
+        dissolve_delay_seconds: Some(i * dissolve_delay_interval_seconds),
+    };
+    assert_eq!(
+        simulate(&swap, 300 * E8),
+        SimulateParticipationResponse {
+            accepted_amount_icp_e8s: Some(300 * E8),
+            estimated_sns_token_e8s: Some(300_000 * E8),
+            neuron_basket: vec![
+                simulated_neuron(0),
+                simulated_neuron(1),
+                simulated_neuron(2)
+            ],
+            current_total_participation_e8s: Some(700 * E8),
+            max_total_participation_e8s: Some(params.max_icp_e8s),
+        }
+    );
+
+    // At most the maximum per participant is accepted.
+    let response = simulate(&swap, params.max_participant_icp_e8s + E8);
+    assert_eq!(
+        response.accepted_amount_icp_e8s,
+        Some(params.max_participant_icp_e8s)
+    );
+
+    // Nothing is accepted below the minimum per participant.
+    let response = simulate(&swap, params.min_participant_icp_e8s - 1);
+    assert_eq!(response.accepted_amount_icp_e8s, Some(0));
+    assert_eq!(response.estimated_sns_token_e8s, Some(0));
+    assert_eq!(response.neuron_basket, vec![]);
+    assert_eq!(
+        simulate(&swap, params.min_participant_icp_e8s).accepted_amount_icp_e8s,
+        Some(params.min_participant_icp_e8s)
+    );
+
+    // Only the remaining capacity is accepted.
+    swap.direct_participation_icp_e8s = Some(params.max_icp_e8s - 500 * E8);
+    let response = simulate(&swap, 1_000 * E8);
+    assert_eq!(response.accepted_amount_icp_e8s, Some(500 * E8));
+    assert_eq!(response.estimated_sns_token_e8s, Some(500 * E8));
+
+    // Nothing is accepted if the remaining capacity is below the minimum per participant.
+    swap.direct_participation_icp_e8s =
+        Some(params.max_icp_e8s - params.min_participant_icp_e8s + 1);
+    let response = simulate(&swap, 1_000 * E8);
+    assert_eq!(response.accepted_amount_icp_e8s, Some(0));
+    assert_eq!(response.estimated_sns_token_e8s, Some(0));
+}
+
+/// Test that `validate_open_sale_params` reports all violated invariants of
+/// the parameters, including the due timestamp, without opening the swap.
+#[test]
+fn test_validate_open_sale_params() {
+    let swap = Swap::new(init());
+    let violated_fields = |params: Option<Params>| -> Vec<Option<String>> {
+        swap.validate_open_sale_params(
+            START_TIMESTAMP_SECONDS,
+            &ValidateOpenSaleParamsRequest { params },
+        )
+        .violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect()
+    };
+
+    assert_eq!(
+        violated_fields(Some(params())),
+        Vec::<Option<String>>::new()
+    );
+    assert_eq!(violated_fields(None), vec![None]);
+
+    let params = Params {
+        swap_due_timestamp_seconds: START_TIMESTAMP_SECONDS + 1,
+        min_participants: 0,
+        max_participant_icp_e8s: params().min_participant_icp_e8s - 1,
+        neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+            count: 1,
+            dissolve_delay_interval_seconds: 0,
+        }),
+        ..params()
+    };
+    assert_eq!(
+        violated_fields(Some(params)),
+        [
+            "swap_due_timestamp_seconds",
+            "min_participants",
+            "max_participant_icp_e8s",
+            "neuron_basket_construction_parameters",
+            "neuron_basket_construction_parameters",
+        ]
+        .map(|field| Some(field.to_string()))
+    );
+    // Nothing was changed.
+    assert_eq!(swap.lifecycle(), Pending);
+    assert_eq!(swap.params, None);
+}
+
 /// Test that the get_state API bounds the dynamic data sources returned in the
 /// GetStateResponse.
 #[test]
@@ -4836,7 +5532,279 @@ async fn test_finalize_swap_abort_sets_amount_transferred_and_fees_correctly() {
                 transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS + 10,
                 amount_transferred_e8s: Some(50 * E8 - DEFAULT_TRANSFER_FEE.get_e8s()),
                 transfer_fee_paid_e8s: Some(DEFAULT_TRANSFER_FEE.get_e8s())
-            })
+            }),
+            residual_icp_refund: None,
+            confirmation_text: None,
         }
     );
 }
+
+/// Test that the ICP left in the subaccounts of buyers is refunded in batches once
+/// their committed ICP has been swept, and that failed refunds are retried in the
+/// next round.
+#[tokio::test]
+async fn test_try_refund_residual_icp() {
+    let swept = || BuyerState {
+        icp: Some(TransferableAmount {
+            amount_e8s: E8,
+            transfer_start_timestamp_seconds: END_TIMESTAMP_SECONDS,
+            transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS + 1,
+            ..Default::default()
+        }),
+        residual_icp_refund: None,
+        confirmation_text: None,
+    };
+    let mut buyers: Vec<PrincipalId> = (1..=3).map(PrincipalId::new_user_test_id).collect();
+    buyers.sort_by_key(|buyer| buyer.to_string());
+    let (refunded, failed, unswept) = (buyers[0], buyers[1], buyers[2]);
+    let mut swap = Swap {
+        lifecycle: Aborted as i32,
+        init: Some(init()),
+        params: Some(params()),
+        buyers: btreemap! {
+            refunded.to_string() => swept(),
+            failed.to_string() => swept(),
+            unswept.to_string() => BuyerState::new(E8),
+        },
+        ..Default::default()
+    };
+    let residual_e8s = 3 * E8;
+
+    // The first batch refunds one buyer, and fails to refund the other.
+    let ledger = mock_stub(
+        [
+            get_transfer_and_account_balance_mock_ledger(
+                &residual_e8s,
+                &refunded,
+                &refunded,
+                false,
+            ),
+            get_transfer_and_account_balance_mock_ledger(&residual_e8s, &failed, &failed, true),
+        ]
+        .concat(),
+    );
+    let result = swap
+        .try_refund_residual_icp(now_fn, SWAP_CANISTER_ID, &ledger, 2)
+        .await;
+    assert_eq!(
+        result,
+        Some(SweepResult {
+            success: 1,
+            failure: 1,
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        swap.residual_icp_refund_next_buyer,
+        Some(unswept.to_string())
+    );
+
+    // The second batch completes the round without calling the ledger.
+    let result = swap
+        .try_refund_residual_icp(now_fn, SWAP_CANISTER_ID, &mock_stub(vec![]), 2)
+        .await;
+    assert_eq!(
+        result,
+        Some(SweepResult {
+            skipped: 1,
+            ..Default::default()
+        })
+    );
+    assert_eq!(swap.residual_icp_refund_next_buyer, None);
+    assert_eq!(
+        swap.residual_icp_refund_last_completion_timestamp_seconds,
+        Some(now_fn(false))
+    );
+
+    // No new round starts before the interval has passed.
+    let result = swap
+        .try_refund_residual_icp(now_fn, SWAP_CANISTER_ID, &mock_stub(vec![]), 2)
+        .await;
+    assert_eq!(result, None);
+
+    let refund_of = |swap: &Swap, buyer: &PrincipalId| {
+        swap.get_buyer_state(&GetBuyerStateRequest {
+            principal_id: Some(*buyer),
+        })
+        .buyer_state
+        .unwrap()
+        .residual_icp_refund
+    };
+    assert_eq!(
+        refund_of(&swap, &refunded),
+        Some(ResidualIcpRefund {
+            status: Some(residual_icp_refund::Status::Refunded as i32),
+            timestamp_seconds: now_fn(false),
+            amount_refunded_e8s: Some(residual_e8s - DEFAULT_TRANSFER_FEE.get_e8s()),
+            block_height: Some(100),
+            error_message: None,
+        })
+    );
+    assert_eq!(
+        refund_of(&swap, &failed).unwrap().status(),
+        residual_icp_refund::Status::Failed
+    );
+    assert_eq!(refund_of(&swap, &unswept), None);
+
+    // The next round only retries the failed refund.
+    swap.residual_icp_refund_last_completion_timestamp_seconds =
+        Some(now_fn(false) - RESIDUAL_ICP_REFUND_INTERVAL_SECONDS);
+    let ledger = mock_stub(get_transfer_and_account_balance_mock_ledger(
+        &residual_e8s,
+        &failed,
+        &failed,
+        false,
+    ));
+    let result = swap
+        .try_refund_residual_icp(now_fn, SWAP_CANISTER_ID, &ledger, 10)
+        .await;
+    assert_eq!(
+        result,
+        Some(SweepResult {
+            success: 1,
+            skipped: 2,
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        refund_of(&swap, &failed).unwrap().status(),
+        residual_icp_refund::Status::Refunded
+    );
+}
+
+fn create_open_swap_for_amendment() -> Swap {
+    Swap {
+        lifecycle: Open as i32,
+        init: Some(init()),
+        params: Some(params()),
+        decentralization_sale_open_timestamp_seconds: Some(START_TIMESTAMP_SECONDS),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_amend_open_sale_params_happy() {
+    let mut swap = create_open_swap_for_amendment();
+    let now = START_TIMESTAMP_SECONDS + SECONDS_PER_DAY;
+    let new_due = END_TIMESTAMP_SECONDS + 7 * SECONDS_PER_DAY;
+    let new_max_icp_e8s = 2 * params().max_icp_e8s;
+
+    let result = swap.amend_open_sale_params(
+        NNS_GOVERNANCE_CANISTER_ID.get(),
+        now,
+        AmendOpenSaleParamsRequest {
+            swap_due_timestamp_seconds: Some(new_due),
+            max_icp_e8s: None,
+            proposal_id: Some(42),
+        },
+    );
+    assert_eq!(result, Ok(AmendOpenSaleParamsResponse {}));
+
+    let result = swap.amend_open_sale_params(
+        NNS_GOVERNANCE_CANISTER_ID.get(),
+        now + 1,
+        AmendOpenSaleParamsRequest {
+            swap_due_timestamp_seconds: None,
+            max_icp_e8s: Some(new_max_icp_e8s),
+            proposal_id: Some(43),
+        },
+    );
+    assert_eq!(result, Ok(AmendOpenSaleParamsResponse {}));
+
+    // The swap is no longer due at the original deadline.
+    assert!(!swap.swap_due(END_TIMESTAMP_SECONDS));
+    assert!(swap.swap_due(new_due));
+
+    assert_eq!(
+        swap.get_sale_parameters(&GetSaleParametersRequest {}),
+        GetSaleParametersResponse {
+            params: Some(Params {
+                swap_due_timestamp_seconds: new_due,
+                max_icp_e8s: new_max_icp_e8s,
+                ..params()
+            }),
+            amendments: vec![
+                SaleParamsAmendment {
+                    timestamp_seconds: now,
+                    proposal_id: Some(42),
+                    old_swap_due_timestamp_seconds: Some(END_TIMESTAMP_SECONDS),
+                    new_swap_due_timestamp_seconds: Some(new_due),
+                    old_max_icp_e8s: None,
+                    new_max_icp_e8s: None,
+                },
+                SaleParamsAmendment {
+                    timestamp_seconds: now + 1,
+                    proposal_id: Some(43),
+                    old_swap_due_timestamp_seconds: None,
+                    new_swap_due_timestamp_seconds: None,
+                    old_max_icp_e8s: Some(params().max_icp_e8s),
+                    new_max_icp_e8s: Some(new_max_icp_e8s),
+                },
+            ],
+        },
+    );
+}
+
+#[test]
+fn test_amend_open_sale_params_rejects_invalid_amendments() {
+    let now = START_TIMESTAMP_SECONDS + SECONDS_PER_DAY;
+    let extend = AmendOpenSaleParamsRequest {
+        swap_due_timestamp_seconds: Some(END_TIMESTAMP_SECONDS + SECONDS_PER_DAY),
+        max_icp_e8s: None,
+        proposal_id: None,
+    };
+
+    // Only NNS governance may amend the swap.
+    let mut swap = create_open_swap_for_amendment();
+    assert_is_err!(swap.amend_open_sale_params(*TEST_USER1_PRINCIPAL, now, extend.clone()));
+
+    // The swap must be open and not yet due.
+    for lifecycle in [Pending, Committed, Aborted] {
+        let mut swap = Swap {
+            lifecycle: lifecycle as i32,
+            ..create_open_swap_for_amendment()
+        };
+        assert_is_err!(swap.amend_open_sale_params(
+            NNS_GOVERNANCE_CANISTER_ID.get(),
+            now,
+            extend.clone()
+        ));
+    }
+    assert_is_err!(swap.amend_open_sale_params(
+        NNS_GOVERNANCE_CANISTER_ID.get(),
+        END_TIMESTAMP_SECONDS,
+        extend.clone()
+    ));
+
+    let invalid_requests = vec![
+        // Nothing to amend.
+        AmendOpenSaleParamsRequest {
+            swap_due_timestamp_seconds: None,
+            max_icp_e8s: None,
+            proposal_id: None,
+        },
+        // The deadline can only be extended.
+        AmendOpenSaleParamsRequest {
+            swap_due_timestamp_seconds: Some(END_TIMESTAMP_SECONDS - 1),
+            ..extend.clone()
+        },
+        // The swap cannot become longer than the maximum sale duration.
+        AmendOpenSaleParamsRequest {
+            swap_due_timestamp_seconds: Some(START_TIMESTAMP_SECONDS + 91 * SECONDS_PER_DAY),
+            ..extend.clone()
+        },
+        // The maximum can only be raised.
+        AmendOpenSaleParamsRequest {
+            swap_due_timestamp_seconds: None,
+            max_icp_e8s: Some(params().max_icp_e8s - 1),
+            proposal_id: None,
+        },
+    ];
+    for request in invalid_requests {
+        assert_is_err!(swap.amend_open_sale_params(NNS_GOVERNANCE_CANISTER_ID.get(), now, request));
+    }
+
+    // Nothing was changed by the rejected amendments.
+    assert_eq!(swap.params, Some(params()));
+    assert!(swap.sale_params_amendments.is_empty());
+}
@@ -138,6 +138,8 @@ impl SnsTestsInitPayloadBuilder {
                 controller_id: CanisterId::from_u64(0).into(),
                 cycles_for_archive_creation: Some(0),
                 max_transactions_per_response: None,
+                more_controller_ids: None,
+                archive_created_callback: None,
             })
             .with_transfer_fee(DEFAULT_TRANSFER_FEE)
             .build();
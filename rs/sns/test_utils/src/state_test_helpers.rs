@@ -550,6 +550,7 @@ pub fn new_sale_ticket(
     let args = Encode!(&swap_pb::NewSaleTicketRequest {
         amount_icp_e8s,
         subaccount,
+        confirmation_text: None,
     })
     .unwrap();
     let res = env
@@ -0,0 +1,67 @@
+//! Collects the output of `ic0.debug_print`.
+//!
+//! If `canister_debug_print_records` is enabled, the system API emits a record for every debug
+//! print on the logger of the execution environment. A state machine capturing canister logs
+//! adds a [DebugPrintDrain] to its own logger, so the records of different state machines never
+//! mix and executions need no coordination.
+
+use crate::{CanisterLogRecord, MAX_CANISTER_LOG_RECORDS};
+use ic_system_api::{DEBUG_PRINT_CANISTER_ID_KEY, DEBUG_PRINT_TIME_KEY};
+use ic_types::{CanisterId, Time};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// The debug print records of a state machine, oldest first.
+pub(crate) type CanisterLogs = Arc<Mutex<VecDeque<CanisterLogRecord>>>;
+
+/// A drain that keeps the debug print records and ignores everything else.
+pub(crate) struct DebugPrintDrain {
+    pub logs: CanisterLogs,
+}
+
+impl slog::Drain for DebugPrintDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &slog::Record, _values: &slog::OwnedKVList) -> Result<(), slog::Never> {
+        let mut fields = DebugPrintFields::default();
+        // `DebugPrintFields` never fails to serialize a value.
+        let _ = slog::KV::serialize(&record.kv(), record, &mut fields);
+        if let (Some(canister_id), Some(time)) = (fields.canister_id, fields.time) {
+            let mut logs = self.logs.lock().unwrap();
+            if logs.len() == MAX_CANISTER_LOG_RECORDS {
+                logs.pop_front();
+            }
+            logs.push_back(CanisterLogRecord {
+                canister_id,
+                time,
+                message: record.msg().to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct DebugPrintFields {
+    canister_id: Option<CanisterId>,
+    time: Option<Time>,
+}
+
+impl slog::Serializer for DebugPrintFields {
+    fn emit_arguments(&mut self, key: slog::Key, val: &fmt::Arguments) -> slog::Result {
+        if key == DEBUG_PRINT_CANISTER_ID_KEY {
+            self.canister_id = CanisterId::from_str(&val.to_string()).ok();
+        }
+        Ok(())
+    }
+
+    fn emit_u64(&mut self, key: slog::Key, val: u64) -> slog::Result {
+        if key == DEBUG_PRINT_TIME_KEY {
+            self.time = Some(Time::from_nanos_since_unix_epoch(val));
+        }
+        Ok(())
+    }
+}
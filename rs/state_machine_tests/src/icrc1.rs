@@ -0,0 +1,132 @@
+//! Helpers for tests that interact with ICRC-1 ledgers (including the ICP
+//! ledger, which implements ICRC-1) running on a [`StateMachine`].
+
+use crate::StateMachine;
+use candid::{CandidType, Decode, Encode, Nat};
+use ic_types::CanisterId;
+use icrc_ledger_types::icrc1::account::Account;
+use num_traits::ToPrimitive;
+use std::collections::BTreeMap;
+
+/// Builder for the initial balances of a ledger.
+///
+/// Amounts added for the same account accumulate, so the resulting balances
+/// can be compared against the ledger with [`assert_balances`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InitialBalances(BTreeMap<Account, u64>);
+
+impl InitialBalances {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `amount` to the initial balance of `account`.
+    pub fn with(mut self, account: impl Into<Account>, amount: u64) -> Self {
+        let balance = self.0.entry(account.into()).or_default();
+        *balance = balance
+            .checked_add(amount)
+            .expect("initial balance overflows u64");
+        self
+    }
+
+    /// Returns the sum of all initial balances.
+    pub fn total(&self) -> u64 {
+        self.0.values().sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Account, &u64)> {
+        self.0.iter()
+    }
+
+    /// Returns the balances in the format of the `initial_balances` field of
+    /// the ICRC-1 ledger's init arguments.
+    pub fn to_icrc1_initial_balances(&self) -> Vec<(Account, Nat)> {
+        self.0
+            .iter()
+            .map(|(account, amount)| (*account, Nat::from(*amount)))
+            .collect()
+    }
+}
+
+impl IntoIterator for InitialBalances {
+    type Item = (Account, u64);
+    type IntoIter = std::collections::btree_map::IntoIter<Account, u64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Creates a canister and installs the ledger `wasm` in it, passing
+/// `init_arg` (e.g., an ICRC-1 `LedgerArgument` or an ICP ledger init
+/// payload) as the Candid-encoded install argument.
+pub fn install_icrc1_ledger(
+    env: &StateMachine,
+    wasm: Vec<u8>,
+    init_arg: &impl CandidType,
+) -> CanisterId {
+    env.install_canister(wasm, Encode!(init_arg).unwrap(), None)
+        .expect("failed to install the ledger")
+}
+
+/// Same as [`install_icrc1_ledger`], but installs the ledger in the existing,
+/// empty canister `canister_id`.
+pub fn install_icrc1_ledger_at(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    wasm: Vec<u8>,
+    init_arg: &impl CandidType,
+) {
+    env.install_existing_canister(canister_id, wasm, Encode!(init_arg).unwrap())
+        .expect("failed to install the ledger")
+}
+
+/// Returns the balance of `account` on the ledger `ledger_id`.
+pub fn icrc1_balance_of(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    account: impl Into<Account>,
+) -> u64 {
+    Decode!(
+        &env.query(
+            ledger_id,
+            "icrc1_balance_of",
+            Encode!(&account.into()).unwrap()
+        )
+        .expect("failed to query balance")
+        .bytes(),
+        Nat
+    )
+    .expect("failed to decode icrc1_balance_of response")
+    .0
+    .to_u64()
+    .expect("balance does not fit into u64")
+}
+
+/// Asserts that the accounts have the expected balances on the ledger
+/// `ledger_id`. All mismatches are reported at once.
+pub fn assert_balances<A: Into<Account>>(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    expected_balances: impl IntoIterator<Item = (A, u64)>,
+) {
+    let mismatches: Vec<String> = expected_balances
+        .into_iter()
+        .filter_map(|(account, expected)| {
+            let account = account.into();
+            let actual = icrc1_balance_of(env, ledger_id, account);
+            (actual != expected).then(|| {
+                format!(
+                    "{}: expected balance {}, actual balance {}",
+                    account, expected, actual
+                )
+            })
+        })
+        .collect();
+    assert!(
+        mismatches.is_empty(),
+        "unexpected balances on ledger {}:\n{}",
+        ledger_id,
+        mismatches.join("\n")
+    );
+}
@@ -1,4 +1,5 @@
 use core::sync::atomic::Ordering;
+use debug_print::{CanisterLogs, DebugPrintDrain};
 use ic_config::flag_status::FlagStatus;
 use ic_config::{execution_environment::Config as HypervisorConfig, subnet_config::SubnetConfig};
 use ic_constants::{MAX_INGRESS_TTL, PERMITTED_DRIFT, SMALL_APP_SUBNET_MAX_SIZE};
@@ -26,7 +27,9 @@ use ic_interfaces::{
     validation::ValidationResult,
 };
 use ic_interfaces_certified_stream_store::{CertifiedStreamStore, EncodeStreamError};
-use ic_interfaces_registry::RegistryClient;
+use ic_interfaces_registry::{
+    RegistryClient, RegistryDataProvider, RegistryTransportRecord, ZERO_REGISTRY_VERSION,
+};
 use ic_interfaces_state_manager::{CertificationScope, StateHashError, StateManager, StateReader};
 use ic_logger::ReplicaLogger;
 use ic_messaging::SyncMessageRouting;
@@ -45,8 +48,9 @@ use ic_registry_client_helpers::subnet::SubnetListRegistry;
 use ic_registry_keys::{
     make_canister_migrations_record_key, make_crypto_node_key, make_ecdsa_signing_subnet_list_key,
     make_node_record_key, make_provisional_whitelist_record_key, make_routing_table_record_key,
-    ROOT_SUBNET_ID_KEY,
+    make_subnet_record_key, ROOT_SUBNET_ID_KEY,
 };
+use ic_registry_local_store::{Changelog, KeyMutation, LocalStoreImpl, LocalStoreWriter};
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{
@@ -106,13 +110,16 @@ use std::io::stderr;
 use std::path::Path;
 use std::str::FromStr;
 use std::string::ToString;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant, SystemTime};
 use std::{collections::BTreeMap, convert::TryFrom};
 use std::{fmt, io};
 use tempfile::TempDir;
 use tokio::runtime::Runtime;
 
+mod debug_print;
+pub mod icrc1;
+pub mod network;
 #[cfg(test)]
 mod tests;
 
@@ -255,7 +262,18 @@ fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
     ser.into_inner()
 }
 
-fn replica_logger() -> ReplicaLogger {
+/// A message printed by a canister via `ic0.debug_print`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanisterLogRecord {
+    pub canister_id: CanisterId,
+    pub time: Time,
+    pub message: String,
+}
+
+/// The number of debug print records a `StateMachine` keeps before dropping the oldest ones.
+const MAX_CANISTER_LOG_RECORDS: usize = 10_000;
+
+fn replica_logger(canister_logs: Option<CanisterLogs>) -> ReplicaLogger {
     use slog::Drain;
     let log_level = std::env::var("RUST_LOG")
         .ok()
@@ -272,7 +290,13 @@ fn replica_logger() -> ReplicaLogger {
         .build()
         .filter_level(log_level)
         .fuse();
-    let logger = slog::Logger::root(drain, slog::o!());
+    let logger = match canister_logs {
+        Some(logs) => slog::Logger::root(
+            slog::Duplicate::new(drain, DebugPrintDrain { logs }).fuse(),
+            slog::o!(),
+        ),
+        None => slog::Logger::root(drain, slog::o!()),
+    };
     logger.into()
 }
 
@@ -299,6 +323,8 @@ pub struct StateMachine {
     public_key: ThresholdSigPublicKey,
     secret_key: SecretKeyBytes,
     ecdsa_secret_key: PrivateKey,
+    /// Combined with the batch number to obtain the randomness of each batch.
+    randomness_seed: [u8; 32],
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
     registry_client: Arc<FakeRegistryClient>,
     pub state_manager: Arc<StateManagerImpl>,
@@ -311,7 +337,8 @@ pub struct StateMachine {
     checkpoints_enabled: std::sync::atomic::AtomicBool,
     nonce: std::sync::atomic::AtomicU64,
     time: std::sync::atomic::AtomicU64,
-    ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    ecdsa_subnet_public_keys: RwLock<BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>>,
+    canister_logs: CanisterLogs,
 }
 
 impl Default for StateMachine {
@@ -344,6 +371,9 @@ pub struct StateMachineBuilder {
     ecdsa_keys: Vec<EcdsaKeyId>,
     features: SubnetFeatures,
     runtime: Option<Arc<Runtime>>,
+    registry_data_provider: Option<Arc<ProtoRegistryDataProvider>>,
+    seed: Option<[u8; 32]>,
+    capture_canister_logs: bool,
 }
 
 impl StateMachineBuilder {
@@ -370,6 +400,9 @@ impl StateMachineBuilder {
                 ..SubnetFeatures::default()
             },
             runtime: None,
+            registry_data_provider: None,
+            seed: None,
+            capture_canister_logs: false,
         }
     }
 
@@ -377,11 +410,11 @@ impl StateMachineBuilder {
         Self { state_dir, ..self }
     }
 
-    fn with_nonce(self, nonce: u64) -> Self {
+    pub fn with_nonce(self, nonce: u64) -> Self {
         Self { nonce, ..self }
     }
 
-    fn with_time(self, time: Time) -> Self {
+    pub fn with_time(self, time: Time) -> Self {
         Self { time, ..self }
     }
 
@@ -481,6 +514,36 @@ impl StateMachineBuilder {
         }
     }
 
+    /// Uses the registry stored at the given path (see
+    /// [StateMachine::write_registry_to_file]) instead of building a fresh
+    /// one from the builder configuration.
+    pub fn with_registry_from_file(self, path: impl AsRef<Path>) -> Self {
+        Self {
+            registry_data_provider: Some(Arc::new(ProtoRegistryDataProvider::load_from_file(path))),
+            ..self
+        }
+    }
+
+    /// Derives the randomness of every batch (and thus the result of `raw_rand`) and
+    /// the secret key backing all ECDSA keys from the given seed. State machines built
+    /// with the same seed produce the same randomness and signatures. Without a seed,
+    /// fixed values are used.
+    pub fn with_seed(self, seed: [u8; 32]) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Records the messages canisters print via `ic0.debug_print`, see
+    /// [StateMachine::canister_log]. Canisters executed in a sandbox process are not recorded.
+    pub fn with_canister_logs(self) -> Self {
+        Self {
+            capture_canister_logs: true,
+            ..self
+        }
+    }
+
     pub fn build(self) -> StateMachine {
         StateMachine::setup_from_dir(
             self.state_dir,
@@ -502,6 +565,9 @@ impl StateMachineBuilder {
                     .expect("failed to create a tokio runtime")
                     .into()
             }),
+            self.registry_data_provider,
+            self.seed,
+            self.capture_canister_logs,
         )
     }
 }
@@ -543,8 +609,13 @@ impl StateMachine {
         ecdsa_keys: Vec<EcdsaKeyId>,
         features: SubnetFeatures,
         runtime: Arc<Runtime>,
+        registry_data_provider: Option<Arc<ProtoRegistryDataProvider>>,
+        seed: Option<[u8; 32]>,
+        capture_canister_logs: bool,
     ) -> Self {
-        let replica_logger = replica_logger();
+        let canister_logs = CanisterLogs::default();
+        let replica_logger =
+            replica_logger(capture_canister_logs.then(|| Arc::clone(&canister_logs)));
 
         let mut node_ids = vec![];
         for id in 0..subnet_size {
@@ -558,15 +629,23 @@ impl StateMachine {
             None => (SubnetConfig::new(subnet_type), HypervisorConfig::default()),
         };
 
-        let (registry_data_provider, registry_client) = make_nodes_registry(
-            nns_subnet_id,
-            subnet_id,
-            subnet_type,
-            routing_table,
-            &node_ids,
-            &ecdsa_keys,
-            features,
-        );
+        let (registry_data_provider, registry_client) = match registry_data_provider {
+            Some(data_provider) => {
+                let registry_client =
+                    Arc::new(FakeRegistryClient::new(Arc::clone(&data_provider) as _));
+                registry_client.update_to_latest_version();
+                (data_provider, registry_client)
+            }
+            None => make_nodes_registry(
+                nns_subnet_id,
+                subnet_id,
+                subnet_type,
+                routing_table,
+                &node_ids,
+                &ecdsa_keys,
+                features,
+            ),
+        };
 
         let sm_config = ic_config::state_manager::Config::new(state_dir.path().to_path_buf());
 
@@ -574,6 +653,12 @@ impl StateMachine {
             hypervisor_config.canister_sandboxing_flag = FlagStatus::Disabled;
             hypervisor_config.deterministic_time_slicing = FlagStatus::Disabled;
         }
+        if capture_canister_logs {
+            hypervisor_config
+                .embedders_config
+                .feature_flags
+                .canister_debug_print_records = FlagStatus::Enabled;
+        }
 
         let mut cycles_account_manager = CyclesAccountManager::new(
             subnet_config.scheduler_config.max_instructions_per_message,
@@ -656,17 +741,25 @@ impl StateMachine {
             hex::decode("fb7d1f5b82336bb65b82bf4f27776da4db71c1ef632c6a7c171c0cbfa2ea4920")
                 .unwrap();
 
-        let ecdsa_secret_key: PrivateKey =
-            PrivateKey::deserialize_sec1(private_key_bytes.as_slice()).unwrap();
+        let ecdsa_secret_key: PrivateKey = match seed {
+            Some(seed) => PrivateKey::generate_using_rng(
+                &mut Seed::from_bytes(&seed)
+                    .derive("ic-state-machine-tests-ecdsa-secret-key")
+                    .into_rng(),
+            ),
+            None => PrivateKey::deserialize_sec1(private_key_bytes.as_slice()).unwrap(),
+        };
 
         let mut ecdsa_subnet_public_keys = BTreeMap::new();
 
+        // All keys are backed by the same secret key, so that `ecdsa_public_key` agrees with
+        // the signatures produced in `tick`.
         for ecdsa_key in ecdsa_keys {
             ecdsa_subnet_public_keys.insert(
                 ecdsa_key,
                 MasterEcdsaPublicKey {
                     algorithm_id: AlgorithmId::EcdsaSecp256k1,
-                    public_key: b"master_ecdsa_public_key".to_vec(),
+                    public_key: ecdsa_secret_key.public_key().serialize_sec1(true),
                 },
             );
         }
@@ -687,6 +780,7 @@ impl StateMachine {
             secret_key: secret_key_bytes.get(0).unwrap().clone(),
             public_key,
             ecdsa_secret_key,
+            randomness_seed: seed.unwrap_or_default(),
             registry_data_provider,
             registry_client,
             state_manager,
@@ -701,7 +795,8 @@ impl StateMachine {
             checkpoints_enabled: std::sync::atomic::AtomicBool::new(checkpoints_enabled),
             nonce: std::sync::atomic::AtomicU64::new(nonce),
             time: std::sync::atomic::AtomicU64::new(time.as_nanos_since_unix_epoch()),
-            ecdsa_subnet_public_keys,
+            ecdsa_subnet_public_keys: RwLock::new(ecdsa_subnet_public_keys),
+            canister_logs,
         }
     }
 
@@ -858,14 +953,20 @@ impl StateMachine {
         }
     }
 
+    /// Returns the randomness of the given batch: the seed of the state machine
+    /// with the batch number mixed into its first bytes.
+    fn batch_randomness(&self, batch_number: Height) -> Randomness {
+        let mut seed = self.randomness_seed;
+        for (byte, batch_byte) in seed.iter_mut().zip(batch_number.get().to_le_bytes()) {
+            *byte ^= batch_byte;
+        }
+        Randomness::from(seed)
+    }
+
     /// Triggers a single round of execution with block payload as an input.
     pub fn execute_payload(&self, payload: PayloadBuilder) -> Height {
         let batch_number = self.message_routing.expected_batch_height();
 
-        let mut seed = [0u8; 32];
-        // use the batch number to seed randomness
-        seed[..8].copy_from_slice(batch_number.get().to_le_bytes().as_slice());
-
         let batch = Batch {
             batch_number,
             requires_full_state_hash: self.checkpoints_enabled.load(Ordering::Relaxed),
@@ -875,8 +976,8 @@ impl StateMachine {
                 bitcoin_adapter_responses: vec![],
                 query_stats: payload.query_stats,
             },
-            randomness: Randomness::from(seed),
-            ecdsa_subnet_public_keys: self.ecdsa_subnet_public_keys.clone(),
+            randomness: self.batch_randomness(batch_number),
+            ecdsa_subnet_public_keys: self.ecdsa_subnet_public_keys.read().unwrap().clone(),
             registry_version: self.registry_client.get_latest_version(),
             time: Time::from_nanos_since_unix_epoch(self.time.load(Ordering::Relaxed)),
             consensus_responses: payload.consensus_responses,
@@ -970,6 +1071,64 @@ impl StateMachine {
         SystemTime::UNIX_EPOCH + Duration::from_nanos(self.time.load(Ordering::Relaxed))
     }
 
+    /// Returns the messages the given canister printed via `ic0.debug_print`, oldest first.
+    /// Messages are only recorded by state machines built
+    /// [with canister logs](StateMachineBuilder::with_canister_logs), and only the most recent
+    /// records are kept.
+    pub fn canister_log(&self, canister_id: CanisterId) -> Vec<CanisterLogRecord> {
+        self.canister_logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.canister_id == canister_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the nonce that will be used for the next ingress message.
+    pub fn nonce(&self) -> u64 {
+        self.nonce.load(Ordering::Relaxed)
+    }
+
+    /// Writes the registry of this state machine to the given path, so that it
+    /// can be restored with [StateMachineBuilder::with_registry_from_file].
+    pub fn write_registry_to_file(&self, path: impl AsRef<Path>) {
+        self.registry_data_provider.write_to_file(path);
+    }
+
+    /// Writes the registry of this state machine to the given directory in the format of the
+    /// registry local store of a replica, which tools like `ic-replay` read.
+    pub fn write_registry_to_local_store(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let local_store = LocalStoreImpl::new(path);
+        let mut records = self
+            .registry_data_provider
+            .get_updates_since(ZERO_REGISTRY_VERSION)
+            .expect("Failed to read the registry");
+        records.sort_by_key(|record| record.version);
+        let mut changelog = Changelog::default();
+        for RegistryTransportRecord {
+            key,
+            version,
+            value,
+        } in records
+        {
+            let index = (version - ZERO_REGISTRY_VERSION).get() as usize - 1;
+            if changelog.len() <= index {
+                changelog.resize(index + 1, vec![]);
+            }
+            changelog[index].push(KeyMutation { key, value });
+        }
+        changelog
+            .into_iter()
+            .enumerate()
+            .try_for_each(|(i, entry)| {
+                local_store.store(
+                    ZERO_REGISTRY_VERSION + RegistryVersion::from(i as u64 + 1),
+                    entry,
+                )
+            })
+    }
+
     /// Advances the state machine time by the given amount.
     pub fn advance_time(&self, amount: Duration) {
         self.set_time(self.time() + amount);
@@ -1633,6 +1792,70 @@ impl StateMachine {
         assert_eq!(next_version, self.registry_client.get_latest_version());
     }
 
+    /// Enables the threshold ECDSA key `key_id` on this subnet, so that
+    /// `ecdsa_public_key` and `sign_with_ecdsa` requests for this key succeed.
+    ///
+    /// Like the keys passed to [`StateMachineBuilder::with_ecdsa_key`], the key
+    /// is backed by the state machine's fixed ECDSA secret key, so signatures
+    /// are deterministic and are produced in [`StateMachine::tick`].
+    pub fn enable_ecdsa_key(&self, key_id: EcdsaKeyId) {
+        use ic_registry_client_helpers::subnet::SubnetRegistry;
+
+        let last_version = self.registry_client.get_latest_version();
+        let next_version = last_version.increment();
+
+        let mut subnet_record = self
+            .registry_client
+            .get_subnet_record(self.subnet_id, last_version)
+            .expect("malformed subnet record")
+            .expect("missing subnet record");
+        let ecdsa_config = subnet_record.ecdsa_config.get_or_insert_with(|| {
+            EcdsaConfig {
+                quadruples_to_create_in_advance: 1,
+                key_ids: vec![],
+                max_queue_size: Some(DEFAULT_ECDSA_MAX_QUEUE_SIZE),
+                signature_request_timeout_ns: None,
+                idkg_key_rotation_period_ms: None,
+            }
+            .into()
+        });
+        let key_id_proto = ic_protobuf::registry::crypto::v1::EcdsaKeyId::from(&key_id);
+        if !ecdsa_config.key_ids.contains(&key_id_proto) {
+            ecdsa_config.key_ids.push(key_id_proto);
+        }
+        self.registry_data_provider
+            .add(
+                &make_subnet_record_key(self.subnet_id),
+                next_version,
+                Some(subnet_record),
+            )
+            .unwrap();
+
+        self.registry_data_provider
+            .add(
+                &make_ecdsa_signing_subnet_list_key(&key_id),
+                next_version,
+                Some(EcdsaSigningSubnetList {
+                    subnets: vec![SubnetIdProto {
+                        principal_id: Some(PrincipalIdIdProto {
+                            raw: self.subnet_id.get_ref().to_vec(),
+                        }),
+                    }],
+                }),
+            )
+            .unwrap();
+        self.registry_client.update_to_latest_version();
+        assert_eq!(next_version, self.registry_client.get_latest_version());
+
+        self.ecdsa_subnet_public_keys.write().unwrap().insert(
+            key_id,
+            MasterEcdsaPublicKey {
+                algorithm_id: AlgorithmId::EcdsaSecp256k1,
+                public_key: self.ecdsa_secret_key.public_key().serialize_sec1(true),
+            },
+        );
+    }
+
     /// Returns the subnet id of this state machine.
     pub fn get_subnet_id(&self) -> SubnetId {
         self.subnet_id
@@ -1853,6 +2076,30 @@ impl StateMachine {
         balance
     }
 
+    /// Sets the cycle balance of the specified canister to the given amount.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the specified canister does not exist.
+    pub fn set_cycles(&self, canister_id: CanisterId, amount: u128) {
+        let (height, mut state) = self.state_manager.take_tip();
+        let canister_state = state
+            .canister_state_mut(&canister_id)
+            .unwrap_or_else(|| panic!("Canister {} not found", canister_id));
+        let balance = canister_state.system_state.balance().get();
+        if amount > balance {
+            canister_state
+                .system_state
+                .add_cycles(Cycles::from(amount - balance), CyclesUseCase::NonConsumed);
+        } else {
+            canister_state
+                .system_state
+                .remove_cycles(Cycles::from(balance - amount), CyclesUseCase::NonConsumed);
+        }
+        self.state_manager
+            .commit_and_certify(state, height.increment(), CertificationScope::Full);
+    }
+
     /// Returns sign with ECDSA contexts from internal subnet call context manager.
     pub fn sign_with_ecdsa_contexts(&self) -> BTreeMap<CallbackId, SignWithEcdsaContext> {
         let state = self.state_manager.get_latest_state().take();
@@ -0,0 +1,275 @@
+//! A network of [`StateMachine`]s, each simulating its own subnet, that
+//! exchange XNet streams with each other.
+//!
+//! Streams are routed between the subnets on every [`StateMachineNetwork::tick`].
+//! The latency of each directed link can be configured in rounds, links can be
+//! paused and resumed, and the order in which subnets execute their rounds can
+//! be chosen freely, which allows tests to exercise interleavings of
+//! cross-subnet messages deterministically.
+
+use crate::{PayloadBuilder, StateMachine, StateMachineBuilder};
+use ic_interfaces_certified_stream_store::EncodeStreamError;
+use ic_registry_routing_table::{routing_table_insert_subnet, RoutingTable};
+use ic_replicated_state::replicated_state::ReplicatedStateMessageRouting;
+use ic_types::batch::XNetPayload;
+use ic_types::{xnet::StreamIndex, SubnetId};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// A directed link from a sending subnet to a receiving subnet.
+type Link = (SubnetId, SubnetId);
+
+/// A set of [`StateMachine`]s sharing a routing table, with XNet streams
+/// routed between them.
+pub struct StateMachineNetwork {
+    subnets: BTreeMap<SubnetId, Arc<StateMachine>>,
+    /// The order in which [`Self::tick`] executes the subnets.
+    order: Vec<SubnetId>,
+    /// The latency (in network ticks) of the links with non-zero latency.
+    latencies: BTreeMap<Link, usize>,
+    paused: BTreeSet<Link>,
+    /// For each link, the end of the sender's stream after each of the most
+    /// recent ticks, used to hold back messages on links with latency.
+    stream_ends: Mutex<BTreeMap<Link, VecDeque<StreamIndex>>>,
+}
+
+impl StateMachineNetwork {
+    /// Builds one [`StateMachine`] from each of the given builders. All state
+    /// machines share a routing table assigning each subnet its default
+    /// canister range, and the first subnet is used as the NNS subnet.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the builders are empty or if two of them have
+    /// the same subnet ID (see [`StateMachineBuilder::with_subnet_id`]).
+    pub fn new(builders: Vec<StateMachineBuilder>) -> Self {
+        let order: Vec<SubnetId> = builders.iter().map(|b| b.subnet_id).collect();
+        let nns_subnet_id = *order
+            .first()
+            .expect("the network needs at least one subnet");
+        assert_eq!(
+            order.iter().collect::<BTreeSet<_>>().len(),
+            order.len(),
+            "subnet IDs must be unique: {:?}",
+            order
+        );
+
+        let mut routing_table = RoutingTable::new();
+        for subnet_id in &order {
+            routing_table_insert_subnet(&mut routing_table, *subnet_id)
+                .expect("failed to update the routing table");
+        }
+
+        let subnets = builders
+            .into_iter()
+            .map(|builder| {
+                let env = builder
+                    .with_routing_table(routing_table.clone())
+                    .with_nns_subnet_id(nns_subnet_id)
+                    .build();
+                (env.get_subnet_id(), Arc::new(env))
+            })
+            .collect();
+
+        Self {
+            subnets,
+            order,
+            latencies: BTreeMap::new(),
+            paused: BTreeSet::new(),
+            stream_ends: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the state machine of the given subnet.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the subnet is not part of the network.
+    pub fn get(&self, subnet_id: SubnetId) -> Arc<StateMachine> {
+        self.subnets
+            .get(&subnet_id)
+            .unwrap_or_else(|| panic!("subnet {} is not part of the network", subnet_id))
+            .clone()
+    }
+
+    /// Returns the IDs of all subnets in the network, in execution order.
+    pub fn subnet_ids(&self) -> Vec<SubnetId> {
+        self.order.clone()
+    }
+
+    /// Sets the order in which [`Self::tick`] executes the subnets. Subnets
+    /// missing from `order` do not execute any rounds (and thus do not induct
+    /// any messages) until the order is changed again.
+    pub fn set_order(&mut self, order: Vec<SubnetId>) {
+        for subnet_id in &order {
+            assert!(
+                self.subnets.contains_key(subnet_id),
+                "subnet {} is not part of the network",
+                subnet_id
+            );
+        }
+        self.order = order;
+    }
+
+    /// Sets the latency of the link from `from` to `to`: messages that `from`
+    /// added to its stream are only delivered to `to` `ticks` network ticks
+    /// later. A latency of zero (the default) delivers all messages in the
+    /// next round executed by `to`.
+    ///
+    /// Stream headers (and thus signals) are not delayed.
+    pub fn set_latency(&mut self, from: SubnetId, to: SubnetId, ticks: usize) {
+        if ticks == 0 {
+            self.latencies.remove(&(from, to));
+        } else {
+            self.latencies.insert((from, to), ticks);
+        }
+    }
+
+    /// Stops delivering anything (messages and signals) on the link from
+    /// `from` to `to`, until [`Self::resume`] is called.
+    pub fn pause(&mut self, from: SubnetId, to: SubnetId) {
+        self.paused.insert((from, to));
+    }
+
+    /// Resumes delivery on the link from `from` to `to`.
+    pub fn resume(&mut self, from: SubnetId, to: SubnetId) {
+        self.paused.remove(&(from, to));
+    }
+
+    /// Executes one round on every subnet, in the configured order. Each
+    /// subnet inducts the stream slices that all other subnets currently
+    /// have for it, subject to the latency and pausing of the links.
+    pub fn tick(&self) {
+        for subnet_id in &self.order {
+            self.tick_subnet(*subnet_id);
+        }
+        self.record_stream_ends();
+    }
+
+    /// Executes one round on the given subnet only, delivering the stream
+    /// slices that all other subnets currently have for it.
+    pub fn tick_subnet(&self, subnet_id: SubnetId) {
+        let env = self.get(subnet_id);
+        let payload = self.xnet_payload_for(&env);
+        env.execute_payload(PayloadBuilder::new().xnet_payload(payload));
+    }
+
+    /// Ticks the network until no subnet has pending messages, neither in its
+    /// canister or subnet queues nor in streams that have not been fully
+    /// inducted by the receiving subnet.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the network did not reach completion within
+    /// `max_ticks` ticks.
+    pub fn run_until_completion(&self, max_ticks: usize) {
+        for _tick in 0..max_ticks {
+            if self.is_idle() {
+                return;
+            }
+            self.tick();
+        }
+        if !self.is_idle() {
+            panic!(
+                "The network did not reach completion after {} ticks",
+                max_ticks
+            );
+        }
+    }
+
+    /// Returns true if no subnet has any messages left to process or route.
+    fn is_idle(&self) -> bool {
+        self.subnets.values().all(|env| {
+            let state = env.get_latest_state();
+            let queues_empty = !state
+                .canisters_iter()
+                .any(|canister| canister.has_input() || canister.has_output())
+                && !state.subnet_queues().has_input()
+                && !state.subnet_queues().has_output();
+            queues_empty
+                && state.streams().iter().all(|(remote_subnet_id, stream)| {
+                    match self.subnets.get(remote_subnet_id) {
+                        Some(remote) => {
+                            stream.messages_end()
+                                <= Self::expected_begin(remote, env.get_subnet_id())
+                        }
+                        // Streams to subnets outside of the network are never
+                        // delivered.
+                        None => true,
+                    }
+                })
+        })
+    }
+
+    /// Returns the index of the next message `receiver` expects from `sender`.
+    fn expected_begin(receiver: &StateMachine, sender: SubnetId) -> StreamIndex {
+        receiver
+            .get_latest_state()
+            .get_stream(&sender)
+            .map(|stream| stream.signals_end())
+            .unwrap_or_default()
+    }
+
+    /// Collects the stream slices from all other subnets to `receiver`.
+    fn xnet_payload_for(&self, receiver: &StateMachine) -> XNetPayload {
+        let receiver_id = receiver.get_subnet_id();
+        let stream_ends = self.stream_ends.lock().unwrap();
+        let mut payload = XNetPayload::default();
+        for (sender_id, sender) in &self.subnets {
+            let link = (*sender_id, receiver_id);
+            if *sender_id == receiver_id || self.paused.contains(&link) {
+                continue;
+            }
+
+            let begin = Self::expected_begin(receiver, *sender_id);
+            let msg_limit = self.latencies.get(&link).map(|latency| {
+                let delivered_end = stream_ends
+                    .get(&link)
+                    .and_then(|ends| ends.len().checked_sub(*latency).map(|i| ends[i]))
+                    .unwrap_or_default();
+                delivered_end.get().saturating_sub(begin.get()) as usize
+            });
+
+            match sender.generate_xnet_payload(
+                receiver_id,
+                Some(begin),
+                Some(begin),
+                msg_limit,
+                None,
+            ) {
+                Ok(slice) => payload.stream_slices.extend(slice.stream_slices),
+                Err(EncodeStreamError::NoStreamForSubnet(_)) => {}
+                Err(err) => panic!(
+                    "failed to encode the stream from {} to {}: {:?}",
+                    sender_id, receiver_id, err
+                ),
+            }
+        }
+        payload
+    }
+
+    /// Records the current end of every stream within the network, so that
+    /// links with latency can hold back messages added since.
+    fn record_stream_ends(&self) {
+        let mut stream_ends = self.stream_ends.lock().unwrap();
+        for (sender_id, sender) in &self.subnets {
+            let state = sender.get_latest_state();
+            for receiver_id in self.subnets.keys() {
+                let link = (*sender_id, *receiver_id);
+                let Some(latency) = self.latencies.get(&link) else {
+                    stream_ends.remove(&link);
+                    continue;
+                };
+                let end = state
+                    .get_stream(receiver_id)
+                    .map(|stream| stream.messages_end())
+                    .unwrap_or_default();
+                let ends = stream_ends.entry(link).or_default();
+                ends.push_back(end);
+                while ends.len() > *latency {
+                    ends.pop_front();
+                }
+            }
+        }
+    }
+}
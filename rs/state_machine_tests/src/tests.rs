@@ -122,3 +122,255 @@ fn public_derivation_path() {
         derived_public_key_bytes.derived_public_key
     );
 }
+
+#[test]
+fn enable_ecdsa_key_registers_signing_subnet_and_public_key() {
+    use crate::{EcdsaCurve, EcdsaKeyId, StateMachine};
+    use ic_interfaces_registry::RegistryClient;
+    use ic_registry_client_helpers::{ecdsa_keys::EcdsaKeysRegistry, subnet::SubnetRegistry};
+
+    let env = StateMachine::new();
+    let key_id = EcdsaKeyId {
+        curve: EcdsaCurve::Secp256k1,
+        name: "test_key_1".to_string(),
+    };
+    env.enable_ecdsa_key(key_id.clone());
+
+    let version = env.registry_client.get_latest_version();
+    let signing_subnets = env
+        .registry_client
+        .get_ecdsa_signing_subnets(version)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        signing_subnets.get(&key_id),
+        Some(&vec![env.get_subnet_id()])
+    );
+
+    let subnet_record = env
+        .registry_client
+        .get_subnet_record(env.get_subnet_id(), version)
+        .unwrap()
+        .unwrap();
+    assert!(subnet_record
+        .ecdsa_config
+        .unwrap()
+        .key_ids
+        .contains(&(&key_id).into()));
+
+    assert_eq!(
+        env.ecdsa_subnet_public_keys
+            .read()
+            .unwrap()
+            .get(&key_id)
+            .map(|key| key.public_key.clone()),
+        Some(env.ecdsa_secret_key.public_key().serialize_sec1(true))
+    );
+}
+
+#[test]
+fn seed_determines_randomness_and_ecdsa_key() {
+    use crate::{Height, StateMachineBuilder};
+
+    let public_key =
+        |env: &crate::StateMachine| env.ecdsa_secret_key.public_key().serialize_sec1(true);
+
+    let env = StateMachineBuilder::new().with_seed([1; 32]).build();
+    let same_seed = StateMachineBuilder::new().with_seed([1; 32]).build();
+    let other_seed = StateMachineBuilder::new().with_seed([2; 32]).build();
+    let unseeded = StateMachineBuilder::new().build();
+
+    for height in [Height::new(1), Height::new(2)] {
+        assert_eq!(
+            env.batch_randomness(height),
+            same_seed.batch_randomness(height)
+        );
+        assert_ne!(
+            env.batch_randomness(height),
+            other_seed.batch_randomness(height)
+        );
+    }
+    assert_ne!(
+        env.batch_randomness(Height::new(1)),
+        env.batch_randomness(Height::new(2))
+    );
+    assert_eq!(public_key(&env), public_key(&same_seed));
+    assert_ne!(public_key(&env), public_key(&other_seed));
+    assert_ne!(public_key(&env), public_key(&unseeded));
+}
+
+#[test]
+fn network_routes_calls_between_subnets() {
+    use crate::network::StateMachineNetwork;
+    use crate::{
+        IngressState, IngressStatus, PrincipalId, StateMachineBuilder, SubnetId, WasmResult,
+    };
+
+    // `ping` calls `pong` on the canister whose ID is passed as argument and
+    // replies with the response.
+    const PING_PONG_WAT: &str = r#"
+        (module
+          (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+          (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+          (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+          (import "ic0" "msg_reply" (func $msg_reply))
+          (import "ic0" "call_new"
+            (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+          (import "ic0" "call_perform" (func $call_perform (result i32)))
+          (func $ping
+            (call $msg_arg_data_copy (i32.const 0) (i32.const 0) (call $msg_arg_data_size))
+            (call $call_new
+              (i32.const 0) (call $msg_arg_data_size)
+              (i32.const 100) (i32.const 4)
+              (i32.const 0) (i32.const 0)
+              (i32.const 1) (i32.const 0))
+            (drop (call $call_perform)))
+          (func $pong
+            (call $msg_reply_data_append (i32.const 100) (i32.const 4))
+            (call $msg_reply))
+          (func $on_reply (param i32)
+            (call $msg_reply_data_append (i32.const 100) (i32.const 4))
+            (call $msg_reply))
+          (func $on_reject (param i32)
+            (call $msg_reply))
+          (table funcref (elem $on_reply $on_reject))
+          (memory 1)
+          (data (i32.const 100) "pong")
+          (export "canister_update ping" (func $ping))
+          (export "canister_update pong" (func $pong)))
+    "#;
+
+    let subnet_a = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+    let subnet_b = SubnetId::from(PrincipalId::new_subnet_test_id(2));
+    let mut network = StateMachineNetwork::new(vec![
+        StateMachineBuilder::new().with_subnet_id(subnet_a),
+        StateMachineBuilder::new().with_subnet_id(subnet_b),
+    ]);
+    let env_a = network.get(subnet_a);
+    let env_b = network.get(subnet_b);
+    let canister_a = env_a.install_canister_wat(PING_PONG_WAT, vec![], None);
+    let canister_b = env_b.install_canister_wat(PING_PONG_WAT, vec![], None);
+    assert_ne!(canister_a, canister_b);
+
+    let send_ping = || {
+        env_a.send_ingress(
+            PrincipalId::new_anonymous(),
+            canister_a,
+            "ping",
+            canister_b.get().to_vec(),
+        )
+    };
+    let is_completed = |msg_id| match env_a.ingress_status(msg_id) {
+        IngressStatus::Known {
+            state: IngressState::Completed(result),
+            ..
+        } => {
+            assert_eq!(result, WasmResult::Reply(b"pong".to_vec()));
+            true
+        }
+        _ => false,
+    };
+
+    // Messages are routed in both directions without any delay.
+    let msg_id = send_ping();
+    network.run_until_completion(100);
+    assert!(is_completed(&msg_id));
+
+    // Nothing is delivered while the link is paused.
+    network.pause(subnet_a, subnet_b);
+    let msg_id = send_ping();
+    for _ in 0..10 {
+        network.tick();
+    }
+    assert!(!is_completed(&msg_id));
+    network.resume(subnet_a, subnet_b);
+    network.run_until_completion(100);
+    assert!(is_completed(&msg_id));
+
+    // The request is held back for the configured latency.
+    network.set_latency(subnet_a, subnet_b, 5);
+    let msg_id = send_ping();
+    for _ in 0..5 {
+        network.tick();
+        assert!(!is_completed(&msg_id));
+    }
+    network.run_until_completion(100);
+    assert!(is_completed(&msg_id));
+}
+
+#[test]
+fn canister_log_records_debug_prints() {
+    use crate::{StateMachine, StateMachineBuilder};
+
+    const WAT: &str = r#"
+(module
+  (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+  (import "ic0" "msg_reply" (func $msg_reply))
+  (func $log
+    (call $debug_print (i32.const 0) (i32.const 5))
+    (call $msg_reply))
+  (memory $memory 1)
+  (data (i32.const 0) "hello")
+  (export "memory" (memory $memory))
+  (export "canister_update log" (func $log))
+  (export "canister_query log_query" (func $log))
+)"#;
+
+    let env = StateMachineBuilder::new().with_canister_logs().build();
+    let canister_id = env.install_canister_wat(WAT, vec![], None);
+    env.execute_ingress(canister_id, "log", vec![]).unwrap();
+    env.query(canister_id, "log_query", vec![]).unwrap();
+    let log = env.canister_log(canister_id);
+    assert_eq!(
+        log.iter()
+            .map(|record| record.message.as_str())
+            .collect::<Vec<_>>(),
+        vec!["hello", "hello"]
+    );
+    assert!(log.iter().all(|record| record.canister_id == canister_id));
+
+    let env = StateMachine::new();
+    let canister_id = env.install_canister_wat(WAT, vec![], None);
+    env.execute_ingress(canister_id, "log", vec![]).unwrap();
+    assert!(env.canister_log(canister_id).is_empty());
+}
+
+#[test]
+fn canister_logs_are_kept_per_state_machine() {
+    use crate::StateMachineBuilder;
+
+    const WAT: &str = r#"
+(module
+  (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+  (import "ic0" "msg_reply" (func $msg_reply))
+  (func $log
+    (call $debug_print (i32.const 0) (i32.const 5))
+    (call $msg_reply))
+  (memory $memory 1)
+  (data (i32.const 0) "hello")
+  (export "memory" (memory $memory))
+  (export "canister_update log" (func $log))
+)"#;
+
+    // Both state machines install a canister with the same id and execute it concurrently.
+    let log_lengths: Vec<usize> = std::thread::scope(|scope| {
+        let handles: Vec<_> = [1, 3]
+            .into_iter()
+            .map(|calls| {
+                scope.spawn(move || {
+                    let env = StateMachineBuilder::new().with_canister_logs().build();
+                    let canister_id = env.install_canister_wat(WAT, vec![], None);
+                    for _ in 0..calls {
+                        env.execute_ingress(canister_id, "log", vec![]).unwrap();
+                    }
+                    env.canister_log(canister_id).len()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    assert_eq!(log_lengths, vec![1, 3]);
+}
@@ -52,6 +52,11 @@ const WASM_NATIVE_STABLE_MEMORY_ERROR: &str = "Stable memory cannot be accessed
 
 const MAX_32_BIT_STABLE_MEMORY_IN_PAGES: u64 = 64 * 1024; // 4GiB
 
+/// The key under which `ic0.debug_print` records carry the printing canister's id.
+pub const DEBUG_PRINT_CANISTER_ID_KEY: &str = "debug_print_canister_id";
+/// The key under which `ic0.debug_print` records carry the time of the printing execution.
+pub const DEBUG_PRINT_TIME_KEY: &str = "debug_print_time_nanos";
+
 // This macro is used in system calls for tracing.
 macro_rules! trace_syscall {
     ($self:ident, $name:ident, $result:expr $( , $args:expr )*) => {{
@@ -757,6 +762,10 @@ pub struct SystemApiImpl {
     /// still be read through the hidden read API for speed on the first access.
     wasm_native_stable_memory: FlagStatus,
 
+    /// `ic0.debug_print` additionally emits a structured record on `log`, with
+    /// the message, [DEBUG_PRINT_CANISTER_ID_KEY] and [DEBUG_PRINT_TIME_KEY].
+    canister_debug_print_records: FlagStatus,
+
     /// The maximum sum of `<name>` lengths in exported functions called `canister_update <name>`,
     /// `canister_query <name>`, or `canister_composite_query <name>`.
     max_sum_exported_function_name_lengths: usize,
@@ -798,6 +807,7 @@ impl SystemApiImpl {
         execution_parameters: ExecutionParameters,
         subnet_available_memory: SubnetAvailableMemory,
         wasm_native_stable_memory: FlagStatus,
+        canister_debug_print_records: FlagStatus,
         max_sum_exported_function_name_lengths: usize,
         stable_memory: Memory,
         out_of_instructions_handler: Arc<dyn OutOfInstructionsHandler>,
@@ -819,6 +829,7 @@ impl SystemApiImpl {
             memory_usage,
             execution_parameters,
             wasm_native_stable_memory,
+            canister_debug_print_records,
             max_sum_exported_function_name_lengths,
             stable_memory,
             sandbox_safe_system_state,
@@ -2743,10 +2754,20 @@ impl SystemApi for SystemApiImpl {
             | ApiType::PreUpgrade { time, .. }
             | ApiType::ReplyCallback { time, .. }
             | ApiType::RejectCallback { time, .. }
-            | ApiType::InspectMessage { time, .. } => eprintln!(
-                "{}: [Canister {}] {}",
-                time, self.sandbox_safe_system_state.canister_id, msg
-            ),
+            | ApiType::InspectMessage { time, .. } => {
+                eprintln!(
+                    "{}: [Canister {}] {}",
+                    time, self.sandbox_safe_system_state.canister_id, msg
+                );
+                if self.canister_debug_print_records == FlagStatus::Enabled {
+                    slog::info!(
+                        self.log.inner_logger.root,
+                        "{}", msg;
+                        DEBUG_PRINT_CANISTER_ID_KEY => %self.sandbox_safe_system_state.canister_id,
+                        DEBUG_PRINT_TIME_KEY => time.as_nanos_since_unix_epoch(),
+                    );
+                }
+            }
         }
         trace_syscall!(self, ic0_debug_print, src, size, summarize(heap, src, size));
         Ok(())
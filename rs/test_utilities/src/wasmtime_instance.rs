@@ -161,6 +161,7 @@ impl WasmtimeInstanceBuilder {
                 subnet_memory_capacity,
             ),
             embedder.config().feature_flags.wasm_native_stable_memory,
+            embedder.config().feature_flags.canister_debug_print_records,
             embedder.config().max_sum_exported_function_name_lengths,
             Memory::new_for_testing(),
             Arc::new(ic_system_api::DefaultOutOfInstructionsHandler {}),
@@ -283,7 +283,8 @@ impl Request<NewSaleTicketRes> for NewSaleTicketRequest {
     fn payload(&self) -> Vec<u8> {
         Encode!(&NewSaleTicketReq {
             amount_icp_e8s: self.amount_icp_e8s,
-            subaccount: self.subaccount.map(|x| x.to_vec())
+            subaccount: self.subaccount.map(|x| x.to_vec()),
+            confirmation_text: None,
         })
         .unwrap()
     }
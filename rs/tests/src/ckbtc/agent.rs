@@ -13,6 +13,12 @@ Runbook::
 5. Perform calls and verify results for following endpoints:
     - get_btc_address
     - get_withdrawal_account
+    - retrieve_btc
+    - update_balance
+    - get_deposit_fee
+    - get_minter_info
+    - get_known_utxos
+    - get_events
 
 end::catalog[] */
 
@@ -73,10 +79,7 @@ pub fn test_ckbtc_minter_agent(env: TestEnv) {
         activate_ecdsa_signature(sys_node, subnet_sys.subnet_id, TEST_KEY_LOCAL, &logger).await;
 
         // Build agent.
-        let agent = CkBtcMinterAgent {
-            agent,
-            minter_canister_id: minter,
-        };
+        let agent = CkBtcMinterAgent::new(agent, minter);
 
         // Test agent endpoints.
         info!(logger, "Testing get_btc_address endpoint...");
@@ -87,6 +90,8 @@ pub fn test_ckbtc_minter_agent(env: TestEnv) {
         test_retrieve_btc(&agent).await;
         info!(logger, "Testing update_balance endpoint...");
         test_update_balance(&agent).await;
+        info!(logger, "Testing query endpoints...");
+        test_queries(&agent).await;
     });
 }
 
@@ -120,6 +125,7 @@ async fn test_retrieve_btc(agent: &CkBtcMinterAgent) {
     let args = RetrieveBtcArgs {
         amount: 42_000,
         address: "".to_string(),
+        fee_rate: None,
     };
     let res = agent
         .retrieve_btc(args)
@@ -142,3 +148,33 @@ async fn test_update_balance(agent: &CkBtcMinterAgent) {
         .expect("Error while decoding response.");
     assert!(res.is_err());
 }
+
+async fn test_queries(agent: &CkBtcMinterAgent) {
+    let info = agent
+        .get_minter_info()
+        .await
+        .expect("Error while decoding response.");
+    assert_eq!(
+        info.kyt_fee,
+        agent
+            .get_deposit_fee()
+            .await
+            .expect("Error while decoding response.")
+    );
+
+    let utxos = agent
+        .get_known_utxos()
+        .await
+        .expect("Error while decoding response.");
+    assert!(utxos.is_empty());
+
+    let events = agent
+        .get_events(0, 1000)
+        .await
+        .expect("Error while decoding response.");
+    assert!(
+        !events.events.is_empty(),
+        "the event log must start with Init"
+    );
+    assert!(events.total_event_count >= events.events.len() as u64);
+}
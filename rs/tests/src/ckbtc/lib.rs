@@ -272,6 +272,8 @@ pub(crate) async fn install_ledger(
                 controller_id: minting_user,
                 cycles_for_archive_creation: None,
                 max_transactions_per_response: None,
+                more_controller_ids: None,
+                archive_created_callback: None,
             })
             .build(),
     );
@@ -301,6 +303,9 @@ pub(crate) async fn install_minter(
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(KYT_FEE),
         kyt_principal: Some(kyt_canister_id),
+        resubmission_delay_blocks: None,
+        max_fee_bump_percent: None,
+        tainted_utxo_reviewer: None,
     };
 
     let minter_arg = MinterArg::Init(args);
@@ -355,6 +360,9 @@ pub(crate) async fn upgrade_kyt(kyt_canister: &mut Canister<'_>, mode: KytMode)
         mode: Some(mode),
         maintainers: None,
         minter_id: None,
+        provider_modes: None,
+        failover_order: None,
+        quorum_policy: None,
     });
 
     kyt_canister
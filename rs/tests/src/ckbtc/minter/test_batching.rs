@@ -119,10 +119,7 @@ pub fn test_batching(env: TestEnv) {
             agent: agent.clone(),
             ledger_canister_id: ledger,
         };
-        let minter_agent = CkBtcMinterAgent {
-            agent: agent.clone(),
-            minter_canister_id: minter,
-        };
+        let minter_agent = CkBtcMinterAgent::new(agent.clone(), minter);
 
         let caller = agent
             .get_principal()
@@ -79,10 +79,7 @@ pub fn test_heartbeat(env: TestEnv) {
             agent: agent.clone(),
             ledger_canister_id: ledger,
         };
-        let minter_agent = CkBtcMinterAgent {
-            agent: agent.clone(),
-            minter_canister_id: minter,
-        };
+        let minter_agent = CkBtcMinterAgent::new(agent.clone(), minter);
 
         let caller = agent
             .get_principal()
@@ -142,6 +139,7 @@ pub fn test_heartbeat(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: destination_btc_address.to_string(),
+                fee_rate: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
@@ -209,6 +207,7 @@ pub fn test_heartbeat(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: main_btc_address.clone(),
+                fee_rate: None,
             })
             .await;
         assert!(illegal_retrieve_response.is_err());
@@ -235,7 +234,11 @@ pub fn test_heartbeat(env: TestEnv) {
         let events = minter_agent
             .get_events(0, 1000)
             .await
-            .expect("failed to fetch minter's event log");
+            .expect("failed to fetch minter's event log")
+            .events
+            .into_iter()
+            .map(|entry| entry.payload)
+            .collect::<Vec<_>>();
 
         assert!(
             events.iter().any(|e| matches!(
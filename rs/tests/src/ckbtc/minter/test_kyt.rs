@@ -89,10 +89,7 @@ pub fn test_kyt(env: TestEnv) {
             agent: agent.clone(),
             ledger_canister_id: ledger,
         };
-        let minter_agent = CkBtcMinterAgent {
-            agent: agent.clone(),
-            minter_canister_id: minter,
-        };
+        let minter_agent = CkBtcMinterAgent::new(agent.clone(), minter);
 
         let caller = agent
             .get_principal()
@@ -307,6 +304,7 @@ pub fn test_kyt(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: btc_address2.to_string(),
+                fee_rate: None,
             })
             .await
             .expect("Error while calling retrieve_btc");
@@ -345,6 +343,7 @@ pub fn test_kyt(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: btc_address2.to_string(),
+                fee_rate: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
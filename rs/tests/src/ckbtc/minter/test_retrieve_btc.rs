@@ -84,10 +84,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             agent: agent.clone(),
             ledger_canister_id: ledger,
         };
-        let minter_agent = CkBtcMinterAgent {
-            agent: agent.clone(),
-            minter_canister_id: minter,
-        };
+        let minter_agent = CkBtcMinterAgent::new(agent.clone(), minter);
 
         let caller = agent
             .get_principal()
@@ -180,6 +177,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 35_000_000,
                 address: btc_address2.to_string(),
+                fee_rate: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
@@ -189,7 +187,11 @@ pub fn test_retrieve_btc(env: TestEnv) {
         let events = minter_agent
             .get_events(0, 1000)
             .await
-            .expect("failed to fetch minter's event log");
+            .expect("failed to fetch minter's event log")
+            .events
+            .into_iter()
+            .map(|entry| entry.payload)
+            .collect::<Vec<_>>();
         assert!(
             events.iter().any(|e| matches!(
                 e,
@@ -204,6 +206,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 35_000_000,
                 address: btc_address2.to_string(),
+                fee_rate: None,
             })
             .await
             .expect("Error while calling retrieve_btc");
@@ -219,6 +222,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 33,
                 address: btc_address2.to_string(),
+                fee_rate: None,
             })
             .await
             .expect("Error while calling retrieve_btc");
@@ -244,6 +248,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 1_000_000,
                 address: btc_address2.to_string(),
+                fee_rate: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
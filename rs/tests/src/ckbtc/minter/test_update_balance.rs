@@ -99,10 +99,7 @@ pub fn test_update_balance(env: TestEnv) {
             agent: agent.clone(),
             ledger_canister_id: ledger,
         };
-        let minter_agent = CkBtcMinterAgent {
-            agent: agent.clone(),
-            minter_canister_id: minter,
-        };
+        let minter_agent = CkBtcMinterAgent::new(agent.clone(), minter);
 
         let caller = agent
             .get_principal()
@@ -275,10 +272,7 @@ pub fn test_update_balance(env: TestEnv) {
         let mut rng = ChaChaRng::from_rng(OsRng).unwrap();
         let identity = Secp256k1Identity::from_private_key(SecretKey::random(&mut rng));
         mutable_agent.set_identity(identity);
-        let minter_agent = CkBtcMinterAgent {
-            agent: mutable_agent.clone(),
-            minter_canister_id: minter,
-        };
+        let minter_agent = CkBtcMinterAgent::new(mutable_agent.clone(), minter);
 
         let new_caller = mutable_agent
             .get_principal()
@@ -479,6 +479,7 @@ pub async fn retrieve_btc(
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: destination_btc_address.clone(),
+                fee_rate: None,
             })
             .await
         {
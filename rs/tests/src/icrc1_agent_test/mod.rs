@@ -237,11 +237,12 @@ pub fn test(env: TestEnv) {
 
         assert_eq!(
             hash_tree.lookup(&[b"last_block_index"]),
-            Found(&mleaf((1_u64).to_be_bytes()))
+            // LEB-128 encoding of 1
+            Found(&mleaf([1_u8]))
         );
 
         assert_eq!(
-            hash_tree.lookup(&[b"tip_hash"]),
+            hash_tree.lookup(&[b"last_block_hash"]),
             Found(&mleaf(blocks_response.blocks[1].hash()))
         );
 
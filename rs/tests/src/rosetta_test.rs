@@ -109,6 +109,8 @@ pub fn test_everything(env: TestEnv) {
         controller_id: CanisterId::from_u64(876).into(),
         cycles_for_archive_creation: Some(0),
         max_transactions_per_response: None,
+        more_controller_ids: None,
+        archive_created_callback: None,
     };
 
     let ledger_canister_for_governance_payload = LedgerCanisterInitPayload::builder()
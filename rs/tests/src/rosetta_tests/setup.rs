@@ -192,6 +192,8 @@ fn create_ledger_canister(
             controller_id: CanisterId::from_u64(876).into(),
             cycles_for_archive_creation: Some(0),
             max_transactions_per_response: None,
+            more_controller_ids: None,
+            archive_created_callback: None,
         };
         let ledger_init_args = LedgerCanisterInitPayload::builder()
             .minting_account(minting_account)
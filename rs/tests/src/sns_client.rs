@@ -697,6 +697,7 @@ async fn open_sns_token_swap(nns_api: &'_ Runtime, payload: OpenSnsTokenSwap) {
             neurons_fund_participants: None,             // TODO[NNS1-2339]
             should_auto_finalize: Some(true),
             neurons_fund_participation_constraints: None,
+            participation_eligibility_canister_id: None,
         })
         .unwrap();
 
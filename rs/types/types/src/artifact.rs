@@ -535,13 +535,13 @@ pub struct StateSyncMessage {
     pub root_hash: CryptoHashOfState,
     /// Absolute path to the checkpoint root directory.
     pub checkpoint_root: std::path::PathBuf,
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub meta_manifest: Arc<crate::state_sync::MetaManifest>,
     /// The manifest containing the summary of the content.
     pub manifest: crate::state_sync::Manifest,
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+    #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+    #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
     pub state_sync_file_group: Arc<crate::state_sync::FileGroupChunks>,
 }
 
@@ -303,7 +303,9 @@ pub struct IDkgTranscriptParams {
     receivers: IDkgReceivers,
     registry_version: RegistryVersion,
     /// Identifies the cryptographic signature scheme used in the protocol.
-    /// Currently only [`AlgorithmId::ThresholdEcdsaSecp256k1`] is supported.
+    /// Currently [`AlgorithmId::ThresholdEcdsaSecp256k1`],
+    /// [`AlgorithmId::ThresholdEcdsaSecp256r1`], and
+    /// [`AlgorithmId::ThresholdSchnorrBip340`] are supported.
     algorithm_id: AlgorithmId,
     /// Mode of operation for this current execution of the protocol.
     operation_type: IDkgTranscriptOperation,
@@ -327,8 +329,9 @@ impl IDkgTranscriptParams {
     ///   and `ReceiversEmpty`)
     /// * |dealers| >= self.collection_threshold + faults_tolerated(|dealers|)
     ///   (error: `UnsatisfiedCollectionThreshold`)
-    /// * algorithm_id is of type `ThresholdEcdsaSecp256k1` (error:
-    ///   `UnsupportedAlgorithmId`)
+    /// * algorithm_id is of type `ThresholdEcdsaSecp256k1`,
+    ///   `ThresholdEcdsaSecp256r1`, or `ThresholdSchnorrBip340`
+    ///   (error: `UnsupportedAlgorithmId`)
     /// * If `operation_type` is:
     ///   - ReshareOfMasked(t):
     ///     - t is of type Masked(_)
@@ -497,7 +500,9 @@ impl IDkgTranscriptParams {
 
     fn ensure_algorithm_id_supported(&self) -> Result<(), IDkgParamsValidationError> {
         match self.algorithm_id {
-            AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(()),
+            AlgorithmId::ThresholdEcdsaSecp256k1
+            | AlgorithmId::ThresholdEcdsaSecp256r1
+            | AlgorithmId::ThresholdSchnorrBip340 => Ok(()),
             _ => Err(IDkgParamsValidationError::UnsupportedAlgorithmId {
                 algorithm_id: self.algorithm_id,
             }),
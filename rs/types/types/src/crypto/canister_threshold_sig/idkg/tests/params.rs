@@ -452,6 +452,23 @@ fn should_not_create_with_wrong_algid() {
     );
 }
 
+#[test]
+fn should_create_with_threshold_schnorr_bip340_algid() {
+    let rng = &mut reproducible_rng();
+    let nodes = set_of_nodes(&[1]);
+
+    let result = IDkgTranscriptParams::new(
+        random_transcript_id(rng),
+        nodes.clone(),
+        nodes,
+        RegistryVersion::from(0),
+        AlgorithmId::ThresholdSchnorrBip340,
+        IDkgTranscriptOperation::Random,
+    );
+
+    assert!(result.is_ok());
+}
+
 #[test]
 fn should_not_create_reshare_masked_with_wrong_original_type() {
     let rng = &mut reproducible_rng();
@@ -1,5 +1,8 @@
 //! Utilities for performing deterministic operations. To be used anywhere in
 //! execution where we need to guarantee determinism.
+//!
+//! The operations are implemented in plain Rust without calling into libc, so
+//! they behave identically on all targets.
 
 /// An implementation of [`std::slice::copy_from_slice`] for `&[u8]` which
 /// performs reads/writes in a deterministic order. The implementation in the
@@ -18,3 +21,22 @@ pub fn deterministic_copy_from_slice(dst: &mut [u8], src: &[u8]) {
         dst[i] = src[i];
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic_copy_from_slice() {
+        let src: Vec<u8> = (0..=255).collect();
+        let mut dst = vec![0; src.len()];
+        deterministic_copy_from_slice(&mut dst, &src);
+        assert_eq!(dst, src);
+    }
+
+    #[test]
+    #[should_panic(expected = "source and destination have different lengths")]
+    fn test_deterministic_copy_from_slice_length_mismatch() {
+        deterministic_copy_from_slice(&mut [0; 2], &[1, 2, 3]);
+    }
+}
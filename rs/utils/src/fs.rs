@@ -5,7 +5,12 @@ use std::{fs, io, io::Error, path::Path, path::PathBuf};
 #[cfg(target_family = "unix")] // Otherwise, clippy complains about lack of use.
 use std::io::ErrorKind::AlreadyExists;
 #[cfg(target_family = "unix")]
-use std::os::unix::fs::MetadataExt;
+use std::os::unix::{fs::MetadataExt, io::AsRawFd};
+#[cfg(target_family = "unix")]
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
 
 #[cfg(target_os = "linux")]
 use thiserror::Error;
@@ -127,6 +132,120 @@ where
     })
 }
 
+/// Invokes sync_all on every regular file and directory in the tree rooted at
+/// `path`, syncing each directory after its contents.
+///
+/// Symbolic links are not followed.
+pub fn sync_tree<P>(path: P) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            sync_tree(entry?.path())?;
+        }
+        sync_path(path)
+    } else if metadata.is_file() {
+        sync_path(path)
+    } else {
+        Ok(())
+    }
+}
+
+/// Atomically replaces the directory `dst` with the fully-written directory
+/// `src`, removing the previous contents of `dst`.
+///
+/// Syncs the tree rooted at `src` before moving it into place, and the
+/// parent directories of `src` and `dst` after, so that `dst` is either the
+/// old or the new directory after a crash.
+///
+/// # Pre-conditions
+///   * `src` is a directory.
+///   * `src` and `dst` are on the same file system.
+///
+/// On platforms that cannot exchange two paths atomically, `dst` briefly
+/// does not exist while the new directory is moved into place.
+#[cfg(target_family = "unix")]
+pub fn atomically_replace_dir<PSrc, PDst>(src: PSrc, dst: PDst) -> io::Result<()>
+where
+    PSrc: AsRef<Path>,
+    PDst: AsRef<Path>,
+{
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    if !fs::symlink_metadata(src)?.is_dir() {
+        return Err(Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a directory", src.display()),
+        ));
+    }
+
+    sync_tree(src)?;
+
+    let dst_exists = match fs::symlink_metadata(dst) {
+        Ok(_) => true,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+        Err(err) => return Err(err),
+    };
+
+    if dst_exists {
+        // After the exchange, `src` holds the previous contents of `dst`.
+        exchange_paths(src, dst)?;
+    } else {
+        fs::rename(src, dst)?;
+    }
+
+    let src_parent = src.parent().unwrap_or_else(|| Path::new("/"));
+    let dst_parent = dst.parent().unwrap_or_else(|| Path::new("/"));
+    sync_path(dst_parent)?;
+    if src_parent != dst_parent {
+        sync_path(src_parent)?;
+    }
+
+    if dst_exists {
+        fs::remove_dir_all(src)?;
+    }
+    Ok(())
+}
+
+/// Atomically swaps the file system entries at paths `a` and `b`.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn exchange_paths(a: &Path, b: &Path) -> io::Result<()> {
+    use nix::fcntl::{renameat2, RenameFlags};
+
+    renameat2(None, a, None, b, RenameFlags::RENAME_EXCHANGE).map_err(|err| {
+        Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "failed to exchange {} and {}: {}",
+                a.display(),
+                b.display(),
+                err
+            ),
+        )
+    })
+}
+
+/// Swaps the file system entries at paths `a` and `b` using a temporary
+/// name. Unlike the Linux version, this is not atomic.
+#[cfg(all(
+    target_family = "unix",
+    not(all(target_os = "linux", target_env = "gnu"))
+))]
+fn exchange_paths(a: &Path, b: &Path) -> io::Result<()> {
+    let tmp = b
+        .parent()
+        .unwrap_or_else(|| Path::new("/"))
+        .join(tmp_name());
+    fs::rename(b, &tmp)?;
+    if let Err(err) = fs::rename(a, b) {
+        let _ = fs::rename(&tmp, b);
+        return Err(err);
+    }
+    fs::rename(&tmp, a)
+}
+
 #[cfg(target_os = "linux")]
 /// Copies only valid regions of file preserving the sparseness
 /// of the file. Also utilizes copy_file_range which performs
@@ -143,7 +262,7 @@ pub fn copy_file_sparse(from: &Path, to: &Path) -> io::Result<u64> {
     use fs::OpenOptions;
     use io::{ErrorKind, Read};
     use libc::{ftruncate64, lseek64};
-    use std::os::unix::{fs::OpenOptionsExt, fs::PermissionsExt, io::AsRawFd};
+    use std::os::unix::{fs::OpenOptionsExt, fs::PermissionsExt};
 
     unsafe fn copy_file_range(
         fd_in: libc::c_int,
@@ -322,6 +441,80 @@ pub fn copy_file_sparse(from: &Path, to: &Path) -> io::Result<u64> {
     copy_file_sparse_portable(from, to)
 }
 
+/// How [clone_file] copied a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloneMethod {
+    /// The destination shares data blocks with the source until either of
+    /// them is modified (a reflink).
+    Reflink,
+    /// The contents of the source were copied into the destination.
+    Copy,
+}
+
+/// Copies `src` into `dst`, making a copy-on-write clone on file systems that
+/// support reflinks (e.g. btrfs, XFS) and falling back to [copy_file_sparse]
+/// elsewhere, including when `src` and `dst` are on different file systems.
+///
+/// Returns the method that was used to copy the file.
+#[cfg(target_os = "linux")]
+pub fn clone_file(src: &Path, dst: &Path) -> io::Result<CloneMethod> {
+    use ic_sys::fs::FileCloneError;
+
+    match ic_sys::fs::clone_file(src, dst) {
+        Ok(()) => Ok(CloneMethod::Reflink),
+        Err(FileCloneError::OperationNotSupported) | Err(FileCloneError::DifferentFileSystems) => {
+            copy_file_sparse(src, dst)?;
+            Ok(CloneMethod::Copy)
+        }
+        Err(FileCloneError::IoError(e)) => Err(Error::new(
+            e.kind(),
+            format!(
+                "failed to clone {} -> {}: {}",
+                src.display(),
+                dst.display(),
+                e
+            ),
+        )),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn clone_file(src: &Path, dst: &Path) -> io::Result<CloneMethod> {
+    copy_file_sparse(src, dst)?;
+    Ok(CloneMethod::Copy)
+}
+
+/// Returns true if [clone_file] makes copy-on-write clones of files in
+/// directory `dir`.
+///
+/// The probe creates two temporary files in `dir` and removes them before
+/// returning.
+#[cfg(target_os = "linux")]
+pub fn supports_reflinks(dir: &Path) -> io::Result<bool> {
+    use ic_sys::fs::FileCloneError;
+
+    let src = dir.join(tmp_name());
+    let dst = dir.join(tmp_name());
+    let _cleanup = OnScopeExit::new(|| {
+        let _ = fs::remove_file(&src);
+        let _ = fs::remove_file(&dst);
+    });
+
+    fs::write(&src, b"reflink probe")?;
+    match ic_sys::fs::clone_file(&src, &dst) {
+        Ok(()) => Ok(true),
+        Err(FileCloneError::OperationNotSupported) | Err(FileCloneError::DifferentFileSystems) => {
+            Ok(false)
+        }
+        Err(FileCloneError::IoError(e)) => Err(e),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn supports_reflinks(_dir: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
 fn copy_file_sparse_portable(from: &Path, to: &Path) -> io::Result<u64> {
     fs::copy(from, to)
 }
@@ -542,6 +735,246 @@ fn tmp_name() -> String {
         .collect()
 }
 
+/// Returns the path of the file holding the SHA-256 checksum of `path`,
+/// i.e., `path` with `.sha256` appended.
+///
+/// Examples:
+/// bla.txt -> bla.txt.sha256
+/// /tmp/bla -> /tmp/bla.sha256
+pub fn get_checksum_path_for<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut checksum_path = path.as_ref().as_os_str().to_os_string();
+    checksum_path.push(".sha256");
+    PathBuf::from(checksum_path)
+}
+
+#[cfg(target_family = "unix")]
+/// Writes a file `dest` together with its SHA-256 checksum file (see
+/// [`get_checksum_path_for`]) in a crash-safe manner, hashing the content while
+/// it is streamed to disk so that large files are only written once.
+///
+/// The content is written to the temporary file `dest.tmp`, which is removed if
+/// the writer is dropped before [`ChecksummedWriter::finish`] is called.
+///
+/// The checksum file uses the `sha256sum` format, so the pair can also be
+/// checked with `sha256sum -c dest.sha256` from the directory of `dest`.
+pub struct ChecksummedWriter {
+    dest: PathBuf,
+    dest_tmp: PathBuf,
+    writer: Option<io::BufWriter<fs::File>>,
+    hasher: Option<ic_crypto_sha2::Sha256>,
+    published: bool,
+    // Set when a write fails, after which the file may contain bytes that were
+    // not hashed.
+    poisoned: bool,
+}
+
+#[cfg(target_family = "unix")]
+impl ChecksummedWriter {
+    /// Creates the temporary file for `dest`. If it already exists, it will be
+    /// deleted first.
+    pub fn new<P>(dest: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let dest_tmp = get_tmp_for_path(&dest);
+        let file = create_file_exclusive_and_open(&dest_tmp)?;
+        Ok(Self {
+            dest: dest.as_ref().to_path_buf(),
+            dest_tmp,
+            writer: Some(io::BufWriter::new(file)),
+            hasher: Some(ic_crypto_sha2::Sha256::new()),
+            published: false,
+            poisoned: false,
+        })
+    }
+
+    fn check_not_poisoned(&self) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "a previous write to {} failed",
+                    self.dest_tmp.to_string_lossy()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fsyncs the content and publishes `dest` and its checksum file, returning
+    /// the checksum.
+    ///
+    /// The checksum file of a previous version of `dest` is removed before
+    /// `dest` is replaced, and the new checksum file is renamed into place
+    /// last. Hence, if the checksum file exists, it matches `dest`, and a crash
+    /// at any point leaves either the complete old pair, the complete new pair,
+    /// or a `dest` without checksum file, which [`ChecksummedReader`] rejects.
+    pub fn finish(mut self) -> io::Result<[u8; 32]> {
+        self.check_not_poisoned()?;
+        let file = self
+            .writer
+            .take()
+            .expect("the writer is only taken by finish")
+            .into_inner()
+            .map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        drop(file);
+        let checksum = self
+            .hasher
+            .take()
+            .expect("the hasher is only taken by finish")
+            .finish();
+
+        let checksum_path = get_checksum_path_for(&self.dest);
+        let file_name = self
+            .dest
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let checksum_tmp = get_tmp_for_path(&checksum_path);
+        let mut cleanup = OnScopeExit::new(|| {
+            let _ = fs::remove_file(&checksum_tmp);
+        });
+        {
+            let mut file = create_file_exclusive_and_open(&checksum_tmp)?;
+            writeln!(file, "{}  {}", hex::encode(checksum), file_name)?;
+            file.sync_all()?;
+        }
+
+        match fs::remove_file(&checksum_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => (),
+        }
+        let parent = self
+            .dest
+            .parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .to_path_buf();
+        sync_path(&parent)?;
+        fs::rename(&self.dest_tmp, &self.dest)?;
+        self.published = true;
+        sync_path(&parent)?;
+        fs::rename(&checksum_tmp, &checksum_path)?;
+        cleanup.deactivate();
+        sync_path(&parent)?;
+        Ok(checksum)
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl Write for ChecksummedWriter {
+    // Writes the whole buffer, so that exactly the bytes of `buf` are hashed.
+    // A failed write may have written part of `buf`, so it poisons the writer.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_not_poisoned()?;
+        if let Err(e) = self
+            .writer
+            .as_mut()
+            .expect("the writer is only taken by finish")
+            .write_all(buf)
+        {
+            self.poisoned = true;
+            return Err(e);
+        }
+        self.hasher
+            .as_mut()
+            .expect("the hasher is only taken by finish")
+            .write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer
+            .as_mut()
+            .expect("the writer is only taken by finish")
+            .flush()
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl Drop for ChecksummedWriter {
+    fn drop(&mut self) {
+        if !self.published {
+            let _ = fs::remove_file(&self.dest_tmp);
+        }
+    }
+}
+
+/// Reads a file written by [`ChecksummedWriter`], hashing the content while it
+/// is read. Reaching the end of the file fails with
+/// [`io::ErrorKind::InvalidData`] if the content does not match the checksum
+/// file, so callers must read until the end (e.g. with [`io::copy`]) before
+/// trusting the content.
+pub struct ChecksummedReader {
+    reader: io::BufReader<fs::File>,
+    expected: [u8; 32],
+    hasher: Option<ic_crypto_sha2::Sha256>,
+    mismatch: Option<String>,
+}
+
+impl ChecksummedReader {
+    /// Opens `path` and reads its expected checksum from the checksum file.
+    /// Fails with [`io::ErrorKind::NotFound`] if the checksum file does not
+    /// exist, i.e., if `path` was not completely published.
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let checksum_path = get_checksum_path_for(&path);
+        let content = fs::read_to_string(&checksum_path)?;
+        let invalid_checksum = || {
+            Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid checksum file {}", checksum_path.display()),
+            )
+        };
+        let expected = content
+            .split_whitespace()
+            .next()
+            .and_then(|checksum| hex::decode(checksum).ok())
+            .and_then(|checksum| <[u8; 32]>::try_from(checksum).ok())
+            .ok_or_else(invalid_checksum)?;
+        Ok(Self {
+            reader: io::BufReader::new(fs::File::open(path)?),
+            expected,
+            hasher: Some(ic_crypto_sha2::Sha256::new()),
+            mismatch: None,
+        })
+    }
+}
+
+impl io::Read for ChecksummedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = io::Read::read(&mut self.reader, buf)?;
+        if read > 0 {
+            if let Some(hasher) = self.hasher.as_mut() {
+                hasher.write(&buf[..read]);
+            }
+            return Ok(read);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(hasher) = self.hasher.take() {
+            let actual = hasher.finish();
+            if actual != self.expected {
+                self.mismatch = Some(format!(
+                    "checksum mismatch: expected {}, got {}",
+                    hex::encode(self.expected),
+                    hex::encode(actual)
+                ));
+            }
+        }
+        match &self.mismatch {
+            Some(mismatch) => Err(Error::new(io::ErrorKind::InvalidData, mismatch.clone())),
+            None => Ok(0),
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 #[derive(Error, Clone, Debug, PartialEq, Eq)]
 pub enum CopyFileRangeAllError {
@@ -563,7 +996,6 @@ pub fn copy_file_range_all(
     mut dst_offset: i64,
     len: usize,
 ) -> Result<(), CopyFileRangeAllError> {
-    use std::os::unix::io::AsRawFd;
     let mut copied_total = 0;
     while copied_total < len {
         let copied = nix::fcntl::copy_file_range(
@@ -659,11 +1091,187 @@ fn advance_slices<'a>(
     }
 }
 
+/// The mode of a [FileLock]: any number of processes can hold a shared lock on
+/// a file at the same time, while an exclusive lock excludes all other locks.
+#[cfg(target_family = "unix")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+/// An advisory lock on a lock file (see `flock(2)`), released when dropped or
+/// when the process exits, so that a crashed process never leaves a stale lock
+/// behind.
+///
+/// Lock files are created if they do not exist and are never truncated or
+/// removed, so a lock file may also carry data, such as the port of a server,
+/// written by the holder of an exclusive lock.
+///
+/// To acquire several locks, use [FileLock::lock_all], which acquires them in a
+/// canonical order, so that processes locking overlapping sets of files cannot
+/// deadlock.
+#[cfg(target_family = "unix")]
+#[derive(Debug)]
+pub struct FileLock {
+    file: fs::File,
+    path: PathBuf,
+    mode: LockMode,
+}
+
+#[cfg(target_family = "unix")]
+impl FileLock {
+    /// The time between two attempts to acquire a lock with a timeout.
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Acquires a lock on the file at `path`, creating it if needed, and blocks
+    /// until the lock is available.
+    pub fn lock<P: AsRef<Path>>(path: P, mode: LockMode) -> io::Result<Self> {
+        let file = Self::open(path.as_ref())?;
+        loop {
+            match flock(&file, mode, true) {
+                Ok(()) => return Ok(Self::new(file, path.as_ref(), mode)),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Acquires a lock on the file at `path`, creating it if needed. Returns
+    /// `None` if a conflicting lock is held, e.g., by another process.
+    pub fn try_lock<P: AsRef<Path>>(path: P, mode: LockMode) -> io::Result<Option<Self>> {
+        let file = Self::open(path.as_ref())?;
+        match flock(&file, mode, false) {
+            Ok(()) => Ok(Some(Self::new(file, path.as_ref(), mode))),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Acquires a lock on the file at `path`, creating it if needed. Fails with
+    /// [io::ErrorKind::TimedOut] if the lock is not available within `timeout`.
+    pub fn lock_with_timeout<P: AsRef<Path>>(
+        path: P,
+        mode: LockMode,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(lock) = Self::try_lock(path.as_ref(), mode)? {
+                return Ok(lock);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "timed out after {:?} waiting for a {:?} lock on {}",
+                        timeout,
+                        mode,
+                        path.as_ref().display()
+                    ),
+                ));
+            }
+            std::thread::sleep(Self::POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Acquires locks on all files at `paths` (in the same mode), creating them
+    /// if needed, and returns them in the order in which they were acquired.
+    ///
+    /// The locks are acquired in the lexicographic order of the paths, with
+    /// duplicates removed, no matter the order of `paths`. As long as all
+    /// processes lock several files through this function, they cannot
+    /// deadlock. If `timeout` is set, it bounds the time spent waiting for all
+    /// locks together. If any lock cannot be acquired, the ones acquired so
+    /// far are released.
+    pub fn lock_all<P: AsRef<Path>>(
+        paths: &[P],
+        mode: LockMode,
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<Self>> {
+        let paths: BTreeSet<&Path> = paths.iter().map(|path| path.as_ref()).collect();
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        paths
+            .into_iter()
+            .map(|path| match deadline {
+                Some(deadline) => Self::lock_with_timeout(
+                    path,
+                    mode,
+                    deadline.saturating_duration_since(Instant::now()),
+                ),
+                None => Self::lock(path, mode),
+            })
+            .collect()
+    }
+
+    /// The path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The mode in which the lock is held.
+    pub fn mode(&self) -> LockMode {
+        self.mode
+    }
+
+    /// The open lock file, e.g., to read or write the data it carries.
+    pub fn file(&self) -> &fs::File {
+        &self.file
+    }
+
+    fn open(path: &Path) -> io::Result<fs::File> {
+        fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+    }
+
+    fn new(file: fs::File, path: &Path, mode: LockMode) -> Self {
+        Self {
+            file,
+            path: path.to_path_buf(),
+            mode,
+        }
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock anyway, but unlocking explicitly
+        // releases it even if the file descriptor has been duplicated.
+        unsafe {
+            libc::flock(self.file.as_raw_fd(), libc::LOCK_UN);
+        }
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn flock(file: &fs::File, mode: LockMode, blocking: bool) -> io::Result<()> {
+    let mut operation = match mode {
+        LockMode::Shared => libc::LOCK_SH,
+        LockMode::Exclusive => libc::LOCK_EX,
+    };
+    if !blocking {
+        operation |= libc::LOCK_NB;
+    }
+    if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::advance_slices;
     use super::io::IoSlice;
+    use super::sync_tree;
     use super::write_atomically_using_tmp_file;
+    use super::{clone_file, supports_reflinks, CloneMethod};
 
     #[test]
     fn test_write_success() {
@@ -686,6 +1294,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_clone_file() {
+        let tmp_dir = tempfile::TempDir::new().expect("failed to create a temporary directory");
+        let src = tmp_dir.path().join("src");
+        let dst = tmp_dir.path().join("dst");
+        std::fs::write(&src, b"original contents").expect("failed to write the source file");
+
+        let method = clone_file(&src, &dst).expect("failed to clone a file");
+        let reflinks = supports_reflinks(tmp_dir.path()).expect("failed to probe for reflinks");
+        assert_eq!(method == CloneMethod::Reflink, reflinks);
+        assert_eq!(
+            std::fs::read(&dst).expect("failed to read the destination file"),
+            b"original contents".to_vec()
+        );
+
+        // Modifying the clone does not affect the source.
+        std::fs::write(&dst, b"new contents").expect("failed to write the destination file");
+        assert_eq!(
+            std::fs::read(&src).expect("failed to read the source file"),
+            b"original contents".to_vec()
+        );
+
+        // The probe leaves no files behind.
+        assert_eq!(
+            std::fs::read_dir(tmp_dir.path())
+                .expect("failed to list the directory")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_sync_tree() {
+        let tmp_dir = tempfile::TempDir::new().expect("failed to create a temporary directory");
+        let nested = tmp_dir.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).expect("failed to create directories");
+        std::fs::write(nested.join("file"), b"test").expect("failed to write a file");
+        std::fs::write(tmp_dir.path().join("file"), b"test").expect("failed to write a file");
+
+        sync_tree(tmp_dir.path()).expect("failed to sync the tree");
+        sync_tree(nested.join("file")).expect("failed to sync a single file");
+        assert!(sync_tree(tmp_dir.path().join("missing")).is_err());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_atomically_replace_dir() {
+        use super::atomically_replace_dir;
+
+        let tmp_dir = tempfile::TempDir::new().expect("failed to create a temporary directory");
+        let src = tmp_dir.path().join("src");
+        let dst = tmp_dir.path().join("dst");
+
+        std::fs::create_dir(&src).expect("failed to create the source directory");
+        std::fs::write(src.join("first"), b"first").expect("failed to write a file");
+        atomically_replace_dir(&src, &dst).expect("failed to move a new directory into place");
+        assert!(!src.exists());
+        assert_eq!(
+            std::fs::read(dst.join("first")).expect("failed to read a file"),
+            b"first".to_vec()
+        );
+
+        std::fs::create_dir(&src).expect("failed to create the source directory");
+        std::fs::write(src.join("second"), b"second").expect("failed to write a file");
+        atomically_replace_dir(&src, &dst).expect("failed to replace a directory");
+        assert!(!src.exists());
+        assert!(!dst.join("first").exists());
+        assert_eq!(
+            std::fs::read(dst.join("second")).expect("failed to read a file"),
+            b"second".to_vec()
+        );
+
+        std::fs::write(&src, b"not a directory").expect("failed to write a file");
+        assert!(atomically_replace_dir(&src, &dst).is_err());
+        assert!(dst.join("second").exists());
+    }
+
     #[test]
     fn test_write_failure() {
         let tmp_dir = tempfile::TempDir::new().expect("failed to create a temporary directory");
@@ -1131,4 +1816,199 @@ mod tests {
             );
         }
     }
+
+    #[cfg(target_family = "unix")]
+    mod file_lock {
+        use crate::fs::{FileLock, LockMode};
+        use assert_matches::assert_matches;
+        use std::io::{ErrorKind, Read, Write};
+        use std::time::Duration;
+
+        #[test]
+        fn should_create_the_lock_file_and_keep_its_content() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let path = temp_dir.path().join("port");
+            {
+                let lock = FileLock::lock(&path, LockMode::Exclusive).expect("failed to lock");
+                assert_eq!(lock.path(), path);
+                lock.file().write_all(b"8080").unwrap();
+            }
+            let lock = FileLock::lock(&path, LockMode::Shared).expect("failed to lock");
+            let mut content = String::new();
+            lock.file().read_to_string(&mut content).unwrap();
+            assert_eq!(content, "8080");
+        }
+
+        #[test]
+        fn should_exclude_conflicting_locks() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let path = temp_dir.path().join("lock");
+
+            let shared = FileLock::try_lock(&path, LockMode::Shared).unwrap();
+            assert!(shared.is_some());
+            assert!(FileLock::try_lock(&path, LockMode::Shared)
+                .unwrap()
+                .is_some());
+            assert!(FileLock::try_lock(&path, LockMode::Exclusive)
+                .unwrap()
+                .is_none());
+            assert_matches!(
+                FileLock::lock_with_timeout(&path, LockMode::Exclusive, Duration::from_millis(50)),
+                Err(err) if err.kind() == ErrorKind::TimedOut
+            );
+
+            drop(shared);
+            let exclusive = FileLock::try_lock(&path, LockMode::Exclusive).unwrap();
+            assert!(exclusive.is_some());
+            assert!(FileLock::try_lock(&path, LockMode::Shared)
+                .unwrap()
+                .is_none());
+        }
+
+        #[test]
+        fn should_lock_all_files_in_canonical_order() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let a = temp_dir.path().join("a");
+            let b = temp_dir.path().join("b");
+
+            let locks = FileLock::lock_all(&[&b, &a, &b], LockMode::Exclusive, None).unwrap();
+            let paths: Vec<_> = locks.iter().map(|lock| lock.path()).collect();
+            assert_eq!(paths, vec![a.as_path(), b.as_path()]);
+
+            // Holding one of the locks makes acquiring all of them time out, and
+            // releases the locks acquired in the meantime.
+            drop(locks);
+            let held = FileLock::lock(&b, LockMode::Exclusive).unwrap();
+            assert_matches!(
+                FileLock::lock_all(
+                    &[&a, &b],
+                    LockMode::Exclusive,
+                    Some(Duration::from_millis(50))
+                ),
+                Err(err) if err.kind() == ErrorKind::TimedOut
+            );
+            assert!(FileLock::try_lock(&a, LockMode::Exclusive)
+                .unwrap()
+                .is_some());
+            drop(held);
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    mod checksummed_files {
+        use crate::fs::{
+            get_checksum_path_for, get_tmp_for_path, ChecksummedReader, ChecksummedWriter,
+        };
+        use assert_matches::assert_matches;
+        use std::io::{ErrorKind, Read, Write};
+
+        #[test]
+        fn should_write_and_verify_file_with_checksum() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let path = temp_dir.path().join("state.bin");
+            let content: Vec<u8> = (0..100_000_u32).flat_map(|i| i.to_le_bytes()).collect();
+
+            let mut writer = ChecksummedWriter::new(&path).unwrap();
+            for chunk in content.chunks(4096) {
+                writer.write_all(chunk).unwrap();
+            }
+            let checksum = writer.finish().unwrap();
+
+            assert_eq!(checksum, ic_crypto_sha2::Sha256::hash(&content));
+            assert!(!get_tmp_for_path(&path).exists());
+            assert_eq!(
+                std::fs::read_to_string(get_checksum_path_for(&path)).unwrap(),
+                format!("{}  state.bin\n", hex::encode(checksum))
+            );
+
+            let mut read = vec![];
+            ChecksummedReader::open(&path)
+                .unwrap()
+                .read_to_end(&mut read)
+                .unwrap();
+            assert_eq!(read, content);
+        }
+
+        #[test]
+        fn should_reject_corrupted_file() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let path = temp_dir.path().join("state.bin");
+            let mut writer = ChecksummedWriter::new(&path).unwrap();
+            writer.write_all(b"some data").unwrap();
+            writer.finish().unwrap();
+
+            std::fs::write(&path, b"some date").unwrap();
+
+            let mut reader = ChecksummedReader::open(&path).unwrap();
+            let mut read = vec![];
+            assert_matches!(
+                reader.read_to_end(&mut read),
+                Err(err) if err.kind() == ErrorKind::InvalidData
+            );
+            // The error is reported again on subsequent reads.
+            assert_matches!(
+                reader.read(&mut [0; 16]),
+                Err(err) if err.kind() == ErrorKind::InvalidData
+            );
+        }
+
+        #[test]
+        fn should_replace_previous_version() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let path = temp_dir.path().join("state.bin");
+            for content in [b"version 1", b"version 2"] {
+                let mut writer = ChecksummedWriter::new(&path).unwrap();
+                writer.write_all(content).unwrap();
+                writer.finish().unwrap();
+            }
+
+            let mut read = vec![];
+            ChecksummedReader::open(&path)
+                .unwrap()
+                .read_to_end(&mut read)
+                .unwrap();
+            assert_eq!(read, b"version 2");
+        }
+
+        #[test]
+        fn should_not_publish_anything_if_not_finished() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let path = temp_dir.path().join("state.bin");
+
+            let mut writer = ChecksummedWriter::new(&path).unwrap();
+            writer.write_all(b"some data").unwrap();
+            assert!(get_tmp_for_path(&path).exists());
+            drop(writer);
+
+            assert!(!get_tmp_for_path(&path).exists());
+            assert!(!path.exists());
+            assert!(!get_checksum_path_for(&path).exists());
+        }
+
+        #[test]
+        fn should_reject_file_without_checksum() {
+            let temp_dir =
+                tempfile::TempDir::new().expect("failed to create a temporary directory");
+            let path = temp_dir.path().join("state.bin");
+            std::fs::write(&path, b"some data").unwrap();
+
+            assert_matches!(
+                ChecksummedReader::open(&path).map(|_| ()),
+                Err(err) if err.kind() == ErrorKind::NotFound
+            );
+
+            std::fs::write(get_checksum_path_for(&path), "not a checksum").unwrap();
+            assert_matches!(
+                ChecksummedReader::open(&path).map(|_| ()),
+                Err(err) if err.kind() == ErrorKind::InvalidData
+            );
+        }
+    }
 }
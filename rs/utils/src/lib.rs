@@ -2,10 +2,10 @@
 //! to live in a separate package.
 
 pub mod byte_slice_fmt;
-#[cfg(unix)]
 pub mod deterministic_operations;
 pub mod fs;
 pub mod rle;
-pub mod serde_arc;
+pub mod serde_shared;
+pub mod serde_sorted;
 pub mod str;
 pub mod thread;
@@ -0,0 +1,226 @@
+//! Functions for serializing and deserializing shared pointers (`Arc` and
+//! `Rc`) with serde.
+//!
+//! There can be tricky correctness issues when serializing a shared pointer,
+//! so please provide some justification whenever using this module.
+//!
+//! # Warning
+//!
+//! Serializing `Arc`s or `Rc`s with [`serialize_arc`] or [`serialize_rc`] will
+//! not preserve identity. The warnings in the [serde documentation of the `rc`](https://serde.rs/feature-flags.html#-features-rc)
+//! feature apply when using this crate.
+//!
+//! If the data behind the `Arc` exhibits interior mutability (e.g. a `Mutex`),
+//! then serializing the `Arc` will create a new value which is not kept in sync
+//! with the original value. And serializing any value which contains multiple
+//! references to that `Arc` will result in multiple copies of the `Mutex` which
+//! are not kept in sync with each other.
+//!
+//! Even if the data in the `Arc` is read-only, there may be performance issues
+//! with serializing because the referenced data will be duplicated for each
+//! reference. Collections of shared pointers with many identical payloads
+//! (e.g. repeated principals) can be serialized with [`serialize_interned`]
+//! instead, which stores every distinct payload once and restores the sharing
+//! on deserialization.
+//!
+//! `Weak` pointers are not supported: a deserialized `Weak` would not have any
+//! strong owner and would therefore always be dangling.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::rc::Rc;
+//! use std::sync::Arc;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Foo {
+//!     /// It is same to serialize this `Arc` field because ...
+//!     #[serde(serialize_with = "ic_utils::serde_shared::serialize_arc")]
+//!     #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_arc")]
+//!     foo: Arc<u32>,
+//!     /// It is same to serialize this `Rc` field because ...
+//!     #[serde(serialize_with = "ic_utils::serde_shared::serialize_rc")]
+//!     #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_rc")]
+//!     bar: Rc<u32>,
+//!     /// Every distinct string is serialized only once.
+//!     #[serde(serialize_with = "ic_utils::serde_shared::serialize_interned")]
+//!     #[serde(deserialize_with = "ic_utils::serde_shared::deserialize_interned")]
+//!     names: Vec<Arc<String>>,
+//! }
+//! ```
+use std::collections::hash_map::{Entry, HashMap};
+use std::hash::Hash;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize_arc<T: Serialize, S: Serializer>(
+    data: &Arc<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    T::serialize(data, serializer)
+}
+
+pub fn deserialize_arc<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Arc<T>, D::Error> {
+    T::deserialize(deserializer).map(Arc::new)
+}
+
+pub fn serialize_rc<T: Serialize, S: Serializer>(
+    data: &Rc<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    T::serialize(data, serializer)
+}
+
+pub fn deserialize_rc<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Rc<T>, D::Error> {
+    T::deserialize(deserializer).map(Rc::new)
+}
+
+/// A shared pointer that can be created from its payload.
+pub trait SharedPointer: Deref + Clone {
+    fn new(payload: Self::Target) -> Self;
+}
+
+impl<T> SharedPointer for Arc<T> {
+    fn new(payload: T) -> Self {
+        Arc::new(payload)
+    }
+}
+
+impl<T> SharedPointer for Rc<T> {
+    fn new(payload: T) -> Self {
+        Rc::new(payload)
+    }
+}
+
+/// Serializes a collection of shared pointers (e.g. `Arc<T>` or `Rc<T>`) as a
+/// table of the distinct payloads followed by the index of the payload of
+/// every element.
+pub fn serialize_interned<'a, C, P, S>(data: &'a C, serializer: S) -> Result<S::Ok, S::Error>
+where
+    &'a C: IntoIterator<Item = &'a P>,
+    P: Deref + 'a,
+    P::Target: Serialize + Eq + Hash,
+    S: Serializer,
+{
+    let mut table: Vec<&P::Target> = Vec::new();
+    let mut index_of: HashMap<&P::Target, u64> = HashMap::new();
+    let mut indices: Vec<u64> = Vec::new();
+    for pointer in data {
+        let payload: &P::Target = pointer;
+        let index = match index_of.entry(payload) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                table.push(payload);
+                *entry.insert(table.len() as u64 - 1)
+            }
+        };
+        indices.push(index);
+    }
+    (table, indices).serialize(serializer)
+}
+
+/// Deserializes a collection serialized with [`serialize_interned`]. Elements
+/// with identical payloads point to the same allocation.
+pub fn deserialize_interned<'de, C, P, D>(deserializer: D) -> Result<C, D::Error>
+where
+    C: FromIterator<P>,
+    P: SharedPointer,
+    P::Target: Deserialize<'de> + Sized,
+    D: Deserializer<'de>,
+{
+    let (table, indices) = <(Vec<P::Target>, Vec<u64>)>::deserialize(deserializer)?;
+    let table: Vec<P> = table.into_iter().map(P::new).collect();
+    indices
+        .into_iter()
+        .map(|index| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| table.get(index))
+                .cloned()
+                .ok_or_else(|| {
+                    D::Error::custom(format!(
+                        "interned index {} out of bounds of a table of length {}",
+                        index,
+                        table.len()
+                    ))
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Interned {
+        #[serde(serialize_with = "serialize_interned")]
+        #[serde(deserialize_with = "deserialize_interned")]
+        arcs: Vec<Arc<String>>,
+        #[serde(serialize_with = "serialize_interned")]
+        #[serde(deserialize_with = "deserialize_interned")]
+        rcs: Vec<Rc<String>>,
+    }
+
+    #[derive(Serialize)]
+    struct Plain {
+        arcs: Vec<String>,
+        rcs: Vec<String>,
+    }
+
+    #[test]
+    fn test_interned_roundtrip_restores_sharing() {
+        let names = ["alice", "bob", "alice", "carol", "bob", "alice"];
+        let value = Interned {
+            arcs: names.iter().map(|n| Arc::new(n.to_string())).collect(),
+            rcs: names.iter().map(|n| Rc::new(n.to_string())).collect(),
+        };
+
+        let bytes = serde_cbor::to_vec(&value).unwrap();
+        let decoded: Interned = serde_cbor::from_slice(&bytes).unwrap();
+
+        assert_eq!(decoded.arcs, value.arcs);
+        assert_eq!(decoded.rcs, value.rcs);
+        assert!(Arc::ptr_eq(&decoded.arcs[0], &decoded.arcs[2]));
+        assert!(Arc::ptr_eq(&decoded.arcs[0], &decoded.arcs[5]));
+        assert!(Arc::ptr_eq(&decoded.arcs[1], &decoded.arcs[4]));
+        assert!(!Arc::ptr_eq(&decoded.arcs[0], &decoded.arcs[1]));
+        assert_eq!(Rc::strong_count(&decoded.rcs[0]), 3);
+        assert_eq!(Rc::strong_count(&decoded.rcs[3]), 1);
+    }
+
+    #[test]
+    fn test_interned_encoding_is_smaller_for_repeated_payloads() {
+        let name = "2vxsx-fae".repeat(10);
+        let interned = Interned {
+            arcs: (0..100).map(|_| Arc::new(name.clone())).collect(),
+            rcs: (0..100).map(|_| Rc::new(name.clone())).collect(),
+        };
+        let plain = Plain {
+            arcs: vec![name.clone(); 100],
+            rcs: vec![name; 100],
+        };
+
+        assert!(
+            serde_cbor::to_vec(&interned).unwrap().len() * 10
+                < serde_cbor::to_vec(&plain).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_interned_rejects_out_of_bounds_index() {
+        let bytes = serde_cbor::to_vec(&(vec!["alice"], vec![0_u64, 1])).unwrap();
+        let mut deserializer = serde_cbor::Deserializer::from_slice(&bytes);
+        let err = deserialize_interned::<Vec<Arc<String>>, _, _>(&mut deserializer).unwrap_err();
+        assert!(err.to_string().contains("out of bounds"), "{}", err);
+    }
+}
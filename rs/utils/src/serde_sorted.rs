@@ -0,0 +1,136 @@
+//! Functions for serializing maps and sets with serde in key order.
+//!
+//! Serializing a `HashMap` or a `HashSet` visits the entries in an unspecified
+//! order, so the same value may be encoded differently every time. Whenever
+//! an encoding feeds into a hash (e.g., a certified value), use the functions
+//! in this module to make the encoding deterministic instead of converting the
+//! collection into a `BTreeMap` or a `BTreeSet` by hand.
+//!
+//! The entries are ordered according to the `Ord` implementation of the keys,
+//! which is not necessarily the order of their encoded bytes. Callers that
+//! need canonical CBOR must use keys whose `Ord` agrees with it.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::{HashMap, HashSet};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Foo {
+//!     #[serde(serialize_with = "ic_utils::serde_sorted::serialize_sorted_map")]
+//!     balances: HashMap<String, u64>,
+//!     #[serde(serialize_with = "ic_utils::serde_sorted::serialize_sorted_set")]
+//!     owners: HashSet<String>,
+//! }
+//! ```
+//!
+//! Values that are not struct fields can be wrapped into a [`SortedMap`]:
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use ic_utils::serde_sorted::SortedMap;
+//!
+//! let balances: HashMap<&str, u64> = [("b", 2), ("a", 1)].into_iter().collect();
+//! // Serializes as `{"a":1,"b":2}` with any iteration order of `balances`.
+//! let sorted = SortedMap::new(&balances);
+//! ```
+use serde::{Serialize, Serializer};
+
+/// A view of map entries that serializes them in key order.
+pub struct SortedMap<'a, K, V> {
+    entries: Vec<(&'a K, &'a V)>,
+}
+
+impl<'a, K: Ord, V> SortedMap<'a, K, V> {
+    /// Collects and sorts the entries of the given map.
+    pub fn new(map: impl IntoIterator<Item = (&'a K, &'a V)>) -> Self {
+        let mut entries: Vec<_> = map.into_iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        Self { entries }
+    }
+}
+
+impl<'a, K: Serialize, V: Serialize> Serialize for SortedMap<'a, K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.entries.iter().copied())
+    }
+}
+
+/// Serializes a map with its entries sorted by key.
+pub fn serialize_sorted_map<'a, M, K, V, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+where
+    &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+    K: Ord + Serialize + 'a,
+    V: Serialize + 'a,
+    S: Serializer,
+{
+    SortedMap::new(map).serialize(serializer)
+}
+
+/// Serializes a set as a sequence of sorted elements.
+pub fn serialize_sorted_set<'a, C, T, S>(set: &'a C, serializer: S) -> Result<S::Ok, S::Error>
+where
+    &'a C: IntoIterator<Item = &'a T>,
+    T: Ord + Serialize + 'a,
+    S: Serializer,
+{
+    let mut elements: Vec<_> = set.into_iter().collect();
+    elements.sort_unstable();
+    serializer.collect_seq(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+    #[derive(Serialize)]
+    struct Unsorted {
+        #[serde(serialize_with = "serialize_sorted_map")]
+        map: HashMap<u64, String>,
+        #[serde(serialize_with = "serialize_sorted_set")]
+        set: HashSet<String>,
+    }
+
+    #[derive(Serialize)]
+    struct Sorted {
+        map: BTreeMap<u64, String>,
+        set: BTreeSet<String>,
+    }
+
+    #[test]
+    fn test_encoding_matches_btree_collections() {
+        let map: BTreeMap<u64, String> =
+            (0..100).map(|i| (i * 7919 % 101, i.to_string())).collect();
+        let set: BTreeSet<String> = (0..100).map(|i| format!("element-{}", i)).collect();
+
+        let unsorted = Unsorted {
+            map: map.clone().into_iter().collect(),
+            set: set.clone().into_iter().collect(),
+        };
+        let sorted = Sorted { map, set };
+
+        assert_eq!(
+            serde_json::to_vec(&unsorted).unwrap(),
+            serde_json::to_vec(&sorted).unwrap()
+        );
+        assert_eq!(
+            serde_cbor::to_vec(&unsorted).unwrap(),
+            serde_cbor::to_vec(&sorted).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sorted_map() {
+        let map: HashMap<&str, u64> = [("c", 3), ("a", 1), ("b", 2)].into_iter().collect();
+        assert_eq!(
+            serde_json::to_string(&SortedMap::new(&map)).unwrap(),
+            r#"{"a":1,"b":2,"c":3}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&SortedMap::<u64, u64>::new(&HashMap::new())).unwrap(),
+            "{}"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! Helpers for truncating string slices at character boundaries.
 
+use unicode_segmentation::UnicodeSegmentation;
+
 /// Trait, implemented for `str`, for truncating string slices at character
 /// boundaries.
 pub trait StrTruncate {
@@ -78,6 +80,90 @@ fn test_safe_truncate_right() {
     assert_eq!("₿€", s.safe_truncate_right(7));
 }
 
+/// Returns a prefix of `s` consisting of at most `max_chars` characters
+/// (Unicode scalar values).
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// Returns a prefix of `s` consisting of at most `max_graphemes` extended
+/// grapheme clusters, so that user-perceived characters (e.g., letters with
+/// combining marks or emoji sequences) are never split.
+pub fn truncate_graphemes(s: &str, max_graphemes: usize) -> &str {
+    match s.grapheme_indices(true).nth(max_graphemes) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// Replaces the middle of `s` with an ellipsis, keeping the first
+/// `keep_prefix` and the last `keep_suffix` characters.
+///
+/// Returns the original string if it has at most `keep_prefix + keep_suffix`
+/// characters.
+pub fn redact_middle(s: &str, keep_prefix: usize, keep_suffix: usize) -> String {
+    const ELLIPSIS: &str = "...";
+
+    let char_count = s.chars().count();
+    if char_count <= keep_prefix.saturating_add(keep_suffix) {
+        return s.to_string();
+    }
+    let suffix_start = s
+        .char_indices()
+        .nth(char_count - keep_suffix)
+        .map(|(start, _)| start)
+        .unwrap_or(s.len());
+
+    let mut redacted = String::with_capacity(s.len());
+    redacted.push_str(truncate_chars(s, keep_prefix));
+    redacted.push_str(ELLIPSIS);
+    redacted.push_str(&s[suffix_start..]);
+    redacted
+}
+
+#[test]
+fn test_truncate_chars() {
+    assert_eq!("abc", truncate_chars("abcde", 3));
+    assert_eq!("abcde", truncate_chars("abcde", 10));
+    assert_eq!("", truncate_chars("abcde", 0));
+
+    // A unicode string consisting of 2 3-byte characters.
+    assert_eq!("₿", truncate_chars("₿€", 1));
+    assert_eq!("₿€", truncate_chars("₿€", 2));
+}
+
+#[test]
+fn test_truncate_graphemes() {
+    assert_eq!("abc", truncate_graphemes("abcde", 3));
+    assert_eq!("abcde", truncate_graphemes("abcde", 10));
+
+    // "e" followed by a combining acute accent is a single grapheme.
+    let s = "e\u{301}e\u{301}";
+    assert_eq!("e\u{301}", truncate_graphemes(s, 1));
+    assert_eq!("e", truncate_chars(s, 1));
+
+    // A family emoji made of several code points joined with zero-width joiners.
+    let family = "👨\u{200d}👩\u{200d}👧";
+    assert_eq!(family, truncate_graphemes(family, 1));
+    assert_eq!("", truncate_graphemes(family, 0));
+}
+
+#[test]
+fn test_redact_middle() {
+    let principal = "rrkah-fqaaa-aaaaa-aaaaq-cai";
+    assert_eq!("rrkah...q-cai", redact_middle(principal, 5, 5));
+    assert_eq!("...cai", redact_middle(principal, 0, 3));
+    assert_eq!("rrk...", redact_middle(principal, 3, 0));
+    assert_eq!(principal, redact_middle(principal, 20, 7));
+    assert_eq!(principal, redact_middle(principal, usize::MAX, usize::MAX));
+
+    // Multi-byte characters are never split.
+    assert_eq!("₿...€", redact_middle("₿₿₿€€€", 1, 1));
+}
+
 /// Trait for strings that can be represented in an ellipsis format.
 pub trait StrEllipsize {
     /// Ellipsize the string with a max length and prefix percentage [0, 100].
@@ -1,4 +1,10 @@
-use std::thread;
+use std::any::Any;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, PoisonError};
+use std::time::Duration;
+use std::{fmt, io, thread};
 
 /// An object that joins a thread when it's dropped. Mostly helpful to implement
 /// graceful shutdowns.
@@ -67,6 +73,362 @@ where
         .collect()
 }
 
+/// Hooks that a [ThreadPool] calls to report its load, e.g. to update metrics.
+pub trait ThreadPoolObserver: Send + Sync {
+    /// Called with the new number of tasks waiting for a worker whenever it changes.
+    fn observe_queue_depth(&self, _depth: usize) {}
+
+    /// Called with the new number of running tasks whenever it changes.
+    fn observe_active_tasks(&self, _active: usize) {}
+}
+
+struct NoOpObserver;
+
+impl ThreadPoolObserver for NoOpObserver {}
+
+/// Describes a task that panicked on a [ThreadPool] worker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskPanic {
+    /// The name of the worker thread that ran the task.
+    pub thread_name: String,
+    /// The panic message, if it was a string.
+    pub message: String,
+}
+
+impl fmt::Display for TaskPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task panicked on thread {}: {}",
+            self.thread_name, self.message
+        )
+    }
+}
+
+impl std::error::Error for TaskPanic {}
+
+impl TaskPanic {
+    fn new(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "<non-string panic payload>".to_string()
+        };
+        Self {
+            thread_name: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            message,
+        }
+    }
+}
+
+/// Indicates that some workers were still running tasks when the shutdown
+/// deadline expired. The pool detaches these workers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShutdownTimeout {
+    pub running_workers: usize,
+}
+
+impl fmt::Display for ShutdownTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} workers did not finish before the shutdown deadline",
+            self.running_workers
+        )
+    }
+}
+
+impl std::error::Error for ShutdownTimeout {}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct PoolState {
+    observer: Arc<dyn ThreadPoolObserver>,
+    queue_depth: AtomicUsize,
+    active_tasks: AtomicUsize,
+    live_workers: Mutex<usize>,
+    workers_done: Condvar,
+}
+
+impl PoolState {
+    fn add_queued(&self, delta: isize) {
+        let depth = if delta > 0 {
+            self.queue_depth.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            self.queue_depth.fetch_sub(1, Ordering::SeqCst) - 1
+        };
+        self.observer.observe_queue_depth(depth);
+    }
+
+    fn add_active(&self, delta: isize) {
+        let active = if delta > 0 {
+            self.active_tasks.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            self.active_tasks.fetch_sub(1, Ordering::SeqCst) - 1
+        };
+        self.observer.observe_active_tasks(active);
+    }
+}
+
+/// A fixed-size pool of named worker threads.
+///
+/// Unlike `scoped_threadpool::Pool`, the pool reports its load to a
+/// [ThreadPoolObserver], returns panics of tasks to the caller as
+/// [TaskPanic] errors, and can shut down with a deadline.
+///
+/// Dropping the pool waits for all queued tasks to complete.
+pub struct ThreadPool {
+    sender: Mutex<Option<mpsc::Sender<Job>>>,
+    state: Arc<PoolState>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    /// Creates a pool with `num_threads` workers named `{name}-{index}`.
+    pub fn new(name: &str, num_threads: usize) -> io::Result<Self> {
+        Self::with_observer(name, num_threads, Arc::new(NoOpObserver))
+    }
+
+    /// Creates a pool that reports its load to the given observer.
+    pub fn with_observer(
+        name: &str,
+        num_threads: usize,
+        observer: Arc<dyn ThreadPoolObserver>,
+    ) -> io::Result<Self> {
+        assert!(num_threads > 0, "a thread pool needs at least one thread");
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let state = Arc::new(PoolState {
+            observer,
+            queue_depth: AtomicUsize::new(0),
+            active_tasks: AtomicUsize::new(0),
+            live_workers: Mutex::new(0),
+            workers_done: Condvar::new(),
+        });
+
+        let mut pool = Self {
+            sender: Mutex::new(Some(sender)),
+            state: Arc::clone(&state),
+            workers: Vec::with_capacity(num_threads),
+        };
+        for i in 0..num_threads {
+            let receiver = Arc::clone(&receiver);
+            let worker_state = Arc::clone(&state);
+            *state.live_workers.lock().unwrap() += 1;
+            let worker = thread::Builder::new()
+                .name(format!("{}-{}", name, i))
+                .spawn(move || Self::run_worker(&receiver, &worker_state));
+            match worker {
+                Ok(handle) => pool.workers.push(handle),
+                Err(err) => {
+                    *state.live_workers.lock().unwrap() -= 1;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(pool)
+    }
+
+    fn run_worker(receiver: &Mutex<mpsc::Receiver<Job>>, state: &PoolState) {
+        loop {
+            // The lock guard is a temporary, so it's released before the job runs.
+            let job = receiver.lock().unwrap().recv();
+            match job {
+                Ok(job) => {
+                    state.add_queued(-1);
+                    state.add_active(1);
+                    job();
+                    state.add_active(-1);
+                }
+                // The pool is shutting down and the queue is empty.
+                Err(_) => break,
+            }
+        }
+        let mut live_workers = state.live_workers.lock().unwrap();
+        *live_workers -= 1;
+        if *live_workers == 0 {
+            state.workers_done.notify_all();
+        }
+    }
+
+    /// Returns the number of worker threads.
+    pub fn thread_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    fn submit(&self, job: Job) {
+        self.state.add_queued(1);
+        self.sender
+            .lock()
+            .unwrap()
+            .as_ref()
+            .expect("bug: submitting a task to a pool that is shutting down")
+            .send(job)
+            .expect("bug: all workers of the pool exited");
+    }
+
+    /// Runs `f` on a worker thread. The returned handle yields the result of
+    /// `f` or the details of its panic.
+    pub fn spawn<F, T>(&self, f: F) -> TaskHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        self.submit(Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f)).map_err(TaskPanic::new);
+            // The caller might have dropped the handle, so we ignore errors.
+            let _ = sender.send(result);
+        }));
+        TaskHandle(receiver)
+    }
+
+    /// Executes tasks that can borrow from the caller's stack, similar to
+    /// `scoped_threadpool::Pool::scoped`. Waits for all tasks submitted via
+    /// the [Scope] to complete before returning.
+    ///
+    /// Returns the result of `f`, or the first panic of the submitted tasks.
+    /// Other tasks of the scope still run to completion if one of them panics.
+    pub fn scoped<'pool, 'scope, F, R>(&'pool self, f: F) -> Result<R, TaskPanic>
+    where
+        F: FnOnce(&Scope<'pool, 'scope>) -> R,
+    {
+        let scope = Scope {
+            pool: self,
+            state: Arc::new(ScopeState::default()),
+            _marker: PhantomData,
+        };
+        let result = {
+            // Waits for the tasks even if `f` panics: they might borrow
+            // values that the unwinding destroys.
+            let _wait = WaitOnDrop(&scope.state);
+            f(&scope)
+        };
+        let task_panic = scope.state.first_panic.lock().unwrap().take();
+        match task_panic {
+            Some(task_panic) => Err(task_panic),
+            None => Ok(result),
+        }
+    }
+
+    /// Stops accepting tasks and waits up to `timeout` for the workers to
+    /// complete the queued tasks.
+    pub fn shutdown(mut self, timeout: Duration) -> Result<(), ShutdownTimeout> {
+        self.close();
+        let running_workers = {
+            let live_workers = self.state.live_workers.lock().unwrap();
+            let (live_workers, _) = self
+                .state
+                .workers_done
+                .wait_timeout_while(live_workers, timeout, |live| *live > 0)
+                .unwrap();
+            *live_workers
+        };
+        if running_workers > 0 {
+            // Detach the workers, they exit once they complete their tasks.
+            self.workers.clear();
+            return Err(ShutdownTimeout { running_workers });
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        Ok(())
+    }
+
+    /// Closes the queue, so that workers exit once they drain it.
+    fn close(&self) {
+        self.sender.lock().unwrap().take();
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.close();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// The result of a task submitted with [ThreadPool::spawn].
+pub struct TaskHandle<T>(mpsc::Receiver<Result<T, TaskPanic>>);
+
+impl<T> TaskHandle<T> {
+    /// Waits for the task to complete.
+    pub fn join(self) -> Result<T, TaskPanic> {
+        self.0.recv().unwrap_or_else(|_| {
+            Err(TaskPanic {
+                thread_name: "<unknown>".to_string(),
+                message: "the task was dropped before completion".to_string(),
+            })
+        })
+    }
+}
+
+#[derive(Default)]
+struct ScopeState {
+    pending: Mutex<usize>,
+    all_done: Condvar,
+    first_panic: Mutex<Option<TaskPanic>>,
+}
+
+struct WaitOnDrop<'a>(&'a ScopeState);
+
+impl Drop for WaitOnDrop<'_> {
+    fn drop(&mut self) {
+        // This code might run during unwinding, so we must not panic on poisoned locks.
+        let pending = self
+            .0
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let _pending = self
+            .0
+            .all_done
+            .wait_while(pending, |pending| *pending > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+/// Submits tasks that can borrow values outliving the [ThreadPool::scoped] call.
+pub struct Scope<'pool, 'scope> {
+    pool: &'pool ThreadPool,
+    state: Arc<ScopeState>,
+    // Makes the type invariant over 'scope.
+    _marker: PhantomData<std::cell::Cell<&'scope mut ()>>,
+}
+
+impl<'pool, 'scope> Scope<'pool, 'scope> {
+    /// Runs `f` on a worker thread.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        *self.state.pending.lock().unwrap() += 1;
+        let state = Arc::clone(&self.state);
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+                let mut first_panic = state.first_panic.lock().unwrap();
+                if first_panic.is_none() {
+                    *first_panic = Some(TaskPanic::new(payload));
+                }
+            }
+            let mut pending = state.pending.lock().unwrap();
+            *pending -= 1;
+            if *pending == 0 {
+                state.all_done.notify_all();
+            }
+        });
+        // SAFETY: `ThreadPool::scoped` does not return before all jobs of
+        // the scope complete, so the job never outlives the values it borrows.
+        let job: Job = unsafe { std::mem::transmute(job) };
+        self.pool.submit(job);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,4 +460,112 @@ mod tests {
         let actual = parallel_map(&mut thread_pool(), items.into_iter(), |x| x * 2);
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_thread_pool_names_workers() {
+        let pool = ThreadPool::new("test_pool", 2).unwrap();
+        assert_eq!(pool.thread_count(), 2);
+        let name = pool
+            .spawn(|| thread::current().name().map(str::to_string))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert!(name.starts_with("test_pool-"), "unexpected name {}", name);
+    }
+
+    #[test]
+    fn test_thread_pool_scoped_borrows() {
+        let pool = ThreadPool::new("test_pool", 4).unwrap();
+        let mut items = vec![1, 2, 3, 4, 5];
+        pool.scoped(|scope| {
+            for item in items.iter_mut() {
+                scope.execute(move || *item *= 2);
+            }
+        })
+        .unwrap();
+        assert_eq!(items, vec![2, 4, 6, 8, 10]);
+    }
+
+    #[test]
+    fn test_thread_pool_propagates_panics() {
+        let pool = ThreadPool::new("test_pool", 2).unwrap();
+        let completed = AtomicUsize::new(0);
+        let result = pool.scoped(|scope| {
+            scope.execute(|| panic!("oops"));
+            for _ in 0..10 {
+                scope.execute(|| {
+                    completed.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+        let task_panic = result.unwrap_err();
+        assert_eq!(task_panic.message, "oops");
+        assert!(task_panic.thread_name.starts_with("test_pool-"));
+        assert_eq!(completed.load(Ordering::SeqCst), 10);
+
+        let err = pool
+            .spawn(|| -> usize { panic!("task {} failed", 42) })
+            .join()
+            .unwrap_err();
+        assert_eq!(err.message, "task 42 failed");
+
+        // The workers survive panics.
+        assert_eq!(pool.spawn(|| 1).join(), Ok(1));
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        max_queue_depth: AtomicUsize,
+        max_active_tasks: AtomicUsize,
+        last_queue_depth: AtomicUsize,
+        last_active_tasks: AtomicUsize,
+    }
+
+    impl ThreadPoolObserver for RecordingObserver {
+        fn observe_queue_depth(&self, depth: usize) {
+            self.max_queue_depth.fetch_max(depth, Ordering::SeqCst);
+            self.last_queue_depth.store(depth, Ordering::SeqCst);
+        }
+
+        fn observe_active_tasks(&self, active: usize) {
+            self.max_active_tasks.fetch_max(active, Ordering::SeqCst);
+            self.last_active_tasks.store(active, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_thread_pool_reports_load() {
+        let observer = Arc::new(RecordingObserver::default());
+        let pool = ThreadPool::with_observer("test_pool", 2, observer.clone()).unwrap();
+        pool.scoped(|scope| {
+            for _ in 0..10 {
+                scope.execute(|| thread::sleep(Duration::from_millis(1)));
+            }
+        })
+        .unwrap();
+        pool.shutdown(Duration::from_secs(10)).unwrap();
+
+        assert!(observer.max_queue_depth.load(Ordering::SeqCst) >= 1);
+        assert!(observer.max_active_tasks.load(Ordering::SeqCst) <= 2);
+        assert_eq!(observer.last_queue_depth.load(Ordering::SeqCst), 0);
+        assert_eq!(observer.last_active_tasks.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_thread_pool_shutdown_deadline() {
+        let pool = ThreadPool::new("test_pool", 1).unwrap();
+        let (sender, receiver) = mpsc::channel::<()>();
+        let _handle = pool.spawn(move || receiver.recv());
+        assert_eq!(
+            pool.shutdown(Duration::from_millis(10)),
+            Err(ShutdownTimeout { running_workers: 1 })
+        );
+        // Unblocks the detached worker.
+        drop(sender);
+
+        let pool = ThreadPool::new("test_pool", 2).unwrap();
+        let handle = pool.spawn(|| 42);
+        assert_eq!(pool.shutdown(Duration::from_secs(10)), Ok(()));
+        assert_eq!(handle.join(), Ok(42));
+    }
 }